@@ -12,22 +12,39 @@ use clap::{ArgAction, Parser as ClapParser, Subcommand as ClapSubcommand};
 use ethrex_blockchain::{
     BlockchainOptions, BlockchainType, L2Config,
     error::{ChainError, InvalidBlockError},
+    history::HistoryRetention,
+    payload::{DEFAULT_PAYLOAD_BUILD_TIME, PayloadBuilderOptions, TxSelectionPolicy},
+};
+use ethrex_common::{
+    H256, U256,
+    types::{Block, DEFAULT_BUILDER_GAS_CEIL, Genesis, validate_block_body},
 };
-use ethrex_common::types::{Block, DEFAULT_BUILDER_GAS_CEIL, Genesis, validate_block_body};
 use ethrex_p2p::{
-    discv4::{peer_table::TARGET_PEERS, server::INITIAL_LOOKUP_INTERVAL_MS},
+    crawler::{DEFAULT_CRAWL_CONCURRENCY, crawl},
+    discv4::{
+        peer_table::{PeerTable, TARGET_PEERS},
+        server::INITIAL_LOOKUP_INTERVAL_MS,
+    },
+    nat::NatMode,
+    network::{DiscoveryProtocols, P2PContext},
     sync::SyncMode,
     tx_broadcaster::BROADCAST_INTERVAL_MS,
     types::Node,
 };
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_storage::error::StoreError;
-use tokio_util::sync::CancellationToken;
+use ethrex_storage::{Store, error::StoreError};
+use ethrex_vm::PrefetchMode;
+use ipnet::IpNet;
+use rand::rngs::OsRng;
+use secp256k1::SecretKey;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tracing::{Level, error, info, warn};
 
 use crate::{
+    era1::{self, Era1Builder, MAX_ERA1_SIZE},
     initializers::{
-        get_network, init_blockchain, init_store, init_tracing, load_store, regenerate_head_state,
+        get_bootnodes, get_local_p2p_node, get_network, init_blockchain, init_store, init_tracing,
+        load_store, regenerate_head_state,
     },
     utils::{self, default_datadir, get_client_version, get_minimal_client_version, init_datadir},
 };
@@ -83,6 +100,15 @@ pub struct Options {
     pub force: bool,
     #[arg(long = "syncmode", default_value = "snap", value_name = "SYNC_MODE", value_parser = utils::parse_sync_mode, help = "The way in which the node will sync its state.", long_help = "Can be either \"full\" or \"snap\" with \"snap\" as default value.", help_heading = "P2P options")]
     pub syncmode: SyncMode,
+    #[arg(
+        long = "sync.checkpoint",
+        value_name = "BLOCK_HASH_OR_FILE",
+        value_parser = utils::parse_sync_checkpoint,
+        help = "Trusted block to sync to without a consensus client.",
+        long_help = "Either a block hash or the path to a checkpoint file, containing a block hash or a JSON object with a `blockHash` field. Its headers are verified back to the genesis block before syncing to it. Once a consensus client connects, its forkchoice updates drive the sync as usual.",
+        help_heading = "P2P options"
+    )]
+    pub sync_checkpoint: Option<H256>,
     #[arg(
         long = "metrics.addr",
         value_name = "ADDRESS",
@@ -145,6 +171,32 @@ pub struct Options {
         help_heading = "Node options"
     )]
     pub mempool_max_size: usize,
+    #[arg(
+        long = "execution.parallel",
+        action = ArgAction::SetTrue,
+        help = "Execute block transactions optimistically in parallel",
+        long_help = "Transactions are executed speculatively in parallel and validated in order, re-executing the ones that conflict. Only applies to L1 block import.",
+        help_heading = "Node options"
+    )]
+    pub parallel_execution: bool,
+    #[arg(
+        long = "execution.prefetch",
+        default_value_t = PrefetchMode::Disabled,
+        value_name = "MODE",
+        help = "State prefetched concurrently while importing blocks",
+        long_help = "Possible values: none, static (senders, recipients, access lists and EIP-7702 authorities) or speculative (also everything read by a parallel pre-execution of the transactions).",
+        help_heading = "Node options"
+    )]
+    pub prefetch: PrefetchMode,
+    #[arg(
+        long = "history.retention",
+        default_value_t = HistoryRetention::All,
+        value_name = "RETENTION",
+        help = "Which block bodies and receipts are kept in the database.",
+        long_help = "Possible values: all, postmerge, or a number of recent blocks to keep. Older history is pruned in the background once finalized.",
+        help_heading = "Node options"
+    )]
+    pub history_retention: HistoryRetention,
     #[arg(
         long = "http.addr",
         default_value = "0.0.0.0",
@@ -240,6 +292,15 @@ pub struct Options {
         help_heading = "P2P options"
     )]
     pub discovery_port: String,
+    #[arg(
+        long = "discovery.protocols",
+        default_value_t = DiscoveryProtocols::All,
+        value_name = "PROTOCOLS",
+        help = "Discovery protocols to run.",
+        long_help = "Possible values: v4, v5, all. They share the UDP port given by --discovery.port.",
+        help_heading = "P2P options"
+    )]
+    pub discovery_protocols: DiscoveryProtocols,
     #[arg(
         long = "p2p.tx-broadcasting-interval",
         default_value_t = BROADCAST_INTERVAL_MS,
@@ -264,6 +325,34 @@ pub struct Options {
         help_heading = "P2P options"
     )]
     pub lookup_interval: f64,
+    #[arg(
+        long = "p2p.snap-v2",
+        default_value = "false",
+        action = ArgAction::SetTrue,
+        help = "Advertise snap/2 to peers, serving block access lists.",
+        long_help = "snap/2 is still a draft, so only snap/1 is advertised unless this flag is set. Trie nodes are served with either version.",
+        help_heading = "P2P options"
+    )]
+    pub snap_v2: bool,
+    #[arg(
+        long = "netrestrict",
+        value_name = "CIDR_LIST",
+        value_delimiter = ',',
+        num_args = 1..,
+        help = "Comma separated list of CIDR networks, only peers within them are contacted or accepted.",
+        long_help = "Restricts discovery and RLPx connections to the given networks, e.g. `10.0.0.0/8,192.168.0.0/16`. If not specified, every network is allowed.",
+        help_heading = "P2P options"
+    )]
+    pub netrestrict: Vec<IpNet>,
+    #[arg(
+        long = "nat",
+        default_value_t = NatMode::None,
+        value_name = "MODE",
+        help = "How the external address advertised to other nodes is found.",
+        long_help = "Possible values: none (advertise the local address), extip:<IP> (advertise the given address), upnp, pmp (map the p2p ports on the gateway through UPnP or NAT-PMP and advertise its external address), auto (try upnp and then pmp). Gateway modes open ports on the router, so they must be enabled explicitly.",
+        help_heading = "P2P options"
+    )]
+    pub nat: NatMode,
     #[arg(
         long = "builder.extra-data",
         default_value = get_minimal_client_version(),
@@ -280,9 +369,41 @@ pub struct Options {
         help_heading = "Block building options"
     )]
     pub gas_limit: u64,
+    #[arg(
+        long = "builder.tx-selection",
+        default_value_t = TxSelectionPolicy::FeePriority,
+        value_name = "POLICY",
+        help = "Order in which pending transactions are included in built blocks.",
+        long_help = "Possible values: fee (highest tip first), fcfs (first come first served), fair (round-robin across senders)",
+        help_heading = "Block building options"
+    )]
+    pub tx_selection: TxSelectionPolicy,
+    #[arg(
+        long = "builder.build-time",
+        default_value_t = DEFAULT_PAYLOAD_BUILD_TIME.as_millis() as u64,
+        value_name = "TIME_MS",
+        help = "Time (ms) during which a requested payload keeps being rebuilt with new transactions.",
+        help_heading = "Block building options"
+    )]
+    pub payload_build_time_ms: u64,
+    #[arg(
+        long = "builder.fill-time-limit",
+        value_name = "TIME_MS",
+        help = "Maximum time (ms) spent adding transactions to a single payload before sealing it.",
+        help_heading = "Block building options"
+    )]
+    pub payload_fill_time_limit_ms: Option<u64>,
 }
 
 impl Options {
+    pub fn payload_builder_options(&self) -> PayloadBuilderOptions {
+        PayloadBuilderOptions {
+            tx_selection: self.tx_selection,
+            build_time: Duration::from_millis(self.payload_build_time_ms),
+            fill_time_limit: self.payload_fill_time_limit_ms.map(Duration::from_millis),
+        }
+    }
+
     pub fn default_l1() -> Self {
         Self {
             network: Some(Network::LocalDevnet),
@@ -340,21 +461,32 @@ impl Default for Options {
             p2p_addr: None,
             p2p_port: Default::default(),
             discovery_port: Default::default(),
+            discovery_protocols: Default::default(),
             network: Default::default(),
             bootnodes: Default::default(),
             datadir: Default::default(),
             syncmode: Default::default(),
+            sync_checkpoint: None,
             metrics_addr: "0.0.0.0".to_owned(),
             metrics_port: Default::default(),
             metrics_enabled: Default::default(),
             dev: Default::default(),
             force: false,
             mempool_max_size: Default::default(),
+            parallel_execution: false,
+            prefetch: PrefetchMode::Disabled,
+            history_retention: HistoryRetention::All,
             tx_broadcasting_time_interval: Default::default(),
             target_peers: Default::default(),
             lookup_interval: Default::default(),
+            snap_v2: false,
+            netrestrict: Default::default(),
+            nat: Default::default(),
             extra_data: get_minimal_client_version(),
             gas_limit: DEFAULT_BUILDER_GAS_CEIL,
+            tx_selection: TxSelectionPolicy::default(),
+            payload_build_time_ms: DEFAULT_PAYLOAD_BUILD_TIME.as_millis() as u64,
+            payload_fill_time_limit_ms: None,
         }
     }
 }
@@ -381,6 +513,21 @@ pub enum Subcommand {
         removedb: bool,
         #[arg(long, action = ArgAction::SetTrue)]
         l2: bool,
+        #[arg(
+            long = "format",
+            default_value_t = ChainFormat::Rlp,
+            value_name = "FORMAT",
+            help = "Format of the files to import.",
+            long_help = "Possible values: rlp, era1. Era1 files are read from the given folder (or file) and their accumulator is verified."
+        )]
+        format: ChainFormat,
+        #[arg(
+            long = "differential",
+            action = ArgAction::SetTrue,
+            help = "Execute every transaction in LEVM and revm and stop at the first divergence",
+            long_help = "Execute every transaction in LEVM and revm, comparing gas, status, logs and state changes, and stop at the first divergence after writing a reproducer of it to the `differential` folder of the data directory. Blocks are imported one by one and forks after Osaka aren't supported. Requires building with the `revm_differential` feature."
+        )]
+        differential: bool,
     },
     #[command(
         name = "import-bench",
@@ -397,16 +544,23 @@ pub enum Subcommand {
         removedb: bool,
         #[arg(long, action = ArgAction::SetTrue)]
         l2: bool,
+        #[arg(
+            long = "differential",
+            action = ArgAction::SetTrue,
+            help = "Execute every transaction in LEVM and revm and stop at the first divergence",
+            long_help = "Execute every transaction in LEVM and revm, comparing gas, status, logs and state changes, and stop at the first divergence after writing a reproducer of it to the `differential` folder of the data directory. Blocks are imported one by one and forks after Osaka aren't supported. Requires building with the `revm_differential` feature."
+        )]
+        differential: bool,
     },
     #[command(
         name = "export",
-        about = "Export blocks in the current chain into a file in rlp encoding"
+        about = "Export blocks in the current chain into a file in rlp encoding or into era1 files"
     )]
     Export {
         #[arg(
             required = true,
             value_name = "FILE_PATH",
-            help = "Path to the file where the rlp blocks will be written to"
+            help = "Path to the file where the rlp blocks will be written to, or to the folder for era1 files"
         )]
         path: String,
         #[arg(
@@ -421,6 +575,14 @@ pub enum Subcommand {
             help = "Last block number to export"
         )]
         last: Option<u64>,
+        #[arg(
+            long = "format",
+            default_value_t = ChainFormat::Rlp,
+            value_name = "FORMAT",
+            help = "Format of the exported blocks.",
+            long_help = "Possible values: rlp, era1. Era1 files hold up to 8192 blocks each along with their receipts, so era1 exports must start at a multiple of 8192 and need the bodies and receipts of every block in the range."
+        )]
+        format: ChainFormat,
     },
     #[command(
         name = "compute-state-root",
@@ -435,6 +597,41 @@ pub enum Subcommand {
         )]
         genesis_path: PathBuf,
     },
+    #[command(
+        name = "crawl",
+        about = "Crawl the network and write a census of the discovered nodes"
+    )]
+    Crawl {
+        #[arg(
+            long = "output",
+            default_value = "census.json",
+            value_name = "FILE_PATH",
+            help = "Path to the file where the census will be written to"
+        )]
+        output: PathBuf,
+        #[arg(
+            long = "format",
+            default_value_t = CensusFormat::Json,
+            value_name = "FORMAT",
+            help = "Format of the census.",
+            long_help = "Possible values: json, csv."
+        )]
+        format: CensusFormat,
+        #[arg(
+            long = "duration",
+            default_value_t = 300,
+            value_name = "SECONDS",
+            help = "Time spent discovering new nodes"
+        )]
+        duration: u64,
+        #[arg(
+            long = "concurrency",
+            default_value_t = DEFAULT_CRAWL_CONCURRENCY,
+            value_name = "NODES",
+            help = "Maximum amount of nodes handshaked with at the same time"
+        )]
+        concurrency: usize,
+    },
     #[cfg(feature = "l2")]
     #[command(name = "l2")]
     L2(crate::l2::L2Command),
@@ -456,7 +653,16 @@ impl Subcommand {
             Subcommand::RemoveDB { datadir, force } => {
                 remove_db(&datadir, force);
             }
-            Subcommand::Import { path, removedb, l2 } => {
+            Subcommand::Import {
+                path,
+                removedb,
+                l2,
+                format,
+                differential,
+            } => {
+                if differential && l2 {
+                    eyre::bail!("--differential only supports L1 blocks");
+                }
                 if removedb {
                     remove_db(&opts.datadir.clone(), opts.force);
                 }
@@ -475,12 +681,24 @@ impl Subcommand {
                     BlockchainOptions {
                         max_mempool_size: opts.mempool_max_size,
                         r#type: blockchain_type,
+                        parallel_execution: opts.parallel_execution,
+                        prefetch: opts.prefetch,
                         ..Default::default()
                     },
+                    format,
+                    differential,
                 )
                 .await?;
             }
-            Subcommand::ImportBench { path, removedb, l2 } => {
+            Subcommand::ImportBench {
+                path,
+                removedb,
+                l2,
+                differential,
+            } => {
+                if differential && l2 {
+                    eyre::bail!("--differential only supports L1 blocks");
+                }
                 if removedb {
                     remove_db(&opts.datadir.clone(), opts.force);
                 }
@@ -500,19 +718,43 @@ impl Subcommand {
                     BlockchainOptions {
                         r#type: blockchain_type,
                         perf_logs_enabled: true,
+                        parallel_execution: opts.parallel_execution,
+                        prefetch: opts.prefetch,
                         ..Default::default()
                     },
+                    differential,
                 )
                 .await?;
             }
-            Subcommand::Export { path, first, last } => {
-                export_blocks(&path, &opts.datadir, first, last).await
+            Subcommand::Export {
+                path,
+                first,
+                last,
+                format,
+            } => {
+                let network = get_network(opts);
+                export_blocks(&path, &opts.datadir, first, last, format, &network).await?
             }
             Subcommand::ComputeStateRoot { genesis_path } => {
                 let genesis = Network::from(genesis_path).get_genesis()?;
                 let state_root = genesis.compute_state_root();
                 println!("{state_root:#x}");
             }
+            Subcommand::Crawl {
+                output,
+                format,
+                duration,
+                concurrency,
+            } => {
+                crawl_network(
+                    opts,
+                    &output,
+                    format,
+                    Duration::from_secs(duration),
+                    concurrency,
+                )
+                .await?;
+            }
             #[cfg(feature = "l2")]
             Subcommand::L2(command) => command.run().await?,
         }
@@ -539,6 +781,68 @@ impl Display for LogColor {
     }
 }
 
+/// Format of the files read by `import` and written by `export`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChainFormat {
+    /// Concatenated RLP encoded blocks
+    #[default]
+    Rlp,
+    /// Era1 archives, see [crate::era1]
+    Era1,
+}
+
+impl Display for ChainFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainFormat::Rlp => write!(f, "rlp"),
+            ChainFormat::Era1 => write!(f, "era1"),
+        }
+    }
+}
+
+impl FromStr for ChainFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rlp" => Ok(ChainFormat::Rlp),
+            "era1" => Ok(ChainFormat::Era1),
+            _ => Err(format!("Invalid format '{s}'. Expected: rlp or era1")),
+        }
+    }
+}
+
+/// Format of the census written by `crawl`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CensusFormat {
+    /// Array of JSON objects, one per node
+    #[default]
+    Json,
+    /// One row per node, capabilities are separated by spaces
+    Csv,
+}
+
+impl Display for CensusFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CensusFormat::Json => write!(f, "json"),
+            CensusFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+impl FromStr for CensusFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(CensusFormat::Json),
+            "csv" => Ok(CensusFormat::Csv),
+            _ => Err(format!("Invalid format '{s}'. Expected: json or csv")),
+        }
+    }
+}
+
 impl FromStr for LogColor {
     type Err = String;
 
@@ -586,6 +890,8 @@ pub async fn import_blocks(
     datadir: &Path,
     genesis: Genesis,
     blockchain_opts: BlockchainOptions,
+    format: ChainFormat,
+    differential: bool,
 ) -> Result<(), ChainError> {
     const IMPORT_BATCH_SIZE: usize = 1024;
     // This value is higher than the spec (128) as the latter block's state nodes will be kept in memory and not committed when using rocksdb
@@ -597,32 +903,41 @@ pub async fn import_blocks(
     let blockchain = init_blockchain(store.clone(), blockchain_opts);
     let path_metadata = metadata(path).expect("Failed to read path");
 
-    // If it's an .rlp file it will be just one chain, but if it's a directory there can be multiple chains.
-    let chains: Vec<Vec<Block>> = if path_metadata.is_dir() {
+    // If it's a single file it will be just one chain, but if it's a directory there can be multiple chains.
+    let files: Vec<PathBuf> = if path_metadata.is_dir() {
         info!(path = %path, "Importing blocks from directory");
         let mut entries: Vec<_> = read_dir(path)
             .expect("Failed to read blocks directory")
             .map(|res| res.expect("Failed to open file in directory").path())
+            .filter(|entry| {
+                format != ChainFormat::Era1 || entry.extension().is_some_and(|ext| ext == "era1")
+            })
             .collect();
 
         // Sort entries to process files in order (e.g., 1.rlp, 2.rlp, ...)
         entries.sort();
-
         entries
-            .iter()
-            .map(|entry| {
-                let path_str = entry.to_str().expect("Couldn't convert path to string");
-                info!(path = %path_str, "Importing blocks from file");
-                utils::read_chain_file(path_str)
-            })
-            .collect()
     } else {
-        info!(path = %path, "Importing blocks from file");
-        vec![utils::read_chain_file(path)]
+        vec![PathBuf::from(path)]
     };
 
     let mut total_blocks_imported = 0;
-    for blocks in chains {
+    for file in files {
+        let path_str = file.to_str().expect("Couldn't convert path to string");
+        info!(path = %path_str, "Importing blocks from file");
+        // The receipts carried by era1 archives are checked against the headers when reading them,
+        // the ones stored are produced by executing the blocks
+        let blocks: Vec<Block> = match format {
+            ChainFormat::Rlp => utils::read_chain_file(path_str),
+            ChainFormat::Era1 => era1::read_era1_file(path_str)
+                .map_err(|err| {
+                    ChainError::Custom(format!("Failed to read era1 file {path_str}: {err}"))
+                })?
+                .into_iter()
+                .map(|era_block| era_block.block)
+                .collect(),
+        };
+
         let mut block_batch = vec![];
         let size = blocks.len();
         let mut numbers_and_hashes = blocks
@@ -658,7 +973,12 @@ pub async fn import_blocks(
             validate_block_body(&block.header, &block.body)
                 .map_err(InvalidBlockError::InvalidBody)?;
 
-            if index + MIN_FULL_BLOCKS < size {
+            if differential {
+                check_block_differential(&store, &block, datadir)?;
+            }
+
+            // The differential execution needs the state of the parent, so blocks aren't batched
+            if !differential && index + MIN_FULL_BLOCKS < size {
                 block_batch.push(block);
                 if block_batch.len() >= IMPORT_BATCH_SIZE || index + MIN_FULL_BLOCKS + 1 == size {
                     blockchain
@@ -708,6 +1028,7 @@ pub async fn import_blocks_bench(
     datadir: &Path,
     genesis: Genesis,
     blockchain_opts: BlockchainOptions,
+    differential: bool,
 ) -> Result<(), ChainError> {
     let start_time = Instant::now();
     init_datadir(datadir);
@@ -776,6 +1097,10 @@ pub async fn import_blocks_bench(
             validate_block_body(&block.header, &block.body)
                 .map_err(InvalidBlockError::InvalidBody)?;
 
+            if differential {
+                check_block_differential(&store, &block, datadir)?;
+            }
+
             blockchain
                 .add_block_pipeline(block)
                 .inspect_err(|err| match err {
@@ -815,20 +1140,119 @@ pub async fn import_blocks_bench(
         seconds = total_duration.as_secs_f64(),
         "Import completed"
     );
+
+    #[cfg(feature = "perf_contract_profiling")]
+    write_contract_profile(datadir);
+
     Ok(())
 }
 
+/// Executes the transactions of the block in LEVM and in revm over the state of its parent and
+/// fails on the first divergence between them, after writing a reproducer of it to the data
+/// directory.
+#[cfg(feature = "revm_differential")]
+fn check_block_differential(
+    store: &Store,
+    block: &Block,
+    datadir: &Path,
+) -> Result<(), ChainError> {
+    use ethrex_blockchain::vm::StoreVmDatabase;
+    use ethrex_vm::differential::execute_block_differential;
+
+    let parent_header = store
+        .get_block_header_by_hash(block.header.parent_hash)?
+        .ok_or(ChainError::ParentNotFound)?;
+    let vm_db = StoreVmDatabase::new(store.clone(), parent_header)?;
+    let Some(divergence) = execute_block_differential(block, vm_db)? else {
+        return Ok(());
+    };
+
+    for mismatch in &divergence.mismatches {
+        error!(
+            block = divergence.block_number,
+            tx = ?divergence.tx_hash,
+            "{mismatch}"
+        );
+    }
+    let dir = datadir.join("differential");
+    let files = divergence.write_reproducer(&dir).map_err(|err| {
+        ChainError::Custom(format!(
+            "Failed to write the differential reproducer: {err}"
+        ))
+    })?;
+    for file in files {
+        info!(path = %file.display(), "Wrote differential reproducer");
+    }
+    Err(ChainError::Custom(format!(
+        "LEVM and revm diverged executing transaction {} ({:#x}) of block {}",
+        divergence.tx_index, divergence.tx_hash, divergence.block_number
+    )))
+}
+
+#[cfg(not(feature = "revm_differential"))]
+fn check_block_differential(
+    _store: &Store,
+    _block: &Block,
+    _datadir: &Path,
+) -> Result<(), ChainError> {
+    Err(ChainError::Custom(
+        "Differential execution requires building ethrex with the `revm_differential` feature"
+            .to_string(),
+    ))
+}
+
+/// Writes the per-contract profile gathered during the import to the data directory, as a JSON
+/// report and as folded stacks weighted by gas and by time, ready to be fed to flamegraph tools.
+#[cfg(feature = "perf_contract_profiling")]
+fn write_contract_profile(datadir: &Path) {
+    use ethrex_vm::contract_profiling::{CONTRACT_PROFILE, ProfileWeight};
+
+    let Ok(profile) = CONTRACT_PROFILE.lock() else {
+        warn!("Contract profile lock was poisoned");
+        return;
+    };
+    info!("{}", profile.info_pretty(20));
+
+    let report = match serde_json::to_string_pretty(&profile.report()) {
+        Ok(report) => report,
+        Err(err) => {
+            warn!(%err, "Failed to serialize contract profile");
+            return;
+        }
+    };
+    let files = [
+        ("contract_profile.json", report),
+        (
+            "contract_profile_gas.folded",
+            profile.folded_stacks(ProfileWeight::Gas),
+        ),
+        (
+            "contract_profile_time.folded",
+            profile.folded_stacks(ProfileWeight::Time),
+        ),
+    ];
+    for (name, contents) in files {
+        let path = datadir.join(name);
+        match std::fs::write(&path, contents) {
+            Ok(()) => info!(path = %path.display(), "Wrote contract profile"),
+            Err(err) => warn!(path = %path.display(), %err, "Failed to write contract profile"),
+        }
+    }
+}
+
 pub async fn export_blocks(
     path: &str,
     datadir: &Path,
     first_number: Option<u64>,
     last_number: Option<u64>,
-) {
+    format: ChainFormat,
+    network: &Network,
+) -> eyre::Result<()> {
     init_datadir(datadir);
     let store = match load_store(datadir).await {
         Err(err) => {
             error!("Failed to load Store due to: {err}");
-            return;
+            return Ok(());
         }
         Ok(store) => store,
     };
@@ -838,7 +1262,7 @@ pub async fn export_blocks(
         Ok(number) => number,
         Err(StoreError::MissingLatestBlockNumber) => {
             warn!("No blocks in the current chain, nothing to export!");
-            return;
+            return Ok(());
         }
         Err(_) => panic!("Internal DB Error"),
     };
@@ -847,13 +1271,16 @@ pub async fn export_blocks(
         warn!(
             "The requested block range exceeds the current amount of blocks in the chain {latest_number}"
         );
-        return;
+        return Ok(());
     }
     let end = last_number.unwrap_or(latest_number);
     // Check that the requested range makes sense
     if start > end {
         warn!("Cannot export block range [{start}..{end}], please input a valid range");
-        return;
+        return Ok(());
+    }
+    if format == ChainFormat::Era1 {
+        return export_era1(&store, path, start, end, network).await;
     }
     // Fetch blocks from the store and export them to the file
     let mut file = File::create(path).expect("Failed to open file");
@@ -880,4 +1307,155 @@ pub async fn export_blocks(
         buffer.clear();
     }
     info!(blocks = end.saturating_sub(start) + 1, path = %path, "Exported blocks to file");
+    Ok(())
+}
+
+/// Crawls the network selected in the options and writes the census of the discovered nodes.
+/// An in-memory store holding only the genesis block is used, fork ids are checked against the
+/// forks active at the current time. A throwaway key is used so the crawler can't be linked to the
+/// node identity kept in the datadir.
+pub async fn crawl_network(
+    opts: &Options,
+    output: &Path,
+    format: CensusFormat,
+    duration: Duration,
+    concurrency: usize,
+) -> eyre::Result<()> {
+    let network = get_network(opts);
+    let store = init_store("memory", network.get_genesis()?).await?;
+    let blockchain = init_blockchain(store.clone(), BlockchainOptions::default());
+
+    let signer = SecretKey::new(&mut OsRng);
+    let local_p2p_node = get_local_p2p_node(opts, &signer);
+    let peer_table = PeerTable::spawn(opts.target_peers, opts.netrestrict.clone());
+    let bootnodes = get_bootnodes(opts, &network, &opts.datadir);
+
+    let context = P2PContext::new(
+        local_p2p_node,
+        TaskTracker::new(),
+        signer,
+        peer_table,
+        store,
+        blockchain,
+        get_client_version(),
+        None,
+        opts.tx_broadcasting_time_interval,
+        opts.lookup_interval,
+    )?;
+    let census = crawl(context, bootnodes, duration, concurrency).await?;
+
+    let mut file = File::create(output)?;
+    match format {
+        CensusFormat::Json => serde_json::to_writer_pretty(&mut file, &census.entries)?,
+        CensusFormat::Csv => file.write_all(census.to_csv().as_bytes())?,
+    }
+    for (client, count) in census.client_counts() {
+        info!(client, count, "Nodes per client");
+    }
+    info!(nodes = census.entries.len(), path = %output.display(), "Wrote census to file");
+    Ok(())
+}
+
+/// Writes blocks `start..=end` and their receipts into era1 files inside the `path` directory.
+/// Files are split at multiples of [MAX_ERA1_SIZE] so that each one covers a single era.
+/// Era1 only covers the proof of work chain, so the export stops before the merge block.
+/// Files are named after their era, so `start` must be the first block of one, and every
+/// exported block must still have its body and receipts.
+async fn export_era1(
+    store: &Store,
+    path: &str,
+    start: u64,
+    end: u64,
+    network: &Network,
+) -> eyre::Result<()> {
+    let network_name = match network {
+        Network::PublicNetwork(_) => network.to_string(),
+        _ => "custom".to_string(),
+    };
+    if start % MAX_ERA1_SIZE != 0 {
+        eyre::bail!(
+            "Era1 exports must start at the first block of an era, a multiple of {MAX_ERA1_SIZE}, got {start}"
+        );
+    }
+    // Blocks before the earliest one were pruned or never downloaded, only their headers are kept
+    let earliest = store.get_earliest_block_number().await?;
+    if start < earliest {
+        eyre::bail!(
+            "Bodies and receipts are only stored from block {earliest}, can't export era1 files from {start}"
+        );
+    }
+    std::fs::create_dir_all(path)?;
+
+    // Era1 records carry the total difficulty, which isn't stored so it's accumulated from genesis
+    let mut total_difficulty = U256::zero();
+    for n in 0..start {
+        let header = store
+            .get_block_header(n)?
+            .ok_or_else(|| eyre::eyre!("Missing header of block {n}"))?;
+        total_difficulty += header.difficulty;
+    }
+
+    let mut builder = Era1Builder::new();
+    let mut files = 0;
+    let mut last_exported = None;
+    let mut last_output = Instant::now();
+    for n in start..=end {
+        let block = store
+            .get_block_by_number(n)
+            .await?
+            .ok_or_else(|| eyre::eyre!("Missing body of block {n}"))?;
+        // Proof of stake blocks have no difficulty
+        if n > 0 && block.header.difficulty.is_zero() {
+            info!(
+                merge_block = n,
+                "Reached the merge block, stopping the era1 export"
+            );
+            break;
+        }
+        let receipts = store.get_receipts_for_block(&block.hash()).await?;
+        // Missing receipts are read as an empty list
+        if receipts.len() != block.body.transactions.len() {
+            eyre::bail!(
+                "Missing receipts of block {n}, found {} for {} transactions",
+                receipts.len(),
+                block.body.transactions.len()
+            );
+        }
+        total_difficulty += block.header.difficulty;
+        builder.add(&block, &receipts, total_difficulty)?;
+        last_exported = Some(n);
+
+        if (n + 1) % MAX_ERA1_SIZE == 0 {
+            write_era1_file(path, &network_name, n, mem::take(&mut builder))?;
+            files += 1;
+        }
+
+        if last_output.elapsed() > Duration::from_secs(5) {
+            info!(n, end, "Exporting blocks");
+            last_output = Instant::now();
+        }
+    }
+    let Some(last_exported) = last_exported else {
+        warn!("No proof of work blocks in the requested range, nothing to export!");
+        return Ok(());
+    };
+    if !builder.is_empty() {
+        write_era1_file(path, &network_name, last_exported, builder)?;
+        files += 1;
+    }
+    info!(blocks = last_exported - start + 1, files, path = %path, "Exported blocks to era1 files");
+    Ok(())
+}
+
+/// Writes the era1 file ending at block `last_number` into the `path` directory
+fn write_era1_file(
+    path: &str,
+    network_name: &str,
+    last_number: u64,
+    builder: Era1Builder,
+) -> std::io::Result<()> {
+    let epoch = last_number / MAX_ERA1_SIZE;
+    let (data, accumulator) = builder.finish();
+    let file_path = Path::new(path).join(era1::era1_file_name(network_name, epoch, accumulator));
+    std::fs::write(&file_path, data)
 }
@@ -0,0 +1,474 @@
+//! Reading and writing of [Era1](https://github.com/ethereum/go-ethereum/blob/master/internal/era/era.go) archives.
+//!
+//! An era1 file is an e2store file holding up to [MAX_ERA1_SIZE] consecutive blocks:
+//!
+//! ```text
+//! era1 := Version | block-tuple* | other-entries* | Accumulator | BlockIndex
+//! block-tuple := CompressedHeader | CompressedBody | CompressedReceipts | TotalDifficulty
+//! ```
+//!
+//! Headers, bodies and receipts are snappy-framed RLP, the total difficulty is a little endian
+//! uint256 and the accumulator is the SSZ root of the `(block_hash, total_difficulty)` records
+//! of every block in the file.
+
+use ethrex_common::{
+    H256, U256,
+    types::{Block, BlockBody, BlockHeader, Receipt, ReceiptWithBloom, compute_receipts_root},
+};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode, error::RLPDecodeError};
+use sha2::{Digest, Sha256};
+use std::io::{self, Read, Write};
+
+/// Maximum amount of blocks in an era1 file
+pub const MAX_ERA1_SIZE: u64 = 8192;
+
+const VERSION: u16 = 0x3265;
+const COMPRESSED_HEADER: u16 = 0x03;
+const COMPRESSED_BODY: u16 = 0x04;
+const COMPRESSED_RECEIPTS: u16 = 0x05;
+const TOTAL_DIFFICULTY: u16 = 0x06;
+const ACCUMULATOR: u16 = 0x07;
+const BLOCK_INDEX: u16 = 0x6632;
+
+/// Depth of the accumulator merkle tree, log2([MAX_ERA1_SIZE])
+const ACCUMULATOR_DEPTH: usize = 13;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Era1Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("RLP decode error: {0}")]
+    RLPDecode(#[from] RLPDecodeError),
+    #[error("Malformed era1 file: {0}")]
+    Malformed(String),
+    #[error("Accumulator mismatch: expected {expected:#x}, computed {computed:#x}")]
+    AccumulatorMismatch { expected: H256, computed: H256 },
+    #[error("Receipts of block {0} don't match its receipts root")]
+    ReceiptsRootMismatch(u64),
+    #[error(
+        "File {file} doesn't hold the epoch its name refers to, its accumulator is {computed:#x}"
+    )]
+    UnknownEpoch { file: String, computed: H256 },
+}
+
+/// A block stored in an era1 file alongside its receipts and total difficulty
+#[derive(Debug, Clone)]
+pub struct Era1Block {
+    pub block: Block,
+    pub receipts: Vec<Receipt>,
+    pub total_difficulty: U256,
+}
+
+struct Entry {
+    kind: u16,
+    data: Vec<u8>,
+}
+
+fn read_entry(data: &[u8]) -> Result<(Entry, &[u8]), Era1Error> {
+    let (header, rest) = data
+        .split_first_chunk::<8>()
+        .ok_or_else(|| Era1Error::Malformed("truncated entry header".to_string()))?;
+    let kind = u16::from_le_bytes([header[0], header[1]]);
+    let length = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize;
+    if header[6..] != [0, 0] {
+        return Err(Era1Error::Malformed(
+            "reserved bytes are not zero".to_string(),
+        ));
+    }
+    if rest.len() < length {
+        return Err(Era1Error::Malformed("truncated entry data".to_string()));
+    }
+    let (data, rest) = rest.split_at(length);
+    Ok((
+        Entry {
+            kind,
+            data: data.to_vec(),
+        },
+        rest,
+    ))
+}
+
+fn expect_entry(data: &[u8], kind: u16) -> Result<(Vec<u8>, &[u8]), Era1Error> {
+    let (entry, rest) = read_entry(data)?;
+    if entry.kind != kind {
+        return Err(Era1Error::Malformed(format!(
+            "expected entry of type {kind:#06x}, found {:#06x}",
+            entry.kind
+        )));
+    }
+    Ok((entry.data, rest))
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, Era1Error> {
+    let mut decompressed = Vec::new();
+    snap::read::FrameDecoder::new(data).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, Era1Error> {
+    let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+    encoder.write_all(data)?;
+    encoder
+        .into_inner()
+        .map_err(|err| Era1Error::Io(err.into_error()))
+}
+
+/// Decodes the blocks of an era1 file, checking their receipts and the file's accumulator
+pub fn decode_era1(data: &[u8]) -> Result<Vec<Era1Block>, Era1Error> {
+    let (version, mut rest) = expect_entry(data, VERSION)?;
+    if !version.is_empty() {
+        return Err(Era1Error::Malformed(
+            "version entry is not empty".to_string(),
+        ));
+    }
+
+    let mut blocks = Vec::new();
+    let accumulator = loop {
+        let (entry, next) = read_entry(rest)?;
+        rest = next;
+        match entry.kind {
+            COMPRESSED_HEADER => {
+                let header = BlockHeader::decode(&decompress(&entry.data)?)?;
+                let (body, next) = expect_entry(rest, COMPRESSED_BODY)?;
+                let body = BlockBody::decode(&decompress(&body)?)?;
+                let (receipts, next) = expect_entry(next, COMPRESSED_RECEIPTS)?;
+                let receipts: Vec<Receipt> =
+                    Vec::<ReceiptWithBloom>::decode(&decompress(&receipts)?)?
+                        .iter()
+                        .map(Receipt::from)
+                        .collect();
+                let (total_difficulty, next) = expect_entry(next, TOTAL_DIFFICULTY)?;
+                if total_difficulty.len() != 32 {
+                    return Err(Era1Error::Malformed(
+                        "total difficulty is not 32 bytes long".to_string(),
+                    ));
+                }
+                rest = next;
+
+                if compute_receipts_root(&receipts) != header.receipts_root {
+                    return Err(Era1Error::ReceiptsRootMismatch(header.number));
+                }
+                blocks.push(Era1Block {
+                    block: Block::new(header, body),
+                    receipts,
+                    total_difficulty: U256::from_little_endian(&total_difficulty),
+                });
+            }
+            ACCUMULATOR => {
+                let root: [u8; 32] = entry.data.try_into().map_err(|_| {
+                    Era1Error::Malformed("accumulator is not 32 bytes long".to_string())
+                })?;
+                break H256(root);
+            }
+            // Unknown entries are allowed between the block tuples and the accumulator
+            _ => {}
+        }
+    };
+
+    let (index, _) = expect_entry(rest, BLOCK_INDEX)?;
+    let starting_number = index
+        .first_chunk::<8>()
+        .map(|bytes| u64::from_le_bytes(*bytes))
+        .ok_or_else(|| Era1Error::Malformed("truncated block index".to_string()))?;
+    let count = index
+        .last_chunk::<8>()
+        .map(|bytes| u64::from_le_bytes(*bytes))
+        .ok_or_else(|| Era1Error::Malformed("truncated block index".to_string()))?;
+    let first_number = blocks.first().map(|b| b.block.header.number);
+    if count != blocks.len() as u64 || first_number.is_some_and(|n| n != starting_number) {
+        return Err(Era1Error::Malformed(
+            "block index doesn't match the file contents".to_string(),
+        ));
+    }
+
+    let computed = accumulator_of(&blocks);
+    if computed != accumulator {
+        return Err(Era1Error::AccumulatorMismatch {
+            expected: accumulator,
+            computed,
+        });
+    }
+
+    Ok(blocks)
+}
+
+/// Reads and verifies an era1 file, see [decode_era1].
+/// Published era1 files are named after their epoch and accumulator root (see [era1_file_name]),
+/// so when the file follows that convention its contents are checked against the name as well.
+pub fn read_era1_file(path: &str) -> Result<Vec<Era1Block>, Era1Error> {
+    let blocks = decode_era1(&std::fs::read(path)?)?;
+    let file = std::path::Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path);
+    if let Some((epoch, short_root)) = parse_era1_file_name(file) {
+        let computed = accumulator_of(&blocks);
+        let first_number = blocks.first().map(|b| b.block.header.number);
+        if computed.0[..4] != short_root || first_number.is_some_and(|n| n / MAX_ERA1_SIZE != epoch)
+        {
+            return Err(Era1Error::UnknownEpoch {
+                file: file.to_string(),
+                computed,
+            });
+        }
+    }
+    Ok(blocks)
+}
+
+fn accumulator_of(blocks: &[Era1Block]) -> H256 {
+    let records: Vec<_> = blocks
+        .iter()
+        .map(|b| (b.block.hash(), b.total_difficulty))
+        .collect();
+    compute_accumulator_root(&records)
+}
+
+/// Computes the SSZ hash tree root of `List[HeaderRecord, MAX_ERA1_SIZE]`,
+/// where `HeaderRecord` is the container `{ block_hash: Bytes32, total_difficulty: uint256 }`
+pub fn compute_accumulator_root(records: &[(H256, U256)]) -> H256 {
+    let mut layer: Vec<[u8; 32]> = records
+        .iter()
+        .map(|(hash, total_difficulty)| sha256_pair(&hash.0, &total_difficulty.to_little_endian()))
+        .collect();
+
+    let mut zero_hash = [0u8; 32];
+    for _ in 0..ACCUMULATOR_DEPTH {
+        if layer.len() % 2 == 1 {
+            layer.push(zero_hash);
+        }
+        layer = layer
+            .chunks_exact(2)
+            .map(|pair| sha256_pair(&pair[0], &pair[1]))
+            .collect();
+        zero_hash = sha256_pair(&zero_hash, &zero_hash);
+    }
+    let root = layer.first().copied().unwrap_or(zero_hash);
+
+    H256(sha256_pair(
+        &root,
+        &U256::from(records.len()).to_little_endian(),
+    ))
+}
+
+fn sha256_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Returns the conventional name of an era1 file: `<network>-<epoch>-<short-root>.era1`
+pub fn era1_file_name(network: &str, epoch: u64, accumulator: H256) -> String {
+    format!(
+        "{network}-{epoch:05}-{}.era1",
+        hex::encode(&accumulator.0[..4])
+    )
+}
+
+/// Parses a name built by [era1_file_name], returning the epoch and the first bytes of the accumulator root
+pub fn parse_era1_file_name(name: &str) -> Option<(u64, [u8; 4])> {
+    let mut parts = name.strip_suffix(".era1")?.rsplitn(3, '-');
+    let short_root = hex::decode(parts.next()?).ok()?.try_into().ok()?;
+    let epoch = parts.next()?.parse().ok()?;
+    parts.next()?;
+    Some((epoch, short_root))
+}
+
+/// Builds an era1 file in memory, blocks must be added in order
+pub struct Era1Builder {
+    buffer: Vec<u8>,
+    starting_number: Option<u64>,
+    header_offsets: Vec<u64>,
+    records: Vec<(H256, U256)>,
+}
+
+impl Default for Era1Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Era1Builder {
+    pub fn new() -> Self {
+        let mut builder = Self {
+            buffer: Vec::new(),
+            starting_number: None,
+            header_offsets: Vec::new(),
+            records: Vec::new(),
+        };
+        builder.write_entry(VERSION, &[]);
+        builder
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    fn write_entry(&mut self, kind: u16, data: &[u8]) {
+        self.buffer.extend_from_slice(&kind.to_le_bytes());
+        self.buffer
+            .extend_from_slice(&(data.len() as u32).to_le_bytes());
+        self.buffer.extend_from_slice(&[0, 0]);
+        self.buffer.extend_from_slice(data);
+    }
+
+    pub fn add(
+        &mut self,
+        block: &Block,
+        receipts: &[Receipt],
+        total_difficulty: U256,
+    ) -> Result<(), Era1Error> {
+        if self.records.len() as u64 >= MAX_ERA1_SIZE {
+            return Err(Era1Error::Malformed(format!(
+                "an era1 file can't hold more than {MAX_ERA1_SIZE} blocks"
+            )));
+        }
+        let expected_number = self
+            .starting_number
+            .map(|start| start + self.records.len() as u64);
+        if expected_number.is_some_and(|n| n != block.header.number) {
+            return Err(Era1Error::Malformed(format!(
+                "expected block {expected_number:?}, got {}",
+                block.header.number
+            )));
+        }
+        self.starting_number.get_or_insert(block.header.number);
+
+        self.header_offsets.push(self.buffer.len() as u64);
+        self.write_entry(COMPRESSED_HEADER, &compress(&block.header.encode_to_vec())?);
+        self.write_entry(COMPRESSED_BODY, &compress(&block.body.encode_to_vec())?);
+        let receipts: Vec<ReceiptWithBloom> = receipts.iter().map(ReceiptWithBloom::from).collect();
+        self.write_entry(COMPRESSED_RECEIPTS, &compress(&receipts.encode_to_vec())?);
+        self.write_entry(TOTAL_DIFFICULTY, &total_difficulty.to_little_endian());
+        self.records.push((block.hash(), total_difficulty));
+        Ok(())
+    }
+
+    /// Appends the accumulator and block index, returning the file contents and its accumulator root
+    pub fn finish(mut self) -> (Vec<u8>, H256) {
+        let accumulator = compute_accumulator_root(&self.records);
+        self.write_entry(ACCUMULATOR, &accumulator.0);
+
+        // Offsets are relative to the start of the block index entry
+        let index_offset = self.buffer.len() as i64;
+        let mut index = Vec::with_capacity((self.header_offsets.len() + 2) * 8);
+        index.extend_from_slice(&self.starting_number.unwrap_or_default().to_le_bytes());
+        for offset in &self.header_offsets {
+            index.extend_from_slice(&(*offset as i64 - index_offset).to_le_bytes());
+        }
+        index.extend_from_slice(&(self.header_offsets.len() as u64).to_le_bytes());
+        self.write_entry(BLOCK_INDEX, &index);
+
+        (self.buffer, accumulator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::types::BlockHeader;
+
+    fn test_blocks(count: u64) -> Vec<Block> {
+        (0..count)
+            .map(|number| {
+                let header = BlockHeader {
+                    number,
+                    difficulty: U256::from(1000 + number),
+                    receipts_root: compute_receipts_root(&[]),
+                    ..Default::default()
+                };
+                Block::new(header, BlockBody::empty())
+            })
+            .collect()
+    }
+
+    fn build_file(blocks: &[Block]) -> (Vec<u8>, H256) {
+        let mut builder = Era1Builder::new();
+        let mut total_difficulty = U256::zero();
+        for block in blocks {
+            total_difficulty += block.header.difficulty;
+            builder.add(block, &[], total_difficulty).unwrap();
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn era1_roundtrip() {
+        let blocks = test_blocks(10);
+        let (data, accumulator) = build_file(&blocks);
+        let decoded = decode_era1(&data).unwrap();
+
+        assert_eq!(decoded.len(), blocks.len());
+        for (decoded, block) in decoded.iter().zip(&blocks) {
+            assert_eq!(decoded.block.hash(), block.hash());
+            assert!(decoded.receipts.is_empty());
+        }
+        assert_eq!(decoded.last().unwrap().total_difficulty, U256::from(10045));
+        assert_eq!(
+            accumulator,
+            compute_accumulator_root(
+                &decoded
+                    .iter()
+                    .map(|b| (b.block.hash(), b.total_difficulty))
+                    .collect::<Vec<_>>()
+            )
+        );
+    }
+
+    #[test]
+    fn era1_rejects_wrong_accumulator() {
+        let blocks = test_blocks(3);
+        let (mut data, accumulator) = build_file(&blocks);
+        // The accumulator root is stored right before the block index entry
+        let position = data
+            .windows(32)
+            .position(|window| window == accumulator.0)
+            .unwrap();
+        data[position] ^= 1;
+
+        assert!(matches!(
+            decode_era1(&data),
+            Err(Era1Error::AccumulatorMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn era1_file_name_uses_short_root() {
+        let name = era1_file_name("mainnet", 3, H256::repeat_byte(0xab));
+        assert_eq!(name, "mainnet-00003-abababab.era1");
+        assert_eq!(parse_era1_file_name(&name), Some((3, [0xab; 4])));
+        assert_eq!(parse_era1_file_name("blocks.era1"), None);
+    }
+
+    #[test]
+    fn era1_file_is_checked_against_its_name() {
+        let blocks = test_blocks(3);
+        let (data, accumulator) = build_file(&blocks);
+        let dir = std::env::temp_dir().join(format!("era1-name-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let named = dir.join(era1_file_name("mainnet", 0, accumulator));
+        std::fs::write(&named, &data).unwrap();
+        assert_eq!(read_era1_file(named.to_str().unwrap()).unwrap().len(), 3);
+
+        let wrong_root = dir.join(era1_file_name("mainnet", 0, H256::repeat_byte(0xab)));
+        std::fs::write(&wrong_root, &data).unwrap();
+        assert!(matches!(
+            read_era1_file(wrong_root.to_str().unwrap()),
+            Err(Era1Error::UnknownEpoch { .. })
+        ));
+
+        let wrong_epoch = dir.join(era1_file_name("mainnet", 1, accumulator));
+        std::fs::write(&wrong_epoch, &data).unwrap();
+        assert!(matches!(
+            read_era1_file(wrong_epoch.to_str().unwrap()),
+            Err(Era1Error::UnknownEpoch { .. })
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -2,10 +2,13 @@ use crate::{
     cli::{LogColor, Options},
     utils::{
         display_chain_initialization, get_client_version, init_datadir, parse_socket_addr,
-        read_jwtsecret_file, read_node_config_file,
+        persist_managed_peers, read_jwtsecret_file, read_node_config_file,
     },
 };
-use ethrex_blockchain::{Blockchain, BlockchainOptions, BlockchainType};
+use ethrex_blockchain::{
+    Blockchain, BlockchainOptions, BlockchainType,
+    history::{HistoryRetention, start_history_pruner},
+};
 use ethrex_common::fd_limit::raise_fd_limit;
 use ethrex_common::types::Genesis;
 use ethrex_config::networks::Network;
@@ -15,7 +18,7 @@ use ethrex_metrics::rpc::initialize_rpc_metrics;
 use ethrex_p2p::rlpx::initiator::RLPxInitiator;
 use ethrex_p2p::{
     discv4::peer_table::PeerTable,
-    network::P2PContext,
+    network::{DiscoveryConfig, P2PContext},
     peer_handler::PeerHandler,
     sync::SyncMode,
     sync_manager::SyncManager,
@@ -187,10 +190,10 @@ pub async fn init_rpc_api(
 ) {
     init_datadir(&opts.datadir);
 
-    let syncmode = if opts.dev {
-        &SyncMode::Full
+    let (syncmode, sync_checkpoint) = if opts.dev {
+        (&SyncMode::Full, None)
     } else {
-        &opts.syncmode
+        (&opts.syncmode, opts.sync_checkpoint)
     };
 
     // Create SyncManager
@@ -201,6 +204,7 @@ pub async fn init_rpc_api(
         blockchain.clone(),
         store.clone(),
         opts.datadir.clone(),
+        sync_checkpoint,
     )
     .await;
 
@@ -250,7 +254,13 @@ pub async fn init_network(
 
     let bootnodes = get_bootnodes(opts, network, datadir);
 
-    ethrex_p2p::start_network(context, bootnodes)
+    let discovery_config = DiscoveryConfig {
+        protocols: opts.discovery_protocols,
+        nat: opts.nat,
+        ..Default::default()
+    };
+
+    ethrex_p2p::start_network(context, bootnodes, discovery_config)
         .await
         .expect("Network starts");
 
@@ -300,6 +310,35 @@ pub fn get_network(opts: &Options) -> Network {
     opts.network.clone().unwrap_or(default)
 }
 
+/// Starts the peer table, restoring the static, trusted and banned peers and the peer
+/// reputations stored in the datadir. The node config is stored again each time the managed
+/// peers change, so they aren't lost if the node doesn't shut down cleanly.
+pub async fn init_peer_table(
+    opts: &Options,
+    datadir: &Path,
+    local_node_record: &NodeRecord,
+) -> PeerTable {
+    let mut peer_table = PeerTable::spawn(opts.target_peers, opts.netrestrict.clone());
+    match read_node_config_file(datadir) {
+        Ok(Some(config)) => {
+            if let Err(e) = peer_table.add_managed_peers(config.managed_peers()).await {
+                warn!("Could not restore managed peers: {e}");
+            }
+            if let Err(e) = peer_table.add_reputations(config.peer_reputations).await {
+                warn!("Could not restore peer reputations: {e}");
+            }
+        }
+        Ok(None) => {} // No config file, nothing to do
+        Err(e) => warn!("Could not read from peers file: {e}"),
+    }
+    persist_managed_peers(
+        peer_table.clone(),
+        local_node_record.clone(),
+        datadir.join("node_config.json"),
+    );
+    peer_table
+}
+
 pub fn get_bootnodes(opts: &Options, network: &Network, datadir: &Path) -> Vec<Node> {
     let mut bootnodes: Vec<Node> = opts.bootnodes.clone();
 
@@ -465,6 +504,9 @@ pub async fn init_l1(
             max_mempool_size: opts.mempool_max_size,
             perf_logs_enabled: true,
             r#type: BlockchainType::L1,
+            payload_builder: opts.payload_builder_options(),
+            parallel_execution: opts.parallel_execution,
+            prefetch: opts.prefetch,
         },
     );
 
@@ -476,7 +518,7 @@ pub async fn init_l1(
 
     let local_node_record = get_local_node_record(datadir, &local_p2p_node, &signer);
 
-    let peer_table = PeerTable::spawn(opts.target_peers);
+    let peer_table = init_peer_table(&opts, datadir, &local_node_record).await;
 
     // TODO: Check every module starts properly.
     let tracker = TaskTracker::new();
@@ -495,7 +537,8 @@ pub async fn init_l1(
         opts.tx_broadcasting_time_interval,
         opts.lookup_interval,
     )
-    .expect("P2P context could not be created");
+    .expect("P2P context could not be created")
+    .with_snap_v2(opts.snap_v2);
 
     let initiator = RLPxInitiator::spawn(p2p_context.clone()).await;
 
@@ -518,6 +561,14 @@ pub async fn init_l1(
         init_metrics(&opts, &network, tracker.clone());
     }
 
+    if opts.history_retention != HistoryRetention::All {
+        tracker.spawn(start_history_pruner(
+            store.clone(),
+            opts.history_retention,
+            cancel_token.clone(),
+        ));
+    }
+
     if opts.dev {
         #[cfg(feature = "dev")]
         init_dev_network(&opts, &store, tracker.clone()).await;
@@ -1,7 +1,7 @@
 use crate::cli::Options as L1Options;
 use crate::initializers::{
     self, get_authrpc_socket_addr, get_http_socket_addr, get_local_node_record, get_local_p2p_node,
-    get_network, get_signer, init_blockchain, init_network, init_store,
+    get_network, get_signer, init_blockchain, init_network, init_peer_table, init_store,
 };
 use crate::l2::{L2Options, SequencerOptions};
 use crate::utils::{
@@ -14,7 +14,6 @@ use ethrex_common::{Address, types::DEFAULT_BUILDER_GAS_CEIL};
 use ethrex_l2::sequencer::block_producer;
 use ethrex_l2::sequencer::l1_committer::{self, regenerate_state};
 use ethrex_p2p::{
-    discv4::peer_table::PeerTable,
     network::P2PContext,
     peer_handler::PeerHandler,
     rlpx::{initiator::RLPxInitiator, l2::l2_connection::P2PBasedContext},
@@ -214,6 +213,10 @@ pub async fn init_l2(
         max_mempool_size: opts.node_opts.mempool_max_size,
         r#type: BlockchainType::L2(l2_config),
         perf_logs_enabled: true,
+        payload_builder: opts.node_opts.payload_builder_options(),
+        // Parallel execution is only supported for L1 blocks
+        parallel_execution: false,
+        prefetch: opts.node_opts.prefetch,
     };
 
     let blockchain = init_blockchain(store.clone(), blockchain_opts.clone());
@@ -236,7 +239,7 @@ pub async fn init_l2(
         if !opts.sequencer_opts.based {
             blockchain.set_synced();
         }
-        let peer_table = PeerTable::spawn(opts.node_opts.target_peers);
+        let peer_table = init_peer_table(&opts.node_opts, &datadir, &local_node_record).await;
         let p2p_context = P2PContext::new(
             local_p2p_node.clone(),
             tracker.clone(),
@@ -265,7 +268,8 @@ pub async fn init_l2(
             opts.node_opts.tx_broadcasting_time_interval,
             opts.node_opts.lookup_interval,
         )
-        .expect("P2P context could not be created");
+        .expect("P2P context could not be created")
+        .with_snap_v2(opts.node_opts.snap_v2);
         let initiator = RLPxInitiator::spawn(p2p_context.clone()).await;
         let peer_handler = PeerHandler::new(peer_table, initiator);
 
@@ -277,6 +281,7 @@ pub async fn init_l2(
             blockchain.clone(),
             store.clone(),
             opts.node_opts.datadir.clone(),
+            opts.node_opts.sync_checkpoint,
         )
         .await;
 
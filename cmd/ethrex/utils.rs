@@ -1,9 +1,13 @@
 use crate::decode;
 use bytes::Bytes;
 use directories::ProjectDirs;
-use ethrex_common::types::{Block, Genesis};
+use ethrex_common::{
+    H256,
+    types::{Block, Genesis},
+};
 use ethrex_p2p::{
-    discv4::peer_table::PeerTable,
+    discv4::peer_table::{ManagedPeers, PeerTable},
+    reputation::Reputation,
     sync::SyncMode,
     types::{Node, NodeRecord},
 };
@@ -11,10 +15,12 @@ use hex::FromHexError;
 use secp256k1::{PublicKey, SecretKey};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fs::File,
     io,
     net::{SocketAddr, ToSocketAddrs},
     path::{Path, PathBuf},
+    str::FromStr,
 };
 use tracing::{error, info};
 
@@ -22,15 +28,37 @@ use tracing::{error, info};
 pub struct NodeConfigFile {
     pub known_peers: Vec<Node>,
     pub node_record: NodeRecord,
+    #[serde(default)]
+    pub static_peers: Vec<Node>,
+    #[serde(default)]
+    pub trusted_peers: Vec<Node>,
+    #[serde(default)]
+    pub banned_peers: Vec<Node>,
+    #[serde(default)]
+    pub peer_reputations: BTreeMap<H256, Reputation>,
 }
 
 impl NodeConfigFile {
     pub async fn new(mut peer_table: PeerTable, node_record: NodeRecord) -> Self {
         let connected_peers = peer_table.get_connected_nodes().await.unwrap_or(Vec::new());
+        let managed_peers = peer_table.get_managed_peers().await.unwrap_or_default();
+        let peer_reputations = peer_table.get_reputations().await.unwrap_or_default();
 
         NodeConfigFile {
             known_peers: connected_peers,
             node_record,
+            static_peers: managed_peers.static_peers,
+            trusted_peers: managed_peers.trusted_peers,
+            banned_peers: managed_peers.banned_peers,
+            peer_reputations,
+        }
+    }
+
+    pub fn managed_peers(&self) -> ManagedPeers {
+        ManagedPeers {
+            static_peers: self.static_peers.clone(),
+            trusted_peers: self.trusted_peers.clone(),
+            banned_peers: self.banned_peers.clone(),
         }
     }
 }
@@ -80,6 +108,30 @@ pub fn parse_sync_mode(s: &str) -> eyre::Result<SyncMode> {
     }
 }
 
+/// Contents of a checkpoint file, a local stand-in for a weak subjectivity checkpoint endpoint
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointFile {
+    block_hash: H256,
+}
+
+pub fn parse_sync_checkpoint(s: &str) -> eyre::Result<H256> {
+    if let Ok(hash) = H256::from_str(s) {
+        return Ok(hash);
+    }
+    let contents = std::fs::read_to_string(s).map_err(|err| {
+        eyre::eyre!(
+            "Invalid sync checkpoint {s:?}, expected a block hash or a checkpoint file: {err}"
+        )
+    })?;
+    let contents = contents.trim();
+    if let Ok(hash) = H256::from_str(contents) {
+        return Ok(hash);
+    }
+    let checkpoint: CheckpointFile = serde_json::from_str(contents)?;
+    Ok(checkpoint.block_hash)
+}
+
 pub fn parse_socket_addr(addr: &str, port: &str) -> io::Result<SocketAddr> {
     // NOTE: this blocks until hostname can be resolved
     format!("{addr}:{port}")
@@ -127,6 +179,18 @@ pub fn store_node_config_file(config: NodeConfigFile, file_path: PathBuf) {
     };
 }
 
+/// Stores the node config each time the static, trusted or banned peers change
+pub fn persist_managed_peers(peer_table: PeerTable, node_record: NodeRecord, file_path: PathBuf) {
+    let mut changes = peer_table.subscribe_managed_peers();
+    tokio::spawn(async move {
+        // Ends when the peer table stops
+        while changes.changed().await.is_ok() {
+            let node_config = NodeConfigFile::new(peer_table.clone(), node_record.clone()).await;
+            store_node_config_file(node_config, file_path.clone());
+        }
+    });
+}
+
 pub fn read_node_config_file(datadir: &Path) -> Result<Option<NodeConfigFile>, String> {
     const NODE_CONFIG_FILENAME: &str = "node_config.json";
     let file_path = datadir.join(NODE_CONFIG_FILENAME);
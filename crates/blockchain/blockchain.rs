@@ -1,6 +1,7 @@
 pub mod constants;
 pub mod error;
 pub mod fork_choice;
+pub mod history;
 pub mod mempool;
 pub mod payload;
 mod smoke_test;
@@ -8,15 +9,21 @@ pub mod tracing;
 pub mod vm;
 
 use ::tracing::{debug, info, instrument, trace, warn};
-use constants::{MAX_INITCODE_SIZE, MAX_TRANSACTION_DATA_SIZE, POST_OSAKA_GAS_LIMIT_CAP};
+use constants::{
+    MAX_INITCODE_SIZE, MAX_RIP7560_VALIDATION_GAS, MAX_TRANSACTION_DATA_SIZE,
+    POST_OSAKA_GAS_LIMIT_CAP,
+};
 use error::MempoolError;
 use error::{ChainError, InvalidBlockError};
 use ethrex_common::constants::{
     EMPTY_TRIE_HASH, GAS_PER_BLOB, MAX_RLP_BLOCK_SIZE, MIN_BASE_FEE_PER_BLOB_GAS,
 };
+use ethrex_common::types::block_access_list::BlockAccessList;
 use ethrex_common::types::block_execution_witness::ExecutionWitness;
 use ethrex_common::types::fee_config::FeeConfig;
 use ethrex_common::types::requests::{EncodedRequests, Requests, compute_requests_hash};
+#[cfg(feature = "ubt")]
+use ethrex_common::types::ubt_leaf::UbtLeaf;
 use ethrex_common::types::{
     AccountState, AccountUpdate, Block, BlockHash, BlockHeader, BlockNumber, ChainConfig, Code,
     EIP4844Transaction, Receipt, Transaction, WrappedEIP4844Transaction, compute_receipts_root,
@@ -32,17 +39,17 @@ use ethrex_metrics::metrics;
 use ethrex_rlp::constants::RLP_NULL;
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
-#[cfg(feature = "ubt")]
-use ethrex_storage::account_updates_to_ubt;
 use ethrex_storage::{
     AccountUpdatesList, Store, UpdateBatch, error::StoreError, hash_address, hash_key,
 };
+#[cfg(feature = "ubt")]
+use ethrex_storage::{account_updates_to_ubt, ubt_leaf_key};
 use ethrex_trie::node::{BranchNode, ExtensionNode};
 use ethrex_trie::{Nibbles, Node, NodeRef, Trie};
 use ethrex_vm::backends::levm::db::DatabaseLogger;
-use ethrex_vm::{BlockExecutionResult, DynVmDatabase, Evm, EvmError};
+use ethrex_vm::{BlockExecutionResult, DynVmDatabase, Evm, EvmError, PrefetchMode, PrefetchStats};
 use mempool::Mempool;
-use payload::PayloadOrTask;
+use payload::{PayloadBuilderOptions, PayloadOrTask};
 use rustc_hash::FxHashMap;
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, HashMap};
@@ -103,6 +110,12 @@ pub struct BlockchainOptions {
     /// Whether performance logs should be emitted
     pub perf_logs_enabled: bool,
     pub r#type: BlockchainType,
+    /// Block building strategy used when producing payloads
+    pub payload_builder: PayloadBuilderOptions,
+    /// Whether block transactions are executed optimistically in parallel during import
+    pub parallel_execution: bool,
+    /// Which state is prefetched concurrently while blocks are imported
+    pub prefetch: PrefetchMode,
 }
 
 impl Default for BlockchainOptions {
@@ -111,6 +124,9 @@ impl Default for BlockchainOptions {
             max_mempool_size: MAX_MEMPOOL_SIZE_DEFAULT,
             perf_logs_enabled: false,
             r#type: BlockchainType::default(),
+            payload_builder: PayloadBuilderOptions::default(),
+            parallel_execution: false,
+            prefetch: PrefetchMode::Disabled,
         }
     }
 }
@@ -197,6 +213,11 @@ impl Blockchain {
         validate_gas_used(&execution_result.receipts, &block.header)?;
         validate_receipts_root(&block.header, &execution_result.receipts)?;
         validate_requests_hash(&block.header, &chain_config, &execution_result.requests)?;
+        validate_block_access_list_hash(
+            &block.header,
+            &chain_config,
+            execution_result.block_access_list.as_ref(),
+        )?;
 
         Ok((execution_result, account_updates))
     }
@@ -252,6 +273,11 @@ impl Blockchain {
                         &chain_config,
                         &execution_result.requests,
                     )?;
+                    validate_block_access_list_hash(
+                        &block.header,
+                        &chain_config,
+                        execution_result.block_access_list.as_ref(),
+                    )?;
 
                     let exec_end_instant = Instant::now();
                     Ok((execution_result, exec_end_instant))
@@ -757,6 +783,11 @@ impl Blockchain {
         validate_gas_used(&execution_result.receipts, &block.header)?;
         validate_receipts_root(&block.header, &execution_result.receipts)?;
         validate_requests_hash(&block.header, chain_config, &execution_result.requests)?;
+        validate_block_access_list_hash(
+            &block.header,
+            chain_config,
+            execution_result.block_access_list.as_ref(),
+        )?;
 
         Ok(execution_result)
     }
@@ -1106,12 +1137,15 @@ impl Blockchain {
     /// Apply UBT (EIP-7864) updates for a block.
     ///
     /// This updates the parallel UBT state commitment after a block is accepted.
+    /// If the leaves accessed by the block were recorded (EIP-4762), the witness proving them
+    /// and the ones it overwrites against the parent UBT root is built first and kept.
     #[cfg(feature = "ubt")]
     fn apply_ubt_updates(
         &self,
         block_number: BlockNumber,
         block_hash: H256,
         account_updates: &[AccountUpdate],
+        accessed_leaves: Option<&[UbtLeaf]>,
     ) {
         // Create a code size lookup function using the storage
         // TODO: Distinguish between "code doesn't exist" vs "code lookup failed" to avoid
@@ -1126,7 +1160,7 @@ impl Blockchain {
         };
 
         let ubt_updates = account_updates_to_ubt(account_updates, Some(code_size_lookup));
-        if ubt_updates.is_empty() {
+        if ubt_updates.is_empty() && accessed_leaves.is_none() {
             return;
         }
 
@@ -1134,6 +1168,34 @@ impl Blockchain {
         let entries_count = ubt_updates.len();
         match ubt_state.lock() {
             Ok(mut state) => {
+                // Witnesses need a copy of the leaves, only kept if they will be built
+                if self.storage.get_chain_config().eip4762_time.is_some() {
+                    state.enable_witnesses();
+                }
+                if let Some(accessed_leaves) = accessed_leaves {
+                    let keys: Vec<_> = accessed_leaves
+                        .iter()
+                        .map(ubt_leaf_key)
+                        .chain(ubt_updates.iter().map(|update| update.key))
+                        .collect();
+                    match state.witness(&keys) {
+                        Ok(witness) => {
+                            debug!(
+                                block = block_number,
+                                stems = witness.stems.len(),
+                                "UBT execution witness built"
+                            );
+                            state.store_witness(block_hash, witness);
+                        }
+                        Err(error) => {
+                            warn!(
+                                block = block_number,
+                                %error,
+                                "Failed to build the UBT execution witness"
+                            );
+                        }
+                    }
+                }
                 let root = state.apply_block_updates(block_number, block_hash, &ubt_updates);
                 let stems = state.stem_count();
                 drop(state); // Release lock before logging
@@ -1171,6 +1233,11 @@ impl Blockchain {
             account_updates: account_updates_list.state_updates,
             storage_updates: account_updates_list.storage_updates,
             receipts: vec![(block.hash(), execution_result.receipts)],
+            block_access_lists: execution_result
+                .block_access_list
+                .map(|block_access_list| (block.hash(), block_access_list))
+                .into_iter()
+                .collect(),
             blocks: vec![block],
             code_updates: account_updates_list.code_updates,
             plain_storage_updates: account_updates_list.plain_storage_updates,
@@ -1202,13 +1269,18 @@ impl Blockchain {
 
         let merkleized = Instant::now();
         #[cfg(feature = "ubt")]
-        let block_hash = block.hash();
+        let (block_hash, accessed_leaves) = (block.hash(), res.accessed_leaves.clone());
         let result = self.store_block(block, account_updates_list, res);
         let stored = Instant::now();
 
         #[cfg(feature = "ubt")]
         if result.is_ok() {
-            self.apply_ubt_updates(block_number, block_hash, &updates);
+            self.apply_ubt_updates(
+                block_number,
+                block_hash,
+                &updates,
+                accessed_leaves.as_deref(),
+            );
         }
 
         if self.options.perf_logs_enabled {
@@ -1226,6 +1298,18 @@ impl Blockchain {
         result
     }
 
+    /// Keeps a block rejected as invalid in the bad block store, which backs `debug_getBadBlocks`
+    fn record_bad_block(&self, block: &Block, error: &ChainError) {
+        let reason = match error {
+            ChainError::InvalidBlock(error) => error.to_string(),
+            ChainError::EvmError(error) => error.to_string(),
+            _ => return,
+        };
+        if let Err(err) = self.storage.add_bad_block(block, reason) {
+            warn!("Failed to store bad block {:#x}: {err}", block.hash());
+        }
+    }
+
     pub fn add_block_pipeline(&self, block: Block) -> Result<(), ChainError> {
         // Validate if it can be the new head and find the parent
         let Ok(parent_header) = find_parent_header(&block.header, &self.storage) else {
@@ -1237,8 +1321,9 @@ impl Blockchain {
         let vm_db = StoreVmDatabase::new(self.storage.clone(), parent_header.clone())?;
         let vm = self.new_evm(vm_db)?;
 
-        let (res, merkleization_result, merkle_queue_length, instants) =
-            self.execute_block_pipeline(&block, &parent_header, vm)?;
+        let (res, merkleization_result, merkle_queue_length, instants) = self
+            .execute_block_pipeline(&block, &parent_header, vm)
+            .inspect_err(|err| self.record_bad_block(&block, err))?;
 
         let (gas_used, gas_limit, block_number, transactions_count) = (
             block.header.gas_used,
@@ -1256,13 +1341,19 @@ impl Blockchain {
         let account_updates_list = merkleization_result;
 
         #[cfg(feature = "ubt")]
-        let block_hash = block.hash();
+        let (block_hash, accessed_leaves) = (block.hash(), res.accessed_leaves.clone());
+        let prefetch_stats = res.prefetch_stats;
         let result = self.store_block(block, account_updates_list, res);
         let stored = Instant::now();
 
         #[cfg(feature = "ubt")]
         if result.is_ok() {
-            self.apply_ubt_updates(block_number, block_hash, &raw_account_updates);
+            self.apply_ubt_updates(
+                block_number,
+                block_hash,
+                &raw_account_updates,
+                accessed_leaves.as_deref(),
+            );
         }
 
         let instants = std::array::from_fn(move |i| {
@@ -1279,6 +1370,7 @@ impl Blockchain {
                 block_number,
                 transactions_count,
                 merkle_queue_length,
+                prefetch_stats,
                 instants,
             );
         }
@@ -1346,6 +1438,7 @@ impl Blockchain {
         block_number: u64,
         transactions_count: usize,
         merkle_queue_length: usize,
+        prefetch_stats: Option<PrefetchStats>,
         [
             start_instant,
             block_validated_instant,
@@ -1367,6 +1460,13 @@ impl Blockchain {
                 METRICS_BLOCKS.set_latest_block_gas_limit(gas_limit as f64);
                 METRICS_BLOCKS.set_latest_gigagas(throughput);
                 METRICS_BLOCKS.set_transaction_count(transactions_count as i64);
+                if let Some(stats) = prefetch_stats {
+                    METRICS_BLOCKS.set_prefetch_stats(
+                        stats.account_hit_rate(),
+                        stats.storage_hit_rate(),
+                        (stats.prefetched_accounts + stats.prefetched_slots) as i64,
+                    );
+                }
             );
 
             let base_log = format!(
@@ -1393,7 +1493,18 @@ impl Blockchain {
             } else {
                 "".to_string()
             };
-            info!("{}{}", base_log, extra_log);
+            let prefetch_log = prefetch_stats
+                .map(|stats| {
+                    format!(
+                        " | prefetched: {} accounts, {} slots (hit rate: accounts {:.0}%, storage {:.0}%)",
+                        stats.prefetched_accounts,
+                        stats.prefetched_slots,
+                        stats.account_hit_rate() * 100.0,
+                        stats.storage_hit_rate() * 100.0,
+                    )
+                })
+                .unwrap_or_default();
+            info!("{}{}{}", base_log, extra_log, prefetch_log);
         }
     }
 
@@ -1435,6 +1546,7 @@ impl Blockchain {
 
         let blocks_len = blocks.len();
         let mut all_receipts: Vec<(BlockHash, Vec<Receipt>)> = Vec::with_capacity(blocks_len);
+        let mut block_access_lists = Vec::new();
         let mut total_gas_used = 0;
         let mut transactions_count = 0;
 
@@ -1460,9 +1572,14 @@ impl Blockchain {
                 blocks[i - 1].header.clone()
             };
 
-            let BlockExecutionResult { receipts, .. } = self
+            let BlockExecutionResult {
+                receipts,
+                block_access_list,
+                ..
+            } = self
                 .execute_block_from_state(&parent_header, block, &chain_config, &mut vm)
                 .map_err(|err| {
+                    self.record_bad_block(block, &err);
                     (
                         err,
                         Some(BatchBlockProcessingFailure {
@@ -1476,6 +1593,9 @@ impl Blockchain {
             total_gas_used += block.header.gas_used;
             transactions_count += block.body.transactions.len();
             all_receipts.push((block.hash(), receipts));
+            if let Some(block_access_list) = block_access_list {
+                block_access_lists.push((block.hash(), block_access_list));
+            }
 
             // Conversion is safe because EXECUTE_BATCH_SIZE=1024
             log_batch_progress(blocks_len as u32, i as u32);
@@ -1513,6 +1633,7 @@ impl Blockchain {
             storage_updates: accounts_updates,
             blocks,
             receipts: all_receipts,
+            block_access_lists,
             code_updates,
             plain_storage_updates: account_updates_list.plain_storage_updates,
             plain_storage_removed_accounts: account_updates_list.plain_storage_removed_accounts,
@@ -1669,6 +1790,11 @@ impl Blockchain {
             return Ok(None);
         }
 
+        let is_rip7560 = matches!(tx, &Transaction::RIP7560Transaction(_));
+        if is_rip7560 && !matches!(self.options.r#type, BlockchainType::L2(_)) {
+            return Err(MempoolError::Rip7560NotSupported);
+        }
+
         let header_no = self.storage.get_latest_block_number().await?;
         let header = self
             .storage
@@ -1710,7 +1836,22 @@ impl Blockchain {
         }
 
         // Check that the gas limit covers the gas needs for transaction metadata.
-        if tx.gas_limit() < mempool::transaction_intrinsic_gas(tx, &header, &config)? {
+        // RIP-7560 transactions pay it from their validation gas, which is also capped, since
+        // their validation frames are simulated below before they pay for anything.
+        if let Transaction::RIP7560Transaction(rip7560_tx) = tx {
+            if rip7560_tx.validation_gas_limit < mempool::rip7560_intrinsic_gas(rip7560_tx)? {
+                return Err(MempoolError::TxIntrinsicGasCostAboveLimitError);
+            }
+            if rip7560_tx
+                .validation_gas_limit
+                .saturating_add(rip7560_tx.paymaster_validation_gas_limit)
+                > MAX_RIP7560_VALIDATION_GAS
+            {
+                return Err(MempoolError::Rip7560ValidationGasLimitExceeded(
+                    MAX_RIP7560_VALIDATION_GAS,
+                ));
+            }
+        } else if tx.gas_limit() < mempool::transaction_intrinsic_gas(tx, &header, &config)? {
             return Err(MempoolError::TxIntrinsicGasCostAboveLimitError);
         }
 
@@ -1724,7 +1865,11 @@ impl Blockchain {
 
         let maybe_sender_acc_info = self.storage.get_account_info(header_no, sender).await?;
 
-        if let Some(sender_acc_info) = maybe_sender_acc_info {
+        if is_rip7560 {
+            // The nonce, the payer balance and the account validation are checked by simulating
+            // the validation frames, as the sender may not be deployed and may not pay for gas
+            self.simulate_rip7560_validation(tx, sender, &header)?;
+        } else if let Some(sender_acc_info) = maybe_sender_acc_info {
             if nonce < sender_acc_info.nonce || nonce == u64::MAX {
                 return Err(MempoolError::NonceTooLow);
             }
@@ -1755,6 +1900,20 @@ impl Blockchain {
         Ok(tx_to_replace_hash)
     }
 
+    /// Runs the validation frames of a RIP-7560 transaction on top of the state of `header`
+    fn simulate_rip7560_validation(
+        &self,
+        tx: &Transaction,
+        sender: Address,
+        header: &BlockHeader,
+    ) -> Result<(), MempoolError> {
+        let vm_db = StoreVmDatabase::new(self.storage.clone(), header.clone())
+            .map_err(|err| MempoolError::Rip7560ValidationError(err.to_string()))?;
+        self.new_evm(vm_db)
+            .and_then(|mut evm| evm.validate_rip7560_transaction(tx, sender, header))
+            .map_err(|err| MempoolError::Rip7560ValidationError(err.to_string()))
+    }
+
     /// Marks the node's chain as up to date with the current chain
     /// Once the initial sync has taken place, the node will be considered as sync
     pub fn set_synced(&self) {
@@ -1807,13 +1966,23 @@ impl Blockchain {
                 ));
             }
             Transaction::FeeTokenTransaction(itx) => P2PTransaction::FeeTokenTransaction(itx),
+            // RIP-7560 transactions are only accepted through RPC, since validating them
+            // requires simulating their validation frames
+            Transaction::RIP7560Transaction(_) => {
+                return Err(StoreError::Custom(
+                    "RIP-7560 Transactions are not supported in P2P".to_string(),
+                ));
+            }
         };
 
         Ok(result)
     }
 
     pub fn new_evm(&self, vm_db: StoreVmDatabase) -> Result<Evm, EvmError> {
-        new_evm(&self.options.r#type, vm_db)
+        let mut evm = new_evm(&self.options.r#type, vm_db)?;
+        evm.parallel_execution = self.options.parallel_execution;
+        evm.prefetch = self.options.prefetch;
+        Ok(evm)
     }
 
     /// Get the current fork of the chain, based on the latest block's timestamp
@@ -1868,6 +2037,30 @@ pub fn validate_requests_hash(
     Ok(())
 }
 
+/// Checks the block access list recorded while executing an Amsterdam block against the hash
+/// the header commits to (EIP-7928)
+pub fn validate_block_access_list_hash(
+    header: &BlockHeader,
+    chain_config: &ChainConfig,
+    block_access_list: Option<&BlockAccessList>,
+) -> Result<(), ChainError> {
+    if !chain_config.is_amsterdam_activated(header.timestamp) {
+        return Ok(());
+    }
+
+    let valid = block_access_list
+        .map(BlockAccessList::hash)
+        .is_some_and(|hash| header.block_access_list_hash == Some(hash));
+
+    if !valid {
+        return Err(ChainError::InvalidBlock(
+            InvalidBlockError::BlockAccessListHashMismatch,
+        ));
+    }
+
+    Ok(())
+}
+
 /// Performs post-execution checks
 pub fn validate_state_root(
     block_header: &BlockHeader,
@@ -48,3 +48,12 @@ pub const MIN_GAS_LIMIT: u64 = 5000;
 // === EIP-7825 constants ===
 // https://eips.ethereum.org/EIPS/eip-7825
 pub const POST_OSAKA_GAS_LIMIT_CAP: u64 = 16777216;
+
+// === RIP-7560 constants ===
+
+// Base gas cost for each RIP-7560 transaction, taken from its validation gas
+pub const RIP7560_TX_GAS_COST: u64 = 15000;
+
+// Max validation gas of a RIP-7560 transaction accepted into the mempool, which bounds the
+// work of simulating its validation frames before it pays for anything
+pub const MAX_RIP7560_VALIDATION_GAS: u64 = 1_000_000;
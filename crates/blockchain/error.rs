@@ -72,6 +72,8 @@ impl ChainError {
 pub enum InvalidBlockError {
     #[error("Requests hash does not match the one in the header after executing")]
     RequestsHashMismatch,
+    #[error("Block access list hash does not match the one in the header after executing")]
+    BlockAccessListHashMismatch,
     #[error("World State Root does not match the one in the header after executing")]
     StateRootMismatch,
     #[error("Receipts Root does not match the one in the header after executing")]
@@ -142,6 +144,12 @@ pub enum MempoolError {
     InvalidTxSender(#[from] ethrex_common::EcdsaError),
     #[error("Attempted to replace a pooled transaction with an underpriced transaction")]
     UnderpricedReplacement,
+    #[error("RIP-7560 transactions are only supported on L2")]
+    Rip7560NotSupported,
+    #[error("RIP-7560 validation failed: {0}")]
+    Rip7560ValidationError(String),
+    #[error("RIP-7560 validation gas limit exceeds the maximum of {0}")]
+    Rip7560ValidationGasLimitExceeded(u64),
 }
 
 #[derive(Debug)]
@@ -137,6 +137,51 @@ pub async fn apply_fork_choice(
     Ok(head)
 }
 
+/// Rewinds the canonical chain to an earlier canonical block, dropping every block after it from
+/// the canonical chain. Safe and finalized blocks beyond the new head are moved back to it.
+///
+/// The state of the new head must be available in the DB. If the rewind is applied correctly,
+/// the new head block header is returned.
+pub async fn rewind_head(
+    store: &Store,
+    head_number: BlockNumber,
+) -> Result<BlockHeader, InvalidForkChoice> {
+    let latest = store.get_latest_block_number().await?;
+    if head_number > latest {
+        return Err(InvalidForkChoice::ElementNotFound(
+            error::ForkChoiceElement::Head,
+        ));
+    }
+    let Some(head) = store.get_block_header(head_number)? else {
+        return Err(InvalidForkChoice::ElementNotFound(
+            error::ForkChoiceElement::Head,
+        ));
+    };
+    if !store.has_state_root(head.state_root)? {
+        return Err(InvalidForkChoice::StateNotReachable);
+    }
+
+    let safe = store
+        .get_safe_block_number()
+        .await?
+        .map(|safe| safe.min(head_number));
+    let finalized = store
+        .get_finalized_block_number()
+        .await?
+        .map(|finalized| finalized.min(head_number));
+    store
+        .forkchoice_update(vec![], head_number, head.hash(), safe, finalized)
+        .await?;
+
+    metrics!(
+        use ethrex_metrics::blocks::METRICS_BLOCKS;
+
+        METRICS_BLOCKS.set_head_height(head.number);
+    );
+
+    Ok(head)
+}
+
 // Checks that block 1 is prior to block 2 and that if the second is present, the first one is too.
 fn check_order(
     block_1: &Option<BlockHeader>,
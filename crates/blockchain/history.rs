@@ -0,0 +1,186 @@
+//! History expiry ([EIP-4444](https://eips.ethereum.org/EIPS/eip-4444)).
+//!
+//! Old block bodies, receipts and transaction locations are removed from the store according to
+//! a [HistoryRetention] policy. Headers are always kept so the chain can still be verified.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use ethrex_common::types::{BlockNumber, ChainConfig};
+use ethrex_storage::{Store, error::StoreError};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// First proof-of-stake block of mainnet, whose chain config doesn't set `merge_netsplit_block`
+pub const MAINNET_MERGE_BLOCK: BlockNumber = 15_537_394;
+
+/// Amount of blocks pruned in a single database transaction
+const PRUNE_BATCH_SIZE: u64 = 1024;
+
+/// Time between pruning rounds
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Which part of the chain history is kept in the store
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryRetention {
+    /// Keep the whole history
+    #[default]
+    All,
+    /// Drop the history before the merge
+    PostMerge,
+    /// Keep only the given amount of most recent blocks
+    Recent(u64),
+}
+
+impl HistoryRetention {
+    /// Returns the first block whose history should be kept, if any history should be dropped.
+    /// Finalized blocks are the only ones that can be pruned.
+    pub fn prune_target(
+        &self,
+        chain_config: &ChainConfig,
+        latest: BlockNumber,
+        finalized: BlockNumber,
+    ) -> Option<BlockNumber> {
+        let target = match self {
+            HistoryRetention::All => return None,
+            HistoryRetention::PostMerge => match chain_config.merge_netsplit_block {
+                Some(merge_block) => merge_block,
+                None if chain_config.chain_id == 1 => MAINNET_MERGE_BLOCK,
+                None => return None,
+            },
+            HistoryRetention::Recent(blocks) => latest.saturating_sub(*blocks),
+        };
+        Some(target.min(finalized))
+    }
+}
+
+impl fmt::Display for HistoryRetention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryRetention::All => write!(f, "all"),
+            HistoryRetention::PostMerge => write!(f, "postmerge"),
+            HistoryRetention::Recent(blocks) => write!(f, "{blocks}"),
+        }
+    }
+}
+
+impl FromStr for HistoryRetention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(HistoryRetention::All),
+            "postmerge" | "post-merge" => Ok(HistoryRetention::PostMerge),
+            other => other.parse().map(HistoryRetention::Recent).map_err(|_| {
+                format!(
+                    "Invalid history retention '{s}'. Expected: all, postmerge or a number of blocks"
+                )
+            }),
+        }
+    }
+}
+
+/// Prunes the history that falls outside of the retention policy.
+/// Returns the new earliest block, if anything was pruned.
+pub async fn prune_history(
+    store: &Store,
+    retention: HistoryRetention,
+) -> Result<Option<BlockNumber>, StoreError> {
+    let Some(finalized) = store.get_finalized_block_number().await? else {
+        return Ok(None);
+    };
+    let latest = store.get_latest_block_number().await?;
+    let Some(target) = retention.prune_target(&store.get_chain_config(), latest, finalized) else {
+        return Ok(None);
+    };
+    // Don't let a pending history backfill download blocks that would be pruned right away
+    if let Some(backfill_target) = store.get_history_backfill_target().await?
+        && backfill_target < target
+    {
+        store.set_history_backfill_target(Some(target)).await?;
+    }
+    let mut earliest = store.get_earliest_block_number().await?;
+    if earliest >= target {
+        return Ok(None);
+    }
+
+    while earliest < target {
+        let to = (earliest + PRUNE_BATCH_SIZE).min(target) - 1;
+        debug!(from = earliest, to, "Pruning block history");
+        store.prune_block_history(earliest, to).await?;
+        earliest = to + 1;
+    }
+    Ok(Some(earliest))
+}
+
+/// Periodically prunes the history according to the retention policy until cancelled
+pub async fn start_history_pruner(
+    store: Store,
+    retention: HistoryRetention,
+    cancel_token: CancellationToken,
+) {
+    info!(%retention, "Starting history pruner");
+    loop {
+        match prune_history(&store, retention).await {
+            Ok(Some(earliest)) => info!(earliest, "Pruned block history"),
+            Ok(None) => {}
+            Err(err) => warn!(%err, "Failed to prune block history"),
+        }
+        if cancel_token
+            .run_until_cancelled(tokio::time::sleep(PRUNE_INTERVAL))
+            .await
+            .is_none()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_history_retention() {
+        assert_eq!("all".parse(), Ok(HistoryRetention::All));
+        assert_eq!("postmerge".parse(), Ok(HistoryRetention::PostMerge));
+        assert_eq!("90000".parse(), Ok(HistoryRetention::Recent(90_000)));
+        assert!("forever".parse::<HistoryRetention>().is_err());
+    }
+
+    #[test]
+    fn prune_target_never_exceeds_finalized() {
+        let mainnet = ChainConfig {
+            chain_id: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            HistoryRetention::All.prune_target(&mainnet, 20_000_064, 20_000_000),
+            None
+        );
+        assert_eq!(
+            HistoryRetention::PostMerge.prune_target(&mainnet, 20_000_064, 20_000_000),
+            Some(MAINNET_MERGE_BLOCK)
+        );
+        assert_eq!(
+            HistoryRetention::PostMerge.prune_target(&mainnet, 1_064, 1_000),
+            Some(1_000)
+        );
+        assert_eq!(
+            HistoryRetention::Recent(100).prune_target(&mainnet, 1_064, 1_000),
+            Some(964)
+        );
+        assert_eq!(
+            HistoryRetention::Recent(10).prune_target(&mainnet, 1_064, 1_000),
+            Some(1_000)
+        );
+
+        let devnet = ChainConfig {
+            chain_id: 1337,
+            ..Default::default()
+        };
+        assert_eq!(
+            HistoryRetention::PostMerge.prune_target(&devnet, 1_064, 1_000),
+            None
+        );
+    }
+}
@@ -5,15 +5,18 @@ use std::{
 
 use crate::{
     constants::{
-        TX_ACCESS_LIST_ADDRESS_GAS, TX_ACCESS_LIST_STORAGE_KEY_GAS, TX_CREATE_GAS_COST,
-        TX_DATA_NON_ZERO_GAS, TX_DATA_NON_ZERO_GAS_EIP2028, TX_DATA_ZERO_GAS_COST, TX_GAS_COST,
-        TX_INIT_CODE_WORD_GAS_COST,
+        RIP7560_TX_GAS_COST, TX_ACCESS_LIST_ADDRESS_GAS, TX_ACCESS_LIST_STORAGE_KEY_GAS,
+        TX_CREATE_GAS_COST, TX_DATA_NON_ZERO_GAS, TX_DATA_NON_ZERO_GAS_EIP2028,
+        TX_DATA_ZERO_GAS_COST, TX_GAS_COST, TX_INIT_CODE_WORD_GAS_COST,
     },
     error::MempoolError,
 };
 use ethrex_common::{
     Address, H160, H256, U256,
-    types::{BlobsBundle, BlockHeader, ChainConfig, MempoolTransaction, Transaction, TxType},
+    types::{
+        BlobsBundle, BlockHeader, ChainConfig, MempoolTransaction, RIP7560Transaction, Transaction,
+        TxType,
+    },
 };
 use ethrex_storage::error::StoreError;
 use std::collections::HashSet;
@@ -471,22 +474,60 @@ pub fn transaction_intrinsic_gas(
 
     Ok(gas)
 }
+
+/// Intrinsic gas of a RIP-7560 transaction, paid from its validation gas. Its data fields are
+/// all charged as calldata, and its access list as for other transactions.
+pub fn rip7560_intrinsic_gas(tx: &RIP7560Transaction) -> Result<u64, MempoolError> {
+    let mut gas = RIP7560_TX_GAS_COST;
+
+    for data in [
+        &tx.sender_validation_data,
+        &tx.deployer_data,
+        &tx.paymaster_data,
+        &tx.execution_data,
+    ] {
+        let non_zero_count = data.iter().filter(|&&x| x != 0u8).count() as u64;
+        let zero_count = data.len() as u64 - non_zero_count;
+
+        gas = gas
+            .checked_add(non_zero_count * TX_DATA_NON_ZERO_GAS_EIP2028)
+            .and_then(|gas| gas.checked_add(zero_count * TX_DATA_ZERO_GAS_COST))
+            .ok_or(MempoolError::TxGasOverflowError)?;
+    }
+
+    let storage_keys_count: u64 = tx
+        .access_list
+        .iter()
+        .map(|(_, keys)| keys.len() as u64)
+        .sum();
+
+    gas = gas
+        .checked_add(tx.access_list.len() as u64 * TX_ACCESS_LIST_ADDRESS_GAS)
+        .ok_or(MempoolError::TxGasOverflowError)?;
+
+    gas = gas
+        .checked_add(storage_keys_count * TX_ACCESS_LIST_STORAGE_KEY_GAS)
+        .ok_or(MempoolError::TxGasOverflowError)?;
+
+    Ok(gas)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::Blockchain;
-    use crate::constants::MAX_INITCODE_SIZE;
+    use crate::constants::{MAX_INITCODE_SIZE, MAX_RIP7560_VALIDATION_GAS};
     use crate::error::MempoolError;
     use crate::mempool::{
-        Mempool, TX_ACCESS_LIST_ADDRESS_GAS, TX_ACCESS_LIST_STORAGE_KEY_GAS, TX_CREATE_GAS_COST,
-        TX_DATA_NON_ZERO_GAS, TX_DATA_NON_ZERO_GAS_EIP2028, TX_DATA_ZERO_GAS_COST, TX_GAS_COST,
-        TX_INIT_CODE_WORD_GAS_COST,
+        Mempool, RIP7560_TX_GAS_COST, TX_ACCESS_LIST_ADDRESS_GAS, TX_ACCESS_LIST_STORAGE_KEY_GAS,
+        TX_CREATE_GAS_COST, TX_DATA_NON_ZERO_GAS, TX_DATA_NON_ZERO_GAS_EIP2028,
+        TX_DATA_ZERO_GAS_COST, TX_GAS_COST, TX_INIT_CODE_WORD_GAS_COST,
     };
+    use crate::{Blockchain, BlockchainOptions, BlockchainType};
     use std::collections::HashMap;
 
-    use super::transaction_intrinsic_gas;
+    use super::{rip7560_intrinsic_gas, transaction_intrinsic_gas};
     use ethrex_common::types::{
         BYTES_PER_BLOB, BlobsBundle, BlockHeader, ChainConfig, EIP1559Transaction,
-        EIP4844Transaction, MempoolTransaction, Transaction, TxKind,
+        EIP4844Transaction, MempoolTransaction, RIP7560Transaction, Transaction, TxKind,
     };
     use ethrex_common::{Address, Bytes, H256, U256};
     use ethrex_storage::EngineType;
@@ -812,6 +853,64 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn rip7560_transaction_intrinsic_gas() {
+        let tx = RIP7560Transaction {
+            sender_validation_data: Bytes::from(vec![0x1; 65]),
+            paymaster_data: Bytes::from(vec![0x0; 8]),
+            execution_data: Bytes::from(vec![0x1, 0x0]),
+            access_list: vec![(Address::zero(), vec![H256::default(); 2])],
+            ..Default::default()
+        };
+
+        let expected_gas_cost = RIP7560_TX_GAS_COST
+            + 66 * TX_DATA_NON_ZERO_GAS_EIP2028
+            + 9 * TX_DATA_ZERO_GAS_COST
+            + TX_ACCESS_LIST_ADDRESS_GAS
+            + 2 * TX_ACCESS_LIST_STORAGE_KEY_GAS;
+        assert_eq!(
+            rip7560_intrinsic_gas(&tx).expect("Intrinsic gas"),
+            expected_gas_cost
+        );
+    }
+
+    #[tokio::test]
+    async fn rip7560_transaction_validation_gas_is_bounded_before_simulating() {
+        let (config, header) = build_basic_config_and_header(true, true);
+        let store = setup_storage(config, header).await.expect("Storage setup");
+        let options = BlockchainOptions {
+            r#type: BlockchainType::L2(Default::default()),
+            ..Default::default()
+        };
+        let blockchain = Blockchain::new(store, options);
+        let sender = Address::from_low_u64_be(1);
+
+        // Not enough to cover the intrinsic gas
+        let tx = Transaction::RIP7560Transaction(RIP7560Transaction {
+            sender,
+            validation_gas_limit: RIP7560_TX_GAS_COST - 1,
+            ..Default::default()
+        });
+        assert!(matches!(
+            blockchain.validate_transaction(&tx, sender).await,
+            Err(MempoolError::TxIntrinsicGasCostAboveLimitError)
+        ));
+
+        // More validation gas than the mempool is willing to simulate
+        let tx = Transaction::RIP7560Transaction(RIP7560Transaction {
+            sender,
+            validation_gas_limit: RIP7560_TX_GAS_COST,
+            paymaster_validation_gas_limit: MAX_RIP7560_VALIDATION_GAS,
+            ..Default::default()
+        });
+        assert!(matches!(
+            blockchain.validate_transaction(&tx, sender).await,
+            Err(MempoolError::Rip7560ValidationGasLimitExceeded(
+                MAX_RIP7560_VALIDATION_GAS
+            ))
+        ));
+    }
+
     #[tokio::test]
     async fn transaction_with_blob_base_fee_below_min_should_fail() {
         let (config, header) = build_basic_config_and_header(false, false);
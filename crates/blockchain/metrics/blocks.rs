@@ -22,6 +22,9 @@ pub struct MetricsBlocks {
     execution_ms: IntGauge,
     merkle_ms: IntGauge,
     store_ms: IntGauge,
+    prefetch_account_hit_rate: Gauge,
+    prefetch_storage_hit_rate: Gauge,
+    prefetched_entries: IntGauge,
     /// Keeps track of the head block number
     head_height: IntGauge,
 }
@@ -109,6 +112,21 @@ impl MetricsBlocks {
                 "Keeps track of the execution time spent in block storage in miliseconds",
             )
             .unwrap(),
+            prefetch_account_hit_rate: Gauge::new(
+                "prefetch_account_hit_rate",
+                "Keeps track of the fraction of account reads of the last imported block served by the state prefetch",
+            )
+            .unwrap(),
+            prefetch_storage_hit_rate: Gauge::new(
+                "prefetch_storage_hit_rate",
+                "Keeps track of the fraction of storage reads of the last imported block served by the state prefetch",
+            )
+            .unwrap(),
+            prefetched_entries: IntGauge::new(
+                "prefetched_entries",
+                "Keeps track of the accounts and storage slots prefetched for the last imported block",
+            )
+            .unwrap(),
             transaction_count: IntGauge::new(
                 "transaction_count",
                 "Keeps track of transaction count in a block",
@@ -133,6 +151,17 @@ impl MetricsBlocks {
         self.store_ms.set(store_ms);
     }
 
+    pub fn set_prefetch_stats(
+        &self,
+        account_hit_rate: f64,
+        storage_hit_rate: f64,
+        prefetched_entries: i64,
+    ) {
+        self.prefetch_account_hit_rate.set(account_hit_rate);
+        self.prefetch_storage_hit_rate.set(storage_hit_rate);
+        self.prefetched_entries.set(prefetched_entries);
+    }
+
     pub fn set_latest_block_gas_limit(&self, gas_limit: f64) {
         self.gas_limit.set(gas_limit);
     }
@@ -199,6 +228,12 @@ impl MetricsBlocks {
             .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
         r.register(Box::new(self.transaction_count.clone()))
             .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
+        r.register(Box::new(self.prefetch_account_hit_rate.clone()))
+            .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
+        r.register(Box::new(self.prefetch_storage_hit_rate.clone()))
+            .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
+        r.register(Box::new(self.prefetched_entries.clone()))
+            .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
 
         let encoder = TextEncoder::new();
         let metric_families = r.gather();
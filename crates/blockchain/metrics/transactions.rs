@@ -163,6 +163,7 @@ impl MetricsTxType {
             ethrex_common::types::TxType::EIP7702 => "EIP7702",
             ethrex_common::types::TxType::Privileged => "Privileged",
             ethrex_common::types::TxType::FeeToken => "FeeTokenTransaction",
+            ethrex_common::types::TxType::RIP7560 => "RIP7560",
         }
     }
     pub fn all() -> Vec<String> {
@@ -174,6 +175,7 @@ impl MetricsTxType {
             "EIP7702".to_string(),
             "Privileged".to_string(),
             "FeeToken".to_string(),
+            "RIP7560".to_string(),
         ]
     }
 }
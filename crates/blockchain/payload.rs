@@ -1,19 +1,26 @@
 use std::{
     cmp::{Ordering, max},
     collections::HashMap,
+    fmt,
     ops::Div,
+    str::FromStr,
     sync::Arc,
     time::{Duration, Instant},
 };
 
 use ethrex_common::{
     Address, Bloom, Bytes, H256, U256,
-    constants::{DEFAULT_OMMERS_HASH, DEFAULT_REQUESTS_HASH, GAS_PER_BLOB, MAX_RLP_BLOCK_SIZE},
+    constants::{
+        DEFAULT_OMMERS_HASH, DEFAULT_REQUESTS_HASH, EMPTY_BLOCK_ACCESS_LIST_HASH, GAS_PER_BLOB,
+        MAX_RLP_BLOCK_SIZE,
+    },
     types::{
         AccountUpdate, BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber,
-        ChainConfig, MempoolTransaction, Receipt, Transaction, TxType, Withdrawal, bloom_from_logs,
-        calc_excess_blob_gas, calculate_base_fee_per_blob_gas, calculate_base_fee_per_gas,
-        compute_receipts_root, compute_transactions_root, compute_withdrawals_root,
+        ChainConfig, MempoolTransaction, Receipt, Transaction, TxType, Withdrawal,
+        block_access_list::BlockAccessList,
+        bloom_from_logs, calc_excess_blob_gas, calculate_base_fee_per_blob_gas,
+        calculate_base_fee_per_gas, compute_receipts_root, compute_transactions_root,
+        compute_withdrawals_root,
         requests::{EncodedRequests, compute_requests_hash},
     },
 };
@@ -44,6 +51,69 @@ use crate::{
 use thiserror::Error;
 use tracing::{debug, warn};
 
+/// Default time budget for `Blockchain::build_payload_loop`, matching the slot duration
+pub const DEFAULT_PAYLOAD_BUILD_TIME: Duration = Duration::from_secs(12);
+
+/// Policy used to order pending transactions when filling a payload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TxSelectionPolicy {
+    /// Highest effective tip first, ties broken by arrival time
+    #[default]
+    FeePriority,
+    /// Earliest arrival first, regardless of the offered tip
+    FirstComeFirstServed,
+    /// Round-robin across senders: senders with fewer transactions already included go first,
+    /// ties broken by highest tip
+    SenderFairness,
+}
+
+impl FromStr for TxSelectionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fee" | "fee-priority" => Ok(Self::FeePriority),
+            "fcfs" | "first-come-first-served" => Ok(Self::FirstComeFirstServed),
+            "fair" | "sender-fairness" => Ok(Self::SenderFairness),
+            other => Err(format!(
+                "Invalid transaction selection policy: {other}. Expected one of: fee, fcfs, fair"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TxSelectionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FeePriority => write!(f, "fee"),
+            Self::FirstComeFirstServed => write!(f, "fcfs"),
+            Self::SenderFairness => write!(f, "fair"),
+        }
+    }
+}
+
+/// Block building strategy configuration, shared by the L1 payload builder and the L2 block producer
+#[derive(Debug, Clone)]
+pub struct PayloadBuilderOptions {
+    /// How pending transactions are ordered when filling a payload
+    pub tx_selection: TxSelectionPolicy,
+    /// Total time `build_payload_loop` keeps rebuilding the payload for
+    pub build_time: Duration,
+    /// Maximum time spent adding transactions to a single payload.
+    /// Once it is exhausted the payload is sealed with whatever was included so far
+    pub fill_time_limit: Option<Duration>,
+}
+
+impl Default for PayloadBuilderOptions {
+    fn default() -> Self {
+        Self {
+            tx_selection: TxSelectionPolicy::default(),
+            build_time: DEFAULT_PAYLOAD_BUILD_TIME,
+            fill_time_limit: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PayloadBuildTask {
     task: tokio::task::JoinHandle<Result<PayloadBuildResult, ChainError>>,
@@ -170,6 +240,9 @@ pub fn create_payload(
         requests_hash: chain_config
             .is_prague_activated(args.timestamp)
             .then_some(*DEFAULT_REQUESTS_HASH),
+        block_access_list_hash: chain_config
+            .is_amsterdam_activated(args.timestamp)
+            .then_some(*EMPTY_BLOCK_ACCESS_LIST_HASH),
         ..Default::default()
     };
 
@@ -216,7 +289,11 @@ pub struct PayloadBuildContext {
     pub store: Store,
     pub vm: Evm,
     pub account_updates: Vec<AccountUpdate>,
+    /// Set when finalizing Amsterdam payloads
+    pub block_access_list: Option<BlockAccessList>,
     pub payload_size: u64,
+    /// No more transactions are added to the payload after this instant
+    pub fill_deadline: Option<Instant>,
 }
 
 impl PayloadBuildContext {
@@ -239,7 +316,14 @@ impl PayloadBuildContext {
             .map_err(|e| EvmError::DB(e.to_string()))?
             .ok_or_else(|| EvmError::DB("parent header not found".to_string()))?;
         let vm_db = StoreVmDatabase::new(storage.clone(), parent_header)?;
-        let vm = new_evm(blockchain_type, vm_db)?;
+        let mut vm = new_evm(blockchain_type, vm_db)?;
+        // System calls aren't applied to L2 payloads, so their block access indices wouldn't
+        // match the ones used when executing them
+        if matches!(blockchain_type, BlockchainType::L1)
+            && config.is_amsterdam_activated(payload.header.timestamp)
+        {
+            vm.enable_access_recording();
+        }
 
         let payload_size = payload.length() as u64;
         Ok(PayloadBuildContext {
@@ -255,13 +339,21 @@ impl PayloadBuildContext {
             store: storage.clone(),
             vm,
             account_updates: Vec::new(),
+            block_access_list: None,
             payload_size,
+            fill_deadline: None,
         })
     }
 
     pub fn gas_used(&self) -> u64 {
         self.payload.header.gas_limit - self.remaining_gas
     }
+
+    /// Returns true if the time budget for adding transactions has been exhausted
+    pub fn fill_deadline_reached(&self) -> bool {
+        self.fill_deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
 }
 
 impl PayloadBuildContext {
@@ -289,6 +381,7 @@ pub struct PayloadBuildResult {
     pub receipts: Vec<Receipt>,
     pub requests: Vec<EncodedRequests>,
     pub account_updates: Vec<AccountUpdate>,
+    pub block_access_list: Option<BlockAccessList>,
     pub payload: Block,
 }
 
@@ -300,6 +393,7 @@ impl From<PayloadBuildContext> for PayloadBuildResult {
             requests,
             receipts,
             account_updates,
+            block_access_list,
             payload,
             ..
         } = value;
@@ -310,6 +404,7 @@ impl From<PayloadBuildContext> for PayloadBuildResult {
             requests: requests.unwrap_or_default(),
             receipts,
             account_updates,
+            block_access_list,
             payload,
         }
     }
@@ -359,19 +454,19 @@ impl Blockchain {
         ));
     }
 
-    /// Build the given payload and keep on rebuilding it until either the time slot
-    /// given by `SECONDS_PER_SLOT` is up or the `cancel_token` is cancelled
+    /// Build the given payload and keep on rebuilding it until either the time budget
+    /// given by `PayloadBuilderOptions::build_time` is up or the `cancel_token` is cancelled
     pub async fn build_payload_loop(
         self: Arc<Blockchain>,
         payload: Block,
         cancel_token: CancellationToken,
     ) -> Result<PayloadBuildResult, ChainError> {
         let start = Instant::now();
-        const SECONDS_PER_SLOT: Duration = Duration::from_secs(12);
+        let build_time = self.options.payload_builder.build_time;
         // Attempt to rebuild the payload as many times within the given timeframe to maximize fee revenue
         // TODO(#4997): start with an empty block
         let mut res = self.build_payload(payload.clone())?;
-        while start.elapsed() < SECONDS_PER_SLOT && !cancel_token.is_cancelled() {
+        while start.elapsed() < build_time && !cancel_token.is_cancelled() {
             let payload = payload.clone();
             let self_clone = self.clone();
             let building_task =
@@ -400,12 +495,18 @@ impl Blockchain {
         debug!("Building payload");
         let base_fee = payload.header.base_fee_per_gas.unwrap_or_default();
         let mut context = PayloadBuildContext::new(payload, &self.storage, &self.options.r#type)?;
+        context.fill_deadline = self
+            .options
+            .payload_builder
+            .fill_time_limit
+            .map(|limit| since + limit);
 
         if let BlockchainType::L1 = self.options.r#type {
             self.apply_system_operations(&mut context)?;
         }
-        self.apply_withdrawals(&mut context)?;
         self.fill_transactions(&mut context)?;
+        // Withdrawals are processed after the transactions, as when executing the block
+        self.apply_withdrawals(&mut context)?;
         self.extract_requests(&mut context)?;
         self.finalize_payload(&mut context)?;
 
@@ -455,7 +556,8 @@ impl Blockchain {
     }
 
     /// Fetches suitable transactions from the mempool
-    /// Returns two transaction queues, one for plain and one for blob txs
+    /// Returns two transaction queues, one for plain and one for blob txs,
+    /// ordered according to the configured `TxSelectionPolicy`
     pub fn fetch_mempool_transactions(
         &self,
         context: &mut PayloadBuildContext,
@@ -474,16 +576,19 @@ impl Blockchain {
             only_blob_txs: true,
             ..tx_filter
         };
+        let policy = self.options.payload_builder.tx_selection;
         Ok((
             // Plain txs
             TransactionQueue::new(
                 self.mempool.filter_transactions(&plain_tx_filter)?,
                 context.base_fee_per_gas(),
+                policy,
             )?,
             // Blob txs
             TransactionQueue::new(
                 self.mempool.filter_transactions(&blob_tx_filter)?,
                 context.base_fee_per_gas(),
+                policy,
             )?,
         ))
     }
@@ -491,15 +596,28 @@ impl Blockchain {
     /// Fills the payload with transactions taken from the mempool
     /// Returns the block value
     pub fn fill_transactions(&self, context: &mut PayloadBuildContext) -> Result<(), ChainError> {
+        debug!("Fetching transactions from mempool");
+        // Fetch mempool transactions
+        let (mut plain_txs, mut blob_txs) = self.fetch_mempool_transactions(context)?;
+        self.fill_transactions_with(context, &mut plain_txs, &mut blob_txs, &mut ())
+    }
+
+    /// Fills the payload with the transactions offered by the given selectors.
+    /// Gas, blob and block size limits are enforced here, the selectors only decide the order
+    /// in which candidates are tried and the hook adds the rules specific to the chain.
+    pub fn fill_transactions_with(
+        &self,
+        context: &mut PayloadBuildContext,
+        plain_txs: &mut dyn TransactionSelector,
+        blob_txs: &mut dyn TransactionSelector,
+        hook: &mut dyn PayloadFillHook,
+    ) -> Result<(), ChainError> {
         let chain_config = context.chain_config();
         let max_blob_number_per_block = chain_config
             .get_fork_blob_schedule(context.payload.header.timestamp)
             .map(|schedule| schedule.max)
             .unwrap_or_default() as usize;
 
-        debug!("Fetching transactions from mempool");
-        // Fetch mempool transactions
-        let (mut plain_txs, mut blob_txs) = self.fetch_mempool_transactions(context)?;
         // Execute and add transactions to payload (if suitable)
         loop {
             // Check if we have enough gas to run more transactions
@@ -507,6 +625,11 @@ impl Blockchain {
                 debug!("No more gas to run transactions");
                 break;
             };
+            // Check if we still have time to add more transactions
+            if context.fill_deadline_reached() {
+                debug!("Payload fill time limit reached");
+                break;
+            }
             if !blob_txs.is_empty() && context.blobs_bundle.blobs.len() >= max_blob_number_per_block
             {
                 debug!("No more blob gas to run blob transactions");
@@ -517,14 +640,14 @@ impl Blockchain {
                 (None, None) => break,
                 (None, Some(tx)) => (tx, true),
                 (Some(tx), None) => (tx, false),
-                (Some(a), Some(b)) if b < a => (b, true),
+                (Some(a), Some(b)) if plain_txs.precedes(&b, &a) => (b, true),
                 (Some(tx), _) => (tx, false),
             };
 
             let txs = if is_blob {
-                &mut blob_txs
+                &mut *blob_txs
             } else {
-                &mut plain_txs
+                &mut *plain_txs
             };
 
             // Check if we have enough gas to run the transaction
@@ -535,11 +658,26 @@ impl Blockchain {
                 continue;
             }
 
+            match hook.check_candidate(&head_tx, context)? {
+                CandidateAction::Include => {}
+                CandidateAction::Skip => {
+                    txs.pop();
+                    continue;
+                }
+                CandidateAction::Discard => {
+                    txs.pop();
+                    self.remove_transaction_from_pool(&head_tx.tx.hash())?;
+                    continue;
+                }
+                CandidateAction::Stop => break,
+            }
+
             // Check adding a transaction wouldn't exceed the Osaka block size limit of 10 MiB
             // if inclusion of the transaction puts the block size over the size limit
             // we don't add any more txs to the payload.
+            let previous_payload_size = context.payload_size;
             let potential_rlp_block_size =
-                context.payload_size + head_tx.encode_canonical_to_vec().len() as u64;
+                previous_payload_size + head_tx.encode_canonical_to_vec().len() as u64;
             if context
                 .chain_config()
                 .is_osaka_activated(context.payload.header.timestamp)
@@ -563,12 +701,10 @@ impl Blockchain {
             }
 
             // Execute tx
+            let previous_remaining_gas = context.remaining_gas;
+            let previous_block_value = context.block_value;
             let receipt = match self.apply_transaction(&head_tx, context) {
-                Ok(receipt) => {
-                    txs.shift()?;
-                    metrics!(METRICS_TX.inc_tx_with_type(MetricsTxType(head_tx.tx_type())));
-                    receipt
-                }
+                Ok(receipt) => receipt,
                 // Ignore following txs from sender
                 Err(e) => {
                     debug!("Failed to execute transaction: {tx_hash:x}, {e}");
@@ -577,6 +713,19 @@ impl Blockchain {
                     continue;
                 }
             };
+            // Blob transactions can't be undone, as their blobs were already added to the bundle
+            if !is_blob && !hook.accept_receipt(&head_tx, &receipt, context)? {
+                debug!("Undoing transaction rejected after execution: {tx_hash:x}");
+                context.vm.undo_last_tx()?;
+                context.remaining_gas = previous_remaining_gas;
+                context.block_value = previous_block_value;
+                context.payload_size = previous_payload_size;
+                txs.pop();
+                continue;
+            }
+            txs.shift()?;
+            hook.on_included(&head_tx)?;
+            metrics!(METRICS_TX.inc_tx_with_type(MetricsTxType(head_tx.tx_type())));
             // Add transaction to block
             debug!("Adding transaction: {} to payload", tx_hash);
             context.payload.body.transactions.push(head_tx.into());
@@ -650,6 +799,8 @@ impl Blockchain {
     }
 
     pub fn finalize_payload(&self, context: &mut PayloadBuildContext) -> Result<(), ChainError> {
+        // Must be taken before the state transitions, which clear the accounts it's built from
+        context.block_access_list = context.vm.take_block_access_list()?;
         let account_updates = context.vm.get_state_transitions()?;
 
         let ret_acount_updates_list = self
@@ -667,6 +818,10 @@ impl Blockchain {
             .requests
             .as_ref()
             .map(|requests| compute_requests_hash(requests));
+        context.payload.header.block_access_list_hash = context
+            .block_access_list
+            .as_ref()
+            .map(BlockAccessList::hash);
         context.payload.header.gas_used = context.payload.header.gas_limit - context.remaining_gas;
         context.account_updates = account_updates;
 
@@ -697,15 +852,92 @@ pub fn apply_plain_transaction(
     Ok(report)
 }
 
+/// What the payload builder does with a candidate transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateAction {
+    /// Execute the transaction and include it if it succeeds
+    Include,
+    /// Skip the transaction and every later one from its sender
+    Skip,
+    /// Same as `Skip`, also removing the transaction from the mempool
+    Discard,
+    /// Stop filling the payload
+    Stop,
+}
+
+/// Rules of a chain on top of the gas, blob and size limits enforced by
+/// [`Blockchain::fill_transactions_with`]. The unit type applies no extra rules.
+pub trait PayloadFillHook {
+    /// Decides what to do with the next candidate before executing it
+    fn check_candidate(
+        &mut self,
+        _head: &HeadTransaction,
+        _context: &PayloadBuildContext,
+    ) -> Result<CandidateAction, ChainError> {
+        Ok(CandidateAction::Include)
+    }
+
+    /// Returns whether an executed plain transaction can be kept in the payload.
+    /// Rejected transactions are undone and the later ones from their sender skipped.
+    fn accept_receipt(
+        &mut self,
+        _head: &HeadTransaction,
+        _receipt: &Receipt,
+        _context: &PayloadBuildContext,
+    ) -> Result<bool, ChainError> {
+        Ok(true)
+    }
+
+    /// Called once a transaction was added to the payload
+    fn on_included(&mut self, _head: &HeadTransaction) -> Result<(), ChainError> {
+        Ok(())
+    }
+}
+
+impl PayloadFillHook for () {}
+
+/// Source of candidate transactions for the payload builder.
+///
+/// The builder repeatedly peeks the next candidate and reports back whether it was included
+/// (`shift`) or rejected (`pop`). Implementations only decide the order in which transactions
+/// are offered, gas, blob and size limits are enforced by the builder.
+pub trait TransactionSelector: Send {
+    /// Returns the next transaction that should be tried, without removing it
+    fn peek(&self) -> Option<HeadTransaction>;
+
+    /// Removes current head transaction and all transactions from the given sender
+    fn pop(&mut self);
+
+    /// Remove the top transaction after it was included
+    /// The next transaction from the same sender becomes a candidate
+    fn shift(&mut self) -> Result<(), ChainError>;
+
+    /// Remove all transactions from the selector
+    fn clear(&mut self);
+
+    /// Returns true if there are no more transactions to offer
+    fn is_empty(&self) -> bool;
+
+    /// Returns true if `a` should be tried before `b`
+    /// Used to merge the heads of the plain and blob selectors
+    fn precedes(&self, a: &HeadTransaction, b: &HeadTransaction) -> bool {
+        a < b
+    }
+}
+
 /// A struct representing suitable mempool transactions waiting to be included in a block
 // TODO: Consider using VecDequeue instead of Vec
 pub struct TransactionQueue {
-    // The first transaction for each account along with its tip, sorted by highest tip
+    // The first transaction for each account, sorted according to the selection policy
     heads: Vec<HeadTransaction>,
     // The remaining txs grouped by account and sorted by nonce
     txs: HashMap<Address, Vec<MempoolTransaction>>,
     // Base Fee stored for tip calculations
     base_fee: Option<u64>,
+    // Ordering used for the head transactions
+    policy: TxSelectionPolicy,
+    // Amount of transactions already included per sender, used by `TxSelectionPolicy::SenderFairness`
+    included: HashMap<Address, usize>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -730,9 +962,10 @@ impl From<HeadTransaction> for Transaction {
 
 impl TransactionQueue {
     /// Creates a new TransactionQueue from a set of transactions grouped by sender and sorted by nonce
-    fn new(
+    pub fn new(
         mut txs: HashMap<Address, Vec<MempoolTransaction>>,
         base_fee: Option<u64>,
+        policy: TxSelectionPolicy,
     ) -> Result<Self, ChainError> {
         let mut heads = Vec::with_capacity(100);
         for (_, txs) in txs.iter_mut() {
@@ -749,34 +982,28 @@ impl TransactionQueue {
                 tx: head_tx,
             });
         }
-        // Sort heads by higest tip (and lowest timestamp if tip is equal)
-        heads.sort();
+        let included = HashMap::new();
+        // Sort heads according to the selection policy
+        heads.sort_by(|a, b| compare_heads(policy, &included, a, b));
         Ok(TransactionQueue {
             heads,
             txs,
             base_fee,
+            policy,
+            included,
         })
     }
+}
 
-    /// Remove all transactions from the queue
-    pub fn clear(&mut self) {
-        self.heads.clear();
-        self.txs.clear();
-    }
-
-    /// Returns true if there are no more transactions in the queue
-    pub fn is_empty(&self) -> bool {
-        self.heads.is_empty()
-    }
-
-    /// Returns the head transaction with the highest tip
-    /// If there is more than one transaction with the highest tip, return the one with the lowest timestamp
-    pub fn peek(&self) -> Option<HeadTransaction> {
+impl TransactionSelector for TransactionQueue {
+    /// Returns the head transaction that goes first according to the selection policy
+    /// With the default policy this is the one with the highest tip,
+    /// or the one with the lowest timestamp if more than one share the highest tip
+    fn peek(&self) -> Option<HeadTransaction> {
         self.heads.first().cloned()
     }
 
-    /// Removes current head transaction and all transactions from the given sender
-    pub fn pop(&mut self) {
+    fn pop(&mut self) {
         if !self.is_empty() {
             let sender = self.heads.remove(0).tx.sender();
             self.txs.remove(&sender);
@@ -785,9 +1012,11 @@ impl TransactionQueue {
 
     /// Remove the top transaction
     /// Add a tx from the same sender to the head transactions
-    pub fn shift(&mut self) -> Result<(), ChainError> {
+    fn shift(&mut self) -> Result<(), ChainError> {
         let tx = self.heads.remove(0);
-        if let Some(txs) = self.txs.get_mut(&tx.tx.sender()) {
+        let sender = tx.tx.sender();
+        *self.included.entry(sender).or_default() += 1;
+        if let Some(txs) = self.txs.get_mut(&sender) {
             // Fetch next head
             if !txs.is_empty() {
                 let head_tx = txs.remove(0);
@@ -801,7 +1030,10 @@ impl TransactionQueue {
                     tx: head_tx,
                 };
                 // Insert head into heads list while maintaing order
-                let index = match self.heads.binary_search(&head) {
+                let index = match self
+                    .heads
+                    .binary_search_by(|other| compare_heads(self.policy, &self.included, other, &head))
+                {
                     Ok(index) => index, // Same ordering shouldn't be possible when adding timestamps
                     Err(index) => index,
                 };
@@ -810,17 +1042,66 @@ impl TransactionQueue {
         }
         Ok(())
     }
+
+    fn clear(&mut self) {
+        self.heads.clear();
+        self.txs.clear();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.heads.is_empty()
+    }
+
+    fn precedes(&self, a: &HeadTransaction, b: &HeadTransaction) -> bool {
+        compare_heads(self.policy, &self.included, a, b) == Ordering::Less
+    }
+}
+
+/// Orders two head transactions according to the given selection policy
+/// Privileged transactions always go first, ordered by nonce
+fn compare_heads(
+    policy: TxSelectionPolicy,
+    included: &HashMap<Address, usize>,
+    a: &HeadTransaction,
+    b: &HeadTransaction,
+) -> Ordering {
+    if let Some(ordering) = compare_privileged(a, b) {
+        return ordering;
+    }
+    match policy {
+        TxSelectionPolicy::FeePriority => a.cmp(b),
+        TxSelectionPolicy::FirstComeFirstServed => match a.tx.time().cmp(&b.tx.time()) {
+            Ordering::Equal => b.tip.cmp(&a.tip),
+            ordering => ordering,
+        },
+        TxSelectionPolicy::SenderFairness => {
+            let a_included = included.get(&a.tx.sender()).copied().unwrap_or_default();
+            let b_included = included.get(&b.tx.sender()).copied().unwrap_or_default();
+            match a_included.cmp(&b_included) {
+                Ordering::Equal => a.cmp(b),
+                ordering => ordering,
+            }
+        }
+    }
+}
+
+/// Privileged transactions go before any other transaction and are ordered by nonce among themselves
+/// Returns None if neither transaction is privileged
+fn compare_privileged(a: &HeadTransaction, b: &HeadTransaction) -> Option<Ordering> {
+    match (a.tx_type(), b.tx_type()) {
+        (TxType::Privileged, TxType::Privileged) => Some(a.nonce().cmp(&b.nonce())),
+        (TxType::Privileged, _) => Some(Ordering::Less),
+        (_, TxType::Privileged) => Some(Ordering::Greater),
+        _ => None,
+    }
 }
 
 // Orders transactions by highest tip, if tip is equal, orders by lowest timestamp
 impl Ord for HeadTransaction {
     fn cmp(&self, other: &Self) -> Ordering {
-        match (self.tx_type(), other.tx_type()) {
-            (TxType::Privileged, TxType::Privileged) => return self.nonce().cmp(&other.nonce()),
-            (TxType::Privileged, _) => return Ordering::Less,
-            (_, TxType::Privileged) => return Ordering::Greater,
-            _ => (),
-        };
+        if let Some(ordering) = compare_privileged(self, other) {
+            return ordering;
+        }
         match other.tip.cmp(&self.tip) {
             Ordering::Equal => self.tx.time().cmp(&other.tx.time()),
             ordering => ordering,
@@ -833,3 +1114,95 @@ impl PartialOrd for HeadTransaction {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::types::EIP1559Transaction;
+
+    fn mempool_tx(sender: u64, nonce: u64, tip: u64, arrival: u128) -> MempoolTransaction {
+        let tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+            nonce,
+            max_priority_fee_per_gas: tip,
+            max_fee_per_gas: tip,
+            ..Default::default()
+        });
+        MempoolTransaction::with_timestamp(tx, Address::from_low_u64_be(sender), arrival)
+    }
+
+    fn selection_order(
+        txs: Vec<MempoolTransaction>,
+        policy: TxSelectionPolicy,
+    ) -> Vec<(Address, u64)> {
+        let mut grouped: HashMap<Address, Vec<MempoolTransaction>> = HashMap::new();
+        for tx in txs {
+            grouped.entry(tx.sender()).or_default().push(tx);
+        }
+        let mut queue = TransactionQueue::new(grouped, None, policy).unwrap();
+        let mut order = Vec::new();
+        while let Some(head) = queue.peek() {
+            order.push((head.tx.sender(), head.nonce()));
+            queue.shift().unwrap();
+        }
+        order
+    }
+
+    #[test]
+    fn fee_priority_orders_by_tip() {
+        let txs = vec![
+            mempool_tx(1, 0, 1, 1),
+            mempool_tx(2, 0, 3, 2),
+            mempool_tx(3, 0, 2, 3),
+        ];
+        let order = selection_order(txs, TxSelectionPolicy::FeePriority);
+        let senders: Vec<u64> = order.iter().map(|(a, _)| a.to_low_u64_be()).collect();
+        assert_eq!(senders, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn fcfs_orders_by_arrival() {
+        let txs = vec![
+            mempool_tx(1, 0, 1, 1),
+            mempool_tx(2, 0, 3, 2),
+            mempool_tx(3, 0, 2, 3),
+        ];
+        let order = selection_order(txs, TxSelectionPolicy::FirstComeFirstServed);
+        let senders: Vec<u64> = order.iter().map(|(a, _)| a.to_low_u64_be()).collect();
+        assert_eq!(senders, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn sender_fairness_alternates_senders() {
+        // Sender 1 pays more, but sender 2 should get a transaction in after each one of sender 1
+        let txs = vec![
+            mempool_tx(1, 0, 10, 1),
+            mempool_tx(1, 1, 10, 2),
+            mempool_tx(1, 2, 10, 3),
+            mempool_tx(2, 0, 1, 4),
+            mempool_tx(2, 1, 1, 5),
+        ];
+        let order = selection_order(txs, TxSelectionPolicy::SenderFairness);
+        let senders: Vec<u64> = order.iter().map(|(a, _)| a.to_low_u64_be()).collect();
+        assert_eq!(senders, vec![1, 2, 1, 2, 1]);
+        // Nonces from the same sender are still sequential
+        let sender_1_nonces: Vec<u64> = order
+            .iter()
+            .filter(|(a, _)| a.to_low_u64_be() == 1)
+            .map(|(_, nonce)| *nonce)
+            .collect();
+        assert_eq!(sender_1_nonces, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parse_tx_selection_policy() {
+        assert_eq!(
+            "fcfs".parse::<TxSelectionPolicy>(),
+            Ok(TxSelectionPolicy::FirstComeFirstServed)
+        );
+        assert_eq!(
+            "fee".parse::<TxSelectionPolicy>(),
+            Ok(TxSelectionPolicy::FeePriority)
+        );
+        assert!("unknown".parse::<TxSelectionPolicy>().is_err());
+    }
+}
@@ -19,6 +19,14 @@ pub static DEFAULT_REQUESTS_HASH: LazyLock<H256> = LazyLock::new(|| {
     )
 });
 
+// = Keccak256(RLP([])) as of EIP-7928, the hash of a block without state accesses
+pub static EMPTY_BLOCK_ACCESS_LIST_HASH: LazyLock<H256> = LazyLock::new(|| {
+    H256::from_slice(
+        &hex::decode("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347")
+            .expect("Failed to decode hex from string"),
+    )
+});
+
 // = Root of empty Trie as of EIP-4895
 pub static EMPTY_WITHDRAWALS_HASH: LazyLock<H256> = LazyLock::new(|| {
     H256::from_slice(
@@ -142,6 +142,9 @@ pub struct BlockHeader {
     #[serde(skip_serializing_if = "Option::is_none", default = "Option::default")]
     #[rkyv(with=crate::rkyv_utils::OptionH256Wrapper)]
     pub requests_hash: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none", default = "Option::default")]
+    #[rkyv(with=crate::rkyv_utils::OptionH256Wrapper)]
+    pub block_access_list_hash: Option<H256>,
 }
 
 // Needs a explicit impl due to the hash OnceLock.
@@ -170,6 +173,7 @@ impl PartialEq for BlockHeader {
             excess_blob_gas,
             parent_beacon_block_root,
             requests_hash,
+            block_access_list_hash,
         } = self;
 
         parent_hash == &other.parent_hash
@@ -191,6 +195,7 @@ impl PartialEq for BlockHeader {
             && difficulty == &other.difficulty
             && ommers_hash == &other.ommers_hash
             && requests_hash == &other.requests_hash
+            && block_access_list_hash == &other.block_access_list_hash
             && logs_bloom == &other.logs_bloom
             && extra_data == &other.extra_data
     }
@@ -220,6 +225,7 @@ impl RLPEncode for BlockHeader {
             .encode_optional_field(&self.excess_blob_gas)
             .encode_optional_field(&self.parent_beacon_block_root)
             .encode_optional_field(&self.requests_hash)
+            .encode_optional_field(&self.block_access_list_hash)
             .finish();
     }
 }
@@ -249,6 +255,7 @@ impl RLPDecode for BlockHeader {
         let (excess_blob_gas, decoder) = decoder.decode_optional_field();
         let (parent_beacon_block_root, decoder) = decoder.decode_optional_field();
         let (requests_hash, decoder) = decoder.decode_optional_field();
+        let (block_access_list_hash, decoder) = decoder.decode_optional_field();
 
         Ok((
             BlockHeader {
@@ -274,6 +281,7 @@ impl RLPDecode for BlockHeader {
                 excess_blob_gas,
                 parent_beacon_block_root,
                 requests_hash,
+                block_access_list_hash,
             },
             decoder.finish()?,
         ))
@@ -593,6 +601,8 @@ pub enum InvalidBlockHeaderError {
     ParentBeaconBlockRootNotPresent,
     #[error("Requests hash is not present")]
     RequestsHashNotPresent,
+    #[error("Block access list hash is not present")]
+    BlockAccessListHashNotPresent,
     // Other fork errors
     #[error("Excess blob gas is present")]
     ExcessBlobGasPresent,
@@ -602,6 +612,8 @@ pub enum InvalidBlockHeaderError {
     ParentBeaconBlockRootPresent,
     #[error("Requests hash is present")]
     RequestsHashPresent,
+    #[error("Block access list hash is present")]
+    BlockAccessListHashPresent,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -742,6 +754,15 @@ pub fn validate_prague_header_fields(
     if header.requests_hash.is_none() {
         return Err(InvalidBlockHeaderError::RequestsHashNotPresent);
     }
+    // EIP-7928: Amsterdam blocks commit to their block access list
+    match (
+        chain_config.is_amsterdam_activated(header.timestamp),
+        header.block_access_list_hash,
+    ) {
+        (true, None) => return Err(InvalidBlockHeaderError::BlockAccessListHashNotPresent),
+        (false, Some(_)) => return Err(InvalidBlockHeaderError::BlockAccessListHashPresent),
+        _ => {}
+    }
     Ok(())
 }
 
@@ -765,6 +786,9 @@ pub fn validate_cancun_header_fields(
     if header.requests_hash.is_some() {
         return Err(InvalidBlockHeaderError::RequestsHashPresent);
     }
+    if header.block_access_list_hash.is_some() {
+        return Err(InvalidBlockHeaderError::BlockAccessListHashPresent);
+    }
     Ok(())
 }
 
@@ -785,6 +809,9 @@ pub fn validate_pre_cancun_header_fields(
     if header.requests_hash.is_some() {
         return Err(InvalidBlockHeaderError::RequestsHashPresent);
     }
+    if header.block_access_list_hash.is_some() {
+        return Err(InvalidBlockHeaderError::BlockAccessListHashPresent);
+    }
     Ok(())
 }
 
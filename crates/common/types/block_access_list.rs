@@ -0,0 +1,498 @@
+//! Block-level access lists as defined by [EIP-7928](https://eips.ethereum.org/EIPS/eip-7928).
+//!
+//! The list records every account and storage slot accessed while executing a block, along
+//! with the values they were left with after each change. Changes are keyed by a block access
+//! index: 0 for the system calls done before the transactions, `i` for the i-th transaction
+//! (1-based) and `n + 1` for the withdrawals and requests processed after the `n` transactions.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bytes::Bytes;
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, H256, U256, utils::keccak};
+
+/// Position within the block at which a change happened, see the module docs
+pub type BlockAccessIndex = u16;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockAccessList(pub Vec<AccountChanges>);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountChanges {
+    pub address: Address,
+    /// Slots written during the block, sorted by slot
+    pub storage_changes: Vec<SlotChanges>,
+    /// Slots read but never written during the block, sorted
+    pub storage_reads: Vec<U256>,
+    pub balance_changes: Vec<BalanceChange>,
+    pub nonce_changes: Vec<NonceChange>,
+    pub code_changes: Vec<CodeChange>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotChanges {
+    pub slot: U256,
+    pub changes: Vec<StorageChange>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageChange {
+    pub block_access_index: BlockAccessIndex,
+    pub post_value: U256,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub block_access_index: BlockAccessIndex,
+    pub post_balance: U256,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceChange {
+    pub block_access_index: BlockAccessIndex,
+    pub post_nonce: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeChange {
+    pub block_access_index: BlockAccessIndex,
+    pub new_code: Bytes,
+}
+
+impl BlockAccessList {
+    /// Hash committed to by the `block_access_list_hash` header field
+    pub fn hash(&self) -> H256 {
+        keccak(self.encode_to_vec())
+    }
+
+    /// Addresses of every account accessed during the block
+    pub fn accessed_accounts(&self) -> impl Iterator<Item = Address> + '_ {
+        self.0.iter().map(|account| account.address)
+    }
+
+    /// Every storage slot read or written during the block, useful to prefetch the state needed
+    /// to execute it
+    pub fn accessed_slots(&self) -> impl Iterator<Item = (Address, H256)> + '_ {
+        self.0.iter().flat_map(|account| {
+            account
+                .storage_changes
+                .iter()
+                .map(|slot_changes| &slot_changes.slot)
+                .chain(account.storage_reads.iter())
+                .map(|slot| (account.address, H256(slot.to_big_endian())))
+        })
+    }
+
+    /// Changes made by the given block access index, e.g. to check that an independently executed
+    /// transaction produced the same post-state
+    pub fn changes_at(&self, index: BlockAccessIndex) -> impl Iterator<Item = &AccountChanges> {
+        self.0
+            .iter()
+            .filter(move |account| account.changed_at(index))
+    }
+}
+
+impl AccountChanges {
+    fn changed_at(&self, index: BlockAccessIndex) -> bool {
+        self.storage_changes.iter().any(|slot_changes| {
+            slot_changes
+                .changes
+                .iter()
+                .any(|change| change.block_access_index == index)
+        }) || self
+            .balance_changes
+            .iter()
+            .any(|change| change.block_access_index == index)
+            || self
+                .nonce_changes
+                .iter()
+                .any(|change| change.block_access_index == index)
+            || self
+                .code_changes
+                .iter()
+                .any(|change| change.block_access_index == index)
+    }
+
+    /// Balance left by the changes made before the given index, `None` if it wasn't changed yet.
+    /// Together with the other `*_before` functions this gives the state a transaction starts
+    /// from without executing the ones before it.
+    pub fn balance_before(&self, index: BlockAccessIndex) -> Option<U256> {
+        self.balance_changes
+            .iter()
+            .take_while(|change| change.block_access_index < index)
+            .last()
+            .map(|change| change.post_balance)
+    }
+
+    pub fn nonce_before(&self, index: BlockAccessIndex) -> Option<u64> {
+        self.nonce_changes
+            .iter()
+            .take_while(|change| change.block_access_index < index)
+            .last()
+            .map(|change| change.post_nonce)
+    }
+
+    pub fn code_before(&self, index: BlockAccessIndex) -> Option<&Bytes> {
+        self.code_changes
+            .iter()
+            .take_while(|change| change.block_access_index < index)
+            .last()
+            .map(|change| &change.new_code)
+    }
+
+    pub fn storage_before(&self, slot: U256, index: BlockAccessIndex) -> Option<U256> {
+        self.storage_changes
+            .iter()
+            .find(|slot_changes| slot_changes.slot == slot)?
+            .changes
+            .iter()
+            .take_while(|change| change.block_access_index < index)
+            .last()
+            .map(|change| change.post_value)
+    }
+}
+
+/// Collects accesses and changes in any order and builds the list in its canonical order
+#[derive(Debug, Clone, Default)]
+pub struct BlockAccessListBuilder {
+    accounts: BTreeMap<Address, AccountChangesBuilder>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct AccountChangesBuilder {
+    storage_changes: BTreeMap<U256, BTreeMap<BlockAccessIndex, U256>>,
+    storage_reads: BTreeSet<U256>,
+    balance_changes: BTreeMap<BlockAccessIndex, U256>,
+    nonce_changes: BTreeMap<BlockAccessIndex, u64>,
+    code_changes: BTreeMap<BlockAccessIndex, Bytes>,
+}
+
+impl BlockAccessListBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an access to the account, which is listed even if it has no changes
+    pub fn touch_account(&mut self, address: Address) {
+        self.accounts.entry(address).or_default();
+    }
+
+    pub fn read_storage(&mut self, address: Address, slot: H256) {
+        self.account(address)
+            .storage_reads
+            .insert(U256::from_big_endian(slot.as_bytes()));
+    }
+
+    pub fn write_storage(
+        &mut self,
+        address: Address,
+        slot: H256,
+        index: BlockAccessIndex,
+        post_value: U256,
+    ) {
+        self.account(address)
+            .storage_changes
+            .entry(U256::from_big_endian(slot.as_bytes()))
+            .or_default()
+            .insert(index, post_value);
+    }
+
+    pub fn set_balance(&mut self, address: Address, index: BlockAccessIndex, post_balance: U256) {
+        self.account(address)
+            .balance_changes
+            .insert(index, post_balance);
+    }
+
+    pub fn set_nonce(&mut self, address: Address, index: BlockAccessIndex, post_nonce: u64) {
+        self.account(address)
+            .nonce_changes
+            .insert(index, post_nonce);
+    }
+
+    pub fn set_code(&mut self, address: Address, index: BlockAccessIndex, new_code: Bytes) {
+        self.account(address).code_changes.insert(index, new_code);
+    }
+
+    fn account(&mut self, address: Address) -> &mut AccountChangesBuilder {
+        self.accounts.entry(address).or_default()
+    }
+
+    pub fn build(self) -> BlockAccessList {
+        BlockAccessList(
+            self.accounts
+                .into_iter()
+                .map(|(address, account)| AccountChanges {
+                    address,
+                    storage_reads: account
+                        .storage_reads
+                        .into_iter()
+                        .filter(|slot| !account.storage_changes.contains_key(slot))
+                        .collect(),
+                    storage_changes: account
+                        .storage_changes
+                        .into_iter()
+                        .map(|(slot, changes)| SlotChanges {
+                            slot,
+                            changes: changes
+                                .into_iter()
+                                .map(|(block_access_index, post_value)| StorageChange {
+                                    block_access_index,
+                                    post_value,
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                    balance_changes: account
+                        .balance_changes
+                        .into_iter()
+                        .map(|(block_access_index, post_balance)| BalanceChange {
+                            block_access_index,
+                            post_balance,
+                        })
+                        .collect(),
+                    nonce_changes: account
+                        .nonce_changes
+                        .into_iter()
+                        .map(|(block_access_index, post_nonce)| NonceChange {
+                            block_access_index,
+                            post_nonce,
+                        })
+                        .collect(),
+                    code_changes: account
+                        .code_changes
+                        .into_iter()
+                        .map(|(block_access_index, new_code)| CodeChange {
+                            block_access_index,
+                            new_code,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl RLPEncode for BlockAccessList {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        self.0.encode(buf)
+    }
+}
+
+impl RLPDecode for BlockAccessList {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let (accounts, rest) = Vec::decode_unfinished(rlp)?;
+        Ok((Self(accounts), rest))
+    }
+}
+
+impl RLPEncode for AccountChanges {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.address)
+            .encode_field(&self.storage_changes)
+            .encode_field(&self.storage_reads)
+            .encode_field(&self.balance_changes)
+            .encode_field(&self.nonce_changes)
+            .encode_field(&self.code_changes)
+            .finish();
+    }
+}
+
+impl RLPDecode for AccountChanges {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (address, decoder) = decoder.decode_field("address")?;
+        let (storage_changes, decoder) = decoder.decode_field("storage_changes")?;
+        let (storage_reads, decoder) = decoder.decode_field("storage_reads")?;
+        let (balance_changes, decoder) = decoder.decode_field("balance_changes")?;
+        let (nonce_changes, decoder) = decoder.decode_field("nonce_changes")?;
+        let (code_changes, decoder) = decoder.decode_field("code_changes")?;
+        Ok((
+            Self {
+                address,
+                storage_changes,
+                storage_reads,
+                balance_changes,
+                nonce_changes,
+                code_changes,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+impl RLPEncode for SlotChanges {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.slot)
+            .encode_field(&self.changes)
+            .finish();
+    }
+}
+
+impl RLPDecode for SlotChanges {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (slot, decoder) = decoder.decode_field("slot")?;
+        let (changes, decoder) = decoder.decode_field("changes")?;
+        Ok((Self { slot, changes }, decoder.finish()?))
+    }
+}
+
+impl RLPEncode for StorageChange {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.block_access_index)
+            .encode_field(&self.post_value)
+            .finish();
+    }
+}
+
+impl RLPDecode for StorageChange {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (block_access_index, decoder) = decoder.decode_field("block_access_index")?;
+        let (post_value, decoder) = decoder.decode_field("post_value")?;
+        Ok((
+            Self {
+                block_access_index,
+                post_value,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+impl RLPEncode for BalanceChange {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.block_access_index)
+            .encode_field(&self.post_balance)
+            .finish();
+    }
+}
+
+impl RLPDecode for BalanceChange {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (block_access_index, decoder) = decoder.decode_field("block_access_index")?;
+        let (post_balance, decoder) = decoder.decode_field("post_balance")?;
+        Ok((
+            Self {
+                block_access_index,
+                post_balance,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+impl RLPEncode for NonceChange {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.block_access_index)
+            .encode_field(&self.post_nonce)
+            .finish();
+    }
+}
+
+impl RLPDecode for NonceChange {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (block_access_index, decoder) = decoder.decode_field("block_access_index")?;
+        let (post_nonce, decoder) = decoder.decode_field("post_nonce")?;
+        Ok((
+            Self {
+                block_access_index,
+                post_nonce,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+impl RLPEncode for CodeChange {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.block_access_index)
+            .encode_field(&self.new_code)
+            .finish();
+    }
+}
+
+impl RLPDecode for CodeChange {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (block_access_index, decoder) = decoder.decode_field("block_access_index")?;
+        let (new_code, decoder) = decoder.decode_field("new_code")?;
+        Ok((
+            Self {
+                block_access_index,
+                new_code,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::EMPTY_BLOCK_ACCESS_LIST_HASH;
+
+    #[test]
+    fn empty_list_hash() {
+        assert_eq!(
+            BlockAccessList::default().hash(),
+            *EMPTY_BLOCK_ACCESS_LIST_HASH
+        );
+    }
+
+    #[test]
+    fn builder_produces_canonical_order() {
+        let first = Address::from_low_u64_be(1);
+        let second = Address::from_low_u64_be(2);
+        let slot = H256::from_low_u64_be(7);
+
+        let mut builder = BlockAccessListBuilder::new();
+        builder.set_balance(second, 2, 10.into());
+        builder.set_balance(second, 1, 20.into());
+        builder.touch_account(first);
+        builder.read_storage(second, slot);
+        builder.read_storage(second, H256::from_low_u64_be(3));
+        builder.write_storage(second, slot, 1, 5.into());
+        let list = builder.build();
+
+        assert_eq!(
+            list.accessed_accounts().collect::<Vec<_>>(),
+            [first, second]
+        );
+        let account = &list.0[1];
+        assert_eq!(
+            account
+                .balance_changes
+                .iter()
+                .map(|change| change.block_access_index)
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+        // Written slots aren't listed as reads
+        assert_eq!(account.storage_reads, [U256::from(3)]);
+        assert_eq!(account.storage_before(7.into(), 1), None);
+        assert_eq!(account.storage_before(7.into(), 2), Some(5.into()));
+        assert_eq!(account.balance_before(3), Some(10.into()));
+        assert_eq!(list.changes_at(2).count(), 1);
+        assert_eq!(list.changes_at(3).count(), 0);
+
+        let decoded = BlockAccessList::decode(&list.encode_to_vec()).unwrap();
+        assert_eq!(decoded, list);
+        assert_eq!(decoded.hash(), list.hash());
+    }
+}
@@ -30,7 +30,7 @@ impl fmt::Display for ForkId {
 
 impl ForkId {
     pub fn new(
-        chain_config: ChainConfig,
+        chain_config: &ChainConfig,
         genesis_header: BlockHeader,
         head_timestamp: u64,
         head_block_number: u64,
@@ -70,7 +70,7 @@ impl ForkId {
         remote: Self,
         latest_block_number: u64,
         head_timestamp: u64,
-        chain_config: ChainConfig,
+        chain_config: &ChainConfig,
         genesis_header: BlockHeader,
     ) -> bool {
         let genesis_hash = genesis_header.hash();
@@ -237,7 +237,7 @@ mod tests {
     ) {
         for test_case in test_cases {
             let fork_id = ForkId::new(
-                chain_config,
+                &chain_config,
                 genesis_header.clone(),
                 test_case.time,
                 test_case.head,
@@ -247,7 +247,7 @@ mod tests {
                     test_case.fork_id,
                     test_case.head,
                     test_case.time,
-                    chain_config,
+                    &chain_config,
                     genesis_header.clone()
                 ),
                 test_case.is_valid
@@ -680,14 +680,14 @@ mod tests {
             remote.clone(),
             local_head_block_number,
             0,
-            ChainConfig::default(),
+            &ChainConfig::default(),
             BlockHeader::default(),
         );
         let result_b = local_b.is_valid(
             remote,
             local_head_block_number,
             0,
-            ChainConfig::default(),
+            &ChainConfig::default(),
             BlockHeader::default(),
         );
         assert!(!result_a);
@@ -15,9 +15,10 @@ use tracing::warn;
 use super::{
     AccountState, Block, BlockBody, BlockHeader, BlockNumber, INITIAL_BASE_FEE,
     compute_receipts_root, compute_transactions_root, compute_withdrawals_root,
+    custom_precompiles::CustomPrecompiles,
 };
 use crate::{
-    constants::{DEFAULT_OMMERS_HASH, DEFAULT_REQUESTS_HASH},
+    constants::{DEFAULT_OMMERS_HASH, DEFAULT_REQUESTS_HASH, EMPTY_BLOCK_ACCESS_LIST_HASH},
     rkyv_utils,
 };
 
@@ -51,6 +52,8 @@ pub struct Genesis {
     #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
     pub excess_blob_gas: Option<u64>,
     pub requests_hash: Option<H256>,
+    #[serde(default)]
+    pub block_access_list_hash: Option<H256>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -197,16 +200,7 @@ fn default_bpo2_schedule() -> ForkBlobSchedule {
 /// Blockchain settings defined per block
 #[allow(unused)]
 #[derive(
-    Clone,
-    Copy,
-    Debug,
-    Serialize,
-    Deserialize,
-    Default,
-    PartialEq,
-    RSerialize,
-    RDeserialize,
-    Archive,
+    Clone, Debug, Serialize, Deserialize, Default, PartialEq, RSerialize, RDeserialize, Archive,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct ChainConfig {
@@ -250,6 +244,12 @@ pub struct ChainConfig {
     pub bpo3_time: Option<u64>,
     pub bpo4_time: Option<u64>,
     pub bpo5_time: Option<u64>,
+    pub amsterdam_time: Option<u64>,
+
+    /// Experimental: timestamp from which gas is charged for state accesses as defined by
+    /// EIP-4762 (stateless gas costs over the unified binary tree). Not part of any fork.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eip4762_time: Option<u64>,
 
     /// Amount of total difficulty reached by the network that triggers the consensus upgrade.
     pub terminal_total_difficulty: Option<u128>,
@@ -264,6 +264,10 @@ pub struct ChainConfig {
 
     #[serde(default)]
     pub enable_verkle_at_genesis: bool,
+
+    /// Additional precompiles declared by an L2, ignored on L1
+    #[serde(default, skip_serializing_if = "CustomPrecompiles::is_empty")]
+    pub custom_precompiles: CustomPrecompiles,
 }
 
 lazy_static::lazy_static! {
@@ -308,6 +312,7 @@ pub enum Fork {
     BPO3 = 22,
     BPO4 = 23,
     BPO5 = 24,
+    Amsterdam = 25,
 }
 
 impl From<Fork> for &str {
@@ -338,11 +343,22 @@ impl From<Fork> for &str {
             Fork::BPO3 => "BPO3",
             Fork::BPO4 => "BPO4",
             Fork::BPO5 => "BPO5",
+            Fork::Amsterdam => "Amsterdam",
         }
     }
 }
 
 impl ChainConfig {
+    pub fn is_amsterdam_activated(&self, block_timestamp: u64) -> bool {
+        self.amsterdam_time
+            .is_some_and(|time| time <= block_timestamp)
+    }
+
+    pub fn is_eip4762_activated(&self, block_timestamp: u64) -> bool {
+        self.eip4762_time
+            .is_some_and(|time| time <= block_timestamp)
+    }
+
     pub fn is_bpo1_activated(&self, block_timestamp: u64) -> bool {
         self.bpo1_time.is_some_and(|time| time <= block_timestamp)
     }
@@ -408,6 +424,7 @@ impl ChainConfig {
             ("Prague", self.prague_time),
             ("Verkle", self.verkle_time),
             ("Osaka", self.osaka_time),
+            ("Amsterdam", self.amsterdam_time),
         ];
 
         let active_forks: Vec<_> = post_merge_forks
@@ -423,11 +440,28 @@ impl ChainConfig {
             output.push_str("Network is at Paris\n\n");
         }
 
+        if !self.custom_precompiles.is_empty() {
+            output.push_str("\n\nCustom precompiles:\n");
+            let custom_precompiles: Vec<_> = self
+                .custom_precompiles
+                .iter()
+                .map(|precompile| {
+                    format!(
+                        "- {:#x}: {:?} @{}",
+                        precompile.address, precompile.kind, precompile.activation_time
+                    )
+                })
+                .collect();
+            output.push_str(&custom_precompiles.join("\n"));
+        }
+
         output
     }
 
     pub fn get_fork(&self, block_timestamp: u64) -> Fork {
-        if self.is_bpo5_activated(block_timestamp) {
+        if self.is_amsterdam_activated(block_timestamp) {
+            Fork::Amsterdam
+        } else if self.is_bpo5_activated(block_timestamp) {
             Fork::BPO5
         } else if self.is_bpo4_activated(block_timestamp) {
             Fork::BPO4
@@ -500,12 +534,18 @@ impl ChainConfig {
         };
         match next {
             Some(fork) if fork > self.fork(block_timestamp) => next,
+            // Amsterdam follows whichever blob parameter only fork is the last one scheduled
+            _ if self.amsterdam_time.is_some() && !self.is_amsterdam_activated(block_timestamp) => {
+                Some(Fork::Amsterdam)
+            }
             _ => None,
         }
     }
 
     pub fn get_last_scheduled_fork(&self) -> Fork {
-        if self.bpo5_time.is_some() {
+        if self.amsterdam_time.is_some() {
+            Fork::Amsterdam
+        } else if self.bpo5_time.is_some() {
             Fork::BPO5
         } else if self.bpo4_time.is_some() {
             Fork::BPO4
@@ -536,6 +576,7 @@ impl ChainConfig {
             Fork::BPO3 => self.bpo3_time,
             Fork::BPO4 => self.bpo4_time,
             Fork::BPO5 => self.bpo5_time,
+            Fork::Amsterdam => self.amsterdam_time,
             Fork::Homestead => self.homestead_block,
             Fork::DaoFork => self.dao_fork_block,
             Fork::Byzantium => self.byzantium_block,
@@ -563,6 +604,10 @@ impl ChainConfig {
             Fork::BPO3 => self.blob_schedule.bpo3,
             Fork::BPO4 => self.blob_schedule.bpo4,
             Fork::BPO5 => self.blob_schedule.bpo5,
+            // Amsterdam keeps the blob parameters of the fork it activates after
+            Fork::Amsterdam => self
+                .amsterdam_time
+                .and_then(|time| self.get_fork_blob_schedule(time)),
             _ => None,
         }
     }
@@ -607,6 +652,7 @@ impl ChainConfig {
             self.bpo3_time,
             self.bpo4_time,
             self.bpo5_time,
+            self.amsterdam_time,
             self.verkle_time,
         ]
         .into_iter()
@@ -674,6 +720,14 @@ impl Genesis {
             .is_prague_activated(self.timestamp)
             .then_some(self.requests_hash.unwrap_or(*DEFAULT_REQUESTS_HASH));
 
+        let block_access_list_hash = self
+            .config
+            .is_amsterdam_activated(self.timestamp)
+            .then_some(
+                self.block_access_list_hash
+                    .unwrap_or(*EMPTY_BLOCK_ACCESS_LIST_HASH),
+            );
+
         BlockHeader {
             parent_hash: H256::zero(),
             ommers_hash: *DEFAULT_OMMERS_HASH,
@@ -696,6 +750,7 @@ impl Genesis {
             excess_blob_gas,
             parent_beacon_block_root,
             requests_hash,
+            block_access_list_hash,
             ..Default::default()
         }
     }
@@ -1,3 +1,4 @@
 pub mod balance_diff;
 pub mod batch;
+pub mod custom_precompiles;
 pub mod fee_config;
@@ -0,0 +1,215 @@
+use ethereum_types::Address;
+use rkyv::{Archive, Deserialize as RDeserialize, Serialize as RSerialize};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::rkyv_utils::H160Wrapper;
+
+/// Addresses up to this one are reserved for the precompiles defined by Ethereum
+pub const MAX_RESERVED_PRECOMPILE_ADDRESS: u64 = 0x100;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CustomPrecompilesError {
+    #[error("Custom precompile address {0:#x} is declared more than once")]
+    DuplicatedAddress(Address),
+    #[error("Custom precompile address {0:#x} is reserved for Ethereum precompiles")]
+    ReservedAddress(Address),
+}
+
+/// Native implementations available to custom precompiles. They are executed by LEVM, both in the
+/// node and in the guest program.
+#[derive(
+    Serialize, Deserialize, RDeserialize, RSerialize, Archive, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CustomPrecompileKind {
+    /// Poseidon hash (Starknet parameters) of the calldata, read as a list of 32-byte big-endian
+    /// field elements
+    Poseidon,
+    /// Returns the value of the storage slot of `contract` given as calldata, meant to expose
+    /// values written by a system contract (e.g. an oracle) at a fixed gas cost
+    StorageOracle {
+        #[rkyv(with = H160Wrapper)]
+        contract: Address,
+    },
+}
+
+/// A precompile declared by an L2 in its chain config.
+///
+/// Its gas cost is `base_gas + word_gas * ceil(len(calldata) / 32)`.
+#[derive(
+    Serialize, Deserialize, RDeserialize, RSerialize, Archive, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomPrecompileConfig {
+    #[rkyv(with = H160Wrapper)]
+    pub address: Address,
+    pub kind: CustomPrecompileKind,
+    pub base_gas: u64,
+    #[serde(default)]
+    pub word_gas: u64,
+    /// Timestamp from which the precompile is active
+    #[serde(default)]
+    pub activation_time: u64,
+}
+
+impl CustomPrecompileConfig {
+    pub fn is_active(&self, block_timestamp: u64) -> bool {
+        self.activation_time <= block_timestamp
+    }
+}
+
+/// The custom precompiles of a chain, shared by every copy of its config.
+/// It's (de)serialized as a list.
+#[derive(RDeserialize, RSerialize, Archive, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CustomPrecompiles {
+    entries: Arc<[CustomPrecompileConfig]>,
+}
+
+impl CustomPrecompiles {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CustomPrecompileConfig> {
+        self.entries.iter()
+    }
+
+    pub fn get(&self, address: &Address) -> Option<&CustomPrecompileConfig> {
+        self.iter()
+            .find(|precompile| precompile.address == *address)
+    }
+
+    /// Returns the precompiles that are active at the given timestamp
+    pub fn active_at(&self, block_timestamp: u64) -> Self {
+        if self
+            .iter()
+            .all(|precompile| precompile.is_active(block_timestamp))
+        {
+            return self.clone();
+        }
+        Self {
+            entries: self
+                .iter()
+                .filter(|precompile| precompile.is_active(block_timestamp))
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<Vec<CustomPrecompileConfig>> for CustomPrecompiles {
+    type Error = CustomPrecompilesError;
+
+    fn try_from(precompiles: Vec<CustomPrecompileConfig>) -> Result<Self, Self::Error> {
+        let reserved = Address::from_low_u64_be(MAX_RESERVED_PRECOMPILE_ADDRESS);
+        for precompile in &precompiles {
+            if precompile.address <= reserved {
+                return Err(CustomPrecompilesError::ReservedAddress(precompile.address));
+            }
+            if precompiles
+                .iter()
+                .filter(|other| other.address == precompile.address)
+                .count()
+                > 1
+            {
+                return Err(CustomPrecompilesError::DuplicatedAddress(
+                    precompile.address,
+                ));
+            }
+        }
+        Ok(Self {
+            entries: precompiles.into(),
+        })
+    }
+}
+
+impl From<CustomPrecompiles> for Vec<CustomPrecompileConfig> {
+    fn from(precompiles: CustomPrecompiles) -> Self {
+        precompiles.entries.to_vec()
+    }
+}
+
+impl Serialize for CustomPrecompiles {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomPrecompiles {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let precompiles = Vec::<CustomPrecompileConfig>::deserialize(deserializer)?;
+        Self::try_from(precompiles).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn precompile(address: u64, activation_time: u64) -> CustomPrecompileConfig {
+        CustomPrecompileConfig {
+            address: Address::from_low_u64_be(address),
+            kind: CustomPrecompileKind::Poseidon,
+            base_gas: 100,
+            word_gas: 10,
+            activation_time,
+        }
+    }
+
+    #[test]
+    fn deserialize_custom_precompiles() {
+        let json = r#"[
+            {"address": "0x0000000000000000000000000000000000000101", "kind": {"type": "poseidon"}, "baseGas": 100, "wordGas": 10},
+            {"address": "0x0000000000000000000000000000000000000102", "kind": {"type": "storageOracle", "contract": "0x000000000000000000000000000000000000ffff"}, "baseGas": 2100, "activationTime": 50}
+        ]"#;
+        let precompiles: CustomPrecompiles = serde_json::from_str(json).unwrap();
+
+        assert_eq!(precompiles.iter().count(), 2);
+        let oracle = precompiles.get(&Address::from_low_u64_be(0x102)).unwrap();
+        assert_eq!(
+            oracle.kind,
+            CustomPrecompileKind::StorageOracle {
+                contract: Address::from_low_u64_be(0xffff)
+            }
+        );
+        assert_eq!(oracle.word_gas, 0);
+
+        let active = precompiles.active_at(10);
+        assert!(active.get(&Address::from_low_u64_be(0x101)).is_some());
+        assert!(active.get(&Address::from_low_u64_be(0x102)).is_none());
+
+        let serialized = serde_json::to_string(&precompiles).unwrap();
+        let roundtrip: CustomPrecompiles = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(roundtrip, precompiles);
+    }
+
+    #[test]
+    fn reject_invalid_custom_precompiles() {
+        assert_eq!(
+            CustomPrecompiles::try_from(vec![precompile(0x101, 0), precompile(0x101, 5)]),
+            Err(CustomPrecompilesError::DuplicatedAddress(
+                Address::from_low_u64_be(0x101)
+            ))
+        );
+        assert_eq!(
+            CustomPrecompiles::try_from(vec![precompile(0x0a, 0)]),
+            Err(CustomPrecompilesError::ReservedAddress(
+                Address::from_low_u64_be(0x0a)
+            ))
+        );
+    }
+
+    #[test]
+    fn any_amount_of_custom_precompiles() {
+        let precompiles: Vec<_> = (0..32).map(|i| precompile(0x200 + i, i)).collect();
+        let custom_precompiles = CustomPrecompiles::try_from(precompiles.clone()).unwrap();
+        assert_eq!(Vec::from(custom_precompiles.clone()), precompiles);
+        assert_eq!(custom_precompiles.active_at(15).iter().count(), 16);
+        // Copies of the config share the list
+        assert!(Arc::ptr_eq(
+            &custom_precompiles.entries,
+            &custom_precompiles.clone().entries
+        ));
+    }
+}
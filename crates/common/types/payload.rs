@@ -1,4 +1,4 @@
-use super::{BlobsBundle, Block, requests::EncodedRequests};
+use super::{BlobsBundle, Block, block_access_list::BlockAccessList, requests::EncodedRequests};
 use ethereum_types::U256;
 
 #[derive(Debug, Clone)]
@@ -7,6 +7,8 @@ pub struct PayloadBundle {
     pub block_value: U256,
     pub blobs_bundle: BlobsBundle,
     pub requests: Vec<EncodedRequests>,
+    /// Only built for Amsterdam payloads
+    pub block_access_list: Option<BlockAccessList>,
 }
 
 impl PayloadBundle {
@@ -16,6 +18,7 @@ impl PayloadBundle {
             block_value: U256::zero(),
             blobs_bundle: BlobsBundle::empty(),
             requests: Vec::default(),
+            block_access_list: None,
         }
     }
 }
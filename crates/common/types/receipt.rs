@@ -176,6 +176,7 @@ impl ReceiptWithBloom {
                     0x2 => TxType::EIP1559,
                     0x3 => TxType::EIP4844,
                     0x4 => TxType::EIP7702,
+                    0x5 => TxType::RIP7560,
                     0x7d => TxType::FeeToken,
                     0x7e => TxType::Privileged,
                     ty => {
@@ -239,6 +240,7 @@ impl RLPDecode for ReceiptWithBloom {
                 0x2 => TxType::EIP1559,
                 0x3 => TxType::EIP4844,
                 0x4 => TxType::EIP7702,
+                0x5 => TxType::RIP7560,
                 0x7d => TxType::FeeToken,
                 0x7e => TxType::Privileged,
                 ty => {
@@ -43,11 +43,13 @@ pub enum Transaction {
     EIP7702Transaction(EIP7702Transaction),
     PrivilegedL2Transaction(PrivilegedL2Transaction),
     FeeTokenTransaction(FeeTokenTransaction),
+    RIP7560Transaction(RIP7560Transaction),
 }
 
 /// The same as a Transaction enum, only that blob transactions are in wrapped format, including
 /// the blobs bundle.
 /// PrivilegedL2Transaction is not included as it is not expected to be sent over P2P.
+/// RIP7560Transaction is not included either, it is only accepted through the RPC for now.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum P2PTransaction {
     LegacyTransaction(LegacyTransaction),
@@ -324,6 +326,7 @@ pub enum TxType {
     EIP1559 = 0x02,
     EIP4844 = 0x03,
     EIP7702 = 0x04,
+    RIP7560 = 0x05,
     FeeToken = 0x7d,
     // We take the same approach as Optimism to define the privileged tx prefix
     // https://github.com/ethereum-optimism/specs/blob/c6903a3b2cad575653e1f5ef472debb573d83805/specs/protocol/deposits.md#the-deposited-transaction-type
@@ -338,6 +341,7 @@ impl From<TxType> for u8 {
             TxType::EIP1559 => 0x02,
             TxType::EIP4844 => 0x03,
             TxType::EIP7702 => 0x04,
+            TxType::RIP7560 => 0x05,
             TxType::FeeToken => 0x7d,
             TxType::Privileged => 0x7e,
         }
@@ -352,6 +356,7 @@ impl Display for TxType {
             TxType::EIP1559 => write!(f, "EIP1559"),
             TxType::EIP4844 => write!(f, "EIP4844"),
             TxType::EIP7702 => write!(f, "EIP7702"),
+            TxType::RIP7560 => write!(f, "RIP7560"),
             TxType::Privileged => write!(f, "Privileged"),
             TxType::FeeToken => write!(f, "FeeToken"),
         }
@@ -367,6 +372,7 @@ impl Transaction {
             Transaction::EIP4844Transaction(_) => TxType::EIP4844,
             Transaction::EIP7702Transaction(_) => TxType::EIP7702,
             Transaction::FeeTokenTransaction(_) => TxType::FeeToken,
+            Transaction::RIP7560Transaction(_) => TxType::RIP7560,
             Transaction::PrivilegedL2Transaction(_) => TxType::Privileged,
         }
     }
@@ -391,6 +397,7 @@ impl Transaction {
             TxType::EIP4844 => self.calc_effective_gas_price(base_fee_per_gas),
             TxType::EIP7702 => self.calc_effective_gas_price(base_fee_per_gas),
             TxType::FeeToken => self.calc_effective_gas_price(base_fee_per_gas),
+            TxType::RIP7560 => self.calc_effective_gas_price(base_fee_per_gas),
             TxType::Privileged => Some(self.gas_price()),
         }
     }
@@ -403,6 +410,7 @@ impl Transaction {
             TxType::EIP4844 => U256::from(self.max_fee_per_gas()?),
             TxType::EIP7702 => U256::from(self.max_fee_per_gas()?),
             TxType::FeeToken => U256::from(self.max_fee_per_gas()?),
+            TxType::RIP7560 => U256::from(self.max_fee_per_gas()?),
             TxType::Privileged => self.gas_price(),
         };
 
@@ -461,6 +469,9 @@ impl RLPDecode for Transaction {
                 // EIP7702
                 0x4 => EIP7702Transaction::decode(tx_encoding)
                     .map(|tx| (Transaction::EIP7702Transaction(tx), remainder)),
+                // RIP7560
+                0x5 => RIP7560Transaction::decode(tx_encoding)
+                    .map(|tx| (Transaction::RIP7560Transaction(tx), remainder)),
                 // FeeToken
                 0x7d => FeeTokenTransaction::decode(tx_encoding)
                     .map(|tx| (Transaction::FeeTokenTransaction(tx), remainder)),
@@ -661,6 +672,12 @@ impl RLPEncode for FeeTokenTransaction {
     }
 }
 
+impl RLPEncode for RIP7560Transaction {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        self.encode_with_validation_data(&self.sender_validation_data, buf)
+    }
+}
+
 impl PayloadRLPEncode for Transaction {
     fn encode_payload(&self, buf: &mut dyn bytes::BufMut) {
         match self {
@@ -671,6 +688,7 @@ impl PayloadRLPEncode for Transaction {
             Transaction::EIP7702Transaction(tx) => tx.encode_payload(buf),
             Transaction::PrivilegedL2Transaction(tx) => tx.encode_payload(buf),
             Transaction::FeeTokenTransaction(tx) => tx.encode_payload(buf),
+            Transaction::RIP7560Transaction(tx) => tx.encode_payload(buf),
         }
     }
 }
@@ -788,6 +806,13 @@ impl PayloadRLPEncode for FeeTokenTransaction {
     }
 }
 
+impl PayloadRLPEncode for RIP7560Transaction {
+    /// The validation data usually holds the signature, so it is left empty when signing
+    fn encode_payload(&self, buf: &mut dyn bytes::BufMut) {
+        self.encode_with_validation_data(&Bytes::new(), buf)
+    }
+}
+
 impl RLPDecode for LegacyTransaction {
     fn decode_unfinished(rlp: &[u8]) -> Result<(LegacyTransaction, &[u8]), RLPDecodeError> {
         let decoder = Decoder::new(rlp)?;
@@ -1041,6 +1066,67 @@ impl RLPDecode for FeeTokenTransaction {
     }
 }
 
+impl RLPDecode for RIP7560Transaction {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(RIP7560Transaction, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (chain_id, decoder) = decoder.decode_field("chain_id")?;
+        let (nonce, decoder) = decoder.decode_field("nonce")?;
+        let (sender, decoder) = decoder.decode_field("sender")?;
+        let (sender_validation_data, decoder) = decoder.decode_field("sender_validation_data")?;
+        let (deployer, decoder) = decoder.decode_field("deployer")?;
+        let (deployer_data, decoder) = decoder.decode_field("deployer_data")?;
+        let (paymaster, decoder) = decoder.decode_field("paymaster")?;
+        let (paymaster_data, decoder) = decoder.decode_field("paymaster_data")?;
+        let (execution_data, decoder) = decoder.decode_field("execution_data")?;
+        let (max_priority_fee_per_gas, decoder) =
+            decoder.decode_field("max_priority_fee_per_gas")?;
+        let (max_fee_per_gas, decoder) = decoder.decode_field("max_fee_per_gas")?;
+        let (validation_gas_limit, decoder) = decoder.decode_field("validation_gas_limit")?;
+        let (paymaster_validation_gas_limit, decoder) =
+            decoder.decode_field("paymaster_validation_gas_limit")?;
+        let (post_op_gas_limit, decoder) = decoder.decode_field("post_op_gas_limit")?;
+        let (call_gas_limit, decoder) = decoder.decode_field("call_gas_limit")?;
+        let (access_list, decoder) = decoder.decode_field("access_list")?;
+        let inner_hash = OnceCell::new();
+
+        let tx = RIP7560Transaction {
+            chain_id,
+            nonce,
+            sender,
+            sender_validation_data,
+            deployer: decode_optional_address(deployer)?,
+            deployer_data,
+            paymaster: decode_optional_address(paymaster)?,
+            paymaster_data,
+            execution_data,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            validation_gas_limit,
+            paymaster_validation_gas_limit,
+            post_op_gas_limit,
+            call_gas_limit,
+            access_list,
+            inner_hash,
+        };
+        Ok((tx, decoder.finish()?))
+    }
+}
+
+/// Optional addresses are encoded as an empty string when missing
+fn encode_optional_address(address: &Option<Address>) -> Bytes {
+    address
+        .map(|address| Bytes::copy_from_slice(address.as_bytes()))
+        .unwrap_or_default()
+}
+
+fn decode_optional_address(bytes: Bytes) -> Result<Option<Address>, RLPDecodeError> {
+    match bytes.len() {
+        0 => Ok(None),
+        20 => Ok(Some(Address::from_slice(&bytes))),
+        _ => Err(RLPDecodeError::InvalidLength),
+    }
+}
+
 impl Transaction {
     pub fn sender(&self) -> Result<Address, EcdsaError> {
         match self {
@@ -1156,6 +1242,8 @@ impl Transaction {
                 recover_address_from_message(Signature::from_slice(&sig), &Bytes::from(buf))
             }
             Transaction::PrivilegedL2Transaction(tx) => Ok(tx.from),
+            // The sender authorizes the transaction in its validation frame, not with a signature
+            Transaction::RIP7560Transaction(tx) => Ok(tx.sender),
             Transaction::FeeTokenTransaction(tx) => {
                 let mut buf = vec![self.tx_type() as u8];
                 Encoder::new(&mut buf)
@@ -1188,6 +1276,7 @@ impl Transaction {
             Transaction::EIP4844Transaction(tx) => tx.gas,
             Transaction::PrivilegedL2Transaction(tx) => tx.gas_limit,
             Transaction::FeeTokenTransaction(tx) => tx.gas_limit,
+            Transaction::RIP7560Transaction(tx) => tx.gas_limit(),
         }
     }
 
@@ -1201,6 +1290,7 @@ impl Transaction {
             Transaction::EIP4844Transaction(tx) => U256::from(tx.max_fee_per_gas),
             Transaction::PrivilegedL2Transaction(tx) => U256::from(tx.max_fee_per_gas),
             Transaction::FeeTokenTransaction(tx) => U256::from(tx.max_fee_per_gas),
+            Transaction::RIP7560Transaction(tx) => U256::from(tx.max_fee_per_gas),
         }
     }
 
@@ -1213,6 +1303,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => TxKind::Call(tx.to),
             Transaction::PrivilegedL2Transaction(tx) => tx.to.clone(),
             Transaction::FeeTokenTransaction(tx) => tx.to.clone(),
+            Transaction::RIP7560Transaction(tx) => TxKind::Call(tx.sender),
         }
     }
 
@@ -1225,6 +1316,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => tx.value,
             Transaction::PrivilegedL2Transaction(tx) => tx.value,
             Transaction::FeeTokenTransaction(tx) => tx.value,
+            Transaction::RIP7560Transaction(_) => U256::zero(),
         }
     }
 
@@ -1237,6 +1329,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => Some(tx.max_priority_fee_per_gas),
             Transaction::PrivilegedL2Transaction(tx) => Some(tx.max_priority_fee_per_gas),
             Transaction::FeeTokenTransaction(tx) => Some(tx.max_priority_fee_per_gas),
+            Transaction::RIP7560Transaction(tx) => Some(tx.max_priority_fee_per_gas),
         }
     }
 
@@ -1249,6 +1342,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => Some(tx.chain_id),
             Transaction::PrivilegedL2Transaction(tx) => Some(tx.chain_id),
             Transaction::FeeTokenTransaction(tx) => Some(tx.chain_id),
+            Transaction::RIP7560Transaction(tx) => Some(tx.chain_id),
         }
     }
 
@@ -1262,6 +1356,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => &tx.access_list,
             Transaction::PrivilegedL2Transaction(tx) => &tx.access_list,
             Transaction::FeeTokenTransaction(tx) => &tx.access_list,
+            Transaction::RIP7560Transaction(tx) => &tx.access_list,
         }
     }
     pub fn authorization_list(&self) -> Option<&AuthorizationList> {
@@ -1273,6 +1368,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => Some(&tx.authorization_list),
             Transaction::PrivilegedL2Transaction(_) => None,
             Transaction::FeeTokenTransaction(_) => None,
+            Transaction::RIP7560Transaction(_) => None,
         }
     }
 
@@ -1285,6 +1381,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => tx.nonce,
             Transaction::PrivilegedL2Transaction(tx) => tx.nonce,
             Transaction::FeeTokenTransaction(tx) => tx.nonce,
+            Transaction::RIP7560Transaction(tx) => tx.nonce,
         }
     }
 
@@ -1297,6 +1394,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => &tx.data,
             Transaction::PrivilegedL2Transaction(tx) => &tx.data,
             Transaction::FeeTokenTransaction(tx) => &tx.data,
+            Transaction::RIP7560Transaction(tx) => &tx.execution_data,
         }
     }
 
@@ -1309,6 +1407,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(_) => Vec::new(),
             Transaction::PrivilegedL2Transaction(_) => Vec::new(),
             Transaction::FeeTokenTransaction(_) => Vec::new(),
+            Transaction::RIP7560Transaction(_) => Vec::new(),
         }
     }
 
@@ -1321,6 +1420,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(_) => None,
             Transaction::PrivilegedL2Transaction(_) => None,
             Transaction::FeeTokenTransaction(_) => None,
+            Transaction::RIP7560Transaction(_) => None,
         }
     }
 
@@ -1333,6 +1433,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(_) => false,
             Transaction::PrivilegedL2Transaction(t) => matches!(t.to, TxKind::Create),
             Transaction::FeeTokenTransaction(t) => matches!(t.to, TxKind::Create),
+            Transaction::RIP7560Transaction(_) => false,
         }
     }
 
@@ -1349,6 +1450,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => Some(tx.max_fee_per_gas),
             Transaction::PrivilegedL2Transaction(tx) => Some(tx.max_fee_per_gas),
             Transaction::FeeTokenTransaction(tx) => Some(tx.max_fee_per_gas),
+            Transaction::RIP7560Transaction(tx) => Some(tx.max_fee_per_gas),
         }
     }
 
@@ -1368,6 +1470,7 @@ impl Transaction {
             Transaction::EIP7702Transaction(tx) => &tx.inner_hash,
             Transaction::PrivilegedL2Transaction(tx) => &tx.inner_hash,
             Transaction::FeeTokenTransaction(tx) => &tx.inner_hash,
+            Transaction::RIP7560Transaction(tx) => &tx.inner_hash,
         };
 
         *inner_hash.get_or_init(|| self.compute_hash())
@@ -1510,6 +1613,7 @@ impl TxType {
             0x02 => Some(Self::EIP1559),
             0x03 => Some(Self::EIP4844),
             0x04 => Some(Self::EIP7702),
+            0x05 => Some(Self::RIP7560),
             0x7d => Some(Self::FeeToken),
             0x7e => Some(Self::Privileged),
             _ => None,
@@ -1575,6 +1679,88 @@ pub struct FeeTokenTransaction {
     pub inner_hash: OnceCell<H256>,
 }
 
+/// Native account abstraction transaction, as defined by [RIP-7560](https://github.com/ethereum/RIPs/blob/master/RIPS/rip-7560.md).
+/// It is not signed by an EOA: the `sender` is a smart account that authorizes the transaction in
+/// its validation frame, and the fees can be paid by a `paymaster` instead of the sender.
+/// Only supported by L2s. Two-dimensional nonces (RIP-7712), the builder fee and the
+/// authorization list of the RIP are not supported, so they are not part of the encoding.
+#[derive(Clone, Debug, PartialEq, Eq, Default, RSerialize, RDeserialize, Archive)]
+pub struct RIP7560Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    #[rkyv(with=crate::rkyv_utils::H160Wrapper)]
+    pub sender: Address,
+    /// Passed to the sender validation frame, usually holds a signature
+    #[rkyv(with=crate::rkyv_utils::BytesWrapper)]
+    pub sender_validation_data: Bytes,
+    /// Factory deploying the sender account, only set in the first transaction of the account
+    #[rkyv(with=crate::rkyv_utils::OptionH160Wrapper)]
+    pub deployer: Option<Address>,
+    #[rkyv(with=crate::rkyv_utils::BytesWrapper)]
+    pub deployer_data: Bytes,
+    #[rkyv(with=crate::rkyv_utils::OptionH160Wrapper)]
+    pub paymaster: Option<Address>,
+    #[rkyv(with=crate::rkyv_utils::BytesWrapper)]
+    pub paymaster_data: Bytes,
+    /// Calldata of the execution frame, a call to the sender
+    #[rkyv(with=crate::rkyv_utils::BytesWrapper)]
+    pub execution_data: Bytes,
+    pub max_priority_fee_per_gas: u64,
+    pub max_fee_per_gas: u64,
+    /// Gas of the deployment and sender validation frames, the intrinsic gas is also taken from it
+    pub validation_gas_limit: u64,
+    pub paymaster_validation_gas_limit: u64,
+    pub post_op_gas_limit: u64,
+    pub call_gas_limit: u64,
+    #[rkyv(with=rkyv::with::Map<crate::rkyv_utils::AccessListItemWrapper>)]
+    pub access_list: AccessList,
+    #[rkyv(with=rkyv::with::Skip)]
+    pub inner_hash: OnceCell<H256>,
+}
+
+impl RIP7560Transaction {
+    /// Total gas limit of the transaction, the sum of the gas limits of its frames
+    pub fn gas_limit(&self) -> u64 {
+        self.validation_gas_limit
+            .saturating_add(self.paymaster_validation_gas_limit)
+            .saturating_add(self.post_op_gas_limit)
+            .saturating_add(self.call_gas_limit)
+    }
+
+    /// Account charged with the fees of the transaction
+    pub fn payer(&self) -> Address {
+        self.paymaster.unwrap_or(self.sender)
+    }
+
+    /// Hash passed to the validation frames, it doesn't commit to the sender validation data
+    pub fn signing_hash(&self) -> H256 {
+        let mut buf = vec![TxType::RIP7560 as u8];
+        self.encode_payload(&mut buf);
+        keccak(buf)
+    }
+
+    fn encode_with_validation_data(&self, validation_data: &Bytes, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.chain_id)
+            .encode_field(&self.nonce)
+            .encode_field(&self.sender)
+            .encode_field(validation_data)
+            .encode_field(&encode_optional_address(&self.deployer))
+            .encode_field(&self.deployer_data)
+            .encode_field(&encode_optional_address(&self.paymaster))
+            .encode_field(&self.paymaster_data)
+            .encode_field(&self.execution_data)
+            .encode_field(&self.max_priority_fee_per_gas)
+            .encode_field(&self.max_fee_per_gas)
+            .encode_field(&self.validation_gas_limit)
+            .encode_field(&self.paymaster_validation_gas_limit)
+            .encode_field(&self.post_op_gas_limit)
+            .encode_field(&self.call_gas_limit)
+            .encode_field(&self.access_list)
+            .finish()
+    }
+}
+
 /// Canonical Transaction Encoding
 /// Based on [EIP-2718]
 /// Transactions can be encoded in the following formats:
@@ -1613,6 +1799,9 @@ mod canonic_encoding {
                         // EIP7702
                         0x4 => EIP7702Transaction::decode(tx_bytes)
                             .map(Transaction::EIP7702Transaction),
+                        // RIP7560
+                        0x5 => RIP7560Transaction::decode(tx_bytes)
+                            .map(Transaction::RIP7560Transaction),
                         // FeeTokenTransaction
                         0x7d => FeeTokenTransaction::decode(tx_bytes)
                             .map(Transaction::FeeTokenTransaction),
@@ -1647,6 +1836,7 @@ mod canonic_encoding {
                 Transaction::EIP4844Transaction(t) => t.encode(buf),
                 Transaction::EIP7702Transaction(t) => t.encode(buf),
                 Transaction::FeeTokenTransaction(t) => t.encode(buf),
+                Transaction::RIP7560Transaction(t) => t.encode(buf),
                 Transaction::PrivilegedL2Transaction(t) => t.encode(buf),
             };
         }
@@ -2106,6 +2296,59 @@ mod serde_impl {
         }
     }
 
+    impl Serialize for RIP7560Transaction {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut struct_serializer = serializer.serialize_struct("Rip7560Transaction", 18)?;
+            struct_serializer.serialize_field("type", &TxType::RIP7560)?;
+            struct_serializer.serialize_field("nonce", &format!("{:#x}", self.nonce))?;
+            struct_serializer.serialize_field("sender", &self.sender)?;
+            struct_serializer.serialize_field(
+                "senderValidationData",
+                &format!("0x{:x}", self.sender_validation_data),
+            )?;
+            struct_serializer.serialize_field("deployer", &self.deployer)?;
+            struct_serializer
+                .serialize_field("deployerData", &format!("0x{:x}", self.deployer_data))?;
+            struct_serializer.serialize_field("paymaster", &self.paymaster)?;
+            struct_serializer
+                .serialize_field("paymasterData", &format!("0x{:x}", self.paymaster_data))?;
+            struct_serializer
+                .serialize_field("executionData", &format!("0x{:x}", self.execution_data))?;
+            struct_serializer.serialize_field("gas", &format!("{:#x}", self.gas_limit()))?;
+            struct_serializer.serialize_field(
+                "validationGasLimit",
+                &format!("{:#x}", self.validation_gas_limit),
+            )?;
+            struct_serializer.serialize_field(
+                "paymasterValidationGasLimit",
+                &format!("{:#x}", self.paymaster_validation_gas_limit),
+            )?;
+            struct_serializer
+                .serialize_field("postOpGasLimit", &format!("{:#x}", self.post_op_gas_limit))?;
+            struct_serializer
+                .serialize_field("callGasLimit", &format!("{:#x}", self.call_gas_limit))?;
+            struct_serializer.serialize_field(
+                "maxPriorityFeePerGas",
+                &format!("{:#x}", self.max_priority_fee_per_gas),
+            )?;
+            struct_serializer
+                .serialize_field("maxFeePerGas", &format!("{:#x}", self.max_fee_per_gas))?;
+            struct_serializer.serialize_field(
+                "accessList",
+                &self
+                    .access_list
+                    .iter()
+                    .map(AccessListEntry::from)
+                    .collect::<Vec<_>>(),
+            )?;
+            struct_serializer.serialize_field("chainId", &format!("{:#x}", self.chain_id))?;
+            struct_serializer.end()
+        }
+    }
+
     impl<'de> Deserialize<'de> for Transaction {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
@@ -2172,6 +2415,13 @@ mod serde_impl {
                             serde::de::Error::custom(format!("Couldn't Deserialize FeeToken {e}"))
                         })
                 }
+                TxType::RIP7560 => {
+                    RIP7560Transaction::deserialize(serde::de::value::MapDeserializer::new(iter))
+                        .map(Transaction::RIP7560Transaction)
+                        .map_err(|e| {
+                            serde::de::Error::custom(format!("Couldn't Deserialize RIP7560 {e}"))
+                        })
+                }
             }
         }
     }
@@ -2213,6 +2463,38 @@ mod serde_impl {
             })
     }
 
+    /// Deserializes a hex encoded byte string, missing fields are taken as empty
+    fn deserialize_bytes_field<'de, D>(
+        map: &mut HashMap<String, serde_json::Value>,
+        key: &str,
+    ) -> Result<Bytes, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let Some(value) = deserialize_optional_field::<String, D>(map, key)? else {
+            return Ok(Bytes::new());
+        };
+        hex::decode(value.trim_start_matches("0x"))
+            .map(Bytes::from)
+            .map_err(|_| D::Error::custom(format!("Invalid hex format in '{key}' field")))
+    }
+
+    fn deserialize_optional_field<'de, T, D>(
+        map: &mut HashMap<String, serde_json::Value>,
+        key: &str,
+    ) -> Result<Option<T>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        T: serde::de::DeserializeOwned,
+    {
+        match map.remove(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => serde_json::from_value(value)
+                .map(Some)
+                .map_err(|err| D::Error::custom(err.to_string())),
+        }
+    }
+
     impl<'de> Deserialize<'de> for LegacyTransaction {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
@@ -2457,6 +2739,51 @@ mod serde_impl {
         }
     }
 
+    impl<'de> Deserialize<'de> for RIP7560Transaction {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let mut map = <HashMap<String, serde_json::Value>>::deserialize(deserializer)?;
+
+            Ok(RIP7560Transaction {
+                chain_id: deserialize_field::<U256, D>(&mut map, "chainId")?.as_u64(),
+                nonce: deserialize_field::<U256, D>(&mut map, "nonce")?.as_u64(),
+                sender: deserialize_field::<Address, D>(&mut map, "sender")?,
+                sender_validation_data: deserialize_bytes_field::<D>(
+                    &mut map,
+                    "senderValidationData",
+                )?,
+                deployer: deserialize_optional_field::<Address, D>(&mut map, "deployer")?,
+                deployer_data: deserialize_bytes_field::<D>(&mut map, "deployerData")?,
+                paymaster: deserialize_optional_field::<Address, D>(&mut map, "paymaster")?,
+                paymaster_data: deserialize_bytes_field::<D>(&mut map, "paymasterData")?,
+                execution_data: deserialize_bytes_field::<D>(&mut map, "executionData")?,
+                max_priority_fee_per_gas: deserialize_field::<U256, D>(
+                    &mut map,
+                    "maxPriorityFeePerGas",
+                )?
+                .as_u64(),
+                max_fee_per_gas: deserialize_field::<U256, D>(&mut map, "maxFeePerGas")?.as_u64(),
+                validation_gas_limit: deserialize_field::<U256, D>(&mut map, "validationGasLimit")?
+                    .as_u64(),
+                paymaster_validation_gas_limit: deserialize_field::<U256, D>(
+                    &mut map,
+                    "paymasterValidationGasLimit",
+                )?
+                .as_u64(),
+                post_op_gas_limit: deserialize_field::<U256, D>(&mut map, "postOpGasLimit")?
+                    .as_u64(),
+                call_gas_limit: deserialize_field::<U256, D>(&mut map, "callGasLimit")?.as_u64(),
+                access_list: deserialize_field::<Vec<AccessListEntry>, D>(&mut map, "accessList")?
+                    .into_iter()
+                    .map(|v| (v.address, v.storage_keys))
+                    .collect::<Vec<_>>(),
+                ..Default::default()
+            })
+        }
+    }
+
     #[derive(Debug, thiserror::Error)]
     pub enum GenericTransactionError {
         #[error("Invalid transaction type: {0}")]
@@ -2839,6 +3166,36 @@ mod serde_impl {
         }
     }
 
+    /// Only the execution frame is kept, as a call of the sender to itself
+    impl From<RIP7560Transaction> for GenericTransaction {
+        fn from(value: RIP7560Transaction) -> Self {
+            Self {
+                r#type: TxType::RIP7560,
+                nonce: Some(value.nonce),
+                to: TxKind::Call(value.sender),
+                gas: Some(value.gas_limit()),
+                value: U256::zero(),
+                input: value.execution_data.clone(),
+                gas_price: value.max_fee_per_gas,
+                max_priority_fee_per_gas: Some(value.max_priority_fee_per_gas),
+                max_fee_per_gas: Some(value.max_fee_per_gas),
+                max_fee_per_blob_gas: None,
+                access_list: value
+                    .access_list
+                    .iter()
+                    .map(AccessListEntry::from)
+                    .collect(),
+                fee_token: None,
+                authorization_list: None,
+                blob_versioned_hashes: vec![],
+                blobs: vec![],
+                chain_id: Some(value.chain_id),
+                from: value.sender,
+                wrapper_version: None,
+            }
+        }
+    }
+
     impl TryFrom<GenericTransaction> for FeeTokenTransaction {
         type Error = GenericTransactionError;
 
@@ -2938,6 +3295,7 @@ mod serde_impl {
                 Transaction::EIP7702Transaction(tx) => tx.into(),
                 Transaction::PrivilegedL2Transaction(tx) => tx.into(),
                 Transaction::FeeTokenTransaction(tx) => tx.into(),
+                Transaction::RIP7560Transaction(tx) => tx.into(),
             }
         }
     }
@@ -2970,6 +3328,16 @@ mod mempool {
                 inner: Arc::new(tx),
             }
         }
+
+        /// Creates a transaction that reached the MemPool at `timestamp`, in microseconds
+        pub fn with_timestamp(tx: Transaction, sender: Address, timestamp: u128) -> Self {
+            Self {
+                timestamp,
+                sender,
+                inner: Arc::new(tx),
+            }
+        }
+
         pub fn time(&self) -> u128 {
             self.timestamp
         }
@@ -3500,6 +3868,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn encode_decode_rip7560_transaction() -> Result<(), RLPDecodeError> {
+        let rip7560_tx = RIP7560Transaction {
+            chain_id: 65536999,
+            nonce: 3,
+            sender: Address::repeat_byte(0x11),
+            sender_validation_data: Bytes::from_static(&[0xaa; 65]),
+            deployer: None,
+            paymaster: Some(Address::repeat_byte(0x22)),
+            paymaster_data: Bytes::from_static(&[0x01, 0x02]),
+            execution_data: Bytes::from_static(&[0xb6, 0x1d, 0x27, 0xf6]),
+            max_priority_fee_per_gas: 1_000_000,
+            max_fee_per_gas: 875_000_000,
+            validation_gas_limit: 100_000,
+            paymaster_validation_gas_limit: 50_000,
+            post_op_gas_limit: 20_000,
+            call_gas_limit: 200_000,
+            ..Default::default()
+        };
+        let tx = Transaction::RIP7560Transaction(rip7560_tx.clone());
+
+        let encoded = tx.encode_canonical_to_vec();
+        assert_eq!(encoded.first(), Some(&0x05));
+        let decoded = Transaction::decode_canonical(&encoded)?;
+        assert_eq!(decoded, tx);
+        assert_eq!(decoded.sender().unwrap(), Address::repeat_byte(0x11));
+        assert_eq!(decoded.gas_limit(), 370_000);
+
+        let json = serde_json::to_string(&tx).unwrap();
+        let deserialized: Transaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, tx);
+
+        // The validation data is not part of the signing hash
+        let unsigned = RIP7560Transaction {
+            sender_validation_data: Bytes::new(),
+            ..rip7560_tx.clone()
+        };
+        assert_eq!(unsigned.signing_hash(), rip7560_tx.signing_hash());
+        assert_ne!(Transaction::RIP7560Transaction(unsigned).hash(), tx.hash());
+
+        Ok(())
+    }
+
     #[test]
     fn test_legacy_transaction_into_generic() {
         let legacy_tx = LegacyTransaction {
@@ -0,0 +1,104 @@
+//! Leaves of the unified binary tree ([EIP-7864](https://eips.ethereum.org/EIPS/eip-7864)).
+//!
+//! Each account owns a set of stems, each holding 256 leaves. The first stem of the account
+//! (tree index 0) holds its basic data, code hash, the first 64 storage slots and the first 128
+//! code chunks; the rest of the slots and chunks are spread over the following stems.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, H256, U256};
+
+pub const BASIC_DATA_LEAF_KEY: u8 = 0;
+pub const CODE_HASH_LEAF_KEY: u8 = 1;
+pub const HEADER_STORAGE_OFFSET: u64 = 64;
+pub const CODE_OFFSET: u64 = 128;
+pub const STEM_SUBTREE_WIDTH: u64 = 256;
+/// Size of a code chunk, its first byte holds the number of leading bytes that are push data
+pub const CODE_CHUNK_SIZE: usize = 31;
+
+/// A single leaf of the tree, identified by what it stores rather than by its tree key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum UbtLeaf {
+    BasicData(Address),
+    CodeHash(Address),
+    Storage(Address, H256),
+    CodeChunk(Address, u64),
+}
+
+impl UbtLeaf {
+    pub fn address(&self) -> Address {
+        match self {
+            UbtLeaf::BasicData(address)
+            | UbtLeaf::CodeHash(address)
+            | UbtLeaf::Storage(address, _)
+            | UbtLeaf::CodeChunk(address, _) => *address,
+        }
+    }
+
+    /// Returns the tree index of the stem holding the leaf, and the leaf index within the stem
+    pub fn position(&self) -> (U256, u8) {
+        match self {
+            UbtLeaf::BasicData(_) => (U256::zero(), BASIC_DATA_LEAF_KEY),
+            UbtLeaf::CodeHash(_) => (U256::zero(), CODE_HASH_LEAF_KEY),
+            UbtLeaf::Storage(_, key) => {
+                let key = U256::from_big_endian(key.as_bytes());
+                if key < U256::from(CODE_OFFSET - HEADER_STORAGE_OFFSET) {
+                    (U256::zero(), (key.low_u64() + HEADER_STORAGE_OFFSET) as u8)
+                } else {
+                    // Main storage starts at 256^31, so the tree index is 256^30 + key / 256
+                    let tree_index = (U256::one() << 240) + (key >> 8);
+                    (tree_index, key.byte(0))
+                }
+            }
+            UbtLeaf::CodeChunk(_, chunk) => {
+                let position = CODE_OFFSET as u128 + *chunk as u128;
+                (
+                    U256::from(position / STEM_SUBTREE_WIDTH as u128),
+                    (position % STEM_SUBTREE_WIDTH as u128) as u8,
+                )
+            }
+        }
+    }
+}
+
+/// Returns the indices of the code chunks holding the bytes in `offset..offset + size`
+pub fn code_chunks_in_range(offset: u64, size: u64) -> std::ops::Range<u64> {
+    let chunk_size = CODE_CHUNK_SIZE as u64;
+    if size == 0 {
+        return 0..0;
+    }
+    let last_byte = offset.saturating_add(size - 1);
+    (offset / chunk_size)..(last_byte / chunk_size + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaf_positions() {
+        let address = Address::repeat_byte(1);
+        assert_eq!(
+            UbtLeaf::BasicData(address).position(),
+            (U256::zero(), BASIC_DATA_LEAF_KEY)
+        );
+        assert_eq!(
+            UbtLeaf::Storage(address, H256::from_low_u64_be(5)).position(),
+            (U256::zero(), 69)
+        );
+        assert_eq!(
+            UbtLeaf::Storage(address, H256::from_low_u64_be(0x1234)).position(),
+            ((U256::one() << 240) + U256::from(0x12), 0x34)
+        );
+        assert_eq!(
+            UbtLeaf::CodeChunk(address, 127).position(),
+            (U256::zero(), 255)
+        );
+        assert_eq!(
+            UbtLeaf::CodeChunk(address, 128).position(),
+            (U256::one(), 0)
+        );
+        assert_eq!(code_chunks_in_range(30, 2), 0..2);
+        assert!(code_chunks_in_range(30, 0).is_empty());
+    }
+}
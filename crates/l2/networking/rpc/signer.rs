@@ -198,6 +198,8 @@ pub enum SignerError {
     FromHexError(#[from] FromHexError),
     #[error("Tried to sign Privileged L2 transaction")]
     PrivilegedL2TxUnsupported,
+    #[error("Tried to sign RIP-7560 transaction")]
+    Rip7560TxUnsupported,
     #[error("Web3signer error: {0}")]
     Web3SignerError(String),
 }
@@ -241,6 +243,7 @@ impl Signable for Transaction {
             Transaction::EIP7702Transaction(tx) => tx.sign_inplace(signer).await,
             Transaction::PrivilegedL2Transaction(_) => Err(SignerError::PrivilegedL2TxUnsupported), // Privileged Transactions are not signed
             Transaction::FeeTokenTransaction(tx) => tx.sign_inplace(signer).await,
+            Transaction::RIP7560Transaction(_) => Err(SignerError::Rip7560TxUnsupported), // Validated by the sender account, which defines its own signature scheme
         }
     }
 }
@@ -981,7 +981,7 @@ pub async fn build_generic_tx(
         | TxType::EIP7702
         | TxType::Privileged
         | TxType::FeeToken => {}
-        TxType::EIP2930 | TxType::Legacy => {
+        TxType::EIP2930 | TxType::Legacy | TxType::RIP7560 => {
             return Err(EthClientError::Custom(
                 "Unsupported tx type in build_generic_tx".to_owned(),
             ));
@@ -221,6 +221,9 @@ impl BlockProducer {
         let execution_result = BlockExecutionResult {
             receipts: payload_build_result.receipts,
             requests: Vec::new(),
+            block_access_list: payload_build_result.block_access_list,
+            accessed_leaves: None,
+            prefetch_stats: None,
         };
 
         let account_updates_list = self
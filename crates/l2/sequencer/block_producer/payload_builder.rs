@@ -2,11 +2,18 @@ use crate::sequencer::errors::BlockProducerError;
 use ethrex_blockchain::{
     Blockchain,
     constants::TX_GAS_COST,
-    payload::{PayloadBuildContext, PayloadBuildResult, TransactionQueue, apply_plain_transaction},
+    error::ChainError,
+    payload::{
+        CandidateAction, HeadTransaction, PayloadBuildContext, PayloadBuildResult, PayloadFillHook,
+        TransactionQueue, TransactionSelector,
+    },
 };
 use ethrex_common::{
     U256,
-    types::{Block, EIP1559_DEFAULT_SERIALIZED_LENGTH, SAFE_BYTES_PER_BLOB, Transaction},
+    types::{
+        Block, BlockHash, EIP1559_DEFAULT_SERIALIZED_LENGTH, Receipt, SAFE_BYTES_PER_BLOB,
+        Transaction,
+    },
 };
 use ethrex_l2_common::{
     messages::get_block_l2_out_messages, privileged_transactions::PRIVILEGED_TX_BUDGET,
@@ -14,10 +21,7 @@ use ethrex_l2_common::{
 use ethrex_levm::vm::VMType;
 use ethrex_metrics::metrics;
 #[cfg(feature = "metrics")]
-use ethrex_metrics::{
-    blocks::METRICS_BLOCKS,
-    transactions::{METRICS_TX, MetricsTxType},
-};
+use ethrex_metrics::{blocks::METRICS_BLOCKS, transactions::METRICS_TX};
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_storage::Store;
 use std::sync::Arc;
@@ -41,6 +45,11 @@ pub async fn build_payload(
 
     debug!("Building payload");
     let mut context = PayloadBuildContext::new(payload, store, &blockchain.options.r#type)?;
+    context.fill_deadline = blockchain
+        .options
+        .payload_builder
+        .fill_time_limit
+        .and_then(|limit| since.into_std().checked_add(limit));
 
     fill_transactions(
         blockchain.clone(),
@@ -91,6 +100,7 @@ pub async fn build_payload(
 
 /// Same as `blockchain::fill_transactions` but enforces that the block encoded size
 /// does not exceed `SAFE_BYTES_PER_BLOB`.
+/// Transactions are offered in the order given by the blockchain's `TxSelectionPolicy`.
 /// Also, uses a configured `block_gas_limit` to limit the gas used in the block,
 /// which can be lower than the block gas limit specified in the payload header.
 pub async fn fill_transactions(
@@ -101,187 +111,158 @@ pub async fn fill_transactions(
     configured_block_gas_limit: u64,
     registered_chains: Vec<U256>,
 ) -> Result<(), BlockProducerError> {
-    let mut privileged_tx_count = 0;
     let VMType::L2(fee_config) = context.vm.vm_type else {
         return Err(BlockProducerError::Custom("invalid VM type".to_string()));
     };
-    let mut acc_encoded_size = context.payload.length();
-    let fee_config_len = fee_config.to_vec().len();
-    let chain_config = store.get_chain_config();
-    let chain_id = chain_config.chain_id;
 
     debug!("Fetching transactions from mempool");
     // Fetch mempool transactions
     let latest_block_number = store.get_latest_block_number().await?;
-    let mut txs = fetch_mempool_transactions(blockchain.as_ref(), context)?;
+    let latest_block_hash = store.get_canonical_block_hash(latest_block_number).await?;
+    let (mut plain_txs, mut blob_txs) = fetch_mempool_transactions(blockchain.as_ref(), context)?;
 
-    // Execute and add transactions to payload (if suitable)
-    loop {
-        // Check if we have enough gas to run more transactions
-        if context.remaining_gas < TX_GAS_COST {
-            debug!("No more gas to run transactions");
-            break;
-        };
+    let mut hook = L2FillHook {
+        blockchain: blockchain.as_ref(),
+        store,
+        latest_block_hash,
+        privileged_nonces,
+        privileged_tx_count: 0,
+        configured_block_gas_limit,
+        registered_chains,
+        chain_id: store.get_chain_config().chain_id,
+        acc_encoded_size: context.payload.length(),
+        fee_config_len: fee_config.to_vec().len(),
+    };
+    blockchain.fill_transactions_with(context, &mut plain_txs, &mut blob_txs, &mut hook)?;
+    Ok(())
+}
+
+/// L2 rules of the payload builder, on top of the ones shared with L1
+struct L2FillHook<'a> {
+    blockchain: &'a Blockchain,
+    store: &'a Store,
+    /// Block the sender nonces are checked against
+    latest_block_hash: Option<BlockHash>,
+    /// Last privileged transaction included of each chain
+    privileged_nonces: &'a mut HashMap<u64, Option<u64>>,
+    privileged_tx_count: u64,
+    configured_block_gas_limit: u64,
+    /// Chains messages can be sent to
+    registered_chains: Vec<U256>,
+    chain_id: u64,
+    /// Encoded size of the block, which must fit in a blob together with the fee config
+    acc_encoded_size: usize,
+    fee_config_len: usize,
+}
 
+impl PayloadFillHook for L2FillHook<'_> {
+    fn check_candidate(
+        &mut self,
+        head_tx: &HeadTransaction,
+        context: &PayloadBuildContext,
+    ) -> Result<CandidateAction, ChainError> {
         // Check if we have enough gas to run more transactions within the configured block_gas_limit
-        if context.gas_used() + TX_GAS_COST >= configured_block_gas_limit {
+        if context.gas_used() + TX_GAS_COST >= self.configured_block_gas_limit {
             debug!("No more gas to run transactions");
-            break;
+            return Ok(CandidateAction::Stop);
         }
 
         // Check if we have enough blob space to run more transactions
-        if acc_encoded_size + fee_config_len + EIP1559_DEFAULT_SERIALIZED_LENGTH
+        if self.acc_encoded_size + self.fee_config_len + EIP1559_DEFAULT_SERIALIZED_LENGTH
             > SAFE_BYTES_PER_BLOB
         {
             debug!("No more blob space to run transactions");
-            break;
-        };
-
-        // Fetch the next transaction
-        let Some(head_tx) = txs.peek() else {
-            break;
+            return Ok(CandidateAction::Stop);
         };
 
-        // Check if we have enough gas to run the transaction
-        if context.remaining_gas < head_tx.tx.gas_limit() {
-            debug!("Skipping transaction: {}, no gas left", head_tx.tx.hash());
-            // We don't have enough gas left for the transaction, so we skip all txs from this account
-            txs.pop();
-            continue;
-        }
-
         // Check if we have enough gas to run the transaction within the configured block_gas_limit
-        if context.gas_used() + head_tx.tx.gas_limit() >= configured_block_gas_limit {
+        if context.gas_used() + head_tx.tx.gas_limit() >= self.configured_block_gas_limit {
             debug!("Skipping transaction: {}, no gas left", head_tx.tx.hash());
             // We don't have enough gas left for the transaction, so we skip all txs from this account
-            txs.pop();
-            continue;
+            return Ok(CandidateAction::Skip);
         }
 
         // Check if we have enough blob space to add this transaction
-        let tx: Transaction = head_tx.clone().into();
-        let tx_size = tx.length();
-        if acc_encoded_size + fee_config_len + tx_size > SAFE_BYTES_PER_BLOB {
+        let tx_size = head_tx.tx.transaction().length();
+        if self.acc_encoded_size + self.fee_config_len + tx_size > SAFE_BYTES_PER_BLOB {
             debug!("No more blob space to run transactions");
-            break;
+            return Ok(CandidateAction::Stop);
         };
 
-        if let Transaction::PrivilegedL2Transaction(privileged_tx) = &head_tx.clone().into() {
-            if privileged_tx_count >= PRIVILEGED_TX_BUDGET {
+        if let Transaction::PrivilegedL2Transaction(privileged_tx) = head_tx.tx.transaction() {
+            if self.privileged_tx_count >= PRIVILEGED_TX_BUDGET {
                 debug!("Ran out of space for privileged transactions");
-                txs.pop();
-                continue;
+                return Ok(CandidateAction::Skip);
             }
             let id = head_tx.nonce();
-            let entry = privileged_nonces
+            let entry = self
+                .privileged_nonces
                 .entry(privileged_tx.chain_id)
                 .or_insert(None);
             if (*entry).is_some_and(|last_nonce| id != last_nonce + 1) {
                 debug!("Ignoring out-of-order privileged transaction");
-                txs.pop();
-                continue;
+                return Ok(CandidateAction::Skip);
             }
         }
 
-        // TODO: maybe fetch hash too when filtering mempool so we don't have to compute it here (we can do this in the same refactor as adding timestamp)
-        let tx_hash = head_tx.tx.hash();
-
-        // Check whether the tx is replay-protected
-        if head_tx.tx.protected() && !chain_config.is_eip155_activated(context.block_number()) {
-            // Ignore replay protected tx & all txs from the sender
-            // Pull transaction from the mempool
-            debug!("Ignoring replay-protected transaction: {}", tx_hash);
-            txs.pop();
-            blockchain.remove_transaction_from_pool(&tx_hash)?;
-            continue;
-        }
-
-        let maybe_sender_acc_info = store
-            .get_account_info(latest_block_number, head_tx.tx.sender())
-            .await?;
-
+        let maybe_sender_acc_info = match self.latest_block_hash {
+            Some(block_hash) => self
+                .store
+                .get_account_info_by_hash(block_hash, head_tx.tx.sender())?,
+            None => None,
+        };
         if maybe_sender_acc_info.is_some_and(|acc_info| head_tx.nonce() < acc_info.nonce)
             && !head_tx.is_privileged()
         {
-            debug!("Removing transaction with nonce too low from mempool: {tx_hash:#x}");
-            txs.pop();
-            blockchain.remove_transaction_from_pool(&tx_hash)?;
-            continue;
+            debug!(
+                "Removing transaction with nonce too low from mempool: {:#x}",
+                head_tx.tx.hash()
+            );
+            return Ok(CandidateAction::Discard);
         }
 
-        // Execute tx
-        let previous_remaining_gas = context.remaining_gas;
-        let previous_block_value = context.block_value;
-        let receipt = match apply_plain_transaction(&head_tx, context) {
-            Ok(receipt) => receipt,
-            Err(e) => {
-                debug!("Failed to execute transaction: {}, {e}", tx_hash);
-                metrics!(METRICS_TX.inc_tx_errors(e.to_metric()));
-                // Ignore following txs from sender
-                txs.pop();
-                continue;
-            }
-        };
-
-        let l2_messages = get_block_l2_out_messages(std::slice::from_ref(&receipt), chain_id);
-        let mut found_invalid_message = false;
-        for msg in l2_messages {
-            if !registered_chains.contains(&msg.dest_chain_id) {
-                txs.pop();
-                context.vm.undo_last_tx()?;
-                context.remaining_gas = previous_remaining_gas;
-                context.block_value = previous_block_value;
-                found_invalid_message = true;
-                break;
-            }
-        }
-        if found_invalid_message {
-            continue;
-        }
+        Ok(CandidateAction::Include)
+    }
 
-        if let Transaction::PrivilegedL2Transaction(privileged_tx) = &head_tx.clone().into() {
-            let id = head_tx.nonce();
-            privileged_nonces.insert(privileged_tx.chain_id, Some(id));
+    fn accept_receipt(
+        &mut self,
+        _head_tx: &HeadTransaction,
+        receipt: &Receipt,
+        _context: &PayloadBuildContext,
+    ) -> Result<bool, ChainError> {
+        let l2_messages = get_block_l2_out_messages(std::slice::from_ref(receipt), self.chain_id);
+        Ok(l2_messages
+            .iter()
+            .all(|msg| self.registered_chains.contains(&msg.dest_chain_id)))
+    }
 
-            privileged_tx_count += 1;
+    fn on_included(&mut self, head_tx: &HeadTransaction) -> Result<(), ChainError> {
+        if let Transaction::PrivilegedL2Transaction(privileged_tx) = head_tx.tx.transaction() {
+            self.privileged_nonces
+                .insert(privileged_tx.chain_id, Some(head_tx.nonce()));
+            self.privileged_tx_count += 1;
         }
 
         // Update acc_encoded_size
-        acc_encoded_size += tx_size;
+        self.acc_encoded_size += head_tx.tx.transaction().length();
 
-        txs.shift()?;
         // Pull transaction from the mempool
-        blockchain.remove_transaction_from_pool(&head_tx.tx.hash())?;
-
-        // Add transaction to block
-        context.payload.body.transactions.push(tx);
-
-        // Save receipt for hash calculation
-        context.receipts.push(receipt);
-    } // end loop
-
-    metrics!(
-        context
-            .payload
-            .body
-            .transactions
-            .iter()
-            .for_each(|tx| METRICS_TX.inc_tx_with_type(MetricsTxType(tx.tx_type())))
-    );
-
-    Ok(())
+        self.blockchain
+            .remove_transaction_from_pool(&head_tx.tx.hash())?;
+        Ok(())
+    }
 }
 
 // TODO: Once #2857 is implemented, we can completely ignore the blobs pool.
 fn fetch_mempool_transactions(
     blockchain: &Blockchain,
     context: &mut PayloadBuildContext,
-) -> Result<TransactionQueue, BlockProducerError> {
+) -> Result<(TransactionQueue, TransactionQueue), BlockProducerError> {
     let (plain_txs, mut blob_txs) = blockchain.fetch_mempool_transactions(context)?;
     while let Some(blob_tx) = blob_txs.peek() {
         let tx_hash = blob_tx.hash();
         blockchain.remove_transaction_from_pool(&tx_hash)?;
         blob_txs.pop();
     }
-    Ok(plain_txs)
+    Ok((plain_txs, blob_txs))
 }
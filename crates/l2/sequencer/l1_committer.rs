@@ -817,6 +817,9 @@ impl L1Committer {
                     BlockExecutionResult {
                         receipts,
                         requests: vec![],
+                        block_access_list: None,
+                        accessed_leaves: None,
+                        prefetch_stats: None,
                     },
                 )?;
             } else {
@@ -0,0 +1,368 @@
+//! Network census.
+//!
+//! The crawler runs the discv4 server to walk the DHT and handshakes with every node that answers
+//! our pings, recording the client, capabilities, fork id and head it advertises. Nodes are never
+//! registered as peers, each one is disconnected right after its eth Status is received.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ethrex_common::{
+    H256,
+    types::{BlockHeader, ChainConfig, ForkId},
+};
+use ethrex_storage::{Store, error::StoreError};
+use serde::Serialize;
+use tokio::{
+    net::UdpSocket,
+    sync::Semaphore,
+    task::JoinSet,
+    time::{Instant, sleep, timeout},
+};
+use tracing::{debug, info};
+
+use crate::{
+    discv4::server::DiscoveryServer,
+    network::{NetworkError, P2PContext},
+    rlpx::{
+        connection::server::{PeerProbe, probe},
+        error::PeerConnectionError,
+        p2p::Capability,
+    },
+    transport::DatagramSocket,
+    types::{LocalNodeRecord, Node},
+};
+
+/// Default amount of nodes handshaked with at the same time
+pub const DEFAULT_CRAWL_CONCURRENCY: usize = 32;
+/// Time a node has to complete the handshakes
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Interval between each check for newly discovered nodes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a single node told about itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CensusEntry {
+    pub node_id: H256,
+    pub enode: String,
+    pub client_id: Option<String>,
+    pub capabilities: Vec<Capability>,
+    pub eth_version: Option<u8>,
+    pub network_id: Option<u64>,
+    pub genesis: Option<H256>,
+    pub fork_hash: Option<String>,
+    pub fork_next: Option<u64>,
+    /// Whether the fork id is compatible with the crawled network
+    pub fork_id_valid: Option<bool>,
+    pub head_hash: Option<H256>,
+    pub head_number: Option<u64>,
+    /// Why the handshake failed, if it did
+    pub error: Option<String>,
+}
+
+impl CensusEntry {
+    fn new(
+        node: &Node,
+        probe: Result<PeerProbe, PeerConnectionError>,
+        is_fork_id_valid: impl FnOnce(ForkId) -> bool,
+    ) -> Self {
+        let mut entry = CensusEntry {
+            node_id: node.node_id(),
+            enode: node.enode_url(),
+            ..Default::default()
+        };
+        let probe = match probe {
+            Ok(probe) => probe,
+            Err(error) => {
+                entry.error = Some(error.to_string());
+                return entry;
+            }
+        };
+        entry.client_id = probe.client_id;
+        entry.capabilities = probe.capabilities;
+        if let Some(status) = probe.status {
+            entry.eth_version = Some(status.eth_version);
+            entry.network_id = Some(status.network_id);
+            entry.genesis = Some(status.genesis);
+            entry.fork_hash = Some(format!("{:#x}", status.fork_id.fork_hash));
+            entry.fork_next = Some(status.fork_id.fork_next);
+            entry.fork_id_valid = Some(is_fork_id_valid(status.fork_id));
+            entry.head_hash = Some(status.head_hash);
+            entry.head_number = status.head_number;
+        }
+        entry
+    }
+
+    /// Client name without version or platform, e.g. `Geth` for `Geth/v1.16.0-stable/linux-amd64/go1.24.4`
+    pub fn client_name(&self) -> Option<&str> {
+        self.client_id
+            .as_deref()
+            .and_then(|client_id| client_id.split('/').next())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Census {
+    pub entries: Vec<CensusEntry>,
+}
+
+impl Census {
+    /// Amount of nodes running each client, nodes which didn't complete the handshake are not counted
+    pub fn client_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for name in self.entries.iter().filter_map(CensusEntry::client_name) {
+            *counts.entry(name.to_string()).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "node_id,enode,client_id,capabilities,eth_version,network_id,genesis,fork_hash,fork_next,fork_id_valid,head_hash,head_number,error\n",
+        );
+        for entry in &self.entries {
+            let capabilities = entry
+                .capabilities
+                .iter()
+                .map(|cap| format!("{}/{}", cap.protocol(), cap.version))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let fields = [
+                format!("{:#x}", entry.node_id),
+                entry.enode.clone(),
+                entry.client_id.clone().unwrap_or_default(),
+                capabilities,
+                optional(entry.eth_version),
+                optional(entry.network_id),
+                optional(entry.genesis.map(|hash| format!("{hash:#x}"))),
+                entry.fork_hash.clone().unwrap_or_default(),
+                optional(entry.fork_next),
+                optional(entry.fork_id_valid),
+                optional(entry.head_hash.map(|hash| format!("{hash:#x}"))),
+                optional(entry.head_number),
+                entry.error.clone().unwrap_or_default(),
+            ];
+            let row = fields
+                .iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<_>>()
+                .join(",");
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Quotes the field if it contains separators or quotes, as specified in RFC 4180
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Checks the fork ids advertised by the crawled nodes. The crawler's store only holds the genesis
+/// block, so they are checked against the forks active at the current time instead of at our head.
+#[derive(Debug, Clone)]
+struct ForkIdValidator {
+    chain_config: ChainConfig,
+    genesis_header: BlockHeader,
+}
+
+impl ForkIdValidator {
+    fn new(store: &Store) -> Result<Self, StoreError> {
+        let genesis_header = store
+            .get_block_header(0)?
+            .ok_or_else(|| StoreError::Custom("Missing genesis block".to_string()))?;
+        Ok(Self {
+            chain_config: store.get_chain_config(),
+            genesis_header,
+        })
+    }
+
+    /// Validates the fork id as defined in EIP-2124, as if our head was at the given timestamp.
+    /// Block number based forks all predate the timestamp based ones, so they are all considered
+    /// activated.
+    fn is_valid(&self, remote: ForkId, timestamp: u64) -> bool {
+        let fork_id = ForkId::new(
+            &self.chain_config,
+            self.genesis_header.clone(),
+            timestamp,
+            u64::MAX,
+        );
+        fork_id.is_valid(
+            remote,
+            u64::MAX,
+            timestamp,
+            &self.chain_config,
+            self.genesis_header.clone(),
+        )
+    }
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Walks the DHT for the given duration, handshaking with every node found, and returns the census
+/// of the nodes that answered. Handshakes still in progress when the time is up are awaited.
+pub async fn crawl(
+    context: P2PContext,
+    bootnodes: Vec<Node>,
+    duration: Duration,
+    concurrency: usize,
+) -> Result<Census, NetworkError> {
+    let udp_socket = Arc::new(UdpSocket::bind(context.local_node.udp_addr()).await?);
+    crawl_with_socket(context, udp_socket, bootnodes, duration, concurrency).await
+}
+
+/// Same as [`crawl`], running discovery over the given socket
+pub async fn crawl_with_socket(
+    context: P2PContext,
+    udp_socket: Arc<dyn DatagramSocket>,
+    bootnodes: Vec<Node>,
+    duration: Duration,
+    concurrency: usize,
+) -> Result<Census, NetworkError> {
+    let fork_id_validator = ForkIdValidator::new(&context.storage)?;
+    let local_node_record = LocalNodeRecord::new(
+        &context.local_node,
+        &context.signer,
+        context.storage.get_fork_id().await.ok(),
+    )?;
+    DiscoveryServer::spawn(
+        context.storage.clone(),
+        context.local_node.clone(),
+        local_node_record,
+        context.signer,
+        udp_socket,
+        context.table.clone(),
+        bootnodes,
+        context.initial_lookup_interval,
+    )
+    .await?;
+
+    info!(duration_secs = duration.as_secs(), "Crawling the network");
+    let deadline = Instant::now() + duration;
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut table = context.table.clone();
+    let mut probed = HashSet::new();
+    let mut probes = JoinSet::new();
+    let mut census = Census::default();
+    while Instant::now() < deadline {
+        for contact in table.get_validated_contacts().await? {
+            let node = contact.node;
+            if !probed.insert(node.node_id()) {
+                continue;
+            }
+            let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                break;
+            };
+            let context = context.clone();
+            let fork_id_validator = fork_id_validator.clone();
+            probes.spawn(async move {
+                let _permit = permit;
+                let result = timeout(PROBE_TIMEOUT, probe(context, &node))
+                    .await
+                    .unwrap_or(Err(PeerConnectionError::Timeout));
+                CensusEntry::new(&node, result, |fork_id| {
+                    fork_id_validator.is_valid(fork_id, unix_timestamp())
+                })
+            });
+        }
+        while let Some(entry) = probes.try_join_next() {
+            census.entries.extend(entry);
+        }
+        debug!(
+            discovered = probed.len(),
+            recorded = census.entries.len(),
+            "Crawl in progress"
+        );
+        sleep(POLL_INTERVAL).await;
+    }
+    while let Some(entry) = probes.join_next().await {
+        census.entries.extend(entry);
+    }
+    info!(nodes = census.entries.len(), "Crawl finished");
+    Ok(census)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rlpx::connection::server::ProbedStatus;
+    use ethrex_common::{H32, types::ForkId};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn node() -> Node {
+        Node::new(
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            30303,
+            30303,
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn census_entries_record_probes() {
+        let probe = PeerProbe {
+            client_id: Some("Geth/v1.16.0-stable/linux-amd64/go1.24.4".to_string()),
+            capabilities: vec![Capability::eth(68), Capability::snap(1)],
+            status: Some(ProbedStatus {
+                eth_version: 68,
+                network_id: 1,
+                genesis: H256::repeat_byte(1),
+                fork_id: ForkId {
+                    fork_hash: H32::repeat_byte(2),
+                    fork_next: 0,
+                },
+                head_hash: H256::repeat_byte(3),
+                head_number: None,
+            }),
+        };
+        let geth = CensusEntry::new(&node(), Ok(probe), |_| true);
+        assert_eq!(geth.client_name(), Some("Geth"));
+        assert_eq!(geth.fork_hash.as_deref(), Some("0x02020202"));
+        assert_eq!(geth.fork_id_valid, Some(true));
+
+        let failed = CensusEntry::new(&node(), Err(PeerConnectionError::Timeout), |_| true);
+        assert_eq!(failed.client_name(), None);
+        assert!(failed.error.is_some());
+
+        let census = Census {
+            entries: vec![geth, failed],
+        };
+        assert_eq!(
+            census.client_counts(),
+            BTreeMap::from([("Geth".to_string(), 1)])
+        );
+    }
+
+    #[test]
+    fn csv_fields_are_escaped() {
+        let census = Census {
+            entries: vec![CensusEntry {
+                client_id: Some("odd,\"client\"".to_string()),
+                capabilities: vec![Capability::eth(68), Capability::eth(69)],
+                ..Default::default()
+            }],
+        };
+        let csv = census.to_csv();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.contains(",\"odd,\"\"client\"\"\",eth/68 eth/69,"));
+        assert_eq!(csv.lines().count(), 2);
+    }
+}
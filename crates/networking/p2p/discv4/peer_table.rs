@@ -1,39 +1,48 @@
 use crate::{
     discv4::server::MAX_NODES_IN_NEIGHBORS_PACKET,
     metrics::METRICS,
-    rlpx::{connection::server::PeerConnection, p2p::Capability},
+    reputation::{MAX_SCORE, MIN_SCORE, Penalty, Reputation, RequestKind},
+    rlpx::{
+        connection::server::PeerConnection,
+        p2p::{Capability, DisconnectReason},
+    },
     types::{Node, NodeRecord},
+    utils::current_unix_time,
 };
 use ethrex_common::{H256, U256};
 use indexmap::{IndexMap, map::Entry};
-use rand::seq::SliceRandom;
-use rustc_hash::FxHashSet;
+use ipnet::IpNet;
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use rustc_hash::{FxHashMap, FxHashSet};
 use spawned_concurrency::{
     error::GenServerError,
     tasks::{CallResponse, CastResponse, GenServer, GenServerHandle, InitResult, send_message_on},
 };
 use std::{
+    collections::BTreeMap,
     net::IpAddr,
     time::{Duration, Instant},
 };
 use thiserror::Error;
+use tokio::sync::watch;
 
-const MAX_SCORE: i64 = 50;
-const MIN_SCORE: i64 = -50;
-/// Score assigned to peers who are acting maliciously (e.g., returning a node with wrong hash)
-const MIN_SCORE_CRITICAL: i64 = MIN_SCORE * 3;
 /// Maximum amount of FindNode messages sent to a single node.
 const MAX_FIND_NODE_PER_PEER: u64 = 20;
 /// Score weight for the load balancing function.
-const SCORE_WEIGHT: i64 = 1;
+const SCORE_WEIGHT: f64 = 1.0;
 /// Weight for amount of requests being handled by the peer for the load balancing function.
-const REQUESTS_WEIGHT: i64 = 1;
+const REQUESTS_WEIGHT: f64 = 1.0;
 /// Max amount of ongoing requests per peer.
 const MAX_CONCURRENT_REQUESTS_PER_PEER: i64 = 100;
 /// The target number of RLPx connections to reach.
 pub const TARGET_PEERS: usize = 100;
 /// The target number of contacts to maintain in peer_table.
 const TARGET_CONTACTS: usize = 100_000;
+/// Minimum time between two dial attempts to the same static peer.
+const STATIC_PEER_REDIAL_INTERVAL: Duration = Duration::from_secs(30);
+/// Maximum amount of peer reputations kept in memory and persisted, the least relevant ones are
+/// dropped first.
+const MAX_STORED_REPUTATIONS: usize = 5_000;
 
 #[derive(Debug, Clone)]
 pub struct Contact {
@@ -123,8 +132,8 @@ pub struct PeerData {
     pub is_connection_inbound: bool,
     /// communication channels between the peer data and its active connection
     pub connection: Option<PeerConnection>,
-    /// This tracks the score of a peer
-    score: i64,
+    /// Score, penalties and request statistics of the peer
+    pub reputation: Reputation,
     /// Track the amount of concurrent requests this peer is handling
     requests: i64,
 }
@@ -142,24 +151,72 @@ impl PeerData {
             supported_capabilities: capabilities,
             is_connection_inbound: false,
             connection,
-            score: Default::default(),
+            reputation: Default::default(),
             requests: Default::default(),
         }
     }
 }
 
+/// Outcome of checking whether a new RLPx connection with a node should be kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerAdmission {
+    Accepted,
+    /// The node was banned through the admin API
+    Banned,
+    /// The node's score dropped below the ban threshold and its ban didn't expire yet
+    TemporarilyBanned,
+    /// The node's IP is outside the networks allowed by `--netrestrict`
+    Restricted,
+    /// The peer limit was reached and the node is neither trusted nor static
+    TooManyPeers,
+}
+
+/// Peers managed by the node operator, persisted in the datadir between restarts
+#[derive(Debug, Clone, Default)]
+pub struct ManagedPeers {
+    /// Peers which are always dialed and redialed after disconnecting
+    pub static_peers: Vec<Node>,
+    /// Peers allowed to connect even when the peer limit was reached
+    pub trusted_peers: Vec<Node>,
+    /// Peers which are never contacted nor accepted
+    pub banned_peers: Vec<Node>,
+}
+
 #[derive(Clone, Debug)]
 pub struct PeerTable {
     handle: GenServerHandle<PeerTableServer>,
+    managed_peers: watch::Receiver<ManagedPeers>,
 }
 
 impl PeerTable {
-    pub fn spawn(target_peers: usize) -> PeerTable {
+    /// Starts the peer table. If `netrestrict` is not empty only nodes with an IP within one of
+    /// the given networks are contacted or accepted.
+    pub fn spawn(target_peers: usize, netrestrict: Vec<IpNet>) -> PeerTable {
+        Self::spawn_server(PeerTableServer::new(target_peers, netrestrict))
+    }
+
+    /// Same as [`Self::spawn`], with the random choices of peers and contacts made reproducible
+    pub fn spawn_with_seed(target_peers: usize, netrestrict: Vec<IpNet>, seed: u64) -> PeerTable {
+        let mut server = PeerTableServer::new(target_peers, netrestrict);
+        server.rng = StdRng::seed_from_u64(seed);
+        Self::spawn_server(server)
+    }
+
+    fn spawn_server(server: PeerTableServer) -> PeerTable {
+        let managed_peers = server.managed_peers_sender.subscribe();
         PeerTable {
-            handle: PeerTableServer::new(target_peers).start(),
+            handle: server.start(),
+            managed_peers,
         }
     }
 
+    /// Notifies every change of the static, trusted and banned peers, so they can be persisted
+    pub fn subscribe_managed_peers(&self) -> watch::Receiver<ManagedPeers> {
+        let mut receiver = self.managed_peers.clone();
+        receiver.mark_unchanged();
+        receiver
+    }
+
     /// We received a list of Nodes to contact. No conection has been established yet.
     pub async fn new_contacts(
         &mut self,
@@ -249,16 +306,61 @@ impl PeerTable {
 
     /// Record a failed connection, used to score peers
     pub async fn record_failure(&mut self, node_id: &H256) -> Result<(), PeerTableError> {
+        self.penalize(node_id, Penalty::UselessResponse).await
+    }
+
+    /// Record a critical failure for connection, used to score peers
+    pub async fn record_critical_failure(&mut self, node_id: &H256) -> Result<(), PeerTableError> {
+        self.penalize(node_id, Penalty::InvalidData).await
+    }
+
+    /// Lower the score of a peer, disconnecting and temporarily banning it if the score drops
+    /// below the ban threshold
+    pub async fn penalize(
+        &mut self,
+        node_id: &H256,
+        penalty: Penalty,
+    ) -> Result<(), PeerTableError> {
         self.handle
-            .cast(CastMessage::RecordFailure { node_id: *node_id })
+            .cast(CastMessage::Penalize {
+                node_id: *node_id,
+                penalty,
+            })
             .await?;
         Ok(())
     }
 
-    /// Record a critical failure for connection, used to score peers
-    pub async fn record_critical_failure(&mut self, node_id: &H256) -> Result<(), PeerTableError> {
+    /// Record the latency and amount of items of a response, used to keep per request statistics
+    pub async fn record_response(
+        &mut self,
+        node_id: &H256,
+        kind: RequestKind,
+        latency: Duration,
+        items: usize,
+    ) -> Result<(), PeerTableError> {
         self.handle
-            .cast(CastMessage::RecordCriticalFailure { node_id: *node_id })
+            .cast(CastMessage::RecordResponse {
+                node_id: *node_id,
+                kind,
+                latency,
+                items,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Record a request which wasn't answered in time. It doesn't penalize the peer, see
+    /// [`PeerTable::penalize`].
+    pub async fn record_timeout(
+        &mut self,
+        node_id: &H256,
+        kind: RequestKind,
+    ) -> Result<(), PeerTableError> {
+        self.handle
+            .cast(CastMessage::RecordTimeout {
+                node_id: *node_id,
+                kind,
+            })
             .await?;
         Ok(())
     }
@@ -463,6 +565,14 @@ impl PeerTable {
         }
     }
 
+    /// Returns the contacts which answered our pings, whether or not they are usable as peers
+    pub async fn get_validated_contacts(&mut self) -> Result<Vec<Contact>, PeerTableError> {
+        match self.handle.call(CallMessage::GetValidatedContacts).await? {
+            OutMessage::Contacts(contacts) => Ok(contacts),
+            _ => unreachable!(),
+        }
+    }
+
     /// Returns the peer with the highest score and its peer channel.
     pub async fn get_best_peer(
         &mut self,
@@ -585,7 +695,109 @@ impl PeerTable {
         }
     }
 
-    /// Retrieve a random peer.
+    /// Adds a peer which is kept connected, dialing it again whenever the connection drops
+    pub async fn add_static_peer(&mut self, node: Node) -> Result<(), PeerTableError> {
+        self.handle
+            .cast(CastMessage::AddStaticPeer { node })
+            .await?;
+        Ok(())
+    }
+
+    /// Stops redialing the given peer and disconnects from it
+    pub async fn remove_static_peer(&mut self, node_id: H256) -> Result<(), PeerTableError> {
+        self.handle
+            .cast(CastMessage::RemoveStaticPeer { node_id })
+            .await?;
+        Ok(())
+    }
+
+    /// Adds a peer which can connect even when the peer limit was reached
+    pub async fn add_trusted_peer(&mut self, node: Node) -> Result<(), PeerTableError> {
+        self.handle
+            .cast(CastMessage::AddTrustedPeer { node })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_trusted_peer(&mut self, node_id: H256) -> Result<(), PeerTableError> {
+        self.handle
+            .cast(CastMessage::RemoveTrustedPeer { node_id })
+            .await?;
+        Ok(())
+    }
+
+    /// Bans the given peer, disconnecting from it and discarding it from every other category
+    pub async fn ban_peer(&mut self, node: Node) -> Result<(), PeerTableError> {
+        self.handle.cast(CastMessage::BanPeer { node }).await?;
+        Ok(())
+    }
+
+    pub async fn unban_peer(&mut self, node_id: H256) -> Result<(), PeerTableError> {
+        self.handle.cast(CastMessage::UnbanPeer { node_id }).await?;
+        Ok(())
+    }
+
+    /// Adds every peer of the given categories, used to restore them on startup
+    pub async fn add_managed_peers(&mut self, peers: ManagedPeers) -> Result<(), PeerTableError> {
+        for node in peers.banned_peers {
+            self.ban_peer(node).await?;
+        }
+        for node in peers.trusted_peers {
+            self.add_trusted_peer(node).await?;
+        }
+        for node in peers.static_peers {
+            self.add_static_peer(node).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_managed_peers(&mut self) -> Result<ManagedPeers, PeerTableError> {
+        match self.handle.call(CallMessage::GetManagedPeers).await? {
+            OutMessage::ManagedPeers(peers) => Ok(peers),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Restores the reputations of peers, as persisted by a previous run
+    pub async fn add_reputations(
+        &mut self,
+        reputations: BTreeMap<H256, Reputation>,
+    ) -> Result<(), PeerTableError> {
+        self.handle
+            .cast(CastMessage::AddReputations { reputations })
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the reputations worth persisting, of both connected and previously seen peers
+    pub async fn get_reputations(&mut self) -> Result<BTreeMap<H256, Reputation>, PeerTableError> {
+        match self.handle.call(CallMessage::GetReputations).await? {
+            OutMessage::Reputations(reputations) => Ok(reputations),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Checks whether a connection with the given node should be kept, based on bans, the
+    /// allowed networks and the peer limit, which trusted and static peers are exempt from
+    pub async fn check_admission(&mut self, node: &Node) -> Result<PeerAdmission, PeerTableError> {
+        match self
+            .handle
+            .call(CallMessage::CheckAdmission { node: node.clone() })
+            .await?
+        {
+            OutMessage::Admission(admission) => Ok(admission),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the static peers which are not connected and weren't dialed recently
+    pub async fn get_static_peers_to_dial(&mut self) -> Result<Vec<Node>, PeerTableError> {
+        match self.handle.call(CallMessage::GetStaticPeersToDial).await? {
+            OutMessage::Nodes(nodes) => Ok(nodes),
+            _ => unreachable!(),
+        }
+    }
+
     pub async fn get_random_peer(
         &mut self,
         capabilities: &[Capability],
@@ -614,41 +826,64 @@ struct PeerTableServer {
     already_tried_peers: FxHashSet<H256>,
     discarded_contacts: FxHashSet<H256>,
     target_peers: usize,
+    static_peers: IndexMap<H256, Node>,
+    /// Last time each static peer was dialed, to avoid dialing it again while connecting
+    static_peers_last_dial: FxHashMap<H256, Instant>,
+    trusted_peers: IndexMap<H256, Node>,
+    banned_peers: IndexMap<H256, Node>,
+    netrestrict: Vec<IpNet>,
+    /// Reputations of peers which are not connected, the ones of connected peers are kept in
+    /// their `PeerData`
+    reputations: FxHashMap<H256, Reputation>,
+    /// Publishes the managed peers each time they change
+    managed_peers_sender: watch::Sender<ManagedPeers>,
+    /// Source of the random choices of peers and contacts
+    rng: StdRng,
 }
 
 impl PeerTableServer {
-    pub(crate) fn new(target_peers: usize) -> Self {
+    pub(crate) fn new(target_peers: usize, netrestrict: Vec<IpNet>) -> Self {
         Self {
             contacts: Default::default(),
             peers: Default::default(),
             already_tried_peers: Default::default(),
             discarded_contacts: Default::default(),
             target_peers,
+            static_peers: Default::default(),
+            static_peers_last_dial: Default::default(),
+            trusted_peers: Default::default(),
+            banned_peers: Default::default(),
+            netrestrict,
+            reputations: Default::default(),
+            managed_peers_sender: watch::Sender::new(ManagedPeers::default()),
+            rng: StdRng::from_entropy(),
         }
     }
     // Internal functions //
 
     // Weighting function used to select best peer
     // TODO: Review this formula and weight constants.
-    fn weight_peer(&self, score: &i64, requests: &i64) -> i64 {
-        score * SCORE_WEIGHT - requests * REQUESTS_WEIGHT
+    fn weight_peer(&self, score: f64, requests: i64) -> f64 {
+        score * SCORE_WEIGHT - requests as f64 * REQUESTS_WEIGHT
     }
 
     // Returns if the peer has room for more connections given the current score
     // and amount of inflight requests
-    fn can_try_more_requests(&self, score: &i64, requests: &i64) -> bool {
-        let score_ratio = (score - MIN_SCORE) as f64 / (MAX_SCORE - MIN_SCORE) as f64;
-        (*requests as f64) < MAX_CONCURRENT_REQUESTS_PER_PEER as f64 * score_ratio
+    fn can_try_more_requests(&self, score: f64, requests: i64) -> bool {
+        let score_ratio = (score - MIN_SCORE) / (MAX_SCORE - MIN_SCORE);
+        (requests as f64) < MAX_CONCURRENT_REQUESTS_PER_PEER as f64 * score_ratio
     }
 
     fn get_best_peer(&self, capabilities: &[Capability]) -> Option<(H256, PeerConnection)> {
+        let now = current_unix_time();
         self.peers
             .iter()
             // We filter only to those peers which are useful to us
             .filter_map(|(id, peer_data)| {
                 // Skip the peer if it has too many ongoing requests or if it doesn't match
                 // the capabilities
-                if !self.can_try_more_requests(&peer_data.score, &peer_data.requests)
+                let score = peer_data.reputation.score(now);
+                if !self.can_try_more_requests(score, peer_data.requests)
                     || !capabilities
                         .iter()
                         .any(|cap| peer_data.supported_capabilities.contains(cap))
@@ -659,10 +894,13 @@ impl PeerTableServer {
                     let connection = peer_data.connection.clone()?;
 
                     // We return the id, the score and the channel to connect with.
-                    Some((*id, peer_data.score, peer_data.requests, connection))
+                    Some((*id, score, peer_data.requests, connection))
                 }
             })
-            .max_by_key(|(_, score, reqs, _)| self.weight_peer(score, reqs))
+            .max_by(|(_, score_a, reqs_a, _), (_, score_b, reqs_b, _)| {
+                self.weight_peer(*score_a, *reqs_a)
+                    .total_cmp(&self.weight_peer(*score_b, *reqs_b))
+            })
             .map(|(k, _, _, v)| (k, v))
     }
 
@@ -677,6 +915,163 @@ impl PeerTableServer {
             self.contacts.swap_remove(&contact_to_discard_id);
             self.discarded_contacts.insert(contact_to_discard_id);
         }
+        self.prune_reputations();
+    }
+
+    /// Drops the reputations carrying no information and, if there are still too many, the ones
+    /// closest to neutral
+    fn prune_reputations(&mut self) {
+        let now = current_unix_time();
+        self.reputations
+            .retain(|_, reputation| !reputation.is_neutral(now));
+        if self.reputations.len() <= MAX_STORED_REPUTATIONS {
+            return;
+        }
+        let mut reputations = self.reputations.drain().collect::<Vec<_>>();
+        // Active bans are kept first, then the scores furthest from neutral
+        reputations.sort_by(|(_, a), (_, b)| {
+            b.is_banned(now)
+                .cmp(&a.is_banned(now))
+                .then(b.score(now).abs().total_cmp(&a.score(now).abs()))
+        });
+        reputations.truncate(MAX_STORED_REPUTATIONS);
+        self.reputations.extend(reputations);
+    }
+
+    fn reputation_mut(&mut self, node_id: &H256) -> &mut Reputation {
+        match self.peers.get_mut(node_id) {
+            Some(peer_data) => &mut peer_data.reputation,
+            None => self.reputations.entry(*node_id).or_default(),
+        }
+    }
+
+    fn is_temporarily_banned(&self, node_id: &H256) -> bool {
+        let now = current_unix_time();
+        self.peers
+            .get(node_id)
+            .map(|peer_data| &peer_data.reputation)
+            .or_else(|| self.reputations.get(node_id))
+            .is_some_and(|reputation| reputation.is_banned(now))
+    }
+
+    /// Disconnects the peer and bans it for a while. Static and trusted peers are exempt, as the
+    /// operator explicitly asked to stay connected to them.
+    async fn ban_temporarily(&mut self, node_id: H256, reason: Penalty) {
+        if self.static_peers.contains_key(&node_id) || self.trusted_peers.contains_key(&node_id) {
+            return;
+        }
+        let duration = self.reputation_mut(&node_id).ban(current_unix_time());
+        tracing::info!(
+            peer = %node_id,
+            %reason,
+            ban_secs = duration.as_secs(),
+            "Peer score dropped below the threshold, disconnecting and banning it temporarily"
+        );
+        self.disconnect_peer(&node_id, DisconnectReason::UselessPeer)
+            .await;
+    }
+
+    /// Returns the reputations worth keeping of both connected and disconnected peers
+    fn reputations(&self) -> BTreeMap<H256, Reputation> {
+        let now = current_unix_time();
+        self.reputations
+            .iter()
+            .chain(
+                self.peers
+                    .iter()
+                    .map(|(node_id, peer_data)| (node_id, &peer_data.reputation)),
+            )
+            .filter(|(_, reputation)| !reputation.is_neutral(now))
+            .map(|(node_id, reputation)| (*node_id, reputation.clone()))
+            .collect()
+    }
+
+    /// Returns false if the node is banned or its IP is outside the allowed networks
+    fn is_allowed(&self, node: &Node) -> bool {
+        let node_id = node.node_id();
+        !self.banned_peers.contains_key(&node_id)
+            && !self.is_temporarily_banned(&node_id)
+            && self.is_ip_allowed(node.ip)
+    }
+
+    fn is_ip_allowed(&self, ip: IpAddr) -> bool {
+        self.netrestrict.is_empty() || self.netrestrict.iter().any(|net| net.contains(&ip))
+    }
+
+    fn check_admission(&self, node: &Node) -> PeerAdmission {
+        let node_id = node.node_id();
+        if self.banned_peers.contains_key(&node_id) {
+            PeerAdmission::Banned
+        } else if self.is_temporarily_banned(&node_id) {
+            PeerAdmission::TemporarilyBanned
+        } else if !self.is_ip_allowed(node.ip) {
+            PeerAdmission::Restricted
+        } else if self.trusted_peers.contains_key(&node_id)
+            || self.static_peers.contains_key(&node_id)
+        {
+            PeerAdmission::Accepted
+        } else if self.peers.len() >= self.target_peers {
+            PeerAdmission::TooManyPeers
+        } else {
+            PeerAdmission::Accepted
+        }
+    }
+
+    fn get_static_peers_to_dial(&mut self) -> Vec<Node> {
+        let now = Instant::now();
+        let mut nodes = Vec::new();
+        for (node_id, node) in &self.static_peers {
+            if self.peers.contains_key(node_id) || !self.is_ip_allowed(node.ip) {
+                continue;
+            }
+            let recently_dialed = self
+                .static_peers_last_dial
+                .get(node_id)
+                .is_some_and(|last| {
+                    now.saturating_duration_since(*last) < STATIC_PEER_REDIAL_INTERVAL
+                });
+            if !recently_dialed {
+                self.static_peers_last_dial.insert(*node_id, now);
+                nodes.push(node.clone());
+            }
+        }
+        nodes
+    }
+
+    async fn disconnect_peer(&mut self, node_id: &H256, reason: DisconnectReason) {
+        if let Some(mut connection) = self
+            .peers
+            .get(node_id)
+            .and_then(|peer_data| peer_data.connection.clone())
+        {
+            let _ = connection
+                .disconnect(reason)
+                .await
+                .inspect_err(|err| tracing::debug!(%err, "Could not disconnect from peer"));
+        }
+    }
+
+    async fn ban_peer(&mut self, node: Node) {
+        let node_id = node.node_id();
+        self.static_peers.swap_remove(&node_id);
+        self.static_peers_last_dial.remove(&node_id);
+        self.trusted_peers.swap_remove(&node_id);
+        self.contacts.swap_remove(&node_id);
+        self.banned_peers.insert(node_id, node);
+        self.disconnect_peer(&node_id, DisconnectReason::DisconnectRequested)
+            .await;
+    }
+
+    fn managed_peers(&self) -> ManagedPeers {
+        ManagedPeers {
+            static_peers: self.static_peers.values().cloned().collect(),
+            trusted_peers: self.trusted_peers.values().cloned().collect(),
+            banned_peers: self.banned_peers.values().cloned().collect(),
+        }
+    }
+
+    fn publish_managed_peers(&self) {
+        self.managed_peers_sender.send_replace(self.managed_peers());
     }
 
     fn get_contact_to_initiate(&mut self) -> Option<Contact> {
@@ -686,6 +1081,7 @@ impl PeerTableServer {
                 && !self.already_tried_peers.contains(&node_id)
                 && contact.knows_us
                 && !contact.unwanted
+                && !self.is_temporarily_banned(&node_id)
             {
                 self.already_tried_peers.insert(node_id);
 
@@ -698,7 +1094,7 @@ impl PeerTableServer {
         None
     }
 
-    fn get_contact_for_lookup(&self) -> Option<Contact> {
+    fn get_contact_for_lookup(&mut self) -> Option<Contact> {
         self.contacts
             .values()
             .filter(|c| {
@@ -707,7 +1103,7 @@ impl PeerTableServer {
                     && c.is_fork_id_valid != Some(false)
             })
             .collect::<Vec<_>>()
-            .choose(&mut rand::rngs::OsRng)
+            .choose(&mut self.rng)
             .cloned()
             .cloned()
     }
@@ -722,7 +1118,7 @@ impl PeerTableServer {
                     && !c.disposable
             })
             .collect::<Vec<_>>()
-            .choose(&mut rand::rngs::OsRng)
+            .choose(&mut self.rng)
             .cloned()
             .cloned()
     }
@@ -775,6 +1171,9 @@ impl PeerTableServer {
     async fn new_contacts(&mut self, nodes: Vec<Node>, local_node_id: H256) {
         for node in nodes {
             let node_id = node.node_id();
+            if !self.is_allowed(&node) {
+                continue;
+            }
             if let Entry::Vacant(vacant_entry) = self.contacts.entry(node_id)
                 && !self.discarded_contacts.contains(&node_id)
                 && node_id != local_node_id
@@ -822,7 +1221,7 @@ impl PeerTableServer {
             .collect()
     }
 
-    fn get_random_peer(&self, capabilities: Vec<Capability>) -> Option<(H256, PeerConnection)> {
+    fn get_random_peer(&mut self, capabilities: Vec<Capability>) -> Option<(H256, PeerConnection)> {
         let peers: Vec<(H256, PeerConnection)> = self
             .peers
             .iter()
@@ -840,7 +1239,7 @@ impl PeerTableServer {
                     .map(|connection| (*node_id, connection))
             })
             .collect();
-        peers.choose(&mut rand::rngs::OsRng).cloned()
+        peers.choose(&mut self.rng).cloned()
     }
 
     fn distance(node_id_1: &H256, node_id_2: &H256) -> usize {
@@ -897,11 +1296,22 @@ enum CastMessage {
     RecordSuccess {
         node_id: H256,
     },
-    RecordFailure {
+    Penalize {
+        node_id: H256,
+        penalty: Penalty,
+    },
+    RecordResponse {
         node_id: H256,
+        kind: RequestKind,
+        latency: Duration,
+        items: usize,
     },
-    RecordCriticalFailure {
+    RecordTimeout {
         node_id: H256,
+        kind: RequestKind,
+    },
+    AddReputations {
+        reputations: BTreeMap<H256, Reputation>,
     },
     RecordPingSent {
         node_id: H256,
@@ -929,6 +1339,24 @@ enum CastMessage {
     KnowsUs {
         node_id: H256,
     },
+    AddStaticPeer {
+        node: Node,
+    },
+    RemoveStaticPeer {
+        node_id: H256,
+    },
+    AddTrustedPeer {
+        node: Node,
+    },
+    RemoveTrustedPeer {
+        node_id: H256,
+    },
+    BanPeer {
+        node: Node,
+    },
+    UnbanPeer {
+        node_id: H256,
+    },
     Prune,
     Shutdown,
 }
@@ -945,6 +1373,7 @@ enum CallMessage {
     GetContactForEnrLookup,
     GetContact { node_id: H256 },
     GetContactsToRevalidate(Duration),
+    GetValidatedContacts,
     GetBestPeer { capabilities: Vec<Capability> },
     GetScore { node_id: H256 },
     GetConnectedNodes,
@@ -955,6 +1384,10 @@ enum CallMessage {
     GetClosestNodes { node_id: H256 },
     GetPeersData,
     GetRandomPeer { capabilities: Vec<Capability> },
+    GetManagedPeers,
+    CheckAdmission { node: Node },
+    GetStaticPeersToDial,
+    GetReputations,
 }
 
 #[derive(Debug)]
@@ -978,6 +1411,9 @@ pub enum OutMessage {
     UnknownContact,
     IpMismatch,
     PeersData(Vec<PeerData>),
+    ManagedPeers(ManagedPeers),
+    Admission(PeerAdmission),
+    Reputations(BTreeMap<H256, Reputation>),
 }
 
 #[derive(Debug, Error)]
@@ -1047,6 +1483,13 @@ impl GenServer for PeerTableServer {
             CallMessage::GetContactsToRevalidate(revalidation_interval) => CallResponse::Reply(
                 Self::OutMsg::Contacts(self.get_contacts_to_revalidate(revalidation_interval)),
             ),
+            CallMessage::GetValidatedContacts => CallResponse::Reply(Self::OutMsg::Contacts(
+                self.contacts
+                    .values()
+                    .filter(|contact| contact.was_validated())
+                    .cloned()
+                    .collect(),
+            )),
             CallMessage::GetBestPeer { capabilities } => {
                 let channels = self.get_best_peer(&capabilities);
                 CallResponse::Reply(channels.map_or(
@@ -1060,7 +1503,7 @@ impl GenServer for PeerTableServer {
             CallMessage::GetScore { node_id } => CallResponse::Reply(Self::OutMsg::PeerScore(
                 self.peers
                     .get(&node_id)
-                    .map(|peer_data| peer_data.score)
+                    .map(|peer_data| peer_data.reputation.score(current_unix_time()).round() as i64)
                     .unwrap_or_default(),
             )),
             CallMessage::GetConnectedNodes => CallResponse::Reply(Self::OutMsg::Nodes(
@@ -1091,6 +1534,7 @@ impl GenServer for PeerTableServer {
             CallMessage::InsertIfNew { node } => CallResponse::Reply(Self::OutMsg::IsNew(
                 match self.contacts.entry(node.node_id()) {
                     Entry::Occupied(_) => false,
+                    Entry::Vacant(_) if !self.is_allowed(&node) => false,
                     Entry::Vacant(entry) => {
                         METRICS.record_new_discovery().await;
                         entry.insert(Contact::from(node));
@@ -1117,6 +1561,18 @@ impl GenServer for PeerTableServer {
                     OutMessage::NotFound
                 },
             ),
+            CallMessage::GetManagedPeers => {
+                CallResponse::Reply(OutMessage::ManagedPeers(self.managed_peers()))
+            }
+            CallMessage::CheckAdmission { node } => {
+                CallResponse::Reply(OutMessage::Admission(self.check_admission(&node)))
+            }
+            CallMessage::GetStaticPeersToDial => {
+                CallResponse::Reply(OutMessage::Nodes(self.get_static_peers_to_dial()))
+            }
+            CallMessage::GetReputations => {
+                CallResponse::Reply(OutMessage::Reputations(self.reputations()))
+            }
         }
     }
 
@@ -1138,11 +1594,23 @@ impl GenServer for PeerTableServer {
                 capabilities,
             } => {
                 let new_peer_id = node.node_id();
-                let new_peer = PeerData::new(node, None, Some(connection), capabilities);
+                let mut new_peer = PeerData::new(node, None, Some(connection), capabilities);
+                if let Some(reputation) = self
+                    .peers
+                    .get(&new_peer_id)
+                    .map(|peer_data| peer_data.reputation.clone())
+                    .or_else(|| self.reputations.remove(&new_peer_id))
+                {
+                    new_peer.reputation = reputation;
+                }
                 self.peers.insert(new_peer_id, new_peer);
             }
             CastMessage::RemovePeer { node_id } => {
-                self.peers.swap_remove(&node_id);
+                if let Some(peer_data) = self.peers.swap_remove(&node_id)
+                    && !peer_data.reputation.is_neutral(current_unix_time())
+                {
+                    self.reputations.insert(node_id, peer_data.reputation);
+                }
             }
             CastMessage::IncRequests { node_id } => {
                 self.peers
@@ -1167,17 +1635,39 @@ impl GenServer for PeerTableServer {
             CastMessage::RecordSuccess { node_id } => {
                 self.peers
                     .entry(node_id)
-                    .and_modify(|peer_data| peer_data.score = (peer_data.score + 1).min(MAX_SCORE));
+                    .and_modify(|peer_data| peer_data.reputation.reward(current_unix_time()));
             }
-            CastMessage::RecordFailure { node_id } => {
-                self.peers
-                    .entry(node_id)
-                    .and_modify(|peer_data| peer_data.score = (peer_data.score - 1).max(MIN_SCORE));
+            CastMessage::Penalize { node_id, penalty } => {
+                if self
+                    .reputation_mut(&node_id)
+                    .penalize(penalty, current_unix_time())
+                {
+                    self.ban_temporarily(node_id, penalty).await;
+                }
             }
-            CastMessage::RecordCriticalFailure { node_id } => {
-                self.peers
-                    .entry(node_id)
-                    .and_modify(|peer_data| peer_data.score = MIN_SCORE_CRITICAL);
+            CastMessage::RecordResponse {
+                node_id,
+                kind,
+                latency,
+                items,
+            } => {
+                self.peers.entry(node_id).and_modify(|peer_data| {
+                    peer_data.reputation.record_response(kind, latency, items)
+                });
+            }
+            CastMessage::RecordTimeout { node_id, kind } => {
+                self.reputation_mut(&node_id).record_timeout(kind);
+            }
+            CastMessage::AddReputations { reputations } => {
+                for (node_id, reputation) in reputations {
+                    match self.peers.get_mut(&node_id) {
+                        Some(peer_data) => peer_data.reputation = reputation,
+                        None => {
+                            self.reputations.insert(node_id, reputation);
+                        }
+                    }
+                }
+                self.prune_reputations();
             }
             CastMessage::RecordPingSent { node_id, hash } => {
                 self.contacts
@@ -1229,9 +1719,182 @@ impl GenServer for PeerTableServer {
                     .entry(node_id)
                     .and_modify(|c| c.knows_us = true);
             }
+            CastMessage::AddStaticPeer { node } => {
+                if self.is_allowed(&node) {
+                    self.static_peers.insert(node.node_id(), node);
+                    self.publish_managed_peers();
+                }
+            }
+            CastMessage::RemoveStaticPeer { node_id } => {
+                if self.static_peers.swap_remove(&node_id).is_some() {
+                    self.publish_managed_peers();
+                }
+                self.static_peers_last_dial.remove(&node_id);
+                self.disconnect_peer(&node_id, DisconnectReason::DisconnectRequested)
+                    .await;
+            }
+            CastMessage::AddTrustedPeer { node } => {
+                if self.is_allowed(&node) {
+                    self.trusted_peers.insert(node.node_id(), node);
+                    self.publish_managed_peers();
+                }
+            }
+            CastMessage::RemoveTrustedPeer { node_id } => {
+                if self.trusted_peers.swap_remove(&node_id).is_some() {
+                    self.publish_managed_peers();
+                }
+            }
+            CastMessage::BanPeer { node } => {
+                self.ban_peer(node).await;
+                self.publish_managed_peers();
+            }
+            CastMessage::UnbanPeer { node_id } => {
+                if self.banned_peers.swap_remove(&node_id).is_some() {
+                    self.publish_managed_peers();
+                }
+            }
             CastMessage::Prune => self.prune(),
             CastMessage::Shutdown => return CastResponse::Stop,
         }
         CastResponse::NoReply
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::H512;
+
+    fn node(ip: &str, key: u8) -> Node {
+        Node::new(ip.parse().unwrap(), 30303, 30303, H512::repeat_byte(key))
+    }
+
+    #[test]
+    fn netrestrict_filters_contacts_and_connections() {
+        let mut table = PeerTableServer::new(TARGET_PEERS, vec!["10.0.0.0/8".parse().unwrap()]);
+        let allowed = node("10.1.2.3", 1);
+        let restricted = node("192.168.1.1", 2);
+        assert!(table.is_allowed(&allowed));
+        assert!(!table.is_allowed(&restricted));
+        assert_eq!(table.check_admission(&allowed), PeerAdmission::Accepted);
+        assert_eq!(
+            table.check_admission(&restricted),
+            PeerAdmission::Restricted
+        );
+
+        table.static_peers.insert(restricted.node_id(), restricted);
+        assert!(table.get_static_peers_to_dial().is_empty());
+    }
+
+    #[test]
+    fn trusted_and_static_peers_bypass_peer_limit() {
+        let mut table = PeerTableServer::new(0, Vec::new());
+        let regular = node("127.0.0.1", 1);
+        let trusted = node("127.0.0.1", 2);
+        let static_peer = node("127.0.0.1", 3);
+        table
+            .trusted_peers
+            .insert(trusted.node_id(), trusted.clone());
+        table
+            .static_peers
+            .insert(static_peer.node_id(), static_peer.clone());
+
+        assert_eq!(table.check_admission(&regular), PeerAdmission::TooManyPeers);
+        assert_eq!(table.check_admission(&trusted), PeerAdmission::Accepted);
+        assert_eq!(table.check_admission(&static_peer), PeerAdmission::Accepted);
+
+        // Static peers are not dialed again until the redial interval passes
+        assert_eq!(table.get_static_peers_to_dial().len(), 1);
+        assert!(table.get_static_peers_to_dial().is_empty());
+    }
+
+    #[tokio::test]
+    async fn banned_peers_are_discarded() {
+        let mut table = PeerTableServer::new(TARGET_PEERS, Vec::new());
+        let banned = node("127.0.0.1", 1);
+        table.new_contacts(vec![banned.clone()], H256::zero()).await;
+        table.static_peers.insert(banned.node_id(), banned.clone());
+
+        table.ban_peer(banned.clone()).await;
+        assert!(table.contacts.is_empty());
+        assert!(table.static_peers.is_empty());
+        assert_eq!(table.check_admission(&banned), PeerAdmission::Banned);
+
+        table.new_contacts(vec![banned.clone()], H256::zero()).await;
+        assert!(table.contacts.is_empty());
+
+        table.banned_peers.swap_remove(&banned.node_id());
+        table.new_contacts(vec![banned], H256::zero()).await;
+        assert_eq!(table.contacts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn misbehaving_peers_are_banned_temporarily() {
+        let mut table = PeerTableServer::new(TARGET_PEERS, Vec::new());
+        let bad = node("127.0.0.1", 1);
+        let trusted = node("127.0.0.1", 2);
+        table
+            .trusted_peers
+            .insert(trusted.node_id(), trusted.clone());
+
+        for peer in [&bad, &trusted] {
+            let node_id = peer.node_id();
+            if table
+                .reputation_mut(&node_id)
+                .penalize(Penalty::InvalidData, current_unix_time())
+            {
+                table.ban_temporarily(node_id, Penalty::InvalidData).await;
+            }
+        }
+        assert_eq!(
+            table.check_admission(&bad),
+            PeerAdmission::TemporarilyBanned
+        );
+        assert!(!table.is_allowed(&bad));
+        // Trusted peers keep their low score but are never banned
+        assert_eq!(table.check_admission(&trusted), PeerAdmission::Accepted);
+
+        // Reputations survive pruning and are persisted until they become neutral
+        table.prune();
+        let reputations = table.reputations();
+        assert_eq!(reputations.len(), 2);
+        assert_eq!(reputations[&bad.node_id()].bans, 1);
+        assert_eq!(reputations[&trusted.node_id()].bans, 0);
+    }
+
+    #[tokio::test]
+    async fn managed_peer_changes_are_published() {
+        let mut table = PeerTable::spawn(TARGET_PEERS, Vec::new());
+        let mut changes = table.subscribe_managed_peers();
+        let static_peer = node("127.0.0.1", 1);
+        let banned = node("127.0.0.1", 2);
+
+        table.add_static_peer(static_peer.clone()).await.unwrap();
+        changes.changed().await.unwrap();
+        assert_eq!(
+            changes.borrow_and_update().static_peers,
+            vec![static_peer.clone()]
+        );
+
+        table.ban_peer(banned.clone()).await.unwrap();
+        changes.changed().await.unwrap();
+        assert_eq!(changes.borrow_and_update().banned_peers, vec![banned]);
+
+        table
+            .remove_static_peer(static_peer.node_id())
+            .await
+            .unwrap();
+        changes.changed().await.unwrap();
+        let managed_peers = changes.borrow_and_update().clone();
+        assert!(managed_peers.static_peers.is_empty());
+        assert_eq!(managed_peers.banned_peers.len(), 1);
+
+        // Removing a peer which isn't managed changes nothing
+        table.remove_trusted_peer(H256::zero()).await.unwrap();
+        assert_eq!(
+            table.get_managed_peers().await.unwrap().banned_peers.len(),
+            1
+        );
+        assert!(!changes.has_changed().unwrap());
+    }
+}
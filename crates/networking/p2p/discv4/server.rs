@@ -8,13 +8,16 @@ use crate::{
         peer_table::{Contact, OutMessage as PeerTableOutMessage, PeerTable, PeerTableError},
     },
     metrics::METRICS,
-    types::{Endpoint, Node, NodeRecord},
+    nat::IpVotes,
+    transport::{DatagramSocket, datagram_stream},
+    types::{Endpoint, LocalNodeRecord, Node},
     utils::{
         get_msg_expiration_from_seconds, is_msg_expired, node_id, public_key_from_signing_key,
+        validate_fork_id,
     },
 };
 use bytes::BytesMut;
-use ethrex_common::{H256, H512, types::ForkId};
+use ethrex_common::{H256, H512};
 use ethrex_storage::{Store, error::StoreError};
 use futures::StreamExt;
 use rand::rngs::OsRng;
@@ -26,9 +29,11 @@ use spawned_concurrency::{
         send_message_on, spawn_listener,
     },
 };
-use std::{net::SocketAddr, sync::Arc, time::Duration};
-use tokio::net::UdpSocket;
-use tokio_util::udp::UdpFramed;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 use tracing::{debug, error, info, trace};
 
 pub(crate) const MAX_NODES_IN_NEIGHBORS_PACKET: usize = 16;
@@ -71,6 +76,8 @@ pub enum InMessage {
     EnrLookup,
     Prune,
     ChangeFindNodeMessage,
+    /// External address learned by the NAT server, advertised in the local record
+    SetExternalIp(IpAddr),
     Shutdown,
 }
 
@@ -82,37 +89,33 @@ pub enum OutMessage {
 #[derive(Debug)]
 pub struct DiscoveryServer {
     local_node: Node,
-    local_node_record: NodeRecord,
+    local_node_record: LocalNodeRecord,
     signer: SecretKey,
-    udp_socket: Arc<UdpSocket>,
+    udp_socket: Arc<dyn DatagramSocket>,
     store: Store,
     peer_table: PeerTable,
     /// The last `FindNode` message sent, cached due to message
     /// signatures being expensive.
     find_node_message: BytesMut,
     initial_lookup_interval: f64,
+    ip_votes: IpVotes,
+    /// Set once the external address is provided by the NAT server, votes are ignored from then on
+    external_ip_pinned: bool,
 }
 
 impl DiscoveryServer {
     pub async fn spawn(
         storage: Store,
         local_node: Node,
+        local_node_record: LocalNodeRecord,
         signer: SecretKey,
-        udp_socket: Arc<UdpSocket>,
+        udp_socket: Arc<dyn DatagramSocket>,
         mut peer_table: PeerTable,
         bootnodes: Vec<Node>,
         initial_lookup_interval: f64,
-    ) -> Result<(), DiscoveryServerError> {
+    ) -> Result<GenServerHandle<DiscoveryServer>, DiscoveryServerError> {
         info!("Starting Discovery Server");
 
-        let mut local_node_record = NodeRecord::from_node(&local_node, 1, &signer)
-            .expect("Failed to create local node record");
-        if let Ok(fork_id) = storage.get_fork_id().await {
-            local_node_record
-                .set_fork_id(fork_id, &signer)
-                .expect("Failed to set fork_id on local node record");
-        }
-
         let mut discovery_server = Self {
             local_node: local_node.clone(),
             local_node_record,
@@ -122,6 +125,8 @@ impl DiscoveryServer {
             peer_table: peer_table.clone(),
             find_node_message: Self::random_message(&signer),
             initial_lookup_interval,
+            ip_votes: Default::default(),
+            external_ip_pinned: false,
         };
 
         info!(count = bootnodes.len(), "Adding bootnodes");
@@ -133,8 +138,7 @@ impl DiscoveryServer {
             .new_contacts(bootnodes, local_node.node_id())
             .await?;
 
-        discovery_server.start();
-        Ok(())
+        Ok(discovery_server.start())
     }
 
     async fn handle_message(
@@ -254,7 +258,7 @@ impl DiscoveryServer {
         if let Some(contact) = self.peer_table.get_contact_for_lookup().await? {
             if let Err(e) = self
                 .udp_socket
-                .send_to(&self.find_node_message, &contact.node.udp_addr())
+                .send_to(&self.find_node_message, contact.node.udp_addr())
                 .await
             {
                 error!(sending = "FindNode", addr = ?&contact.node.udp_addr(), err=?e, "Error sending message");
@@ -309,7 +313,7 @@ impl DiscoveryServer {
             udp_port: node.udp_port,
             tcp_port: node.tcp_port,
         };
-        let enr_seq = self.local_node_record.seq;
+        let enr_seq = self.local_node_record.seq();
         let ping = Message::Ping(PingMessage::new(from, to, expiration).with_enr_seq(enr_seq));
         let ping_hash = self.send_else_dispose(ping, node).await?;
         trace!(sent = "Ping", to = %format!("{:#x}", node.public_key));
@@ -330,7 +334,7 @@ impl DiscoveryServer {
             tcp_port: node.tcp_port,
         };
 
-        let enr_seq = self.local_node_record.seq;
+        let enr_seq = self.local_node_record.seq();
 
         let pong = Message::Pong(PongMessage::new(to, ping_hash, expiration).with_enr_seq(enr_seq));
 
@@ -375,9 +379,9 @@ impl DiscoveryServer {
         request_hash: H256,
         from: SocketAddr,
     ) -> Result<(), DiscoveryServerError> {
-        let node_record = &self.local_node_record;
+        let node_record = self.local_node_record.get();
 
-        let msg = Message::ENRResponse(ENRResponseMessage::new(request_hash, node_record.clone()));
+        let msg = Message::ENRResponse(ENRResponseMessage::new(request_hash, node_record));
 
         self.send(msg, from).await?;
 
@@ -431,6 +435,11 @@ impl DiscoveryServer {
             .record_pong_received(&node_id, message.ping_hash)
             .await?;
 
+        // The endpoint the pong was sent to is our address as seen by the contact
+        if contact.ping_hash == Some(message.ping_hash) {
+            self.record_ip_vote(node_id, SocketAddr::new(message.to.ip, message.to.udp_port));
+        }
+
         // If the contact has stale ENR then request the updated one.
         let stored_enr_seq = contact.record.map(|r| r.seq);
         let received_enr_seq = message.enr_seq;
@@ -443,6 +452,37 @@ impl DiscoveryServer {
         Ok(())
     }
 
+    /// Updates the local record once enough nodes agree on a new external address
+    fn record_ip_vote(&mut self, voter: H256, addr: SocketAddr) {
+        if self.external_ip_pinned {
+            return;
+        }
+        if let Some(addr) = self.ip_votes.vote(voter, addr, self.local_node.udp_addr()) {
+            self.update_local_address(addr);
+        }
+    }
+
+    /// Re-signs the local record with the given external address and a bumped sequence number,
+    /// unless discv5 already did
+    fn update_local_address(&mut self, addr: SocketAddr) {
+        let mut local_node = self.local_node.clone();
+        local_node.ip = addr.ip();
+        local_node.udp_port = addr.port();
+        match self
+            .local_node_record
+            .set_address(&local_node, &self.signer)
+        {
+            Ok(record) => {
+                if let Some(record) = record {
+                    info!(%addr, seq = record.seq, "Updated local node record with external address");
+                }
+                self.local_node = local_node;
+                self.ip_votes.clear();
+            }
+            Err(err) => error!(%err, "Failed to update local node record"),
+        }
+    }
+
     async fn handle_find_node(
         &mut self,
         sender_public_key: H512,
@@ -556,31 +596,9 @@ impl DiscoveryServer {
             return Ok(());
         };
 
-        let chain_config = self.store.get_chain_config();
-        let genesis_header = self
-            .store
-            .get_block_header(0)?
-            .ok_or(DiscoveryServerError::InvalidContact)?;
-        let latest_block_number = self.store.get_latest_block_number().await?;
-        let latest_block_header = self
-            .store
-            .get_block_header(latest_block_number)?
-            .ok_or(DiscoveryServerError::InvalidContact)?;
-
-        let local_fork_id = ForkId::new(
-            chain_config,
-            genesis_header.clone(),
-            latest_block_header.timestamp,
-            latest_block_number,
-        );
-
-        if !local_fork_id.is_valid(
-            remote_fork_id.clone(),
-            latest_block_number,
-            latest_block_header.timestamp,
-            chain_config,
-            genesis_header,
-        ) {
+        let (is_valid, local_fork_id) =
+            validate_fork_id(&self.store, remote_fork_id.clone()).await?;
+        if !is_valid {
             self.peer_table
                 .set_is_fork_id_valid(&node_id, false)
                 .await?;
@@ -684,7 +702,7 @@ impl GenServer for DiscoveryServer {
         self,
         handle: &GenServerHandle<Self>,
     ) -> Result<spawned_concurrency::tasks::InitResult<Self>, Self::Error> {
-        let stream = UdpFramed::new(self.udp_socket.clone(), Discv4Codec::new(self.signer));
+        let stream = datagram_stream(self.udp_socket.clone(), Discv4Codec::new(self.signer));
 
         spawn_listener(
             handle.clone(),
@@ -768,6 +786,12 @@ impl GenServer for DiscoveryServer {
             Self::CastMsg::ChangeFindNodeMessage => {
                 self.find_node_message = Self::random_message(&self.signer);
             }
+            Self::CastMsg::SetExternalIp(ip) => {
+                self.external_ip_pinned = true;
+                if ip != self.local_node.ip {
+                    self.update_local_address(SocketAddr::new(ip, self.local_node.udp_port));
+                }
+            }
             Self::CastMsg::Shutdown => return CastResponse::Stop,
         }
         CastResponse::NoReply
@@ -156,6 +156,20 @@ impl Packet {
         Ok(())
     }
 
+    /// Unmasks the header of an encoded packet without decrypting its message.
+    /// Used to find out the packet type and sender before the session keys are known.
+    pub fn peek_header(
+        dest_id: &H256,
+        encoded_packet: &[u8],
+    ) -> Result<PacketHeader, PacketCodecError> {
+        if encoded_packet.len() < MIN_PACKET_SIZE || encoded_packet.len() > MAX_PACKET_SIZE {
+            return Err(PacketCodecError::InvalidSize);
+        }
+        let masking_iv = &encoded_packet[..IV_MASKING_SIZE];
+        let mut cipher = <Aes128Ctr64BE as KeyIvInit>::new(dest_id[..16].into(), masking_iv.into());
+        Packet::decode_header(&mut cipher, encoded_packet)
+    }
+
     fn decode_header<T: StreamCipher>(
         cipher: &mut T,
         encoded_packet: &[u8],
@@ -183,6 +197,9 @@ impl Packet {
         let nonce = static_header[9..21].try_into()?;
         let authdata_size = u16::from_be_bytes(static_header[21..23].try_into()?) as usize;
         let authdata_end = STATIC_HEADER_END + authdata_size;
+        if authdata_end > encoded_packet.len() {
+            return Err(PacketCodecError::InvalidSize);
+        }
         let authdata = &mut encoded_packet[STATIC_HEADER_END..authdata_end].to_vec();
 
         cipher.try_apply_keystream(authdata)?;
@@ -330,6 +347,9 @@ impl WhoAreYou {
     }
 
     pub fn decode(authdata: &[u8]) -> Result<WhoAreYou, PacketCodecError> {
+        if authdata.len() != 24 {
+            return Err(PacketCodecError::InvalidSize);
+        }
         let id_nonce = u128::from_be_bytes(authdata[..16].try_into()?);
         let enr_seq = u64::from_be_bytes(authdata[16..].try_into()?);
 
@@ -348,6 +368,15 @@ pub struct Handshake {
     pub message: Message,
 }
 
+/// The authdata of a handshake packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeAuthdata {
+    pub src_id: H256,
+    pub id_signature: Vec<u8>,
+    pub eph_pubkey: Vec<u8>,
+    pub record: Option<NodeRecord>,
+}
+
 impl Handshake {
     fn encode_authdata(&self, buf: &mut dyn BufMut) -> Result<(), PacketCodecError> {
         let sig_size: u8 = self
@@ -413,23 +442,9 @@ impl Handshake {
         Ok((static_header, authdata, message))
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn decode(
-        masking_iv: &[u8],
-        header: PacketHeader,
-        decrypt_key: &[u8],
-        encrypted_message: &[u8],
-    ) -> Result<Handshake, PacketCodecError> {
-        if decrypt_key.len() < 16 {
-            return Err(PacketCodecError::InvalidSize);
-        }
-        let PacketHeader {
-            static_header,
-            nonce,
-            authdata,
-            ..
-        } = header;
-
+    /// Decodes the authdata of a handshake packet. The ephemeral key is needed to derive the
+    /// session keys before the message itself can be decrypted.
+    pub fn decode_authdata(authdata: &[u8]) -> Result<HandshakeAuthdata, PacketCodecError> {
         if authdata.len() < HANDSHAKE_AUTHDATA_HEAD {
             return Err(PacketCodecError::InvalidSize);
         }
@@ -459,6 +474,38 @@ impl Handshake {
             None
         };
 
+        Ok(HandshakeAuthdata {
+            src_id,
+            id_signature,
+            eph_pubkey,
+            record,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn decode(
+        masking_iv: &[u8],
+        header: PacketHeader,
+        decrypt_key: &[u8],
+        encrypted_message: &[u8],
+    ) -> Result<Handshake, PacketCodecError> {
+        if decrypt_key.len() < 16 {
+            return Err(PacketCodecError::InvalidSize);
+        }
+        let PacketHeader {
+            static_header,
+            nonce,
+            authdata,
+            ..
+        } = header;
+
+        let HandshakeAuthdata {
+            src_id,
+            id_signature,
+            eph_pubkey,
+            record,
+        } = Handshake::decode_authdata(&authdata)?;
+
         let mut message_ad = masking_iv.to_vec();
         message_ad.extend_from_slice(&static_header);
         message_ad.extend_from_slice(&authdata);
@@ -516,7 +563,9 @@ impl Message {
     }
 
     pub fn decode(encrypted_message: &[u8]) -> Result<Message, RLPDecodeError> {
-        let message_type = encrypted_message[0];
+        let Some(&message_type) = encrypted_message.first() else {
+            return Err(RLPDecodeError::InvalidLength);
+        };
         match message_type {
             0x01 => {
                 let ping = PingMessage::decode(&encrypted_message[1..])?;
@@ -589,6 +638,7 @@ pub struct PongMessage {
     pub req_id: Bytes,
     pub enr_seq: u64,
     pub recipient_addr: IpAddr,
+    pub recipient_port: u16,
 }
 
 impl RLPEncode for PongMessage {
@@ -597,6 +647,7 @@ impl RLPEncode for PongMessage {
             .encode_field(&self.req_id)
             .encode_field(&self.enr_seq)
             .encode_field(&self.recipient_addr)
+            .encode_field(&self.recipient_port)
             .finish();
     }
 }
@@ -607,12 +658,14 @@ impl RLPDecode for PongMessage {
         let (req_id, decoder) = decoder.decode_field("req_id")?;
         let (enr_seq, decoder) = decoder.decode_field("enr_seq")?;
         let (recipient_addr, decoder) = decoder.decode_field("recipient_addr")?;
+        let (recipient_port, decoder) = decoder.decode_field("recipient_port")?;
 
         Ok((
             Self {
                 req_id,
                 enr_seq,
                 recipient_addr,
+                recipient_port,
             },
             decoder.finish()?,
         ))
@@ -1165,6 +1218,7 @@ mod tests {
             req_id: Bytes::from_static(&[1, 2, 3, 4]),
             enr_seq: 4321,
             recipient_addr: Ipv4Addr::BROADCAST.into(),
+            recipient_port: 30303,
         };
 
         let buf = pkt.encode_to_vec();
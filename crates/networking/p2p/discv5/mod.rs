@@ -1,3 +1,5 @@
 pub mod codec;
 pub mod messages;
+pub mod routing_table;
+pub mod server;
 pub mod session;
@@ -0,0 +1,211 @@
+use std::time::Instant;
+
+use ethrex_common::{H256, U256};
+use rand::seq::SliceRandom;
+
+use crate::types::{Node, NodeRecord};
+
+/// Maximum amount of nodes per bucket, the `k` from the Kademlia paper
+pub const BUCKET_SIZE: usize = 16;
+/// One bucket per possible log2 distance, distance 0 is the local node itself
+const NUM_BUCKETS: usize = 256;
+
+/// Logarithmic distance between two node ids as defined in
+/// https://github.com/ethereum/devp2p/blob/master/discv5/discv5-theory.md#nodes-table
+pub fn log2_distance(node_id_a: &H256, node_id_b: &H256) -> u64 {
+    let xor = node_id_a ^ node_id_b;
+    U256::from_big_endian(xor.as_bytes()).bits() as u64
+}
+
+#[derive(Debug, Clone)]
+pub struct TableEntry {
+    pub record: NodeRecord,
+    pub node: Node,
+    pub last_seen: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertResult {
+    Inserted,
+    Updated,
+    BucketFull,
+    LocalNode,
+}
+
+/// Kademlia table holding the live discv5 nodes, sorted by their distance to the local node.
+/// Each bucket is ordered from least to most recently seen.
+#[derive(Debug)]
+pub struct KademliaTable {
+    local_id: H256,
+    buckets: Vec<Vec<TableEntry>>,
+}
+
+impl KademliaTable {
+    pub fn new(local_id: H256) -> Self {
+        Self {
+            local_id,
+            buckets: vec![Vec::new(); NUM_BUCKETS],
+        }
+    }
+
+    fn bucket_index(&self, node_id: &H256) -> Option<usize> {
+        (log2_distance(&self.local_id, node_id) as usize).checked_sub(1)
+    }
+
+    /// Inserts a node that just proved to be alive, or refreshes it if it's already present.
+    /// Newer records replace the stored one.
+    pub fn insert(&mut self, record: NodeRecord, node: Node) -> InsertResult {
+        let node_id = node.node_id();
+        let Some(index) = self.bucket_index(&node_id) else {
+            return InsertResult::LocalNode;
+        };
+        let Some(bucket) = self.buckets.get_mut(index) else {
+            return InsertResult::LocalNode;
+        };
+        if let Some(position) = bucket.iter().position(|e| e.node.node_id() == node_id) {
+            let mut entry = bucket.remove(position);
+            if record.seq > entry.record.seq {
+                entry.record = record;
+                entry.node = node;
+            }
+            entry.last_seen = Instant::now();
+            bucket.push(entry);
+            return InsertResult::Updated;
+        }
+        if bucket.len() >= BUCKET_SIZE {
+            return InsertResult::BucketFull;
+        }
+        bucket.push(TableEntry {
+            record,
+            node,
+            last_seen: Instant::now(),
+        });
+        InsertResult::Inserted
+    }
+
+    pub fn get(&self, node_id: &H256) -> Option<&TableEntry> {
+        self.buckets
+            .get(self.bucket_index(node_id)?)?
+            .iter()
+            .find(|e| e.node.node_id() == *node_id)
+    }
+
+    pub fn contains(&self, node_id: &H256) -> bool {
+        self.get(node_id).is_some()
+    }
+
+    pub fn remove(&mut self, node_id: &H256) -> bool {
+        let Some(bucket) = self
+            .bucket_index(node_id)
+            .and_then(|index| self.buckets.get_mut(index))
+        else {
+            return false;
+        };
+        let len = bucket.len();
+        bucket.retain(|e| e.node.node_id() != *node_id);
+        bucket.len() != len
+    }
+
+    /// Returns the records of the nodes at the given log2 distance from the local node
+    pub fn nodes_at_distance(&self, distance: u64) -> Vec<NodeRecord> {
+        let Some(bucket) = (distance as usize)
+            .checked_sub(1)
+            .and_then(|index| self.buckets.get(index))
+        else {
+            return Vec::new();
+        };
+        bucket.iter().map(|e| e.record.clone()).collect()
+    }
+
+    /// Returns up to `count` entries sorted by their distance to the target
+    pub fn closest(&self, target: &H256, count: usize) -> Vec<TableEntry> {
+        let mut entries: Vec<_> = self.buckets.iter().flatten().cloned().collect();
+        entries.sort_by_key(|e| e.node.node_id() ^ *target);
+        entries.truncate(count);
+        entries
+    }
+
+    /// Picks the least recently seen node of a random non-empty bucket
+    pub fn revalidation_candidate(&self) -> Option<TableEntry> {
+        let non_empty: Vec<_> = self.buckets.iter().filter(|b| !b.is_empty()).collect();
+        non_empty
+            .choose(&mut rand::rngs::OsRng)
+            .and_then(|bucket| bucket.first())
+            .cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::public_key_from_signing_key;
+    use secp256k1::SecretKey;
+
+    fn random_node() -> (NodeRecord, Node) {
+        let signer = SecretKey::new(&mut rand::rngs::OsRng);
+        let node = Node::new(
+            "127.0.0.1".parse().unwrap(),
+            30303,
+            30303,
+            public_key_from_signing_key(&signer),
+        );
+        (NodeRecord::from_node(&node, 1, &signer).unwrap(), node)
+    }
+
+    #[test]
+    fn log2_distance_matches_spec() {
+        assert_eq!(log2_distance(&H256::zero(), &H256::zero()), 0);
+        assert_eq!(log2_distance(&H256::zero(), &H256::from_low_u64_be(1)), 1);
+        assert_eq!(
+            log2_distance(&H256::zero(), &H256::from_low_u64_be(0xff)),
+            8
+        );
+        assert_eq!(log2_distance(&H256::zero(), &H256::repeat_byte(0xff)), 256);
+    }
+
+    #[test]
+    fn insert_and_lookup_by_distance() {
+        let (_, local) = random_node();
+        let mut table = KademliaTable::new(local.node_id());
+        let (record, node) = random_node();
+        let node_id = node.node_id();
+
+        assert_eq!(
+            table.insert(record.clone(), node.clone()),
+            InsertResult::Inserted
+        );
+        assert_eq!(table.insert(record, node), InsertResult::Updated);
+        assert_eq!(table.len(), 1);
+
+        let distance = log2_distance(&local.node_id(), &node_id);
+        assert_eq!(table.nodes_at_distance(distance).len(), 1);
+        assert_eq!(table.closest(&node_id, 1)[0].node.node_id(), node_id);
+
+        assert!(table.remove(&node_id));
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn full_bucket_rejects_new_nodes() {
+        let (_, local) = random_node();
+        let mut table = KademliaTable::new(local.node_id());
+        let mut rejected = 0;
+        // Half of the random nodes land in the farthest bucket
+        for _ in 0..(BUCKET_SIZE * 8) {
+            let (record, node) = random_node();
+            if table.insert(record, node) == InsertResult::BucketFull {
+                rejected += 1;
+            }
+        }
+        assert!(rejected > 0);
+        assert_eq!(table.nodes_at_distance(256).len(), BUCKET_SIZE);
+    }
+}
@@ -0,0 +1,1174 @@
+use crate::{
+    discv4::peer_table::{PeerTable, PeerTableError},
+    discv5::{
+        messages::{
+            FindNodeMessage, Handshake, Message, NodesMessage, Ordinary, Packet, PacketCodecError,
+            PacketHeader, PingMessage, PongMessage, TalkResMessage, WhoAreYou,
+        },
+        routing_table::{BUCKET_SIZE, InsertResult, KademliaTable, log2_distance},
+        session::{
+            Session, SessionRole, build_challenge_data, create_id_signature, derive_session_keys,
+            verify_id_signature,
+        },
+    },
+    nat::IpVotes,
+    rlpx::utils::compress_pubkey,
+    transport::{DatagramSocket, datagram_stream},
+    types::{LocalNodeRecord, Node, NodeError, NodeRecord},
+    utils::validate_fork_id,
+};
+use bytes::{Bytes, BytesMut};
+use ethrex_common::H256;
+use ethrex_storage::{Store, error::StoreError};
+use futures::StreamExt;
+use rand::{RngCore, rngs::OsRng};
+use rustc_hash::{FxHashMap, FxHashSet};
+use secp256k1::{PublicKey, SECP256K1, SecretKey};
+use spawned_concurrency::{
+    messages::Unused,
+    tasks::{
+        CastResponse, GenServer, GenServerHandle, InitResult::Success, send_after, send_interval,
+        send_message_on, spawn_listener,
+    },
+};
+use std::{
+    fmt::Debug,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio_util::codec::BytesCodec;
+use tracing::{debug, error, info, trace};
+
+const ORDINARY_FLAG: u8 = 0x00;
+const WHOAREYOU_FLAG: u8 = 0x01;
+const HANDSHAKE_FLAG: u8 = 0x02;
+
+/// Time to wait for a response before considering a request as failed
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+/// Interval between revalidations of the nodes in the table
+const REVALIDATION_INTERVAL: Duration = Duration::from_secs(10);
+/// Interval between lookup steps
+const LOOKUP_INTERVAL: Duration = Duration::from_millis(500);
+/// Interval between checks for timed out requests
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+/// Amount of concurrent FINDNODE requests sent in a lookup step
+const LOOKUP_PARALLELISM: usize = 3;
+/// Amount of nodes queried before a lookup is finished and a new one starts
+const LOOKUP_MAX_QUERIES: usize = 16;
+/// Maximum amount of records sent in a single NODES packet, to stay under the packet size limit
+const MAX_RECORDS_PER_NODES_PACKET: usize = 3;
+/// Maximum amount of distances answered for a single FINDNODE
+const MAX_FIND_NODE_DISTANCES: usize = 3;
+/// Maximum amount of sessions kept, sessions of nodes outside of the table are dropped first
+const MAX_SESSIONS: usize = 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Discv5ServerError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    PacketCodec(#[from] PacketCodecError),
+    #[error(transparent)]
+    Node(#[from] NodeError),
+    #[error("Invalid handshake: {0}")]
+    InvalidHandshake(String),
+    #[error(transparent)]
+    PeerTable(#[from] PeerTableError),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+/// Hook called for incoming TALKREQ messages of a given protocol
+pub trait TalkReqHandler: Debug + Send + Sync {
+    /// Returns the response to the request, an empty response means it wasn't handled
+    fn handle(&self, node_id: H256, request: &[u8]) -> Vec<u8>;
+}
+
+#[derive(Debug, Clone)]
+pub enum InMessage {
+    Packet(BytesMut, SocketAddr),
+    Revalidate,
+    Lookup,
+    Prune,
+    RegisterTalkHandler {
+        protocol: Bytes,
+        handler: Arc<dyn TalkReqHandler>,
+    },
+    /// External address learned by the NAT server, advertised in the local record
+    SetExternalIp(IpAddr),
+    Shutdown,
+}
+
+#[derive(Debug, Clone)]
+pub enum OutMessage {
+    Done,
+}
+
+/// A request waiting for its response
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    node: Node,
+    /// The record of the node, if known, inserted in the table once it responds
+    record: Option<NodeRecord>,
+    message: Message,
+    /// Nonce of the last packet carrying the request, a WHOAREYOU references it
+    nonce: [u8; 12],
+    sent_at: Instant,
+    /// Amount of NODES packets received for a FINDNODE request
+    responses: u64,
+}
+
+/// A WHOAREYOU challenge sent to a node that we couldn't decrypt a packet from
+#[derive(Debug, Clone)]
+struct Challenge {
+    data: Vec<u8>,
+    addr: SocketAddr,
+    enr_seq: u64,
+    sent_at: Instant,
+}
+
+/// State of the current iterative lookup
+#[derive(Debug)]
+struct Lookup {
+    target: H256,
+    asked: FxHashSet<H256>,
+    /// Nodes learned during the lookup that aren't in the table yet
+    candidates: Vec<(NodeRecord, Node)>,
+}
+
+impl Lookup {
+    fn new(target: H256) -> Self {
+        Self {
+            target,
+            asked: Default::default(),
+            candidates: Default::default(),
+        }
+    }
+}
+
+/// Discovery v5 service, it feeds the discovered execution nodes to the same [PeerTable] as discv4.
+/// Reference: https://github.com/ethereum/devp2p/blob/master/discv5/discv5.md
+#[derive(Debug)]
+pub struct Discv5Server {
+    local_node: Node,
+    local_record: LocalNodeRecord,
+    signer: SecretKey,
+    udp_socket: Arc<dyn DatagramSocket>,
+    store: Store,
+    peer_table: PeerTable,
+    table: KademliaTable,
+    bootnodes: Vec<Node>,
+    sessions: FxHashMap<H256, (SocketAddr, Session)>,
+    pending_requests: FxHashMap<Bytes, PendingRequest>,
+    challenges: FxHashMap<H256, Challenge>,
+    lookup: Lookup,
+    talk_handlers: FxHashMap<Bytes, Arc<dyn TalkReqHandler>>,
+    ip_votes: IpVotes,
+    /// Set once the external address is provided by the NAT server, votes are ignored from then on
+    external_ip_pinned: bool,
+    /// Nodes already offered to the peer table
+    reported_nodes: FxHashSet<H256>,
+    nonce_counter: u32,
+}
+
+impl Discv5Server {
+    pub async fn spawn(
+        storage: Store,
+        local_node: Node,
+        local_record: LocalNodeRecord,
+        signer: SecretKey,
+        udp_socket: Arc<dyn DatagramSocket>,
+        peer_table: PeerTable,
+        bootnodes: Vec<Node>,
+    ) -> Result<GenServerHandle<Self>, Discv5ServerError> {
+        info!("Starting Discovery v5 Server");
+        info!(enr = %local_record.get().enr_url()?, "Local node record");
+
+        let local_id = local_node.node_id();
+        let mut server = Self {
+            local_node,
+            local_record,
+            signer,
+            udp_socket,
+            store: storage,
+            peer_table,
+            table: KademliaTable::new(local_id),
+            bootnodes: bootnodes.clone(),
+            sessions: Default::default(),
+            pending_requests: Default::default(),
+            challenges: Default::default(),
+            lookup: Lookup::new(local_id),
+            talk_handlers: Default::default(),
+            ip_votes: Default::default(),
+            external_ip_pinned: false,
+            reported_nodes: Default::default(),
+            nonce_counter: 0,
+        };
+
+        info!(count = bootnodes.len(), "Adding discv5 bootnodes");
+        for bootnode in &bootnodes {
+            server.send_ping(bootnode, None).await?;
+        }
+
+        Ok(server.start())
+    }
+
+    fn local_id(&self) -> H256 {
+        self.local_node.node_id()
+    }
+
+    async fn handle_packet(
+        &mut self,
+        data: BytesMut,
+        from: SocketAddr,
+    ) -> Result<(), Discv5ServerError> {
+        let header = Packet::peek_header(&self.local_id(), &data)?;
+        match header.flag {
+            ORDINARY_FLAG => self.handle_ordinary(&data, header, from).await,
+            WHOAREYOU_FLAG => self.handle_who_are_you(&data, header, from).await,
+            HANDSHAKE_FLAG => self.handle_handshake(&data, header, from).await,
+            flag => {
+                trace!(flag, %from, "Unknown discv5 packet flag, skipping");
+                Ok(())
+            }
+        }
+    }
+
+    async fn handle_ordinary(
+        &mut self,
+        data: &[u8],
+        header: PacketHeader,
+        from: SocketAddr,
+    ) -> Result<(), Discv5ServerError> {
+        let src_id = header
+            .authdata
+            .get(..32)
+            .map(H256::from_slice)
+            .ok_or(PacketCodecError::InvalidSize)?;
+
+        let decoded = match self.sessions.get(&src_id) {
+            Some((addr, session)) if *addr == from => {
+                Packet::decode(&self.local_id(), session.inbound_key(), data).ok()
+            }
+            _ => None,
+        };
+        match decoded {
+            Some(Packet::Ordinary(ordinary)) => {
+                self.handle_message(src_id, from, ordinary.message).await
+            }
+            // We don't have a session with the node or it's stale, ask it to do the handshake
+            _ => self.send_who_are_you(src_id, from, header.nonce).await,
+        }
+    }
+
+    async fn handle_who_are_you(
+        &mut self,
+        data: &[u8],
+        header: PacketHeader,
+        from: SocketAddr,
+    ) -> Result<(), Discv5ServerError> {
+        let who_are_you = WhoAreYou::decode(&header.authdata)?;
+        let Some((req_id, request)) = self
+            .pending_requests
+            .iter()
+            .find(|(_, request)| request.nonce == header.nonce)
+            .map(|(req_id, request)| (req_id.clone(), request.clone()))
+        else {
+            trace!(%from, "Unsolicited WHOAREYOU, skipping");
+            return Ok(());
+        };
+        if request.node.udp_addr() != from {
+            trace!(%from, "WHOAREYOU from unexpected address, skipping");
+            return Ok(());
+        }
+
+        let remote_id = request.node.node_id();
+        let remote_pubkey = compress_pubkey(request.node.public_key).ok_or(
+            Discv5ServerError::InvalidHandshake("invalid remote public key".to_string()),
+        )?;
+        let challenge_data =
+            build_challenge_data(&data[..16], &header.static_header, &header.authdata);
+
+        let ephemeral_key = SecretKey::new(&mut OsRng);
+        let ephemeral_pubkey = PublicKey::from_secret_key(SECP256K1, &ephemeral_key).serialize();
+        let keys = derive_session_keys(
+            &ephemeral_key,
+            &remote_pubkey,
+            &self.local_id(),
+            &remote_id,
+            &challenge_data,
+        );
+        let id_signature =
+            create_id_signature(&self.signer, &challenge_data, &ephemeral_pubkey, &remote_id);
+
+        // The record is only sent if the remote doesn't know the latest one
+        let record =
+            (who_are_you.enr_seq < self.local_record.seq()).then(|| self.local_record.get());
+        let handshake = Packet::Handshake(Handshake {
+            src_id: self.local_id(),
+            id_signature: id_signature.serialize_compact().to_vec(),
+            eph_pubkey: ephemeral_pubkey.to_vec(),
+            record,
+            message: request.message.clone(),
+        });
+
+        let session = Session::new(keys, SessionRole::Initiator);
+        let nonce = self.next_nonce();
+        self.send_packet(handshake, nonce, &remote_id, session.outbound_key(), from)
+            .await?;
+        self.sessions.insert(remote_id, (from, session));
+        if let Some(request) = self.pending_requests.get_mut(&req_id) {
+            request.nonce = nonce;
+        }
+        trace!(to = %remote_id, "Sent discv5 handshake");
+        Ok(())
+    }
+
+    async fn handle_handshake(
+        &mut self,
+        data: &[u8],
+        header: PacketHeader,
+        from: SocketAddr,
+    ) -> Result<(), Discv5ServerError> {
+        let authdata = Handshake::decode_authdata(&header.authdata)?;
+        let src_id = authdata.src_id;
+        let Some(challenge) = self.challenges.remove(&src_id) else {
+            trace!(%from, "Handshake without a challenge, skipping");
+            return Ok(());
+        };
+        if challenge.addr != from {
+            return Err(Discv5ServerError::InvalidHandshake(
+                "address doesn't match the challenge".to_string(),
+            ));
+        }
+
+        // The sender must provide its record unless we already know the latest one
+        let record = match authdata.record {
+            Some(record) if record.verify_signature() && record.seq >= challenge.enr_seq => record,
+            Some(_) => {
+                return Err(Discv5ServerError::InvalidHandshake(
+                    "invalid node record".to_string(),
+                ));
+            }
+            None => self
+                .table
+                .get(&src_id)
+                .map(|entry| entry.record.clone())
+                .ok_or(Discv5ServerError::InvalidHandshake(
+                    "missing node record".to_string(),
+                ))?,
+        };
+        let node = Node::from_enr(&record)?;
+        if node.node_id() != src_id {
+            return Err(Discv5ServerError::InvalidHandshake(
+                "node record doesn't match the sender".to_string(),
+            ));
+        }
+
+        let remote_pubkey = compress_pubkey(node.public_key).ok_or(
+            Discv5ServerError::InvalidHandshake("invalid remote public key".to_string()),
+        )?;
+        if !verify_id_signature(
+            &remote_pubkey,
+            &authdata.id_signature,
+            &challenge.data,
+            &authdata.eph_pubkey,
+            &self.local_id(),
+        ) {
+            return Err(Discv5ServerError::InvalidHandshake(
+                "invalid id signature".to_string(),
+            ));
+        }
+        let ephemeral_pubkey = PublicKey::from_slice(&authdata.eph_pubkey).map_err(|_| {
+            Discv5ServerError::InvalidHandshake("invalid ephemeral public key".to_string())
+        })?;
+
+        let keys = derive_session_keys(
+            &self.signer,
+            &ephemeral_pubkey,
+            &src_id,
+            &self.local_id(),
+            &challenge.data,
+        );
+        let session = Session::new(keys, SessionRole::Recipient);
+        let Packet::Handshake(handshake) =
+            Packet::decode(&self.local_id(), session.inbound_key(), data)?
+        else {
+            return Ok(());
+        };
+        self.sessions.insert(src_id, (from, session));
+        trace!(from = %src_id, "Established discv5 session");
+
+        // The node proved it's reachable at the address it advertises
+        if node.udp_addr() == from {
+            self.add_live_node(record, node).await?;
+        }
+        self.handle_message(src_id, from, handshake.message).await
+    }
+
+    async fn handle_message(
+        &mut self,
+        src_id: H256,
+        from: SocketAddr,
+        message: Message,
+    ) -> Result<(), Discv5ServerError> {
+        match message {
+            Message::Ping(ping) => {
+                trace!(received = "Ping", from = %src_id);
+                let pong = Message::Pong(PongMessage {
+                    req_id: ping.req_id,
+                    enr_seq: self.local_record.seq(),
+                    recipient_addr: from.ip(),
+                    recipient_port: from.port(),
+                });
+                self.send_response(src_id, from, pong).await?;
+                self.request_record_if_stale(src_id, ping.enr_seq).await?;
+            }
+            Message::Pong(pong) => {
+                trace!(received = "Pong", from = %src_id);
+                let Some(request) = self.take_response(&pong.req_id, src_id) else {
+                    return Ok(());
+                };
+                if let Some(record) = request.record {
+                    self.add_live_node(record, request.node).await?;
+                } else if let Some(entry) = self.table.get(&src_id) {
+                    self.table.insert(entry.record.clone(), entry.node.clone());
+                }
+                let stored_seq = self.table.get(&src_id).map(|entry| entry.record.seq);
+                if stored_seq.is_none_or(|seq| pong.enr_seq > seq) {
+                    self.request_record(src_id).await?;
+                }
+                self.record_ip_vote(
+                    src_id,
+                    SocketAddr::new(pong.recipient_addr, pong.recipient_port),
+                );
+            }
+            Message::FindNode(find_node) => {
+                trace!(received = "FindNode", from = %src_id, distances = ?find_node.distance);
+                self.handle_find_node(src_id, from, find_node).await?;
+            }
+            Message::Nodes(nodes) => {
+                trace!(received = "Nodes", from = %src_id, count = nodes.nodes.len());
+                self.handle_nodes(src_id, nodes).await?;
+            }
+            Message::TalkReq(talk_req) => {
+                trace!(received = "TalkReq", from = %src_id);
+                let response = self
+                    .talk_handlers
+                    .get(&talk_req.protocol)
+                    .map(|handler| handler.handle(src_id, &talk_req.request))
+                    .unwrap_or_default();
+                let talk_res = Message::TalkRes(TalkResMessage {
+                    req_id: talk_req.req_id,
+                    response,
+                });
+                self.send_response(src_id, from, talk_res).await?;
+            }
+            Message::TalkRes(talk_res) => {
+                trace!(received = "TalkRes", from = %src_id);
+                self.take_response(&talk_res.req_id, src_id);
+            }
+            Message::Ticket(_) => {
+                trace!(received = "Ticket", from = %src_id, "Topic tickets are not supported");
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_find_node(
+        &mut self,
+        src_id: H256,
+        from: SocketAddr,
+        find_node: FindNodeMessage,
+    ) -> Result<(), Discv5ServerError> {
+        let mut records = Vec::new();
+        for distance in find_node.distance.iter().take(MAX_FIND_NODE_DISTANCES) {
+            if *distance == 0 {
+                records.push(self.local_record.get());
+            } else {
+                records.extend(self.table.nodes_at_distance(*distance));
+            }
+        }
+        records.truncate(BUCKET_SIZE);
+
+        let chunks: Vec<_> = records
+            .chunks(MAX_RECORDS_PER_NODES_PACKET)
+            .map(<[NodeRecord]>::to_vec)
+            .collect();
+        // An empty response is still answered with a single NODES message
+        let total = chunks.len().max(1) as u64;
+        if chunks.is_empty() {
+            let nodes = Message::Nodes(NodesMessage {
+                req_id: find_node.req_id.clone(),
+                total,
+                nodes: vec![],
+            });
+            self.send_response(src_id, from, nodes).await?;
+        }
+        for chunk in chunks {
+            let nodes = Message::Nodes(NodesMessage {
+                req_id: find_node.req_id.clone(),
+                total,
+                nodes: chunk,
+            });
+            self.send_response(src_id, from, nodes).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_nodes(
+        &mut self,
+        src_id: H256,
+        nodes: NodesMessage,
+    ) -> Result<(), Discv5ServerError> {
+        // Responses may be split in several packets, the request is done once all of them arrive
+        let Some(request) = self.pending_requests.get_mut(&nodes.req_id) else {
+            trace!(from = %src_id, "Unsolicited Nodes, skipping");
+            return Ok(());
+        };
+        let Message::FindNode(find_node) = &request.message else {
+            return Ok(());
+        };
+        if request.node.node_id() != src_id {
+            return Ok(());
+        }
+        let distances = find_node.distance.clone();
+        request.responses += 1;
+        if request.responses >= nodes.total {
+            self.pending_requests.remove(&nodes.req_id);
+        }
+
+        for record in nodes.nodes {
+            if !record.verify_signature() {
+                continue;
+            }
+            let Ok(node) = Node::from_enr(&record) else {
+                continue;
+            };
+            let node_id = node.node_id();
+            // Only nodes at the requested distances are accepted
+            if !distances.contains(&log2_distance(&node_id, &src_id)) || node_id == self.local_id()
+            {
+                continue;
+            }
+            if node_id == src_id {
+                // Response to an ENR request
+                self.add_live_node(record, node).await?;
+                continue;
+            }
+            self.offer_to_peer_table(&record, &node).await?;
+            if !self.table.contains(&node_id)
+                && !self
+                    .lookup
+                    .candidates
+                    .iter()
+                    .any(|(_, n)| n.node_id() == node_id)
+            {
+                self.lookup.candidates.push((record, node));
+            }
+        }
+        let target = self.lookup.target;
+        self.lookup
+            .candidates
+            .sort_by_key(|(_, node)| node.node_id() ^ target);
+        self.lookup.candidates.truncate(BUCKET_SIZE * 4);
+        Ok(())
+    }
+
+    /// Adds a node that proved to be alive to the table
+    async fn add_live_node(
+        &mut self,
+        record: NodeRecord,
+        node: Node,
+    ) -> Result<(), Discv5ServerError> {
+        let node_id = node.node_id();
+        if self.table.insert(record.clone(), node.clone()) == InsertResult::Inserted {
+            debug!(node = %node_id, table_size = self.table.len(), "New discv5 node");
+        }
+        self.lookup
+            .candidates
+            .retain(|(_, n)| n.node_id() != node_id);
+        self.offer_to_peer_table(&record, &node).await
+    }
+
+    /// Adds the node to the peer table if it's an execution node on our chain.
+    /// Consensus layer nodes don't advertise the `eth` entry and are skipped.
+    async fn offer_to_peer_table(
+        &mut self,
+        record: &NodeRecord,
+        node: &Node,
+    ) -> Result<(), Discv5ServerError> {
+        let node_id = node.node_id();
+        if self.reported_nodes.contains(&node_id) {
+            return Ok(());
+        }
+        let Some(remote_fork_id) = record.decode_pairs().eth else {
+            return Ok(());
+        };
+        self.reported_nodes.insert(node_id);
+        let (is_valid, _) = validate_fork_id(&self.store, remote_fork_id).await?;
+        if !is_valid {
+            return Ok(());
+        }
+        self.peer_table
+            .new_contacts(vec![node.clone()], self.local_id())
+            .await?;
+        self.peer_table.set_is_fork_id_valid(&node_id, true).await?;
+        Ok(())
+    }
+
+    async fn request_record_if_stale(
+        &mut self,
+        node_id: H256,
+        enr_seq: u64,
+    ) -> Result<(), Discv5ServerError> {
+        match self.table.get(&node_id) {
+            Some(entry) if entry.record.seq < enr_seq => self.request_record(node_id).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Requests the latest record of a node, a FINDNODE for distance 0
+    async fn request_record(&mut self, node_id: H256) -> Result<(), Discv5ServerError> {
+        let node = match self.table.get(&node_id) {
+            Some(entry) => entry.node.clone(),
+            None => match self.bootnodes.iter().find(|n| n.node_id() == node_id) {
+                Some(node) => node.clone(),
+                None => return Ok(()),
+            },
+        };
+        let find_node = Message::FindNode(FindNodeMessage {
+            req_id: random_request_id(),
+            distance: vec![0],
+        });
+        self.send_request(&node, None, find_node).await
+    }
+
+    async fn send_ping(
+        &mut self,
+        node: &Node,
+        record: Option<NodeRecord>,
+    ) -> Result<(), Discv5ServerError> {
+        let ping = Message::Ping(PingMessage::new(
+            random_request_id(),
+            self.local_record.seq(),
+        ));
+        self.send_request(node, record, ping).await
+    }
+
+    async fn send_who_are_you(
+        &mut self,
+        src_id: H256,
+        from: SocketAddr,
+        nonce: [u8; 12],
+    ) -> Result<(), Discv5ServerError> {
+        // Don't challenge the same node again while a handshake is in progress
+        if self
+            .challenges
+            .get(&src_id)
+            .is_some_and(|challenge| challenge.sent_at.elapsed() < REQUEST_TIMEOUT)
+        {
+            return Ok(());
+        }
+        let enr_seq = self
+            .table
+            .get(&src_id)
+            .map(|entry| entry.record.seq)
+            .unwrap_or_default();
+        let who_are_you = Packet::WhoAreYou(WhoAreYou {
+            id_nonce: rand::random(),
+            enr_seq,
+        });
+
+        let mut buf = BytesMut::new();
+        who_are_you.encode(&mut buf, rand::random(), &nonce, &src_id, &[])?;
+        // challenge-data = masking-iv || static-header || authdata, read back from the packet
+        let header = Packet::peek_header(&src_id, &buf)?;
+        let data = build_challenge_data(&buf[..16], &header.static_header, &header.authdata);
+        self.udp_socket.send_to(&buf, from).await?;
+
+        self.challenges.insert(
+            src_id,
+            Challenge {
+                data,
+                addr: from,
+                enr_seq,
+                sent_at: Instant::now(),
+            },
+        );
+        trace!(to = %src_id, "Sent WHOAREYOU");
+        Ok(())
+    }
+
+    /// Sends a request, tracking it until its response arrives.
+    /// Without a session the packet is sent with a random key, the remote then answers with a
+    /// WHOAREYOU and the request is sent again inside the handshake.
+    async fn send_request(
+        &mut self,
+        node: &Node,
+        record: Option<NodeRecord>,
+        message: Message,
+    ) -> Result<(), Discv5ServerError> {
+        let req_id = match &message {
+            Message::Ping(ping) => ping.req_id.clone(),
+            Message::FindNode(find_node) => find_node.req_id.clone(),
+            Message::TalkReq(talk_req) => talk_req.req_id.clone(),
+            _ => return Ok(()),
+        };
+        let node_id = node.node_id();
+        let addr = node.udp_addr();
+        let key = match self.sessions.get(&node_id) {
+            Some((session_addr, session)) if *session_addr == addr => *session.outbound_key(),
+            _ => rand::random(),
+        };
+        let nonce = self.next_nonce();
+        let packet = Packet::Ordinary(Ordinary {
+            src_id: self.local_id(),
+            message: message.clone(),
+        });
+        if let Err(err) = self.send_packet(packet, nonce, &node_id, &key, addr).await {
+            debug!(to = %node_id, %err, "Failed to send discv5 request");
+            return Ok(());
+        }
+        self.pending_requests.insert(
+            req_id,
+            PendingRequest {
+                node: node.clone(),
+                record,
+                message,
+                nonce,
+                sent_at: Instant::now(),
+                responses: 0,
+            },
+        );
+        Ok(())
+    }
+
+    async fn send_response(
+        &mut self,
+        node_id: H256,
+        addr: SocketAddr,
+        message: Message,
+    ) -> Result<(), Discv5ServerError> {
+        let Some(key) = self
+            .sessions
+            .get(&node_id)
+            .map(|(_, session)| *session.outbound_key())
+        else {
+            return Ok(());
+        };
+        let nonce = self.next_nonce();
+        let packet = Packet::Ordinary(Ordinary {
+            src_id: self.local_id(),
+            message,
+        });
+        self.send_packet(packet, nonce, &node_id, &key, addr).await
+    }
+
+    async fn send_packet(
+        &self,
+        packet: Packet,
+        nonce: [u8; 12],
+        dest_id: &H256,
+        key: &[u8],
+        addr: SocketAddr,
+    ) -> Result<(), Discv5ServerError> {
+        let mut buf = BytesMut::new();
+        packet.encode(&mut buf, rand::random(), &nonce, dest_id, key)?;
+        self.udp_socket.send_to(&buf, addr).await?;
+        Ok(())
+    }
+
+    /// Removes and returns the pending request answered by the given response
+    fn take_response(&mut self, req_id: &Bytes, src_id: H256) -> Option<PendingRequest> {
+        let request = self.pending_requests.get(req_id)?;
+        if request.node.node_id() != src_id {
+            return None;
+        }
+        self.pending_requests.remove(req_id)
+    }
+
+    /// Generates a 96-bit AES-GCM nonce, the outgoing message count followed by random data
+    fn next_nonce(&mut self) -> [u8; 12] {
+        let counter = self.nonce_counter;
+        self.nonce_counter = self.nonce_counter.wrapping_add(1);
+        let mut nonce = [0u8; 12];
+        nonce[..4].copy_from_slice(&counter.to_be_bytes());
+        OsRng.fill_bytes(&mut nonce[4..]);
+        nonce
+    }
+
+    /// Updates the local record once enough nodes agree on a new external address
+    fn record_ip_vote(&mut self, voter: H256, addr: SocketAddr) {
+        if self.external_ip_pinned {
+            return;
+        }
+        if let Some(addr) = self.ip_votes.vote(voter, addr, self.local_node.udp_addr()) {
+            self.update_local_address(addr);
+        }
+    }
+
+    /// Re-signs the local record with the given external address and a bumped sequence number,
+    /// unless discv4 already did
+    fn update_local_address(&mut self, addr: SocketAddr) {
+        let mut local_node = self.local_node.clone();
+        local_node.ip = addr.ip();
+        local_node.udp_port = addr.port();
+        match self.local_record.set_address(&local_node, &self.signer) {
+            Ok(record) => {
+                if let Some(record) = record {
+                    info!(%addr, seq = record.seq, "Updated local node record with external address");
+                }
+                self.local_node = local_node;
+                self.ip_votes.clear();
+            }
+            Err(err) => error!(%err, "Failed to update local node record"),
+        }
+    }
+
+    async fn lookup(&mut self) -> Result<(), Discv5ServerError> {
+        if self.table.is_empty() && self.pending_requests.is_empty() {
+            for bootnode in self.bootnodes.clone() {
+                self.send_ping(&bootnode, None).await?;
+            }
+        }
+
+        let target = self.lookup.target;
+        let mut candidates: Vec<(Option<NodeRecord>, Node)> = self
+            .table
+            .closest(&target, BUCKET_SIZE)
+            .into_iter()
+            .map(|entry| (None, entry.node))
+            .chain(
+                self.lookup
+                    .candidates
+                    .iter()
+                    .map(|(record, node)| (Some(record.clone()), node.clone())),
+            )
+            .filter(|(_, node)| !self.lookup.asked.contains(&node.node_id()))
+            .collect();
+        candidates.sort_by_key(|(_, node)| node.node_id() ^ target);
+        candidates.truncate(LOOKUP_PARALLELISM);
+
+        if candidates.is_empty() || self.lookup.asked.len() >= LOOKUP_MAX_QUERIES {
+            let mut target = H256::zero();
+            OsRng.fill_bytes(target.as_bytes_mut());
+            trace!(%target, "Starting new discv5 lookup");
+            self.lookup = Lookup::new(target);
+            return Ok(());
+        }
+
+        for (record, node) in candidates {
+            self.lookup.asked.insert(node.node_id());
+            let distance = log2_distance(&target, &node.node_id());
+            // Ask for the distance of the target and its neighbours in case that bucket is empty
+            let distances = [distance, distance + 1, distance.saturating_sub(1)]
+                .into_iter()
+                .filter(|d| (1..=256).contains(d))
+                .collect();
+            let find_node = Message::FindNode(FindNodeMessage {
+                req_id: random_request_id(),
+                distance: distances,
+            });
+            self.send_request(&node, record, find_node).await?;
+        }
+        Ok(())
+    }
+
+    async fn revalidate(&mut self) -> Result<(), Discv5ServerError> {
+        if let Some(entry) = self.table.revalidation_candidate() {
+            self.send_ping(&entry.node, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Drops timed out requests, challenges and excess sessions.
+    /// Nodes in the table that didn't answer a PING are removed.
+    fn prune(&mut self) {
+        let expired: Vec<_> = self
+            .pending_requests
+            .iter()
+            .filter(|(_, request)| request.sent_at.elapsed() > REQUEST_TIMEOUT)
+            .map(|(req_id, _)| req_id.clone())
+            .collect();
+        for req_id in expired {
+            let Some(request) = self.pending_requests.remove(&req_id) else {
+                continue;
+            };
+            if matches!(request.message, Message::Ping(_)) && request.responses == 0 {
+                let node_id = request.node.node_id();
+                if self.table.remove(&node_id) {
+                    debug!(node = %node_id, "Removed unresponsive discv5 node");
+                }
+                self.sessions.remove(&node_id);
+            }
+        }
+        self.challenges
+            .retain(|_, challenge| challenge.sent_at.elapsed() <= REQUEST_TIMEOUT);
+
+        if self.sessions.len() > MAX_SESSIONS {
+            let table = &self.table;
+            self.sessions.retain(|node_id, _| table.contains(node_id));
+        }
+    }
+}
+
+fn random_request_id() -> Bytes {
+    Bytes::copy_from_slice(&rand::random::<u64>().to_be_bytes())
+}
+
+impl GenServer for Discv5Server {
+    type CallMsg = Unused;
+    type CastMsg = InMessage;
+    type OutMsg = OutMessage;
+    type Error = Discv5ServerError;
+
+    async fn init(
+        self,
+        handle: &GenServerHandle<Self>,
+    ) -> Result<spawned_concurrency::tasks::InitResult<Self>, Self::Error> {
+        let stream = datagram_stream(self.udp_socket.clone(), BytesCodec::new());
+
+        spawn_listener(
+            handle.clone(),
+            stream.filter_map(|result| async move {
+                match result {
+                    Ok((data, addr)) => Some(InMessage::Packet(data, addr)),
+                    Err(e) => {
+                        debug!(error=?e, "Error receiving Discv5 packet");
+                        None
+                    }
+                }
+            }),
+        );
+        send_interval(REVALIDATION_INTERVAL, handle.clone(), InMessage::Revalidate);
+        send_interval(PRUNE_INTERVAL, handle.clone(), InMessage::Prune);
+        let _ = handle.clone().cast(InMessage::Lookup).await;
+        send_message_on(handle.clone(), tokio::signal::ctrl_c(), InMessage::Shutdown);
+
+        Ok(Success(self))
+    }
+
+    async fn handle_cast(
+        &mut self,
+        message: Self::CastMsg,
+        handle: &GenServerHandle<Self>,
+    ) -> CastResponse {
+        match message {
+            Self::CastMsg::Packet(data, from) => {
+                let _ = self
+                    .handle_packet(data, from)
+                    .await
+                    .inspect_err(|e| debug!(err=?e, %from, "Error handling discv5 packet"));
+            }
+            Self::CastMsg::Revalidate => {
+                let _ = self
+                    .revalidate()
+                    .await
+                    .inspect_err(|e| error!(err=?e, "Error revalidating discv5 nodes"));
+            }
+            Self::CastMsg::Lookup => {
+                let _ = self
+                    .lookup()
+                    .await
+                    .inspect_err(|e| error!(err=?e, "Error performing discv5 lookup"));
+                send_after(LOOKUP_INTERVAL, handle.clone(), Self::CastMsg::Lookup);
+            }
+            Self::CastMsg::Prune => self.prune(),
+            Self::CastMsg::RegisterTalkHandler { protocol, handler } => {
+                self.talk_handlers.insert(protocol, handler);
+            }
+            Self::CastMsg::SetExternalIp(ip) => {
+                self.external_ip_pinned = true;
+                if ip != self.local_node.ip {
+                    self.update_local_address(SocketAddr::new(ip, self.local_node.udp_port));
+                }
+            }
+            Self::CastMsg::Shutdown => return CastResponse::Stop,
+        }
+        CastResponse::NoReply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        discv4::server::DiscoveryServer, transport::split_discovery_socket,
+        utils::public_key_from_signing_key,
+    };
+    use ethrex_storage::EngineType;
+    use std::net::Ipv4Addr;
+    use tokio::net::UdpSocket;
+
+    const TEST_GENESIS: &str = include_str!("../../../../fixtures/genesis/l1.json");
+
+    struct TestNode {
+        store: Store,
+        node: Node,
+        record: LocalNodeRecord,
+        signer: SecretKey,
+        socket: Arc<dyn DatagramSocket>,
+        peer_table: PeerTable,
+    }
+
+    async fn test_node(seed: u8) -> TestNode {
+        let mut store = Store::new("", EngineType::InMemory).unwrap();
+        store
+            .add_initial_state(serde_json::from_str(TEST_GENESIS).unwrap())
+            .await
+            .unwrap();
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let signer = SecretKey::from_slice(&[seed; 32]).unwrap();
+        let node = Node::new(
+            Ipv4Addr::LOCALHOST.into(),
+            port,
+            port,
+            public_key_from_signing_key(&signer),
+        );
+        let record = LocalNodeRecord::new(&node, &signer, store.get_fork_id().await.ok()).unwrap();
+        TestNode {
+            store,
+            node,
+            record,
+            signer,
+            socket: Arc::new(socket),
+            peer_table: PeerTable::spawn(10, Vec::new()),
+        }
+    }
+
+    async fn spawn_discv5(node: &TestNode, bootnodes: Vec<Node>) -> GenServerHandle<Discv5Server> {
+        Discv5Server::spawn(
+            node.store.clone(),
+            node.node.clone(),
+            node.record.clone(),
+            node.signer,
+            node.socket.clone(),
+            node.peer_table.clone(),
+            bootnodes,
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Waits until `peer` is in the peer table of `node`, for at most 5 seconds
+    async fn wait_for_contact(node: &TestNode, peer: &Node) -> bool {
+        let mut peer_table = node.peer_table.clone();
+        for _ in 0..50 {
+            if peer_table
+                .get_contact(peer.node_id())
+                .await
+                .unwrap()
+                .is_some()
+            {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn bootnode_is_added_to_the_peer_table() {
+        let bootnode = test_node(1).await;
+        let node = test_node(2).await;
+        spawn_discv5(&bootnode, Vec::new()).await;
+        spawn_discv5(&node, vec![bootnode.node.clone()]).await;
+
+        // The handshake gives each side the record of the other
+        assert!(wait_for_contact(&node, &bootnode.node).await);
+        assert!(wait_for_contact(&bootnode, &node.node).await);
+    }
+
+    #[tokio::test]
+    async fn nodes_on_another_chain_are_not_added_to_the_peer_table() {
+        let bootnode = test_node(1).await;
+        let node = test_node(2).await;
+        // Same chain config, but the fork id advertised by the bootnode is from another genesis
+        let mut fork_id = bootnode.store.get_fork_id().await.unwrap();
+        fork_id.fork_hash.0[0] ^= 0xff;
+        let record = LocalNodeRecord::new(&bootnode.node, &bootnode.signer, Some(fork_id)).unwrap();
+        let bootnode = TestNode { record, ..bootnode };
+        spawn_discv5(&bootnode, Vec::new()).await;
+        spawn_discv5(&node, vec![bootnode.node.clone()]).await;
+
+        assert!(wait_for_contact(&bootnode, &node.node).await);
+        // Give the node time to get the record of the bootnode
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let mut peer_table = node.peer_table.clone();
+        assert!(
+            peer_table
+                .get_contact(bootnode.node.node_id())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn external_ip_updates_the_shared_record() {
+        let node = test_node(1).await;
+        let seq = node.record.seq();
+        let mut handle = spawn_discv5(&node, Vec::new()).await;
+        let external_ip = IpAddr::from(Ipv4Addr::new(203, 0, 113, 1));
+        handle
+            .cast(InMessage::SetExternalIp(external_ip))
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            if node.record.seq() > seq {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let record = node.record.get();
+        assert_eq!(record.seq, seq + 1);
+        assert_eq!(
+            record.decode_pairs().ip.map(IpAddr::from),
+            Some(external_ip)
+        );
+        // The fork id survives the update
+        assert_eq!(
+            record.decode_pairs().eth,
+            Some(node.store.get_fork_id().await.unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn discv4_and_discv5_share_the_socket() {
+        let bootnode = test_node(1).await;
+        let node = test_node(2).await;
+        for (test_node, bootnodes) in [(&bootnode, vec![]), (&node, vec![bootnode.node.clone()])] {
+            let (discv4_socket, discv5_socket) = split_discovery_socket(test_node.socket.clone());
+            DiscoveryServer::spawn(
+                test_node.store.clone(),
+                test_node.node.clone(),
+                test_node.record.clone(),
+                test_node.signer,
+                discv4_socket,
+                test_node.peer_table.clone(),
+                bootnodes.clone(),
+                1000.0,
+            )
+            .await
+            .unwrap();
+            Discv5Server::spawn(
+                test_node.store.clone(),
+                test_node.node.clone(),
+                test_node.record.clone(),
+                test_node.signer,
+                discv5_socket,
+                test_node.peer_table.clone(),
+                bootnodes,
+            )
+            .await
+            .unwrap();
+        }
+
+        assert!(wait_for_contact(&node, &bootnode.node).await);
+        assert!(wait_for_contact(&bootnode, &node.node).await);
+    }
+}
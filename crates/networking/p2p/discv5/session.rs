@@ -102,6 +102,29 @@ pub fn create_id_signature(
     SECP256K1.sign_ecdsa(&message, static_key)
 }
 
+/// Verifies the id-signature of a handshake against the public key of its sender
+pub fn verify_id_signature(
+    pubkey: &PublicKey,
+    signature: &[u8],
+    challenge_data: &[u8],
+    ephemeral_pubkey: &[u8],
+    node_id_b: &H256,
+) -> bool {
+    let Ok(signature) = Signature::from_compact(signature) else {
+        return false;
+    };
+    let mut id_signature_input = b"discovery v5 identity proof".to_vec();
+    id_signature_input.extend_from_slice(challenge_data);
+    id_signature_input.extend_from_slice(ephemeral_pubkey);
+    id_signature_input.extend_from_slice(node_id_b.as_bytes());
+
+    let digest = Sha256::digest(&id_signature_input);
+    let Ok(message) = SecpMessage::from_digest_slice(&digest) else {
+        return false;
+    };
+    SECP256K1.verify_ecdsa(&message, &signature, pubkey).is_ok()
+}
+
 /// Creates a secret through elliptic-curve Diffie-Hellman key agreement
 ///
 /// ecdh(pubkey, privkey) from the spec
@@ -180,6 +203,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn id_signature_verification() {
+        let static_key = SecretKey::from_byte_array(&hex!(
+            "fb757dc581730490a1d7a00deea65e9b1936924caaea8f44d476014856b68736"
+        ))
+        .unwrap();
+        let pubkey = PublicKey::from_secret_key(SECP256K1, &static_key);
+        let challenge_data = [1u8; 63];
+        let ephemeral_pubkey = [2u8; 33];
+        let node_id_b = H256::repeat_byte(3);
+
+        let signature =
+            create_id_signature(&static_key, &challenge_data, &ephemeral_pubkey, &node_id_b)
+                .serialize_compact();
+        assert!(verify_id_signature(
+            &pubkey,
+            &signature,
+            &challenge_data,
+            &ephemeral_pubkey,
+            &node_id_b
+        ));
+        assert!(!verify_id_signature(
+            &pubkey,
+            &signature,
+            &challenge_data,
+            &ephemeral_pubkey,
+            &H256::repeat_byte(4)
+        ));
+    }
+
     #[test]
     fn test_next_nonce_counter() {
         let mut codec = Discv5Codec::new(H256::zero());
@@ -96,6 +96,14 @@ pub struct Metrics {
     pub bytecode_download_start_time: Arc<Mutex<Option<SystemTime>>>,
     pub bytecode_download_end_time: Arc<Mutex<Option<SystemTime>>>,
 
+    /* Transaction propagation */
+    /// Announcements of transactions we already had or were already fetching.
+    pub duplicate_tx_announcements: IntCounter,
+    /// Announced transactions that no announcer delivered.
+    pub unfetched_tx_announcements: IntCounter,
+    /// Announced transactions ignored because too many were already being tracked.
+    pub dropped_tx_announcements: IntCounter,
+
     start_time: SystemTime,
 }
 
@@ -675,6 +683,36 @@ impl Default for Metrics {
             .register(Box::new(storage_leaves_downloaded.clone()))
             .expect("Failed to register storage_leaves_downloaded counter");
 
+        let duplicate_tx_announcements = IntCounter::new(
+            "duplicate_tx_announcements",
+            "Total number of announced transactions that were already known or being fetched",
+        )
+        .expect("Failed to create duplicate_tx_announcements counter");
+
+        registry
+            .register(Box::new(duplicate_tx_announcements.clone()))
+            .expect("Failed to register duplicate_tx_announcements counter");
+
+        let unfetched_tx_announcements = IntCounter::new(
+            "unfetched_tx_announcements",
+            "Total number of announced transactions that couldn't be fetched from any announcer",
+        )
+        .expect("Failed to create unfetched_tx_announcements counter");
+
+        registry
+            .register(Box::new(unfetched_tx_announcements.clone()))
+            .expect("Failed to register unfetched_tx_announcements counter");
+
+        let dropped_tx_announcements = IntCounter::new(
+            "dropped_tx_announcements",
+            "Total number of announced transactions ignored because the fetcher was tracking too many",
+        )
+        .expect("Failed to create dropped_tx_announcements counter");
+
+        registry
+            .register(Box::new(dropped_tx_announcements.clone()))
+            .expect("Failed to register dropped_tx_announcements counter");
+
         Metrics {
             _registry: registry,
             enabled: Arc::new(Mutex::new(false)),
@@ -749,6 +787,11 @@ impl Default for Metrics {
             bytecode_download_start_time: Arc::new(Mutex::new(None)),
             bytecode_download_end_time: Arc::new(Mutex::new(None)),
 
+            // Transaction propagation
+            duplicate_tx_announcements,
+            unfetched_tx_announcements,
+            dropped_tx_announcements,
+
             start_time: SystemTime::now(),
         }
     }
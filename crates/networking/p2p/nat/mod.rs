@@ -0,0 +1,257 @@
+pub mod pmp;
+pub mod server;
+pub mod upnp;
+
+use ethrex_common::H256;
+use rustc_hash::FxHashMap;
+use std::{
+    fmt::Display,
+    io,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    time::Duration,
+};
+use tokio::net::UdpSocket;
+
+use pmp::PmpGateway;
+use upnp::UpnpGateway;
+
+/// Description attached to the port mappings created on the gateway
+pub const PORT_MAPPING_DESCRIPTION: &str = "ethrex";
+/// Amount of nodes that must agree on our external address before updating the local record
+const IP_VOTE_THRESHOLD: usize = 10;
+const MAX_IP_VOTES: usize = 200;
+
+/// How the node learns and advertises its external address when running behind a NAT
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NatMode {
+    /// Advertise the local address, only learning the external one from other nodes
+    #[default]
+    None,
+    /// Advertise the given external address
+    ExtIp(IpAddr),
+    /// Map the p2p ports and query the external address through UPnP IGD
+    Upnp,
+    /// Map the p2p ports and query the external address through NAT-PMP
+    Pmp,
+    /// Try UPnP first and fall back to NAT-PMP
+    Auto,
+}
+
+impl NatMode {
+    /// Returns true if the mode requires finding a gateway on the local network
+    pub fn uses_gateway(&self) -> bool {
+        matches!(self, NatMode::Upnp | NatMode::Pmp | NatMode::Auto)
+    }
+}
+
+impl Display for NatMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NatMode::None => write!(f, "none"),
+            NatMode::ExtIp(ip) => write!(f, "extip:{ip}"),
+            NatMode::Upnp => write!(f, "upnp"),
+            NatMode::Pmp => write!(f, "pmp"),
+            NatMode::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl FromStr for NatMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowercase = s.to_lowercase();
+        if let Some(ip) = lowercase.strip_prefix("extip:") {
+            return ip
+                .parse()
+                .map(NatMode::ExtIp)
+                .map_err(|_| format!("Invalid external ip '{ip}'"));
+        }
+        match lowercase.as_str() {
+            "none" => Ok(NatMode::None),
+            "upnp" => Ok(NatMode::Upnp),
+            "pmp" => Ok(NatMode::Pmp),
+            "auto" => Ok(NatMode::Auto),
+            _ => Err(format!(
+                "Invalid nat mode '{s}'. Expected: none, extip:<ip>, upnp, pmp, or auto"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NatError {
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+    #[error("No gateway found")]
+    GatewayNotFound,
+    #[error("Gateway did not respond in time")]
+    Timeout,
+    #[error("Invalid gateway response: {0}")]
+    InvalidResponse(String),
+    #[error("Gateway returned an error: {0}")]
+    GatewayError(String),
+}
+
+/// Transport protocol of a port mapping
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protocol::Tcp => write!(f, "TCP"),
+            Protocol::Udp => write!(f, "UDP"),
+        }
+    }
+}
+
+/// A local port which is exposed on the same external port of the gateway
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PortMapping {
+    pub protocol: Protocol,
+    pub port: u16,
+}
+
+/// A NAT gateway able to map ports and report the external address
+#[derive(Clone, Debug)]
+pub enum Gateway {
+    Upnp(UpnpGateway),
+    Pmp(PmpGateway),
+}
+
+impl Gateway {
+    /// Searches a gateway on the local network using the protocols allowed by the given mode
+    pub async fn discover(mode: NatMode) -> Result<Gateway, NatError> {
+        match mode {
+            NatMode::Upnp => Ok(Gateway::Upnp(UpnpGateway::search().await?)),
+            NatMode::Pmp => Ok(Gateway::Pmp(PmpGateway::search().await?)),
+            NatMode::Auto => match UpnpGateway::search().await {
+                Ok(gateway) => Ok(Gateway::Upnp(gateway)),
+                Err(_) => Ok(Gateway::Pmp(PmpGateway::search().await?)),
+            },
+            NatMode::None | NatMode::ExtIp(_) => Err(NatError::GatewayNotFound),
+        }
+    }
+
+    pub async fn external_ip(&self) -> Result<IpAddr, NatError> {
+        match self {
+            Gateway::Upnp(gateway) => gateway.external_ip().await,
+            Gateway::Pmp(gateway) => gateway.external_ip().await.map(IpAddr::V4),
+        }
+    }
+
+    /// Maps the port for the given lifetime, after which the gateway drops it
+    pub async fn add_mapping(
+        &self,
+        mapping: PortMapping,
+        lifetime: Duration,
+    ) -> Result<(), NatError> {
+        match self {
+            Gateway::Upnp(gateway) => gateway.add_mapping(mapping, lifetime).await,
+            Gateway::Pmp(gateway) => gateway.add_mapping(mapping, lifetime).await,
+        }
+    }
+
+    pub async fn remove_mapping(&self, mapping: PortMapping) -> Result<(), NatError> {
+        match self {
+            Gateway::Upnp(gateway) => gateway.remove_mapping(mapping).await,
+            Gateway::Pmp(gateway) => gateway.remove_mapping(mapping).await,
+        }
+    }
+}
+
+/// External address of the local node as reported by the PONGs of other nodes
+#[derive(Debug, Default)]
+pub(crate) struct IpVotes {
+    votes: FxHashMap<H256, SocketAddr>,
+}
+
+impl IpVotes {
+    /// Records the address seen by the voter, returning it once enough nodes agree on an
+    /// address different from the current one
+    pub(crate) fn vote(
+        &mut self,
+        voter: H256,
+        addr: SocketAddr,
+        current: SocketAddr,
+    ) -> Option<SocketAddr> {
+        if self.votes.len() >= MAX_IP_VOTES {
+            self.votes.clear();
+        }
+        self.votes.insert(voter, addr);
+        if addr == current {
+            return None;
+        }
+        let votes = self.votes.values().filter(|vote| **vote == addr).count();
+        (votes >= IP_VOTE_THRESHOLD).then_some(addr)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.votes.clear();
+    }
+}
+
+/// Returns the local address used to reach the given gateway, which is the one ports are mapped to
+pub(crate) async fn local_ip_towards(gateway: SocketAddr) -> Result<IpAddr, NatError> {
+    let bind_addr: SocketAddr = if gateway.is_ipv4() {
+        (std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(bind_addr).await?;
+    socket.connect(gateway).await?;
+    Ok(socket.local_addr()?.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_votes_reach_threshold() {
+        let current: SocketAddr = "10.0.0.1:30303".parse().unwrap();
+        let external: SocketAddr = "203.0.113.7:30303".parse().unwrap();
+        let mut votes = IpVotes::default();
+        for voter in 0..IP_VOTE_THRESHOLD - 1 {
+            let voter = H256::from_low_u64_be(voter as u64);
+            assert_eq!(votes.vote(voter, external, current), None);
+            // Votes for the current address never trigger an update
+            assert_eq!(votes.vote(H256::repeat_byte(0xff), current, current), None);
+        }
+        // Repeated votes from the same node only count once
+        assert_eq!(votes.vote(H256::zero(), external, current), None);
+        assert_eq!(
+            votes.vote(H256::repeat_byte(0xee), external, current),
+            Some(external)
+        );
+    }
+
+    #[test]
+    fn parse_nat_mode() {
+        assert_eq!("none".parse::<NatMode>(), Ok(NatMode::None));
+        assert_eq!("UPnP".parse::<NatMode>(), Ok(NatMode::Upnp));
+        assert_eq!("pmp".parse::<NatMode>(), Ok(NatMode::Pmp));
+        assert_eq!("auto".parse::<NatMode>(), Ok(NatMode::Auto));
+        assert_eq!(
+            "extip:1.2.3.4".parse::<NatMode>(),
+            Ok(NatMode::ExtIp("1.2.3.4".parse().unwrap()))
+        );
+        assert!("extip:foo".parse::<NatMode>().is_err());
+        assert!("stun".parse::<NatMode>().is_err());
+
+        for mode in [
+            NatMode::None,
+            NatMode::ExtIp("::1".parse().unwrap()),
+            NatMode::Upnp,
+            NatMode::Pmp,
+            NatMode::Auto,
+        ] {
+            assert_eq!(mode.to_string().parse::<NatMode>(), Ok(mode));
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! NAT-PMP client, see https://datatracker.ietf.org/doc/html/rfc6886
+
+use super::{NatError, PortMapping, Protocol};
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::net::UdpSocket;
+
+/// Port the gateway listens on for NAT-PMP requests
+pub const NAT_PMP_PORT: u16 = 5351;
+const NAT_PMP_VERSION: u8 = 0;
+const OP_EXTERNAL_ADDRESS: u8 = 0;
+const OP_MAP_UDP: u8 = 1;
+const OP_MAP_TCP: u8 = 2;
+/// Responses have the opcode of the request plus 128
+const RESPONSE_OFFSET: u8 = 128;
+/// Time to wait for the first response, doubled on each retry as the RFC recommends
+const INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+const MAX_ATTEMPTS: u32 = 4;
+
+#[derive(Clone, Debug)]
+pub struct PmpGateway {
+    addr: SocketAddr,
+}
+
+impl PmpGateway {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+
+    /// Uses the default gateway of the host, checking that it answers NAT-PMP requests
+    pub async fn search() -> Result<Self, NatError> {
+        let gateway_ip = default_gateway().ok_or(NatError::GatewayNotFound)?;
+        let gateway = Self::new(SocketAddr::new(gateway_ip.into(), NAT_PMP_PORT));
+        gateway.external_ip().await?;
+        Ok(gateway)
+    }
+
+    pub async fn external_ip(&self) -> Result<Ipv4Addr, NatError> {
+        let response = self
+            .request(
+                &[NAT_PMP_VERSION, OP_EXTERNAL_ADDRESS],
+                OP_EXTERNAL_ADDRESS,
+                12,
+            )
+            .await?;
+        Ok(Ipv4Addr::new(
+            response[8],
+            response[9],
+            response[10],
+            response[11],
+        ))
+    }
+
+    /// Requests the same external port as the local one. The gateway may assign another one, in
+    /// which case the mapping is removed, as node records advertise a single port per protocol.
+    pub async fn add_mapping(
+        &self,
+        mapping: PortMapping,
+        lifetime: Duration,
+    ) -> Result<(), NatError> {
+        let lifetime = u32::try_from(lifetime.as_secs()).unwrap_or(u32::MAX);
+        let external_port = self.map(mapping, mapping.port, lifetime).await?;
+        if external_port != mapping.port {
+            self.remove_mapping(mapping).await?;
+            return Err(NatError::GatewayError(format!(
+                "{} port {} was mapped to external port {external_port}",
+                mapping.protocol, mapping.port
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn remove_mapping(&self, mapping: PortMapping) -> Result<(), NatError> {
+        // A mapping is deleted by requesting it with a lifetime and external port of zero
+        self.map(mapping, 0, 0).await.map(|_| ())
+    }
+
+    /// Sends a mapping request and returns the external port assigned by the gateway
+    async fn map(
+        &self,
+        mapping: PortMapping,
+        external_port: u16,
+        lifetime: u32,
+    ) -> Result<u16, NatError> {
+        let op = match mapping.protocol {
+            Protocol::Udp => OP_MAP_UDP,
+            Protocol::Tcp => OP_MAP_TCP,
+        };
+        let mut request = vec![NAT_PMP_VERSION, op, 0, 0];
+        request.extend_from_slice(&mapping.port.to_be_bytes());
+        request.extend_from_slice(&external_port.to_be_bytes());
+        request.extend_from_slice(&lifetime.to_be_bytes());
+
+        let response = self.request(&request, op, 16).await?;
+        let internal_port = u16::from_be_bytes([response[8], response[9]]);
+        if internal_port != mapping.port {
+            return Err(NatError::InvalidResponse(format!(
+                "Expected mapping for port {}, got {internal_port}",
+                mapping.port
+            )));
+        }
+        Ok(u16::from_be_bytes([response[10], response[11]]))
+    }
+
+    /// Sends the request, retrying with increasing timeouts, and returns the validated response
+    async fn request(&self, request: &[u8], op: u8, len: usize) -> Result<Vec<u8>, NatError> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+        socket.connect(self.addr).await?;
+        let mut timeout = INITIAL_TIMEOUT;
+        let mut buf = [0u8; 64];
+        for _ in 0..MAX_ATTEMPTS {
+            socket.send(request).await?;
+            match tokio::time::timeout(timeout, socket.recv(&mut buf)).await {
+                Ok(Ok(read)) if read >= len => {
+                    let response = &buf[..len];
+                    if response[0] != NAT_PMP_VERSION || response[1] != op + RESPONSE_OFFSET {
+                        return Err(NatError::InvalidResponse(format!(
+                            "Unexpected version {} or opcode {}",
+                            response[0], response[1]
+                        )));
+                    }
+                    let result = u16::from_be_bytes([response[2], response[3]]);
+                    if result != 0 {
+                        return Err(NatError::GatewayError(format!("result code {result}")));
+                    }
+                    return Ok(response.to_vec());
+                }
+                Ok(Ok(read)) => {
+                    return Err(NatError::InvalidResponse(format!(
+                        "Response too short: {read} bytes"
+                    )));
+                }
+                Ok(Err(err)) => return Err(err.into()),
+                Err(_) => timeout *= 2,
+            }
+        }
+        Err(NatError::Timeout)
+    }
+}
+
+/// Reads the default IPv4 gateway from the kernel routing table
+#[cfg(target_os = "linux")]
+pub fn default_gateway() -> Option<Ipv4Addr> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+    parse_default_gateway(&routes)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn default_gateway() -> Option<Ipv4Addr> {
+    None
+}
+
+/// Parses the routing table, where addresses are written as hex numbers in host byte order
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let _interface = fields.next()?;
+        let destination = fields.next()?;
+        let gateway = fields.next()?;
+        if destination != "00000000" {
+            return None;
+        }
+        let gateway = u32::from_str_radix(gateway, 16).ok()?;
+        Some(Ipv4Addr::from(u32::from_be(gateway)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers NAT-PMP requests as a gateway with the given external address would, mapping
+    /// ports to `assigned_port` if given. Mapping requests are sent through the returned channel.
+    async fn spawn_mock_gateway(
+        external_ip: Ipv4Addr,
+        assigned_port: Option<u16>,
+    ) -> (SocketAddr, tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let (requests, received) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            loop {
+                let (read, from) = socket.recv_from(&mut buf).await.unwrap();
+                let op = buf[1];
+                let mut response = vec![0, op + RESPONSE_OFFSET, 0, 0];
+                // Seconds since the mapping table was initialized
+                response.extend_from_slice(&7u32.to_be_bytes());
+                if op == OP_EXTERNAL_ADDRESS {
+                    response.extend_from_slice(&external_ip.octets());
+                } else {
+                    assert_eq!(read, 12);
+                    requests.send(buf[..12].to_vec()).unwrap();
+                    // Internal port, external port and lifetime are echoed back
+                    response.extend_from_slice(&buf[4..12]);
+                    let deletion = buf[8..12] == [0, 0, 0, 0];
+                    if let Some(port) = assigned_port.filter(|_| !deletion) {
+                        response[10..12].copy_from_slice(&port.to_be_bytes());
+                    }
+                }
+                socket.send_to(&response, from).await.unwrap();
+            }
+        });
+        (addr, received)
+    }
+
+    #[tokio::test]
+    async fn mock_gateway_external_ip_and_mappings() {
+        let external_ip = Ipv4Addr::new(203, 0, 113, 7);
+        let (addr, _) = spawn_mock_gateway(external_ip, None).await;
+        let gateway = PmpGateway::new(addr);
+
+        assert_eq!(gateway.external_ip().await.unwrap(), external_ip);
+        let mapping = PortMapping {
+            protocol: Protocol::Tcp,
+            port: 30303,
+        };
+        gateway
+            .add_mapping(mapping, Duration::from_secs(1200))
+            .await
+            .unwrap();
+        gateway.remove_mapping(mapping).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn mapping_to_another_port_is_removed() {
+        let (addr, mut requests) =
+            spawn_mock_gateway(Ipv4Addr::new(203, 0, 113, 7), Some(40000)).await;
+        let gateway = PmpGateway::new(addr);
+        let mapping = PortMapping {
+            protocol: Protocol::Udp,
+            port: 30303,
+        };
+        assert!(matches!(
+            gateway
+                .add_mapping(mapping, Duration::from_secs(1200))
+                .await,
+            Err(NatError::GatewayError(_))
+        ));
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request[1], OP_MAP_UDP);
+        // The mismatched mapping is deleted with a lifetime and external port of zero
+        let deletion = requests.recv().await.unwrap();
+        assert_eq!(deletion[1], OP_MAP_UDP);
+        assert_eq!(&deletion[4..6], &30303u16.to_be_bytes());
+        assert_eq!(&deletion[6..12], &[0; 6]);
+    }
+
+    #[tokio::test]
+    async fn unresponsive_gateway_times_out() {
+        // Bound but never answering
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let gateway = PmpGateway::new(socket.local_addr().unwrap());
+        assert!(matches!(
+            gateway.external_ip().await,
+            Err(NatError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn parse_routing_table() {
+        let routes = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+                      eth0\t0001A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0\n\
+                      eth0\t00000000\t0101A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n";
+        assert_eq!(
+            parse_default_gateway(routes),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+    }
+}
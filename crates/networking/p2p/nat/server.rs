@@ -0,0 +1,164 @@
+use super::{Gateway, NatMode, PortMapping};
+use crate::{
+    discv4::server::{DiscoveryServer, InMessage as Discv4InMessage},
+    discv5::server::{Discv5Server, InMessage as Discv5InMessage},
+};
+use spawned_concurrency::{
+    messages::Unused,
+    tasks::{CastResponse, GenServer, GenServerHandle, InitResult, send_interval, send_message_on},
+};
+use std::{net::IpAddr, time::Duration};
+use tracing::{debug, info, warn};
+
+/// Lifetime requested for the port mappings
+const MAPPING_LIFETIME: Duration = Duration::from_secs(20 * 60);
+/// Interval between renewals of the mappings and checks of the external address, well within
+/// the mapping lifetime so mappings never expire while the node is running
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone)]
+pub enum InMessage {
+    Refresh,
+    Shutdown,
+}
+
+#[derive(Debug, Clone)]
+pub enum OutMessage {
+    Done,
+}
+
+/// Keeps the p2p ports mapped on the NAT gateway and pushes the external address to the
+/// discovery servers, which advertise it in their node records
+#[derive(Debug)]
+pub struct NatServer {
+    mode: NatMode,
+    mappings: Vec<PortMapping>,
+    gateway: Option<Gateway>,
+    external_ip: Option<IpAddr>,
+    discv4: Option<GenServerHandle<DiscoveryServer>>,
+    discv5: Option<GenServerHandle<Discv5Server>>,
+}
+
+impl NatServer {
+    pub fn spawn(
+        mode: NatMode,
+        mappings: Vec<PortMapping>,
+        discv4: Option<GenServerHandle<DiscoveryServer>>,
+        discv5: Option<GenServerHandle<Discv5Server>>,
+    ) -> GenServerHandle<NatServer> {
+        info!(%mode, "Starting NAT server");
+        Self {
+            mode,
+            mappings,
+            gateway: None,
+            external_ip: None,
+            discv4,
+            discv5,
+        }
+        .start()
+    }
+
+    async fn refresh(&mut self) {
+        if let NatMode::ExtIp(ip) = self.mode {
+            self.set_external_ip(ip).await;
+            return;
+        }
+        if !self.mode.uses_gateway() {
+            return;
+        }
+
+        if self.gateway.is_none() {
+            match Gateway::discover(self.mode).await {
+                Ok(gateway) => {
+                    info!(?gateway, "Found NAT gateway");
+                    self.gateway = Some(gateway);
+                }
+                Err(err) => {
+                    debug!(%err, "Could not find a NAT gateway, retrying later");
+                    return;
+                }
+            }
+        }
+        let Some(gateway) = self.gateway.clone() else {
+            return;
+        };
+
+        let mut all_mapped = true;
+        for mapping in &self.mappings {
+            if let Err(err) = gateway.add_mapping(*mapping, MAPPING_LIFETIME).await {
+                warn!(protocol = %mapping.protocol, port = mapping.port, %err, "Could not map port on NAT gateway");
+                all_mapped = false;
+            }
+        }
+        // The gateway address is only reachable through the mapped ports
+        if !all_mapped {
+            warn!("Not advertising the NAT gateway address, as not every port could be mapped");
+            return;
+        }
+        match gateway.external_ip().await {
+            Ok(ip) => self.set_external_ip(ip).await,
+            Err(err) => {
+                warn!(%err, "Could not get external address from NAT gateway, searching it again");
+                self.gateway = None;
+            }
+        }
+    }
+
+    /// Notifies the discovery servers if the external address changed
+    async fn set_external_ip(&mut self, ip: IpAddr) {
+        if self.external_ip == Some(ip) {
+            return;
+        }
+        info!(%ip, "External address changed");
+        self.external_ip = Some(ip);
+        if let Some(discv4) = self.discv4.as_mut() {
+            let _ = discv4.cast(Discv4InMessage::SetExternalIp(ip)).await;
+        }
+        if let Some(discv5) = self.discv5.as_mut() {
+            let _ = discv5.cast(Discv5InMessage::SetExternalIp(ip)).await;
+        }
+    }
+
+    async fn remove_mappings(&self) {
+        let Some(gateway) = &self.gateway else {
+            return;
+        };
+        for mapping in &self.mappings {
+            let _ = gateway
+                .remove_mapping(*mapping)
+                .await
+                .inspect_err(|err| debug!(%err, "Could not remove port mapping"));
+        }
+    }
+}
+
+impl GenServer for NatServer {
+    type CallMsg = Unused;
+    type CastMsg = InMessage;
+    type OutMsg = OutMessage;
+    type Error = std::convert::Infallible;
+
+    async fn init(self, handle: &GenServerHandle<Self>) -> Result<InitResult<Self>, Self::Error> {
+        let _ = handle.clone().cast(InMessage::Refresh).await;
+        send_interval(REFRESH_INTERVAL, handle.clone(), InMessage::Refresh);
+        send_message_on(handle.clone(), tokio::signal::ctrl_c(), InMessage::Shutdown);
+        Ok(InitResult::Success(self))
+    }
+
+    async fn handle_cast(
+        &mut self,
+        message: Self::CastMsg,
+        _handle: &GenServerHandle<Self>,
+    ) -> CastResponse {
+        match message {
+            InMessage::Refresh => {
+                self.refresh().await;
+                CastResponse::NoReply
+            }
+            InMessage::Shutdown => {
+                self.remove_mappings().await;
+                CastResponse::Stop
+            }
+        }
+    }
+}
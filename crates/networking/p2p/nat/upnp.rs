@@ -0,0 +1,477 @@
+//! Minimal UPnP Internet Gateway Device client, only supporting the WANIPConnection and
+//! WANPPPConnection actions needed to map ports and query the external address.
+
+use super::{NatError, PORT_MAPPING_DESCRIPTION, PortMapping, local_ip_towards};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UdpSocket},
+};
+
+/// Multicast address gateways listen on for SSDP searches
+pub const SSDP_ADDR: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)), 1900);
+const SEARCH_TARGET: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+/// Services able to map ports, in order of preference
+const WAN_SERVICES: [&str; 3] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:2",
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(3);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug)]
+pub struct UpnpGateway {
+    /// Address of the gateway's HTTP server
+    addr: SocketAddr,
+    /// Path of the control endpoint of the WAN service
+    control_path: String,
+    service_type: String,
+    /// Local address ports are mapped to
+    local_ip: IpAddr,
+}
+
+impl UpnpGateway {
+    /// Searches a gateway through SSDP multicast on the local network
+    pub async fn search() -> Result<Self, NatError> {
+        Self::search_at(SSDP_ADDR, SEARCH_TIMEOUT).await
+    }
+
+    /// Sends an SSDP search to the given address and returns the first gateway which offers a
+    /// WAN connection service
+    pub async fn search_at(ssdp_addr: SocketAddr, timeout: Duration) -> Result<Self, NatError> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+        let request = format!(
+            "M-SEARCH * HTTP/1.1\r\nHOST: {SSDP_ADDR}\r\nST: {SEARCH_TARGET}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\n\r\n"
+        );
+        socket.send_to(request.as_bytes(), ssdp_addr).await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut buf = [0u8; 2048];
+        loop {
+            let (read, _) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf))
+                .await
+                .map_err(|_| NatError::GatewayNotFound)??;
+            let response = String::from_utf8_lossy(&buf[..read]);
+            let Some(location) = header(&response, "location") else {
+                continue;
+            };
+            match Self::from_description(location).await {
+                Ok(gateway) => return Ok(gateway),
+                Err(err) => tracing::debug!(%location, %err, "Ignoring UPnP device"),
+            }
+        }
+    }
+
+    /// Builds the gateway from the device description found at the given url
+    async fn from_description(location: &str) -> Result<Self, NatError> {
+        let (addr, path) = parse_http_url(location)
+            .ok_or_else(|| NatError::InvalidResponse(format!("Invalid location {location}")))?;
+        let description = http_request(addr, "GET", &path, &[], "").await?;
+
+        let (service_type, control_url) = services(&description)
+            .into_iter()
+            .filter_map(|(service_type, control_url)| {
+                let preference = WAN_SERVICES.iter().position(|s| *s == service_type)?;
+                Some((preference, service_type, control_url))
+            })
+            .min_by_key(|(preference, _, _)| *preference)
+            .map(|(_, service_type, control_url)| (service_type, control_url))
+            .ok_or_else(|| NatError::InvalidResponse("No WAN connection service".to_string()))?;
+
+        let (addr, control_path) = if control_url.starts_with("http://") {
+            parse_http_url(&control_url).ok_or_else(|| {
+                NatError::InvalidResponse(format!("Invalid control url {control_url}"))
+            })?
+        } else if control_url.starts_with('/') {
+            (addr, control_url)
+        } else {
+            (addr, format!("/{control_url}"))
+        };
+
+        Ok(Self {
+            addr,
+            control_path,
+            service_type,
+            local_ip: local_ip_towards(addr).await?,
+        })
+    }
+
+    pub async fn external_ip(&self) -> Result<IpAddr, NatError> {
+        let response = self.soap("GetExternalIPAddress", &[]).await?;
+        let ip = xml_element(&response, "NewExternalIPAddress")
+            .ok_or_else(|| NatError::InvalidResponse("Missing external address".to_string()))?;
+        ip.trim()
+            .parse()
+            .map_err(|_| NatError::InvalidResponse(format!("Invalid external address {ip}")))
+    }
+
+    pub async fn add_mapping(
+        &self,
+        mapping: PortMapping,
+        lifetime: Duration,
+    ) -> Result<(), NatError> {
+        self.soap(
+            "AddPortMapping",
+            &[
+                ("NewRemoteHost", String::new()),
+                ("NewExternalPort", mapping.port.to_string()),
+                ("NewProtocol", mapping.protocol.to_string()),
+                ("NewInternalPort", mapping.port.to_string()),
+                ("NewInternalClient", self.local_ip.to_string()),
+                ("NewEnabled", "1".to_string()),
+                (
+                    "NewPortMappingDescription",
+                    PORT_MAPPING_DESCRIPTION.to_string(),
+                ),
+                ("NewLeaseDuration", lifetime.as_secs().to_string()),
+            ],
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn remove_mapping(&self, mapping: PortMapping) -> Result<(), NatError> {
+        self.soap(
+            "DeletePortMapping",
+            &[
+                ("NewRemoteHost", String::new()),
+                ("NewExternalPort", mapping.port.to_string()),
+                ("NewProtocol", mapping.protocol.to_string()),
+            ],
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Invokes an action of the WAN service and returns the response body
+    async fn soap(&self, action: &str, args: &[(&str, String)]) -> Result<String, NatError> {
+        let args: String = args
+            .iter()
+            .map(|(name, value)| format!("<{name}>{value}</{name}>"))
+            .collect();
+        let body = format!(
+            "<?xml version=\"1.0\"?>\r\n<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body><u:{action} xmlns:u=\"{}\">{args}</u:{action}></s:Body></s:Envelope>",
+            self.service_type
+        );
+        let soap_action = format!("\"{}#{action}\"", self.service_type);
+        http_request(
+            self.addr,
+            "POST",
+            &self.control_path,
+            &[
+                ("Content-Type", "text/xml; charset=\"utf-8\""),
+                ("SOAPAction", &soap_action),
+            ],
+            &body,
+        )
+        .await
+    }
+}
+
+/// Sends an HTTP/1.1 request and returns the body of a successful response
+async fn http_request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> Result<String, NatError> {
+    let mut request = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        body.len()
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+
+    let response = tokio::time::timeout(HTTP_TIMEOUT, async {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        Ok::<_, std::io::Error>(response)
+    })
+    .await
+    .map_err(|_| NatError::Timeout)??;
+    let response = String::from_utf8_lossy(&response);
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| NatError::InvalidResponse("Missing HTTP headers".to_string()))?;
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or_else(|| NatError::InvalidResponse("Missing HTTP status".to_string()))?;
+    let body =
+        if header(head, "transfer-encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked")) {
+            dechunk(body)?
+        } else {
+            body.to_string()
+        };
+    if status != "200" {
+        let description = xml_element(&body, "errorDescription").unwrap_or_default();
+        return Err(NatError::GatewayError(format!(
+            "HTTP status {status} {description}"
+        )));
+    }
+    Ok(body)
+}
+
+/// Decodes a body sent with chunked transfer encoding
+fn dechunk(mut body: &str) -> Result<String, NatError> {
+    let mut decoded = String::new();
+    loop {
+        let (size, rest) = body
+            .split_once("\r\n")
+            .ok_or_else(|| NatError::InvalidResponse("Invalid chunk".to_string()))?;
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16)
+            .map_err(|_| NatError::InvalidResponse(format!("Invalid chunk size {size}")))?;
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = rest
+            .get(..size)
+            .ok_or_else(|| NatError::InvalidResponse("Truncated chunk".to_string()))?;
+        decoded.push_str(chunk);
+        body = rest
+            .get(size..)
+            .unwrap_or_default()
+            .trim_start_matches("\r\n");
+    }
+}
+
+/// Returns the value of a header, matching its name case insensitively
+fn header<'a>(message: &'a str, name: &str) -> Option<&'a str> {
+    message.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
+
+/// Splits an `http://host[:port]/path` url into the socket address and the path
+fn parse_http_url(url: &str) -> Option<(SocketAddr, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (host, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], rest[index..].to_string()),
+        None => (rest, "/".to_string()),
+    };
+    let addr = host.parse::<SocketAddr>().ok().or_else(|| {
+        host.parse::<IpAddr>()
+            .ok()
+            .map(|ip| SocketAddr::new(ip, 80))
+    })?;
+    Some((addr, path))
+}
+
+/// Returns the text of the first element with the given name, ignoring namespace prefixes
+fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = xml;
+    loop {
+        let start = rest.find('<')?;
+        rest = &rest[start + 1..];
+        let end = rest.find('>')?;
+        let tag = &rest[..end];
+        let tag_name = tag.split_whitespace().next().unwrap_or_default();
+        let local_name = tag_name.rsplit(':').next().unwrap_or_default();
+        if local_name == name && !tag.ends_with('/') {
+            let content = &rest[end + 1..];
+            let close = content.find(&format!("</{tag_name}>"))?;
+            return Some(&content[..close]);
+        }
+        rest = &rest[end + 1..];
+    }
+}
+
+/// Returns the service type and control url of every service in a device description
+fn services(description: &str) -> Vec<(String, String)> {
+    description
+        .split("<service>")
+        .skip(1)
+        .filter_map(|service| {
+            let service = service.split("</service>").next()?;
+            Some((
+                xml_element(service, "serviceType")?.trim().to_string(),
+                xml_element(service, "controlURL")?.trim().to_string(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nat::Protocol;
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+
+    const DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:InternetGatewayDevice:1</deviceType>
+    <serviceList>
+      <service>
+        <serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>
+        <controlURL>/ctl/L3F</controlURL>
+      </service>
+      <service>
+        <serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+        <controlURL>/ctl/IPConn</controlURL>
+      </service>
+    </serviceList>
+  </device>
+</root>"#;
+
+    /// Reads a whole HTTP request, returning its head and body
+    async fn read_request(stream: &mut TcpStream) -> (String, String) {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            let read = stream.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..read]);
+            let text = String::from_utf8_lossy(&data).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let len: usize = header(head, "content-length").unwrap().parse().unwrap();
+                if body.len() >= len {
+                    return (head.to_string(), body.to_string());
+                }
+            }
+        }
+    }
+
+    /// Serves the device description and answers SOAP actions, recording the received ones.
+    /// Returns the SSDP address of the mock gateway.
+    async fn spawn_mock_gateway(
+        external_ip: &'static str,
+        actions: Arc<Mutex<Vec<String>>>,
+    ) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let (head, body) = read_request(&mut stream).await;
+                let response_body = if head.starts_with("GET /rootDesc.xml") {
+                    DESCRIPTION.to_string()
+                } else {
+                    assert!(head.starts_with("POST /ctl/IPConn"));
+                    let action = header(&head, "soapaction").unwrap();
+                    let action = action.trim_matches('"').rsplit('#').next().unwrap();
+                    assert!(body.contains(&format!("<u:{action} ")));
+                    actions.lock().unwrap().push(action.to_string());
+                    format!(
+                        "<s:Envelope><s:Body><u:{action}Response><NewExternalIPAddress>{external_ip}</NewExternalIPAddress></u:{action}Response></s:Body></s:Envelope>"
+                    )
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\n\r\n{response_body}",
+                    response_body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let ssdp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let ssdp_addr = ssdp.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            loop {
+                let (read, from) = ssdp.recv_from(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..read]).to_string();
+                assert!(request.starts_with("M-SEARCH"));
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nST: {SEARCH_TARGET}\r\nLOCATION: http://{http_addr}/rootDesc.xml\r\n\r\n"
+                );
+                ssdp.send_to(response.as_bytes(), from).await.unwrap();
+            }
+        });
+        ssdp_addr
+    }
+
+    #[tokio::test]
+    async fn mock_gateway_external_ip_and_mappings() {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let ssdp_addr = spawn_mock_gateway("203.0.113.7", actions.clone()).await;
+
+        let gateway = UpnpGateway::search_at(ssdp_addr, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(gateway.control_path, "/ctl/IPConn");
+        assert_eq!(
+            gateway.service_type,
+            "urn:schemas-upnp-org:service:WANIPConnection:1"
+        );
+        assert_eq!(
+            gateway.external_ip().await.unwrap(),
+            "203.0.113.7".parse::<IpAddr>().unwrap()
+        );
+
+        let mapping = PortMapping {
+            protocol: Protocol::Udp,
+            port: 30303,
+        };
+        gateway
+            .add_mapping(mapping, Duration::from_secs(1200))
+            .await
+            .unwrap();
+        gateway.remove_mapping(mapping).await.unwrap();
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec![
+                "GetExternalIPAddress",
+                "AddPortMapping",
+                "DeletePortMapping"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn search_without_gateway_fails() {
+        // Bound but never answering
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let result =
+            UpnpGateway::search_at(socket.local_addr().unwrap(), Duration::from_millis(200)).await;
+        assert!(matches!(result, Err(NatError::GatewayNotFound)));
+    }
+
+    #[test]
+    fn parse_urls_and_xml() {
+        assert_eq!(
+            parse_http_url("http://192.168.1.1:5000/rootDesc.xml"),
+            Some((
+                "192.168.1.1:5000".parse().unwrap(),
+                "/rootDesc.xml".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_http_url("http://192.168.1.1"),
+            Some(("192.168.1.1:80".parse().unwrap(), "/".to_string()))
+        );
+        assert_eq!(parse_http_url("https://192.168.1.1/desc.xml"), None);
+
+        assert_eq!(
+            services(DESCRIPTION)[1],
+            (
+                "urn:schemas-upnp-org:service:WANIPConnection:1".to_string(),
+                "/ctl/IPConn".to_string()
+            )
+        );
+        assert_eq!(
+            xml_element("<a:Body><b:Value attr=\"1\">42</b:Value></a:Body>", "Value"),
+            Some("42")
+        );
+        assert_eq!(
+            dechunk("4\r\nabcd\r\n2\r\nef\r\n0\r\n\r\n").unwrap(),
+            "abcdef"
+        );
+    }
+}
@@ -3,27 +3,38 @@ use crate::rlpx::l2::l2_connection::P2PBasedContext;
 #[cfg(not(feature = "l2"))]
 #[derive(Clone, Debug)]
 pub struct P2PBasedContext;
+#[cfg(feature = "test-utils")]
+use crate::rlpx::connection::server::MessageInterceptor;
 use crate::{
     discv4::{
-        peer_table::{PeerData, PeerTable},
+        peer_table::{PeerData, PeerTable, PeerTableError},
         server::{DiscoveryServer, DiscoveryServerError},
     },
+    discv5::server::{
+        Discv5Server, Discv5ServerError, InMessage as Discv5InMessage, TalkReqHandler,
+    },
     metrics::METRICS,
+    nat::{NatMode, PortMapping, Protocol, server::NatServer},
     rlpx::{
         connection::server::{PeerConnBroadcastSender, PeerConnection},
         message::Message,
         p2p::SUPPORTED_SNAP_CAPABILITIES,
     },
+    transport::{DatagramSocket, TcpTransport, Transport, split_discovery_socket},
     tx_broadcaster::{TxBroadcaster, TxBroadcasterError},
-    types::Node,
+    tx_fetcher::TxFetcher,
+    types::{LocalNodeRecord, Node, NodeError},
 };
+use bytes::Bytes;
 use ethrex_blockchain::Blockchain;
-use ethrex_storage::Store;
+use ethrex_storage::{Store, error::StoreError};
 use secp256k1::SecretKey;
 use spawned_concurrency::tasks::GenServerHandle;
 use std::{
+    fmt::Display,
     io,
     net::SocketAddr,
+    str::FromStr,
     sync::{Arc, atomic::Ordering},
     time::{Duration, SystemTime},
 };
@@ -46,7 +57,14 @@ pub struct P2PContext {
     #[cfg(feature = "l2")]
     pub based_context: Option<P2PBasedContext>,
     pub tx_broadcaster: GenServerHandle<TxBroadcaster>,
+    pub tx_fetcher: GenServerHandle<TxFetcher>,
     pub initial_lookup_interval: f64,
+    /// Used to open the outgoing RLPx connections
+    pub transport: Arc<dyn Transport>,
+    /// Whether snap/2 is advertised to peers
+    pub snap_v2: bool,
+    #[cfg(feature = "test-utils")]
+    pub interceptor: Option<Arc<dyn MessageInterceptor>>,
 }
 
 impl P2PContext {
@@ -77,6 +95,8 @@ impl P2PContext {
             error!("Failed to start Tx Broadcaster: {e}");
         })?;
 
+        let tx_fetcher = TxFetcher::spawn(peer_table.clone(), blockchain.clone());
+
         #[cfg(not(feature = "l2"))]
         let _ = &based_context;
 
@@ -92,39 +112,194 @@ impl P2PContext {
             #[cfg(feature = "l2")]
             based_context,
             tx_broadcaster,
+            tx_fetcher,
             initial_lookup_interval: lookup_interval,
+            transport: Arc::new(TcpTransport),
+            snap_v2: false,
+            #[cfg(feature = "test-utils")]
+            interceptor: None,
         })
     }
+
+    /// Replaces the TCP transport used to open outgoing connections
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Advertises snap/2 to peers, which serves block access lists
+    pub fn with_snap_v2(mut self, enabled: bool) -> Self {
+        self.snap_v2 = enabled;
+        self
+    }
+
+    /// Sets a hook that can tamper with every message sent to peers
+    #[cfg(feature = "test-utils")]
+    pub fn with_interceptor(mut self, interceptor: Arc<dyn MessageInterceptor>) -> Self {
+        self.interceptor = Some(interceptor);
+        self
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum NetworkError {
     #[error("Failed to start discovery server: {0}")]
     DiscoveryServerError(#[from] DiscoveryServerError),
+    #[error("Failed to start discv5 server: {0}")]
+    Discv5ServerError(#[from] Discv5ServerError),
     #[error("Failed to start Tx Broadcaster: {0}")]
     TxBroadcasterError(#[from] TxBroadcasterError),
+    #[error(transparent)]
+    PeerTableError(#[from] PeerTableError),
+    #[error("Failed to bind the discovery socket: {0}")]
+    DiscoverySocket(#[from] io::Error),
+    #[error("Failed to build the local node record: {0}")]
+    NodeRecord(#[from] NodeError),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+/// Discovery protocols to run, both feed the same peer table
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiscoveryProtocols {
+    V4,
+    V5,
+    #[default]
+    All,
+}
+
+impl DiscoveryProtocols {
+    pub fn discv4_enabled(&self) -> bool {
+        matches!(self, DiscoveryProtocols::V4 | DiscoveryProtocols::All)
+    }
+
+    pub fn discv5_enabled(&self) -> bool {
+        matches!(self, DiscoveryProtocols::V5 | DiscoveryProtocols::All)
+    }
+}
+
+impl Display for DiscoveryProtocols {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiscoveryProtocols::V4 => write!(f, "v4"),
+            DiscoveryProtocols::V5 => write!(f, "v5"),
+            DiscoveryProtocols::All => write!(f, "all"),
+        }
+    }
+}
+
+impl FromStr for DiscoveryProtocols {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "v4" => Ok(DiscoveryProtocols::V4),
+            "v5" => Ok(DiscoveryProtocols::V5),
+            "all" => Ok(DiscoveryProtocols::All),
+            _ => Err(format!(
+                "Invalid discovery protocols '{s}'. Expected: v4, v5, or all"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DiscoveryConfig {
+    /// Protocols to run, they share the UDP port and node record of the local node
+    pub protocols: DiscoveryProtocols,
+    /// Handlers for TALKREQ messages received through discv5, keyed by protocol name
+    pub talk_handlers: Vec<(Bytes, Arc<dyn TalkReqHandler>)>,
+    /// How the external address advertised by the discovery servers is found
+    pub nat: NatMode,
 }
 
-pub async fn start_network(context: P2PContext, bootnodes: Vec<Node>) -> Result<(), NetworkError> {
-    let udp_socket = Arc::new(
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            protocols: DiscoveryProtocols::default(),
+            talk_handlers: Vec::new(),
+            nat: NatMode::default(),
+        }
+    }
+}
+
+pub async fn start_network(
+    context: P2PContext,
+    bootnodes: Vec<Node>,
+    discovery_config: DiscoveryConfig,
+) -> Result<(), NetworkError> {
+    let mut mappings = vec![
+        PortMapping {
+            protocol: Protocol::Tcp,
+            port: context.local_node.tcp_port,
+        },
+        PortMapping {
+            protocol: Protocol::Udp,
+            port: context.local_node.udp_port,
+        },
+    ];
+    let mut discv4_handle = None;
+    let mut discv5_handle = None;
+
+    let udp_socket: Arc<dyn DatagramSocket> = Arc::new(
         UdpSocket::bind(context.local_node.udp_addr())
             .await
-            .expect("Failed to bind udp socket"),
+            .inspect_err(|e| error!("Failed to bind udp socket: {e}"))?,
     );
+    let local_node_record = LocalNodeRecord::new(
+        &context.local_node,
+        &context.signer,
+        context.storage.get_fork_id().await.ok(),
+    )?;
+    let (discv4_socket, discv5_socket) = match discovery_config.protocols {
+        DiscoveryProtocols::All => split_discovery_socket(udp_socket),
+        _ => (udp_socket.clone(), udp_socket),
+    };
+
+    if discovery_config.protocols.discv4_enabled() {
+        let handle = DiscoveryServer::spawn(
+            context.storage.clone(),
+            context.local_node.clone(),
+            local_node_record.clone(),
+            context.signer,
+            discv4_socket,
+            context.table.clone(),
+            bootnodes.clone(),
+            context.initial_lookup_interval,
+        )
+        .await
+        .inspect_err(|e| {
+            error!("Failed to start discovery server: {e}");
+        })?;
+        discv4_handle = Some(handle);
+    }
 
-    DiscoveryServer::spawn(
-        context.storage.clone(),
-        context.local_node.clone(),
-        context.signer,
-        udp_socket.clone(),
-        context.table.clone(),
-        bootnodes,
-        context.initial_lookup_interval,
-    )
-    .await
-    .inspect_err(|e| {
-        error!("Failed to start discovery server: {e}");
-    })?;
+    if discovery_config.protocols.discv5_enabled() {
+        let mut handle = Discv5Server::spawn(
+            context.storage.clone(),
+            context.local_node.clone(),
+            local_node_record,
+            context.signer,
+            discv5_socket,
+            context.table.clone(),
+            bootnodes,
+        )
+        .await
+        .inspect_err(|e| {
+            error!("Failed to start discv5 server: {e}");
+        })?;
+
+        for (protocol, handler) in discovery_config.talk_handlers {
+            let _ = handle
+                .cast(Discv5InMessage::RegisterTalkHandler { protocol, handler })
+                .await;
+        }
+        discv5_handle = Some(handle);
+    }
+
+    if discovery_config.nat != NatMode::None {
+        NatServer::spawn(discovery_config.nat, mappings, discv4_handle, discv5_handle);
+    }
 
     context.tracker.spawn(serve_p2p_requests(context.clone()));
 
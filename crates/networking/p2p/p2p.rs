@@ -1,14 +1,18 @@
+pub mod crawler;
 pub mod discv4;
-#[cfg(feature = "experimental-discv5")]
 pub mod discv5;
 pub(crate) mod metrics;
+pub mod nat;
 pub mod network;
 pub mod peer_handler;
+pub mod reputation;
 pub mod rlpx;
 pub(crate) mod snap;
 pub mod sync;
 pub mod sync_manager;
+pub mod transport;
 pub mod tx_broadcaster;
+pub mod tx_fetcher;
 pub mod types;
 pub mod utils;
 
@@ -2,6 +2,7 @@ use crate::rlpx::initiator::RLPxInitiator;
 use crate::{
     discv4::peer_table::{PeerData, PeerTable, PeerTableError},
     metrics::{CurrentStepValue, METRICS},
+    reputation::{Penalty, RequestKind, response_items},
     rlpx::{
         connection::server::PeerConnection,
         error::PeerConnectionError,
@@ -9,6 +10,7 @@ use crate::{
             BLOCK_HEADER_LIMIT, BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders,
             HashOrNumber,
         },
+        eth::receipts::GetReceipts,
         message::Message as RLPxMessage,
         p2p::{Capability, SUPPORTED_ETH_CAPABILITIES},
         snap::{
@@ -26,7 +28,9 @@ use crate::{
 use bytes::Bytes;
 use ethrex_common::{
     BigEndianHash, H256, U256,
-    types::{AccountState, BlockBody, BlockHeader, validate_block_body},
+    types::{
+        AccountState, BlockBody, BlockHeader, Receipt, compute_receipts_root, validate_block_body,
+    },
 };
 use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 use ethrex_storage::Store;
@@ -38,7 +42,7 @@ use std::{
     io::ErrorKind,
     path::{Path, PathBuf},
     sync::atomic::Ordering,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 use tracing::{debug, error, info, trace, warn};
 pub const PEER_REPLY_TIMEOUT: Duration = Duration::from_secs(15);
@@ -63,6 +67,9 @@ pub const SNAP_LIMIT: usize = 128;
 // increasing them may be the cause of peers disconnection
 pub const MAX_BLOCK_BODIES_TO_REQUEST: usize = 128;
 
+// Same limit as geth's downloader, receipts are usually bigger than bodies so peers may return less
+pub const MAX_RECEIPTS_TO_REQUEST: usize = 64;
+
 /// An abstraction over the [Kademlia] containing logic to make requests to peers
 #[derive(Debug, Clone)]
 pub struct PeerHandler {
@@ -158,9 +165,25 @@ impl PeerHandler {
         message: RLPxMessage,
         timeout: Duration,
     ) -> Result<RLPxMessage, PeerConnectionError> {
+        let kind = RequestKind::of(&message);
         peer_table.inc_requests(peer_id).await?;
+        let start = Instant::now();
         let result = connection.outgoing_request(message, timeout).await;
         peer_table.dec_requests(peer_id).await?;
+        if let Some(kind) = kind {
+            match &result {
+                Ok(response) => {
+                    peer_table
+                        .record_response(&peer_id, kind, start.elapsed(), response_items(response))
+                        .await?
+                }
+                // Only counted here, the caller penalizes the peer for any request that failed
+                Err(PeerConnectionError::Timeout) => {
+                    peer_table.record_timeout(&peer_id, kind).await?
+                }
+                Err(_) => {}
+            }
+        }
         result
     }
 
@@ -602,6 +625,77 @@ impl PeerHandler {
         Ok(None)
     }
 
+    /// Internal method to request receipts from any suitable peer given their block hashes
+    /// Returns the receipts or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The requested peer did not return a valid response in the given time limit
+    async fn request_receipts_inner(
+        &mut self,
+        block_hashes: &[H256],
+    ) -> Result<Option<(Vec<Vec<Receipt>>, H256)>, PeerHandlerError> {
+        let block_hashes_len = block_hashes.len();
+        let request =
+            RLPxMessage::GetReceipts(GetReceipts::new(rand::random(), block_hashes.to_vec()));
+        match self.get_random_peer(&SUPPORTED_ETH_CAPABILITIES).await? {
+            None => Ok(None),
+            Some((peer_id, mut connection)) => {
+                // The response format depends on the eth version negotiated with the peer
+                let receipts = match PeerHandler::make_request(
+                    &mut self.peer_table,
+                    peer_id,
+                    &mut connection,
+                    request,
+                    PEER_REPLY_TIMEOUT,
+                )
+                .await
+                {
+                    Ok(RLPxMessage::Receipts68(receipts)) => Some(receipts.get_receipts()),
+                    Ok(RLPxMessage::Receipts69(receipts)) => Some(receipts.receipts),
+                    _ => None,
+                };
+                if let Some(receipts) = receipts
+                    && !receipts.is_empty()
+                    && receipts.len() <= block_hashes_len
+                {
+                    self.peer_table.record_success(&peer_id).await?;
+                    return Ok(Some((receipts, peer_id)));
+                }
+                warn!("[SYNCING] Didn't receive receipts from peer, penalizing peer {peer_id}...");
+                self.peer_table.record_failure(&peer_id).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Requests the receipts of the given blocks from any suitable peer and validates them against
+    /// the receipts root of each header
+    /// Returns the receipts of a prefix of the given blocks or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - No peer returned a valid response in the given time and retry limits
+    pub async fn request_receipts(
+        &mut self,
+        block_headers: &[BlockHeader],
+    ) -> Result<Option<Vec<Vec<Receipt>>>, PeerHandlerError> {
+        let block_hashes: Vec<H256> = block_headers.iter().map(|h| h.hash()).collect();
+
+        for _ in 0..REQUEST_RETRY_ATTEMPTS {
+            let Some((receipts, peer_id)) = self.request_receipts_inner(&block_hashes).await?
+            else {
+                continue; // Retry on empty response
+            };
+            let valid = block_headers
+                .iter()
+                .zip(&receipts)
+                .all(|(header, receipts)| compute_receipts_root(receipts) == header.receipts_root);
+            if valid {
+                return Ok(Some(receipts));
+            }
+            warn!("Invalid receipts, discarding peer {peer_id} and retrying...");
+            self.peer_table.record_critical_failure(&peer_id).await?;
+        }
+        Ok(None)
+    }
+
     /// Requests an account range from any suitable peer given the state trie's root and the starting hash and the limit hash.
     /// Will also return a boolean indicating if there is more state to be fetched towards the right of the trie
     /// (Note that the boolean will be true even if the remaining state is ouside the boundary set by the limit hash)
@@ -895,6 +989,9 @@ impl PeerHandler {
                     .await
                     .ok();
                 tracing::error!("Received invalid account range");
+                peer_table
+                    .penalize(&peer_id, Penalty::InvalidSnapProof)
+                    .await?;
                 return Ok(());
             };
 
@@ -1742,6 +1839,9 @@ impl PeerHandler {
                     &proof,
                 ) else {
                     tx.send(empty_task_result).await.ok();
+                    peer_table
+                        .penalize(&peer_id, Penalty::InvalidSnapProof)
+                        .await?;
                     return Ok(());
                 };
                 should_continue = sc;
@@ -1755,6 +1855,9 @@ impl PeerHandler {
             .is_err()
             {
                 tx.send(empty_task_result.clone()).await.ok();
+                peer_table
+                    .penalize(&peer_id, Penalty::InvalidSnapProof)
+                    .await?;
                 return Ok(());
             }
 
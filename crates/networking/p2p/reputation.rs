@@ -0,0 +1,293 @@
+//! Peer reputation tracking.
+//!
+//! Each peer accumulates a score which is raised by useful responses and lowered by penalties,
+//! decaying towards zero over time so old misbehaviour is eventually forgiven. Peers whose score
+//! drops below [`BAN_THRESHOLD`] are disconnected and temporarily banned. Reputations outlive
+//! connections and are persisted in the datadir, so a restart doesn't hand bad peers a clean slate.
+
+use crate::rlpx::message::Message;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+pub const MAX_SCORE: f64 = 50.0;
+pub const MIN_SCORE: f64 = -50.0;
+/// Lowest possible score, reached by peers acting maliciously (e.g., returning a node with wrong hash)
+pub const MIN_SCORE_CRITICAL: f64 = MIN_SCORE * 3.0;
+/// Peers with a score below this value are disconnected and temporarily banned
+pub const BAN_THRESHOLD: f64 = MIN_SCORE;
+/// Time it takes for a score to decay to half of its value
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(15 * 60);
+/// Duration of the first temporary ban, doubled for each subsequent ban of the same peer
+const BASE_BAN_DURATION: Duration = Duration::from_secs(30 * 60);
+/// Bans stop getting longer after this many repetitions
+const MAX_BAN_DOUBLINGS: u32 = 6;
+/// Weight of the latest sample in the latency and throughput moving averages
+const EMA_WEIGHT: f64 = 0.2;
+
+/// Type of request sent to a peer, used to keep separate statistics for each of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RequestKind {
+    BlockHeaders,
+    BlockBodies,
+    Receipts,
+    PooledTransactions,
+    AccountRange,
+    StorageRanges,
+    ByteCodes,
+    TrieNodes,
+    BlockAccessLists,
+}
+
+impl RequestKind {
+    /// Returns the kind of the given request, or None if the message is not a request
+    pub fn of(request: &Message) -> Option<Self> {
+        Some(match request {
+            Message::GetBlockHeaders(_) => RequestKind::BlockHeaders,
+            Message::GetBlockBodies(_) => RequestKind::BlockBodies,
+            Message::GetReceipts(_) => RequestKind::Receipts,
+            Message::GetPooledTransactions(_) => RequestKind::PooledTransactions,
+            Message::GetAccountRange(_) => RequestKind::AccountRange,
+            Message::GetStorageRanges(_) => RequestKind::StorageRanges,
+            Message::GetByteCodes(_) => RequestKind::ByteCodes,
+            Message::GetTrieNodes(_) => RequestKind::TrieNodes,
+            Message::GetBlockAccessLists(_) => RequestKind::BlockAccessLists,
+            _ => return None,
+        })
+    }
+}
+
+/// Returns the amount of items (headers, bodies, accounts, slots, ...) in a response
+pub fn response_items(response: &Message) -> usize {
+    match response {
+        Message::BlockHeaders(msg) => msg.block_headers.len(),
+        Message::BlockBodies(msg) => msg.block_bodies.len(),
+        Message::Receipts68(msg) => msg.receipts.len(),
+        Message::Receipts69(msg) => msg.receipts.len(),
+        Message::PooledTransactions(msg) => msg.pooled_transactions.len(),
+        Message::AccountRange(msg) => msg.accounts.len(),
+        Message::StorageRanges(msg) => msg.slots.iter().map(Vec::len).sum(),
+        Message::ByteCodes(msg) => msg.codes.len(),
+        Message::TrieNodes(msg) => msg.nodes.len(),
+        Message::BlockAccessLists(msg) => msg.access_lists.len(),
+        _ => 0,
+    }
+}
+
+/// Misbehaviour of a peer, each one lowering its score by a different amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Penalty {
+    /// The peer returned an empty or otherwise useless response
+    UselessResponse,
+    /// The peer didn't answer a request in time
+    Timeout,
+    /// The peer didn't know a recent block, meaning it is behind the chain head
+    StaleHeaders,
+    /// The peer returned a snap range whose proof doesn't match the requested root, or trie
+    /// nodes which don't hash to the requested ones
+    InvalidSnapProof,
+    /// The peer returned data which doesn't match what was requested (e.g. bodies not matching
+    /// their headers). This sends the peer straight to the lowest score.
+    InvalidData,
+}
+
+impl Penalty {
+    fn weight(&self) -> f64 {
+        match self {
+            Penalty::UselessResponse => 1.0,
+            Penalty::Timeout => 2.0,
+            Penalty::StaleHeaders => 5.0,
+            Penalty::InvalidSnapProof => 25.0,
+            Penalty::InvalidData => MAX_SCORE - MIN_SCORE_CRITICAL,
+        }
+    }
+}
+
+impl Display for Penalty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Penalty::UselessResponse => write!(f, "useless response"),
+            Penalty::Timeout => write!(f, "timeout"),
+            Penalty::StaleHeaders => write!(f, "stale headers"),
+            Penalty::InvalidSnapProof => write!(f, "invalid snap proof"),
+            Penalty::InvalidData => write!(f, "invalid data"),
+        }
+    }
+}
+
+/// Statistics of the requests of one kind sent to a peer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestStats {
+    pub responses: u64,
+    pub timeouts: u64,
+    /// Moving average of the time taken to answer, in milliseconds
+    pub avg_latency_ms: f64,
+    /// Moving average of the items returned per second
+    pub avg_throughput: f64,
+}
+
+impl RequestStats {
+    fn record_response(&mut self, latency: Duration, items: usize) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        // Avoid infinite throughput on instant responses
+        let throughput = items as f64 / latency.as_secs_f64().max(0.001);
+        if self.responses == 0 {
+            self.avg_latency_ms = latency_ms;
+            self.avg_throughput = throughput;
+        } else {
+            self.avg_latency_ms += EMA_WEIGHT * (latency_ms - self.avg_latency_ms);
+            self.avg_throughput += EMA_WEIGHT * (throughput - self.avg_throughput);
+        }
+        self.responses += 1;
+    }
+}
+
+/// Reputation of a single peer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reputation {
+    /// Score at the time of the last update, see [`Reputation::score`] for the current one
+    score: f64,
+    /// Unix timestamp in seconds of the last score update
+    updated_at: u64,
+    /// Amount of times each penalty was applied
+    pub penalties: BTreeMap<Penalty, u64>,
+    pub requests: BTreeMap<RequestKind, RequestStats>,
+    /// Unix timestamp in seconds until which the peer is banned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub banned_until: Option<u64>,
+    /// Amount of temporary bans the peer got so far
+    #[serde(default)]
+    pub bans: u32,
+}
+
+impl Reputation {
+    /// Returns the score at the given time, after applying the decay since the last update
+    pub fn score(&self, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.updated_at) as f64;
+        self.score * 0.5f64.powf(elapsed / SCORE_HALF_LIFE.as_secs_f64())
+    }
+
+    fn add_to_score(&mut self, delta: f64, now: u64) {
+        self.score = (self.score(now) + delta).clamp(MIN_SCORE_CRITICAL, MAX_SCORE);
+        self.updated_at = now;
+    }
+
+    /// Raises the score after a useful response
+    pub fn reward(&mut self, now: u64) {
+        self.add_to_score(1.0, now);
+    }
+
+    /// Lowers the score, returning true if the peer should now be banned
+    pub fn penalize(&mut self, penalty: Penalty, now: u64) -> bool {
+        self.add_to_score(-penalty.weight(), now);
+        *self.penalties.entry(penalty).or_default() += 1;
+        self.score(now) < BAN_THRESHOLD && !self.is_banned(now)
+    }
+
+    pub fn record_response(&mut self, kind: RequestKind, latency: Duration, items: usize) {
+        self.requests
+            .entry(kind)
+            .or_default()
+            .record_response(latency, items);
+    }
+
+    /// Records a request which wasn't answered in time. The peer is penalized separately, by
+    /// whoever handles the failed request.
+    pub fn record_timeout(&mut self, kind: RequestKind) {
+        self.requests.entry(kind).or_default().timeouts += 1;
+    }
+
+    /// Bans the peer, each ban of the same peer lasting twice as long as the previous one
+    pub fn ban(&mut self, now: u64) -> Duration {
+        let duration = BASE_BAN_DURATION * 2u32.pow(self.bans.min(MAX_BAN_DOUBLINGS));
+        self.banned_until = Some(now.saturating_add(duration.as_secs()));
+        self.bans += 1;
+        duration
+    }
+
+    pub fn is_banned(&self, now: u64) -> bool {
+        self.banned_until.is_some_and(|until| until > now)
+    }
+
+    /// Returns true if the reputation carries no information worth keeping
+    pub fn is_neutral(&self, now: u64) -> bool {
+        self.score(now).abs() < 1.0 && !self.is_banned(now) && self.bans == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_decays_over_time() {
+        let mut reputation = Reputation::default();
+        for _ in 0..10 {
+            reputation.penalize(Penalty::StaleHeaders, 1000);
+        }
+        assert_eq!(reputation.score(1000), -50.0);
+        let half_life = SCORE_HALF_LIFE.as_secs();
+        assert_eq!(reputation.score(1000 + half_life), -25.0);
+        assert_eq!(reputation.penalties.get(&Penalty::StaleHeaders), Some(&10));
+
+        for _ in 0..100 {
+            reputation.reward(1000);
+        }
+        assert_eq!(reputation.score(1000), MAX_SCORE);
+    }
+
+    #[test]
+    fn peers_below_threshold_get_increasing_bans() {
+        let mut reputation = Reputation::default();
+        assert!(!reputation.penalize(Penalty::InvalidSnapProof, 0));
+        assert!(!reputation.penalize(Penalty::InvalidSnapProof, 0));
+        assert!(reputation.penalize(Penalty::InvalidSnapProof, 0));
+
+        assert_eq!(reputation.ban(0), BASE_BAN_DURATION);
+        assert!(reputation.is_banned(0));
+        // Already banned peers aren't banned again
+        assert!(!reputation.penalize(Penalty::InvalidData, 0));
+        assert_eq!(reputation.score(0), MIN_SCORE_CRITICAL);
+
+        let unbanned_at = BASE_BAN_DURATION.as_secs();
+        assert!(!reputation.is_banned(unbanned_at));
+        assert!(reputation.penalize(Penalty::InvalidData, unbanned_at));
+        assert_eq!(reputation.ban(unbanned_at), BASE_BAN_DURATION * 2);
+    }
+
+    #[test]
+    fn request_stats_track_latency_and_timeouts() {
+        let mut reputation = Reputation::default();
+        reputation.record_response(RequestKind::BlockHeaders, Duration::from_millis(100), 10);
+        reputation.record_response(RequestKind::BlockHeaders, Duration::from_millis(600), 10);
+        reputation.record_timeout(RequestKind::BlockHeaders);
+
+        let stats = &reputation.requests[&RequestKind::BlockHeaders];
+        assert_eq!(stats.responses, 2);
+        assert_eq!(stats.timeouts, 1);
+        assert_eq!(stats.avg_latency_ms, 200.0);
+        assert_eq!(
+            stats.avg_throughput,
+            100.0 + EMA_WEIGHT * (10.0 / 0.6 - 100.0)
+        );
+        // Counting the timeout doesn't penalize the peer on its own
+        assert!(reputation.penalties.is_empty());
+        assert_eq!(reputation.score(0), 0.0);
+    }
+
+    #[test]
+    fn reputation_roundtrips_through_json() {
+        let mut reputation = Reputation::default();
+        reputation.penalize(Penalty::InvalidData, 7);
+        reputation.ban(7);
+        reputation.record_response(RequestKind::TrieNodes, Duration::from_secs(1), 5);
+        let json = serde_json::to_string(&reputation).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Reputation>(&json).unwrap(),
+            reputation
+        );
+    }
+}
@@ -11,6 +11,7 @@ use crate::{
         message::EthCapVersion,
         utils::{compress_pubkey, decompress_pubkey, ecdh_xchng, kdf, sha256, sha256_hmac},
     },
+    transport::BoxedStream,
     types::Node,
 };
 use aes::cipher::{KeyIvInit, StreamCipher};
@@ -33,10 +34,7 @@ use std::{
     net::SocketAddr,
     sync::{Arc, RwLock},
 };
-use tokio::{
-    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
-    net::{TcpSocket, TcpStream},
-};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio_util::codec::Framed;
 use tracing::{debug, trace};
 
@@ -61,11 +59,11 @@ pub(crate) struct LocalState {
 pub(crate) async fn perform(
     state: ConnectionState,
     eth_version: Arc<RwLock<EthCapVersion>>,
-) -> Result<(Established, SplitStream<Framed<TcpStream, RLPxCodec>>), PeerConnectionError> {
+) -> Result<(Established, SplitStream<Framed<BoxedStream, RLPxCodec>>), PeerConnectionError> {
     let (context, node, framed) = match state {
         ConnectionState::Initiator(Initiator { context, node }) => {
             let addr = SocketAddr::new(node.ip, node.tcp_port);
-            let mut stream = match tcp_stream(addr).await {
+            let mut stream = match context.transport.dial(addr).await {
                 Ok(result) => result,
                 Err(error) => {
                     // If we can't find a TCP connection it's an issue we should track in debug
@@ -135,6 +133,8 @@ pub(crate) async fn perform(
             negotiated_snap_capability: None,
             last_block_range_update_block: 0,
             requested_pooled_txs: HashMap::new(),
+            snap_rate_limiter: Default::default(),
+            snap_v2: context.snap_v2,
             client_version: context.client_version.clone(),
             connection_broadcast_send: context.broadcast.clone(),
             peer_table: context.table.clone(),
@@ -143,21 +143,17 @@ pub(crate) async fn perform(
                 .based_context
                 .map_or_else(|| L2ConnState::Unsupported, L2ConnState::Disconnected),
             tx_broadcaster: context.tx_broadcaster,
+            tx_fetcher: context.tx_fetcher,
             current_requests: HashMap::new(),
             disconnect_reason: None,
             is_validated: false,
+            #[cfg(feature = "test-utils")]
+            interceptor: context.interceptor.clone(),
         },
         stream,
     ))
 }
 
-async fn tcp_stream(addr: SocketAddr) -> Result<TcpStream, std::io::Error> {
-    match addr {
-        SocketAddr::V4(_) => TcpSocket::new_v4()?.connect(addr).await,
-        SocketAddr::V6(_) => TcpSocket::new_v6()?.connect(addr).await,
-    }
-}
-
 async fn send_auth<S: AsyncWrite + std::marker::Unpin>(
     signer: &SecretKey,
     remote_public_key: H512,
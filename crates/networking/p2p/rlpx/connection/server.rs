@@ -6,7 +6,7 @@ use crate::rlpx::l2::{
     },
 };
 use crate::{
-    discv4::peer_table::PeerTable,
+    discv4::peer_table::{PeerAdmission, PeerTable},
     metrics::METRICS,
     network::P2PContext,
     rlpx::{
@@ -24,25 +24,29 @@ use crate::{
         message::EthCapVersion,
         p2p::{
             self, Capability, DisconnectMessage, DisconnectReason, PingMessage, PongMessage,
-            SUPPORTED_ETH_CAPABILITIES, SUPPORTED_SNAP_CAPABILITIES,
+            SUPPORTED_ETH_CAPABILITIES, advertised_snap_capabilities, negotiate_snap_capability,
         },
-        snap::TrieNodes,
+        snap::{AccountRange, BlockAccessLists, ByteCodes, StorageRanges, TrieNodes},
     },
     snap::{
-        process_account_range_request, process_byte_codes_request, process_storage_ranges_request,
-        process_trie_nodes_request,
+        SnapRateLimiter, process_account_range_request, process_block_access_lists_request,
+        process_byte_codes_request, process_storage_ranges_request, process_trie_nodes_request,
     },
+    transport::{BoxedStream, RLPxStream},
     tx_broadcaster::{InMessage, TxBroadcaster, send_tx_hashes},
+    tx_fetcher::{self, TxFetcher},
     types::Node,
 };
 use ethrex_blockchain::Blockchain;
 #[cfg(feature = "l2")]
 use ethrex_common::types::Transaction;
-use ethrex_common::types::{MempoolTransaction, P2PTransaction};
+use ethrex_common::{
+    H256,
+    types::{ForkId, MempoolTransaction, P2PTransaction},
+};
 use ethrex_storage::{Store, error::StoreError};
 use ethrex_trie::TrieError;
 use futures::{SinkExt as _, Stream, stream::SplitSink};
-use rand::random;
 use secp256k1::{PublicKey, SecretKey};
 use spawned_concurrency::{
     messages::Unused,
@@ -60,7 +64,6 @@ use std::{
     time::Duration,
 };
 use tokio::{
-    net::TcpStream,
     sync::{Mutex, broadcast, oneshot},
     task::{self, Id},
 };
@@ -82,12 +85,12 @@ impl PeerConnection {
     pub fn spawn_as_receiver(
         context: P2PContext,
         peer_addr: SocketAddr,
-        stream: TcpStream,
+        stream: impl RLPxStream,
     ) -> PeerConnection {
         let state = ConnectionState::Receiver(Receiver {
             context,
             peer_addr,
-            stream: Arc::new(stream),
+            stream: Arc::new(Box::new(stream)),
         });
         let connection = PeerConnectionServer { state };
         Self {
@@ -113,6 +116,19 @@ impl PeerConnection {
             .map_err(|err| PeerConnectionError::InternalError(err.to_string()))
     }
 
+    /// Requests the announced transactions from the peer, the response is forwarded to the
+    /// transaction fetcher
+    pub(crate) async fn fetch_pooled_transactions(
+        &mut self,
+        id: u64,
+        announcement: NewPooledTransactionHashes,
+    ) -> Result<(), PeerConnectionError> {
+        self.handle
+            .cast(CastMessage::FetchPooledTransactions { id, announcement })
+            .await
+            .map_err(|err| PeerConnectionError::InternalError(err.to_string()))
+    }
+
     pub async fn outgoing_request(
         &mut self,
         message: Message,
@@ -143,6 +159,17 @@ impl PeerConnection {
             }
         }
     }
+
+    /// Sends a Disconnect message to the remote peer and closes the connection
+    pub async fn disconnect(
+        &mut self,
+        reason: DisconnectReason,
+    ) -> Result<(), PeerConnectionError> {
+        self.handle
+            .cast(CastMessage::Disconnect(reason))
+            .await
+            .map_err(|err| PeerConnectionError::InternalError(err.to_string()))
+    }
 }
 
 #[derive(Debug)]
@@ -155,15 +182,15 @@ pub struct Initiator {
 pub struct Receiver {
     pub(crate) context: P2PContext,
     pub(crate) peer_addr: SocketAddr,
-    pub(crate) stream: Arc<TcpStream>,
+    pub(crate) stream: Arc<BoxedStream>,
 }
 
 #[derive(Debug)]
 pub struct Established {
     pub(crate) signer: SecretKey,
-    // Sending part of the TcpStream to connect with the remote peer
+    // Sending part of the stream to connect with the remote peer
     // The receiving part is owned by the stream listen loop task
-    pub(crate) sink: SplitSink<Framed<TcpStream, RLPxCodec>, Message>,
+    pub(crate) sink: SplitSink<Framed<BoxedStream, RLPxCodec>, Message>,
     pub(crate) node: Node,
     pub(crate) storage: Store,
     pub(crate) blockchain: Arc<Blockchain>,
@@ -188,11 +215,18 @@ pub struct Established {
     #[cfg(feature = "l2")]
     pub(crate) l2_state: L2ConnState,
     pub(crate) tx_broadcaster: GenServerHandle<TxBroadcaster>,
+    pub(crate) tx_fetcher: GenServerHandle<TxFetcher>,
     pub(crate) current_requests: HashMap<u64, (String, oneshot::Sender<Message>)>,
     // We store the disconnection reason to handle it in the teardown
     pub(crate) disconnect_reason: Option<DisconnectReason>,
+    // Limits the rate of snap requests served to the peer
+    pub(crate) snap_rate_limiter: SnapRateLimiter,
+    // Whether snap/2 is advertised to the peer
+    pub(crate) snap_v2: bool,
     // Indicates if the peer has been validated (ie. the connection was established successfully)
     pub(crate) is_validated: bool,
+    #[cfg(feature = "test-utils")]
+    pub(crate) interceptor: Option<Arc<dyn MessageInterceptor>>,
 }
 
 impl Established {
@@ -226,10 +260,17 @@ pub enum CastMessage {
     OutgoingRequest(Message, Arc<oneshot::Sender<Message>>),
     /// Received a notification of a request that timeouted.
     RequestTimeout { id: u64 },
+    /// We request announced transactions from the remote peer on behalf of the transaction fetcher
+    FetchPooledTransactions {
+        id: u64,
+        announcement: NewPooledTransactionHashes,
+    },
     /// Periodic message to send ping to remote peer
     SendPing,
     /// Periodic message to send block range update to remote peer
     BlockRangeUpdate,
+    /// Disconnect from the remote peer with the given reason
+    Disconnect(DisconnectReason),
     /// Received a message to broadcast. Used only for L2, we have to move this logic to tx_broadcaster.
     BroadcastMessage(task::Id, Arc<Message>),
     /// L2 message
@@ -240,7 +281,7 @@ pub enum CastMessage {
 pub enum OutMessage {
     InitResponse {
         node: Node,
-        framed: Arc<Mutex<Framed<TcpStream, RLPxCodec>>>,
+        framed: Arc<Mutex<Framed<BoxedStream, RLPxCodec>>>,
     },
     Done,
     Error,
@@ -370,9 +411,23 @@ impl GenServer for PeerConnectionServer {
                     }
                     Ok(())
                 }
+                Self::CastMsg::FetchPooledTransactions { id, announcement } => {
+                    let request =
+                        GetPooledTransactions::new(id, announcement.transaction_hashes.clone());
+                    established_state
+                        .requested_pooled_txs
+                        .insert(id, announcement);
+                    send(established_state, Message::GetPooledTransactions(request)).await
+                }
                 Self::CastMsg::SendPing => {
                     send(established_state, Message::Ping(PingMessage {})).await
                 }
+                Self::CastMsg::Disconnect(reason) => {
+                    debug!(peer=%established_state.node, %reason, "Disconnecting from peer");
+                    send_disconnect_message(established_state, Some(reason)).await;
+                    established_state.disconnect_reason = Some(reason);
+                    Err(PeerConnectionError::DisconnectSent(reason))
+                }
                 Self::CastMsg::BroadcastMessage(id, msg) => {
                     trace!(
                         peer=%established_state.node,
@@ -517,9 +572,18 @@ async fn initialize_connection<S>(
 where
     S: Unpin + Send + Stream<Item = Result<Message, PeerConnectionError>> + 'static,
 {
-    if state.peer_table.target_peers_reached().await? {
-        debug!(peer=%state.node, "Reached target peer connections, discarding.");
-        return Err(PeerConnectionError::TooManyPeers);
+    match state.peer_table.check_admission(&state.node).await? {
+        PeerAdmission::Accepted => {}
+        PeerAdmission::TooManyPeers => {
+            debug!(peer=%state.node, "Reached target peer connections, discarding.");
+            return Err(PeerConnectionError::TooManyPeers);
+        }
+        PeerAdmission::Banned | PeerAdmission::TemporarilyBanned | PeerAdmission::Restricted => {
+            debug!(peer=%state.node, "Peer is banned or outside the allowed networks, discarding.");
+            return Err(PeerConnectionError::DisconnectSent(
+                DisconnectReason::UselessPeer,
+            ));
+        }
     }
     exchange_hello_messages(state, &mut stream).await?;
 
@@ -539,13 +603,19 @@ where
         handle: handle.clone(),
     };
 
+    // Only the negotiated snap version can be used with this peer, so it is the only one registered.
+    // This keeps requests removed in newer versions (e.g. GetTrieNodes) from being sent to it.
+    let capabilities = state
+        .capabilities
+        .iter()
+        .filter(|cap| {
+            cap.protocol() != "snap" || state.negotiated_snap_capability.as_ref() == Some(*cap)
+        })
+        .cloned()
+        .collect();
     state
         .peer_table
-        .new_connected_peer(
-            state.node.clone(),
-            connection.clone(),
-            state.capabilities.clone(),
-        )
+        .new_connected_peer(state.node.clone(), connection.clone(), capabilities)
         .await?;
 
     trace!(peer=%state.node, "Peer connection initialized.");
@@ -673,15 +743,7 @@ where
 {
     // Sending eth Status if peer supports it
     if let Some(eth) = state.negotiated_eth_capability.clone() {
-        let status = match eth.version {
-            68 => Message::Status68(StatusMessage68::new(&state.storage).await?),
-            69 => Message::Status69(StatusMessage69::new(&state.storage).await?),
-            ver => {
-                return Err(PeerConnectionError::HandshakeError(format!(
-                    "Invalid eth version {ver}"
-                )));
-            }
-        };
+        let status = local_status(&state.storage, &eth).await?;
         trace!(peer=%state.node, "Sending status");
         send(state, status).await?;
         // The next immediate message in the ETH protocol is the
@@ -716,6 +778,116 @@ where
     Ok(())
 }
 
+async fn local_status(storage: &Store, eth: &Capability) -> Result<Message, PeerConnectionError> {
+    match eth.version {
+        68 => Ok(Message::Status68(StatusMessage68::new(storage).await?)),
+        69 => Ok(Message::Status69(StatusMessage69::new(storage).await?)),
+        ver => Err(PeerConnectionError::HandshakeError(format!(
+            "Invalid eth version {ver}"
+        ))),
+    }
+}
+
+/// What a node told about itself during the RLPx and eth handshakes, see [`probe`]
+#[derive(Debug, Clone)]
+pub struct PeerProbe {
+    pub client_id: Option<String>,
+    pub capabilities: Vec<Capability>,
+    /// The eth Status of the node, None if it doesn't support any of our eth versions
+    pub status: Option<ProbedStatus>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbedStatus {
+    pub eth_version: u8,
+    pub network_id: u64,
+    pub genesis: H256,
+    pub fork_id: ForkId,
+    pub head_hash: H256,
+    /// Only advertised since eth/69
+    pub head_number: Option<u64>,
+}
+
+/// Connects to the node, performs the RLPx and eth handshakes without registering it as a peer and
+/// disconnects, returning what the node told about itself
+pub async fn probe(context: P2PContext, node: &Node) -> Result<PeerProbe, PeerConnectionError> {
+    let eth_version = Arc::new(RwLock::new(EthCapVersion::default()));
+    let initiator = ConnectionState::Initiator(Initiator {
+        context,
+        node: node.clone(),
+    });
+    let (mut state, mut stream) = handshake::perform(initiator, eth_version.clone()).await?;
+    let result = probe_established(&mut state, &mut stream, eth_version).await;
+    send_disconnect_message(&mut state, Some(DisconnectReason::ClientQuitting)).await;
+    state.teardown().await;
+    result
+}
+
+async fn probe_established<S>(
+    state: &mut Established,
+    stream: &mut S,
+    eth_version: Arc<RwLock<EthCapVersion>>,
+) -> Result<PeerProbe, PeerConnectionError>
+where
+    S: Unpin + Stream<Item = Result<Message, PeerConnectionError>>,
+{
+    let hello = exchange_hello_messages(state, stream).await;
+    let mut probe = PeerProbe {
+        client_id: state.node.version.clone(),
+        capabilities: state.capabilities.clone(),
+        status: None,
+    };
+    match hello {
+        Ok(()) => {}
+        // Nodes without a common eth version are still part of the census
+        Err(PeerConnectionError::NoMatchingCapabilities) => return Ok(probe),
+        Err(error) => return Err(error),
+    }
+    let Some(eth) = state.negotiated_eth_capability.clone() else {
+        return Ok(probe);
+    };
+    *eth_version
+        .write()
+        .map_err(|err| PeerConnectionError::InternalError(err.to_string()))? =
+        if eth == Capability::eth(69) {
+            EthCapVersion::V69
+        } else {
+            EthCapVersion::V68
+        };
+
+    let status = local_status(&state.storage, &eth).await?;
+    send(state, status).await?;
+    probe.status = match receive(stream).await {
+        Some(Ok(Message::Status68(msg))) => Some(ProbedStatus {
+            eth_version: msg.eth_version,
+            network_id: msg.network_id,
+            genesis: msg.genesis,
+            fork_id: msg.fork_id,
+            head_hash: msg.block_hash,
+            head_number: None,
+        }),
+        Some(Ok(Message::Status69(msg))) => Some(ProbedStatus {
+            eth_version: msg.eth_version,
+            network_id: msg.network_id,
+            genesis: msg.genesis,
+            fork_id: msg.fork_id,
+            head_hash: msg.lastest_block_hash,
+            head_number: Some(msg.lastest_block),
+        }),
+        Some(Ok(Message::Disconnect(disconnect))) => {
+            return Err(PeerConnectionError::DisconnectReceived(disconnect.reason()));
+        }
+        Some(Ok(_)) => {
+            return Err(PeerConnectionError::HandshakeError(
+                "Expected a Status message".to_string(),
+            ));
+        }
+        Some(Err(error)) => return Err(error),
+        None => return Err(PeerConnectionError::Disconnected),
+    };
+    Ok(probe)
+}
+
 async fn send_disconnect_message(state: &mut Established, reason: Option<DisconnectReason>) {
     send(state, Message::Disconnect(DisconnectMessage { reason }))
         .await
@@ -789,7 +961,7 @@ where
     #[allow(unused_mut)]
     let mut supported_capabilities: Vec<Capability> = [
         &SUPPORTED_ETH_CAPABILITIES[..],
-        &SUPPORTED_SNAP_CAPABILITIES[..],
+        advertised_snap_capabilities(state.snap_v2),
     ]
     .concat();
     #[cfg(feature = "l2")]
@@ -813,7 +985,6 @@ where
     match msg {
         Message::Hello(hello_message) => {
             let mut negotiated_eth_version = 0;
-            let mut negotiated_snap_version = 0;
 
             trace!(
                 peer=%state.node,
@@ -831,13 +1002,6 @@ where
                             negotiated_eth_version = cap.version;
                         }
                     }
-                    "snap" => {
-                        if SUPPORTED_SNAP_CAPABILITIES.contains(cap)
-                            && cap.version > negotiated_snap_version
-                        {
-                            negotiated_snap_version = cap.version;
-                        }
-                    }
                     #[cfg(feature = "l2")]
                     "based" if state.l2_state.is_supported() => {
                         state.l2_state.set_established()?;
@@ -846,7 +1010,10 @@ where
                 }
             }
 
+            let negotiated_snap_capability =
+                negotiate_snap_capability(&hello_message.capabilities, state.snap_v2);
             state.capabilities = hello_message.capabilities;
+            state.node.version = Some(hello_message.client_id);
 
             if negotiated_eth_version == 0 {
                 return Err(PeerConnectionError::NoMatchingCapabilities);
@@ -854,13 +1021,11 @@ where
             debug!("Negotatied eth version: eth/{}", negotiated_eth_version);
             state.negotiated_eth_capability = Some(Capability::eth(negotiated_eth_version));
 
-            if negotiated_snap_version != 0 {
-                debug!("Negotatied snap version: snap/{}", negotiated_snap_version);
-                state.negotiated_snap_capability = Some(Capability::snap(negotiated_snap_version));
+            if let Some(capability) = negotiated_snap_capability {
+                debug!("Negotatied snap version: snap/{}", capability.version);
+                state.negotiated_snap_capability = Some(capability);
             }
 
-            state.node.version = Some(hello_message.client_id);
-
             Ok(())
         }
         Message::Disconnect(disconnect) => {
@@ -875,10 +1040,25 @@ where
     }
 }
 
+/// Hook that can tamper with the messages sent to peers, used to simulate misbehaving nodes
+#[cfg(feature = "test-utils")]
+pub trait MessageInterceptor: std::fmt::Debug + Send + Sync {
+    /// Returns the message to send instead, or None to drop it
+    fn outgoing(&self, peer: &Node, message: Message) -> Option<Message>;
+}
+
 pub(crate) async fn send(
     state: &mut Established,
     message: Message,
 ) -> Result<(), PeerConnectionError> {
+    #[cfg(feature = "test-utils")]
+    let message = match &state.interceptor {
+        Some(interceptor) => match interceptor.outgoing(&state.node, message) {
+            Some(message) => message,
+            None => return Ok(()),
+        },
+        None => message,
+    };
     state.sink.send(message).await
 }
 
@@ -905,6 +1085,7 @@ async fn handle_incoming_message(
     message: Message,
 ) -> Result<(), PeerConnectionError> {
     let peer_supports_eth = state.negotiated_eth_capability.is_some();
+    let peer_supports_snap2 = state.negotiated_snap_capability == Some(Capability::snap(2));
     #[cfg(feature = "l2")]
     let peer_supports_l2 = state.l2_state.connection_state().is_ok();
     match message {
@@ -939,7 +1120,15 @@ async fn handle_incoming_message(
             };
         }
         Message::GetAccountRange(req) => {
-            let response = process_account_range_request(req, state.storage.clone()).await?;
+            let response = if snap_request_allowed(state) {
+                process_account_range_request(req, state.storage.clone()).await?
+            } else {
+                AccountRange {
+                    id: req.id,
+                    accounts: vec![],
+                    proof: vec![],
+                }
+            };
             send(state, Message::AccountRange(response)).await?
         }
         Message::Transactions(txs) if peer_supports_eth => {
@@ -967,12 +1156,15 @@ async fn handle_incoming_message(
                         continue;
                     }
                 }
+                let hashes: Vec<_> = txs.transactions.iter().map(|tx| tx.hash()).collect();
+                state
+                    .tx_fetcher
+                    .cast(tx_fetcher::InMessage::Received(hashes.clone()))
+                    .await
+                    .map_err(|e| PeerConnectionError::BroadcastError(e.to_string()))?;
                 state
                     .tx_broadcaster
-                    .cast(InMessage::AddTxs(
-                        txs.transactions.iter().map(|tx| tx.hash()).collect(),
-                        state.node.node_id(),
-                    ))
+                    .cast(InMessage::AddTxs(hashes, state.node.node_id()))
                     .await
                     .map_err(|e| PeerConnectionError::BroadcastError(e.to_string()))?;
             }
@@ -1030,13 +1222,23 @@ async fn handle_incoming_message(
             }
         }
         Message::NewPooledTransactionHashes(new_pooled_transaction_hashes) if peer_supports_eth => {
-            let hashes =
-                new_pooled_transaction_hashes.get_transactions_to_request(&state.blockchain)?;
-            let request = GetPooledTransactions::new(random(), hashes);
+            // The peer knows these transactions, so there's no need to announce them back
+            state
+                .tx_broadcaster
+                .cast(InMessage::AddTxs(
+                    new_pooled_transaction_hashes.transaction_hashes.clone(),
+                    state.node.node_id(),
+                ))
+                .await
+                .map_err(|e| PeerConnectionError::BroadcastError(e.to_string()))?;
             state
-                .requested_pooled_txs
-                .insert(request.id, new_pooled_transaction_hashes);
-            send(state, Message::GetPooledTransactions(request)).await?;
+                .tx_fetcher
+                .cast(tx_fetcher::InMessage::Announced(
+                    state.node.node_id(),
+                    new_pooled_transaction_hashes,
+                ))
+                .await
+                .map_err(|e| PeerConnectionError::BroadcastError(e.to_string()))?;
         }
         Message::GetPooledTransactions(msg) => {
             let response = msg.handle(&state.blockchain)?;
@@ -1078,6 +1280,19 @@ async fn handle_incoming_message(
                         ));
                     } else {
                         state.requested_pooled_txs.remove(&msg.id);
+                        state
+                            .tx_fetcher
+                            .cast(tx_fetcher::InMessage::Delivered {
+                                peer_id: state.node.node_id(),
+                                id: msg.id,
+                                hashes: msg
+                                    .pooled_transactions
+                                    .iter()
+                                    .map(|tx| tx.compute_hash())
+                                    .collect(),
+                            })
+                            .await
+                            .map_err(|e| PeerConnectionError::BroadcastError(e.to_string()))?;
                     }
                 }
                 #[cfg(feature = "l2")]
@@ -1090,25 +1305,61 @@ async fn handle_incoming_message(
             }
         }
         Message::GetStorageRanges(req) => {
-            let response = process_storage_ranges_request(req, state.storage.clone()).await?;
+            let response = if snap_request_allowed(state) {
+                process_storage_ranges_request(req, state.storage.clone()).await?
+            } else {
+                StorageRanges {
+                    id: req.id,
+                    slots: vec![],
+                    proof: vec![],
+                }
+            };
             send(state, Message::StorageRanges(response)).await?
         }
         Message::GetByteCodes(req) => {
-            let storage_clone = state.storage.clone();
-            let response = process_byte_codes_request(req, storage_clone).map_err(|_| {
-                PeerConnectionError::InternalError(
-                    "Failed to execute bytecode retrieval task".to_string(),
-                )
-            })?;
+            let response = if snap_request_allowed(state) {
+                let storage_clone = state.storage.clone();
+                process_byte_codes_request(req, storage_clone).map_err(|_| {
+                    PeerConnectionError::InternalError(
+                        "Failed to execute bytecode retrieval task".to_string(),
+                    )
+                })?
+            } else {
+                ByteCodes {
+                    id: req.id,
+                    codes: vec![],
+                }
+            };
             send(state, Message::ByteCodes(response)).await?
         }
         Message::GetTrieNodes(req) => {
             let id = req.id;
+            if !snap_request_allowed(state) {
+                send(state, Message::TrieNodes(TrieNodes { id, nodes: vec![] })).await?;
+                return Ok(());
+            }
             match process_trie_nodes_request(req, state.storage.clone()).await {
                 Ok(response) => send(state, Message::TrieNodes(response)).await?,
                 Err(_) => send(state, Message::TrieNodes(TrieNodes { id, nodes: vec![] })).await?,
             }
         }
+        Message::GetBlockAccessLists(req) if peer_supports_snap2 => {
+            let response = if snap_request_allowed(state) {
+                process_block_access_lists_request(req, state.storage.clone())
+                    .await
+                    .map_err(|_| {
+                        PeerConnectionError::InternalError(
+                            "Failed to execute block access lists retrieval task".to_string(),
+                        )
+                    })?
+            } else {
+                BlockAccessLists {
+                    id: req.id,
+                    access_lists: vec![],
+                }
+            };
+            send(state, Message::BlockAccessLists(response)).await?
+        }
         #[cfg(feature = "l2")]
         Message::L2(req) if peer_supports_l2 => {
             handle_based_capability_message(state, req).await?;
@@ -1118,6 +1369,7 @@ async fn handle_incoming_message(
         | message @ Message::StorageRanges(_)
         | message @ Message::ByteCodes(_)
         | message @ Message::TrieNodes(_)
+        | message @ Message::BlockAccessLists(_)
         | message @ Message::BlockBodies(_)
         | message @ Message::BlockHeaders(_)
         | message @ Message::Receipts68(_)
@@ -1138,6 +1390,16 @@ async fn handle_incoming_message(
     Ok(())
 }
 
+/// Returns false if the peer exceeded its snap request rate, in which case the request gets an
+/// empty response, which the snap protocol allows when a node can't serve it
+fn snap_request_allowed(state: &mut Established) -> bool {
+    let allowed = state.snap_rate_limiter.try_acquire();
+    if !allowed {
+        debug!(peer=%state.node, "Snap request rate exceeded, sending empty response");
+    }
+    allowed
+}
+
 async fn handle_outgoing_message(
     state: &mut Established,
     message: Message,
@@ -12,24 +12,11 @@ pub async fn validate_status<ST: StatusMessage>(
 ) -> Result<(), PeerConnectionError> {
     let chain_config = storage.get_chain_config();
 
-    // These blocks must always be available
-    let genesis_header = storage
+    // This block must always be available
+    let genesis_hash = storage
         .get_block_header(0)?
-        .ok_or(PeerConnectionError::NotFound("Genesis Block".to_string()))?;
-    let genesis_hash = genesis_header.hash();
-    let latest_block_number = storage.get_latest_block_number().await?;
-    let latest_block_header =
-        storage
-            .get_block_header(latest_block_number)?
-            .ok_or(PeerConnectionError::NotFound(format!(
-                "Block {latest_block_number}"
-            )))?;
-    let fork_id = ForkId::new(
-        chain_config,
-        genesis_header.clone(),
-        latest_block_header.timestamp,
-        latest_block_number,
-    );
+        .ok_or(PeerConnectionError::NotFound("Genesis Block".to_string()))?
+        .hash();
 
     //Check networkID
     if msg_data.get_network_id() != chain_config.chain_id {
@@ -50,13 +37,7 @@ pub async fn validate_status<ST: StatusMessage>(
         ));
     }
     // Check ForkID
-    if !fork_id.is_valid(
-        msg_data.get_fork_id(),
-        latest_block_number,
-        latest_block_header.timestamp,
-        chain_config,
-        genesis_header,
-    ) {
+    if !is_fork_id_valid(msg_data.get_fork_id(), storage).await? {
         return Err(PeerConnectionError::HandshakeError(
             "Invalid Fork Id".to_string(),
         ));
@@ -65,6 +46,40 @@ pub async fn validate_status<ST: StatusMessage>(
     Ok(())
 }
 
+/// Checks whether a remote fork id is compatible with our chain, as defined in EIP-2124
+pub async fn is_fork_id_valid(
+    remote_fork_id: ForkId,
+    storage: &Store,
+) -> Result<bool, PeerConnectionError> {
+    let chain_config = storage.get_chain_config();
+
+    // These blocks must always be available
+    let genesis_header = storage
+        .get_block_header(0)?
+        .ok_or(PeerConnectionError::NotFound("Genesis Block".to_string()))?;
+    let latest_block_number = storage.get_latest_block_number().await?;
+    let latest_block_header =
+        storage
+            .get_block_header(latest_block_number)?
+            .ok_or(PeerConnectionError::NotFound(format!(
+                "Block {latest_block_number}"
+            )))?;
+    let fork_id = ForkId::new(
+        &chain_config,
+        genesis_header.clone(),
+        latest_block_header.timestamp,
+        latest_block_number,
+    );
+
+    Ok(fork_id.is_valid(
+        remote_fork_id,
+        latest_block_number,
+        latest_block_header.timestamp,
+        &chain_config,
+        genesis_header,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::validate_status;
@@ -94,11 +109,11 @@ mod tests {
             .add_initial_state(genesis.clone())
             .await
             .expect("Failed to add genesis block to DB");
-        let config = genesis.config;
+        let config = genesis.config.clone();
         let total_difficulty = U256::from(config.terminal_total_difficulty.unwrap_or_default());
         let genesis_header = genesis.get_block().header;
         let genesis_hash = genesis_header.hash();
-        let fork_id = ForkId::new(config, genesis_header, 2707305664, 123);
+        let fork_id = ForkId::new(&config, genesis_header, 2707305664, 123);
 
         let eth = Capability::eth(68);
         let message = StatusMessage68 {
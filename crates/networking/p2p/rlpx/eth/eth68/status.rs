@@ -96,7 +96,7 @@ impl StatusMessage68 {
         let genesis = genesis_header.hash();
         let lastest_block_hash = block_header.hash();
         let fork_id = ForkId::new(
-            chain_config,
+            &chain_config,
             genesis_header,
             block_header.timestamp,
             lastest_block,
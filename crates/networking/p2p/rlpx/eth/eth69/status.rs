@@ -95,18 +95,21 @@ impl StatusMessage69 {
         let genesis = genesis_header.hash();
         let lastest_block_hash = block_header.hash();
         let fork_id = ForkId::new(
-            chain_config,
+            &chain_config,
             genesis_header,
             block_header.timestamp,
             lastest_block,
         );
 
+        // Blocks before the earliest one may have had their history pruned
+        let earliest_block = storage.get_earliest_block_number().await?;
+
         Ok(StatusMessage69 {
             eth_version: 69,
             network_id,
             genesis,
             fork_id,
-            earliest_block: 0,
+            earliest_block,
             lastest_block,
             lastest_block_hash,
         })
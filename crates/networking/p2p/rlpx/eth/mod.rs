@@ -1,5 +1,5 @@
 pub(crate) mod backend;
-pub(crate) mod blocks;
+pub mod blocks;
 mod eth68;
 mod eth69;
 pub(crate) mod receipts;
@@ -117,6 +117,33 @@ impl NewPooledTransactionHashes {
             .mempool
             .filter_unknown_transactions(&self.transaction_hashes)
     }
+
+    /// Returns the announced (hash, type, size) triples
+    pub fn entries(&self) -> impl Iterator<Item = (H256, u8, usize)> + '_ {
+        self.transaction_hashes
+            .iter()
+            .zip(self.transaction_types.iter())
+            .zip(self.transaction_sizes.iter())
+            .map(|((hash, tx_type), size)| (*hash, *tx_type, *size))
+    }
+}
+
+impl FromIterator<(H256, u8, usize)> for NewPooledTransactionHashes {
+    fn from_iter<T: IntoIterator<Item = (H256, u8, usize)>>(iter: T) -> Self {
+        let mut transaction_types = Vec::new();
+        let mut transaction_sizes = Vec::new();
+        let mut transaction_hashes = Vec::new();
+        for (hash, tx_type, size) in iter {
+            transaction_hashes.push(hash);
+            transaction_types.push(tx_type);
+            transaction_sizes.push(size);
+        }
+        Self {
+            transaction_types: transaction_types.into(),
+            transaction_sizes,
+            transaction_hashes,
+        }
+    }
 }
 
 impl RLPxMessage for NewPooledTransactionHashes {
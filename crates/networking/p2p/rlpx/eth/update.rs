@@ -28,9 +28,11 @@ impl BlockRangeUpdate {
                     "Block {latest_block}"
                 )))?;
         let latest_block_hash = block_header.hash();
+        // Blocks before the earliest one may have had their history pruned
+        let earliest_block = storage.get_earliest_block_number().await?;
 
         Ok(Self {
-            earliest_block: 0,
+            earliest_block,
             latest_block,
             latest_block_hash,
         })
@@ -38,6 +38,12 @@ impl RLPxInitiator {
     }
 
     async fn look_for_peer(&mut self) -> Result<(), RLPxInitiatorError> {
+        // Static peers are dialed regardless of the peer limit
+        for node in self.context.table.get_static_peers_to_dial().await? {
+            debug!(peer=%node, "Dialing static peer");
+            PeerConnection::spawn_as_initiator(self.context.clone(), &node);
+            METRICS.record_new_rlpx_conn_attempt().await;
+        }
         if !self.context.table.target_peers_reached().await? {
             if let Some(contact) = self.context.table.get_contact_to_initiate().await? {
                 PeerConnection::spawn_as_initiator(self.context.clone(), &contact.node);
@@ -3,8 +3,8 @@ use ethrex_rlp::error::{RLPDecodeError, RLPEncodeError};
 use std::fmt::Display;
 
 use crate::rlpx::snap::{
-    AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
-    StorageRanges, TrieNodes,
+    AccountRange, BlockAccessLists, ByteCodes, GetAccountRange, GetBlockAccessLists, GetByteCodes,
+    GetStorageRanges, GetTrieNodes, StorageRanges, TrieNodes,
 };
 
 use super::eth::blocks::{BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders};
@@ -94,6 +94,9 @@ pub enum Message {
     ByteCodes(ByteCodes),
     GetTrieNodes(GetTrieNodes),
     TrieNodes(TrieNodes),
+    // snap/2 only
+    GetBlockAccessLists(GetBlockAccessLists),
+    BlockAccessLists(BlockAccessLists),
     // based capability
     #[cfg(feature = "l2")]
     L2(messages::L2Message),
@@ -147,6 +150,12 @@ impl Message {
             Message::ByteCodes(_) => eth_version.snap_capability_offset() + ByteCodes::CODE,
             Message::GetTrieNodes(_) => eth_version.snap_capability_offset() + GetTrieNodes::CODE,
             Message::TrieNodes(_) => eth_version.snap_capability_offset() + TrieNodes::CODE,
+            Message::GetBlockAccessLists(_) => {
+                eth_version.snap_capability_offset() + GetBlockAccessLists::CODE
+            }
+            Message::BlockAccessLists(_) => {
+                eth_version.snap_capability_offset() + BlockAccessLists::CODE
+            }
 
             #[cfg(feature = "l2")]
             // based capability
@@ -227,6 +236,12 @@ impl Message {
                 ByteCodes::CODE => Ok(Message::ByteCodes(ByteCodes::decode(data)?)),
                 GetTrieNodes::CODE => Ok(Message::GetTrieNodes(GetTrieNodes::decode(data)?)),
                 TrieNodes::CODE => Ok(Message::TrieNodes(TrieNodes::decode(data)?)),
+                GetBlockAccessLists::CODE => Ok(Message::GetBlockAccessLists(
+                    GetBlockAccessLists::decode(data)?,
+                )),
+                BlockAccessLists::CODE => {
+                    Ok(Message::BlockAccessLists(BlockAccessLists::decode(data)?))
+                }
                 _ => Err(RLPDecodeError::MalformedData),
             }
         } else {
@@ -284,6 +299,8 @@ impl Message {
             Message::ByteCodes(msg) => msg.encode(buf),
             Message::GetTrieNodes(msg) => msg.encode(buf),
             Message::TrieNodes(msg) => msg.encode(buf),
+            Message::GetBlockAccessLists(msg) => msg.encode(buf),
+            Message::BlockAccessLists(msg) => msg.encode(buf),
             #[cfg(feature = "l2")]
             Message::L2(l2_msg) => match l2_msg {
                 L2Message::BatchSealed(msg) => msg.encode(buf),
@@ -302,6 +319,7 @@ impl Message {
             Message::GetStorageRanges(message) => Some(message.id),
             Message::GetByteCodes(message) => Some(message.id),
             Message::GetTrieNodes(message) => Some(message.id),
+            Message::GetBlockAccessLists(message) => Some(message.id),
             Message::BlockHeaders(message) => Some(message.id),
             Message::BlockBodies(message) => Some(message.id),
             Message::PooledTransactions(message) => Some(message.id),
@@ -311,6 +329,7 @@ impl Message {
             Message::StorageRanges(message) => Some(message.id),
             Message::ByteCodes(message) => Some(message.id),
             Message::TrieNodes(message) => Some(message.id),
+            Message::BlockAccessLists(message) => Some(message.id),
             // The rest of the message types does not have a request id.
             Message::Hello(_)
             | Message::Disconnect(_)
@@ -356,6 +375,8 @@ impl Display for Message {
             Message::ByteCodes(_) => "snap:ByteCodes".fmt(f),
             Message::GetTrieNodes(_) => "snap:GetTrieNodes".fmt(f),
             Message::TrieNodes(_) => "snap:TrieNodes".fmt(f),
+            Message::GetBlockAccessLists(_) => "snap:GetBlockAccessLists".fmt(f),
+            Message::BlockAccessLists(_) => "snap:BlockAccessLists".fmt(f),
             #[cfg(feature = "l2")]
             Message::L2(l2_msg) => match l2_msg {
                 L2Message::BatchSealed(_) => "based:BatchSealed".fmt(f),
@@ -15,7 +15,32 @@ use secp256k1::PublicKey;
 use serde::Serialize;
 
 pub const SUPPORTED_ETH_CAPABILITIES: [Capability; 2] = [Capability::eth(68), Capability::eth(69)];
-pub const SUPPORTED_SNAP_CAPABILITIES: [Capability; 1] = [Capability::snap(1)];
+/// Snap versions we can serve, the highest version shared with the peer is negotiated.
+/// snap/2 adds block access lists and is only advertised when enabled, see [`advertised_snap_capabilities`]
+pub const SUPPORTED_SNAP_CAPABILITIES: [Capability; 2] = [Capability::snap(1), Capability::snap(2)];
+/// Snap versions serving `GetTrieNodes`, which was dropped in snap/2
+pub const TRIE_NODES_SNAP_CAPABILITIES: [Capability; 1] = [Capability::snap(1)];
+
+/// Returns the snap versions announced in our hello message. snap/2 is still a draft so it is opt-in.
+pub fn advertised_snap_capabilities(snap_v2: bool) -> &'static [Capability] {
+    if snap_v2 {
+        &SUPPORTED_SNAP_CAPABILITIES
+    } else {
+        &SUPPORTED_SNAP_CAPABILITIES[..1]
+    }
+}
+
+/// Returns the highest snap version announced by the peer that we also advertise
+pub fn negotiate_snap_capability(
+    peer_capabilities: &[Capability],
+    snap_v2: bool,
+) -> Option<Capability> {
+    peer_capabilities
+        .iter()
+        .filter(|cap| advertised_snap_capabilities(snap_v2).contains(cap))
+        .max_by_key(|cap| cap.version)
+        .cloned()
+}
 
 /// The version of the base P2P protocol we support.
 /// This is sent at the start of the Hello message instead of the capabilities list.
@@ -386,7 +411,7 @@ impl RLPxMessage for PongMessage {
 mod tests {
     use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 
-    use crate::rlpx::p2p::Capability;
+    use crate::rlpx::p2p::{Capability, advertised_snap_capabilities, negotiate_snap_capability};
 
     #[test]
     fn test_encode_capability() {
@@ -404,6 +429,43 @@ mod tests {
         assert_eq!(decoded, Capability::eth(8));
     }
 
+    #[test]
+    fn snap_v2_is_only_negotiated_when_enabled() {
+        assert_eq!(advertised_snap_capabilities(false), &[Capability::snap(1)]);
+        assert_eq!(
+            advertised_snap_capabilities(true),
+            &[Capability::snap(1), Capability::snap(2)]
+        );
+
+        let both = [
+            Capability::eth(68),
+            Capability::snap(1),
+            Capability::snap(2),
+        ];
+        assert_eq!(
+            negotiate_snap_capability(&both, false),
+            Some(Capability::snap(1))
+        );
+        assert_eq!(
+            negotiate_snap_capability(&both, true),
+            Some(Capability::snap(2))
+        );
+        // A snap/1 only peer keeps using snap/1 with nodes that enabled snap/2
+        assert_eq!(
+            negotiate_snap_capability(&[Capability::snap(1)], true),
+            Some(Capability::snap(1))
+        );
+        // Unknown versions and peers without snap negotiate nothing
+        assert_eq!(
+            negotiate_snap_capability(&[Capability::snap(3)], true),
+            None
+        );
+        assert_eq!(
+            negotiate_snap_capability(&[Capability::eth(69)], true),
+            None
+        );
+    }
+
     #[test]
     fn test_protocol() {
         let capability = Capability::eth(68);
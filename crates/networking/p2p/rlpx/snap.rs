@@ -80,6 +80,22 @@ pub struct TrieNodes {
     pub nodes: Vec<Bytes>,
 }
 
+/// snap/2 request replacing `GetTrieNodes`, healing is done by applying the block access lists
+/// of the blocks between the old and the new pivot
+#[derive(Debug, Clone)]
+pub struct GetBlockAccessLists {
+    pub id: u64,
+    pub block_hashes: Vec<H256>,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockAccessLists {
+    pub id: u64,
+    /// RLP encoded access list of each requested block, or an empty entry if it is not available
+    pub access_lists: Vec<Bytes>,
+}
+
 impl RLPxMessage for GetAccountRange {
     const CODE: u8 = 0x00;
     fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
@@ -336,6 +352,62 @@ impl RLPxMessage for TrieNodes {
     }
 }
 
+impl RLPxMessage for GetBlockAccessLists {
+    const CODE: u8 = 0x08;
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&self.id)
+            .encode_field(&self.block_hashes)
+            .encode_field(&self.bytes)
+            .finish();
+
+        let msg_data = snappy_compress(encoded_data)?;
+        buf.put_slice(&msg_data);
+        Ok(())
+    }
+
+    fn decode(msg_data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let decompressed_data = snappy_decompress(msg_data)?;
+        let decoder = Decoder::new(&decompressed_data)?;
+        let (id, decoder) = decoder.decode_field("request-id")?;
+        let (block_hashes, decoder) = decoder.decode_field("blockHashes")?;
+        let (bytes, decoder) = decoder.decode_field("bytes")?;
+        decoder.finish()?;
+
+        Ok(Self {
+            id,
+            block_hashes,
+            bytes,
+        })
+    }
+}
+
+impl RLPxMessage for BlockAccessLists {
+    const CODE: u8 = 0x09;
+    fn encode(&self, buf: &mut dyn BufMut) -> Result<(), RLPEncodeError> {
+        let mut encoded_data = vec![];
+        Encoder::new(&mut encoded_data)
+            .encode_field(&self.id)
+            .encode_field(&self.access_lists)
+            .finish();
+
+        let msg_data = snappy_compress(encoded_data)?;
+        buf.put_slice(&msg_data);
+        Ok(())
+    }
+
+    fn decode(msg_data: &[u8]) -> Result<Self, RLPDecodeError> {
+        let decompressed_data = snappy_decompress(msg_data)?;
+        let decoder = Decoder::new(&decompressed_data)?;
+        let (id, decoder) = decoder.decode_field("request-id")?;
+        let (access_lists, decoder) = decoder.decode_field("accessLists")?;
+        decoder.finish()?;
+
+        Ok(Self { id, access_lists })
+    }
+}
+
 // Intermediate structures
 
 #[derive(Debug, Clone)]
@@ -1,16 +1,89 @@
 use bytes::Bytes;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_storage::{Store, error::StoreError};
+use std::time::{Duration, Instant};
 
 use crate::rlpx::{
     error::PeerConnectionError,
     snap::{
-        AccountRange, AccountRangeUnit, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges,
-        GetTrieNodes, StorageRanges, StorageSlot, TrieNodes,
+        AccountRange, AccountRangeUnit, BlockAccessLists, ByteCodes, GetAccountRange,
+        GetBlockAccessLists, GetByteCodes, GetStorageRanges, GetTrieNodes, StorageRanges,
+        StorageSlot, TrieNodes,
     },
 };
 use ethrex_common::types::AccountStateSlimCodec;
 
+/// Maximum size of a served response, regardless of the amount of bytes requested
+pub const SOFT_RESPONSE_LIMIT: u64 = 2 * 1024 * 1024;
+/// Maximum time spent reading state for a single request, the response is cut short once exceeded
+const RESPONSE_TIME_BUDGET: Duration = Duration::from_secs(1);
+/// Maximum amount of bytecodes looked up for a single request
+const MAX_CODE_LOOKUPS: usize = 1024;
+/// Maximum amount of trie nodes looked up for a single request
+const MAX_TRIE_NODE_LOOKUPS: usize = 1024;
+/// Maximum amount of block access lists looked up for a single request
+const MAX_ACCESS_LIST_LOOKUPS: usize = 1024;
+/// Sustained amount of snap requests per second served to a single peer
+const REQUESTS_PER_SECOND: f64 = 50.0;
+/// Amount of snap requests a peer can send at once before being rate limited
+const REQUESTS_BURST: f64 = 100.0;
+
+/// Size and time limits of a single response. Once one of them is exceeded the response is
+/// returned with the data collected so far, which peers must handle as a partial range.
+#[derive(Debug, Clone, Copy)]
+struct ResponseBudget {
+    bytes: u64,
+    deadline: Instant,
+}
+
+impl ResponseBudget {
+    fn new(requested_bytes: u64) -> Self {
+        Self {
+            bytes: requested_bytes.min(SOFT_RESPONSE_LIMIT),
+            deadline: Instant::now() + RESPONSE_TIME_BUDGET,
+        }
+    }
+
+    fn exhausted(&self, bytes_used: u64) -> bool {
+        bytes_used >= self.bytes || Instant::now() >= self.deadline
+    }
+}
+
+/// Token bucket limiting the rate of snap requests served to a peer
+#[derive(Debug, Clone)]
+pub struct SnapRateLimiter {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Default for SnapRateLimiter {
+    fn default() -> Self {
+        Self {
+            tokens: REQUESTS_BURST,
+            last_refill: Instant::now(),
+        }
+    }
+}
+
+impl SnapRateLimiter {
+    /// Returns true if the request can be served, consuming one token
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * REQUESTS_PER_SECOND).min(REQUESTS_BURST);
+        self.last_refill = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
 // Request Processing
 
 pub async fn process_account_range_request(
@@ -18,13 +91,14 @@ pub async fn process_account_range_request(
     store: Store,
 ) -> Result<AccountRange, StoreError> {
     tokio::task::spawn_blocking(move || {
+        let budget = ResponseBudget::new(request.response_bytes);
         let mut accounts = vec![];
         let mut bytes_used = 0;
         for (hash, account) in store.iter_accounts_from(request.root_hash, request.starting_hash)? {
             debug_assert!(hash >= request.starting_hash);
             bytes_used += 32 + AccountStateSlimCodec(account).length() as u64;
             accounts.push(AccountRangeUnit { hash, account });
-            if hash >= request.limit_hash || bytes_used >= request.response_bytes {
+            if hash >= request.limit_hash || budget.exhausted(bytes_used) {
                 break;
             }
         }
@@ -48,6 +122,7 @@ pub async fn process_storage_ranges_request(
     store: Store,
 ) -> Result<StorageRanges, StoreError> {
     tokio::task::spawn_blocking(move || {
+        let budget = ResponseBudget::new(request.response_bytes);
         let mut slots = vec![];
         let mut proof = vec![];
         let mut bytes_used = 0;
@@ -63,8 +138,8 @@ pub async fn process_storage_ranges_request(
                     debug_assert!(hash >= request.starting_hash);
                     bytes_used += 64_u64; // slot size
                     account_slots.push(StorageSlot { hash, data });
-                    if hash >= request.limit_hash || bytes_used >= request.response_bytes {
-                        if bytes_used >= request.response_bytes {
+                    if hash >= request.limit_hash || budget.exhausted(bytes_used) {
+                        if budget.exhausted(bytes_used) {
                             res_capped = true;
                         }
                         break;
@@ -91,7 +166,7 @@ pub async fn process_storage_ranges_request(
                 slots.push(account_slots);
             }
 
-            if bytes_used >= request.response_bytes {
+            if budget.exhausted(bytes_used) {
                 break;
             }
         }
@@ -109,14 +184,15 @@ pub fn process_byte_codes_request(
     request: GetByteCodes,
     store: Store,
 ) -> Result<ByteCodes, StoreError> {
+    let budget = ResponseBudget::new(request.bytes);
     let mut codes = vec![];
     let mut bytes_used = 0;
-    for code_hash in request.hashes {
+    for code_hash in request.hashes.into_iter().take(MAX_CODE_LOOKUPS) {
         if let Some(code) = store.get_account_code(code_hash)?.map(|c| c.bytecode) {
             bytes_used += code.len() as u64;
             codes.push(code);
         }
-        if bytes_used >= request.bytes {
+        if budget.exhausted(bytes_used) {
             break;
         }
     }
@@ -131,14 +207,20 @@ pub async fn process_trie_nodes_request(
     store: Store,
 ) -> Result<TrieNodes, PeerConnectionError> {
     tokio::task::spawn_blocking(move || {
+        let budget = ResponseBudget::new(request.bytes);
         let mut nodes = vec![];
-        let mut remaining_bytes = request.bytes;
+        let mut remaining_bytes = budget.bytes;
+        let mut lookups = 0;
         for paths in request.paths {
             if paths.is_empty() {
                 return Err(PeerConnectionError::BadRequest(
                     "zero-item pathset requested".to_string(),
                 ));
             }
+            lookups += paths.len();
+            if lookups > MAX_TRIE_NODE_LOOKUPS {
+                break;
+            }
             let trie_nodes = store.get_trie_nodes(
                 request.root_hash,
                 paths.into_iter().map(|bytes| bytes.to_vec()).collect(),
@@ -147,7 +229,7 @@ pub async fn process_trie_nodes_request(
             nodes.extend(trie_nodes.iter().map(|nodes| Bytes::copy_from_slice(nodes)));
             remaining_bytes = remaining_bytes
                 .saturating_sub(trie_nodes.iter().fold(0, |acc, nodes| acc + nodes.len()) as u64);
-            if remaining_bytes == 0 {
+            if budget.exhausted(budget.bytes - remaining_bytes) {
                 break;
             }
         }
@@ -161,6 +243,37 @@ pub async fn process_trie_nodes_request(
     .map_err(|e| StoreError::Custom(format!("task panicked: {e}")))?
 }
 
+/// Serves a snap/2 block access lists request. Unknown blocks and blocks executed before
+/// Amsterdam get an empty entry, which the protocol defines as the access list being unavailable.
+pub async fn process_block_access_lists_request(
+    request: GetBlockAccessLists,
+    store: Store,
+) -> Result<BlockAccessLists, StoreError> {
+    let budget = ResponseBudget::new(request.bytes);
+    let mut access_lists = vec![];
+    let mut bytes_used = 0;
+    for block_hash in request
+        .block_hashes
+        .into_iter()
+        .take(MAX_ACCESS_LIST_LOOKUPS)
+    {
+        let access_list = store
+            .get_block_access_list(block_hash)
+            .await?
+            .map(|access_list| Bytes::from(access_list.encode_to_vec()))
+            .unwrap_or_default();
+        bytes_used += access_list.len() as u64;
+        access_lists.push(access_list);
+        if budget.exhausted(bytes_used) {
+            break;
+        }
+    }
+    Ok(BlockAccessLists {
+        id: request.id,
+        access_lists,
+    })
+}
+
 // Helper method to convert proof to RLP-encodable format
 #[inline]
 pub(crate) fn proof_to_encodable(proof: Vec<Vec<u8>>) -> Vec<Bytes> {
@@ -177,9 +290,17 @@ pub(crate) fn encodable_to_proof(proof: &[Bytes]) -> Vec<Vec<u8>> {
 mod tests {
     use std::str::FromStr;
 
-    use ethrex_common::{BigEndianHash, H256, types::AccountStateSlimCodec};
+    use ethrex_common::{
+        Address, BigEndianHash, H256, U256,
+        types::{
+            AccountStateSlimCodec, Block, BlockBody, BlockHeader,
+            block_access_list::{BlockAccessList, BlockAccessListBuilder},
+        },
+    };
     use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
-    use ethrex_storage::EngineType;
+    use ethrex_storage::{EngineType, UpdateBatch};
+
+    use crate::rlpx::message::RLPxMessage;
     use ethrex_trie::EMPTY_TRIE_HASH;
 
     use super::*;
@@ -1005,4 +1126,106 @@ mod tests {
         }
         Ok((store, state_trie.hash().unwrap()))
     }
+
+    #[test]
+    fn rate_limiter_refills_over_time() {
+        let start = Instant::now();
+        let mut limiter = SnapRateLimiter {
+            tokens: REQUESTS_BURST,
+            last_refill: start,
+        };
+        for _ in 0..REQUESTS_BURST as usize {
+            assert!(limiter.try_acquire_at(start));
+        }
+        assert!(!limiter.try_acquire_at(start));
+        // One token is refilled every 1/REQUESTS_PER_SECOND seconds
+        let later = start + Duration::from_secs_f64(1.0 / REQUESTS_PER_SECOND);
+        assert!(limiter.try_acquire_at(later));
+        assert!(!limiter.try_acquire_at(later));
+    }
+
+    #[tokio::test]
+    async fn block_access_lists_are_served_from_the_store() {
+        let mut store = Store::new("", EngineType::InMemory).unwrap();
+        store
+            .add_initial_state(
+                serde_json::from_str(include_str!("../../../fixtures/genesis/l1.json")).unwrap(),
+            )
+            .await
+            .unwrap();
+        let genesis = store.get_block_header(0).unwrap().unwrap();
+        let block = Block::new(
+            BlockHeader {
+                number: 1,
+                parent_hash: genesis.hash(),
+                state_root: genesis.state_root,
+                ..Default::default()
+            },
+            BlockBody::default(),
+        );
+        let mut builder = BlockAccessListBuilder::new();
+        builder.set_balance(Address::repeat_byte(1), 1, U256::from(42));
+        builder.write_storage(
+            Address::repeat_byte(2),
+            H256::repeat_byte(3),
+            1,
+            U256::one(),
+        );
+        let block_access_list = builder.build();
+        store
+            .store_block_updates(UpdateBatch {
+                account_updates: vec![],
+                storage_updates: vec![],
+                blocks: vec![block.clone()],
+                receipts: vec![],
+                block_access_lists: vec![(block.hash(), block_access_list.clone())],
+                code_updates: vec![],
+                plain_storage_updates: vec![],
+                plain_storage_removed_accounts: vec![],
+            })
+            .unwrap();
+
+        // The request and response go through the wire encoding, like they would between peers
+        let request = GetBlockAccessLists {
+            id: 7,
+            block_hashes: vec![block.hash(), H256::repeat_byte(0xff)],
+            bytes: SOFT_RESPONSE_LIMIT,
+        };
+        let mut buf = vec![];
+        RLPxMessage::encode(&request, &mut buf).unwrap();
+        let request = <GetBlockAccessLists as RLPxMessage>::decode(&buf).unwrap();
+
+        let response = process_block_access_lists_request(request, store.clone())
+            .await
+            .unwrap();
+        let mut buf = vec![];
+        RLPxMessage::encode(&response, &mut buf).unwrap();
+        let response = <BlockAccessLists as RLPxMessage>::decode(&buf).unwrap();
+
+        assert_eq!(response.id, 7);
+        assert_eq!(response.access_lists.len(), 2);
+        assert_eq!(
+            BlockAccessList::decode(&response.access_lists[0]).unwrap(),
+            block_access_list
+        );
+        // Unknown blocks keep their position with an empty entry
+        assert!(response.access_lists[1].is_empty());
+    }
+
+    #[tokio::test]
+    async fn block_access_lists_request_is_capped() {
+        let store = Store::new("", EngineType::InMemory).unwrap();
+        let response = process_block_access_lists_request(
+            GetBlockAccessLists {
+                id: 7,
+                block_hashes: vec![H256::zero(); MAX_ACCESS_LIST_LOOKUPS + 1],
+                bytes: SOFT_RESPONSE_LIMIT,
+            },
+            store,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.access_lists.len(), MAX_ACCESS_LIST_LOOKUPS);
+        assert!(response.access_lists.iter().all(Bytes::is_empty));
+    }
 }
@@ -1,11 +1,15 @@
+mod checkpoint;
 mod code_collector;
+mod history_backfill;
 mod state_healing;
 mod storage_healing;
 
 use crate::discv4::peer_table::PeerTableError;
 use crate::peer_handler::{BlockRequestOrder, PeerHandlerError, SNAP_LIMIT};
 use crate::rlpx::p2p::SUPPORTED_ETH_CAPABILITIES;
+use crate::sync::checkpoint::verify_checkpoint;
 use crate::sync::code_collector::CodeHashCollector;
+use crate::sync::history_backfill::{backfill_history, schedule_history_backfill};
 use crate::sync::state_healing::heal_state_trie_wrap;
 use crate::sync::storage_healing::heal_storage_trie;
 use crate::utils::{
@@ -15,6 +19,7 @@ use crate::utils::{
 use crate::{
     metrics::METRICS,
     peer_handler::{MAX_BLOCK_BODIES_TO_REQUEST, PeerHandler},
+    reputation::Penalty,
 };
 use ethrex_blockchain::{BatchBlockProcessingFailure, Blockchain, error::ChainError};
 #[cfg(not(feature = "rocksdb"))]
@@ -89,6 +94,10 @@ pub struct Syncer {
     /// This string indicates a folder where the snap algorithm will store temporary files that are
     /// used during the syncing process
     datadir: PathBuf,
+    /// Set while the background download of the history before the snap sync pivot is running
+    history_backfill_active: Arc<AtomicBool>,
+    /// Chains shorter than this are full synced even if snap sync is enabled
+    min_full_blocks: u64,
 }
 
 impl Syncer {
@@ -105,6 +114,44 @@ impl Syncer {
             cancel_token,
             blockchain,
             datadir,
+            history_backfill_active: Arc::new(AtomicBool::new(false)),
+            min_full_blocks: MIN_FULL_BLOCKS,
+        }
+    }
+
+    /// Overrides the chain length below which snap sync falls back to full sync, so short test
+    /// chains can be snap synced
+    pub fn with_min_full_blocks(mut self, min_full_blocks: u64) -> Self {
+        self.min_full_blocks = min_full_blocks;
+        self
+    }
+
+    /// Starts downloading the bodies and receipts of the blocks before the last snap sync pivot in
+    /// the background, if there is a pending history backfill and it isn't running already
+    pub fn start_history_backfill(&self, store: Store) {
+        if self.history_backfill_active.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let peers = self.peers.clone();
+        let cancel_token = self.cancel_token.clone();
+        let active = self.history_backfill_active.clone();
+        tokio::spawn(async move {
+            if let Err(err) = backfill_history(peers, store, cancel_token).await {
+                error!(%err, "History backfill failed");
+            }
+            active.store(false, Ordering::Relaxed);
+        });
+    }
+
+    /// Verifies that the given trusted checkpoint descends from our local chain, see
+    /// [`verify_checkpoint`]. Returns false if the checkpoint couldn't be verified.
+    pub async fn verify_checkpoint(&mut self, checkpoint: H256, store: &Store) -> bool {
+        match verify_checkpoint(&mut self.peers, store, checkpoint, &self.cancel_token).await {
+            Ok(verified) => verified.is_some(),
+            Err(error) => {
+                error!(%checkpoint, %error, "Failed to verify sync checkpoint");
+                false
+            }
         }
     }
 
@@ -115,7 +162,7 @@ impl Syncer {
     /// After the sync cycle is complete, the sync mode will be set to full
     /// If the sync fails, no error will be returned but a warning will be emitted
     /// [WARNING] Sync is done optimistically, so headers and bodies may be stored even if their data has not been fully synced if the sync is aborted halfway
-    /// [WARNING] Sync is currenlty simplified and will not download bodies + receipts previous to the pivot during snap sync
+    /// After a snap sync, bodies + receipts previous to the pivot are downloaded by a background history backfill
     pub async fn start_sync(&mut self, sync_head: H256, store: Store) {
         let start_time = Instant::now();
         match self.sync_cycle(sync_head, store).await {
@@ -153,7 +200,7 @@ impl Syncer {
     }
 
     /// Performs the sync cycle described in `start_sync`, returns an error if the sync fails at any given step and aborts all active processes
-    async fn sync_cycle(&mut self, sync_head: H256, store: Store) -> Result<(), SyncError> {
+    pub async fn sync_cycle(&mut self, sync_head: H256, store: Store) -> Result<(), SyncError> {
         // Take picture of the current sync mode, we will update the original value when we need to
         if self.snap_enabled.load(Ordering::Relaxed) {
             METRICS.enable().await;
@@ -261,7 +308,7 @@ impl Syncer {
             // If the sync head is not 0 we search to fullsync
             let head_found = sync_head_found && store.get_latest_block_number().await? > 0;
             // Or the head is very close to 0
-            let head_close_to_0 = last_block_number < MIN_FULL_BLOCKS;
+            let head_close_to_0 = last_block_number < self.min_full_blocks;
 
             if head_found || head_close_to_0 {
                 // Too few blocks for a snap sync, switching to full sync
@@ -289,6 +336,10 @@ impl Syncer {
         store.clear_snap_state().await?;
         self.snap_enabled.store(false, Ordering::Relaxed);
 
+        // Only the pivot body was downloaded, the history before it is fetched in the background
+        schedule_history_backfill(&store).await?;
+        self.start_history_backfill(store);
+
         Ok(())
     }
 
@@ -1061,8 +1112,11 @@ pub async fn update_pivot(
             .await
             .map_err(SyncError::PeerHandler)?
         else {
-            // Penalize peer
-            peers.peer_table.record_failure(&peer_id).await?;
+            // The peer doesn't know the new pivot yet, so it is lagging behind the chain head
+            peers
+                .peer_table
+                .penalize(&peer_id, Penalty::StaleHeaders)
+                .await?;
             let peer_score = peers.peer_table.get_score(&peer_id).await?;
             warn!(
                 "Received None pivot from peer {peer_id} (score after penalizing: {peer_score}). Retrying"
@@ -1168,6 +1222,8 @@ pub enum SyncError {
     PeerTableError(#[from] PeerTableError),
     #[error("Missing fullsync batch")]
     MissingFullsyncBatch,
+    #[error("Sync checkpoint {0:?} doesn't descend from our genesis block")]
+    CheckpointNotOnChain(H256),
 }
 
 impl SyncError {
@@ -1192,7 +1248,8 @@ impl SyncError {
             | SyncError::BytecodeFileError
             | SyncError::NoLatestCanonical
             | SyncError::PeerTableError(_)
-            | SyncError::MissingFullsyncBatch => false,
+            | SyncError::MissingFullsyncBatch
+            | SyncError::CheckpointNotOnChain(_) => false,
             SyncError::Chain(_)
             | SyncError::Store(_)
             | SyncError::Send(_)
@@ -0,0 +1,210 @@
+use crate::peer_handler::{BlockRequestOrder, PeerHandler};
+use crate::sync::SyncError;
+use crate::sync::history_backfill::wait_retry;
+use ethrex_common::{
+    H256,
+    types::{BlockHeader, BlockNumber},
+};
+use ethrex_storage::Store;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+/// Amount of verified headers between progress logs
+const LOG_INTERVAL_HEADERS: u64 = 100_000;
+
+/// Verifies that a trusted checkpoint descends from our local chain, so it can be used as sync
+/// head without a consensus client. Headers are downloaded backwards from the checkpoint, checking
+/// that each one hashes to the parent hash of its child, until a block of our canonical chain is
+/// reached (the genesis block on a fresh node).
+/// Returns the number of the checkpoint block, or None if the verification was cancelled.
+pub async fn verify_checkpoint(
+    peers: &mut PeerHandler,
+    store: &Store,
+    checkpoint: H256,
+    cancel_token: &CancellationToken,
+) -> Result<Option<BlockNumber>, SyncError> {
+    info!(%checkpoint, "Verifying sync checkpoint");
+    if store.is_canonical_sync(checkpoint)? {
+        // The checkpoint is already part of our chain
+        let number = store
+            .get_block_number(checkpoint)
+            .await?
+            .ok_or(SyncError::BlockNumber(checkpoint))?;
+        info!(%checkpoint, number, "Sync checkpoint verified");
+        return Ok(Some(number));
+    }
+
+    let mut walk = CheckpointWalk::new(checkpoint);
+    loop {
+        if cancel_token.is_cancelled() {
+            return Ok(None);
+        }
+        let headers = match peers
+            .request_block_headers_from_hash(walk.expected, BlockRequestOrder::NewToOld)
+            .await?
+        {
+            Some(headers) => headers,
+            None if !wait_retry(cancel_token).await => return Ok(None),
+            None => continue,
+        };
+        if let Some(number) = walk.verify_headers(store, &headers)? {
+            return Ok(Some(number));
+        }
+    }
+}
+
+/// Progress of the backwards walk from the checkpoint to our canonical chain
+struct CheckpointWalk {
+    checkpoint: H256,
+    checkpoint_number: Option<BlockNumber>,
+    /// Hash of the next header to verify
+    expected: H256,
+    verified: u64,
+}
+
+impl CheckpointWalk {
+    fn new(checkpoint: H256) -> Self {
+        Self {
+            checkpoint,
+            checkpoint_number: None,
+            expected: checkpoint,
+            verified: 0,
+        }
+    }
+
+    /// Verifies headers received newest first, returning the checkpoint number once one of them
+    /// is part of our canonical chain. Headers after one not matching the expected hash are
+    /// discarded, so they are requested again.
+    fn verify_headers(
+        &mut self,
+        store: &Store,
+        headers: &[BlockHeader],
+    ) -> Result<Option<BlockNumber>, SyncError> {
+        for header in headers {
+            let hash = header.hash();
+            if hash != self.expected {
+                debug!(expected = %self.expected, %hash, "Received header not matching the requested hash, retrying");
+                return Ok(None);
+            }
+            let checkpoint_number = *self.checkpoint_number.get_or_insert(header.number);
+            if store.is_canonical_sync(hash)? {
+                info!(
+                    checkpoint = %self.checkpoint,
+                    number = checkpoint_number,
+                    verified = self.verified,
+                    "Sync checkpoint verified"
+                );
+                return Ok(Some(checkpoint_number));
+            }
+            if header.number == 0 {
+                // We walked down to a genesis block that isn't ours
+                return Err(SyncError::CheckpointNotOnChain(self.checkpoint));
+            }
+            self.expected = header.parent_hash;
+            self.verified += 1;
+            if self.verified % LOG_INTERVAL_HEADERS == 0 {
+                info!(
+                    number = header.number,
+                    verified = self.verified,
+                    "Sync checkpoint verification in progress"
+                );
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_storage::EngineType;
+
+    const TEST_GENESIS: &str = include_str!("../../../../fixtures/genesis/l1.json");
+
+    async fn store_with_genesis() -> (Store, BlockHeader) {
+        let mut store = Store::new("", EngineType::InMemory).unwrap();
+        store
+            .add_initial_state(serde_json::from_str(TEST_GENESIS).unwrap())
+            .await
+            .unwrap();
+        let genesis = store.get_block_header(0).unwrap().unwrap();
+        (store, genesis)
+    }
+
+    /// Returns `len` headers built on top of `parent`, newest first
+    fn chain(parent: &BlockHeader, len: u64) -> Vec<BlockHeader> {
+        let mut headers = vec![];
+        let mut parent = parent.clone();
+        for _ in 0..len {
+            let header = BlockHeader {
+                number: parent.number + 1,
+                parent_hash: parent.hash(),
+                ..Default::default()
+            };
+            headers.push(header.clone());
+            parent = header;
+        }
+        headers.reverse();
+        headers
+    }
+
+    #[tokio::test]
+    async fn checkpoint_on_our_chain_is_verified() {
+        let (store, genesis) = store_with_genesis().await;
+        let mut headers = chain(&genesis, 3);
+        headers.push(genesis);
+
+        // Walking down to our own genesis block verifies the checkpoint
+        let mut walk = CheckpointWalk::new(headers[0].hash());
+        assert_eq!(walk.verify_headers(&store, &headers).unwrap(), Some(3));
+        assert_eq!(walk.verified, 3);
+    }
+
+    #[tokio::test]
+    async fn checkpoint_off_our_chain_is_rejected() {
+        let (store, genesis) = store_with_genesis().await;
+        let other_genesis = BlockHeader {
+            hash: Default::default(),
+            gas_limit: genesis.gas_limit + 1,
+            ..genesis
+        };
+        let mut headers = chain(&other_genesis, 3);
+        headers.push(other_genesis);
+
+        let mut walk = CheckpointWalk::new(headers[0].hash());
+        assert!(matches!(
+            walk.verify_headers(&store, &headers),
+            Err(SyncError::CheckpointNotOnChain(checkpoint)) if checkpoint == headers[0].hash()
+        ));
+    }
+
+    #[tokio::test]
+    async fn canonical_block_in_the_middle_of_a_batch_ends_the_walk() {
+        let (store, genesis) = store_with_genesis().await;
+        let mut headers = chain(&genesis, 5);
+        headers.push(genesis);
+        // Block 2 is part of our canonical chain
+        let canonical = headers[3].clone();
+        assert_eq!(canonical.number, 2);
+        let canonical_hash = canonical.hash();
+        store
+            .add_block_header(canonical_hash, canonical)
+            .await
+            .unwrap();
+        store
+            .forkchoice_update(vec![(2, canonical_hash)], 2, canonical_hash, None, None)
+            .await
+            .unwrap();
+
+        let mut walk = CheckpointWalk::new(headers[0].hash());
+        // The first batch doesn't reach our chain
+        assert_eq!(walk.verify_headers(&store, &headers[..2]).unwrap(), None);
+        assert_eq!(walk.expected, headers[2].hash());
+        // A batch starting anywhere else is discarded
+        assert_eq!(walk.verify_headers(&store, &headers[3..]).unwrap(), None);
+        assert_eq!(walk.expected, headers[2].hash());
+        // The next one does halfway through, the rest of the batch isn't needed
+        assert_eq!(walk.verify_headers(&store, &headers[2..]).unwrap(), Some(5));
+        assert_eq!(walk.verified, 3);
+    }
+}
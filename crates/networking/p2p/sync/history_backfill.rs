@@ -0,0 +1,188 @@
+use crate::peer_handler::{MAX_BLOCK_BODIES_TO_REQUEST, MAX_RECEIPTS_TO_REQUEST, PeerHandler};
+use crate::sync::SyncError;
+use ethrex_common::constants::EMPTY_TRIE_HASH;
+use ethrex_common::types::{Block, BlockHeader, BlockNumber, Receipt};
+use ethrex_storage::Store;
+use std::{ops::Range, time::Duration};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+/// Lowest block whose history is backfilled, genesis is stored with its body when the db is created
+pub const HISTORY_BACKFILL_TARGET: BlockNumber = 1;
+
+/// Amount of blocks downloaded and stored together, the backfill resumes from the last stored batch
+const BACKFILL_BATCH_SIZE: u64 = 1024;
+
+/// Time to wait before retrying when no peer returned the requested data
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Amount of batches between progress logs
+const LOG_INTERVAL_BATCHES: u64 = 100;
+
+/// Sets up the backfill of the history before the snap sync pivot. Only the pivot was downloaded
+/// with its body, so it becomes the earliest block.
+pub async fn schedule_history_backfill(store: &Store) -> Result<(), SyncError> {
+    let pivot_number = store.get_latest_block_number().await?;
+    store.update_earliest_block_number(pivot_number).await?;
+    store
+        .set_history_backfill_target(Some(HISTORY_BACKFILL_TARGET))
+        .await?;
+    Ok(())
+}
+
+/// Blocks of the next batch to backfill, the ones right before the earliest block.
+/// Returns None once the target is reached.
+fn next_batch(earliest: BlockNumber, target: BlockNumber) -> Option<Range<BlockNumber>> {
+    (earliest > target).then(|| earliest.saturating_sub(BACKFILL_BATCH_SIZE).max(target)..earliest)
+}
+
+/// Downloads the bodies and receipts of the blocks before the snap sync pivot, walking backwards
+/// from the earliest block number until the history backfill target stored in the db is reached.
+/// Bodies are validated against the transactions and withdrawals roots of the stored headers, and
+/// receipts against their receipts root.
+/// Progress is tracked through the earliest block number, so the backfill resumes after a restart.
+pub async fn backfill_history(
+    mut peers: PeerHandler,
+    store: Store,
+    cancel_token: CancellationToken,
+) -> Result<(), SyncError> {
+    let mut stored_batches = 0u64;
+    loop {
+        if cancel_token.is_cancelled() {
+            return Ok(());
+        }
+        // The target is re-read on each batch as history expiry may raise it
+        let Some(target) = store.get_history_backfill_target().await? else {
+            return Ok(());
+        };
+        let earliest = store.get_earliest_block_number().await?;
+        let Some(batch) = next_batch(earliest, target) else {
+            store.set_history_backfill_target(None).await?;
+            info!(earliest, "History backfill finished");
+            return Ok(());
+        };
+
+        let from = batch.start;
+        let headers = batch
+            .map(|number| store.get_block_header(number)?.ok_or(SyncError::CorruptDB))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Some(blocks) = download_history(&mut peers, headers, &cancel_token).await? else {
+            return Ok(());
+        };
+        store.add_backfilled_blocks(blocks).await?;
+        debug!(earliest = from, target, "Backfilled block history");
+        stored_batches += 1;
+        if stored_batches % LOG_INTERVAL_BATCHES == 0 {
+            info!(earliest = from, target, "History backfill in progress");
+        }
+    }
+}
+
+/// Fetches the bodies and receipts of the given blocks, retrying until all of them are received.
+/// Returns None if the backfill was cancelled.
+async fn download_history(
+    peers: &mut PeerHandler,
+    headers: Vec<BlockHeader>,
+    cancel_token: &CancellationToken,
+) -> Result<Option<Vec<(Block, Vec<Receipt>)>>, SyncError> {
+    let mut bodies = Vec::with_capacity(headers.len());
+    while bodies.len() < headers.len() {
+        let end = (bodies.len() + MAX_BLOCK_BODIES_TO_REQUEST).min(headers.len());
+        match peers
+            .request_block_bodies(&headers[bodies.len()..end])
+            .await?
+        {
+            Some(received) => bodies.extend(received),
+            None if !wait_retry(cancel_token).await => return Ok(None),
+            None => {}
+        }
+    }
+
+    // Blocks without transactions have no receipts, there is nothing to request for them
+    let with_receipts: Vec<BlockHeader> = headers
+        .iter()
+        .filter(|header| header.receipts_root != *EMPTY_TRIE_HASH)
+        .cloned()
+        .collect();
+    let mut receipts = Vec::with_capacity(with_receipts.len());
+    while receipts.len() < with_receipts.len() {
+        let end = (receipts.len() + MAX_RECEIPTS_TO_REQUEST).min(with_receipts.len());
+        match peers
+            .request_receipts(&with_receipts[receipts.len()..end])
+            .await?
+        {
+            Some(received) => receipts.extend(received),
+            None if !wait_retry(cancel_token).await => return Ok(None),
+            None => {}
+        }
+    }
+
+    let mut receipts = receipts.into_iter();
+    let blocks = headers
+        .into_iter()
+        .zip(bodies)
+        .map(|(header, body)| {
+            let block_receipts = if header.receipts_root != *EMPTY_TRIE_HASH {
+                receipts.next().unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            (Block { header, body }, block_receipts)
+        })
+        .collect();
+    Ok(Some(blocks))
+}
+
+/// Waits before retrying a request, returns false if the sync was cancelled meanwhile
+pub(super) async fn wait_retry(cancel_token: &CancellationToken) -> bool {
+    cancel_token
+        .run_until_cancelled(tokio::time::sleep(RETRY_DELAY))
+        .await
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_storage::EngineType;
+
+    const TEST_GENESIS: &str = include_str!("../../../../fixtures/genesis/l1.json");
+
+    #[tokio::test]
+    async fn snap_sync_pivot_is_the_earliest_block() {
+        let mut store = Store::new("", EngineType::InMemory).unwrap();
+        store
+            .add_initial_state(serde_json::from_str(TEST_GENESIS).unwrap())
+            .await
+            .unwrap();
+        // Snap sync only stores the pivot block on top of the genesis
+        let pivot = BlockHeader {
+            number: 2000,
+            ..Default::default()
+        };
+        let pivot_hash = pivot.hash();
+        store.add_block_header(pivot_hash, pivot).await.unwrap();
+        store
+            .forkchoice_update(vec![(2000, pivot_hash)], 2000, pivot_hash, None, None)
+            .await
+            .unwrap();
+
+        schedule_history_backfill(&store).await.unwrap();
+
+        let earliest = store.get_earliest_block_number().await.unwrap();
+        assert_eq!(earliest, 2000);
+        assert!(earliest <= store.get_latest_block_number().await.unwrap());
+        assert_eq!(
+            store.get_history_backfill_target().await.unwrap(),
+            Some(HISTORY_BACKFILL_TARGET)
+        );
+        // The pivot isn't downloaded again
+        assert_eq!(
+            next_batch(earliest, HISTORY_BACKFILL_TARGET),
+            Some(976..2000)
+        );
+        assert_eq!(next_batch(976, HISTORY_BACKFILL_TARGET), Some(1..976));
+        assert_eq!(next_batch(1, HISTORY_BACKFILL_TARGET), None);
+    }
+}
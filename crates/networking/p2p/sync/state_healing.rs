@@ -24,7 +24,7 @@ use tracing::{debug, trace};
 use crate::{
     metrics::{CurrentStepValue, METRICS},
     peer_handler::{PeerHandler, RequestMetadata, RequestStateTrieNodesError},
-    rlpx::p2p::SUPPORTED_SNAP_CAPABILITIES,
+    rlpx::p2p::TRIE_NODES_SNAP_CAPABILITIES,
     sync::{AccountStorageRoots, code_collector::CodeHashCollector},
     utils::current_unix_time,
 };
@@ -126,7 +126,7 @@ async fn heal_state_trie(
         if last_update.elapsed() >= SHOW_PROGRESS_INTERVAL_DURATION {
             let num_peers = peers
                 .peer_table
-                .peer_count_by_capabilities(&SUPPORTED_SNAP_CAPABILITIES)
+                .peer_count_by_capabilities(&TRIE_NODES_SNAP_CAPABILITIES)
                 .await
                 .unwrap_or(0);
             last_update = Instant::now();
@@ -226,7 +226,7 @@ async fn heal_state_trie(
                 );
                 let Some((peer_id, connection)) = peers
                     .peer_table
-                    .get_best_peer(&SUPPORTED_SNAP_CAPABILITIES)
+                    .get_best_peer(&TRIE_NODES_SNAP_CAPABILITIES)
                     .await
                     .inspect_err(
                         |err| debug!(err=?err, "Error requesting a peer to perform state healing"),
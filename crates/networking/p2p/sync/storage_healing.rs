@@ -1,8 +1,9 @@
 use crate::{
     metrics::{CurrentStepValue, METRICS},
     peer_handler::{MAX_RESPONSE_BYTES, PeerHandler, RequestStorageTrieNodes},
+    reputation::Penalty,
     rlpx::{
-        p2p::SUPPORTED_SNAP_CAPABILITIES,
+        p2p::TRIE_NODES_SNAP_CAPABILITIES,
         snap::{GetTrieNodes, TrieNodes},
     },
     sync::{
@@ -178,7 +179,7 @@ pub async fn heal_storage_trie(
             state.last_update = Instant::now();
             let snap_peer_count = peers
                 .peer_table
-                .peer_count_by_capabilities(&SUPPORTED_SNAP_CAPABILITIES)
+                .peer_count_by_capabilities(&TRIE_NODES_SNAP_CAPABILITIES)
                 .await
                 .unwrap_or(0);
             debug!(
@@ -325,7 +326,7 @@ async fn ask_peers_for_nodes(
     if (requests.len() as u32) < MAX_IN_FLIGHT_REQUESTS && !download_queue.is_empty() {
         let Some((peer_id, connection)) = peers
             .peer_table
-            .get_best_peer(&SUPPORTED_SNAP_CAPABILITIES)
+            .get_best_peer(&TRIE_NODES_SNAP_CAPABILITIES)
             .await
             .inspect_err(|err| debug!(?err, "Error requesting a peer to perform storage healing"))
             .unwrap_or(None)
@@ -486,10 +487,11 @@ async fn zip_requeue_node_responses_score_peer(
             .await?;
         Ok(Some(nodes))
     } else {
+        // The nodes don't hash to the requested ones
         *failed_downloads += 1;
         peer_handler
             .peer_table
-            .record_failure(&request.peer_id)
+            .penalize(&request.peer_id, Penalty::InvalidSnapProof)
             .await?;
         download_queue.extend(request.requests);
         Ok(None)
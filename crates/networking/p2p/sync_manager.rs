@@ -40,28 +40,41 @@ impl SyncManager {
         blockchain: Arc<Blockchain>,
         store: Store,
         datadir: PathBuf,
+        checkpoint: Option<H256>,
     ) -> Self {
         let snap_enabled = Arc::new(AtomicBool::new(matches!(sync_mode, SyncMode::Snap)));
-        let syncer = Arc::new(Mutex::new(Syncer::new(
+        let syncer = Syncer::new(
             peer_handler,
             snap_enabled.clone(),
             cancel_token,
             blockchain,
             datadir,
-        )));
+        );
+        // Resume the download of the history before the snap sync pivot if it was interrupted
+        if store
+            .get_history_backfill_target()
+            .await
+            .is_ok_and(|target| target.is_some())
+        {
+            syncer.start_history_backfill(store.clone());
+        }
+        let syncer = Arc::new(Mutex::new(syncer));
         let sync_manager = Self {
             snap_enabled,
             syncer,
             last_fcu_head: Arc::new(Mutex::new(H256::zero())),
             store: store.clone(),
         };
-        // If the node was in the middle of a sync and then re-started we must resume syncing
-        // Otherwise we will incorreclty assume the node is already synced and work on invalid state
-        if store
+        if let Some(checkpoint) = checkpoint {
+            // Also resumes an interrupted sync, as it keeps syncing while snap sync checkpoints are stored
+            sync_manager.start_checkpoint_sync(checkpoint);
+        } else if store
             .get_header_download_checkpoint()
             .await
             .is_ok_and(|res| res.is_some())
         {
+            // If the node was in the middle of a sync and then re-started we must resume syncing
+            // Otherwise we will incorreclty assume the node is already synced and work on invalid state
             sync_manager.start_sync();
         }
         sync_manager
@@ -116,34 +129,39 @@ impl SyncManager {
             let Ok(mut syncer) = syncer.try_lock() else {
                 return;
             };
-            loop {
-                let sync_head = {
-                    // Read latest fcu head without holding the lock for longer than needed
-                    let Ok(sync_head) = sync_head.try_lock() else {
-                        error!("Failed to read latest fcu head, unable to sync");
-                        return;
-                    };
-                    *sync_head
+            sync_cycles(&mut syncer, store, sync_head).await;
+        });
+    }
+
+    /// Syncs to a trusted checkpoint without a consensus client, once it is verified to descend
+    /// from our genesis block. If an engine API client sends a forkchoice update meanwhile, its
+    /// head is synced instead, and later updates drive the sync as usual.
+    fn start_checkpoint_sync(&self, checkpoint: H256) {
+        let syncer = self.syncer.clone();
+        let store = self.store.clone();
+        let sync_head = self.last_fcu_head.clone();
+
+        tokio::spawn(async move {
+            let Ok(mut syncer) = syncer.try_lock() else {
+                return;
+            };
+            if !syncer.verify_checkpoint(checkpoint, &store).await {
+                return;
+            }
+            {
+                let Ok(mut sync_head) = sync_head.try_lock() else {
+                    error!("Failed to read latest fcu head, unable to sync");
+                    return;
                 };
-                // Edge case: If we are resuming a sync process after a node restart, wait until the next fcu to start
                 if sync_head.is_zero() {
-                    info!("Resuming sync after node restart, waiting for next FCU");
-                    sleep(Duration::from_secs(5)).await;
-                    continue;
-                }
-                // Start the sync cycle
-                syncer.start_sync(sync_head, store.clone()).await;
-                // Continue to the next sync cycle if we have an ongoing snap sync (aka if we still have snap sync checkpoints stored)
-                if store
-                    .get_header_download_checkpoint()
-                    .await
-                    .ok()
-                    .flatten()
-                    .is_none()
-                {
-                    break;
+                    *sync_head = checkpoint;
+                } else {
+                    info!(
+                        "Consensus client connected, syncing to its head instead of the checkpoint"
+                    );
                 }
             }
+            sync_cycles(&mut syncer, store, sync_head).await;
         });
     }
 
@@ -151,3 +169,35 @@ impl SyncManager {
         Ok(*self.last_fcu_head.try_lock()?)
     }
 }
+
+/// Runs sync cycles towards the latest fcu head until the sync is complete
+async fn sync_cycles(syncer: &mut Syncer, store: Store, sync_head: Arc<Mutex<H256>>) {
+    loop {
+        let sync_head = {
+            // Read latest fcu head without holding the lock for longer than needed
+            let Ok(sync_head) = sync_head.try_lock() else {
+                error!("Failed to read latest fcu head, unable to sync");
+                return;
+            };
+            *sync_head
+        };
+        // Edge case: If we are resuming a sync process after a node restart, wait until the next fcu to start
+        if sync_head.is_zero() {
+            info!("Resuming sync after node restart, waiting for next FCU");
+            sleep(Duration::from_secs(5)).await;
+            continue;
+        }
+        // Start the sync cycle
+        syncer.start_sync(sync_head, store.clone()).await;
+        // Continue to the next sync cycle if we have an ongoing snap sync (aka if we still have snap sync checkpoints stored)
+        if store
+            .get_header_download_checkpoint()
+            .await
+            .ok()
+            .flatten()
+            .is_none()
+        {
+            break;
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Transports used to reach other nodes.
+//!
+//! Nodes use TCP for RLPx and UDP for discovery, but both can be replaced through the
+//! [`P2PContext`](crate::network::P2PContext) and the discovery server, e.g. to run many nodes
+//! in-process over an in-memory network.
+
+use std::{fmt::Debug, io, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use ethrex_common::utils::keccak;
+use futures::{Stream, stream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpSocket, UdpSocket},
+    sync::{Mutex, mpsc},
+};
+use tokio_util::codec::Decoder;
+use tracing::debug;
+
+/// Maximum size of a received datagram, same as the buffer used by `UdpFramed`
+const MAX_DATAGRAM_SIZE: usize = 64 * 1024;
+
+/// Datagrams buffered for each discovery protocol when they share a socket
+const SPLIT_SOCKET_BUFFER: usize = 1024;
+
+/// Size of the discv4 packet header: hash and signature
+const DISCV4_HEADER_SIZE: usize = 32 + 65;
+
+/// Byte stream over which an RLPx connection runs
+pub trait RLPxStream: AsyncRead + AsyncWrite + Debug + Send + Sync + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Debug + Send + Sync + Unpin + 'static> RLPxStream for T {}
+
+pub type BoxedStream = Box<dyn RLPxStream>;
+
+/// Opens the outgoing RLPx connections
+#[async_trait]
+pub trait Transport: Debug + Send + Sync {
+    async fn dial(&self, addr: SocketAddr) -> io::Result<BoxedStream>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TcpTransport;
+
+#[async_trait]
+impl Transport for TcpTransport {
+    async fn dial(&self, addr: SocketAddr) -> io::Result<BoxedStream> {
+        let stream = match addr {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?.connect(addr).await,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?.connect(addr).await,
+        }?;
+        Ok(Box::new(stream))
+    }
+}
+
+/// Socket over which discovery packets are exchanged
+#[async_trait]
+pub trait DatagramSocket: Debug + Send + Sync {
+    async fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize>;
+
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+}
+
+#[async_trait]
+impl DatagramSocket for UdpSocket {
+    async fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, buf, addr).await
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buf).await
+    }
+}
+
+/// Stream of the datagrams received by the socket, each one decoded on its own with the codec.
+/// Empty datagrams are skipped.
+pub fn datagram_stream<C>(
+    socket: Arc<dyn DatagramSocket>,
+    codec: C,
+) -> impl Stream<Item = Result<(C::Item, SocketAddr), C::Error>>
+where
+    C: Decoder + Send + 'static,
+{
+    let buf = vec![0; MAX_DATAGRAM_SIZE];
+    stream::unfold(
+        (socket, codec, buf),
+        |(socket, mut codec, mut buf)| async move {
+            loop {
+                let result = match socket.recv_from(&mut buf).await {
+                    Ok((read, addr)) => codec
+                        .decode(&mut BytesMut::from(&buf[..read]))
+                        .map(|item| item.map(|item| (item, addr))),
+                    Err(error) => Err(error.into()),
+                };
+                match result.transpose() {
+                    Some(result) => return Some((result, (socket, codec, buf))),
+                    None => continue,
+                }
+            }
+        },
+    )
+}
+
+/// Splits a socket between discv4 and discv5 so that both run on the same UDP port.
+///
+/// Datagrams starting with the keccak hash of their remaining bytes are discv4 packets, everything
+/// else is handed to discv5. Returns the discv4 and discv5 sockets, in that order.
+pub fn split_discovery_socket(
+    socket: Arc<dyn DatagramSocket>,
+) -> (Arc<dyn DatagramSocket>, Arc<dyn DatagramSocket>) {
+    let (discv4_sender, discv4_receiver) = mpsc::channel(SPLIT_SOCKET_BUFFER);
+    let (discv5_sender, discv5_receiver) = mpsc::channel(SPLIT_SOCKET_BUFFER);
+    tokio::spawn(route_datagrams(
+        socket.clone(),
+        discv4_sender,
+        discv5_sender,
+    ));
+    let discv4 = ChannelSocket {
+        socket: socket.clone(),
+        receiver: Mutex::new(discv4_receiver),
+    };
+    let discv5 = ChannelSocket {
+        socket,
+        receiver: Mutex::new(discv5_receiver),
+    };
+    (Arc::new(discv4), Arc::new(discv5))
+}
+
+fn is_discv4_packet(datagram: &[u8]) -> bool {
+    datagram.len() > DISCV4_HEADER_SIZE && keccak(&datagram[32..]).0 == datagram[..32]
+}
+
+/// Reads from the shared socket until both protocols drop their half
+async fn route_datagrams(
+    socket: Arc<dyn DatagramSocket>,
+    discv4: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+    discv5: mpsc::Sender<(Vec<u8>, SocketAddr)>,
+) {
+    let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+    while !(discv4.is_closed() && discv5.is_closed()) {
+        let (read, from) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(error) => {
+                debug!(%error, "Failed to read from the discovery socket");
+                continue;
+            }
+        };
+        let datagram = buf[..read].to_vec();
+        let sender = if is_discv4_packet(&datagram) {
+            &discv4
+        } else {
+            &discv5
+        };
+        // A full or closed queue drops the datagram, like a busy socket would
+        let _ = sender.try_send((datagram, from));
+    }
+}
+
+/// One of the halves returned by [`split_discovery_socket`]
+#[derive(Debug)]
+struct ChannelSocket {
+    socket: Arc<dyn DatagramSocket>,
+    receiver: Mutex<mpsc::Receiver<(Vec<u8>, SocketAddr)>>,
+}
+
+#[async_trait]
+impl DatagramSocket for ChannelSocket {
+    async fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        self.socket.send_to(buf, addr).await
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (datagram, from) =
+            self.receiver.lock().await.recv().await.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::BrokenPipe, "discovery socket closed")
+            })?;
+        let read = datagram.len().min(buf.len());
+        buf[..read].copy_from_slice(&datagram[..read]);
+        Ok((read, from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[tokio::test]
+    async fn split_socket_routes_datagrams_by_protocol() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let (discv4, discv5) = split_discovery_socket(Arc::new(socket));
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+
+        let mut discv4_packet = vec![0; 32];
+        discv4_packet.extend([0x01; 70]);
+        let hash = keccak(&discv4_packet[32..]);
+        discv4_packet[..32].copy_from_slice(hash.as_bytes());
+        // Same length but a wrong hash, e.g. a discv5 packet
+        let mut discv5_packet = discv4_packet.clone();
+        discv5_packet[0] ^= 0xff;
+
+        sender.send_to(&discv5_packet, addr).await.unwrap();
+        sender.send_to(&discv4_packet, addr).await.unwrap();
+
+        let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+        let (read, from) = discv4.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..read], &discv4_packet[..]);
+        assert_eq!(from, sender.local_addr().unwrap());
+        let (read, _) = discv5.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..read], &discv5_packet[..]);
+
+        // Both halves send through the shared socket
+        discv5
+            .send_to(b"pong", sender.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (read, from) = sender.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..read], b"pong");
+        assert_eq!(from, addr);
+    }
+}
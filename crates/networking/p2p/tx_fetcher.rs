@@ -0,0 +1,547 @@
+//! Retrieval of announced transactions.
+//!
+//! Peers announce most transactions by hash (see [`crate::tx_broadcaster`]), so we keep track of
+//! which peers announced each unknown transaction and fetch it from one of them. Fetching waits a
+//! short while after the first announcement, since the transaction may still arrive through a
+//! full broadcast. Requests which time out or come back without some of the transactions are
+//! retried from the remaining announcers, until none are left.
+
+use std::{
+    collections::{HashMap, HashSet, hash_map::Entry},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethrex_blockchain::Blockchain;
+use ethrex_common::H256;
+use ethrex_storage::error::StoreError;
+use rand::random;
+use spawned_concurrency::{
+    messages::Unused,
+    tasks::{CastResponse, GenServer, GenServerHandle, send_interval},
+};
+use tracing::{debug, info, trace};
+
+use crate::{
+    discv4::peer_table::{PeerTable, PeerTableError},
+    metrics::METRICS,
+    reputation::{Penalty, RequestKind},
+    rlpx::{eth::transactions::NewPooledTransactionHashes, p2p::SUPPORTED_ETH_CAPABILITIES},
+};
+
+/// Time to wait after the first announcement before fetching, in case the transaction is broadcast
+const TX_ARRIVE_TIMEOUT: Duration = Duration::from_millis(500);
+/// Time a peer has to deliver the requested transactions before asking another announcer
+const TX_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Interval between each scheduling of requests
+const DISPATCH_INTERVAL: Duration = Duration::from_millis(100);
+/// Maximum amount of transactions requested at once from a single peer
+const MAX_TXS_PER_REQUEST: usize = 256;
+/// Soft limit for the announced size of the transactions requested at once from a single peer
+const MAX_REQUEST_SIZE: usize = 128 * 1024;
+/// Maximum amount of announced transactions being tracked, further announcements are ignored
+const MAX_TRACKED_TXS: usize = 32768;
+
+/// Type and size of a transaction, as announced by a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TxMetadata {
+    tx_type: u8,
+    size: usize,
+}
+
+#[derive(Debug, Clone)]
+struct TxAnnouncement {
+    /// Peers that announced the transaction and haven't failed to deliver it, in announcement order
+    announcers: Vec<(H256, TxMetadata)>,
+    first_seen: Instant,
+    /// Peer the transaction is currently being requested from
+    fetching_from: Option<H256>,
+}
+
+#[derive(Debug, Clone)]
+struct FetchRequest {
+    id: u64,
+    hashes: Vec<H256>,
+    sent_at: Instant,
+}
+
+/// Request to be sent to a peer, see [`FetchQueue::schedule`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FetchBatch {
+    peer_id: H256,
+    id: u64,
+    announcement: NewPooledTransactionHashes,
+}
+
+/// Announced transactions, and the requests in flight for them
+#[derive(Debug, Clone, Default)]
+struct FetchQueue {
+    announced: HashMap<H256, TxAnnouncement>,
+    // peer_id -> request in flight, peers are asked for one batch at a time
+    requests: HashMap<H256, FetchRequest>,
+}
+
+/// What the fetch queue did with an announced transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Announced {
+    /// The transaction is now tracked
+    New,
+    /// The transaction was already tracked, the peer was added to its announcers
+    Duplicate,
+    /// The transaction was ignored because too many are already tracked
+    Dropped,
+}
+
+impl FetchQueue {
+    /// Tracks a transaction announced by a peer
+    fn announce(
+        &mut self,
+        peer_id: H256,
+        hash: H256,
+        metadata: TxMetadata,
+        now: Instant,
+    ) -> Announced {
+        let tracked = self.announced.len();
+        match self.announced.entry(hash) {
+            Entry::Occupied(mut entry) => {
+                let announcers = &mut entry.get_mut().announcers;
+                if !announcers.iter().any(|(peer, _)| *peer == peer_id) {
+                    announcers.push((peer_id, metadata));
+                }
+                Announced::Duplicate
+            }
+            Entry::Vacant(_) if tracked >= MAX_TRACKED_TXS => Announced::Dropped,
+            Entry::Vacant(entry) => {
+                entry.insert(TxAnnouncement {
+                    announcers: vec![(peer_id, metadata)],
+                    first_seen: now,
+                    fetching_from: None,
+                });
+                Announced::New
+            }
+        }
+    }
+
+    /// Stops tracking transactions that reached us, either through a fetch or a broadcast
+    fn received(&mut self, hashes: &[H256]) {
+        for hash in hashes {
+            self.announced.remove(hash);
+        }
+    }
+
+    /// Handles the response to a request, returning the time it took if it was the one in flight.
+    /// The transactions the peer didn't deliver will be requested from other announcers.
+    fn delivered(
+        &mut self,
+        peer_id: H256,
+        id: u64,
+        delivered: &[H256],
+        now: Instant,
+    ) -> Option<Duration> {
+        self.received(delivered);
+        // Late answers to requests that already timed out leave the current one in flight
+        if self
+            .requests
+            .get(&peer_id)
+            .is_none_or(|request| request.id != id)
+        {
+            return None;
+        }
+        let request = self.requests.remove(&peer_id)?;
+        self.fetch_failed(peer_id, &request.hashes);
+        Some(now.saturating_duration_since(request.sent_at))
+    }
+
+    /// Drops requests that weren't answered in time, returning the peers that failed to answer
+    fn expire(&mut self, now: Instant) -> Vec<H256> {
+        let expired: Vec<(H256, FetchRequest)> = self
+            .requests
+            .extract_if(|_, request| now.duration_since(request.sent_at) >= TX_FETCH_TIMEOUT)
+            .collect();
+        expired
+            .into_iter()
+            .map(|(peer_id, request)| {
+                self.fetch_failed(peer_id, &request.hashes);
+                peer_id
+            })
+            .collect()
+    }
+
+    /// Removes the peer from the announcers of the given transactions, so they are retried elsewhere
+    fn fetch_failed(&mut self, peer_id: H256, hashes: &[H256]) {
+        for hash in hashes {
+            if let Some(announcement) = self.announced.get_mut(hash) {
+                announcement.announcers.retain(|(peer, _)| *peer != peer_id);
+                announcement.fetching_from = None;
+            }
+        }
+    }
+
+    /// Assigns the transactions ready to be fetched to idle announcers, returning the requests to
+    /// send and the amount of transactions dropped because they have no connected announcer left
+    fn schedule(
+        &mut self,
+        now: Instant,
+        is_connected: impl Fn(&H256) -> bool,
+    ) -> (Vec<FetchBatch>, usize) {
+        let mut batches: HashMap<H256, (Vec<(H256, TxMetadata)>, usize)> = HashMap::new();
+        let mut unfetched = Vec::new();
+        for (hash, announcement) in self.announced.iter_mut() {
+            if announcement.fetching_from.is_some()
+                || now.duration_since(announcement.first_seen) < TX_ARRIVE_TIMEOUT
+            {
+                continue;
+            }
+            announcement
+                .announcers
+                .retain(|(peer_id, _)| is_connected(peer_id));
+            if announcement.announcers.is_empty() {
+                unfetched.push(*hash);
+                continue;
+            }
+            let available = announcement.announcers.iter().find(|(peer_id, _)| {
+                !self.requests.contains_key(peer_id)
+                    && batches.get(peer_id).is_none_or(|(batch, size)| {
+                        batch.len() < MAX_TXS_PER_REQUEST && *size < MAX_REQUEST_SIZE
+                    })
+            });
+            // Otherwise all announcers are busy, try again on the next round
+            if let Some((peer_id, metadata)) = available {
+                let (batch, size) = batches.entry(*peer_id).or_default();
+                batch.push((*hash, *metadata));
+                *size += metadata.size;
+                announcement.fetching_from = Some(*peer_id);
+            }
+        }
+        for hash in &unfetched {
+            self.announced.remove(hash);
+        }
+
+        let batches = batches
+            .into_iter()
+            .map(|(peer_id, (batch, _))| {
+                let id = random();
+                self.requests.insert(
+                    peer_id,
+                    FetchRequest {
+                        id,
+                        hashes: batch.iter().map(|(hash, _)| *hash).collect(),
+                        sent_at: now,
+                    },
+                );
+                FetchBatch {
+                    peer_id,
+                    id,
+                    announcement: batch
+                        .into_iter()
+                        .map(|(hash, metadata)| (hash, metadata.tx_type, metadata.size))
+                        .collect(),
+                }
+            })
+            .collect();
+        (batches, unfetched.len())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TxFetcher {
+    peer_table: PeerTable,
+    blockchain: Arc<Blockchain>,
+    queue: FetchQueue,
+}
+
+#[derive(Debug, Clone)]
+pub enum InMessage {
+    /// A peer announced the given transactions
+    Announced(H256, NewPooledTransactionHashes), // (peer_id, announcement)
+    /// A peer answered one of our requests with the given transactions
+    Delivered {
+        peer_id: H256,
+        id: u64,
+        hashes: Vec<H256>,
+    },
+    /// The given transactions were broadcast to us
+    Received(Vec<H256>),
+    /// Periodic message to send the pending requests and retry the expired ones
+    Dispatch,
+}
+
+impl TxFetcher {
+    pub fn spawn(peer_table: PeerTable, blockchain: Arc<Blockchain>) -> GenServerHandle<TxFetcher> {
+        info!("Starting Transaction Fetcher");
+
+        let state = TxFetcher {
+            peer_table,
+            blockchain,
+            queue: FetchQueue::default(),
+        };
+
+        let server = state.start();
+
+        send_interval(DISPATCH_INTERVAL, server.clone(), InMessage::Dispatch);
+
+        server
+    }
+
+    fn announced(
+        &mut self,
+        peer_id: H256,
+        announcement: NewPooledTransactionHashes,
+    ) -> Result<(), TxFetcherError> {
+        // There's no point in fetching transactions we won't add to the mempool
+        if !self.blockchain.is_synced() {
+            return Ok(());
+        }
+        let unknown: HashSet<H256> = announcement
+            .get_transactions_to_request(&self.blockchain)?
+            .into_iter()
+            .collect();
+        let now = Instant::now();
+        for (hash, tx_type, size) in announcement.entries() {
+            let metadata = TxMetadata { tx_type, size };
+            if !unknown.contains(&hash) {
+                METRICS.duplicate_tx_announcements.inc();
+                continue;
+            }
+            match self.queue.announce(peer_id, hash, metadata, now) {
+                Announced::New => {}
+                Announced::Duplicate => METRICS.duplicate_tx_announcements.inc(),
+                Announced::Dropped => METRICS.dropped_tx_announcements.inc(),
+            }
+        }
+        Ok(())
+    }
+
+    async fn delivered(
+        &mut self,
+        peer_id: H256,
+        id: u64,
+        hashes: Vec<H256>,
+    ) -> Result<(), TxFetcherError> {
+        if let Some(latency) = self.queue.delivered(peer_id, id, &hashes, Instant::now()) {
+            self.peer_table
+                .record_response(
+                    &peer_id,
+                    RequestKind::PooledTransactions,
+                    latency,
+                    hashes.len(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&mut self) -> Result<(), TxFetcherError> {
+        let now = Instant::now();
+        for peer_id in self.queue.expire(now) {
+            debug!(peer_id = %format!("{:#x}", peer_id), "Pooled transactions request timed out");
+            self.peer_table
+                .record_timeout(&peer_id, RequestKind::PooledTransactions)
+                .await?;
+            self.peer_table.penalize(&peer_id, Penalty::Timeout).await?;
+        }
+        if self.queue.announced.is_empty() {
+            return Ok(());
+        }
+
+        let mut connections: HashMap<_, _> = self
+            .peer_table
+            .get_peer_connections(&SUPPORTED_ETH_CAPABILITIES)
+            .await?
+            .into_iter()
+            .collect();
+        let (batches, unfetched) = self
+            .queue
+            .schedule(now, |peer_id| connections.contains_key(peer_id));
+        METRICS.unfetched_tx_announcements.inc_by(unfetched as u64);
+
+        for batch in batches {
+            let Some(connection) = connections.get_mut(&batch.peer_id) else {
+                continue;
+            };
+            trace!(
+                peer_id = %format!("{:#x}", batch.peer_id),
+                tx_count = batch.announcement.transaction_hashes.len(),
+                "Fetching pooled transactions"
+            );
+            // If the request can't be sent it will time out and be retried from another announcer
+            let _ = connection
+                .fetch_pooled_transactions(batch.id, batch.announcement)
+                .await
+                .inspect_err(|err| debug!(err = ?err, "Failed to request pooled transactions"));
+        }
+        Ok(())
+    }
+}
+
+impl GenServer for TxFetcher {
+    type CallMsg = Unused;
+    type CastMsg = InMessage;
+    type OutMsg = Unused;
+    type Error = TxFetcherError;
+
+    async fn handle_cast(
+        &mut self,
+        message: Self::CastMsg,
+        _handle: &GenServerHandle<Self>,
+    ) -> CastResponse {
+        let result = match message {
+            Self::CastMsg::Announced(peer_id, announcement) => {
+                trace!(
+                    received = "Announced",
+                    tx_count = announcement.transaction_hashes.len()
+                );
+                self.announced(peer_id, announcement)
+            }
+            Self::CastMsg::Delivered {
+                peer_id,
+                id,
+                hashes,
+            } => {
+                trace!(received = "Delivered", tx_count = hashes.len());
+                self.delivered(peer_id, id, hashes).await
+            }
+            Self::CastMsg::Received(hashes) => {
+                trace!(received = "Received", tx_count = hashes.len());
+                self.queue.received(&hashes);
+                Ok(())
+            }
+            Self::CastMsg::Dispatch => self.dispatch().await,
+        };
+        if let Err(err) = result {
+            debug!(err = %err, "Transaction fetcher error");
+        }
+        CastResponse::NoReply
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TxFetcherError {
+    #[error(transparent)]
+    StoreError(#[from] StoreError),
+    #[error(transparent)]
+    PeerTableError(#[from] PeerTableError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: TxMetadata = TxMetadata {
+        tx_type: 2,
+        size: 100,
+    };
+
+    fn scheduled_hashes(batches: &[FetchBatch], peer_id: H256) -> Vec<H256> {
+        batches
+            .iter()
+            .filter(|batch| batch.peer_id == peer_id)
+            .flat_map(|batch| batch.announcement.transaction_hashes.clone())
+            .collect()
+    }
+
+    #[test]
+    fn fetches_after_arrive_timeout_and_retries_alternate_announcers() {
+        let mut queue = FetchQueue::default();
+        let (peer_a, peer_b) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+        let tx = H256::from_low_u64_be(10);
+        let start = Instant::now();
+
+        assert_eq!(queue.announce(peer_a, tx, METADATA, start), Announced::New);
+        assert_eq!(
+            queue.announce(peer_b, tx, METADATA, start),
+            Announced::Duplicate
+        );
+
+        // Give the transaction a chance to be broadcast before fetching it
+        let (batches, _) = queue.schedule(start, |_| true);
+        assert!(batches.is_empty());
+
+        let now = start + TX_ARRIVE_TIMEOUT;
+        let (batches, unfetched) = queue.schedule(now, |_| true);
+        assert_eq!(scheduled_hashes(&batches, peer_a), vec![tx]);
+        assert_eq!(unfetched, 0);
+        // Already in flight
+        assert!(queue.schedule(now, |_| true).0.is_empty());
+
+        // The first announcer doesn't answer, so we ask the second one
+        assert_eq!(queue.expire(now + TX_FETCH_TIMEOUT), vec![peer_a]);
+        let (batches, _) = queue.schedule(now + TX_FETCH_TIMEOUT, |_| true);
+        assert_eq!(scheduled_hashes(&batches, peer_b), vec![tx]);
+
+        // Which answers without it, leaving no announcers
+        let id = batches[0].id;
+        assert!(queue.delivered(peer_b, id, &[], now).is_some());
+        let (batches, unfetched) = queue.schedule(now + TX_FETCH_TIMEOUT, |_| true);
+        assert!(batches.is_empty());
+        assert_eq!(unfetched, 1);
+        assert!(queue.announced.is_empty());
+    }
+
+    #[test]
+    fn broadcast_and_delivered_txs_are_not_fetched() {
+        let mut queue = FetchQueue::default();
+        let peer = H256::from_low_u64_be(1);
+        let (broadcast_tx, fetched_tx) = (H256::from_low_u64_be(10), H256::from_low_u64_be(11));
+        let start = Instant::now();
+
+        queue.announce(peer, broadcast_tx, METADATA, start);
+        queue.announce(peer, fetched_tx, METADATA, start);
+        queue.received(&[broadcast_tx]);
+
+        let now = start + TX_ARRIVE_TIMEOUT;
+        let (batches, _) = queue.schedule(now, |_| true);
+        assert_eq!(scheduled_hashes(&batches, peer), vec![fetched_tx]);
+
+        // Stale responses don't affect the request in flight
+        assert!(queue.delivered(peer, batches[0].id + 1, &[], now).is_none());
+        assert!(queue.requests.contains_key(&peer));
+
+        assert!(
+            queue
+                .delivered(peer, batches[0].id, &[fetched_tx], now)
+                .is_some()
+        );
+        assert!(queue.announced.is_empty());
+        assert!(queue.requests.is_empty());
+    }
+
+    #[test]
+    fn txs_from_disconnected_announcers_are_dropped() {
+        let mut queue = FetchQueue::default();
+        let peer = H256::from_low_u64_be(1);
+        let start = Instant::now();
+        queue.announce(peer, H256::from_low_u64_be(10), METADATA, start);
+
+        let (batches, unfetched) = queue.schedule(start + TX_ARRIVE_TIMEOUT, |_| false);
+        assert!(batches.is_empty());
+        assert_eq!(unfetched, 1);
+        assert!(queue.announced.is_empty());
+    }
+
+    #[test]
+    fn announcements_over_the_cap_are_dropped() {
+        let mut queue = FetchQueue::default();
+        let (peer_a, peer_b) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+        let start = Instant::now();
+        for i in 0..MAX_TRACKED_TXS as u64 {
+            assert_eq!(
+                queue.announce(peer_a, H256::from_low_u64_be(i), METADATA, start),
+                Announced::New
+            );
+        }
+
+        let extra = H256::from_low_u64_be(MAX_TRACKED_TXS as u64);
+        assert_eq!(
+            queue.announce(peer_a, extra, METADATA, start),
+            Announced::Dropped
+        );
+        assert!(!queue.announced.contains_key(&extra));
+        // Tracked transactions still collect announcers
+        assert_eq!(
+            queue.announce(peer_b, H256::zero(), METADATA, start),
+            Announced::Duplicate
+        );
+        assert_eq!(queue.announced.len(), MAX_TRACKED_TXS);
+    }
+}
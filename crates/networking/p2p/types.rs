@@ -15,7 +15,7 @@ use std::{
     fmt::Display,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
-    sync::OnceLock,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
 };
 use thiserror::Error;
 
@@ -184,15 +184,12 @@ impl Node {
     pub fn from_enr_url(enr: &str) -> Result<Self, NodeError> {
         let base64_decoded = ethrex_common::base64::decode(&enr.as_bytes()[4..]);
         let record = NodeRecord::decode(&base64_decoded).map_err(NodeError::from)?;
+        Self::from_enr(&record)
+    }
+
+    pub fn from_enr(record: &NodeRecord) -> Result<Self, NodeError> {
         let pairs = record.decode_pairs();
-        let public_key = pairs.secp256k1.ok_or(NodeError::MissingField(
-            "public key not found in record".into(),
-        ))?;
-        let verifying_key = PublicKey::from_slice(public_key.as_bytes()).map_err(|_| {
-            NodeError::ParseError("public key could not be built from msg pub key bytes".into())
-        })?;
-        let encoded = verifying_key.serialize_uncompressed();
-        let public_key = H512::from_slice(&encoded[1..]);
+        let public_key = record.public_key()?;
 
         let ip: IpAddr = match (pairs.ip, pairs.ip6) {
             (None, None) => {
@@ -332,6 +329,38 @@ impl NodeRecord {
         decoded_pairs
     }
 
+    /// Returns the uncompressed public key of the record's `secp256k1` entry
+    pub fn public_key(&self) -> Result<H512, NodeError> {
+        let public_key = self
+            .decode_pairs()
+            .secp256k1
+            .ok_or(NodeError::MissingField(
+                "public key not found in record".into(),
+            ))?;
+        let verifying_key = PublicKey::from_slice(public_key.as_bytes()).map_err(|_| {
+            NodeError::ParseError("public key could not be built from msg pub key bytes".into())
+        })?;
+        let encoded = verifying_key.serialize_uncompressed();
+        Ok(H512::from_slice(&encoded[1..]))
+    }
+
+    /// Checks that the record was signed by the owner of its `secp256k1` key
+    pub fn verify_signature(&self) -> bool {
+        let Some(public_key) = self.decode_pairs().secp256k1 else {
+            return false;
+        };
+        let (Ok(public_key), Ok(signature), Ok(message)) = (
+            PublicKey::from_slice(public_key.as_bytes()),
+            secp256k1::ecdsa::Signature::from_compact(self.signature.as_bytes()),
+            secp256k1::Message::from_digest_slice(&self.get_signature_digest()),
+        ) else {
+            return false;
+        };
+        secp256k1::SECP256K1
+            .verify_ecdsa(&message, &signature, &public_key)
+            .is_ok()
+    }
+
     pub fn enr_url(&self) -> Result<String, NodeError> {
         let rlp_encoded = self.encode_to_vec();
         let base64_encoded = ethrex_common::base64::encode(&rlp_encoded);
@@ -410,6 +439,61 @@ impl NodeRecord {
     }
 }
 
+/// Record of the local node, shared by the discovery protocols so that they advertise the same one
+#[derive(Debug, Clone)]
+pub struct LocalNodeRecord(Arc<RwLock<NodeRecord>>);
+
+impl LocalNodeRecord {
+    /// The sequence number starts from the current time so that it grows across restarts
+    pub fn new(
+        node: &Node,
+        signer: &SecretKey,
+        fork_id: Option<ForkId>,
+    ) -> Result<Self, NodeError> {
+        let seq = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut record = NodeRecord::from_node(node, seq, signer)?;
+        if let Some(fork_id) = fork_id {
+            record.set_fork_id(fork_id, signer)?;
+        }
+        Ok(Self(Arc::new(RwLock::new(record))))
+    }
+
+    pub fn get(&self) -> NodeRecord {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.0.read().unwrap_or_else(PoisonError::into_inner).seq
+    }
+
+    /// Re-signs the record with the address of `node` and a bumped sequence number, keeping its
+    /// fork id. Returns the new record, or `None` if it already advertised that address.
+    pub fn set_address(
+        &self,
+        node: &Node,
+        signer: &SecretKey,
+    ) -> Result<Option<NodeRecord>, NodeError> {
+        let mut current = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        let pairs = current.decode_pairs();
+        let advertised_ip = pairs.ip.map(IpAddr::from).or(pairs.ip6.map(IpAddr::from));
+        if advertised_ip == Some(node.ip) && pairs.udp_port == Some(node.udp_port) {
+            return Ok(None);
+        }
+        let mut record = NodeRecord::from_node(node, current.seq + 1, signer)?;
+        if let Some(fork_id) = pairs.eth {
+            record.set_fork_id(fork_id, signer)?;
+        }
+        *current = record.clone();
+        Ok(Some(record))
+    }
+}
+
 impl From<NodeRecordPairs> for Vec<(Bytes, Bytes)> {
     fn from(value: NodeRecordPairs) -> Self {
         let mut pairs = vec![];
@@ -513,10 +597,10 @@ impl RLPEncode for Node {
 #[cfg(test)]
 mod tests {
     use crate::{
-        types::{Node, NodeRecord},
+        types::{LocalNodeRecord, Node, NodeRecord},
         utils::public_key_from_signing_key,
     };
-    use ethrex_common::H512;
+    use ethrex_common::{H512, types::ForkId};
     use ethrex_rlp::decode::RLPDecode;
     use ethrex_storage::{EngineType, Store};
     use secp256k1::SecretKey;
@@ -645,4 +729,59 @@ mod tests {
 
         assert_eq!(pairs.eth, Some(fork_id));
     }
+
+    #[test]
+    fn verify_node_record_signature() {
+        let signer = SecretKey::from_slice(&[
+            16, 125, 177, 238, 167, 212, 168, 215, 239, 165, 77, 224, 199, 143, 55, 205, 9, 194,
+            87, 139, 92, 46, 30, 191, 74, 37, 68, 242, 38, 225, 104, 246,
+        ])
+        .unwrap();
+        let node = Node::new(
+            "127.0.0.1".parse().unwrap(),
+            30303,
+            30303,
+            public_key_from_signing_key(&signer),
+        );
+        let mut record = NodeRecord::from_node(&node, 1, &signer).unwrap();
+        assert!(record.verify_signature());
+        assert_eq!(Node::from_enr(&record).unwrap().node_id(), node.node_id());
+
+        record.seq = 2;
+        assert!(!record.verify_signature());
+    }
+
+    #[test]
+    fn local_node_record_only_bumps_seq_on_address_changes() {
+        let signer = SecretKey::from_slice(&[1; 32]).unwrap();
+        let node = Node::new(
+            "127.0.0.1".parse().unwrap(),
+            30303,
+            30303,
+            public_key_from_signing_key(&signer),
+        );
+        let fork_id = ForkId {
+            fork_hash: ethrex_common::H32::repeat_byte(0xab),
+            fork_next: 0,
+        };
+        let record = LocalNodeRecord::new(&node, &signer, Some(fork_id.clone())).unwrap();
+        // Clones share the record, like the discovery servers do
+        let shared = record.clone();
+        let seq = record.seq();
+
+        assert!(shared.set_address(&node, &signer).unwrap().is_none());
+        assert_eq!(record.seq(), seq);
+
+        let mut moved = node.clone();
+        moved.ip = "203.0.113.1".parse().unwrap();
+        let updated = shared.set_address(&moved, &signer).unwrap().unwrap();
+        assert_eq!(updated.seq, seq + 1);
+        assert_eq!(record.get(), updated);
+        assert_eq!(updated.decode_pairs().eth, Some(fork_id));
+        assert!(updated.verify_signature());
+
+        // The other protocol voting for the same address doesn't bump it again
+        assert!(record.set_address(&moved, &signer).unwrap().is_none());
+        assert_eq!(record.seq(), seq + 1);
+    }
 }
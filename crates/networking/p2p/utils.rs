@@ -1,6 +1,11 @@
 use crate::peer_handler::DumpError;
-use ethrex_common::{H256, H512, U256, types::AccountState, utils::keccak};
+use ethrex_common::{
+    H256, H512, U256,
+    types::{AccountState, ForkId},
+    utils::keccak,
+};
 use ethrex_rlp::encode::RLPEncode;
+use ethrex_storage::{Store, error::StoreError};
 use secp256k1::{PublicKey, SecretKey};
 use std::{
     path::{Path, PathBuf},
@@ -39,6 +44,40 @@ pub fn public_key_from_signing_key(signer: &SecretKey) -> H512 {
     H512::from_slice(&encoded[1..])
 }
 
+/// Checks whether a fork id advertised by a remote node is compatible with our chain.
+/// Returns the result along with the local fork id.
+pub async fn validate_fork_id(
+    store: &Store,
+    remote_fork_id: ForkId,
+) -> Result<(bool, ForkId), StoreError> {
+    let chain_config = store.get_chain_config();
+    let genesis_header = store
+        .get_block_header(0)?
+        .ok_or(StoreError::Custom("Genesis header not found".to_string()))?;
+    let latest_block_number = store.get_latest_block_number().await?;
+    let latest_block_header =
+        store
+            .get_block_header(latest_block_number)?
+            .ok_or(StoreError::Custom(
+                "Latest block header not found".to_string(),
+            ))?;
+
+    let local_fork_id = ForkId::new(
+        &chain_config,
+        genesis_header.clone(),
+        latest_block_header.timestamp,
+        latest_block_number,
+    );
+    let is_valid = local_fork_id.is_valid(
+        remote_fork_id,
+        latest_block_number,
+        latest_block_header.timestamp,
+        &chain_config,
+        genesis_header,
+    );
+    Ok((is_valid, local_fork_id))
+}
+
 /// Deletes the snap folders needed for downloading the leaves during the initial
 /// step of snap sync.
 pub fn delete_leaves_folder(datadir: &Path) {
@@ -10,7 +10,9 @@ use crate::{
     utils::{RpcErr, RpcRequest},
 };
 mod peers;
-pub use peers::{add_peer, peers};
+pub use peers::{
+    add_peer, add_trusted_peer, ban_peer, peers, remove_peer, remove_trusted_peer, unban_peer,
+};
 
 #[derive(Serialize, Debug)]
 struct NodeInfo {
@@ -3,13 +3,16 @@ use crate::{rpc::RpcApiContext, utils::RpcErr};
 use core::net::SocketAddr;
 use ethrex_common::H256;
 use ethrex_p2p::{
-    discv4::peer_table::PeerData,
+    discv4::peer_table::{PeerData, PeerTable},
     peer_handler::PeerHandler,
+    reputation::{Penalty, RequestKind, RequestStats},
     rlpx::{initiator::InMessage, p2p::Capability},
     types::Node,
+    utils::current_unix_time,
 };
 use serde::Serialize;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use tokio::time::{Duration, Instant};
 
 /// Serializable peer data returned by the node's rpc
@@ -21,6 +24,7 @@ pub struct RpcPeer {
     name: String,
     network: PeerNetwork,
     protocols: Protocols,
+    reputation: PeerReputation,
 }
 
 /// Serializable peer network data returned by the node's rpc
@@ -32,6 +36,17 @@ struct PeerNetwork {
     remote_address: SocketAddr,
 }
 
+/// Serializable peer reputation returned by the node's rpc
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PeerReputation {
+    score: f64,
+    /// Amount of times each penalty was applied to the peer
+    penalties: BTreeMap<Penalty, u64>,
+    /// Latency and throughput statistics for each type of request
+    requests: BTreeMap<RequestKind, RequestStats>,
+}
+
 /// Serializable peer protocols data returned by the node's rpc
 #[derive(Default, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -81,6 +96,11 @@ impl From<PeerData> for RpcPeer {
                 inbound: peer.is_connection_inbound,
             },
             protocols,
+            reputation: PeerReputation {
+                score: peer.reputation.score(current_unix_time()),
+                penalties: peer.reputation.penalties,
+                requests: peer.reputation.requests,
+            },
         }
     }
 }
@@ -126,6 +146,13 @@ pub async fn add_peer(context: &mut RpcApiContext, request: &RpcRequest) -> Resu
     let mut server = peer_handler.initiator.clone();
     let node = parse(request)?;
 
+    // Peers added through the admin API are static, they are redialed whenever they disconnect
+    peer_handler
+        .peer_table
+        .add_static_peer(node.clone())
+        .await
+        .map_err(|e| RpcErr::Internal(e.to_string()))?;
+
     let start = Instant::now();
     let runtime = Duration::from_secs(10);
 
@@ -146,6 +173,71 @@ pub async fn add_peer(context: &mut RpcApiContext, request: &RpcRequest) -> Resu
     }
 }
 
+pub async fn remove_peer(
+    context: &mut RpcApiContext,
+    request: &RpcRequest,
+) -> Result<Value, RpcErr> {
+    let node = parse(request)?;
+    peer_table(context)?
+        .remove_static_peer(node.node_id())
+        .await
+        .map_err(|e| RpcErr::Internal(e.to_string()))?;
+    Ok(serde_json::to_value(true)?)
+}
+
+pub async fn add_trusted_peer(
+    context: &mut RpcApiContext,
+    request: &RpcRequest,
+) -> Result<Value, RpcErr> {
+    let node = parse(request)?;
+    peer_table(context)?
+        .add_trusted_peer(node)
+        .await
+        .map_err(|e| RpcErr::Internal(e.to_string()))?;
+    Ok(serde_json::to_value(true)?)
+}
+
+pub async fn remove_trusted_peer(
+    context: &mut RpcApiContext,
+    request: &RpcRequest,
+) -> Result<Value, RpcErr> {
+    let node = parse(request)?;
+    peer_table(context)?
+        .remove_trusted_peer(node.node_id())
+        .await
+        .map_err(|e| RpcErr::Internal(e.to_string()))?;
+    Ok(serde_json::to_value(true)?)
+}
+
+pub async fn ban_peer(context: &mut RpcApiContext, request: &RpcRequest) -> Result<Value, RpcErr> {
+    let node = parse(request)?;
+    peer_table(context)?
+        .ban_peer(node)
+        .await
+        .map_err(|e| RpcErr::Internal(e.to_string()))?;
+    Ok(serde_json::to_value(true)?)
+}
+
+pub async fn unban_peer(
+    context: &mut RpcApiContext,
+    request: &RpcRequest,
+) -> Result<Value, RpcErr> {
+    let node = parse(request)?;
+    peer_table(context)?
+        .unban_peer(node.node_id())
+        .await
+        .map_err(|e| RpcErr::Internal(e.to_string()))?;
+    Ok(serde_json::to_value(true)?)
+}
+
+fn peer_table(context: &mut RpcApiContext) -> Result<&mut PeerTable, RpcErr> {
+    context
+        .peer_handler
+        .as_mut()
+        .map(|peer_handler| &mut peer_handler.peer_table)
+        .ok_or(RpcErr::Internal("Peer handler not initialized".to_string()))
+}
+
 async fn peer_is_connected(peer_handler: &mut PeerHandler, enode_url: &str) -> bool {
     peer_handler
         .read_connected_peers()
@@ -180,7 +272,7 @@ mod tests {
         // The first serialized peer shown in geth's documentation example: https://geth.ethereum.org/docs/interacting-with-geth/rpc/ns-admin#admin-peers
         // The fields "localAddress", "static", "trusted" and "name" were removed as we do not have the necessary information to show them
         // Misc: Added 0x prefix to node id, there is no set spec for this method so the prefix shouldn't be a problem, also changed version name
-        let expected_serialized_peer = r#"{"caps":["eth/68","snap/1"],"enode":"enode://4aeb4ab6c14b23e2c4cfdce879c04b0748a20d8e9b59e25ded2a08143e265c6c25936e74cbc8e641e3312ca288673d91f2f93f8e277de3cfa444ecdaaf982052@157.90.35.166:30303","id":"0x6b36f791352f15eb3ec4f67787074ab8ad9d487e37c4401d383f0561a0a20507","name":"ethrex/test","network":{"inbound":false,"remoteAddress":"157.90.35.166:30303"},"protocols":{"eth":{"version":68},"snap":{"version":1}}}"#.to_string();
+        let expected_serialized_peer = r#"{"caps":["eth/68","snap/1"],"enode":"enode://4aeb4ab6c14b23e2c4cfdce879c04b0748a20d8e9b59e25ded2a08143e265c6c25936e74cbc8e641e3312ca288673d91f2f93f8e277de3cfa444ecdaaf982052@157.90.35.166:30303","id":"0x6b36f791352f15eb3ec4f67787074ab8ad9d487e37c4401d383f0561a0a20507","name":"ethrex/test","network":{"inbound":false,"remoteAddress":"157.90.35.166:30303"},"protocols":{"eth":{"version":68},"snap":{"version":1}},"reputation":{"score":0.0,"penalties":{},"requests":{}}}"#.to_string();
         let serialized_peer =
             serde_json::to_string(&RpcPeer::from(peer)).expect("Failed to serialize peer");
         assert_eq!(serialized_peer, expected_serialized_peer);
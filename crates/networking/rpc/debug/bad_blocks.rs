@@ -0,0 +1,158 @@
+use ethrex_blockchain::{error::InvalidForkChoice, fork_choice::rewind_head};
+use ethrex_common::H256;
+use ethrex_rlp::encode::RLPEncode;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{debug, info};
+
+use crate::{RpcApiContext, RpcErr, RpcHandler, types::block::RpcBlock, utils::parse_json_hex};
+
+/// A block that was rejected during validation or execution, as returned by `debug_getBadBlocks`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBadBlock {
+    pub hash: H256,
+    pub block: RpcBlock,
+    pub rlp: String,
+    pub reason: String,
+}
+
+pub struct GetBadBlocksRequest;
+
+impl RpcHandler for GetBadBlocksRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<GetBadBlocksRequest, RpcErr> {
+        if params.as_ref().is_some_and(|params| !params.is_empty()) {
+            return Err(RpcErr::BadParams("No params expected".to_owned()));
+        }
+        Ok(GetBadBlocksRequest)
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        debug!("Requested bad blocks");
+        let bad_blocks = context
+            .storage
+            .get_bad_blocks()
+            .await?
+            .into_iter()
+            .map(|(block, reason)| -> Result<RpcBadBlock, RpcErr> {
+                let hash = block.hash();
+                let rlp = format!("0x{}", hex::encode(block.encode_to_vec()));
+                Ok(RpcBadBlock {
+                    hash,
+                    block: RpcBlock::build(block.header, block.body, hash, true)?,
+                    rlp,
+                    reason,
+                })
+            })
+            .collect::<Result<Vec<_>, RpcErr>>()?;
+
+        serde_json::to_value(bad_blocks).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+/// Rewinds the canonical chain to the given block number. Only served through the authenticated
+/// RPC endpoint.
+pub struct SetHeadRequest {
+    pub number: u64,
+}
+
+impl RpcHandler for SetHeadRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<SetHeadRequest, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams("Expected 1 param".to_owned()));
+        };
+        Ok(SetHeadRequest {
+            number: parse_json_hex(&params[0]).map_err(RpcErr::BadParams)?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let head = rewind_head(&context.storage, self.number)
+            .await
+            .map_err(|err| match err {
+                InvalidForkChoice::StoreError(err) => RpcErr::from(err),
+                err => RpcErr::BadParams(err.to_string()),
+            })?;
+        info!(number = head.number, hash = %head.hash(), "Rewound canonical chain head");
+        Ok(Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::map_http_requests;
+    use crate::test_utils::{default_context_with_storage, setup_store};
+    use crate::utils::RpcRequest;
+    use ethrex_blockchain::error::ChainError;
+    use ethrex_common::types::{Block, BlockBody, BlockHeader};
+    use serde_json::json;
+
+    fn get_bad_blocks_request() -> RpcRequest {
+        serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "method": "debug_getBadBlocks",
+            "id": 1
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn no_bad_blocks() {
+        let context = default_context_with_storage(setup_store().await).await;
+        let response = map_http_requests(&get_bad_blocks_request(), context)
+            .await
+            .unwrap();
+        assert_eq!(response, json!([]));
+    }
+
+    #[tokio::test]
+    async fn rejects_params() {
+        let params = Some(vec![json!("0x1")]);
+        assert!(matches!(
+            GetBadBlocksRequest::parse(&params),
+            Err(RpcErr::BadParams(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn returns_blocks_rejected_by_the_blockchain() {
+        let context = default_context_with_storage(setup_store().await).await;
+        let genesis = context.storage.get_block_header(0).unwrap().unwrap();
+        // A child of the genesis block whose header doesn't pass validation
+        let block = Block::new(
+            BlockHeader {
+                parent_hash: genesis.hash(),
+                number: 1,
+                timestamp: genesis.timestamp + 12,
+                gas_limit: genesis.gas_limit,
+                ..Default::default()
+            },
+            BlockBody::default(),
+        );
+        let hash = block.hash();
+        let error = context
+            .blockchain
+            .add_block_pipeline(block.clone())
+            .unwrap_err();
+        let ChainError::InvalidBlock(reason) = error else {
+            panic!("Block wasn't rejected as invalid: {error}");
+        };
+
+        let response = map_http_requests(&get_bad_blocks_request(), context)
+            .await
+            .unwrap();
+        let bad_blocks = response.as_array().unwrap();
+        assert_eq!(bad_blocks.len(), 1);
+        assert_eq!(bad_blocks[0]["hash"], json!(hash));
+        assert_eq!(bad_blocks[0]["block"]["hash"], json!(hash));
+        assert_eq!(bad_blocks[0]["reason"], json!(reason.to_string()));
+        assert_eq!(
+            bad_blocks[0]["rlp"],
+            json!(format!("0x{}", hex::encode(block.encode_to_vec())))
+        );
+    }
+}
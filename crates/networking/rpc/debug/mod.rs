@@ -1 +1,2 @@
+pub mod bad_blocks;
 pub mod execution_witness;
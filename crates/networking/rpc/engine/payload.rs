@@ -212,6 +212,9 @@ impl RpcHandler for NewPayloadV4Request {
                 chain_config.get_fork(block.header.timestamp)
             )));
         }
+        if chain_config.is_amsterdam_activated(block.header.timestamp) {
+            return Err(RpcErr::UnsuportedFork(format!("{:?}", Fork::Amsterdam)));
+        }
         // We use v3 since the execution payload remains the same.
         validate_execution_payload_v3(&self.payload)?;
         let payload_status = handle_new_payload_v3(
@@ -225,6 +228,85 @@ impl RpcHandler for NewPayloadV4Request {
     }
 }
 
+pub struct NewPayloadV5Request {
+    pub payload: ExecutionPayload,
+    pub expected_blob_versioned_hashes: Vec<H256>,
+    pub parent_beacon_block_root: H256,
+    pub execution_requests: Vec<EncodedRequests>,
+}
+
+impl From<NewPayloadV5Request> for RpcRequest {
+    fn from(val: NewPayloadV5Request) -> Self {
+        RpcRequest {
+            method: "engine_newPayloadV5".to_string(),
+            params: Some(vec![
+                serde_json::json!(val.payload),
+                serde_json::json!(val.expected_blob_versioned_hashes),
+                serde_json::json!(val.parent_beacon_block_root),
+                serde_json::json!(val.execution_requests),
+            ]),
+            ..Default::default()
+        }
+    }
+}
+
+impl RpcHandler for NewPayloadV5Request {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let NewPayloadV4Request {
+            payload,
+            expected_blob_versioned_hashes,
+            parent_beacon_block_root,
+            execution_requests,
+        } = NewPayloadV4Request::parse(params)?;
+        Ok(NewPayloadV5Request {
+            payload,
+            expected_blob_versioned_hashes,
+            parent_beacon_block_root,
+            execution_requests,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        // validate the received requests
+        validate_execution_requests(&self.execution_requests)?;
+        // The access list is checked before building the block so a missing one isn't reported
+        // as an invalid block hash
+        validate_execution_payload_v4(&self.payload)?;
+
+        let requests_hash = compute_requests_hash(&self.execution_requests);
+        let block = match get_block_from_payload(
+            &self.payload,
+            Some(self.parent_beacon_block_root),
+            Some(requests_hash),
+        ) {
+            Ok(block) => block,
+            Err(err) => {
+                return Ok(serde_json::to_value(PayloadStatus::invalid_with_err(
+                    &err.to_string(),
+                ))?);
+            }
+        };
+
+        let chain_config = context.storage.get_chain_config();
+
+        if !chain_config.is_amsterdam_activated(block.header.timestamp) {
+            return Err(RpcErr::UnsuportedFork(format!(
+                "{:?}",
+                chain_config.get_fork(block.header.timestamp)
+            )));
+        }
+        // The access list itself is checked against the one recorded while executing the block
+        let payload_status = handle_new_payload_v3(
+            &self.payload,
+            context,
+            block,
+            self.expected_blob_versioned_hashes.clone(),
+        )
+        .await?;
+        serde_json::to_value(payload_status).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
 // GetPayload V1-V2-V3 implementations
 pub struct GetPayloadV1Request {
     pub payload_id: u64,
@@ -392,6 +474,9 @@ impl RpcHandler for GetPayloadV5Request {
                 chain_config.get_fork(payload_bundle.block.header.timestamp)
             )));
         }
+        if chain_config.is_amsterdam_activated(payload_bundle.block.header.timestamp) {
+            return Err(RpcErr::UnsuportedFork(format!("{:?}", Fork::Amsterdam)));
+        }
 
         let response = ExecutionPayloadResponse {
             execution_payload: ExecutionPayload::from_block(payload_bundle.block),
@@ -411,6 +496,61 @@ impl RpcHandler for GetPayloadV5Request {
     }
 }
 
+pub struct GetPayloadV6Request {
+    pub payload_id: u64,
+}
+
+impl From<GetPayloadV6Request> for RpcRequest {
+    fn from(val: GetPayloadV6Request) -> Self {
+        RpcRequest {
+            method: "engine_getPayloadV6".to_string(),
+            params: Some(vec![serde_json::json!(U256::from(val.payload_id))]),
+            ..Default::default()
+        }
+    }
+}
+
+impl RpcHandler for GetPayloadV6Request {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let payload_id = parse_get_payload_request(params)?;
+        Ok(Self { payload_id })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let payload_bundle = get_payload(self.payload_id, &context).await?;
+        let chain_config = &context.storage.get_chain_config();
+
+        if !chain_config.is_amsterdam_activated(payload_bundle.block.header.timestamp) {
+            return Err(RpcErr::UnsuportedFork(format!(
+                "{:?}",
+                chain_config.get_fork(payload_bundle.block.header.timestamp)
+            )));
+        }
+        let Some(block_access_list) = payload_bundle.block_access_list else {
+            return Err(RpcErr::Internal(
+                "Amsterdam payload was built without a block access list".to_string(),
+            ));
+        };
+
+        let response = ExecutionPayloadResponse {
+            execution_payload: ExecutionPayload::from_block(payload_bundle.block)
+                .with_block_access_list(&block_access_list),
+            block_value: payload_bundle.block_value,
+            blobs_bundle: Some(payload_bundle.blobs_bundle),
+            should_override_builder: Some(false),
+            execution_requests: Some(
+                payload_bundle
+                    .requests
+                    .into_iter()
+                    .filter(|r| !r.is_empty())
+                    .collect(),
+            ),
+        };
+
+        serde_json::to_value(response).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
 pub struct GetPayloadBodiesByHashV1Request {
     pub hashes: Vec<BlockHash>,
 }
@@ -507,6 +647,9 @@ fn validate_execution_payload_v1(payload: &ExecutionPayload) -> Result<(), RpcEr
     if payload.excess_blob_gas.is_some() {
         return Err(RpcErr::WrongParam("excess_blob_gas".to_string()));
     }
+    if payload.block_access_list.is_some() {
+        return Err(RpcErr::WrongParam("block_access_list".to_string()));
+    }
 
     Ok(())
 }
@@ -522,6 +665,9 @@ fn validate_execution_payload_v2(payload: &ExecutionPayload) -> Result<(), RpcEr
     if payload.excess_blob_gas.is_some() {
         return Err(RpcErr::WrongParam("excess_blob_gas".to_string()));
     }
+    if payload.block_access_list.is_some() {
+        return Err(RpcErr::WrongParam("block_access_list".to_string()));
+    }
 
     Ok(())
 }
@@ -537,6 +683,27 @@ fn validate_execution_payload_v3(payload: &ExecutionPayload) -> Result<(), RpcEr
     if payload.excess_blob_gas.is_none() {
         return Err(RpcErr::WrongParam("excess_blob_gas".to_string()));
     }
+    if payload.block_access_list.is_some() {
+        return Err(RpcErr::WrongParam("block_access_list".to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate_execution_payload_v4(payload: &ExecutionPayload) -> Result<(), RpcErr> {
+    // Validate that only the required arguments are present
+    if payload.withdrawals.is_none() {
+        return Err(RpcErr::WrongParam("withdrawals".to_string()));
+    }
+    if payload.blob_gas_used.is_none() {
+        return Err(RpcErr::WrongParam("blob_gas_used".to_string()));
+    }
+    if payload.excess_blob_gas.is_none() {
+        return Err(RpcErr::WrongParam("excess_blob_gas".to_string()));
+    }
+    if payload.block_access_list.is_none() {
+        return Err(RpcErr::WrongParam("block_access_list".to_string()));
+    }
 
     Ok(())
 }
@@ -814,12 +981,13 @@ async fn get_payload(payload_id: u64, context: &RpcApiContext) -> Result<Payload
         id = %format!("{:#018x}", payload_id),
         "Requested payload with"
     );
-    let (blobs_bundle, requests, block_value, block) = {
+    let (blobs_bundle, requests, block_value, block, block_access_list) = {
         let PayloadBuildResult {
             blobs_bundle,
             block_value,
             requests,
             payload,
+            block_access_list,
             ..
         } = context
             .blockchain
@@ -831,7 +999,13 @@ async fn get_payload(payload_id: u64, context: &RpcApiContext) -> Result<Payload
                 }
                 err => RpcErr::Internal(err.to_string()),
             })?;
-        (blobs_bundle, requests, block_value, payload)
+        (
+            blobs_bundle,
+            requests,
+            block_value,
+            payload,
+            block_access_list,
+        )
     };
 
     let new_payload = PayloadBundle {
@@ -839,6 +1013,7 @@ async fn get_payload(payload_id: u64, context: &RpcApiContext) -> Result<Payload
         block_value,
         blobs_bundle,
         requests,
+        block_access_list,
     };
 
     Ok(new_payload)
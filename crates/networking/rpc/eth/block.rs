@@ -14,7 +14,7 @@ use crate::{
 use ethrex_common::types::{
     Block, BlockBody, BlockHash, BlockHeader, BlockNumber, Receipt, calculate_base_fee_per_blob_gas,
 };
-use ethrex_storage::Store;
+use ethrex_storage::{Store, error::StoreError};
 
 pub struct GetBlockByNumberRequest {
     pub block: BlockIdentifier,
@@ -70,6 +70,7 @@ impl RpcHandler for GetBlockByNumberRequest {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(storage, block_number).await?;
         let header = storage.get_block_header(block_number)?;
         let body = storage.get_block_body(block_number).await?;
         let (header, body) = match (header, body) {
@@ -104,6 +105,7 @@ impl RpcHandler for GetBlockByHashRequest {
             Some(number) => number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(storage, block_number).await?;
         let header = storage.get_block_header(block_number)?;
         let body = storage.get_block_body(block_number).await?;
         let (header, body) = match (header, body) {
@@ -139,6 +141,7 @@ impl RpcHandler for GetBlockTransactionCountRequest {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(&context.storage, block_number).await?;
         let block_body = match context.storage.get_block_body(block_number).await? {
             Some(block_body) => block_body,
             _ => return Ok(Value::Null),
@@ -170,6 +173,7 @@ impl RpcHandler for GetBlockReceiptsRequest {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(storage, block_number).await?;
         let header = storage.get_block_header(block_number)?;
         let body = storage.get_block_body(block_number).await?;
         let (header, body) = match (header, body) {
@@ -235,6 +239,7 @@ impl RpcHandler for GetRawBlockRequest {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(&context.storage, block_number).await?;
         let header = context.storage.get_block_header(block_number)?;
         let body = context.storage.get_block_body(block_number).await?;
         let (header, body) = match (header, body) {
@@ -268,6 +273,7 @@ impl RpcHandler for GetRawReceipts {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(storage, block_number).await?;
         let header = storage.get_block_header(block_number)?;
         let body = storage.get_block_body(block_number).await?;
         let (header, body) = match (header, body) {
@@ -324,6 +330,23 @@ impl RpcHandler for GetBlobBaseFee {
     }
 }
 
+/// Returns [RpcErr::PrunedHistory] if the body and receipts of the block were removed by history expiry
+pub(crate) async fn ensure_history_available(
+    storage: &Store,
+    block_number: BlockNumber,
+) -> Result<(), RpcErr> {
+    let earliest = match storage.get_earliest_block_number().await {
+        Ok(earliest) => earliest,
+        // Nothing was ever pruned
+        Err(StoreError::MissingEarliestBlockNumber) => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    if block_number < earliest {
+        return Err(RpcErr::PrunedHistory);
+    }
+    Ok(())
+}
+
 pub async fn get_all_block_rpc_receipts(
     block_number: BlockNumber,
     header: BlockHeader,
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
-    eth::block,
+    eth::block::{self, ensure_history_available},
     rpc::{RpcApiContext, RpcHandler},
     types::{
         block_identifier::BlockIdentifier,
@@ -148,6 +148,7 @@ impl RpcHandler for GetTransactionByBlockNumberAndIndexRequest {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(&context.storage, block_number).await?;
         let block_body = match context.storage.get_block_body(block_number).await? {
             Some(block_body) => block_body,
             _ => return Ok(Value::Null),
@@ -199,6 +200,7 @@ impl RpcHandler for GetTransactionByBlockHashAndIndexRequest {
             Some(number) => number,
             _ => return Ok(Value::Null),
         };
+        ensure_history_available(&context.storage, block_number).await?;
         let block_body = match context.storage.get_block_body(block_number).await? {
             Some(block_body) => block_body,
             _ => return Ok(Value::Null),
@@ -1,7 +1,8 @@
 use crate::authentication::authenticate;
+use crate::debug::bad_blocks::{GetBadBlocksRequest, SetHeadRequest};
 use crate::debug::execution_witness::ExecutionWitnessRequest;
 use crate::engine::blobs::{BlobsV2Request, BlobsV3Request};
-use crate::engine::payload::GetPayloadV5Request;
+use crate::engine::payload::{GetPayloadV5Request, GetPayloadV6Request, NewPayloadV5Request};
 use crate::engine::{
     ExchangeCapabilitiesRequest,
     blobs::BlobsV1Request,
@@ -39,7 +40,7 @@ use crate::eth::{
 use crate::pir::{DumpStorageRequest, GetStateDeltaRequest};
 use crate::tracing::{TraceBlockByNumberRequest, TraceTransactionRequest};
 use crate::types::transaction::SendRawTransactionRequest;
-use crate::ubt::GetRootRequest;
+use crate::ubt::{GetRootRequest, GetWitnessRequest};
 use crate::utils::{
     RpcErr, RpcErrorMetadata, RpcErrorResponse, RpcNamespace, RpcRequest, RpcRequestId,
     RpcSuccessResponse,
@@ -238,6 +239,7 @@ fn get_error_kind(err: &RpcErr) -> &'static str {
         RpcErr::InvalidForkChoiceState(_) => "InvalidForkChoiceState",
         RpcErr::InvalidPayloadAttributes(_) => "InvalidPayloadAttributes",
         RpcErr::UnknownPayload(_) => "UnknownPayload",
+        RpcErr::PrunedHistory => "PrunedHistory",
     }
 }
 
@@ -511,6 +513,10 @@ pub async fn map_authrpc_requests(
     match req.namespace() {
         Ok(RpcNamespace::Engine) => map_engine_requests(req, context).await,
         Ok(RpcNamespace::Eth) => map_eth_requests(req, context).await,
+        // Rewinding the chain is only allowed through the authenticated endpoint
+        Ok(RpcNamespace::Debug) if req.method == "debug_setHead" => {
+            SetHeadRequest::call(req, context).await
+        }
         _ => Err(RpcErr::MethodNotFound(req.method.clone())),
     }
 }
@@ -576,6 +582,7 @@ pub async fn map_debug_requests(req: &RpcRequest, context: RpcApiContext) -> Res
         "debug_executionWitness" => ExecutionWitnessRequest::call(req, context).await,
         "debug_traceTransaction" => TraceTransactionRequest::call(req, context).await,
         "debug_traceBlockByNumber" => TraceBlockByNumberRequest::call(req, context).await,
+        "debug_getBadBlocks" => GetBadBlocksRequest::call(req, context).await,
         unknown_debug_method => Err(RpcErr::MethodNotFound(unknown_debug_method.to_owned())),
     }
 }
@@ -589,6 +596,7 @@ pub async fn map_engine_requests(
         "engine_forkchoiceUpdatedV1" => ForkChoiceUpdatedV1::call(req, context).await,
         "engine_forkchoiceUpdatedV2" => ForkChoiceUpdatedV2::call(req, context).await,
         "engine_forkchoiceUpdatedV3" => ForkChoiceUpdatedV3::call(req, context).await,
+        "engine_newPayloadV5" => NewPayloadV5Request::call(req, context).await,
         "engine_newPayloadV4" => NewPayloadV4Request::call(req, context).await,
         "engine_newPayloadV3" => NewPayloadV3Request::call(req, context).await,
         "engine_newPayloadV2" => NewPayloadV2Request::call(req, context).await,
@@ -596,6 +604,7 @@ pub async fn map_engine_requests(
         "engine_exchangeTransitionConfigurationV1" => {
             ExchangeTransitionConfigV1Req::call(req, context).await
         }
+        "engine_getPayloadV6" => GetPayloadV6Request::call(req, context).await,
         "engine_getPayloadV5" => GetPayloadV5Request::call(req, context).await,
         "engine_getPayloadV4" => GetPayloadV4Request::call(req, context).await,
         "engine_getPayloadV3" => GetPayloadV3Request::call(req, context).await,
@@ -623,6 +632,11 @@ pub async fn map_admin_requests(
         "admin_peers" => admin::peers(&mut context).await,
         "admin_setLogLevel" => admin::set_log_level(req, &context.log_filter_handler),
         "admin_addPeer" => admin::add_peer(&mut context, req).await,
+        "admin_removePeer" => admin::remove_peer(&mut context, req).await,
+        "admin_addTrustedPeer" => admin::add_trusted_peer(&mut context, req).await,
+        "admin_removeTrustedPeer" => admin::remove_trusted_peer(&mut context, req).await,
+        "admin_banPeer" => admin::ban_peer(&mut context, req).await,
+        "admin_unbanPeer" => admin::unban_peer(&mut context, req).await,
         unknown_admin_method => Err(RpcErr::MethodNotFound(unknown_admin_method.to_owned())),
     }
 }
@@ -654,6 +668,7 @@ pub fn map_mempool_requests(req: &RpcRequest, contex: RpcApiContext) -> Result<V
 pub async fn map_ubt_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
     match req.method.as_str() {
         "ubt_getRoot" => GetRootRequest::call(req, context).await,
+        "ubt_getWitness" => GetWitnessRequest::call(req, context).await,
         unknown_ubt_method => Err(RpcErr::MethodNotFound(unknown_ubt_method.to_owned())),
     }
 }
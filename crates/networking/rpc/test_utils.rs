@@ -289,6 +289,7 @@ pub async fn dummy_sync_manager() -> SyncManager {
         Store::new("temp.db", ethrex_storage::EngineType::InMemory)
             .expect("Failed to start Storage Engine"),
         ".".into(),
+        None,
     )
     .await
 }
@@ -296,7 +297,7 @@ pub async fn dummy_sync_manager() -> SyncManager {
 /// Creates a dummy PeerHandler for tests where interacting with peers is not needed
 /// This should only be used in tests as it won't be able to interact with the node's connected peers
 pub async fn dummy_peer_handler() -> PeerHandler {
-    let peer_table = PeerTable::spawn(TARGET_PEERS);
+    let peer_table = PeerTable::spawn(TARGET_PEERS, Vec::new());
     PeerHandler::new(peer_table.clone(), dummy_gen_server(peer_table).await)
 }
 
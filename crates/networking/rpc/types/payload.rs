@@ -1,5 +1,5 @@
 use bytes::Bytes;
-use ethrex_rlp::error::RLPDecodeError;
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode, error::RLPDecodeError};
 use serde::{Deserialize, Serialize};
 
 use ethrex_common::{
@@ -8,7 +8,8 @@ use ethrex_common::{
     serde_utils,
     types::{
         BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, Transaction, Withdrawal,
-        compute_transactions_root, compute_withdrawals_root, requests::EncodedRequests,
+        block_access_list::BlockAccessList, compute_transactions_root, compute_withdrawals_root,
+        requests::EncodedRequests,
     },
 };
 
@@ -50,6 +51,13 @@ pub struct ExecutionPayload {
         default
     )]
     pub excess_blob_gas: Option<u64>,
+    // ExecutionPayloadV4 fields. The RLP encoded block access list, only present since Amsterdam
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "serde_utils::bytes::opt",
+        default
+    )]
+    pub block_access_list: Option<Bytes>,
 }
 
 #[derive(Clone, Debug)]
@@ -106,6 +114,15 @@ impl ExecutionPayload {
             ommers: vec![],
             withdrawals: self.withdrawals,
         };
+        // The header commits to the access list, which is checked against the one recorded when
+        // executing the block
+        let block_access_list_hash = self
+            .block_access_list
+            .as_deref()
+            .map(BlockAccessList::decode)
+            .transpose()?
+            .as_ref()
+            .map(BlockAccessList::hash);
         let header = BlockHeader {
             parent_hash: self.parent_hash,
             ommers_hash: *DEFAULT_OMMERS_HASH,
@@ -132,6 +149,7 @@ impl ExecutionPayload {
             parent_beacon_block_root,
             // TODO: set the value properly
             requests_hash,
+            block_access_list_hash,
             ..Default::default()
         };
 
@@ -162,8 +180,15 @@ impl ExecutionPayload {
             withdrawals: block.body.withdrawals,
             blob_gas_used: block.header.blob_gas_used,
             excess_blob_gas: block.header.excess_blob_gas,
+            block_access_list: None,
         }
     }
+
+    /// Includes the access list of the block, as required since `ExecutionPayloadV4`
+    pub fn with_block_access_list(mut self, block_access_list: &BlockAccessList) -> Self {
+        self.block_access_list = Some(block_access_list.encode_to_vec().into());
+        self
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -281,6 +306,7 @@ pub struct ExecutionPayloadResponseV2 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use ethrex_common::types::block_access_list::BlockAccessListBuilder;
 
     #[test]
     fn deserialize_payload_into_block() {
@@ -289,4 +315,53 @@ mod test {
         let payload: ExecutionPayload = serde_json::from_str(json).unwrap();
         assert!(payload.into_block(Some(H256::zero()), None).is_ok());
     }
+
+    #[test]
+    fn block_access_list_is_committed_to_by_the_payload_header() {
+        let mut builder = BlockAccessListBuilder::new();
+        builder.set_balance(Address::repeat_byte(1), 1, U256::from(7));
+        let block_access_list = builder.build();
+        let block = Block::new(
+            BlockHeader {
+                ommers_hash: *DEFAULT_OMMERS_HASH,
+                number: 1,
+                base_fee_per_gas: Some(7),
+                transactions_root: compute_transactions_root(&[]),
+                withdrawals_root: Some(compute_withdrawals_root(&[])),
+                blob_gas_used: Some(0),
+                excess_blob_gas: Some(0),
+                parent_beacon_block_root: Some(H256::zero()),
+                requests_hash: Some(H256::zero()),
+                block_access_list_hash: Some(block_access_list.hash()),
+                ..Default::default()
+            },
+            BlockBody {
+                withdrawals: Some(vec![]),
+                ..Default::default()
+            },
+        );
+
+        let payload =
+            ExecutionPayload::from_block(block.clone()).with_block_access_list(&block_access_list);
+        let json = serde_json::to_value(&payload).unwrap();
+        assert!(json.get("blockAccessList").is_some());
+        let payload: ExecutionPayload = serde_json::from_value(json).unwrap();
+        let decoded = payload
+            .into_block(Some(H256::zero()), Some(H256::zero()))
+            .unwrap();
+        assert_eq!(
+            decoded.header.block_access_list_hash,
+            Some(block_access_list.hash())
+        );
+        assert_eq!(decoded.hash(), block.hash());
+
+        // Payloads from before Amsterdam don't commit to an access list
+        let payload = ExecutionPayload::from_block(block);
+        let json = serde_json::to_value(&payload).unwrap();
+        assert!(json.get("blockAccessList").is_none());
+        let decoded = payload
+            .into_block(Some(H256::zero()), Some(H256::zero()))
+            .unwrap();
+        assert_eq!(decoded.header.block_access_list_hash, None);
+    }
 }
@@ -3,8 +3,8 @@ use ethrex_common::{
     Address, H256, serde_utils,
     types::{
         BlockHash, BlockNumber, EIP1559Transaction, EIP2930Transaction, EIP7702Transaction,
-        FeeTokenTransaction, LegacyTransaction, PrivilegedL2Transaction, Transaction,
-        WrappedEIP4844Transaction,
+        FeeTokenTransaction, LegacyTransaction, PrivilegedL2Transaction, RIP7560Transaction,
+        Transaction, WrappedEIP4844Transaction,
     },
 };
 use ethrex_rlp::{decode::RLPDecode, error::RLPDecodeError};
@@ -55,6 +55,7 @@ pub enum SendRawTransactionRequest {
     EIP7702(EIP7702Transaction),
     PrivilegedL2(PrivilegedL2Transaction),
     FeeToken(FeeTokenTransaction),
+    RIP7560(RIP7560Transaction),
 }
 
 impl SendRawTransactionRequest {
@@ -69,6 +70,7 @@ impl SendRawTransactionRequest {
                 Transaction::PrivilegedL2Transaction(t.clone())
             }
             SendRawTransactionRequest::FeeToken(t) => Transaction::FeeTokenTransaction(t.clone()),
+            SendRawTransactionRequest::RIP7560(t) => Transaction::RIP7560Transaction(t.clone()),
         }
     }
 
@@ -100,6 +102,10 @@ impl SendRawTransactionRequest {
                     0x4 => {
                         EIP7702Transaction::decode(tx_bytes).map(SendRawTransactionRequest::EIP7702)
                     }
+                    // RIP7560
+                    0x5 => {
+                        RIP7560Transaction::decode(tx_bytes).map(SendRawTransactionRequest::RIP7560)
+                    }
                     // FeeTokenTransaction
                     0x7d => FeeTokenTransaction::decode(tx_bytes)
                         .map(SendRawTransactionRequest::FeeToken),
@@ -1,6 +1,6 @@
 use crate::utils::RpcErr;
 use crate::{RpcApiContext, RpcHandler};
-use ethrex_common::types::BlockNumber;
+use ethrex_common::{H256, types::BlockNumber};
 use serde_json::Value;
 
 /// RPC request for `ubt_getRoot`.
@@ -86,3 +86,55 @@ impl RpcHandler for GetRootRequest {
         }
     }
 }
+
+/// RPC request for `ubt_getWitness`.
+///
+/// Returns the UBT execution witness of a recent block: the values of the leaves it accessed
+/// and the proofs of them against the UBT root of its parent. Witnesses are only built when
+/// EIP-4762 stateless gas costs are enabled (`eip4762Time` in the chain config), and only for
+/// the most recent blocks.
+///
+/// # Parameters
+/// - `block_hash`: The hash of the block
+///
+/// # Returns
+/// - The witness, or null if it wasn't built or is no longer kept
+pub struct GetWitnessRequest {
+    block_hash: H256,
+}
+
+impl RpcHandler for GetWitnessRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::MissingParam("params".to_string()))?;
+
+        let block_hash = params
+            .first()
+            .ok_or(RpcErr::MissingParam("block_hash".to_string()))?;
+
+        Ok(Self {
+            block_hash: serde_json::from_value(block_hash.clone())?,
+        })
+    }
+
+    async fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        #[cfg(feature = "ubt")]
+        {
+            let ubt_state = context.storage.ubt_state();
+            let state = ubt_state
+                .lock()
+                .map_err(|e| RpcErr::Internal(format!("Failed to lock UBT state: {e}")))?;
+
+            Ok(serde_json::to_value(state.get_witness(self.block_hash))?)
+        }
+
+        #[cfg(not(feature = "ubt"))]
+        {
+            let _ = (self, context);
+            Err(RpcErr::UnsuportedFork(
+                "UBT feature not enabled. Rebuild with --features ubt".to_string(),
+            ))
+        }
+    }
+}
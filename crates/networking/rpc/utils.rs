@@ -39,6 +39,8 @@ pub enum RpcErr {
     InvalidPayloadAttributes(String),
     #[error("Unknown payload: {0}")]
     UnknownPayload(String),
+    #[error("Pruned history unavailable")]
+    PrunedHistory,
 }
 
 impl From<RpcErr> for RpcErrorMetadata {
@@ -140,6 +142,12 @@ impl From<RpcErr> for RpcErrorMetadata {
                 data: None,
                 message: format!("Unknown payload: {context}"),
             },
+            // Same code as other clients implementing history expiry
+            RpcErr::PrunedHistory => RpcErrorMetadata {
+                code: 4444,
+                data: None,
+                message: "pruned history unavailable".to_string(),
+            },
         }
     }
 }
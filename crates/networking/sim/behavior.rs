@@ -0,0 +1,69 @@
+use bytes::Bytes;
+use ethrex_p2p::{
+    rlpx::{Message, connection::server::MessageInterceptor, eth::blocks::BlockBodies},
+    types::Node,
+};
+
+/// How a simulated node answers the requests of its peers. Slow peers are simulated through the
+/// [`LinkConditions`](crate::network::LinkConditions) of their host instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Behavior {
+    #[default]
+    Honest,
+    /// Answers block body requests without any body
+    WithholdBodies,
+    /// Answers snap range requests with proofs that don't match the requested state
+    BadProofs,
+    /// Never answers eth or snap requests
+    Unresponsive,
+}
+
+impl MessageInterceptor for Behavior {
+    fn outgoing(&self, _peer: &Node, message: Message) -> Option<Message> {
+        match (self, message) {
+            (Behavior::WithholdBodies, Message::BlockBodies(bodies)) => {
+                Some(Message::BlockBodies(BlockBodies {
+                    id: bodies.id,
+                    block_bodies: vec![],
+                }))
+            }
+            (Behavior::BadProofs, Message::AccountRange(mut range)) => {
+                corrupt_proof(&mut range.proof);
+                Some(Message::AccountRange(range))
+            }
+            (Behavior::BadProofs, Message::StorageRanges(mut ranges)) => {
+                corrupt_proof(&mut ranges.proof);
+                Some(Message::StorageRanges(ranges))
+            }
+            (Behavior::Unresponsive, message) if is_response(&message) => None,
+            (_, message) => Some(message),
+        }
+    }
+}
+
+/// Flips a byte of every proof node, so none of them can be found by its hash
+fn corrupt_proof(proof: &mut [Bytes]) {
+    for node in proof.iter_mut() {
+        let mut corrupted = node.to_vec();
+        if let Some(byte) = corrupted.last_mut() {
+            *byte ^= 0xff;
+        }
+        *node = corrupted.into();
+    }
+}
+
+fn is_response(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::BlockHeaders(_)
+            | Message::BlockBodies(_)
+            | Message::PooledTransactions(_)
+            | Message::Receipts68(_)
+            | Message::Receipts69(_)
+            | Message::AccountRange(_)
+            | Message::StorageRanges(_)
+            | Message::ByteCodes(_)
+            | Message::TrieNodes(_)
+            | Message::BlockAccessLists(_)
+    )
+}
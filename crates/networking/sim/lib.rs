@@ -0,0 +1,236 @@
+//! In-process p2p network simulator.
+//!
+//! Runs many nodes, each one with its own peer table, RLPx connections, discovery server and
+//! in-memory store, over a [`SimNetwork`] with configurable latency and packet loss. Nodes can be
+//! made to misbehave through their [`Behavior`], so sync and discovery can be exercised against
+//! bad peers from `cargo test`:
+//!
+//! ```ignore
+//! let network = SimNetwork::new(seed);
+//! let miner = SimNode::spawn(&network, genesis.clone(), NodeConfig::default()).await?;
+//! let blocks = miner.mine_blocks(64).await?;
+//! let node = SimNode::spawn(&network, genesis, NodeConfig::default()).await?;
+//! node.connect(&miner).await?;
+//! node.sync_to(blocks[63].hash(), SyncMode::Full, timeout).await?;
+//! ```
+//!
+//! Randomness of the simulation (node keys, packet loss and the peers and contacts picked by each
+//! node) comes from the network seed. Handshake nonces, request ids and the scheduling of the
+//! nodes' tasks are still up to the OS and the tokio runtime.
+
+pub mod behavior;
+pub mod network;
+pub mod node;
+
+use std::time::Duration;
+
+pub use behavior::Behavior;
+pub use network::{LinkConditions, SimNetwork};
+pub use node::{NodeConfig, SimNode, crawl};
+
+use ethrex_blockchain::error::ChainError;
+use ethrex_p2p::{discv4::peer_table::PeerTableError, network::NetworkError, sync::SyncError};
+use ethrex_storage::error::StoreError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SimError {
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error(transparent)]
+    Chain(#[from] ChainError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    PeerTable(#[from] PeerTableError),
+    #[error(transparent)]
+    Sync(#[from] SyncError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::types::{Block, Genesis};
+    use ethrex_p2p::sync::SyncMode;
+    use std::{
+        fs::File,
+        io::BufReader,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    fn genesis() -> Genesis {
+        let file = File::open("../../../fixtures/genesis/execution-api.json")
+            .expect("Failed to open genesis file");
+        serde_json::from_reader(BufReader::new(file)).expect("Failed to deserialize genesis file")
+    }
+
+    /// Spawns a node with the given behavior which already has the blocks
+    async fn spawn_with_blocks(
+        network: &SimNetwork,
+        genesis: Genesis,
+        behavior: Behavior,
+        blocks: &[Block],
+    ) -> SimNode {
+        let config = NodeConfig {
+            behavior,
+            link: LinkConditions::with_latency(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let node = SimNode::spawn(network, genesis, config).await.unwrap();
+        for block in blocks {
+            node.blockchain.add_block(block.clone()).unwrap();
+        }
+        if let Some(head) = blocks.last() {
+            node.store
+                .forkchoice_update(vec![], head.header.number, head.hash(), None, None)
+                .await
+                .unwrap();
+        }
+        node
+    }
+
+    #[tokio::test]
+    async fn full_sync_with_misbehaving_peers() {
+        let network = SimNetwork::new(1);
+        let miner = SimNode::spawn(&network, genesis(), NodeConfig::default())
+            .await
+            .unwrap();
+        let blocks = miner.mine_blocks(32).await.unwrap();
+        let head = miner.head().await.unwrap();
+
+        let mut peers = vec![miner];
+        for behavior in [Behavior::WithholdBodies, Behavior::Unresponsive] {
+            peers.push(spawn_with_blocks(&network, genesis(), behavior, &blocks).await);
+        }
+
+        let node = SimNode::spawn(&network, genesis(), NodeConfig::default())
+            .await
+            .unwrap();
+        for peer in &peers {
+            node.connect(peer).await.unwrap();
+        }
+        node.wait_for_peers(peers.len(), TIMEOUT).await.unwrap();
+        node.sync_to(head.1, SyncMode::Full, TIMEOUT).await.unwrap();
+        assert_eq!(node.head().await.unwrap(), head);
+    }
+
+    #[tokio::test]
+    async fn snap_sync_with_misbehaving_peers() {
+        const BLOCKS: u64 = 32;
+        let network = SimNetwork::new(4);
+        // Stale pivots are replaced by newer blocks, so the chain has to end around now
+        let mut genesis = genesis();
+        genesis.timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - BLOCKS * 12;
+        let miner = SimNode::spawn(&network, genesis.clone(), NodeConfig::default())
+            .await
+            .unwrap();
+        let blocks = miner.mine_blocks(BLOCKS).await.unwrap();
+        let head = miner.head().await.unwrap();
+
+        let mut peers = vec![miner];
+        for behavior in [Behavior::BadProofs, Behavior::Unresponsive] {
+            peers.push(spawn_with_blocks(&network, genesis.clone(), behavior, &blocks).await);
+        }
+
+        let node = SimNode::spawn(&network, genesis.clone(), NodeConfig::default())
+            .await
+            .unwrap();
+        for peer in &peers {
+            node.connect(peer).await.unwrap();
+        }
+        node.wait_for_peers(peers.len(), TIMEOUT).await.unwrap();
+        node.sync_to(head.1, SyncMode::Snap, TIMEOUT).await.unwrap();
+        assert_eq!(node.head().await.unwrap(), head);
+        // The state of the pivot was downloaded instead of executing the blocks
+        for address in genesis.alloc.keys() {
+            assert_eq!(
+                node.store.get_account_info(head.0, *address).await.unwrap(),
+                peers[0]
+                    .store
+                    .get_account_info(head.0, *address)
+                    .await
+                    .unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn discovery_over_lossy_links() {
+        let network = SimNetwork::new(2);
+        let bootnode = SimNode::spawn(
+            &network,
+            genesis(),
+            NodeConfig {
+                discovery: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let mut nodes = vec![];
+        for _ in 0..4 {
+            let config = NodeConfig {
+                discovery: true,
+                bootnodes: vec![bootnode.node.clone()],
+                link: LinkConditions {
+                    latency: Duration::from_millis(10),
+                    loss: 0.1,
+                },
+                ..Default::default()
+            };
+            nodes.push(SimNode::spawn(&network, genesis(), config).await.unwrap());
+        }
+        // Every node has to find some other node besides the bootnode
+        for node in &nodes {
+            node.wait_for_peers(2, TIMEOUT).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn crawler_checks_fork_ids_against_the_current_fork() {
+        let network = SimNetwork::new(3);
+        // Osaka activated long ago, after the genesis block
+        let mut upgraded = genesis();
+        upgraded.config.osaka_time = Some(1_000);
+        let discovery = |bootnodes| NodeConfig {
+            discovery: true,
+            bootnodes,
+            ..Default::default()
+        };
+        let bootnode = SimNode::spawn(&network, upgraded.clone(), discovery(vec![]))
+            .await
+            .unwrap();
+        // A node that missed the upgrade, still announcing no upcoming fork
+        let stale = SimNode::spawn(&network, genesis(), discovery(vec![bootnode.node.clone()]))
+            .await
+            .unwrap();
+        stale.wait_for_peers(1, TIMEOUT).await.unwrap();
+
+        let census = crawl(
+            &network,
+            upgraded,
+            vec![bootnode.node.clone()],
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        let fork_id_valid = |node: &SimNode| {
+            census
+                .entries
+                .iter()
+                .find(|entry| entry.node_id == node.node.node_id())
+                .and_then(|entry| entry.fork_id_valid)
+        };
+        assert_eq!(fork_id_valid(&bootnode), Some(true));
+        assert_eq!(fork_id_valid(&stale), Some(false));
+    }
+}
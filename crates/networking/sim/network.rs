@@ -0,0 +1,357 @@
+//! In-memory network connecting the simulated nodes.
+//!
+//! Every node gets its own host address. RLPx connections are in-memory byte pipes and discovery
+//! packets are delivered through channels, both delayed and dropped according to the link
+//! conditions of the hosts at each end.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use ethrex_p2p::transport::{BoxedStream, DatagramSocket, Transport};
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+use secp256k1::SecretKey;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, duplex},
+    sync::mpsc,
+    time::{Instant, sleep, sleep_until},
+};
+
+/// Capacity of each direction of an in-memory connection
+const PIPE_BUFFER_SIZE: usize = 64 * 1024;
+/// First port handed out to outgoing connections
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+type Datagram = (Vec<u8>, SocketAddr);
+
+/// Conditions of the link between a host and the rest of the network
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkConditions {
+    /// One way delay added to all the traffic sent or received by the host
+    pub latency: Duration,
+    /// Probability of a discovery packet sent or received by the host being lost
+    pub loss: f64,
+}
+
+impl LinkConditions {
+    pub fn with_latency(latency: Duration) -> Self {
+        Self {
+            latency,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_loss(loss: f64) -> Self {
+        Self {
+            loss,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimNetwork {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// Source of all the randomness of the simulation, so runs can be reproduced from the seed
+    rng: StdRng,
+    hosts: HashMap<IpAddr, LinkConditions>,
+    next_port: u16,
+    listeners: HashMap<SocketAddr, mpsc::UnboundedSender<(DuplexStream, SocketAddr)>>,
+    sockets: HashMap<SocketAddr, mpsc::UnboundedSender<Datagram>>,
+}
+
+impl SimNetwork {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                rng: StdRng::seed_from_u64(seed),
+                hosts: HashMap::new(),
+                next_port: FIRST_EPHEMERAL_PORT,
+                listeners: HashMap::new(),
+                sockets: HashMap::new(),
+            })),
+        }
+    }
+
+    fn inner(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().expect("Simulated network lock poisoned")
+    }
+
+    /// Adds a host to the network, returning its address
+    pub fn add_host(&self, link: LinkConditions) -> IpAddr {
+        let mut inner = self.inner();
+        let [_, a, b, c] = u32::try_from(inner.hosts.len() + 1)
+            .expect("Too many hosts")
+            .to_be_bytes();
+        let ip = IpAddr::V4(Ipv4Addr::new(10, a, b, c));
+        inner.hosts.insert(ip, link);
+        ip
+    }
+
+    /// Changes the conditions of the host's link, affecting the packets and connections from now on
+    pub fn set_link(&self, ip: IpAddr, link: LinkConditions) {
+        self.inner().hosts.insert(ip, link);
+    }
+
+    /// Generates a node key from the seeded randomness
+    pub fn new_signer(&self) -> SecretKey {
+        SecretKey::new(&mut self.inner().rng)
+    }
+
+    /// Generates a seed from the seeded randomness, for the random choices made by each node
+    pub fn new_seed(&self) -> u64 {
+        self.inner().rng.next_u64()
+    }
+
+    /// Accepts the connections opened to the address
+    pub fn listen(&self, addr: SocketAddr) -> SimListener {
+        let (sender, incoming) = mpsc::unbounded_channel();
+        self.inner().listeners.insert(addr, sender);
+        SimListener { incoming }
+    }
+
+    /// Binds a datagram socket to the address
+    pub fn bind(&self, addr: SocketAddr) -> Arc<SimSocket> {
+        let (sender, inbox) = mpsc::unbounded_channel();
+        self.inner().sockets.insert(addr, sender);
+        Arc::new(SimSocket {
+            addr,
+            network: self.clone(),
+            inbox: tokio::sync::Mutex::new(inbox),
+        })
+    }
+
+    /// Transport opening connections from the given host
+    pub fn transport(&self, ip: IpAddr) -> SimTransport {
+        SimTransport {
+            ip,
+            network: self.clone(),
+        }
+    }
+
+    fn latency(inner: &Inner, from: IpAddr, to: IpAddr) -> Duration {
+        let latency = |ip| inner.hosts.get(&ip).map(|link| link.latency);
+        latency(from).unwrap_or_default() + latency(to).unwrap_or_default()
+    }
+
+    fn connect(&self, from: IpAddr, to: SocketAddr) -> io::Result<DuplexStream> {
+        let mut inner = self.inner();
+        let listener = inner
+            .listeners
+            .get(&to)
+            .filter(|listener| !listener.is_closed())
+            .cloned()
+            .ok_or(io::ErrorKind::ConnectionRefused)?;
+        let local_addr = SocketAddr::new(from, inner.next_port);
+        inner.next_port = inner
+            .next_port
+            .checked_add(1)
+            .unwrap_or(FIRST_EPHEMERAL_PORT);
+        let (local, remote) = pipe(Self::latency(&inner, from, to.ip()));
+        listener
+            .send((remote, local_addr))
+            .map_err(|_| io::ErrorKind::ConnectionRefused)?;
+        Ok(local)
+    }
+
+    fn deliver(&self, from: SocketAddr, to: SocketAddr, packet: Vec<u8>) {
+        let mut inner = self.inner();
+        let Some(socket) = inner.sockets.get(&to).cloned() else {
+            return;
+        };
+        let loss = |ip| inner.hosts.get(&ip).map(|link| link.loss);
+        let loss = loss(from.ip()).unwrap_or_default() + loss(to.ip()).unwrap_or_default();
+        if inner.rng.gen_bool(loss.clamp(0.0, 1.0)) {
+            return;
+        }
+        let latency = Self::latency(&inner, from.ip(), to.ip());
+        if latency.is_zero() {
+            let _ = socket.send((packet, from));
+        } else {
+            tokio::spawn(async move {
+                sleep(latency).await;
+                let _ = socket.send((packet, from));
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SimListener {
+    incoming: mpsc::UnboundedReceiver<(DuplexStream, SocketAddr)>,
+}
+
+impl SimListener {
+    /// Waits for an incoming connection, returning the stream and the address of the dialer
+    pub async fn accept(&mut self) -> Option<(DuplexStream, SocketAddr)> {
+        self.incoming.recv().await
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimTransport {
+    ip: IpAddr,
+    network: SimNetwork,
+}
+
+#[async_trait]
+impl Transport for SimTransport {
+    async fn dial(&self, addr: SocketAddr) -> io::Result<BoxedStream> {
+        Ok(Box::new(self.network.connect(self.ip, addr)?))
+    }
+}
+
+#[derive(Debug)]
+pub struct SimSocket {
+    addr: SocketAddr,
+    network: SimNetwork,
+    inbox: tokio::sync::Mutex<mpsc::UnboundedReceiver<Datagram>>,
+}
+
+#[async_trait]
+impl DatagramSocket for SimSocket {
+    async fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        // As with UDP, packets to unknown addresses are silently lost
+        self.network.deliver(self.addr, addr, buf.to_vec());
+        Ok(buf.len())
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (packet, from) = self
+            .inbox
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or(io::ErrorKind::NotConnected)?;
+        // Truncated like a datagram that doesn't fit in the buffer
+        let read = packet.len().min(buf.len());
+        buf[..read].copy_from_slice(&packet[..read]);
+        Ok((read, from))
+    }
+}
+
+/// Creates both ends of an in-memory connection, delaying the data written to each end before
+/// it can be read from the other one
+fn pipe(latency: Duration) -> (DuplexStream, DuplexStream) {
+    let (local, local_relay) = duplex(PIPE_BUFFER_SIZE);
+    if latency.is_zero() {
+        return (local, local_relay);
+    }
+    let (remote_relay, remote) = duplex(PIPE_BUFFER_SIZE);
+    let (local_reader, local_writer) = tokio::io::split(local_relay);
+    let (remote_reader, remote_writer) = tokio::io::split(remote_relay);
+    tokio::spawn(relay(local_reader, remote_writer, latency));
+    tokio::spawn(relay(remote_reader, local_writer, latency));
+    (local, remote)
+}
+
+/// Copies the data from the reader to the writer, each chunk is written `latency` after it was
+/// read. The writer is shut down once the reader is closed.
+async fn relay<R, W>(mut reader: R, mut writer: W, latency: Duration)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (sender, mut receiver) = mpsc::unbounded_channel::<(Instant, Vec<u8>)>();
+    let read = async move {
+        let mut buf = vec![0; PIPE_BUFFER_SIZE];
+        while let Ok(read @ 1..) = reader.read(&mut buf).await {
+            if sender
+                .send((Instant::now() + latency, buf[..read].to_vec()))
+                .is_err()
+            {
+                break;
+            }
+        }
+    };
+    let write = async move {
+        while let Some((deadline, chunk)) = receiver.recv().await {
+            sleep_until(deadline).await;
+            if writer.write_all(&chunk).await.is_err() {
+                return;
+            }
+        }
+        let _ = writer.shutdown().await;
+    };
+    tokio::join!(read, write);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn connections_are_delayed() {
+        let network = SimNetwork::new(0);
+        let server_ip = network.add_host(LinkConditions::with_latency(Duration::from_millis(50)));
+        let client_ip = network.add_host(LinkConditions::default());
+        let server_addr = SocketAddr::new(server_ip, 30303);
+        let mut listener = network.listen(server_addr);
+
+        let mut client = network
+            .transport(client_ip)
+            .dial(server_addr)
+            .await
+            .unwrap();
+        let (mut server, client_addr) = listener.accept().await.unwrap();
+        assert_eq!(client_addr.ip(), client_ip);
+
+        let start = Instant::now();
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // Closing one end is seen by the other one
+        drop(client);
+        assert_eq!(server.read(&mut buf).await.unwrap(), 0);
+
+        let unknown = SocketAddr::new(client_ip, 30303);
+        let err = network
+            .transport(server_ip)
+            .dial(unknown)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn datagrams_are_lost() {
+        let network = SimNetwork::new(0);
+        let lossy = network.add_host(LinkConditions::with_loss(1.0));
+        let reliable = network.add_host(LinkConditions::default());
+        let lossy_socket = network.bind(SocketAddr::new(lossy, 30303));
+        let reliable_socket = network.bind(SocketAddr::new(reliable, 30303));
+
+        lossy_socket
+            .send_to(b"lost", reliable_socket.addr)
+            .await
+            .unwrap();
+        reliable_socket
+            .send_to(b"lost", lossy_socket.addr)
+            .await
+            .unwrap();
+        network.set_link(lossy, LinkConditions::default());
+        lossy_socket
+            .send_to(b"delivered", reliable_socket.addr)
+            .await
+            .unwrap();
+
+        let mut buf = [0; 16];
+        let (read, from) = reliable_socket.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..read], b"delivered");
+        assert_eq!(from, lossy_socket.addr);
+        assert!(lossy_socket.inbox.lock().await.is_empty());
+    }
+}
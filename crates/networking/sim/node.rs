@@ -0,0 +1,286 @@
+use std::{
+    sync::{Arc, atomic::AtomicBool},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use ethrex_blockchain::{
+    Blockchain, BlockchainOptions,
+    payload::{BuildPayloadArgs, create_payload},
+};
+use ethrex_common::{
+    Address, H256,
+    types::{Block, BlockNumber, DEFAULT_BUILDER_GAS_CEIL, ELASTICITY_MULTIPLIER, Genesis},
+};
+use ethrex_p2p::{
+    crawler::{Census, DEFAULT_CRAWL_CONCURRENCY, crawl_with_socket},
+    discv4::{
+        peer_table::{PeerTable, TARGET_PEERS},
+        server::{DiscoveryServer, INITIAL_LOOKUP_INTERVAL_MS},
+    },
+    network::{NetworkError, P2PContext},
+    peer_handler::PeerHandler,
+    rlpx::{connection::server::PeerConnection, initiator::RLPxInitiator},
+    sync::{SyncMode, Syncer},
+    tx_broadcaster::BROADCAST_INTERVAL_MS,
+    types::{LocalNodeRecord, Node},
+    utils::public_key_from_signing_key,
+};
+use ethrex_storage::{EngineType, Store, error::StoreError};
+use tempfile::TempDir;
+use tokio::time::{Instant, sleep};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::debug;
+
+use crate::{
+    SimError,
+    behavior::Behavior,
+    network::{LinkConditions, SimNetwork},
+};
+
+/// Port used by every simulated node, for both RLPx and discovery
+pub const SIM_PORT: u16 = 30303;
+/// Interval between checks of a node's progress
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    pub behavior: Behavior,
+    pub link: LinkConditions,
+    pub target_peers: usize,
+    /// Whether to run discv4, if not peers have to be added through [`SimNode::connect`]
+    pub discovery: bool,
+    /// Nodes contacted first by discovery
+    pub bootnodes: Vec<Node>,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            behavior: Behavior::Honest,
+            link: LinkConditions::default(),
+            target_peers: TARGET_PEERS,
+            discovery: false,
+            bootnodes: vec![],
+        }
+    }
+}
+
+/// A full p2p stack running over the simulated network, backed by an in-memory store
+#[derive(Debug, Clone)]
+pub struct SimNode {
+    pub node: Node,
+    pub store: Store,
+    pub blockchain: Arc<Blockchain>,
+    pub peer_table: PeerTable,
+    pub peer_handler: PeerHandler,
+    /// Scratch space used by snap sync
+    datadir: Arc<TempDir>,
+}
+
+impl SimNode {
+    pub async fn spawn(
+        network: &SimNetwork,
+        genesis: Genesis,
+        config: NodeConfig,
+    ) -> Result<Self, SimError> {
+        let ip = network.add_host(config.link);
+        let signer = network.new_signer();
+        let node = Node::new(ip, SIM_PORT, SIM_PORT, public_key_from_signing_key(&signer));
+
+        let mut store = Store::new("", EngineType::InMemory)?;
+        store.add_initial_state(genesis).await?;
+        let blockchain = Arc::new(Blockchain::new(store.clone(), BlockchainOptions::default()));
+        let peer_table =
+            PeerTable::spawn_with_seed(config.target_peers, vec![], network.new_seed());
+
+        let context = P2PContext::new(
+            node.clone(),
+            TaskTracker::new(),
+            signer,
+            peer_table.clone(),
+            store.clone(),
+            blockchain.clone(),
+            format!("ethrex-sim/{ip}"),
+            None,
+            BROADCAST_INTERVAL_MS,
+            INITIAL_LOOKUP_INTERVAL_MS,
+        )?
+        .with_transport(Arc::new(network.transport(ip)))
+        .with_interceptor(Arc::new(config.behavior));
+
+        let mut listener = network.listen(node.tcp_addr());
+        let receiver_context = context.clone();
+        context.tracker.spawn(async move {
+            while let Some((stream, peer_addr)) = listener.accept().await {
+                PeerConnection::spawn_as_receiver(receiver_context.clone(), peer_addr, stream);
+            }
+        });
+
+        if config.discovery {
+            let local_node_record =
+                LocalNodeRecord::new(&node, &signer, store.get_fork_id().await.ok())
+                    .map_err(NetworkError::from)?;
+            DiscoveryServer::spawn(
+                store.clone(),
+                node.clone(),
+                local_node_record,
+                signer,
+                network.bind(node.udp_addr()),
+                peer_table.clone(),
+                config.bootnodes,
+                INITIAL_LOOKUP_INTERVAL_MS,
+            )
+            .await
+            .map_err(NetworkError::from)?;
+        }
+
+        let initiator = RLPxInitiator::spawn(context).await;
+        debug!(%node, behavior = ?config.behavior, "Spawned simulated node");
+
+        Ok(Self {
+            node,
+            store,
+            blockchain,
+            peer_handler: PeerHandler::new(peer_table.clone(), initiator),
+            peer_table,
+            datadir: Arc::new(TempDir::new()?),
+        })
+    }
+
+    /// Keeps the node connected to the other one
+    pub async fn connect(&self, other: &SimNode) -> Result<(), SimError> {
+        self.peer_table
+            .clone()
+            .add_static_peer(other.node.clone())
+            .await?;
+        Ok(())
+    }
+
+    /// Waits until the node has at least `count` peers
+    pub async fn wait_for_peers(&self, count: usize, timeout: Duration) -> Result<(), SimError> {
+        let deadline = Instant::now() + timeout;
+        while self.peer_table.clone().peer_count().await? < count {
+            if Instant::now() >= deadline {
+                return Err(SimError::Timeout(timeout));
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    /// Number and hash of the node's head block
+    pub async fn head(&self) -> Result<(BlockNumber, H256), SimError> {
+        let number = self.store.get_latest_block_number().await?;
+        let hash = self
+            .store
+            .get_canonical_block_hash(number)
+            .await?
+            .ok_or(StoreError::MissingLatestBlockNumber)?;
+        Ok((number, hash))
+    }
+
+    /// Builds empty blocks on top of the head and makes them canonical
+    pub async fn mine_blocks(&self, count: u64) -> Result<Vec<Block>, SimError> {
+        let (head_number, _) = self.head().await?;
+        let mut parent = self
+            .store
+            .get_block_header(head_number)?
+            .ok_or(StoreError::MissingLatestBlockNumber)?;
+        let mut blocks = Vec::new();
+        for _ in 0..count {
+            let args = BuildPayloadArgs {
+                parent: parent.hash(),
+                timestamp: parent.timestamp + 12,
+                fee_recipient: Address::zero(),
+                random: H256::zero(),
+                withdrawals: Some(vec![]),
+                beacon_root: Some(H256::zero()),
+                version: 1,
+                elasticity_multiplier: ELASTICITY_MULTIPLIER,
+                gas_ceil: DEFAULT_BUILDER_GAS_CEIL,
+            };
+            let payload = create_payload(&args, &self.store, Bytes::new())?;
+            let block = self.blockchain.build_payload(payload)?.payload;
+            self.blockchain.add_block(block.clone())?;
+            self.store
+                .forkchoice_update(vec![], block.header.number, block.hash(), None, None)
+                .await?;
+            parent = block.header.clone();
+            blocks.push(block);
+        }
+        Ok(blocks)
+    }
+
+    /// Syncs from the node's peers until `sync_head` is part of the canonical chain, retrying
+    /// failed sync cycles until the timeout
+    pub async fn sync_to(
+        &self,
+        sync_head: H256,
+        mode: SyncMode,
+        timeout: Duration,
+    ) -> Result<(), SimError> {
+        let mut syncer = Syncer::new(
+            self.peer_handler.clone(),
+            Arc::new(AtomicBool::new(mode == SyncMode::Snap)),
+            CancellationToken::new(),
+            self.blockchain.clone(),
+            self.datadir.path().to_path_buf(),
+        )
+        // Simulated chains are short, snap sync them anyway
+        .with_min_full_blocks(0);
+        let deadline = Instant::now() + timeout;
+        loop {
+            let cycle =
+                tokio::time::timeout_at(deadline, syncer.sync_cycle(sync_head, self.store.clone()))
+                    .await
+                    .map_err(|_| SimError::Timeout(timeout))?;
+            match cycle {
+                Ok(()) if self.store.is_canonical_sync(sync_head)? => return Ok(()),
+                Ok(()) => debug!(%sync_head, "Sync cycle finished before reaching the head"),
+                Err(error) if error.is_recoverable() => debug!(%error, "Sync cycle failed"),
+                Err(error) => return Err(error.into()),
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Crawls the network from a new host for the given duration, returning the census of the nodes found
+pub async fn crawl(
+    network: &SimNetwork,
+    genesis: Genesis,
+    bootnodes: Vec<Node>,
+    duration: Duration,
+) -> Result<Census, SimError> {
+    let ip = network.add_host(LinkConditions::default());
+    let signer = network.new_signer();
+    let node = Node::new(ip, SIM_PORT, SIM_PORT, public_key_from_signing_key(&signer));
+
+    let mut store = Store::new("", EngineType::InMemory)?;
+    store.add_initial_state(genesis).await?;
+    let blockchain = Arc::new(Blockchain::new(store.clone(), BlockchainOptions::default()));
+    let context = P2PContext::new(
+        node.clone(),
+        TaskTracker::new(),
+        signer,
+        PeerTable::spawn_with_seed(TARGET_PEERS, vec![], network.new_seed()),
+        store,
+        blockchain,
+        format!("ethrex-crawler/{ip}"),
+        None,
+        BROADCAST_INTERVAL_MS,
+        INITIAL_LOOKUP_INTERVAL_MS,
+    )?
+    .with_transport(Arc::new(network.transport(ip)));
+
+    let census = crawl_with_socket(
+        context,
+        network.bind(node.udp_addr()),
+        bootnodes,
+        duration,
+        DEFAULT_CRAWL_CONCURRENCY,
+    )
+    .await?;
+    Ok(census)
+}
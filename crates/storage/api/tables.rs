@@ -93,7 +93,17 @@ pub const MISC_VALUES: &str = "misc_values";
 /// Value format: U256 encoded as big-endian 32 bytes
 pub const PLAIN_STORAGE: &str = "plain_storage";
 
-pub const TABLES: [&str; 18] = [
+/// Bad blocks column family: [`Vec<u8>`] => [`Vec<u8>`]
+/// - [`Vec<u8>`] = `block_hash.as_bytes()`
+/// - [`Vec<u8>`] = `(sequence, block, reason).encode_to_vec()`, where `sequence` is the insertion order
+pub const BAD_BLOCKS: &str = "bad_blocks";
+
+/// Block access lists column family: [`Vec<u8>`] => [`Vec<u8>`]
+/// - [`Vec<u8>`] = `block_hash.encode_to_vec()`
+/// - [`Vec<u8>`] = `block_access_list.encode_to_vec()`
+pub const BLOCK_ACCESS_LISTS: &str = "block_access_lists";
+
+pub const TABLES: [&str; 20] = [
     CHAIN_DATA,
     ACCOUNT_CODES,
     BODIES,
@@ -112,4 +122,6 @@ pub const TABLES: [&str; 18] = [
     STORAGE_FLATKEYVALUE,
     MISC_VALUES,
     PLAIN_STORAGE,
+    BAD_BLOCKS,
+    BLOCK_ACCESS_LISTS,
 ];
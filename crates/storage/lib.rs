@@ -9,7 +9,9 @@ pub mod trie;
 #[cfg(feature = "ubt")]
 pub mod ubt;
 #[cfg(feature = "ubt")]
-pub use ubt::{UbtState, UbtUpdate, account_updates_to_ubt};
+pub use ubt::{UbtState, UbtUpdate, account_updates_to_ubt, ubt_leaf_key};
+#[cfg(feature = "ubt")]
+pub mod ubt_witness;
 pub mod utils;
 
 pub use layering::apply_prefix;
@@ -5,10 +5,10 @@ use crate::{
     api::{
         StorageBackend,
         tables::{
-            ACCOUNT_CODES, ACCOUNT_FLATKEYVALUE, ACCOUNT_TRIE_NODES, BLOCK_NUMBERS, BODIES,
-            CANONICAL_BLOCK_HASHES, CHAIN_DATA, FULLSYNC_HEADERS, HEADERS, INVALID_CHAINS,
-            MISC_VALUES, PENDING_BLOCKS, PLAIN_STORAGE, RECEIPTS, SNAP_STATE, STORAGE_FLATKEYVALUE,
-            STORAGE_TRIE_NODES, TRANSACTION_LOCATIONS,
+            ACCOUNT_CODES, ACCOUNT_FLATKEYVALUE, ACCOUNT_TRIE_NODES, BAD_BLOCKS,
+            BLOCK_ACCESS_LISTS, BLOCK_NUMBERS, BODIES, CANONICAL_BLOCK_HASHES, CHAIN_DATA,
+            FULLSYNC_HEADERS, HEADERS, INVALID_CHAINS, MISC_VALUES, PENDING_BLOCKS, PLAIN_STORAGE,
+            RECEIPTS, SNAP_STATE, STORAGE_FLATKEYVALUE, STORAGE_TRIE_NODES, TRANSACTION_LOCATIONS,
         },
     },
     apply_prefix,
@@ -26,7 +26,7 @@ use ethrex_common::{
     types::{
         AccountInfo, AccountState, AccountUpdate, Block, BlockBody, BlockHash, BlockHeader,
         BlockNumber, ChainConfig, Code, ForkId, Genesis, GenesisAccount, Index, Receipt,
-        Transaction,
+        Transaction, block_access_list::BlockAccessList,
     },
     utils::keccak,
 };
@@ -72,6 +72,9 @@ enum FKVGeneratorControlMessage {
     Continue,
 }
 
+/// Maximum amount of invalid blocks kept in the bad block store
+pub const MAX_BAD_BLOCKS: usize = 10;
+
 // 64mb
 const CODE_CACHE_MAX_SIZE: u64 = 64 * 1024 * 1024;
 
@@ -169,6 +172,8 @@ pub struct UpdateBatch {
     pub blocks: Vec<Block>,
     /// Receipts added per block
     pub receipts: Vec<(H256, Vec<Receipt>)>,
+    /// Access lists of the blocks that record them (Amsterdam onwards), served to snap/2 peers
+    pub block_access_lists: Vec<(H256, BlockAccessList)>,
     /// Code updates
     pub code_updates: Vec<(H256, Code)>,
     /// Plain storage updates for PIR export: (address, slot, value)
@@ -236,6 +241,54 @@ impl Store {
         .map_err(|e| StoreError::Custom(format!("Task panicked: {}", e)))?
     }
 
+    /// Stores the bodies and receipts of blocks whose headers are already canonical, as downloaded
+    /// by the history backfill, and lowers the earliest block number to the lowest of them.
+    /// Everything is written in a single transaction so the backfill can resume from the earliest
+    /// block number after a restart.
+    pub async fn add_backfilled_blocks(
+        &self,
+        blocks: Vec<(Block, Vec<Receipt>)>,
+    ) -> Result<(), StoreError> {
+        let db = self.backend.clone();
+        tokio::task::spawn_blocking(move || {
+            let Some(earliest) = blocks.iter().map(|(block, _)| block.header.number).min() else {
+                return Ok(());
+            };
+            let mut tx = db.begin_write()?;
+            for (block, receipts) in blocks {
+                let block_number = block.header.number;
+                let block_hash = block.hash();
+                let hash_key = block_hash.encode_to_vec();
+
+                let body_value = BlockBodyRLP::from_bytes(block.body.encode_to_vec());
+                tx.put(BODIES, &hash_key, body_value.bytes())?;
+
+                for (index, transaction) in block.body.transactions.iter().enumerate() {
+                    let mut composite_key = Vec::with_capacity(64);
+                    composite_key.extend_from_slice(transaction.hash().as_bytes());
+                    composite_key.extend_from_slice(block_hash.as_bytes());
+                    let location_value = (block_number, block_hash, index as u64).encode_to_vec();
+                    tx.put(TRANSACTION_LOCATIONS, &composite_key, &location_value)?;
+                }
+                for (index, receipt) in receipts.into_iter().enumerate() {
+                    tx.put(
+                        RECEIPTS,
+                        &(block_hash, index as u64).encode_to_vec(),
+                        &receipt.encode_to_vec(),
+                    )?;
+                }
+            }
+            tx.put(
+                CHAIN_DATA,
+                &chain_data_key(ChainDataIndex::EarliestBlockNumber),
+                &earliest.to_le_bytes(),
+            )?;
+            tx.commit()
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("Task panicked: {}", e)))?
+    }
+
     /// Add block header
     pub async fn add_block_header(
         &self,
@@ -328,6 +381,56 @@ impl Store {
         .map_err(|e| StoreError::Custom(format!("Task panicked: {}", e)))?
     }
 
+    /// Removes the bodies, receipts and transaction locations of the canonical blocks in from..=to
+    /// and sets the earliest block number to `to + 1`. Headers and canonical hashes are kept.
+    pub async fn prune_block_history(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<(), StoreError> {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || {
+            let read_txn = backend.begin_read()?;
+            let mut txn = backend.begin_write()?;
+            for number in from..=to {
+                let Some(hash) = read_txn
+                    .get(CANONICAL_BLOCK_HASHES, number.to_le_bytes().as_slice())?
+                    .map(|bytes| H256::decode(bytes.as_slice()))
+                    .transpose()?
+                else {
+                    continue;
+                };
+                let hash_key = hash.encode_to_vec();
+                let Some(body) = read_txn
+                    .get(BODIES, &hash_key)?
+                    .map(|bytes| BlockBodyRLP::from_bytes(bytes).to())
+                    .transpose()
+                    .map_err(StoreError::from)?
+                else {
+                    continue;
+                };
+
+                for (index, transaction) in body.transactions.iter().enumerate() {
+                    let mut composite_key = Vec::with_capacity(64);
+                    composite_key.extend_from_slice(transaction.hash().as_bytes());
+                    composite_key.extend_from_slice(hash.as_bytes());
+                    txn.delete(TRANSACTION_LOCATIONS, &composite_key)?;
+                    txn.delete(RECEIPTS, &(hash, index as u64).encode_to_vec())?;
+                }
+                txn.delete(BODIES, &hash_key)?;
+                txn.delete(BLOCK_ACCESS_LISTS, &hash_key)?;
+            }
+            txn.put(
+                CHAIN_DATA,
+                &chain_data_key(ChainDataIndex::EarliestBlockNumber),
+                &(to + 1).to_le_bytes(),
+            )?;
+            txn.commit()
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("Task panicked: {}", e)))?
+    }
+
     /// Obtain canonical block bodies in from..=to
     pub async fn get_block_bodies(
         &self,
@@ -594,6 +697,18 @@ impl Store {
         self.get_receipt_by_block_hash(block_hash, index).await
     }
 
+    /// Obtain the access list of a block, only stored for blocks executed from Amsterdam onwards
+    pub async fn get_block_access_list(
+        &self,
+        block_hash: BlockHash,
+    ) -> Result<Option<BlockAccessList>, StoreError> {
+        self.read_async(BLOCK_ACCESS_LISTS, block_hash.encode_to_vec())
+            .await?
+            .map(|bytes| BlockAccessList::decode(bytes.as_slice()))
+            .transpose()
+            .map_err(StoreError::from)
+    }
+
     /// Obtain receipt by block hash and index
     async fn get_receipt_by_block_hash(
         &self,
@@ -734,7 +849,7 @@ impl Store {
     /// Stores the chain configuration values, should only be called once after reading the genesis file
     /// Ignores previously stored values if present
     pub async fn set_chain_config(&mut self, chain_config: &ChainConfig) -> Result<(), StoreError> {
-        self.chain_config = *chain_config;
+        self.chain_config = chain_config.clone();
         let key = chain_data_key(ChainDataIndex::ChainConfig);
         let value = serde_json::to_string(chain_config)
             .map_err(|_| StoreError::Custom("Failed to serialize chain config".to_string()))?
@@ -766,6 +881,40 @@ impl Store {
             .ok_or(StoreError::MissingEarliestBlockNumber)?
     }
 
+    /// Sets the lowest block whose body and receipts should be downloaded by the history backfill,
+    /// `None` marks the backfill as done
+    pub async fn set_history_backfill_target(
+        &self,
+        block_number: Option<BlockNumber>,
+    ) -> Result<(), StoreError> {
+        let key = chain_data_key(ChainDataIndex::HistoryBackfillTarget);
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut txn = backend.begin_write()?;
+            match block_number {
+                Some(number) => txn.put(CHAIN_DATA, &key, &number.to_le_bytes())?,
+                None => txn.delete(CHAIN_DATA, &key)?,
+            }
+            txn.commit()
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("Task panicked: {}", e)))?
+    }
+
+    /// Obtain the lowest block the history backfill still has to reach, if a backfill is pending
+    pub async fn get_history_backfill_target(&self) -> Result<Option<BlockNumber>, StoreError> {
+        let key = chain_data_key(ChainDataIndex::HistoryBackfillTarget);
+        self.read_async(CHAIN_DATA, key)
+            .await?
+            .map(|bytes| -> Result<BlockNumber, StoreError> {
+                let array: [u8; 8] = bytes
+                    .try_into()
+                    .map_err(|_| StoreError::Custom("Invalid BlockNumber bytes".to_string()))?;
+                Ok(BlockNumber::from_le_bytes(array))
+            })
+            .transpose()
+    }
+
     /// Obtain finalized block number
     pub async fn get_finalized_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
         let key = chain_data_key(ChainDataIndex::FinalizedBlockNumber);
@@ -932,6 +1081,69 @@ impl Store {
             .await
     }
 
+    /// Persists an invalid block along with the reason it was rejected.
+    /// Only the [MAX_BAD_BLOCKS] most recent bad blocks are kept, older ones are evicted.
+    pub fn add_bad_block(&self, block: &Block, reason: String) -> Result<(), StoreError> {
+        let hash = block.hash();
+        let mut entries = Self::read_bad_block_entries(self.backend.as_ref())?;
+        if entries.iter().any(|(_, stored)| stored.hash() == hash) {
+            return Ok(());
+        }
+        let sequence = entries
+            .iter()
+            .map(|(seq, _)| seq + 1)
+            .max()
+            .unwrap_or_default();
+
+        let mut txn = self.backend.begin_write()?;
+        txn.put(
+            BAD_BLOCKS,
+            hash.as_bytes(),
+            &(sequence, block.clone(), reason).encode_to_vec(),
+        )?;
+        // Entries are sorted newest first, evict the oldest ones
+        entries.sort_by_key(|(seq, _)| std::cmp::Reverse(*seq));
+        for (_, evicted) in entries.iter().skip(MAX_BAD_BLOCKS.saturating_sub(1)) {
+            txn.delete(BAD_BLOCKS, evicted.hash().as_bytes())?;
+        }
+        txn.commit()
+    }
+
+    /// Returns the stored bad blocks along with their rejection reason, newest first
+    pub async fn get_bad_blocks(&self) -> Result<Vec<(Block, String)>, StoreError> {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || {
+            let txn = backend.begin_read()?;
+            let mut entries = txn
+                .prefix_iterator(BAD_BLOCKS, &[])?
+                .map(|entry| {
+                    let (_, value) = entry?;
+                    <(u64, Block, String)>::decode(&value).map_err(StoreError::from)
+                })
+                .collect::<Result<Vec<_>, StoreError>>()?;
+            entries.sort_by_key(|(seq, _, _)| std::cmp::Reverse(*seq));
+            Ok(entries
+                .into_iter()
+                .map(|(_, block, reason)| (block, reason))
+                .collect())
+        })
+        .await
+        .map_err(|e| StoreError::Custom(format!("Task panicked: {}", e)))?
+    }
+
+    fn read_bad_block_entries(
+        backend: &dyn StorageBackend,
+    ) -> Result<Vec<(u64, Block)>, StoreError> {
+        let txn = backend.begin_read()?;
+        txn.prefix_iterator(BAD_BLOCKS, &[])?
+            .map(|entry| -> Result<(u64, Block), StoreError> {
+                let (_, value) = entry?;
+                let (sequence, block, _reason) = <(u64, Block, String)>::decode(&value)?;
+                Ok((sequence, block))
+            })
+            .collect()
+    }
+
     /// Returns the latest valid ancestor hash for a given invalid block hash.
     /// Used to provide `latest_valid_hash` in the Engine API when processing invalid payloads.
     pub async fn get_latest_valid_ancestor(
@@ -1229,6 +1441,14 @@ impl Store {
             }
         }
 
+        for (block_hash, block_access_list) in update_batch.block_access_lists {
+            tx.put(
+                BLOCK_ACCESS_LISTS,
+                &block_hash.encode_to_vec(),
+                &block_access_list.encode_to_vec(),
+            )?;
+        }
+
         for (code_hash, code) in update_batch.code_updates {
             let buf = encode_code(&code);
             tx.put(ACCOUNT_CODES, code_hash.as_ref(), &buf)?;
@@ -1469,7 +1689,7 @@ impl Store {
         let block_header = self.latest_block_header.get();
 
         Ok(ForkId::new(
-            chain_config,
+            &chain_config,
             genesis_header,
             block_header.timestamp,
             block_header.number,
@@ -1851,7 +2071,7 @@ impl Store {
     }
 
     pub fn get_chain_config(&self) -> ChainConfig {
-        self.chain_config
+        self.chain_config.clone()
     }
 
     pub async fn get_latest_canonical_block_hash(&self) -> Result<Option<BlockHash>, StoreError> {
@@ -2922,6 +3142,9 @@ mod tests {
         run_test(test_store_block, engine_type).await;
         run_test(test_store_block_number, engine_type).await;
         run_test(test_store_block_receipt, engine_type).await;
+        run_test(test_prune_block_history, engine_type).await;
+        run_test(test_history_backfill, engine_type).await;
+        run_test(test_bad_blocks, engine_type).await;
         run_test(test_store_account_code, engine_type).await;
         run_test(test_store_block_tags, engine_type).await;
         run_test(test_chain_config_storage, engine_type).await;
@@ -3155,6 +3378,121 @@ mod tests {
         assert_eq!(stored_receipt, receipt);
     }
 
+    async fn test_prune_block_history(store: Store) {
+        let (block_header, block_body) = create_block_for_testing();
+        let block_number = block_header.number;
+        let hash = block_header.hash();
+        let receipt = Receipt {
+            tx_type: TxType::EIP1559,
+            succeeded: true,
+            cumulative_gas_used: 1747,
+            logs: vec![],
+        };
+        let tx_hash = block_body.transactions[0].hash();
+
+        store
+            .add_block(Block::new(block_header, block_body))
+            .await
+            .unwrap();
+        store.add_receipts(hash, vec![receipt]).await.unwrap();
+        store
+            .forkchoice_update(vec![], block_number, hash, None, None)
+            .await
+            .unwrap();
+        assert!(store.get_transaction_location(tx_hash).await.unwrap().is_some());
+
+        store.prune_block_history(0, block_number).await.unwrap();
+
+        assert!(store.get_block_header(block_number).unwrap().is_some());
+        assert!(store.get_block_body(block_number).await.unwrap().is_none());
+        assert!(store.get_receipt(block_number, 0).await.unwrap().is_none());
+        assert!(store.get_transaction_location(tx_hash).await.unwrap().is_none());
+        assert_eq!(
+            store.get_earliest_block_number().await.unwrap(),
+            block_number + 1
+        );
+    }
+
+    async fn test_history_backfill(store: Store) {
+        let (block_header, block_body) = create_block_for_testing();
+        let block_number = block_header.number;
+        let hash = block_header.hash();
+        let block = Block::new(block_header, block_body);
+        let receipt = Receipt {
+            tx_type: TxType::EIP1559,
+            succeeded: true,
+            cumulative_gas_used: 1747,
+            logs: vec![],
+        };
+        let tx_hash = block.body.transactions[0].hash();
+
+        // Simulate a snap synced block, only its header is available
+        store.add_block(block.clone()).await.unwrap();
+        store
+            .forkchoice_update(vec![], block_number, hash, None, None)
+            .await
+            .unwrap();
+        store.prune_block_history(0, block_number).await.unwrap();
+        store
+            .set_history_backfill_target(Some(block_number))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_history_backfill_target().await.unwrap(),
+            Some(block_number)
+        );
+
+        store
+            .add_backfilled_blocks(vec![(block, vec![receipt.clone()])])
+            .await
+            .unwrap();
+
+        assert!(store.get_block_body(block_number).await.unwrap().is_some());
+        assert_eq!(
+            store.get_receipt(block_number, 0).await.unwrap(),
+            Some(receipt)
+        );
+        assert!(
+            store
+                .get_transaction_location(tx_hash)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(
+            store.get_earliest_block_number().await.unwrap(),
+            block_number
+        );
+
+        store.set_history_backfill_target(None).await.unwrap();
+        assert_eq!(store.get_history_backfill_target().await.unwrap(), None);
+    }
+
+    async fn test_bad_blocks(store: Store) {
+        let (block_header, block_body) = create_block_for_testing();
+        let mut hashes = vec![];
+        for number in 0..(MAX_BAD_BLOCKS as u64 + 2) {
+            let header = BlockHeader {
+                hash: Default::default(),
+                number,
+                ..block_header.clone()
+            };
+            let block = Block::new(header, block_body.clone());
+            hashes.push(block.hash());
+            store
+                .add_bad_block(&block, format!("bad block {number}"))
+                .unwrap();
+        }
+
+        let bad_blocks = store.get_bad_blocks().await.unwrap();
+        assert_eq!(bad_blocks.len(), MAX_BAD_BLOCKS);
+        // Newest first, the two oldest were evicted
+        let stored: Vec<_> = bad_blocks.iter().map(|(block, _)| block.hash()).collect();
+        hashes.reverse();
+        assert_eq!(stored, hashes[..MAX_BAD_BLOCKS]);
+        assert_eq!(bad_blocks[0].1, format!("bad block {}", MAX_BAD_BLOCKS + 1));
+    }
+
     async fn test_store_account_code(store: Store) {
         let code = Code::from_bytecode(Bytes::from("kiwi"));
         let code_hash = code.hash;
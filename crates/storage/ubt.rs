@@ -3,17 +3,24 @@
 //! This module maintains an auxiliary UBT alongside the MPT state,
 //! computing UBT roots for each canonical block.
 
+use std::collections::VecDeque;
+
 use ethereum_types::H256;
-use ethrex_common::types::AccountUpdate;
+use ethrex_common::types::{AccountUpdate, ubt_leaf::UbtLeaf};
 use tracing::warn;
 use ubt::{
     Address as UbtAddress, B256, BasicDataLeaf, Blake3Hasher, TreeKey, UnifiedBinaryTree,
     chunkify_code, get_basic_data_key, get_code_chunk_key, get_code_hash_key, get_storage_slot_key,
 };
 
+use crate::ubt_witness::{UbtLeaves, UbtWitness, UbtWitnessError};
+
 /// Block number type alias for clarity.
 pub type BlockNumber = u64;
 
+/// Number of execution witnesses kept, for the most recent blocks.
+const MAX_STORED_WITNESSES: usize = 128;
+
 /// A single UBT update entry (key-value pair for batch insert).
 #[derive(Debug, Clone)]
 pub struct UbtUpdate {
@@ -34,6 +41,11 @@ pub struct UbtState {
     current_head: Option<BlockNumber>,
     /// Whether the UBT is currently being rebuilt (e.g., after reorg).
     rebuilding: bool,
+    /// Copy of the leaves of the tree, used to build execution witnesses. Only kept when
+    /// they are enabled, i.e. when the chain schedules stateless gas costs (EIP-4762).
+    leaves: Option<UbtLeaves>,
+    /// Execution witnesses of the most recent blocks, by block hash.
+    witnesses: VecDeque<(H256, UbtWitness)>,
 }
 
 impl std::fmt::Debug for UbtState {
@@ -59,6 +71,8 @@ impl UbtState {
             tree: UnifiedBinaryTree::new(),
             current_head: None,
             rebuilding: false,
+            leaves: None,
+            witnesses: VecDeque::new(),
         }
     }
 
@@ -68,6 +82,8 @@ impl UbtState {
             tree: UnifiedBinaryTree::with_capacity(stem_capacity),
             current_head: None,
             rebuilding: false,
+            leaves: None,
+            witnesses: VecDeque::new(),
         }
     }
 
@@ -92,6 +108,15 @@ impl UbtState {
         self.rebuilding = rebuilding;
     }
 
+    /// Starts keeping the copy of the leaves execution witnesses are built from. It must be
+    /// enabled before any update is applied, as the copy has to hold every leaf of the tree;
+    /// otherwise it stays disabled until the next reset.
+    pub fn enable_witnesses(&mut self) {
+        if self.leaves.is_none() && self.tree.len() == 0 {
+            self.leaves = Some(UbtLeaves::default());
+        }
+    }
+
     /// Apply a batch of updates for a single block.
     ///
     /// Returns the new UBT root hash after applying the updates.
@@ -106,6 +131,12 @@ impl UbtState {
         let mut deletions: Vec<TreeKey> = Vec::new();
 
         for update in updates {
+            if let Some(leaves) = &mut self.leaves {
+                leaves.insert(
+                    tree_key_bytes(&update.key),
+                    update.value.map(|value| value.0),
+                );
+            }
             match update.value {
                 Some(value) => insertions.push((update.key, value)),
                 None => deletions.push(update.key),
@@ -132,6 +163,46 @@ impl UbtState {
         self.tree = UnifiedBinaryTree::new();
         self.current_head = None;
         self.rebuilding = true;
+        if let Some(leaves) = &mut self.leaves {
+            leaves.clear();
+        }
+        self.witnesses.clear();
+    }
+
+    /// Build the witness of the given leaves against the current root.
+    ///
+    /// Called before applying the updates of a block, it proves the values the block read
+    /// and overwrote against the root of its parent. Fails if witnesses aren't enabled, or if
+    /// the copy of the leaves doesn't hash to the root of the tree, as its proofs would be
+    /// useless.
+    pub fn witness(&mut self, keys: &[TreeKey]) -> Result<UbtWitness, UbtWitnessError> {
+        let root = self.root();
+        let leaves = self.leaves.as_mut().ok_or(UbtWitnessError::LeavesNotKept)?;
+        let keys: Vec<[u8; 32]> = keys.iter().map(tree_key_bytes).collect();
+        let witness = leaves.witness(&keys);
+        if witness.parent_root != root {
+            return Err(UbtWitnessError::RootMismatch {
+                expected: root,
+                computed: witness.parent_root,
+            });
+        }
+        Ok(witness)
+    }
+
+    /// Keep the execution witness of a block, evicting the oldest one if needed.
+    pub fn store_witness(&mut self, block_hash: H256, witness: UbtWitness) {
+        if self.witnesses.len() >= MAX_STORED_WITNESSES {
+            self.witnesses.pop_front();
+        }
+        self.witnesses.push_back((block_hash, witness));
+    }
+
+    /// Get the execution witness of a recent block, if it was built.
+    pub fn get_witness(&self, block_hash: H256) -> Option<&UbtWitness> {
+        self.witnesses
+            .iter()
+            .find(|(hash, _)| *hash == block_hash)
+            .map(|(_, witness)| witness)
     }
 
     /// Get the number of stems in the tree (for diagnostics).
@@ -145,6 +216,22 @@ fn to_ubt_address(addr: &ethrex_common::Address) -> UbtAddress {
     UbtAddress::from_slice(addr.as_bytes())
 }
 
+/// Get the 32 bytes of a tree key (31-byte stem + 1-byte subindex).
+fn tree_key_bytes(key: &TreeKey) -> [u8; 32] {
+    key.to_bytes().0
+}
+
+/// Get the tree key of a leaf accessed during execution.
+pub fn ubt_leaf_key(leaf: &UbtLeaf) -> TreeKey {
+    let address = to_ubt_address(&leaf.address());
+    match leaf {
+        UbtLeaf::BasicData(_) => get_basic_data_key(&address),
+        UbtLeaf::CodeHash(_) => get_code_hash_key(&address),
+        UbtLeaf::Storage(_, slot) => get_storage_slot_key(&address, &slot.0),
+        UbtLeaf::CodeChunk(_, chunk) => get_code_chunk_key(&address, *chunk),
+    }
+}
+
 /// Convert account updates to UBT updates.
 ///
 /// This function extracts all state changes from `AccountUpdate`s and converts
@@ -318,6 +405,70 @@ mod tests {
         assert!(state.is_rebuilding());
     }
 
+    #[test]
+    fn test_witnesses_need_every_leaf() {
+        let mut state = UbtState::new();
+        let key = get_basic_data_key(&Address::repeat_byte(0x42));
+        let updates = vec![UbtUpdate {
+            key,
+            value: Some(BasicDataLeaf::new(1, 1000, 0).encode()),
+        }];
+        state.apply_block_updates(1, H256::zero(), &updates);
+
+        // Too late, the copy would miss the leaves already in the tree
+        state.enable_witnesses();
+        assert!(matches!(
+            state.witness(&[key]),
+            Err(UbtWitnessError::LeavesNotKept)
+        ));
+
+        state.reset();
+        state.enable_witnesses();
+        state.apply_block_updates(1, H256::zero(), &updates);
+        assert_eq!(state.witness(&[key]).unwrap().parent_root, state.root());
+    }
+
+    #[test]
+    fn test_witness_against_tree_root() {
+        use crate::ubt_witness::verify_ubt_witness;
+
+        let mut state = UbtState::new();
+        state.enable_witnesses();
+        let updates: Vec<UbtUpdate> = (1..=20u8)
+            .flat_map(|byte| {
+                let address = Address::repeat_byte(byte);
+                [
+                    UbtUpdate {
+                        key: get_basic_data_key(&address),
+                        value: Some(BasicDataLeaf::new(byte.into(), 1000, 0).encode()),
+                    },
+                    UbtUpdate {
+                        key: get_storage_slot_key(&address, &[byte; 32]),
+                        value: Some(B256::repeat_byte(byte)),
+                    },
+                ]
+            })
+            .collect();
+        state.apply_block_updates(1, H256::zero(), &updates);
+
+        let root = state.root();
+        let keys = [
+            get_basic_data_key(&Address::repeat_byte(3)),
+            get_storage_slot_key(&Address::repeat_byte(7), &[7; 32]),
+            get_basic_data_key(&Address::repeat_byte(0xaa)),
+        ];
+        let witness = state.witness(&keys).unwrap();
+        assert_eq!(witness.parent_root, root);
+
+        let values = verify_ubt_witness(&witness, root).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(
+            values[&H256(tree_key_bytes(&keys[1]))],
+            Some(H256::repeat_byte(7))
+        );
+        assert_eq!(values[&H256(tree_key_bytes(&keys[2]))], None);
+    }
+
     #[test]
     fn test_account_updates_to_ubt() {
         use ethrex_common::types::{AccountInfo, Code};
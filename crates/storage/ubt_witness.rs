@@ -0,0 +1,575 @@
+//! UBT (Unified Binary Tree) execution witnesses.
+//!
+//! A witness holds the values of the tree leaves accessed by a block together with the sibling
+//! hashes needed to recompute the root of the tree the block was executed on, so a stateless
+//! client can check them knowing only that root. Hashing follows EIP-7864: a stem node hashes
+//! `stem || 0x00 || values_root`, where `values_root` is the root of the binary tree of its 256
+//! leaf hashes, internal nodes hash their two children, and hashing 64 zero bytes gives zero.
+
+use std::collections::{BTreeMap, HashMap};
+
+use bytes::Bytes;
+use ethereum_types::H256;
+use serde::{Deserialize, Serialize};
+
+/// The first 31 bytes of a tree key, shared by the 256 leaves of a stem node
+pub type Stem = [u8; 31];
+
+type Hash = [u8; 32];
+
+const ZERO_HASH: Hash = [0; 32];
+/// Depth of the tree of values below each stem node
+const VALUES_TREE_DEPTH: usize = 8;
+/// Number of bits of a stem, the maximum depth of a stem node
+const STEM_BITS: usize = 31 * 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UbtWitnessError {
+    #[error("Invalid stem {0:?}, stems are 31 bytes long")]
+    InvalidStem(Bytes),
+    #[error("Invalid proof for stem {stem:?}: {reason}")]
+    InvalidProof { stem: Bytes, reason: &'static str },
+    #[error("Witness root {computed:?} doesn't match the expected root {expected:?}")]
+    RootMismatch { expected: H256, computed: H256 },
+    #[error("The leaves of the tree aren't being kept, witnesses can't be built")]
+    LeavesNotKept,
+}
+
+/// Proof of the leaves of a tree accessed by a block, against the root of the tree before it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UbtWitness {
+    pub parent_root: H256,
+    pub stems: Vec<UbtStemProof>,
+}
+
+/// Proof of the leaves of a single stem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UbtStemProof {
+    #[serde(with = "ethrex_common::serde_utils::bytes")]
+    pub stem: Bytes,
+    /// Sibling hashes from the node found for the stem up to the root
+    pub path: Vec<H256>,
+    /// What was found at the end of the path
+    pub terminal: StemTerminal,
+    pub leaves: Vec<UbtLeafProof>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StemTerminal {
+    /// The stem node itself, its leaves are proven against its values root
+    Present,
+    /// An empty subtree, so the stem and all its leaves are absent
+    Empty,
+    /// Another stem node sharing the path, so the stem and all its leaves are absent
+    Other {
+        #[serde(with = "ethrex_common::serde_utils::bytes")]
+        stem: Bytes,
+        values_root: H256,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UbtLeafProof {
+    pub sub_index: u8,
+    pub value: Option<H256>,
+    /// Sibling hashes in the values tree of the stem, from the leaf up. Empty if the stem
+    /// isn't present.
+    pub siblings: Vec<H256>,
+}
+
+/// Copy of the leaves of the tree, from which witnesses are built. The tree itself only
+/// exposes its root.
+///
+/// The hashes of the subtrees are cached and only the ones in the path of a changed stem are
+/// recomputed, so building the witness of a block costs in proportion to what it changed and
+/// accessed rather than to the size of the tree.
+#[derive(Debug, Clone, Default)]
+pub struct UbtLeaves {
+    stems: BTreeMap<Stem, StemLeaves>,
+    /// Hashes of the subtrees with more than one stem, keyed by their depth and the stem prefix
+    /// leading to them (see [prefix])
+    subtree_hashes: HashMap<(usize, Stem), Hash>,
+}
+
+/// What a subtree holds
+enum Subtree {
+    Empty,
+    Stem(Stem, Hash),
+    Branch,
+}
+
+#[derive(Debug, Clone, Default)]
+struct StemLeaves {
+    values: BTreeMap<u8, Hash>,
+    /// Cached root of the values tree, cleared on every change
+    values_root: Option<Hash>,
+}
+
+impl StemLeaves {
+    fn values_root(&mut self) -> Hash {
+        *self
+            .values_root
+            .get_or_insert_with(|| values_tree(&self.values)[VALUES_TREE_DEPTH][0])
+    }
+}
+
+impl UbtLeaves {
+    /// Sets the value of the leaf at `key`, `None` removes it
+    pub fn insert(&mut self, key: [u8; 32], value: Option<[u8; 32]>) {
+        let (stem, sub_index) = split_key(&key);
+        for depth in 0..STEM_BITS {
+            self.subtree_hashes.remove(&(depth, prefix(&stem, depth)));
+        }
+        match value {
+            Some(value) => {
+                let stem_leaves = self.stems.entry(stem).or_default();
+                stem_leaves.values.insert(sub_index, value);
+                stem_leaves.values_root = None;
+            }
+            None => {
+                if let Some(stem_leaves) = self.stems.get_mut(&stem) {
+                    stem_leaves.values.remove(&sub_index);
+                    stem_leaves.values_root = None;
+                    if stem_leaves.values.is_empty() {
+                        self.stems.remove(&stem);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, key: &[u8; 32]) -> Option<[u8; 32]> {
+        let (stem, sub_index) = split_key(key);
+        self.stems.get(&stem)?.values.get(&sub_index).copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.stems.clear();
+        self.subtree_hashes.clear();
+    }
+
+    pub fn root(&mut self) -> H256 {
+        self.witness(&[]).parent_root
+    }
+
+    /// Builds the witness of the leaves at `keys` against the current root
+    pub fn witness(&mut self, keys: &[[u8; 32]]) -> UbtWitness {
+        let mut requested: BTreeMap<Stem, Vec<u8>> = BTreeMap::new();
+        for key in keys {
+            let (stem, sub_index) = split_key(key);
+            requested.entry(stem).or_default().push(sub_index);
+        }
+        let targets: Vec<Stem> = requested.keys().copied().collect();
+
+        let mut proofs = BTreeMap::new();
+        let root = self.subtree_root([0; 31], 0, &targets, &mut proofs);
+
+        let stems = requested
+            .into_iter()
+            .filter_map(|(stem, mut sub_indices)| {
+                let (path, terminal) = proofs.remove(&stem)?;
+                sub_indices.sort_unstable();
+                sub_indices.dedup();
+                let tree = match terminal {
+                    StemTerminal::Present => self
+                        .stems
+                        .get(&stem)
+                        .map(|leaves| (values_tree(&leaves.values), &leaves.values)),
+                    _ => None,
+                };
+                let leaves = sub_indices
+                    .into_iter()
+                    .map(|sub_index| match &tree {
+                        Some((levels, values)) => UbtLeafProof {
+                            sub_index,
+                            value: values.get(&sub_index).map(|value| H256(*value)),
+                            siblings: values_tree_siblings(levels, sub_index),
+                        },
+                        None => UbtLeafProof {
+                            sub_index,
+                            value: None,
+                            siblings: Vec::new(),
+                        },
+                    })
+                    .collect();
+                Some(UbtStemProof {
+                    stem: Bytes::copy_from_slice(&stem),
+                    path,
+                    terminal,
+                    leaves,
+                })
+            })
+            .collect();
+
+        UbtWitness {
+            parent_root: H256(root),
+            stems,
+        }
+    }
+
+    /// Computes the root of the subtree at `depth` holding the stems starting with `prefix`.
+    /// Fills `proofs` with the path and terminal of each of the `targets`, sorted, that fall in
+    /// the subtree.
+    fn subtree_root(
+        &mut self,
+        prefix: Stem,
+        depth: usize,
+        targets: &[Stem],
+        proofs: &mut BTreeMap<Stem, (Vec<H256>, StemTerminal)>,
+    ) -> Hash {
+        if targets.is_empty()
+            && let Some(hash) = self.subtree_hashes.get(&(depth, prefix))
+        {
+            return *hash;
+        }
+
+        let subtree = {
+            let mut stems = self.stems.range_mut(prefix..=last_stem(&prefix, depth));
+            match (stems.next(), stems.next()) {
+                (None, _) => Subtree::Empty,
+                (Some((stem, leaves)), None) => Subtree::Stem(*stem, leaves.values_root()),
+                _ => Subtree::Branch,
+            }
+        };
+        match subtree {
+            Subtree::Empty => {
+                for target in targets {
+                    proofs.insert(*target, (Vec::new(), StemTerminal::Empty));
+                }
+                ZERO_HASH
+            }
+            Subtree::Stem(stem, values_root) => {
+                for target in targets {
+                    let terminal = if *target == stem {
+                        StemTerminal::Present
+                    } else {
+                        StemTerminal::Other {
+                            stem: Bytes::copy_from_slice(&stem),
+                            values_root: H256(values_root),
+                        }
+                    };
+                    proofs.insert(*target, (Vec::new(), terminal));
+                }
+                stem_hash(&stem, &values_root)
+            }
+            Subtree::Branch => {
+                // Sorted stems have the ones going left first
+                let target_split = targets.partition_point(|stem| !bit(stem, depth));
+                let (left_targets, right_targets) = targets.split_at(target_split);
+                let mut right_prefix = prefix;
+                right_prefix[depth / 8] |= 0x80 >> (depth % 8);
+
+                let left = self.subtree_root(prefix, depth + 1, left_targets, proofs);
+                let right = self.subtree_root(right_prefix, depth + 1, right_targets, proofs);
+                for (targets, sibling) in [(left_targets, right), (right_targets, left)] {
+                    for target in targets {
+                        if let Some((path, _)) = proofs.get_mut(target) {
+                            path.push(H256(sibling));
+                        }
+                    }
+                }
+                let hash = hash_pair(&left, &right);
+                self.subtree_hashes.insert((depth, prefix), hash);
+                hash
+            }
+        }
+    }
+}
+
+/// Checks the witness against `root`, returning the proven value of every leaf in it, keyed
+/// by its tree key. Absent leaves are proven to have no value.
+pub fn verify_ubt_witness(
+    witness: &UbtWitness,
+    root: H256,
+) -> Result<BTreeMap<H256, Option<H256>>, UbtWitnessError> {
+    if witness.parent_root != root {
+        return Err(UbtWitnessError::RootMismatch {
+            expected: root,
+            computed: witness.parent_root,
+        });
+    }
+
+    let mut values = BTreeMap::new();
+    for proof in &witness.stems {
+        let stem = to_stem(&proof.stem)?;
+        let invalid = |reason| UbtWitnessError::InvalidProof {
+            stem: proof.stem.clone(),
+            reason,
+        };
+        if proof.path.len() >= stem.len() * 8 {
+            return Err(invalid("path longer than the stem"));
+        }
+
+        let mut node = match &proof.terminal {
+            StemTerminal::Present => {
+                let mut values_root = None;
+                for leaf in &proof.leaves {
+                    if leaf.siblings.len() != VALUES_TREE_DEPTH {
+                        return Err(invalid("wrong number of siblings in the values tree"));
+                    }
+                    let mut node = leaf.value.map(|value| hash(&value.0)).unwrap_or(ZERO_HASH);
+                    let mut index = leaf.sub_index;
+                    for sibling in &leaf.siblings {
+                        node = if index & 1 == 0 {
+                            hash_pair(&node, &sibling.0)
+                        } else {
+                            hash_pair(&sibling.0, &node)
+                        };
+                        index >>= 1;
+                    }
+                    if *values_root.get_or_insert(node) != node {
+                        return Err(invalid("leaves prove different values roots"));
+                    }
+                }
+                let values_root = values_root.ok_or_else(|| invalid("no leaves"))?;
+                stem_hash(&stem, &values_root)
+            }
+            StemTerminal::Empty => ZERO_HASH,
+            StemTerminal::Other {
+                stem: other,
+                values_root,
+            } => {
+                let other = to_stem(other)?;
+                if other == stem || (0..proof.path.len()).any(|i| bit(&other, i) != bit(&stem, i)) {
+                    return Err(invalid("the other stem isn't in the path of the stem"));
+                }
+                stem_hash(&other, &values_root.0)
+            }
+        };
+
+        for (height, sibling) in proof.path.iter().enumerate() {
+            let depth = proof.path.len() - 1 - height;
+            node = if bit(&stem, depth) {
+                hash_pair(&sibling.0, &node)
+            } else {
+                hash_pair(&node, &sibling.0)
+            };
+        }
+        if node != root.0 {
+            return Err(invalid("path doesn't lead to the root"));
+        }
+
+        for leaf in &proof.leaves {
+            let value = match proof.terminal {
+                StemTerminal::Present => leaf.value,
+                _ => None,
+            };
+            let mut key = [0; 32];
+            key[..31].copy_from_slice(&stem);
+            key[31] = leaf.sub_index;
+            values.insert(H256(key), value);
+        }
+    }
+    Ok(values)
+}
+
+/// Returns every level of the values tree of a stem, from the 256 leaf hashes up to the root
+fn values_tree(values: &BTreeMap<u8, Hash>) -> Vec<Vec<Hash>> {
+    let mut level = vec![ZERO_HASH; 256];
+    for (sub_index, value) in values {
+        level[usize::from(*sub_index)] = hash(value);
+    }
+    let mut levels = vec![level];
+    for _ in 0..VALUES_TREE_DEPTH {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+fn values_tree_siblings(levels: &[Vec<Hash>], sub_index: u8) -> Vec<H256> {
+    let mut index = usize::from(sub_index);
+    levels[..VALUES_TREE_DEPTH]
+        .iter()
+        .map(|level| {
+            let sibling = H256(level[index ^ 1]);
+            index >>= 1;
+            sibling
+        })
+        .collect()
+}
+
+/// The first `depth` bits of the stem, followed by zeros, which is also the first stem of the
+/// subtree at `depth` the stem belongs to
+fn prefix(stem: &Stem, depth: usize) -> Stem {
+    let mut prefix = *stem;
+    if depth < STEM_BITS {
+        prefix[depth / 8] &= !(0xff >> (depth % 8));
+        prefix[depth / 8 + 1..].fill(0);
+    }
+    prefix
+}
+
+/// The last stem of the subtree at `depth` whose stems start with `prefix`
+fn last_stem(prefix: &Stem, depth: usize) -> Stem {
+    let mut last = *prefix;
+    if depth < STEM_BITS {
+        last[depth / 8] |= 0xff >> (depth % 8);
+        last[depth / 8 + 1..].fill(0xff);
+    }
+    last
+}
+
+fn split_key(key: &[u8; 32]) -> (Stem, u8) {
+    let mut stem = [0; 31];
+    stem.copy_from_slice(&key[..31]);
+    (stem, key[31])
+}
+
+fn to_stem(bytes: &Bytes) -> Result<Stem, UbtWitnessError> {
+    Stem::try_from(bytes.as_ref()).map_err(|_| UbtWitnessError::InvalidStem(bytes.clone()))
+}
+
+/// Bit of the stem at `depth`, most significant first
+fn bit(stem: &Stem, depth: usize) -> bool {
+    (stem[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+fn hash(data: &[u8]) -> Hash {
+    if data.len() == 64 && data.iter().all(|byte| *byte == 0) {
+        return ZERO_HASH;
+    }
+    *blake3::hash(data).as_bytes()
+}
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    let mut data = [0; 64];
+    data[..32].copy_from_slice(left);
+    data[32..].copy_from_slice(right);
+    hash(&data)
+}
+
+fn stem_hash(stem: &Stem, values_root: &Hash) -> Hash {
+    let mut data = [0; 64];
+    data[..31].copy_from_slice(stem);
+    data[32..].copy_from_slice(values_root);
+    hash(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(stem_byte: u8, sub_index: u8) -> [u8; 32] {
+        let mut key = [stem_byte; 32];
+        key[31] = sub_index;
+        key
+    }
+
+    fn leaves() -> UbtLeaves {
+        let mut leaves = UbtLeaves::default();
+        let entries = [
+            (0x00, 0, 1),
+            (0x00, 7, 2),
+            (0x20, 3, 5),
+            (0x80, 0, 3),
+            (0xc0, 1, 4),
+        ];
+        for (stem_byte, sub_index, value) in entries {
+            leaves.insert(key(stem_byte, sub_index), Some([value; 32]));
+        }
+        leaves
+    }
+
+    #[test]
+    fn empty_tree_root_is_zero() {
+        assert_eq!(UbtLeaves::default().root(), H256::zero());
+    }
+
+    #[test]
+    fn removing_every_leaf_of_a_stem_removes_it() {
+        let mut leaves = leaves();
+        let root = leaves.root();
+        leaves.insert(key(0x40, 5), Some([9; 32]));
+        assert_ne!(leaves.root(), root);
+        leaves.insert(key(0x40, 5), None);
+        assert_eq!(leaves.root(), root);
+    }
+
+    #[test]
+    fn cached_hashes_follow_changes() {
+        let mut leaves = leaves();
+        let keys = [key(0x00, 7), key(0x20, 3), key(0x21, 0), key(0xc0, 1)];
+        leaves.witness(&keys);
+
+        // Changes a leaf, adds a stem next to another and removes one
+        leaves.insert(key(0x00, 7), Some([6; 32]));
+        leaves.insert(key(0x21, 0), Some([7; 32]));
+        leaves.insert(key(0xc0, 1), None);
+        let updated = leaves.witness(&keys);
+
+        let mut rebuilt = UbtLeaves::default();
+        for (stem_byte, sub_index, value) in [
+            (0x00, 0, 1),
+            (0x00, 7, 6),
+            (0x20, 3, 5),
+            (0x21, 0, 7),
+            (0x80, 0, 3),
+        ] {
+            rebuilt.insert(key(stem_byte, sub_index), Some([value; 32]));
+        }
+        assert_eq!(updated, rebuilt.witness(&keys));
+        assert!(verify_ubt_witness(&updated, updated.parent_root).is_ok());
+    }
+
+    #[test]
+    fn stem_prefixes() {
+        let stem = [0b1011_0110; 31];
+        assert_eq!(prefix(&stem, 0), [0; 31]);
+        let mut expected = [0; 31];
+        expected[0] = 0b1010_0000;
+        assert_eq!(prefix(&stem, 3), expected);
+        expected[0] = 0b1011_1111;
+        expected[1..].fill(0xff);
+        assert_eq!(last_stem(&prefix(&stem, 3), 3), expected);
+        assert_eq!(prefix(&stem, STEM_BITS), stem);
+        assert_eq!(last_stem(&stem, STEM_BITS), stem);
+    }
+
+    #[test]
+    fn witness_roundtrip() {
+        let mut leaves = leaves();
+        let root = leaves.root();
+        let keys = [
+            key(0x00, 7), // present
+            key(0x00, 8), // absent leaf of a present stem
+            key(0x81, 0), // absent stem sharing its path with another one
+            key(0x40, 0), // absent stem in an empty subtree
+            key(0xc0, 1), // present
+        ];
+        let witness = leaves.witness(&keys);
+        assert_eq!(witness.parent_root, root);
+
+        let values = verify_ubt_witness(&witness, root).unwrap();
+        let expected: BTreeMap<H256, Option<H256>> = [
+            (key(0x00, 7), Some(H256([2; 32]))),
+            (key(0x00, 8), None),
+            (key(0x81, 0), None),
+            (key(0x40, 0), None),
+            (key(0xc0, 1), Some(H256([4; 32]))),
+        ]
+        .into_iter()
+        .map(|(key, value)| (H256(key), value))
+        .collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn tampered_witness_is_rejected() {
+        let mut leaves = leaves();
+        let root = leaves.root();
+        let mut witness = leaves.witness(&[key(0x80, 0)]);
+        witness.stems[0].leaves[0].value = Some(H256([5; 32]));
+        assert!(verify_ubt_witness(&witness, root).is_err());
+
+        let witness = leaves.witness(&[key(0x80, 0)]);
+        assert!(verify_ubt_witness(&witness, H256::repeat_byte(1)).is_err());
+    }
+}
@@ -8,6 +8,8 @@ pub enum ChainDataIndex {
     SafeBlockNumber = 3,
     LatestBlockNumber = 4,
     PendingBlockNumber = 5,
+    // Lowest block whose history is still to be downloaded after a snap sync
+    HistoryBackfillTarget = 6,
 }
 
 impl From<u8> for ChainDataIndex {
@@ -25,6 +27,9 @@ impl From<u8> for ChainDataIndex {
             x if x == ChainDataIndex::PendingBlockNumber as u8 => {
                 ChainDataIndex::PendingBlockNumber
             }
+            x if x == ChainDataIndex::HistoryBackfillTarget as u8 => {
+                ChainDataIndex::HistoryBackfillTarget
+            }
             _ => panic!("Invalid value when casting to ChainDataIndex: {value}"),
         }
     }
@@ -1,4 +1,6 @@
 pub mod db;
+mod parallel;
+pub mod prefetch;
 mod tracing;
 
 use super::BlockExecutionResult;
@@ -25,6 +27,7 @@ use ethrex_levm::constants::{
 };
 use ethrex_levm::db::gen_db::GeneralizedDatabase;
 use ethrex_levm::errors::{InternalError, TxValidationError};
+use ethrex_levm::hooks::rip7560_hook::simulate_rip7560_validation;
 #[cfg(feature = "perf_opcode_timings")]
 use ethrex_levm::timings::{OPCODE_TIMINGS, PRECOMPILES_TIMINGS};
 use ethrex_levm::tracing::LevmCallTracer;
@@ -35,10 +38,45 @@ use ethrex_levm::{
     errors::{ExecutionReport, TxResult, VMError},
     vm::VM,
 };
+use prefetch::PrefetchMode;
 use std::cmp::min;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 
+/// Sends the state transitions of the block to the merkleizer every few transactions,
+/// whenever it is idle
+pub(crate) struct StateFlusher<'a> {
+    merkleizer: &'a Sender<Vec<AccountUpdate>>,
+    queue_length: &'a AtomicUsize,
+    tx_since_last_flush: usize,
+}
+
+impl<'a> StateFlusher<'a> {
+    pub(crate) fn new(
+        merkleizer: &'a Sender<Vec<AccountUpdate>>,
+        queue_length: &'a AtomicUsize,
+    ) -> Self {
+        Self {
+            merkleizer,
+            queue_length,
+            // Starts at 2 to account for the two precompile calls done in `LEVM::prepare_block`.
+            // The value itself can be safely changed.
+            tx_since_last_flush: 2,
+        }
+    }
+
+    /// Must be called after each transaction's changes are in `db`
+    pub(crate) fn tx_executed(&mut self, db: &mut GeneralizedDatabase) -> Result<(), EvmError> {
+        if self.queue_length.load(Ordering::Relaxed) == 0 && self.tx_since_last_flush > 5 {
+            LEVM::send_state_transitions_tx(self.merkleizer, db, self.queue_length)?;
+            self.tx_since_last_flush = 0;
+        } else {
+            self.tx_since_last_flush += 1;
+        }
+        Ok(())
+    }
+}
+
 /// The struct implements the following functions:
 /// [LEVM::execute_block]
 /// [LEVM::execute_tx]
@@ -48,39 +86,24 @@ use std::sync::mpsc::Sender;
 pub struct LEVM;
 
 impl LEVM {
+    /// Executes the block over `db`.
+    /// If `parallel` is set, L1 transactions are executed optimistically in parallel,
+    /// see [LEVM::execute_transactions_parallel].
     pub fn execute_block(
         block: &Block,
         db: &mut GeneralizedDatabase,
         vm_type: VMType,
+        parallel: bool,
     ) -> Result<BlockExecutionResult, EvmError> {
+        Self::start_access_recording(block, db, vm_type)?;
         Self::prepare_block(block, db, vm_type)?;
+        db.next_block_access_index()?;
 
-        let mut receipts = Vec::new();
-        let mut cumulative_gas_used = 0;
-
-        for (tx, tx_sender) in block.body.get_transactions_with_sender().map_err(|error| {
-            EvmError::Transaction(format!("Couldn't recover addresses with error: {error}"))
-        })? {
-            if cumulative_gas_used + tx.gas_limit() > block.header.gas_limit {
-                return Err(EvmError::Transaction(format!(
-                    "Gas allowance exceeded. Block gas limit {} can be surpassed by executing transaction with gas limit {}",
-                    block.header.gas_limit,
-                    tx.gas_limit()
-                )));
-            }
-
-            let report = Self::execute_tx(tx, tx_sender, &block.header, db, vm_type)?;
-
-            cumulative_gas_used += report.gas_used;
-            let receipt = Receipt::new(
-                tx.tx_type(),
-                matches!(report.result, TxResult::Success),
-                cumulative_gas_used,
-                report.logs,
-            );
-
-            receipts.push(receipt);
-        }
+        let receipts = if parallel && matches!(vm_type, VMType::L1) {
+            Self::execute_transactions_parallel(block, db, vm_type, None)?
+        } else {
+            Self::execute_transactions(block, db, vm_type)?
+        };
 
         if let Some(withdrawals) = &block.body.withdrawals {
             Self::process_withdrawals(db, withdrawals)?;
@@ -93,28 +116,47 @@ impl LEVM {
             VMType::L1 => extract_all_requests_levm(&receipts, db, &block.header, vm_type)?,
             VMType::L2(_) => Default::default(),
         };
-
-        Ok(BlockExecutionResult { receipts, requests })
+        let block_access_list = db.take_block_access_list()?;
+        let accessed_leaves = db.take_accessed_leaves();
+
+        Ok(BlockExecutionResult {
+            receipts,
+            requests,
+            block_access_list,
+            accessed_leaves,
+            prefetch_stats: None,
+        })
     }
 
-    pub fn execute_block_pipeline(
+    /// Starts recording the block access list if the block is an L1 block from Amsterdam
+    /// onwards, and the EIP-4762 access events if stateless gas costs are enabled
+    fn start_access_recording(
         block: &Block,
         db: &mut GeneralizedDatabase,
         vm_type: VMType,
-        merkleizer: Sender<Vec<AccountUpdate>>,
-        queue_length: &AtomicUsize,
-    ) -> Result<BlockExecutionResult, EvmError> {
-        Self::prepare_block(block, db, vm_type)?;
-
-        let mut shared_stack_pool = Vec::with_capacity(STACK_LIMIT);
+    ) -> Result<(), EvmError> {
+        let chain_config = db.store.get_chain_config()?;
+        let block_access_list = matches!(vm_type, VMType::L1)
+            && chain_config.is_amsterdam_activated(block.header.timestamp);
+        if block_access_list {
+            db.enable_access_recording();
+        }
+        if chain_config.is_eip4762_activated(block.header.timestamp) {
+            db.enable_access_events_recording();
+        }
+        Ok(())
+    }
 
+    /// Executes the transactions of the block one after the other and returns their receipts
+    fn execute_transactions(
+        block: &Block,
+        db: &mut GeneralizedDatabase,
+        vm_type: VMType,
+    ) -> Result<Vec<Receipt>, EvmError> {
+        let mut stack_pool = Vec::new();
         let mut receipts = Vec::new();
         let mut cumulative_gas_used = 0;
 
-        // Starts at 2 to account for the two precompile calls done in `Self::prepare_block`.
-        // The value itself can be safely changed.
-        let mut tx_since_last_flush = 2;
-
         for (tx, tx_sender) in block.body.get_transactions_with_sender().map_err(|error| {
             EvmError::Transaction(format!("Couldn't recover addresses with error: {error}"))
         })? {
@@ -132,14 +174,9 @@ impl LEVM {
                 &block.header,
                 db,
                 vm_type,
-                &mut shared_stack_pool,
+                &mut stack_pool,
             )?;
-            if queue_length.load(Ordering::Relaxed) == 0 && tx_since_last_flush > 5 {
-                LEVM::send_state_transitions_tx(&merkleizer, db, queue_length)?;
-                tx_since_last_flush = 0;
-            } else {
-                tx_since_last_flush += 1;
-            }
+            db.next_block_access_index()?;
 
             cumulative_gas_used += report.gas_used;
             let receipt = Receipt::new(
@@ -152,6 +189,35 @@ impl LEVM {
             receipts.push(receipt);
         }
 
+        Ok(receipts)
+    }
+
+    /// Executes the block over `db`, sending state transitions to the merkleizer as they are produced.
+    /// If `parallel` is set, L1 transactions are executed optimistically in parallel,
+    /// see [LEVM::execute_transactions_parallel].
+    pub fn execute_block_pipeline(
+        block: &Block,
+        db: &mut GeneralizedDatabase,
+        vm_type: VMType,
+        merkleizer: Sender<Vec<AccountUpdate>>,
+        queue_length: &AtomicUsize,
+        parallel: bool,
+        prefetch: PrefetchMode,
+    ) -> Result<BlockExecutionResult, EvmError> {
+        Self::start_access_recording(block, db, vm_type)?;
+        let (receipts, prefetch_stats) =
+            Self::execute_with_prefetch(block, db, vm_type, prefetch, |db| {
+                Self::prepare_block(block, db, vm_type)?;
+                db.next_block_access_index()?;
+
+                let mut flusher = StateFlusher::new(&merkleizer, queue_length);
+                if parallel && matches!(vm_type, VMType::L1) {
+                    Self::execute_transactions_parallel(block, db, vm_type, Some(&mut flusher))
+                } else {
+                    Self::execute_transactions_pipeline(block, db, vm_type, &mut flusher)
+                }
+            })?;
+
         #[cfg(feature = "perf_opcode_timings")]
         {
             let mut timings = OPCODE_TIMINGS.lock().expect("poison");
@@ -162,6 +228,11 @@ impl LEVM {
             ::tracing::info!("{}", precompiles_timings.info_pretty());
         }
 
+        #[cfg(feature = "perf_contract_profiling")]
+        if let Ok(mut profile) = ethrex_levm::contract_profiling::CONTRACT_PROFILE.lock() {
+            profile.inc_block_count();
+        }
+
         if queue_length.load(Ordering::Relaxed) == 0 {
             LEVM::send_state_transitions_tx(&merkleizer, db, queue_length)?;
         }
@@ -188,9 +259,66 @@ impl LEVM {
             VMType::L1 => extract_all_requests_levm(&receipts, db, &block.header, vm_type)?,
             VMType::L2(_) => Default::default(),
         };
+        let block_access_list = db.take_block_access_list()?;
+        let accessed_leaves = db.take_accessed_leaves();
         LEVM::send_state_transitions_tx(&merkleizer, db, queue_length)?;
 
-        Ok(BlockExecutionResult { receipts, requests })
+        Ok(BlockExecutionResult {
+            receipts,
+            requests,
+            block_access_list,
+            accessed_leaves,
+            prefetch_stats,
+        })
+    }
+
+    /// Executes the transactions of the block one after the other, flushing state transitions
+    /// to the merkleizer whenever it is idle, and returns their receipts
+    fn execute_transactions_pipeline(
+        block: &Block,
+        db: &mut GeneralizedDatabase,
+        vm_type: VMType,
+        flusher: &mut StateFlusher<'_>,
+    ) -> Result<Vec<Receipt>, EvmError> {
+        let mut shared_stack_pool = Vec::with_capacity(STACK_LIMIT);
+
+        let mut receipts = Vec::new();
+        let mut cumulative_gas_used = 0;
+
+        for (tx, tx_sender) in block.body.get_transactions_with_sender().map_err(|error| {
+            EvmError::Transaction(format!("Couldn't recover addresses with error: {error}"))
+        })? {
+            if cumulative_gas_used + tx.gas_limit() > block.header.gas_limit {
+                return Err(EvmError::Transaction(format!(
+                    "Gas allowance exceeded. Block gas limit {} can be surpassed by executing transaction with gas limit {}",
+                    block.header.gas_limit,
+                    tx.gas_limit()
+                )));
+            }
+
+            let report = Self::execute_tx_in_block(
+                tx,
+                tx_sender,
+                &block.header,
+                db,
+                vm_type,
+                &mut shared_stack_pool,
+            )?;
+            db.next_block_access_index()?;
+            flusher.tx_executed(db)?;
+
+            cumulative_gas_used += report.gas_used;
+            let receipt = Receipt::new(
+                tx.tx_type(),
+                matches!(report.result, TxResult::Success),
+                cumulative_gas_used,
+                report.logs,
+            );
+
+            receipts.push(receipt);
+        }
+
+        Ok(receipts)
     }
 
     fn send_state_transitions_tx(
@@ -206,7 +334,7 @@ impl LEVM {
         Ok(())
     }
 
-    fn setup_env(
+    pub fn setup_env(
         tx: &Transaction,
         tx_sender: Address,
         block_header: &BlockHeader,
@@ -222,6 +350,7 @@ impl LEVM {
 
         let block_excess_blob_gas = block_header.excess_blob_gas.map(U256::from);
         let config = EVMConfig::new_from_chain_config(&chain_config, block_header);
+        let base_blob_fee_per_gas = get_base_fee_per_blob_gas(block_excess_blob_gas, &config)?;
         let env = Environment {
             origin: tx_sender,
             gas_limit: tx.gas_limit(),
@@ -232,7 +361,7 @@ impl LEVM {
             prev_randao: Some(block_header.prev_randao),
             chain_id: chain_config.chain_id.into(),
             base_fee_per_gas: block_header.base_fee_per_gas.unwrap_or_default().into(),
-            base_blob_fee_per_gas: get_base_fee_per_blob_gas(block_excess_blob_gas, &config)?,
+            base_blob_fee_per_gas,
             gas_price,
             block_excess_blob_gas,
             block_blob_gas_used: block_header.blob_gas_used.map(U256::from),
@@ -266,7 +395,8 @@ impl LEVM {
         vm.execute().map_err(VMError::into)
     }
 
-    // Like execute_tx but allows reusing the stack pool
+    // Like execute_tx but allows reusing the stack pool, and profiles the contracts it runs since
+    // the transaction is part of the block being executed
     fn execute_tx_in_block(
         // The transaction to execute.
         tx: &Transaction,
@@ -280,13 +410,40 @@ impl LEVM {
     ) -> Result<ExecutionReport, EvmError> {
         let env = Self::setup_env(tx, tx_sender, block_header, db, vm_type)?;
         let mut vm = VM::new(env, db, tx, LevmCallTracer::disabled(), vm_type)?;
+        #[cfg(feature = "perf_contract_profiling")]
+        vm.enable_contract_profiling();
 
         std::mem::swap(&mut vm.stack_pool, stack_pool);
         let result = vm.execute().map_err(VMError::into);
         std::mem::swap(&mut vm.stack_pool, stack_pool);
+
+        #[cfg(feature = "perf_contract_profiling")]
+        if result.is_ok()
+            && let Some(profile) = vm.contract_profile.take()
+        {
+            ethrex_levm::contract_profiling::record_transaction(profile);
+        }
         result
     }
 
+    /// Runs the validation frames of a RIP-7560 transaction over `db` without keeping its
+    /// changes, as done before accepting it into the mempool.
+    pub fn validate_rip7560_transaction(
+        tx: &Transaction,
+        tx_sender: Address,
+        block_header: &BlockHeader,
+        db: &mut GeneralizedDatabase,
+        vm_type: VMType,
+    ) -> Result<(), EvmError> {
+        let VMType::L2(fee_config) = vm_type else {
+            return Err(VMError::TxValidation(TxValidationError::Rip7560NotSupported).into());
+        };
+        let env = Self::setup_env(tx, tx_sender, block_header, db, vm_type)?;
+        let mut vm = VM::new(env, db, tx, LevmCallTracer::disabled(), vm_type)?;
+
+        simulate_rip7560_validation(&mut vm, fee_config).map_err(VMError::into)
+    }
+
     pub fn undo_last_tx(db: &mut GeneralizedDatabase) -> Result<(), EvmError> {
         db.undo_last_transaction()?;
         Ok(())
@@ -570,6 +727,10 @@ pub fn generic_system_contract_levm(
     } else {
         // If the system account was not in the cache, we need to remove it
         db.current_accounts_state.remove(&system_address);
+        // The system address is only part of the block access list if the block itself uses it
+        if let Some(recorder) = &mut db.access_recorder {
+            recorder.discard_account(system_address);
+        }
     }
 
     if let Some(coinbase_account) = coinbase_backup {
@@ -578,6 +739,9 @@ pub fn generic_system_contract_levm(
     } else {
         // If the coinbase account was not in the cache, we need to remove it
         db.current_accounts_state.remove(&block_header.coinbase);
+        if let Some(recorder) = &mut db.access_recorder {
+            recorder.discard_account(block_header.coinbase);
+        }
     }
 
     Ok(report)
@@ -704,6 +868,7 @@ fn env_from_generic(
         calculate_gas_price_for_generic(tx, header.base_fee_per_gas.unwrap_or(INITIAL_BASE_FEE));
     let block_excess_blob_gas = header.excess_blob_gas.map(U256::from);
     let config = EVMConfig::new_from_chain_config(&chain_config, header);
+    let base_blob_fee_per_gas = get_base_fee_per_blob_gas(block_excess_blob_gas, &config)?;
     Ok(Environment {
         origin: tx.from.0.into(),
         gas_limit: tx
@@ -716,7 +881,7 @@ fn env_from_generic(
         prev_randao: Some(header.prev_randao),
         chain_id: chain_config.chain_id.into(),
         base_fee_per_gas: header.base_fee_per_gas.unwrap_or_default().into(),
-        base_blob_fee_per_gas: get_base_fee_per_blob_gas(block_excess_blob_gas, &config)?,
+        base_blob_fee_per_gas,
         gas_price,
         block_excess_blob_gas,
         block_blob_gas_used: header.blob_gas_used.map(U256::from),
@@ -783,3 +948,535 @@ pub fn get_max_allowed_gas_limit(block_gas_limit: u64, fork: Fork) -> u64 {
         block_gas_limit
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::test_utils::{
+        BASE_FEE, TestDatabase, address_of, call_tx, cancun_config, initial_balance, secret_key,
+        test_block,
+    };
+    use ethrex_common::H256;
+    use ethrex_common::evm::calculate_create_address;
+    use ethrex_common::types::{
+        ChainConfig, RIP7560Transaction,
+        custom_precompiles::{CustomPrecompileConfig, CustomPrecompileKind},
+    };
+    use ethrex_common::utils::keccak;
+    use ethrex_levm::gas_cost::{
+        CALL_COLD_DYNAMIC, CALL_WARM_DYNAMIC, CHUNK_EDIT_COST, CHUNK_FILL_COST, SLOAD_COLD_DYNAMIC,
+        SLOAD_WARM_DYNAMIC, SUBTREE_EDIT_COST, WITNESS_BRANCH_COST, WITNESS_CHUNK_COST,
+    };
+
+    const CONTRACT: Address = Address::repeat_byte(0x10);
+    const CALLEE: Address = Address::repeat_byte(0x20);
+    const SMART_ACCOUNT: Address = Address::repeat_byte(0x50);
+    const PAYMASTER: Address = Address::repeat_byte(0x60);
+    const DEPLOYER: Address = Address::repeat_byte(0x70);
+    const ORACLE: Address = Address::repeat_byte(0x80);
+    const MAPPING: Address = Address::repeat_byte(0x90);
+    const STORAGE_ORACLE_PRECOMPILE: u64 = 0x101;
+
+    // validateTransaction(uint256,bytes32,bytes)
+    const VALIDATE_TRANSACTION: [u8; 4] = [0xbf, 0x45, 0xc1, 0x66];
+    // postPaymasterTransaction(bool,uint256,bytes)
+    const POST_PAYMASTER_TRANSACTION: [u8; 4] = [0x34, 0xa4, 0xa7, 0x7c];
+    // validatePaymasterTransaction(uint256,bytes32,bytes)
+    const VALIDATE_PAYMASTER_TRANSACTION: [u8; 4] = [0xe0, 0xe6, 0x18, 0x3a];
+    /// Context returned by the paymaster validation, a single word
+    const PAYMASTER_CONTEXT: u8 = 0x42;
+
+    /// Gas used by a transaction calling a contract that runs `code`
+    fn gas_used(code: &[u8], stateless_gas: bool) -> u64 {
+        let chain_config = ChainConfig {
+            eip4762_time: stateless_gas.then_some(0),
+            ..cancun_config()
+        };
+        let key = secret_key(1);
+        let sender = address_of(&key);
+        let mut db = TestDatabase::new(chain_config)
+            .with_funded(sender, Bytes::new())
+            .with_funded(CONTRACT, Bytes::copy_from_slice(code))
+            .into_generalized();
+        let tx = call_tx(&key, 0, CONTRACT, U256::zero(), Bytes::new());
+        let block = test_block(vec![tx.clone()]);
+
+        let report = LEVM::execute_tx(&tx, sender, &block.header, &mut db, VMType::L1).unwrap();
+        assert!(report.is_success());
+        report.gas_used
+    }
+
+    /// Gas charged on top of the EIP-2929 costs when EIP-4762 stateless gas costs are enabled
+    fn stateless_overhead(code: &[u8]) -> i128 {
+        i128::from(gas_used(code, true)) - i128::from(gas_used(code, false))
+    }
+
+    #[test]
+    fn stateless_gas_replaces_cold_accesses_with_access_events() {
+        // The header of the contract is in the witness for free, but its first code chunk isn't
+        let first_chunk = i128::from(WITNESS_CHUNK_COST);
+        assert_eq!(stateless_overhead(&[0x00]), first_chunk);
+
+        // SLOAD(0): the slot lives in the header stem, so only its leaf is charged
+        let sload = [0x60, 0x00, 0x54, 0x00];
+        assert_eq!(
+            stateless_overhead(&sload),
+            first_chunk + i128::from(SLOAD_WARM_DYNAMIC + WITNESS_CHUNK_COST)
+                - i128::from(SLOAD_COLD_DYNAMIC)
+        );
+
+        // CALL(0, CALLEE, 0, 0, 0, 0, 0): the basic data of the callee is read
+        let mut call = vec![0x5f; 5];
+        call.push(0x73);
+        call.extend_from_slice(CALLEE.as_bytes());
+        call.extend_from_slice(&[0x5f, 0xf1, 0x00]);
+        assert_eq!(
+            stateless_overhead(&call),
+            first_chunk + i128::from(CALL_WARM_DYNAMIC + WITNESS_BRANCH_COST + WITNESS_CHUNK_COST)
+                - i128::from(CALL_COLD_DYNAMIC)
+        );
+
+        // CREATE(0, 0, 0): the header of the new account is filled
+        let create = [0x5f, 0x5f, 0x5f, 0xf0, 0x00];
+        assert_eq!(
+            stateless_overhead(&create),
+            first_chunk
+                + i128::from(
+                    WITNESS_BRANCH_COST
+                        + SUBTREE_EDIT_COST
+                        + 2 * (WITNESS_CHUNK_COST + CHUNK_EDIT_COST + CHUNK_FILL_COST)
+                )
+        );
+
+        // 40 JUMPDESTs and a STOP run through the first two code chunks
+        let mut jumpdests = vec![0x5b; 40];
+        jumpdests.push(0x00);
+        assert_eq!(stateless_overhead(&jumpdests), 2 * first_chunk);
+    }
+
+    /// Smart account code. When called with calldata it runs `validation` and then returns the
+    /// validation data accepting the transaction, otherwise it does SSTORE(0, 1).
+    fn smart_account_code(validation: &[u8]) -> Vec<u8> {
+        let execution_start = 5 + validation.len() + 14;
+        // if CALLDATASIZE == 0 jump to the execution
+        let mut code = vec![0x36, 0x15, 0x60, execution_start as u8, 0x57];
+        code.extend_from_slice(validation);
+        // MSTORE(0, VALIDATE_TRANSACTION << 224), RETURN(0, 32)
+        code.push(0x63);
+        code.extend_from_slice(&VALIDATE_TRANSACTION);
+        code.extend_from_slice(&[0x60, 0xe0, 0x1b, 0x5f, 0x52, 0x60, 0x20, 0x5f, 0xf3]);
+        assert_eq!(code.len(), execution_start);
+        code.extend_from_slice(&[0x5b, 0x60, 0x01, 0x5f, 0x55, 0x00]);
+        code
+    }
+
+    /// Paymaster code. Its validation accepts the transaction returning [`PAYMASTER_CONTEXT`],
+    /// its post transaction call stores the context it gets back in slot 0 and then runs
+    /// `post_op_end`.
+    fn paymaster_code(post_op_end: &[u8]) -> Bytes {
+        // MSTORE(0, VALIDATE_PAYMASTER_TRANSACTION << 224), MSTORE(32, 64), MSTORE(64, 32),
+        // MSTORE(96, PAYMASTER_CONTEXT), RETURN(0, 128)
+        let mut validation = vec![0x63];
+        validation.extend_from_slice(&VALIDATE_PAYMASTER_TRANSACTION);
+        validation.extend_from_slice(&[
+            0x60,
+            0xe0,
+            0x1b,
+            0x5f,
+            0x52,
+            0x60,
+            0x40,
+            0x60,
+            0x20,
+            0x52,
+            0x60,
+            0x20,
+            0x60,
+            0x40,
+            0x52,
+            0x60,
+            PAYMASTER_CONTEXT,
+            0x60,
+            0x60,
+            0x52,
+            0x60,
+            0x80,
+            0x5f,
+            0xf3,
+        ]);
+        let post_op_start = 14 + validation.len();
+
+        // if CALLDATALOAD(0) >> 224 == POST_PAYMASTER_TRANSACTION jump to the post transaction
+        let mut code = vec![0x5f, 0x35, 0x60, 0xe0, 0x1c, 0x63];
+        code.extend_from_slice(&POST_PAYMASTER_TRANSACTION);
+        code.extend_from_slice(&[0x14, 0x60, post_op_start as u8, 0x57]);
+        code.extend_from_slice(&validation);
+        assert_eq!(code.len(), post_op_start);
+        // SSTORE(0, CALLDATALOAD(4 + 32 * 4)), the first word of the context
+        code.extend_from_slice(&[0x5b, 0x60, 0x84, 0x35, 0x5f, 0x55]);
+        code.extend_from_slice(post_op_end);
+        code.into()
+    }
+
+    /// Factory deploying `code` with CREATE, so at the first address it creates
+    fn deployer_code(code: &[u8]) -> Bytes {
+        // CODECOPY(0, 10, len), RETURN(0, len)
+        let mut initcode = vec![0x60, code.len() as u8, 0x60, 0x0a, 0x5f, 0x39];
+        initcode.extend_from_slice(&[0x60, code.len() as u8, 0x5f, 0xf3]);
+        initcode.extend_from_slice(code);
+        // CODECOPY(0, 13, len), CREATE(0, 0, len)
+        let len = initcode.len() as u8;
+        let mut deployer = vec![0x60, len, 0x60, 0x0d, 0x5f, 0x39];
+        deployer.extend_from_slice(&[0x60, len, 0x5f, 0x5f, 0xf0, 0x50, 0x00]);
+        deployer.extend_from_slice(&initcode);
+        deployer.into()
+    }
+
+    fn rip7560_state() -> TestDatabase {
+        TestDatabase::new(cancun_config())
+            .with_funded(SMART_ACCOUNT, smart_account_code(&[]).into())
+            .with_funded(PAYMASTER, paymaster_code(&[0x00]))
+    }
+
+    fn rip7560_tx(sender: Address, paymaster: Option<Address>) -> Transaction {
+        Transaction::RIP7560Transaction(RIP7560Transaction {
+            chain_id: 1,
+            sender,
+            sender_validation_data: Bytes::from_static(&[0x01]),
+            paymaster,
+            max_priority_fee_per_gas: BASE_FEE,
+            max_fee_per_gas: 2 * BASE_FEE,
+            validation_gas_limit: 200_000,
+            paymaster_validation_gas_limit: 100_000,
+            post_op_gas_limit: 100_000,
+            call_gas_limit: 100_000,
+            ..Default::default()
+        })
+    }
+
+    fn execute_rip7560(
+        db: &mut GeneralizedDatabase,
+        tx: &Transaction,
+        vm_type: VMType,
+    ) -> Result<ExecutionReport, EvmError> {
+        let block = test_block(Vec::new());
+        LEVM::execute_tx(tx, tx.sender().unwrap(), &block.header, db, vm_type)
+    }
+
+    fn l2() -> VMType {
+        VMType::L2(FeeConfig::default())
+    }
+
+    /// Paid for `gas_used` at twice the base fee, the effective gas price of the transactions
+    fn fee(gas_used: u64) -> U256 {
+        U256::from(gas_used) * U256::from(2 * BASE_FEE)
+    }
+
+    fn slot_zero(db: &mut GeneralizedDatabase, address: Address) -> U256 {
+        db.get_storage_value(address, H256::zero()).unwrap()
+    }
+
+    #[test]
+    fn rip7560_transactions_are_only_executed_on_l2() {
+        let mut db = rip7560_state().into_generalized();
+        let error =
+            execute_rip7560(&mut db, &rip7560_tx(SMART_ACCOUNT, None), VMType::L1).unwrap_err();
+        assert!(matches!(
+            error,
+            EvmError::Transaction(message) if message.contains("only supported on L2")
+        ));
+    }
+
+    #[test]
+    fn rip7560_sender_pays_and_is_refunded_the_unused_gas() {
+        let mut db = rip7560_state().into_generalized();
+        let report = execute_rip7560(&mut db, &rip7560_tx(SMART_ACCOUNT, None), l2()).unwrap();
+        assert!(report.is_success());
+
+        let sender = db.get_account(SMART_ACCOUNT).unwrap().info.clone();
+        assert_eq!(sender.nonce, 1);
+        assert_eq!(sender.balance, initial_balance() - fee(report.gas_used));
+        assert_eq!(slot_zero(&mut db, SMART_ACCOUNT), U256::one());
+    }
+
+    #[test]
+    fn rip7560_sender_validation_failure_invalidates_the_transaction() {
+        // REVERT(0, 0) before returning the validation data
+        let state = TestDatabase::new(cancun_config()).with_funded(
+            SMART_ACCOUNT,
+            smart_account_code(&[0x5f, 0x5f, 0xfd]).into(),
+        );
+        let mut db = state.into_generalized();
+        let error = execute_rip7560(&mut db, &rip7560_tx(SMART_ACCOUNT, None), l2()).unwrap_err();
+        assert!(matches!(
+            error,
+            EvmError::Transaction(message) if message.contains("sender validation reverted")
+        ));
+
+        // Nothing was charged
+        let sender = db.get_account(SMART_ACCOUNT).unwrap().info.clone();
+        assert_eq!((sender.nonce, sender.balance), (0, initial_balance()));
+    }
+
+    #[test]
+    fn rip7560_paymaster_pays_and_gets_its_context_back() {
+        let mut db = rip7560_state().into_generalized();
+        let tx = rip7560_tx(SMART_ACCOUNT, Some(PAYMASTER));
+        let report = execute_rip7560(&mut db, &tx, l2()).unwrap();
+        assert!(report.is_success());
+
+        assert_eq!(slot_zero(&mut db, SMART_ACCOUNT), U256::one());
+        assert_eq!(slot_zero(&mut db, PAYMASTER), U256::from(PAYMASTER_CONTEXT));
+        let paymaster_balance = db.get_account(PAYMASTER).unwrap().info.balance;
+        assert_eq!(paymaster_balance, initial_balance() - fee(report.gas_used));
+        let sender_balance = db.get_account(SMART_ACCOUNT).unwrap().info.balance;
+        assert_eq!(sender_balance, initial_balance());
+    }
+
+    #[test]
+    fn rip7560_reverted_post_transaction_undoes_the_execution() {
+        // The post transaction call ends with REVERT(0, 0)
+        let state = TestDatabase::new(cancun_config())
+            .with_funded(SMART_ACCOUNT, smart_account_code(&[]).into())
+            .with_funded(PAYMASTER, paymaster_code(&[0x5f, 0x5f, 0xfd]));
+        let mut db = state.into_generalized();
+        let tx = rip7560_tx(SMART_ACCOUNT, Some(PAYMASTER));
+        let report = execute_rip7560(&mut db, &tx, l2()).unwrap();
+        assert!(!report.is_success());
+
+        assert_eq!(slot_zero(&mut db, SMART_ACCOUNT), U256::zero());
+        assert_eq!(slot_zero(&mut db, PAYMASTER), U256::zero());
+        // The validation is kept and the paymaster still pays
+        assert_eq!(db.get_account(SMART_ACCOUNT).unwrap().info.nonce, 1);
+        let paymaster_balance = db.get_account(PAYMASTER).unwrap().info.balance;
+        assert_eq!(paymaster_balance, initial_balance() - fee(report.gas_used));
+    }
+
+    #[test]
+    fn rip7560_deployer_frame_deploys_the_sender() {
+        let code = smart_account_code(&[]);
+        let sender = calculate_create_address(DEPLOYER, 0);
+        let state = rip7560_state().with_funded(DEPLOYER, deployer_code(&code));
+        let mut db = state.into_generalized();
+        let Transaction::RIP7560Transaction(mut tx) = rip7560_tx(sender, Some(PAYMASTER)) else {
+            unreachable!();
+        };
+        tx.deployer = Some(DEPLOYER);
+        let tx = Transaction::RIP7560Transaction(tx);
+
+        let report = execute_rip7560(&mut db, &tx, l2()).unwrap();
+        assert!(report.is_success());
+        assert_eq!(
+            db.get_account(sender).unwrap().info.code_hash,
+            keccak(&code)
+        );
+        assert_eq!(slot_zero(&mut db, sender), U256::one());
+
+        // Without a deployer, the sender has to exist already
+        let mut db = rip7560_state().into_generalized();
+        let error =
+            execute_rip7560(&mut db, &rip7560_tx(sender, Some(PAYMASTER)), l2()).unwrap_err();
+        assert!(matches!(
+            error,
+            EvmError::Transaction(message) if message.contains("sender is not deployed")
+        ));
+    }
+
+    #[test]
+    fn rip7560_simulation_rejects_restricted_opcodes() {
+        // TIMESTAMP, POP before returning the validation data
+        let state = TestDatabase::new(cancun_config())
+            .with_funded(SMART_ACCOUNT, smart_account_code(&[0x42, 0x50]).into());
+        let tx = rip7560_tx(SMART_ACCOUNT, None);
+        let header = test_block(Vec::new()).header;
+
+        let mut db = state.clone().into_generalized();
+        let error = LEVM::validate_rip7560_transaction(&tx, SMART_ACCOUNT, &header, &mut db, l2())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            EvmError::Transaction(message) if message.contains("restricted opcode 0x42")
+        ));
+        // The simulation doesn't change the state
+        let sender = db.get_account(SMART_ACCOUNT).unwrap().info.clone();
+        assert_eq!((sender.nonce, sender.balance), (0, initial_balance()));
+        LEVM::validate_rip7560_transaction(
+            &rip7560_tx(SMART_ACCOUNT, None),
+            SMART_ACCOUNT,
+            &header,
+            &mut rip7560_state().into_generalized(),
+            l2(),
+        )
+        .unwrap();
+
+        // Once included, the opcodes are allowed
+        let mut db = state.into_generalized();
+        assert!(execute_rip7560(&mut db, &tx, l2()).unwrap().is_success());
+    }
+
+    /// Simulates the validation of a sender that reads its slot 1, and then calls [`MAPPING`] to
+    /// read the value of the mapping at its slot 0 with the key pushed by `push_key`
+    fn simulate_mapping_read(push_key: &[u8]) -> Result<(), EvmError> {
+        // CALLDATACOPY(0, 0, 64), SLOAD(KECCAK256(0, 64))
+        let mapping_code = [
+            0x60, 0x40, 0x5f, 0x5f, 0x37, 0x60, 0x40, 0x5f, 0x20, 0x54, 0x00,
+        ];
+        // SLOAD(1), MSTORE(0, key), CALL(GAS, MAPPING, 0, 0, 64, 0, 0)
+        let mut validation = vec![0x60, 0x01, 0x54, 0x50];
+        validation.extend_from_slice(push_key);
+        validation.extend_from_slice(&[0x5f, 0x52, 0x5f, 0x5f, 0x60, 0x40, 0x5f, 0x5f, 0x73]);
+        validation.extend_from_slice(MAPPING.as_bytes());
+        validation.extend_from_slice(&[0x5a, 0xf1, 0x50]);
+
+        let mut db = TestDatabase::new(cancun_config())
+            .with_funded(SMART_ACCOUNT, smart_account_code(&validation).into())
+            .with_funded(MAPPING, Bytes::copy_from_slice(&mapping_code))
+            .into_generalized();
+        let header = test_block(Vec::new()).header;
+        let tx = rip7560_tx(SMART_ACCOUNT, None);
+        LEVM::validate_rip7560_transaction(&tx, SMART_ACCOUNT, &header, &mut db, l2())
+    }
+
+    #[test]
+    fn rip7560_simulation_only_allows_storage_associated_with_the_sender() {
+        // Keyed by the sender, with ADDRESS
+        simulate_mapping_read(&[0x30]).unwrap();
+
+        // Keyed by anything else, the slot is shared with other senders
+        let error = simulate_mapping_read(&[0x60, 0x01]).unwrap_err();
+        assert!(matches!(
+            error,
+            EvmError::Transaction(message) if message.contains("not associated with the sender")
+        ));
+    }
+
+    /// Chain exposing the storage of `ORACLE` through a custom precompile
+    fn oracle_chain_config(activation_time: u64) -> ChainConfig {
+        let precompile = CustomPrecompileConfig {
+            address: Address::from_low_u64_be(STORAGE_ORACLE_PRECOMPILE),
+            kind: CustomPrecompileKind::StorageOracle { contract: ORACLE },
+            base_gas: 2100,
+            word_gas: 10,
+            activation_time,
+        };
+        ChainConfig {
+            custom_precompiles: vec![precompile].try_into().unwrap(),
+            ..cancun_config()
+        }
+    }
+
+    /// Calls the storage oracle precompile with `key`, returning the first word of the output and
+    /// the gas spent by the call
+    fn call_storage_oracle(chain_config: ChainConfig, key: H256, vm_type: VMType) -> (U256, u64) {
+        // CALLDATACOPY(0, 0, CALLDATASIZE), STATICCALL(GAS, precompile, 0, CALLDATASIZE, 0, 32)
+        // between two GAS, then SSTORE(1, gas spent), SSTORE(0, MLOAD(0))
+        let mut code = vec![
+            0x36, 0x5f, 0x5f, 0x37, 0x5a, 0x60, 0x20, 0x5f, 0x36, 0x5f, 0x73,
+        ];
+        code.extend_from_slice(Address::from_low_u64_be(STORAGE_ORACLE_PRECOMPILE).as_bytes());
+        code.extend_from_slice(&[0x5a, 0xfa, 0x50, 0x5a, 0x90, 0x03, 0x60, 0x01, 0x55]);
+        code.extend_from_slice(&[0x5f, 0x51, 0x5f, 0x55, 0x00]);
+
+        let secret = secret_key(1);
+        let sender = address_of(&secret);
+        let mut db = TestDatabase::new(chain_config)
+            .with_funded(sender, Bytes::new())
+            .with_funded(CONTRACT, code.into())
+            .with_storage(ORACLE, key, U256::from(42))
+            .into_generalized();
+        let calldata = Bytes::copy_from_slice(key.as_bytes());
+        let tx = call_tx(&secret, 0, CONTRACT, U256::zero(), calldata);
+        let block = test_block(vec![tx.clone()]);
+
+        let report = LEVM::execute_tx(&tx, sender, &block.header, &mut db, vm_type).unwrap();
+        assert!(report.is_success());
+        let gas_spent = db
+            .get_storage_value(CONTRACT, H256::from_low_u64_be(1))
+            .unwrap();
+        (slot_zero(&mut db, CONTRACT), gas_spent.as_u64())
+    }
+
+    #[test]
+    fn custom_precompiles_are_only_reachable_on_l2() {
+        let timestamp = test_block(Vec::new()).header.timestamp;
+        let key = H256::from_low_u64_be(7);
+        let precompile_gas = 2100 + 10;
+
+        let (l1_output, l1_gas) =
+            call_storage_oracle(oracle_chain_config(timestamp), key, VMType::L1);
+        let (l2_output, l2_gas) = call_storage_oracle(oracle_chain_config(timestamp), key, l2());
+        // On L1 the address is an empty account, so the input is left in memory
+        assert_eq!(l1_output, U256::from_big_endian(key.as_bytes()));
+        assert_eq!(l2_output, U256::from(42));
+        // Custom precompiles are warm from the start of L2 transactions
+        assert_eq!(
+            l2_gas + CALL_COLD_DYNAMIC,
+            l1_gas + CALL_WARM_DYNAMIC + precompile_gas
+        );
+
+        // Before its activation time, the address is an empty account on L2 too
+        let inactive = call_storage_oracle(oracle_chain_config(timestamp + 1), key, l2());
+        assert_eq!(inactive, (l1_output, l1_gas));
+
+        // The guest program gets the chain config through the rkyv encoded execution witness
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&oracle_chain_config(timestamp)).unwrap();
+        let guest_config = rkyv::from_bytes::<ChainConfig, rkyv::rancor::Error>(&bytes).unwrap();
+        assert_eq!(
+            call_storage_oracle(guest_config, key, l2()),
+            (l2_output, l2_gas)
+        );
+    }
+
+    #[cfg(feature = "perf_contract_profiling")]
+    #[test]
+    fn profiles_the_contracts_of_nested_calls() {
+        // CALL(GAS, CALLEE, 0, 0, 0, 0, 0), SLOAD(0)
+        let mut outer = vec![0x5f; 5];
+        outer.push(0x73);
+        outer.extend_from_slice(CALLEE.as_bytes());
+        outer.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x5f, 0x54, 0x00]);
+        // SSTORE(0, 1)
+        let inner = [0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+
+        let key = secret_key(1);
+        let sender = address_of(&key);
+        let mut db = TestDatabase::new(cancun_config())
+            .with_funded(sender, Bytes::new())
+            .with_funded(CONTRACT, outer.into())
+            .with_funded(CALLEE, Bytes::copy_from_slice(&inner))
+            .into_generalized();
+        let tx = call_tx(&key, 0, CONTRACT, U256::zero(), Bytes::new());
+        let block = test_block(vec![tx.clone()]);
+
+        let env = LEVM::setup_env(&tx, sender, &block.header, &db, VMType::L1).unwrap();
+        let mut vm = VM::new(env, &mut db, &tx, LevmCallTracer::disabled(), VMType::L1).unwrap();
+        vm.enable_contract_profiling();
+        assert!(vm.execute().unwrap().is_success());
+        let profile = vm.contract_profile.take().unwrap();
+
+        let report = profile.report();
+        assert_eq!(report.txs, 1);
+        let entry = |address: Address| {
+            let contract = format!("{address:#x}::fallback");
+            report
+                .contracts
+                .iter()
+                .find(|entry| entry.contract == contract)
+                .unwrap()
+        };
+        let (outer, inner) = (entry(CONTRACT), entry(CALLEE));
+        assert_eq!((outer.calls, outer.sloads, outer.sstores), (1, 1, 0));
+        assert_eq!((inner.calls, inner.sloads, inner.sstores), (1, 0, 1));
+        // PUSH1, PUSH1 and a cold SSTORE setting a slot, none of it counted as the caller's
+        assert_eq!(inner.gas_used, 3 + 3 + 22100);
+        assert_eq!(
+            profile
+                .folded_stacks(ethrex_levm::contract_profiling::ProfileWeight::Gas)
+                .lines()
+                .last(),
+            Some(format!("{CONTRACT:#x}::fallback;{CALLEE:#x}::fallback 22106").as_str())
+        );
+    }
+}
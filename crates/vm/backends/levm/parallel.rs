@@ -0,0 +1,699 @@
+//! Optimistic parallel execution of the transactions of a block.
+//!
+//! Every transaction is first executed speculatively, in parallel, against the state left by the
+//! block's system calls while recording every account and storage slot it reads. Transactions
+//! are then committed in block order: if everything a transaction read still matches the state
+//! produced by the transactions before it, its writes are merged into the block state as they
+//! are, otherwise it is re-executed on top of that state. This yields exactly the same receipts
+//! and state transitions as sequential execution.
+//!
+//! The priority fee paid to the coinbase would make every transaction conflict with the previous
+//! one, so when the coinbase is only touched by the fee payment its balance change is applied as
+//! a delta instead of being validated.
+//!
+//! When the block access list or the EIP-4762 access events are being recorded, speculative
+//! executions record their own, and the accesses of committed speculations are replayed on the
+//! block state before their writes are merged, so the block recorder sees the same accesses and
+//! changes it would have seen with sequential execution.
+
+use super::{LEVM, StateFlusher};
+use crate::EvmError;
+use ethrex_common::{
+    Address, H256, U256,
+    constants::EMPTY_TRIE_HASH,
+    types::{AccountState, Block, ChainConfig, Code, Receipt, Transaction},
+};
+use ethrex_levm::{
+    access_events::AccessEvents,
+    account::AccountStatus,
+    call_frame::Stack,
+    constants::STACK_LIMIT,
+    db::{
+        Database,
+        gen_db::{CacheDB, GeneralizedDatabase},
+    },
+    errors::{ContextResult, DatabaseError, ExecutionReport, TxResult, VMError},
+    hooks::hook::Hook,
+    tracing::LevmCallTracer,
+    vm::{VM, VMType},
+};
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+/// Storage root reported for cached accounts that have storage.
+/// LEVM only uses the storage root to know whether an account has storage, see `LevmAccount::has_storage`.
+const NON_EMPTY_STORAGE_ROOT: H256 = H256([0xff; 32]);
+
+/// Read-only view of the block state right before its first transaction is executed
+struct BlockSnapshot {
+    store: Arc<dyn Database>,
+    accounts: CacheDB,
+}
+
+impl Database for BlockSnapshot {
+    fn get_account_state(&self, address: Address) -> Result<AccountState, DatabaseError> {
+        let Some(account) = self.accounts.get(&address) else {
+            return self.store.get_account_state(address);
+        };
+        Ok(AccountState {
+            nonce: account.info.nonce,
+            balance: account.info.balance,
+            code_hash: account.info.code_hash,
+            storage_root: if account.has_storage {
+                NON_EMPTY_STORAGE_ROOT
+            } else {
+                *EMPTY_TRIE_HASH
+            },
+        })
+    }
+
+    fn get_storage_value(&self, address: Address, key: H256) -> Result<U256, DatabaseError> {
+        if let Some(account) = self.accounts.get(&address) {
+            if let Some(value) = account.storage.get(&key) {
+                return Ok(*value);
+            }
+            if account.status == AccountStatus::DestroyedModified {
+                return Ok(U256::zero());
+            }
+        }
+        self.store.get_storage_value(address, key)
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<H256, DatabaseError> {
+        self.store.get_block_hash(block_number)
+    }
+
+    fn get_chain_config(&self) -> Result<ChainConfig, DatabaseError> {
+        self.store.get_chain_config()
+    }
+
+    fn get_account_code(&self, code_hash: H256) -> Result<Code, DatabaseError> {
+        // Code is addressed by its hash so it can never be stale
+        self.store.get_account_code(code_hash)
+    }
+}
+
+/// Accounts and storage slots read by a transaction, with the values it observed
+#[derive(Default)]
+struct ReadSet {
+    accounts: HashMap<Address, AccountState>,
+    storage: HashMap<(Address, H256), U256>,
+}
+
+/// Database used for speculative execution, it records every read that reaches the snapshot.
+/// `GeneralizedDatabase` caches what it reads, so this is the first read of each item.
+struct RecordingDatabase {
+    snapshot: Arc<BlockSnapshot>,
+    reads: Mutex<ReadSet>,
+}
+
+impl RecordingDatabase {
+    fn take_reads(&self) -> Result<ReadSet, EvmError> {
+        let mut reads = self
+            .reads
+            .lock()
+            .map_err(|_| EvmError::Custom("Read set lock was poisoned".to_string()))?;
+        Ok(std::mem::take(&mut *reads))
+    }
+
+    fn record<F: FnOnce(&mut ReadSet)>(&self, f: F) -> Result<(), DatabaseError> {
+        let mut reads = self
+            .reads
+            .lock()
+            .map_err(|_| DatabaseError::Custom("Read set lock was poisoned".to_string()))?;
+        f(&mut reads);
+        Ok(())
+    }
+}
+
+impl Database for RecordingDatabase {
+    fn get_account_state(&self, address: Address) -> Result<AccountState, DatabaseError> {
+        let state = self.snapshot.get_account_state(address)?;
+        self.record(|reads| {
+            reads.accounts.insert(address, state.clone());
+        })?;
+        Ok(state)
+    }
+
+    fn get_storage_value(&self, address: Address, key: H256) -> Result<U256, DatabaseError> {
+        let value = self.snapshot.get_storage_value(address, key)?;
+        self.record(|reads| {
+            reads.storage.insert((address, key), value);
+        })?;
+        Ok(value)
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<H256, DatabaseError> {
+        self.snapshot.get_block_hash(block_number)
+    }
+
+    fn get_chain_config(&self) -> Result<ChainConfig, DatabaseError> {
+        self.snapshot.get_chain_config()
+    }
+
+    fn get_account_code(&self, code_hash: H256) -> Result<Code, DatabaseError> {
+        self.snapshot.get_account_code(code_hash)
+    }
+}
+
+/// Runs before the default hook's finalization and checks whether the coinbase was accessed
+/// before the priority fee is paid to it.
+#[derive(Default)]
+struct CoinbaseObserver {
+    observed: bool,
+}
+
+impl Hook for CoinbaseObserver {
+    fn prepare_execution(&mut self, _vm: &mut VM<'_>) -> Result<(), VMError> {
+        Ok(())
+    }
+
+    fn finalize_execution(
+        &mut self,
+        vm: &mut VM<'_>,
+        _ctx_result: &mut ContextResult,
+    ) -> Result<(), VMError> {
+        self.observed = vm.db.current_accounts_state.contains_key(&vm.env.coinbase);
+        Ok(())
+    }
+}
+
+/// Result of executing a transaction against the block snapshot
+struct Speculation {
+    report: ExecutionReport,
+    reads: ReadSet,
+    accounts: CacheDB,
+    codes: FxHashMap<H256, Code>,
+    /// True if the coinbase was only touched to pay it the priority fee
+    coinbase_fee_only: bool,
+    /// Accounts and storage slots accessed, if the block access list is being recorded
+    accesses: Option<(FxHashSet<Address>, FxHashSet<(Address, H256)>)>,
+    /// EIP-4762 access events, if they are being recorded
+    access_events: Option<AccessEvents>,
+    /// Only added to the global profile if the speculation is committed
+    #[cfg(feature = "perf_contract_profiling")]
+    contract_profile: Option<ethrex_levm::contract_profiling::ContractProfile>,
+}
+
+/// What speculative executions have to record, as the block state does
+#[derive(Clone, Copy)]
+struct Recording {
+    accesses: bool,
+    access_events: bool,
+}
+
+impl LEVM {
+    /// Executes the transactions of the block optimistically in parallel and returns their receipts.
+    /// `db` must already contain the changes of the block's system calls, it ends up containing
+    /// the changes of every transaction, exactly as if they were executed sequentially.
+    /// If a `flusher` is given, state transitions are sent to the merkleizer as transactions are committed.
+    pub(crate) fn execute_transactions_parallel(
+        block: &Block,
+        db: &mut GeneralizedDatabase,
+        vm_type: VMType,
+        mut flusher: Option<&mut StateFlusher<'_>>,
+    ) -> Result<Vec<Receipt>, EvmError> {
+        let transactions = block.body.get_transactions_with_sender().map_err(|error| {
+            EvmError::Transaction(format!("Couldn't recover addresses with error: {error}"))
+        })?;
+
+        let snapshot = Arc::new(BlockSnapshot {
+            store: db.store.clone(),
+            accounts: db.current_accounts_state.clone(),
+        });
+        let recording = Recording {
+            accesses: db.access_recorder.is_some(),
+            access_events: db.access_events.is_some(),
+        };
+
+        let speculations: Vec<Option<Speculation>> = transactions
+            .par_iter()
+            .map(|(tx, tx_sender)| {
+                Self::speculate_tx(tx, *tx_sender, block, snapshot.clone(), vm_type, recording)
+                    // Failed speculative executions are re-executed over the actual state
+                    .ok()
+            })
+            .collect();
+
+        let coinbase = block.header.coinbase;
+        let mut shared_stack_pool: Vec<Stack> = Vec::with_capacity(STACK_LIMIT);
+        let mut receipts = Vec::with_capacity(transactions.len());
+        let mut cumulative_gas_used = 0;
+        let mut reexecuted = 0;
+
+        for ((tx, tx_sender), speculation) in transactions.into_iter().zip(speculations) {
+            if cumulative_gas_used + tx.gas_limit() > block.header.gas_limit {
+                return Err(EvmError::Transaction(format!(
+                    "Gas allowance exceeded. Block gas limit {} can be surpassed by executing transaction with gas limit {}",
+                    block.header.gas_limit,
+                    tx.gas_limit()
+                )));
+            }
+
+            let report = match speculation {
+                Some(speculation) if reads_are_valid(db, &speculation, coinbase)? => {
+                    commit_speculation(db, speculation, coinbase)?
+                }
+                _ => {
+                    reexecuted += 1;
+                    // Validation may have accessed items the transaction won't access this time
+                    db.discard_block_access_index();
+                    Self::execute_tx_in_block(
+                        tx,
+                        tx_sender,
+                        &block.header,
+                        db,
+                        vm_type,
+                        &mut shared_stack_pool,
+                    )?
+                }
+            };
+            db.next_block_access_index()?;
+            if let Some(flusher) = flusher.as_mut() {
+                flusher.tx_executed(db)?;
+            }
+
+            cumulative_gas_used += report.gas_used;
+            receipts.push(Receipt::new(
+                tx.tx_type(),
+                matches!(report.result, TxResult::Success),
+                cumulative_gas_used,
+                report.logs,
+            ));
+        }
+
+        ::tracing::debug!(
+            block_number = block.header.number,
+            transactions = receipts.len(),
+            reexecuted,
+            "Executed block transactions in parallel"
+        );
+
+        Ok(receipts)
+    }
+
+    fn speculate_tx(
+        tx: &Transaction,
+        tx_sender: Address,
+        block: &Block,
+        snapshot: Arc<BlockSnapshot>,
+        vm_type: VMType,
+        recording: Recording,
+    ) -> Result<Speculation, EvmError> {
+        let recorder = Arc::new(RecordingDatabase {
+            snapshot,
+            reads: Mutex::new(ReadSet::default()),
+        });
+        let mut db = GeneralizedDatabase::new(recorder.clone());
+        if recording.accesses {
+            db.enable_access_recording();
+        }
+        if recording.access_events {
+            db.enable_access_events_recording();
+        }
+        let observer = Rc::new(RefCell::new(CoinbaseObserver::default()));
+
+        let env = Self::setup_env(tx, tx_sender, &block.header, &db, vm_type)?;
+        let mut vm = VM::new(env, &mut db, tx, LevmCallTracer::disabled(), vm_type)?;
+        // The observer must run before the default hook pays the coinbase
+        let observer_hook: Rc<RefCell<dyn Hook>> = observer.clone();
+        vm.hooks.insert(0, observer_hook);
+        #[cfg(feature = "perf_contract_profiling")]
+        vm.enable_contract_profiling();
+        let report = vm.execute()?;
+        #[cfg(feature = "perf_contract_profiling")]
+        let contract_profile = vm.contract_profile.take();
+        drop(vm);
+
+        let coinbase_fee_only = !observer.borrow().observed;
+        Ok(Speculation {
+            report,
+            reads: recorder.take_reads()?,
+            accounts: std::mem::take(&mut db.current_accounts_state),
+            codes: std::mem::take(&mut db.codes),
+            coinbase_fee_only,
+            accesses: db
+                .access_recorder
+                .as_mut()
+                .map(|recorder| recorder.take_pending()),
+            access_events: db.access_events.take(),
+            #[cfg(feature = "perf_contract_profiling")]
+            contract_profile,
+        })
+    }
+}
+
+/// Checks that every value read during speculative execution matches the current block state
+fn reads_are_valid(
+    db: &mut GeneralizedDatabase,
+    speculation: &Speculation,
+    coinbase: Address,
+) -> Result<bool, EvmError> {
+    for (address, state) in &speculation.reads.accounts {
+        if *address == coinbase && speculation.coinbase_fee_only {
+            continue;
+        }
+        let account = db.get_account(*address)?;
+        let matches = !matches!(
+            account.status,
+            AccountStatus::Destroyed | AccountStatus::DestroyedModified
+        ) && account.info.nonce == state.nonce
+            && account.info.balance == state.balance
+            && account.info.code_hash == state.code_hash
+            && account.has_storage == (state.storage_root != *EMPTY_TRIE_HASH);
+        if !matches {
+            return Ok(false);
+        }
+    }
+
+    for ((address, key), value) in &speculation.reads.storage {
+        if db.get_storage_value(*address, *key)? != *value {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Merges the writes of a validated speculative execution into the block state
+fn commit_speculation(
+    db: &mut GeneralizedDatabase,
+    speculation: Speculation,
+    coinbase: Address,
+) -> Result<ExecutionReport, EvmError> {
+    let Speculation {
+        report,
+        reads,
+        accounts,
+        codes,
+        coinbase_fee_only,
+        accesses,
+        access_events,
+        #[cfg(feature = "perf_contract_profiling")]
+        contract_profile,
+    } = speculation;
+
+    #[cfg(feature = "perf_contract_profiling")]
+    if let Some(profile) = contract_profile {
+        ethrex_levm::contract_profiling::record_transaction(profile);
+    }
+
+    // Accesses are replayed before merging, so the block recorder sees the values from before
+    // the transaction as the committed ones
+    if let Some((accessed_accounts, accessed_slots)) = accesses {
+        for address in accessed_accounts {
+            db.get_account(address)?;
+        }
+        for (address, key) in accessed_slots {
+            db.get_storage_value(address, key)?;
+        }
+    }
+    if let Some(access_events) = &access_events {
+        db.record_access_events(access_events);
+    }
+
+    for (address, account) in accounts {
+        if account.is_unmodified() {
+            continue;
+        }
+
+        if address == coinbase && coinbase_fee_only {
+            let initial_balance = reads
+                .accounts
+                .get(&address)
+                .map(|state| state.balance)
+                .unwrap_or_default();
+            let fee = account
+                .info
+                .balance
+                .checked_sub(initial_balance)
+                .ok_or_else(|| EvmError::Custom("Coinbase balance decreased".to_string()))?;
+            let current = db.get_account_mut(address)?;
+            current.info.balance = current
+                .info
+                .balance
+                .checked_add(fee)
+                .ok_or_else(|| EvmError::Custom("Coinbase balance overflow".to_string()))?;
+            continue;
+        }
+
+        let current = db.get_account_mut(address)?;
+        match account.status {
+            // The account's storage was wiped, so its whole state is the one left by the transaction
+            AccountStatus::Destroyed | AccountStatus::DestroyedModified => *current = account,
+            _ => {
+                current.info = account.info;
+                current.storage.extend(account.storage);
+            }
+        }
+    }
+
+    for (hash, code) in codes {
+        db.codes.entry(hash).or_insert(code);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::backends::levm::prefetch::PrefetchMode;
+    use crate::system_contracts::PRAGUE_SYSTEM_CONTRACTS;
+    use crate::test_utils::{
+        TestDatabase, address_of, call_tx, cancun_config, secret_key, test_block,
+    };
+    use bytes::Bytes;
+    use ethrex_common::types::AccountUpdate;
+    use std::sync::{atomic::AtomicUsize, mpsc};
+
+    const STORE_CALLDATA: Address = Address::repeat_byte(0x10);
+    const STORE_BALANCE: Address = Address::repeat_byte(0x20);
+    const RECIPIENT: Address = Address::repeat_byte(0x30);
+
+    /// SSTORE(0, CALLDATALOAD(0))
+    fn store_calldata_code() -> Bytes {
+        Bytes::from_static(&[0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x00])
+    }
+
+    /// SSTORE(0, BALANCE(RECIPIENT))
+    fn store_balance_code() -> Bytes {
+        let mut code = vec![0x73];
+        code.extend_from_slice(RECIPIENT.as_bytes());
+        code.extend_from_slice(&[0x31, 0x60, 0x00, 0x55, 0x00]);
+        code.into()
+    }
+
+    fn state(senders: u8) -> TestDatabase {
+        (1..=senders)
+            .fold(TestDatabase::new(cancun_config()), |db, seed| {
+                db.with_funded(address_of(&secret_key(seed)), Bytes::new())
+            })
+            .with_funded(STORE_CALLDATA, store_calldata_code())
+            .with_funded(STORE_BALANCE, store_balance_code())
+    }
+
+    fn sorted_transitions(db: &mut GeneralizedDatabase) -> Vec<AccountUpdate> {
+        let mut updates = LEVM::get_state_transitions(db).unwrap();
+        updates.sort_by_key(|update| update.address);
+        updates
+    }
+
+    /// Executes the block in parallel, checks it produces the same receipts and state as sequential
+    /// execution and returns the resulting state
+    fn execute_and_compare(state: TestDatabase, block: &Block) -> GeneralizedDatabase {
+        let mut sequential = state.clone().into_generalized();
+        let expected = LEVM::execute_block(block, &mut sequential, VMType::L1, false).unwrap();
+        let mut parallel = state.into_generalized();
+        let result = LEVM::execute_block(block, &mut parallel, VMType::L1, true).unwrap();
+        assert_eq!(result.receipts, expected.receipts);
+        assert_eq!(
+            sorted_transitions(&mut parallel),
+            sorted_transitions(&mut sequential)
+        );
+        parallel
+    }
+
+    /// Speculates the last transaction of the block and validates what it read against the state
+    /// left by executing the transactions before it
+    fn last_speculation_is_valid(state: TestDatabase, block: &Block) -> bool {
+        let mut db = state.into_generalized();
+        let transactions = block.body.get_transactions_with_sender().unwrap();
+        let snapshot = Arc::new(BlockSnapshot {
+            store: db.store.clone(),
+            accounts: db.current_accounts_state.clone(),
+        });
+        let ((last, last_sender), previous) = transactions.split_last().unwrap();
+        let recording = Recording {
+            accesses: false,
+            access_events: false,
+        };
+        let speculation =
+            LEVM::speculate_tx(last, *last_sender, block, snapshot, VMType::L1, recording).unwrap();
+        for (tx, tx_sender) in previous {
+            LEVM::execute_tx(tx, *tx_sender, &block.header, &mut db, VMType::L1).unwrap();
+        }
+        reads_are_valid(&mut db, &speculation, block.header.coinbase).unwrap()
+    }
+
+    fn store_tx(seed: u8, value: u64) -> Transaction {
+        let mut data = [0u8; 32];
+        data[24..].copy_from_slice(&value.to_be_bytes());
+        call_tx(
+            &secret_key(seed),
+            0,
+            STORE_CALLDATA,
+            U256::zero(),
+            Bytes::copy_from_slice(&data),
+        )
+    }
+
+    fn transfer_tx(seed: u8, to: Address, value: u64) -> Transaction {
+        call_tx(&secret_key(seed), 0, to, value.into(), Bytes::new())
+    }
+
+    #[test]
+    fn writes_to_the_same_slot_conflict() {
+        let block = test_block(vec![store_tx(1, 7), store_tx(2, 9)]);
+        assert!(!last_speculation_is_valid(state(2), &block));
+
+        let mut db = execute_and_compare(state(2), &block);
+        assert_eq!(
+            db.get_storage_value(STORE_CALLDATA, H256::zero()).unwrap(),
+            U256::from(9)
+        );
+    }
+
+    #[test]
+    fn balance_read_after_write_conflicts() {
+        let block = test_block(vec![
+            transfer_tx(1, RECIPIENT, 1000),
+            call_tx(&secret_key(2), 0, STORE_BALANCE, U256::zero(), Bytes::new()),
+        ]);
+        assert!(!last_speculation_is_valid(state(2), &block));
+
+        let mut db = execute_and_compare(state(2), &block);
+        assert_eq!(
+            db.get_storage_value(STORE_BALANCE, H256::zero()).unwrap(),
+            U256::from(1000)
+        );
+    }
+
+    #[test]
+    fn independent_transactions_dont_conflict() {
+        // Both pay the priority fee to the coinbase, which is applied as a delta
+        let block = test_block(vec![
+            transfer_tx(1, RECIPIENT, 1000),
+            transfer_tx(2, Address::repeat_byte(0x40), 1000),
+        ]);
+        assert!(last_speculation_is_valid(state(2), &block));
+        execute_and_compare(state(2), &block);
+    }
+
+    #[test]
+    fn parallel_execution_records_the_block_access_list() {
+        let state = TestDatabase {
+            chain_config: ChainConfig {
+                prague_time: Some(0),
+                osaka_time: Some(0),
+                amsterdam_time: Some(0),
+                ..cancun_config()
+            },
+            ..state(3)
+        };
+        // The request system contracts only need to have code
+        let state = PRAGUE_SYSTEM_CONTRACTS
+            .iter()
+            .fold(state, |state, contract| {
+                state.with_funded(contract.address, Bytes::from_static(&[0x00]))
+            });
+        // The second transaction conflicts with the first one and is re-executed
+        let block = test_block(vec![
+            store_tx(1, 7),
+            store_tx(2, 9),
+            transfer_tx(3, RECIPIENT, 1000),
+        ]);
+
+        let mut sequential = state.clone().into_generalized();
+        let expected = LEVM::execute_block(&block, &mut sequential, VMType::L1, false).unwrap();
+        let mut parallel = state.into_generalized();
+        let result = LEVM::execute_block(&block, &mut parallel, VMType::L1, true).unwrap();
+        assert_eq!(result.receipts, expected.receipts);
+
+        let block_access_list = result.block_access_list.unwrap();
+        assert_eq!(
+            Some(&block_access_list),
+            expected.block_access_list.as_ref()
+        );
+        let store_calldata = block_access_list
+            .0
+            .iter()
+            .find(|account| account.address == STORE_CALLDATA)
+            .unwrap();
+        let slot_changes = store_calldata.storage_changes.first().unwrap();
+        assert_eq!(
+            slot_changes
+                .changes
+                .iter()
+                .map(|change| (change.block_access_index, change.post_value))
+                .collect::<Vec<_>>(),
+            vec![(1, U256::from(7)), (2, U256::from(9))]
+        );
+        assert!(
+            block_access_list
+                .changes_at(3)
+                .any(|account| account.address == RECIPIENT)
+        );
+    }
+
+    #[test]
+    fn pipeline_flushes_state_transitions_while_committing() {
+        let block = test_block(
+            (1..=6)
+                .map(|seed| transfer_tx(seed, RECIPIENT, 1000))
+                .collect(),
+        );
+        let mut db = state(6).into_generalized();
+        let (merkleizer, updates) = mpsc::channel();
+        let queue_length = AtomicUsize::new(0);
+        LEVM::execute_block_pipeline(
+            &block,
+            &mut db,
+            VMType::L1,
+            merkleizer,
+            &queue_length,
+            true,
+            PrefetchMode::Disabled,
+        )
+        .unwrap();
+
+        // The first batch is sent before the last transaction is committed
+        let first_batch: Vec<AccountUpdate> = updates.recv().unwrap();
+        let first_sender = address_of(&secret_key(1));
+        let last_sender = address_of(&secret_key(6));
+        assert!(
+            first_batch
+                .iter()
+                .any(|update| update.address == first_sender)
+        );
+        assert!(
+            first_batch
+                .iter()
+                .all(|update| update.address != last_sender)
+        );
+        assert!(
+            updates
+                .try_iter()
+                .flatten()
+                .any(|update| update.address == last_sender)
+        );
+    }
+}
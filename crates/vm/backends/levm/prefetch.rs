@@ -0,0 +1,578 @@
+//! Prefetching of the state read by a block.
+//!
+//! LEVM reads every account, storage slot and code it needs for the first time from the
+//! underlying database, one at a time, as execution reaches it. While a block is executed, the
+//! accounts and slots it is likely to touch (senders, recipients, access lists, EIP-7702
+//! authorities, withdrawals and system contracts) are fetched concurrently on the rayon pool into
+//! a cache placed in front of the database, so that most of those reads are served from memory.
+//! With [`PrefetchMode::Speculative`], every transaction is also pre-executed against the parent
+//! state in parallel, warming whatever it reads.
+//!
+//! The cache only holds values of the parent state, which doesn't change while the block is
+//! executed, so serving a read from it is always equivalent to reading the database.
+
+use super::LEVM;
+use crate::EvmError;
+use crate::system_contracts::{
+    BEACON_ROOTS_ADDRESS, HISTORY_STORAGE_ADDRESS, PRAGUE_SYSTEM_CONTRACTS,
+};
+use ethrex_common::{
+    Address, H256, U256,
+    constants::EMPTY_KECCACK_HASH,
+    types::{AccountState, Block, ChainConfig, Code, Transaction, TxKind},
+};
+use ethrex_levm::{
+    db::{Database, gen_db::GeneralizedDatabase},
+    errors::DatabaseError,
+    tracing::LevmCallTracer,
+    utils::eip7702_recover_address,
+    vm::{VM, VMType},
+};
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    fmt,
+    hash::Hash,
+    str::FromStr,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+/// Which state is prefetched while a block is executed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrefetchMode {
+    #[default]
+    Disabled,
+    /// Only the accounts and slots that can be derived from the block itself
+    Static,
+    /// Also everything read by a speculative parallel pre-execution of the transactions
+    Speculative,
+}
+
+impl fmt::Display for PrefetchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefetchMode::Disabled => write!(f, "none"),
+            PrefetchMode::Static => write!(f, "static"),
+            PrefetchMode::Speculative => write!(f, "speculative"),
+        }
+    }
+}
+
+impl FromStr for PrefetchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "disabled" => Ok(PrefetchMode::Disabled),
+            "static" => Ok(PrefetchMode::Static),
+            "speculative" => Ok(PrefetchMode::Speculative),
+            _ => Err(format!(
+                "Invalid prefetch mode '{s}'. Expected: none, static or speculative"
+            )),
+        }
+    }
+}
+
+/// How much state was prefetched for a block and how many of the reads done by its execution
+/// were served from the prefetch cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrefetchStats {
+    pub prefetched_accounts: u64,
+    pub prefetched_slots: u64,
+    pub account_hits: u64,
+    pub account_misses: u64,
+    pub storage_hits: u64,
+    pub storage_misses: u64,
+}
+
+impl PrefetchStats {
+    /// Fraction of the account reads served from the cache, 0 if there were none
+    pub fn account_hit_rate(&self) -> f64 {
+        hit_rate(self.account_hits, self.account_misses)
+    }
+
+    /// Fraction of the storage reads served from the cache, 0 if there were none
+    pub fn storage_hit_rate(&self) -> f64 {
+        hit_rate(self.storage_hits, self.storage_misses)
+    }
+}
+
+fn hit_rate(hits: u64, misses: u64) -> f64 {
+    let total = hits + misses;
+    if total == 0 {
+        return 0.0;
+    }
+    hits as f64 / total as f64
+}
+
+/// Parent state read so far, by either the prefetcher or the block execution
+struct PrefetchCache {
+    store: Arc<dyn Database>,
+    accounts: RwLock<FxHashMap<Address, AccountState>>,
+    storage: RwLock<FxHashMap<(Address, H256), U256>>,
+    codes: RwLock<FxHashMap<H256, Code>>,
+    /// Set once the block is executed, so that pending prefetches are skipped and running
+    /// pre-executions fail at their next read
+    done: AtomicBool,
+    prefetched_accounts: AtomicU64,
+    prefetched_slots: AtomicU64,
+    account_hits: AtomicU64,
+    account_misses: AtomicU64,
+    storage_hits: AtomicU64,
+    storage_misses: AtomicU64,
+}
+
+impl PrefetchCache {
+    fn new(store: Arc<dyn Database>) -> Self {
+        Self {
+            store,
+            accounts: Default::default(),
+            storage: Default::default(),
+            codes: Default::default(),
+            done: AtomicBool::new(false),
+            prefetched_accounts: AtomicU64::new(0),
+            prefetched_slots: AtomicU64::new(0),
+            account_hits: AtomicU64::new(0),
+            account_misses: AtomicU64::new(0),
+            storage_hits: AtomicU64::new(0),
+            storage_misses: AtomicU64::new(0),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    fn stats(&self) -> PrefetchStats {
+        PrefetchStats {
+            prefetched_accounts: self.prefetched_accounts.load(Ordering::Relaxed),
+            prefetched_slots: self.prefetched_slots.load(Ordering::Relaxed),
+            account_hits: self.account_hits.load(Ordering::Relaxed),
+            account_misses: self.account_misses.load(Ordering::Relaxed),
+            storage_hits: self.storage_hits.load(Ordering::Relaxed),
+            storage_misses: self.storage_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Prefetches the state the block is likely to read, until it is fully prefetched or the
+    /// block is executed. Errors are ignored, execution will run into them itself if they matter.
+    fn warm(self: &Arc<Self>, block: &Block, vm_type: VMType, mode: PrefetchMode) {
+        let Ok(transactions) = block.body.get_transactions_with_sender() else {
+            return;
+        };
+        let warmer = Arc::new(PrefetchingDatabase {
+            cache: self.clone(),
+            warming: true,
+        });
+
+        let warm_targets = || {
+            let (accounts, slots) = prefetch_targets(block, &transactions);
+            rayon::join(
+                || {
+                    accounts.par_iter().for_each(|address| {
+                        if self.is_done() {
+                            return;
+                        }
+                        if let Ok(state) = warmer.get_account_state(*address)
+                            && state.code_hash != *EMPTY_KECCACK_HASH
+                        {
+                            let _ = warmer.get_account_code(state.code_hash);
+                        }
+                    })
+                },
+                || {
+                    slots.par_iter().for_each(|(address, key)| {
+                        if !self.is_done() {
+                            let _ = warmer.get_storage_value(*address, *key);
+                        }
+                    })
+                },
+            );
+        };
+        let pre_execute = || {
+            if mode != PrefetchMode::Speculative {
+                return;
+            }
+            transactions.par_iter().for_each(|(tx, tx_sender)| {
+                if !self.is_done() {
+                    let _ = LEVM::pre_execute_tx(tx, *tx_sender, block, warmer.clone(), vm_type);
+                }
+            });
+        };
+        rayon::join(warm_targets, pre_execute);
+    }
+}
+
+/// Returns the cached value for `key`, fetching and caching it if missing, and whether it was cached
+fn get_or_fetch<K: Eq + Hash + Copy, V: Clone>(
+    map: &RwLock<FxHashMap<K, V>>,
+    key: K,
+    fetch: impl FnOnce() -> Result<V, DatabaseError>,
+) -> Result<(V, bool), DatabaseError> {
+    if let Some(value) = map.read().map_err(poisoned)?.get(&key) {
+        return Ok((value.clone(), true));
+    }
+    let value = fetch()?;
+    map.write().map_err(poisoned)?.insert(key, value.clone());
+    Ok((value, false))
+}
+
+fn poisoned<E>(_: E) -> DatabaseError {
+    DatabaseError::Custom("Prefetch cache lock was poisoned".to_string())
+}
+
+/// Database reading through the prefetch cache. The block is executed with `warming` unset,
+/// so that only its reads count towards the hit rates.
+struct PrefetchingDatabase {
+    cache: Arc<PrefetchCache>,
+    warming: bool,
+}
+
+impl PrefetchingDatabase {
+    /// Fails the reads done to warm the cache once the block is executed, so that speculative
+    /// pre-executions still running in the background stop instead of holding the rayon pool
+    fn check_warming(&self) -> Result<(), DatabaseError> {
+        if self.warming && self.cache.is_done() {
+            return Err(DatabaseError::Custom(
+                "Block already executed, prefetching stopped".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn record(&self, cached: bool, prefetched: &AtomicU64, hits: &AtomicU64, misses: &AtomicU64) {
+        let counter = match (self.warming, cached) {
+            (true, true) => return,
+            (true, false) => prefetched,
+            (false, true) => hits,
+            (false, false) => misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Database for PrefetchingDatabase {
+    fn get_account_state(&self, address: Address) -> Result<AccountState, DatabaseError> {
+        self.check_warming()?;
+        let cache = &self.cache;
+        let (state, cached) = get_or_fetch(&cache.accounts, address, || {
+            cache.store.get_account_state(address)
+        })?;
+        self.record(
+            cached,
+            &cache.prefetched_accounts,
+            &cache.account_hits,
+            &cache.account_misses,
+        );
+        Ok(state)
+    }
+
+    fn get_storage_value(&self, address: Address, key: H256) -> Result<U256, DatabaseError> {
+        self.check_warming()?;
+        let cache = &self.cache;
+        let (value, cached) = get_or_fetch(&cache.storage, (address, key), || {
+            cache.store.get_storage_value(address, key)
+        })?;
+        self.record(
+            cached,
+            &cache.prefetched_slots,
+            &cache.storage_hits,
+            &cache.storage_misses,
+        );
+        Ok(value)
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<H256, DatabaseError> {
+        self.check_warming()?;
+        self.cache.store.get_block_hash(block_number)
+    }
+
+    fn get_chain_config(&self) -> Result<ChainConfig, DatabaseError> {
+        self.cache.store.get_chain_config()
+    }
+
+    fn get_account_code(&self, code_hash: H256) -> Result<Code, DatabaseError> {
+        self.check_warming()?;
+        let cache = &self.cache;
+        get_or_fetch(&cache.codes, code_hash, || {
+            cache.store.get_account_code(code_hash)
+        })
+        .map(|(code, _)| code)
+    }
+}
+
+/// Accounts and storage slots the block will most likely read, derived from the block alone
+fn prefetch_targets(
+    block: &Block,
+    transactions: &[(&Transaction, Address)],
+) -> (Vec<Address>, Vec<(Address, H256)>) {
+    let mut accounts = FxHashSet::default();
+    let mut slots = FxHashSet::default();
+
+    accounts.insert(block.header.coinbase);
+    accounts.extend([
+        BEACON_ROOTS_ADDRESS.address,
+        HISTORY_STORAGE_ADDRESS.address,
+    ]);
+    accounts.extend(
+        PRAGUE_SYSTEM_CONTRACTS
+            .iter()
+            .map(|contract| contract.address),
+    );
+    accounts.extend(
+        block
+            .body
+            .withdrawals
+            .iter()
+            .flatten()
+            .map(|withdrawal| withdrawal.address),
+    );
+
+    for (tx, tx_sender) in transactions {
+        accounts.insert(*tx_sender);
+        if let TxKind::Call(to) = tx.to() {
+            accounts.insert(to);
+        }
+        for (address, keys) in tx.access_list() {
+            accounts.insert(*address);
+            slots.extend(keys.iter().map(|key| (*address, *key)));
+        }
+        for authorization in tx.authorization_list().into_iter().flatten() {
+            // The delegation target is loaded too when the authority is called
+            accounts.insert(authorization.address);
+            if let Ok(Some(authority)) = eip7702_recover_address(authorization) {
+                accounts.insert(authority);
+            }
+        }
+    }
+
+    (accounts.into_iter().collect(), slots.into_iter().collect())
+}
+
+impl LEVM {
+    /// Runs `execute` over `db` while the state the block is likely to read is prefetched on the
+    /// rayon pool. Returns the prefetch stats along with the result, unless prefetching is disabled.
+    ///
+    /// The prefetching is detached, so the execution never waits for it, not even for a
+    /// pre-execution that keeps computing without reading any state.
+    pub(crate) fn execute_with_prefetch<T>(
+        block: &Block,
+        db: &mut GeneralizedDatabase,
+        vm_type: VMType,
+        mode: PrefetchMode,
+        execute: impl FnOnce(&mut GeneralizedDatabase) -> Result<T, EvmError>,
+    ) -> Result<(T, Option<PrefetchStats>), EvmError> {
+        if mode == PrefetchMode::Disabled {
+            return Ok((execute(db)?, None));
+        }
+
+        let cache = Arc::new(PrefetchCache::new(db.store.clone()));
+        let store = std::mem::replace(
+            &mut db.store,
+            Arc::new(PrefetchingDatabase {
+                cache: cache.clone(),
+                warming: false,
+            }),
+        );
+        let warmer = cache.clone();
+        let warmed_block = block.clone();
+        rayon::spawn(move || warmer.warm(&warmed_block, vm_type, mode));
+        let result = execute(db);
+        // Stops the prefetches that can no longer help
+        cache.done.store(true, Ordering::Relaxed);
+        db.store = store;
+
+        Ok((result?, Some(cache.stats())))
+    }
+
+    /// Executes a transaction over the parent state only to warm the prefetch cache
+    fn pre_execute_tx(
+        tx: &Transaction,
+        tx_sender: Address,
+        block: &Block,
+        store: Arc<PrefetchingDatabase>,
+        vm_type: VMType,
+    ) -> Result<(), EvmError> {
+        let mut db = GeneralizedDatabase::new(store);
+        let env = Self::setup_env(tx, tx_sender, &block.header, &db, vm_type)?;
+        let mut vm = VM::new(env, &mut db, tx, LevmCallTracer::disabled(), vm_type)?;
+        vm.execute()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::test_utils::{
+        TestDatabase, address_of, call_tx, cancun_config, secret_key, test_block,
+    };
+    use bytes::Bytes;
+    use ethrex_common::types::{AccountUpdate, Receipt};
+    use std::collections::{BTreeMap, btree_map::Entry};
+    use std::sync::{atomic::AtomicUsize, mpsc};
+
+    const STORE_CALLDATA: Address = Address::repeat_byte(0x10);
+    const RECIPIENT: Address = Address::repeat_byte(0x30);
+
+    fn state() -> TestDatabase {
+        TestDatabase::new(cancun_config())
+            .with_funded(address_of(&secret_key(1)), Bytes::new())
+            .with_funded(address_of(&secret_key(2)), Bytes::new())
+            // SSTORE(0, CALLDATALOAD(0))
+            .with_funded(
+                STORE_CALLDATA,
+                Bytes::from_static(&[0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x00]),
+            )
+            .with_storage(STORE_CALLDATA, H256::zero(), U256::one())
+    }
+
+    /// A call storing a value in a slot, which only a pre-execution can know it's read,
+    /// and a transfer
+    fn block() -> Block {
+        let mut data = [0u8; 32];
+        data[31] = 7;
+        test_block(vec![
+            call_tx(
+                &secret_key(1),
+                0,
+                STORE_CALLDATA,
+                U256::zero(),
+                Bytes::copy_from_slice(&data),
+            ),
+            call_tx(&secret_key(2), 0, RECIPIENT, 1000.into(), Bytes::new()),
+        ])
+    }
+
+    /// Executes the block through the pipeline, returning its receipts, the prefetch stats and
+    /// the state updates sent to the merkleizer, merged by account
+    fn execute(
+        prefetch: PrefetchMode,
+    ) -> (Vec<Receipt>, Option<PrefetchStats>, Vec<AccountUpdate>) {
+        let mut db = state().into_generalized();
+        let (merkleizer, updates) = mpsc::channel();
+        let queue_length = AtomicUsize::new(0);
+        let result = LEVM::execute_block_pipeline(
+            &block(),
+            &mut db,
+            VMType::L1,
+            merkleizer,
+            &queue_length,
+            false,
+            prefetch,
+        )
+        .unwrap();
+
+        let mut merged: BTreeMap<Address, AccountUpdate> = BTreeMap::new();
+        for update in updates.try_iter().flatten() {
+            match merged.entry(update.address) {
+                Entry::Occupied(mut entry) => entry.get_mut().merge(update),
+                Entry::Vacant(entry) => {
+                    entry.insert(update);
+                }
+            }
+        }
+        (
+            result.receipts,
+            result.prefetch_stats,
+            merged.into_values().collect(),
+        )
+    }
+
+    /// Warms the cache for the block and then executes it, returning the prefetch stats
+    fn warm_then_execute(mode: PrefetchMode) -> PrefetchStats {
+        let block = block();
+        let mut db = state().into_generalized();
+        let cache = Arc::new(PrefetchCache::new(db.store.clone()));
+        cache.warm(&block, VMType::L1, mode);
+        db.store = Arc::new(PrefetchingDatabase {
+            cache: cache.clone(),
+            warming: false,
+        });
+        LEVM::execute_block(&block, &mut db, VMType::L1, false).unwrap();
+        cache.stats()
+    }
+
+    #[test]
+    fn prefetching_doesnt_change_the_result() {
+        let (receipts, stats, updates) = execute(PrefetchMode::Disabled);
+        assert!(stats.is_none());
+        assert!(updates.iter().any(|update| update.address == RECIPIENT));
+
+        for mode in [PrefetchMode::Static, PrefetchMode::Speculative] {
+            let (prefetched_receipts, stats, prefetched_updates) = execute(mode);
+            assert_eq!(prefetched_receipts, receipts);
+            assert_eq!(prefetched_updates, updates);
+            let stats = stats.unwrap();
+            assert!(stats.account_hits + stats.account_misses > 0);
+        }
+    }
+
+    #[test]
+    fn warmed_reads_are_counted_as_hits() {
+        // The senders and recipients are known from the block, the slot isn't
+        let stats = warm_then_execute(PrefetchMode::Static);
+        assert!(stats.prefetched_accounts > 0);
+        assert!(stats.account_hits > 0);
+        assert_eq!(stats.prefetched_slots, 0);
+        assert_eq!((stats.storage_hits, stats.storage_misses), (0, 1));
+
+        // The pre-execution of the call reads the slot too
+        let stats = warm_then_execute(PrefetchMode::Speculative);
+        assert!(stats.account_hits > 0);
+        assert_eq!(stats.prefetched_slots, 1);
+        assert_eq!((stats.storage_hits, stats.storage_misses), (1, 0));
+    }
+
+    #[test]
+    fn warming_stops_once_the_block_is_executed() {
+        let block = block();
+        let transactions = block.body.get_transactions_with_sender().unwrap();
+        let (tx, tx_sender) = transactions.first().unwrap();
+        let db = state().into_generalized();
+        let cache = Arc::new(PrefetchCache::new(db.store.clone()));
+        let warmer = Arc::new(PrefetchingDatabase {
+            cache: cache.clone(),
+            warming: true,
+        });
+        cache.done.store(true, Ordering::Relaxed);
+
+        assert!(warmer.get_account_state(*tx_sender).is_err());
+        assert!(LEVM::pre_execute_tx(tx, *tx_sender, &block, warmer, VMType::L1).is_err());
+        // Execution itself keeps reading through the cache
+        let reader = PrefetchingDatabase {
+            cache: cache.clone(),
+            warming: false,
+        };
+        assert!(reader.get_account_state(*tx_sender).is_ok());
+        assert_eq!(cache.stats().prefetched_accounts, 0);
+    }
+
+    #[test]
+    fn prefetch_mode_round_trip() {
+        for mode in [
+            PrefetchMode::Disabled,
+            PrefetchMode::Static,
+            PrefetchMode::Speculative,
+        ] {
+            assert_eq!(mode.to_string().parse::<PrefetchMode>(), Ok(mode));
+        }
+        assert!("eager".parse::<PrefetchMode>().is_err());
+    }
+
+    #[test]
+    fn hit_rates() {
+        let stats = PrefetchStats {
+            account_hits: 3,
+            account_misses: 1,
+            ..Default::default()
+        };
+        assert_eq!(stats.account_hit_rate(), 0.75);
+        assert_eq!(stats.storage_hit_rate(), 0.0);
+    }
+}
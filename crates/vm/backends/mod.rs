@@ -1,10 +1,13 @@
 pub mod levm;
 use levm::LEVM;
+pub use levm::prefetch::{PrefetchMode, PrefetchStats};
 
 use crate::db::{DynVmDatabase, VmDatabase};
 use crate::errors::EvmError;
 use crate::execution_result::ExecutionResult;
+use ethrex_common::types::block_access_list::BlockAccessList;
 use ethrex_common::types::requests::Requests;
+use ethrex_common::types::ubt_leaf::UbtLeaf;
 use ethrex_common::types::{
     AccessList, AccountUpdate, Block, BlockHeader, Fork, GenericTransaction, Receipt, Transaction,
     Withdrawal,
@@ -23,6 +26,10 @@ use tracing::instrument;
 pub struct Evm {
     pub db: GeneralizedDatabase,
     pub vm_type: VMType,
+    /// Whether block transactions are executed optimistically in parallel.
+    pub parallel_execution: bool,
+    /// Which state is prefetched while a block is executed in the import pipeline.
+    pub prefetch: PrefetchMode,
 }
 
 impl core::fmt::Debug for Evm {
@@ -38,6 +45,8 @@ impl Evm {
         Evm {
             db: GeneralizedDatabase::new(Arc::new(wrapped_db)),
             vm_type: VMType::L1,
+            parallel_execution: false,
+            prefetch: PrefetchMode::Disabled,
         }
     }
 
@@ -50,6 +59,8 @@ impl Evm {
         let evm = Evm {
             db: GeneralizedDatabase::new(Arc::new(wrapped_db)),
             vm_type: VMType::L2(fee_config),
+            parallel_execution: false,
+            prefetch: PrefetchMode::Disabled,
         };
 
         Ok(evm)
@@ -70,11 +81,13 @@ impl Evm {
         Evm {
             db: GeneralizedDatabase::new(store),
             vm_type,
+            parallel_execution: false,
+            prefetch: PrefetchMode::Disabled,
         }
     }
 
     pub fn execute_block(&mut self, block: &Block) -> Result<BlockExecutionResult, EvmError> {
-        LEVM::execute_block(block, &mut self.db, self.vm_type)
+        LEVM::execute_block(block, &mut self.db, self.vm_type, self.parallel_execution)
     }
 
     #[instrument(
@@ -89,7 +102,15 @@ impl Evm {
         merkleizer: Sender<Vec<AccountUpdate>>,
         queue_length: &AtomicUsize,
     ) -> Result<BlockExecutionResult, EvmError> {
-        LEVM::execute_block_pipeline(block, &mut self.db, self.vm_type, merkleizer, queue_length)
+        LEVM::execute_block_pipeline(
+            block,
+            &mut self.db,
+            self.vm_type,
+            merkleizer,
+            queue_length,
+            self.parallel_execution,
+            self.prefetch,
+        )
     }
 
     /// Wraps [LEVM::execute_tx].
@@ -103,7 +124,15 @@ impl Evm {
         sender: Address,
     ) -> Result<(Receipt, u64), EvmError> {
         let execution_report =
-            LEVM::execute_tx(tx, sender, block_header, &mut self.db, self.vm_type)?;
+            match LEVM::execute_tx(tx, sender, block_header, &mut self.db, self.vm_type) {
+                Ok(report) => report,
+                Err(error) => {
+                    // The transaction won't be part of the block, neither will its accesses
+                    self.db.discard_block_access_index();
+                    return Err(error);
+                }
+            };
+        self.db.next_block_access_index()?;
 
         *remaining_gas = remaining_gas.saturating_sub(execution_report.gas_used);
 
@@ -135,9 +164,23 @@ impl Evm {
             LEVM::process_block_hash_history(block_header, &mut self.db, self.vm_type)?;
         }
 
+        // Transactions go after the system calls in the block access list
+        self.db.next_block_access_index()?;
         Ok(())
     }
 
+    /// Starts recording the block access list (EIP-7928) of the block being built.
+    /// [Evm::apply_system_calls] and each successful [Evm::execute_tx] move on to the next
+    /// block access index, everything executed afterwards is recorded as post-execution.
+    pub fn enable_access_recording(&mut self) {
+        self.db.enable_access_recording();
+    }
+
+    /// Returns the block access list recorded since [Evm::enable_access_recording]
+    pub fn take_block_access_list(&mut self) -> Result<Option<BlockAccessList>, EvmError> {
+        Ok(self.db.take_block_access_list()?)
+    }
+
     /// Wraps the [LEVM::get_state_transitions] which gathers the information from a [CacheDB].
     /// The output is `Vec<AccountUpdate>`.
     pub fn get_state_transitions(&mut self) -> Result<Vec<AccountUpdate>, EvmError> {
@@ -166,6 +209,16 @@ impl Evm {
         LEVM::simulate_tx_from_generic(tx, header, &mut self.db, self.vm_type)
     }
 
+    /// Simulates the validation of a RIP-7560 transaction on top of the current state
+    pub fn validate_rip7560_transaction(
+        &mut self,
+        tx: &Transaction,
+        sender: Address,
+        header: &BlockHeader,
+    ) -> Result<(), EvmError> {
+        LEVM::validate_rip7560_transaction(tx, sender, header, &mut self.db, self.vm_type)
+    }
+
     pub fn create_access_list(
         &mut self,
         tx: &GenericTransaction,
@@ -205,4 +258,11 @@ impl Evm {
 pub struct BlockExecutionResult {
     pub receipts: Vec<Receipt>,
     pub requests: Vec<Requests>,
+    /// Recorded for Amsterdam blocks onwards
+    pub block_access_list: Option<BlockAccessList>,
+    /// Unified binary tree leaves read or written by the block, recorded when EIP-4762
+    /// stateless gas costs are enabled
+    pub accessed_leaves: Option<Vec<UbtLeaf>>,
+    /// Set when the state of the block was prefetched during pipelined execution
+    pub prefetch_stats: Option<PrefetchStats>,
 }
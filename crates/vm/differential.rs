@@ -0,0 +1,739 @@
+//! Differential execution of blocks in LEVM and revm.
+//!
+//! Every transaction of a block is executed by both VMs on top of the same state, the one LEVM
+//! left after executing the previous transactions of the block, so a divergence doesn't spill
+//! over the transactions after it. Gas used, status, logs and the state each transaction
+//! changed are compared, and the first divergence is returned together with the state the
+//! transaction read, which can be written as a reproducer for the LEVM runner.
+//!
+//! System calls and withdrawals are only applied by LEVM, only transactions are compared.
+//! Forks after Osaka aren't supported, as revm doesn't implement them.
+
+use crate::backends::Evm;
+use crate::backends::levm::{LEVM, calculate_gas_price_for_tx};
+use crate::db::VmDatabase;
+use crate::errors::EvmError;
+use bytes::Bytes;
+use ethrex_common::types::{AccountInfo, Block, BlockHeader, Fork, Log, Transaction, TxKind};
+use ethrex_common::{Address, H256, U256};
+use ethrex_levm::account::AccountStatus;
+use ethrex_levm::db::gen_db::GeneralizedDatabase;
+use ethrex_levm::environment::EVMConfig;
+use ethrex_levm::errors::{ExecutionReport, InternalError};
+use ethrex_levm::vm::VMType;
+use revm::context::result::ExecutionResult as RevmExecutionResult;
+use revm::context::{BlockEnv, DBErrorMarker, TxEnv, either::Either};
+use revm::context_interface::block::blob::BlobExcessGasAndPrice;
+use revm::context_interface::transaction::{
+    AccessList as RevmAccessList, AccessListItem, Authorization, SignedAuthorization,
+};
+use revm::database::State;
+use revm::database::states::bundle_state::BundleRetention;
+use revm::primitives::hardfork::SpecId;
+use revm::primitives::{
+    Address as RevmAddress, B256, Bytes as RevmBytes, TxKind as RevmTxKind, U256 as RevmU256,
+};
+use revm::state::{AccountInfo as RevmAccountInfo, Bytecode};
+use revm::{Context, Database, ExecuteCommitEvm, MainBuilder, MainContext};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// First transaction of a block whose execution differs between LEVM and revm
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub tx_index: usize,
+    pub tx_hash: H256,
+    /// Human readable description of every difference found
+    pub mismatches: Vec<String>,
+    pub reproducer: Reproducer,
+}
+
+/// Everything needed to execute the diverging transaction again outside of the chain
+#[derive(Debug, Clone)]
+pub struct Reproducer {
+    pub fork: Fork,
+    pub tx: Transaction,
+    pub sender: Address,
+    pub header: BlockHeader,
+    pub effective_gas_price: U256,
+    /// Accounts and storage slots read or written by either VM, with their value before the
+    /// transaction
+    pub prestate: BTreeMap<Address, PrestateAccount>,
+    /// Block hashes read with `BLOCKHASH`
+    pub block_hashes: BTreeMap<u64, H256>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrestateAccount {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Bytes,
+    pub storage: BTreeMap<H256, U256>,
+}
+
+/// Executes every transaction of `block` in LEVM and in revm over `db`, which must hold the
+/// state of the parent block, and returns the first divergence between them. Only L1 blocks
+/// up to Osaka are supported.
+pub fn execute_block_differential(
+    block: &Block,
+    db: impl VmDatabase + 'static,
+) -> Result<Option<Divergence>, EvmError> {
+    let mut evm = Evm::new_for_l1(db);
+    let chain_config = evm.db.store.get_chain_config()?;
+    let config = EVMConfig::new_from_chain_config(&chain_config, &block.header);
+    let spec = spec_id(config.fork)?;
+    let block_env = block_env(&block.header, &config);
+
+    LEVM::prepare_block(block, &mut evm.db, VMType::L1)?;
+
+    let transactions = block.body.get_transactions_with_sender().map_err(|error| {
+        EvmError::Transaction(format!("Couldn't recover addresses with error: {error}"))
+    })?;
+    for (tx_index, (tx, sender)) in transactions.into_iter().enumerate() {
+        let pre_state = evm.db.clone();
+
+        // LEVM
+        let levm_result = LEVM::execute_tx(tx, sender, &block.header, &mut evm.db, VMType::L1);
+        let levm_updates = match &levm_result {
+            Ok(_) => LEVM::get_state_transitions_tx(&mut evm.db)?,
+            Err(_) => Vec::new(),
+        };
+        let levm_loaded = newly_loaded_state(&pre_state, &evm.db);
+
+        // revm
+        let mut revm_state = State::builder()
+            .with_database(RecordingDatabase::new(pre_state))
+            .with_bundle_update()
+            .build();
+        let mut revm_context = Context::mainnet()
+            .with_block(block_env.clone())
+            .with_db(&mut revm_state);
+        revm_context.modify_cfg(|cfg| {
+            cfg.spec = spec;
+            cfg.chain_id = chain_config.chain_id;
+        });
+        let mut revm = revm_context.build_mainnet();
+        let revm_result = revm.transact_commit(tx_env(tx, sender));
+        drop(revm);
+        revm_state.merge_transitions(BundleRetention::PlainState);
+        let bundle = revm_state.take_bundle();
+
+        let mut mismatches = Vec::new();
+        match (&levm_result, &revm_result) {
+            (Err(levm_error), Err(_)) => {
+                // Both VMs rejected the transaction, the block is invalid
+                return Err(EvmError::Transaction(levm_error.to_string()));
+            }
+            (Err(levm_error), Ok(_)) => {
+                mismatches.push(format!("LEVM rejected the transaction: {levm_error}"));
+            }
+            (Ok(_), Err(revm_error)) => {
+                mismatches.push(format!("revm rejected the transaction: {revm_error}"));
+            }
+            (Ok(levm_report), Ok(revm_result)) => {
+                compare_results(levm_report, revm_result, &mut mismatches);
+            }
+        }
+
+        // Accounts and storage slots changed by either VM
+        let mut touched: BTreeMap<Address, BTreeSet<H256>> = BTreeMap::new();
+        for update in &levm_updates {
+            touched
+                .entry(update.address)
+                .or_default()
+                .extend(update.added_storage.keys().copied());
+        }
+        for (address, account) in bundle.state() {
+            if account.status.is_not_modified() {
+                continue;
+            }
+            touched
+                .entry(Address::from_slice(address.as_slice()))
+                .or_default()
+                .extend(
+                    account
+                        .storage
+                        .iter()
+                        .filter(|(_, slot)| slot.is_changed())
+                        .map(|(key, _)| H256::from(key.to_be_bytes())),
+                );
+        }
+        if levm_result.is_ok() && revm_result.is_ok() {
+            compare_state(&touched, &mut evm.db, &mut revm_state, &mut mismatches)?;
+        }
+
+        if mismatches.is_empty() {
+            continue;
+        }
+
+        // Everything LEVM loaded or any VM changed is part of the prestate, on top of what
+        // revm read while executing
+        let recorder = &mut revm_state.database;
+        for (address, keys) in levm_loaded.iter().chain(touched.iter()) {
+            recorder.record_account(*address)?;
+            for key in keys {
+                recorder.record_storage(*address, *key)?;
+            }
+        }
+        let effective_gas_price = calculate_gas_price_for_tx(
+            tx,
+            block.header.base_fee_per_gas.unwrap_or_default(),
+            &VMType::L1,
+        )
+        .unwrap_or_else(|_| tx.gas_price());
+
+        return Ok(Some(Divergence {
+            block_number: block.header.number,
+            block_hash: block.hash(),
+            tx_index,
+            tx_hash: tx.hash(),
+            mismatches,
+            reproducer: Reproducer {
+                fork: config.fork,
+                tx: tx.clone(),
+                sender,
+                header: block.header.clone(),
+                effective_gas_price,
+                prestate: std::mem::take(&mut recorder.prestate),
+                block_hashes: std::mem::take(&mut recorder.block_hashes),
+            },
+        }));
+    }
+
+    Ok(None)
+}
+
+fn compare_results(
+    levm_report: &ExecutionReport,
+    revm_result: &RevmExecutionResult,
+    mismatches: &mut Vec<String>,
+) {
+    if levm_report.is_success() != revm_result.is_success() {
+        mismatches.push(format!(
+            "status: LEVM {:?}, revm {}",
+            levm_report.result,
+            match revm_result {
+                RevmExecutionResult::Success { .. } => "success".to_string(),
+                RevmExecutionResult::Revert { .. } => "revert".to_string(),
+                RevmExecutionResult::Halt { reason, .. } => format!("halt ({reason:?})"),
+            }
+        ));
+    }
+    if levm_report.gas_used != revm_result.gas_used() {
+        mismatches.push(format!(
+            "gas used: LEVM {}, revm {}",
+            levm_report.gas_used,
+            revm_result.gas_used()
+        ));
+    }
+    let revm_logs: Vec<Log> = revm_result
+        .logs()
+        .iter()
+        .map(|log| Log {
+            address: Address::from_slice(log.address.as_slice()),
+            topics: log
+                .data
+                .topics()
+                .iter()
+                .map(|topic| H256::from(topic.0))
+                .collect(),
+            data: log.data.data.0.clone(),
+        })
+        .collect();
+    if levm_report.logs != revm_logs {
+        mismatches.push(format!(
+            "logs: LEVM {:?}, revm {revm_logs:?}",
+            levm_report.logs
+        ));
+    }
+}
+
+/// Compares the state of the touched accounts and storage slots after the transaction.
+/// Empty accounts are considered non-existent, as the VMs don't agree on whether they are kept.
+fn compare_state(
+    touched: &BTreeMap<Address, BTreeSet<H256>>,
+    levm_db: &mut GeneralizedDatabase,
+    revm_state: &mut State<RecordingDatabase>,
+    mismatches: &mut Vec<String>,
+) -> Result<(), EvmError> {
+    for (address, keys) in touched {
+        let levm_info =
+            Some(levm_db.get_account(*address)?.info.clone()).filter(|info| !info.is_empty());
+        let revm_info = revm_state
+            .basic(RevmAddress(address.0.into()))?
+            .map(|info| AccountInfo {
+                code_hash: H256::from(info.code_hash.0),
+                balance: U256::from_little_endian(info.balance.as_le_slice()),
+                nonce: info.nonce,
+            })
+            .filter(|info| !info.is_empty());
+        if levm_info != revm_info {
+            mismatches.push(format!(
+                "account {address:#x}: LEVM {levm_info:?}, revm {revm_info:?}"
+            ));
+        }
+
+        for key in keys {
+            let levm_value = storage_value(levm_db, *address, *key)?;
+            let revm_value = revm_state.storage(
+                RevmAddress(address.0.into()),
+                RevmU256::from_be_bytes(key.0),
+            )?;
+            let revm_value = U256::from_little_endian(revm_value.as_le_slice());
+            if levm_value != revm_value {
+                mismatches.push(format!(
+                    "storage {address:#x}[{key:#x}]: LEVM {levm_value:#x}, revm {revm_value:#x}"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Accounts and storage slots LEVM loaded from the database while executing a transaction
+fn newly_loaded_state(
+    pre_state: &GeneralizedDatabase,
+    post_state: &GeneralizedDatabase,
+) -> BTreeMap<Address, BTreeSet<H256>> {
+    post_state
+        .current_accounts_state
+        .iter()
+        .filter_map(|(address, account)| {
+            let pre_account = pre_state.current_accounts_state.get(address);
+            let keys: BTreeSet<H256> = account
+                .storage
+                .keys()
+                .filter(|key| pre_account.is_none_or(|pre| !pre.storage.contains_key(*key)))
+                .copied()
+                .collect();
+            (pre_account.is_none() || !keys.is_empty()).then_some((*address, keys))
+        })
+        .collect()
+}
+
+/// Reads a storage slot, taking into account that LEVM keeps the storage of accounts destroyed
+/// earlier in the block in the database
+fn storage_value(
+    db: &mut GeneralizedDatabase,
+    address: Address,
+    key: H256,
+) -> Result<U256, InternalError> {
+    if db.get_account(address)?.status == AccountStatus::Destroyed {
+        return Ok(U256::zero());
+    }
+    db.get_storage_value(address, key)
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct RecordingDatabaseError(#[from] InternalError);
+
+impl DBErrorMarker for RecordingDatabaseError {}
+
+impl From<RecordingDatabaseError> for EvmError {
+    fn from(value: RecordingDatabaseError) -> Self {
+        value.0.into()
+    }
+}
+
+/// revm database over the state LEVM had before executing a transaction, which records every
+/// account, storage slot and block hash read so they can be written as a prestate
+struct RecordingDatabase {
+    db: GeneralizedDatabase,
+    prestate: BTreeMap<Address, PrestateAccount>,
+    block_hashes: BTreeMap<u64, H256>,
+}
+
+impl RecordingDatabase {
+    fn new(db: GeneralizedDatabase) -> Self {
+        Self {
+            db,
+            prestate: BTreeMap::new(),
+            block_hashes: BTreeMap::new(),
+        }
+    }
+
+    fn record_account(&mut self, address: Address) -> Result<AccountInfo, InternalError> {
+        let info = self.db.get_account(address)?.info.clone();
+        if !self.prestate.contains_key(&address) {
+            let code = self.db.get_code(info.code_hash)?.bytecode.clone();
+            self.prestate.insert(
+                address,
+                PrestateAccount {
+                    balance: info.balance,
+                    nonce: info.nonce,
+                    code,
+                    storage: BTreeMap::new(),
+                },
+            );
+        }
+        Ok(info)
+    }
+
+    fn record_storage(&mut self, address: Address, key: H256) -> Result<U256, InternalError> {
+        self.record_account(address)?;
+        let value = storage_value(&mut self.db, address, key)?;
+        if let Some(account) = self.prestate.get_mut(&address) {
+            account.storage.entry(key).or_insert(value);
+        }
+        Ok(value)
+    }
+}
+
+impl Database for RecordingDatabase {
+    type Error = RecordingDatabaseError;
+
+    fn basic(&mut self, address: RevmAddress) -> Result<Option<RevmAccountInfo>, Self::Error> {
+        let address = Address::from_slice(address.as_slice());
+        let info = self.record_account(address)?;
+        if info.is_empty() {
+            return Ok(None);
+        }
+        let code = self.db.get_code(info.code_hash)?.bytecode.clone();
+        Ok(Some(RevmAccountInfo {
+            balance: RevmU256::from_limbs(info.balance.0),
+            nonce: info.nonce,
+            code_hash: B256::from(info.code_hash.0),
+            code: Some(Bytecode::new_raw(RevmBytes(code))),
+        }))
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let code = self.db.get_code(H256::from(code_hash.0))?;
+        Ok(Bytecode::new_raw(RevmBytes(code.bytecode.clone())))
+    }
+
+    fn storage(&mut self, address: RevmAddress, index: RevmU256) -> Result<RevmU256, Self::Error> {
+        let value = self.record_storage(
+            Address::from_slice(address.as_slice()),
+            H256::from(index.to_be_bytes()),
+        )?;
+        Ok(RevmU256::from_limbs(value.0))
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        let hash = self
+            .db
+            .store
+            .get_block_hash(number)
+            .map_err(InternalError::from)?;
+        self.block_hashes.insert(number, hash);
+        Ok(B256::from(hash.0))
+    }
+}
+
+fn block_env(header: &BlockHeader, config: &EVMConfig) -> BlockEnv {
+    BlockEnv {
+        number: RevmU256::from(header.number),
+        beneficiary: RevmAddress(header.coinbase.0.into()),
+        timestamp: RevmU256::from(header.timestamp),
+        gas_limit: header.gas_limit,
+        basefee: header.base_fee_per_gas.unwrap_or_default(),
+        difficulty: RevmU256::from_limbs(header.difficulty.0),
+        prevrandao: Some(B256::from(header.prev_randao.0)),
+        blob_excess_gas_and_price: header.excess_blob_gas.map(|excess_blob_gas| {
+            BlobExcessGasAndPrice::new(
+                excess_blob_gas,
+                config.blob_schedule.base_fee_update_fraction,
+            )
+        }),
+    }
+}
+
+fn tx_env(tx: &Transaction, sender: Address) -> TxEnv {
+    let authorization_list = tx
+        .authorization_list()
+        .map(|list| {
+            list.iter()
+                .map(|auth| {
+                    Either::Left(SignedAuthorization::new_unchecked(
+                        Authorization {
+                            chain_id: RevmU256::from_limbs(auth.chain_id.0),
+                            address: RevmAddress(auth.address.0.into()),
+                            nonce: auth.nonce,
+                        },
+                        u8::try_from(auth.y_parity.low_u64()).unwrap_or(u8::MAX),
+                        RevmU256::from_limbs(auth.r_signature.0),
+                        RevmU256::from_limbs(auth.s_signature.0),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    TxEnv {
+        caller: RevmAddress(sender.0.into()),
+        gas_limit: tx.gas_limit(),
+        gas_price: u128::try_from(tx.gas_price()).unwrap_or(u128::MAX),
+        kind: match tx.to() {
+            TxKind::Call(to) => RevmTxKind::Call(RevmAddress(to.0.into())),
+            TxKind::Create => RevmTxKind::Create,
+        },
+        value: RevmU256::from_limbs(tx.value().0),
+        data: RevmBytes(tx.data().clone()),
+        nonce: tx.nonce(),
+        chain_id: tx.chain_id(),
+        access_list: RevmAccessList(
+            tx.access_list()
+                .iter()
+                .map(|(address, keys)| AccessListItem {
+                    address: RevmAddress(address.0.into()),
+                    storage_keys: keys.iter().map(|key| B256::from(key.0)).collect(),
+                })
+                .collect(),
+        ),
+        gas_priority_fee: tx.max_priority_fee().map(u128::from),
+        blob_hashes: tx
+            .blob_versioned_hashes()
+            .iter()
+            .map(|hash| B256::from(hash.0))
+            .collect(),
+        max_fee_per_blob_gas: tx
+            .max_fee_per_blob_gas()
+            .map(|fee| u128::try_from(fee).unwrap_or(u128::MAX))
+            .unwrap_or_default(),
+        authorization_list,
+        tx_type: tx.tx_type() as u8,
+    }
+}
+
+/// revm spec of `fork`, failing for the forks after Osaka revm doesn't implement
+fn spec_id(fork: Fork) -> Result<SpecId, EvmError> {
+    let spec = match fork {
+        Fork::Frontier => SpecId::FRONTIER,
+        Fork::FrontierThawing => SpecId::FRONTIER_THAWING,
+        Fork::Homestead => SpecId::HOMESTEAD,
+        Fork::DaoFork => SpecId::DAO_FORK,
+        Fork::Tangerine => SpecId::TANGERINE,
+        Fork::SpuriousDragon => SpecId::SPURIOUS_DRAGON,
+        Fork::Byzantium => SpecId::BYZANTIUM,
+        Fork::Constantinople => SpecId::CONSTANTINOPLE,
+        Fork::Petersburg => SpecId::PETERSBURG,
+        Fork::Istanbul => SpecId::ISTANBUL,
+        Fork::MuirGlacier => SpecId::MUIR_GLACIER,
+        Fork::Berlin => SpecId::BERLIN,
+        Fork::London => SpecId::LONDON,
+        Fork::ArrowGlacier => SpecId::ARROW_GLACIER,
+        Fork::GrayGlacier => SpecId::GRAY_GLACIER,
+        Fork::Paris => SpecId::MERGE,
+        Fork::Shanghai => SpecId::SHANGHAI,
+        Fork::Cancun => SpecId::CANCUN,
+        Fork::Prague => SpecId::PRAGUE,
+        Fork::Osaka => SpecId::OSAKA,
+        Fork::BPO1 | Fork::BPO2 | Fork::BPO3 | Fork::BPO4 | Fork::BPO5 | Fork::Amsterdam => {
+            return Err(EvmError::InvalidEVM(format!(
+                "revm doesn't support {fork:?}, only forks up to Osaka can be executed differentially"
+            )));
+        }
+    };
+    Ok(spec)
+}
+
+impl Divergence {
+    /// Writes the reproducer of the divergence to `dir`:
+    /// - `input.json`, the transaction and its prestate in the input format of the LEVM runner.
+    ///   The runner executes it as a legacy transaction from a sender with nonce zero, outside
+    ///   of the block, so it's a minimal approximation of the original transaction.
+    /// - `tx.json`, `prestate.json` and `block.json`, to execute the exact transaction with the
+    ///   runner's `--tx`, `--prestate` and `--block` flags.
+    /// - `divergence.json`, the block and transaction that diverged, every difference found
+    ///   and the block hashes read, which the runner can't provide.
+    ///
+    /// Returns the paths of the written files.
+    pub fn write_reproducer(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let reproducer = &self.reproducer;
+        let tx = &reproducer.tx;
+
+        let input = json!({
+            "fork": reproducer.fork,
+            "transaction": {
+                "to": match tx.to() {
+                    TxKind::Call(to) => Some(to),
+                    TxKind::Create => None,
+                },
+                "sender": reproducer.sender,
+                "gas_limit": tx.gas_limit().to_string(),
+                "gas_price": reproducer.effective_gas_price.to_string(),
+                "value": tx.value().to_string(),
+                "data": format!("0x{}", hex::encode(tx.data())),
+            },
+            "pre": reproducer.prestate.iter().map(|(address, account)| {
+                let storage: BTreeMap<String, String> = account
+                    .storage
+                    .iter()
+                    .map(|(key, value)| (format!("{:#x}", U256::from_big_endian(key.as_bytes())), format!("{value:#x}")))
+                    .collect();
+                (format!("{address:#x}"), json!({
+                    "balance": account.balance.to_string(),
+                    "code": format!("0x{}", hex::encode(&account.code)),
+                    "storage": storage,
+                }))
+            }).collect::<serde_json::Map<_, _>>(),
+        });
+
+        let mut tx_json = serde_json::to_value(tx)?;
+        if let Some(fields) = tx_json.as_object_mut() {
+            fields.insert("from".to_string(), json!(reproducer.sender));
+        }
+
+        let prestate: serde_json::Map<_, _> = reproducer
+            .prestate
+            .iter()
+            .map(|(address, account)| {
+                let storage: BTreeMap<H256, H256> = account
+                    .storage
+                    .iter()
+                    .map(|(key, value)| (*key, H256::from(value.to_big_endian())))
+                    .collect();
+                (
+                    format!("{address:#x}"),
+                    json!({
+                        "balance": format!("{:#x}", account.balance),
+                        "nonce": account.nonce,
+                        "code": format!("0x{}", hex::encode(&account.code)),
+                        "storage": storage,
+                    }),
+                )
+            })
+            .collect();
+
+        let divergence = json!({
+            "block_number": self.block_number,
+            "block_hash": self.block_hash,
+            "tx_index": self.tx_index,
+            "tx_hash": self.tx_hash,
+            "fork": reproducer.fork,
+            "mismatches": self.mismatches,
+            "block_hashes": reproducer.block_hashes,
+        });
+
+        std::fs::create_dir_all(dir)?;
+        let files = [
+            ("input.json", input),
+            ("tx.json", tx_json),
+            ("prestate.json", serde_json::Value::Object(prestate)),
+            ("block.json", serde_json::to_value(&reproducer.header)?),
+            ("divergence.json", divergence),
+        ];
+        let mut paths = Vec::with_capacity(files.len());
+        for (name, contents) in files {
+            let path = dir.join(name);
+            std::fs::write(&path, serde_json::to_string_pretty(&contents)?)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::test_utils::{
+        TestDatabase, address_of, call_tx, cancun_config, secret_key, test_block,
+    };
+    use ethrex_common::types::{AccountState, ChainConfig, Code};
+    use runner::input::RunnerInput;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    const CONTRACT: Address = Address::repeat_byte(0x10);
+    const RECIPIENT: Address = Address::repeat_byte(0x20);
+
+    /// Returns a different hash every time a block hash is read, so LEVM and revm disagree on
+    /// the result of `BLOCKHASH`
+    #[derive(Clone)]
+    struct ChangingBlockHashes {
+        db: TestDatabase,
+        reads: Arc<AtomicU64>,
+    }
+
+    impl VmDatabase for ChangingBlockHashes {
+        fn get_account_state(&self, address: Address) -> Result<Option<AccountState>, EvmError> {
+            self.db.get_account_state(address)
+        }
+
+        fn get_storage_slot(&self, address: Address, key: H256) -> Result<Option<U256>, EvmError> {
+            self.db.get_storage_slot(address, key)
+        }
+
+        fn get_block_hash(&self, _: u64) -> Result<H256, EvmError> {
+            let reads = self.reads.fetch_add(1, Ordering::Relaxed);
+            Ok(H256::from_low_u64_be(reads + 1))
+        }
+
+        fn get_chain_config(&self) -> Result<ChainConfig, EvmError> {
+            self.db.get_chain_config()
+        }
+
+        fn get_account_code(&self, code_hash: H256) -> Result<Code, EvmError> {
+            self.db.get_account_code(code_hash)
+        }
+    }
+
+    /// A transfer followed by a call to CONTRACT, running `code`
+    fn block_and_state(code: &[u8]) -> (Block, TestDatabase) {
+        let key = secret_key(1);
+        let db = TestDatabase::new(cancun_config())
+            .with_funded(address_of(&key), Bytes::new())
+            .with_funded(CONTRACT, Bytes::copy_from_slice(code));
+        let block = test_block(vec![
+            call_tx(&key, 0, RECIPIENT, U256::from(1000), Bytes::new()),
+            call_tx(&key, 1, CONTRACT, U256::one(), Bytes::new()),
+        ]);
+        (block, db)
+    }
+
+    #[test]
+    fn finds_no_divergence_when_both_vms_agree() {
+        // SSTORE(0, BLOCKHASH(0)), LOG0
+        let code = [0x5f, 0x40, 0x5f, 0x55, 0x5f, 0x5f, 0xa0, 0x00];
+        let (block, db) = block_and_state(&code);
+        assert!(execute_block_differential(&block, db).unwrap().is_none());
+    }
+
+    #[test]
+    fn writes_a_reproducer_of_the_first_divergence() {
+        // SSTORE(0, BLOCKHASH(0))
+        let code = [0x5f, 0x40, 0x5f, 0x55, 0x00];
+        let (block, db) = block_and_state(&code);
+        let db = ChangingBlockHashes {
+            db,
+            reads: Arc::default(),
+        };
+        let divergence = execute_block_differential(&block, db).unwrap().unwrap();
+        assert_eq!(divergence.tx_index, 1);
+        assert_eq!(divergence.tx_hash, block.body.transactions[1].hash());
+        assert!(
+            divergence
+                .mismatches
+                .iter()
+                .any(|mismatch| mismatch.starts_with("storage")),
+            "{:?}",
+            divergence.mismatches
+        );
+        assert!(divergence.reproducer.block_hashes.contains_key(&0));
+
+        let dir = tempfile::tempdir().unwrap();
+        divergence.write_reproducer(dir.path()).unwrap();
+        let input = std::fs::read_to_string(dir.path().join("input.json")).unwrap();
+        let input: RunnerInput = serde_json::from_str(&input).unwrap();
+        assert_eq!(input.fork, Fork::Cancun);
+        assert_eq!(input.transaction.to, Some(CONTRACT));
+        assert_eq!(input.transaction.sender, address_of(&secret_key(1)));
+        assert_eq!(input.transaction.value, U256::one());
+        assert_eq!(input.pre[&CONTRACT].code, Bytes::copy_from_slice(&code));
+    }
+
+    #[test]
+    fn refuses_forks_after_osaka() {
+        assert_eq!(spec_id(Fork::Osaka).unwrap(), SpecId::OSAKA);
+        for fork in [Fork::BPO1, Fork::Amsterdam] {
+            assert!(matches!(spec_id(fork), Err(EvmError::InvalidEVM(_))));
+        }
+    }
+}
@@ -0,0 +1,808 @@
+//! Interactive step debugger for LEVM.
+//!
+//! It's attached to a VM as its [StepHook], so it's only called when LEVM is built with the
+//! `debug` feature. Before each opcode it records a snapshot of the current call frame and, when
+//! a stop condition is met, it prompts for commands on the standard input.
+//!
+//! Snapshots store the stack and memory as the change from the previous snapshot, with a full
+//! copy every [CHECKPOINT_INTERVAL] steps to bound the work of rewinding.
+
+use std::{
+    collections::VecDeque,
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use ethrex_common::{Address, H256, U256};
+use ethrex_levm::{
+    debug::{StepAction, StepHook},
+    opcodes::Opcode,
+    vm::VM,
+};
+
+/// Number of steps kept in the history that can be rewound
+const MAX_HISTORY: usize = 100_000;
+
+/// Number of steps between snapshots holding a full copy of the stack and memory
+const CHECKPOINT_INTERVAL: u64 = 1024;
+
+const HELP: &str = "\
+Commands:
+  step, s [n]              execute n opcodes (1 by default)
+  next, n                  execute the next opcode, stepping over calls
+  finish, f                run until the current call returns
+  continue, c              run until a breakpoint is hit
+  break, b pc <pc>         break at a pc of any contract
+  break, b op <OPCODE>     break before an opcode
+  break, b depth <depth>   break when entering a call depth
+  breakpoints, bl          list the breakpoints
+  delete, d <index>        delete a breakpoint
+  where, w                 show the current location
+  stack, st                show the stack, top first
+  memory, m [offset [len]] show the memory
+  storage, sl <slot>       show a storage slot of the current contract (live step only)
+  tstorage, tl <slot>      show a transient storage slot of the current contract (live step only)
+  frames, bt               show the call frames, innermost last (live step only)
+  back, r [n]              rewind n steps through the recorded snapshots
+  forward, fw [n]          move forward n steps through the recorded snapshots
+  help, h                  show this help
+  quit, q                  abort the execution";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    Pc(usize),
+    Opcode(u8),
+    Depth(usize),
+}
+
+impl Breakpoint {
+    fn is_hit(&self, snapshot: &Snapshot, previous: Option<&Snapshot>) -> bool {
+        match *self {
+            Breakpoint::Pc(pc) => snapshot.pc == pc,
+            Breakpoint::Opcode(opcode) => snapshot.opcode == opcode,
+            Breakpoint::Depth(depth) => {
+                snapshot.depth == depth && previous.is_none_or(|previous| previous.depth != depth)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Step(u64),
+    Next,
+    Finish,
+    Continue,
+    Break(Breakpoint),
+    Breakpoints,
+    Delete(usize),
+    Where,
+    Stack,
+    Memory { offset: usize, len: Option<usize> },
+    Storage(U256),
+    TransientStorage(U256),
+    Frames,
+    Back(usize),
+    Forward(usize),
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("step");
+        let args: Vec<&str> = words.collect();
+        let arg = |index: usize| {
+            args.get(index)
+                .copied()
+                .ok_or_else(|| format!("Missing argument for `{command}`"))
+        };
+        let count = |index: usize| args.get(index).map_or(Ok(1), |arg| parse_number(arg));
+
+        Ok(match command {
+            "step" | "s" => Command::Step(u64::try_from(count(0)?).map_err(|e| e.to_string())?),
+            "next" | "n" => Command::Next,
+            "finish" | "f" => Command::Finish,
+            "continue" | "c" => Command::Continue,
+            "break" | "b" => Command::Break(match arg(0)? {
+                "pc" => Breakpoint::Pc(parse_number(arg(1)?)?),
+                "op" => {
+                    let opcode = Opcode::from_str(&arg(1)?.to_uppercase())
+                        .map_err(|_| format!("Unknown opcode {}", arg(1).unwrap_or_default()))?;
+                    Breakpoint::Opcode(opcode.into())
+                }
+                "depth" => Breakpoint::Depth(parse_number(arg(1)?)?),
+                kind => return Err(format!("Unknown breakpoint kind {kind}")),
+            }),
+            "breakpoints" | "bl" => Command::Breakpoints,
+            "delete" | "d" => Command::Delete(parse_number(arg(0)?)?),
+            "where" | "w" => Command::Where,
+            "stack" | "st" => Command::Stack,
+            "memory" | "m" => Command::Memory {
+                offset: args.first().map_or(Ok(0), |arg| parse_number(arg))?,
+                len: args.get(1).map(|arg| parse_number(arg)).transpose()?,
+            },
+            "storage" | "sl" => Command::Storage(parse_u256(arg(0)?)?),
+            "tstorage" | "tl" => Command::TransientStorage(parse_u256(arg(0)?)?),
+            "frames" | "bt" => Command::Frames,
+            "back" | "r" => Command::Back(count(0)?),
+            "forward" | "fw" => Command::Forward(count(0)?),
+            "help" | "h" => Command::Help,
+            "quit" | "q" => Command::Quit,
+            command => return Err(format!("Unknown command `{command}`, try `help`")),
+        })
+    }
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|_| format!("Invalid number {value}"))
+}
+
+fn parse_u256(value: &str) -> Result<U256, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).map_err(|e| e.to_string()),
+        None => U256::from_dec_str(value).map_err(|e| e.to_string()),
+    }
+}
+
+/// A vector recorded in a snapshot, either fully or as its change from the previous snapshot
+#[derive(Debug, Clone)]
+enum Recorded<T> {
+    Full(Vec<T>),
+    /// The previous vector resized to `len`, with `values` written at `offset`
+    Delta {
+        len: usize,
+        offset: usize,
+        values: Vec<T>,
+    },
+}
+
+impl<T: Clone + Default + PartialEq> Recorded<T> {
+    /// Records `current` as its change from `previous`
+    fn delta(previous: &[T], current: &[T]) -> Self {
+        let offset = previous
+            .iter()
+            .zip(current)
+            .take_while(|(previous, current)| previous == current)
+            .count();
+        let end = if previous.len() == current.len() {
+            let unchanged_suffix = previous[offset..]
+                .iter()
+                .rev()
+                .zip(current[offset..].iter().rev())
+                .take_while(|(previous, current)| previous == current)
+                .count();
+            current.len() - unchanged_suffix
+        } else {
+            current.len()
+        };
+        Self::Delta {
+            len: current.len(),
+            offset,
+            values: current[offset..end].to_vec(),
+        }
+    }
+
+    /// Turns the vector of the previous snapshot into the one of this snapshot
+    fn apply(&self, value: &mut Vec<T>) {
+        match self {
+            Self::Full(full) => value.clone_from(full),
+            Self::Delta {
+                len,
+                offset,
+                values,
+            } => {
+                value.resize(*len, T::default());
+                value[*offset..offset + values.len()].clone_from_slice(values);
+            }
+        }
+    }
+
+    /// Stores the full vector, given the previous snapshot, so that one can be dropped
+    fn materialize(&mut self, previous: Self) {
+        if let Self::Full(mut value) = previous
+            && matches!(self, Self::Delta { .. })
+        {
+            self.apply(&mut value);
+            *self = Self::Full(value);
+        }
+    }
+}
+
+/// State of the current call frame before executing an opcode
+#[derive(Debug, Clone)]
+struct Snapshot {
+    step: u64,
+    depth: usize,
+    address: Address,
+    pc: usize,
+    opcode: u8,
+    /// Immediate of `PUSHn` opcodes
+    immediate: Vec<u8>,
+    gas_remaining: i64,
+    /// Stack, bottom first
+    stack: Recorded<U256>,
+    memory: Recorded<u8>,
+}
+
+impl Snapshot {
+    /// Snapshot of the current call frame, whose stack and memory are recorded by the caller
+    fn new(step: u64, vm: &VM<'_>, stack: Recorded<U256>, memory: Recorded<u8>) -> Self {
+        let frame = &vm.current_call_frame;
+        let opcode = frame.next_opcode();
+        let push1 = u8::from(Opcode::PUSH1);
+        let immediate = if (push1..=u8::from(Opcode::PUSH32)).contains(&opcode) {
+            let size = usize::from(opcode - push1 + 1);
+            let start = frame.pc.saturating_add(1);
+            let code = &frame.bytecode.bytecode;
+            code.get(start..start.saturating_add(size).min(code.len()))
+                .unwrap_or_default()
+                .to_vec()
+        } else {
+            Vec::new()
+        };
+        Self {
+            step,
+            depth: frame.depth,
+            address: frame.to,
+            pc: frame.pc,
+            opcode,
+            immediate,
+            gas_remaining: frame.gas_remaining,
+            stack,
+            memory,
+        }
+    }
+
+    fn is_checkpoint(&self) -> bool {
+        matches!(self.memory, Recorded::Full(_))
+    }
+
+    fn location(&self) -> String {
+        let immediate = if self.immediate.is_empty() {
+            String::new()
+        } else {
+            format!(" 0x{}", hex::encode(&self.immediate))
+        };
+        format!(
+            "[step {}] depth {} {:#x} pc {:#x}: {:?}{immediate} (gas {})",
+            self.step,
+            self.depth,
+            self.address,
+            self.pc,
+            Opcode::from(self.opcode),
+            self.gas_remaining
+        )
+    }
+}
+
+/// When the debugger stops to prompt for commands
+#[derive(Debug, Clone, Copy)]
+enum RunMode {
+    /// After the given number of steps
+    Step(u64),
+    /// When returning to the given depth or above
+    StepOver(usize),
+    /// When returning above the given depth
+    Finish(usize),
+    /// Only at breakpoints
+    Continue,
+}
+
+pub struct Debugger<R, W> {
+    input: R,
+    output: W,
+    breakpoints: Vec<Breakpoint>,
+    mode: RunMode,
+    history: VecDeque<Snapshot>,
+    /// Position in the history when rewound, `None` when at the live step
+    cursor: Option<usize>,
+    steps: u64,
+    /// Stack of the live step, bottom first
+    stack: Vec<U256>,
+    /// Memory of the live step
+    memory: Vec<u8>,
+}
+
+impl Debugger<std::io::StdinLock<'static>, std::io::Stdout> {
+    /// Debugger reading commands from the standard input, stopping at the first opcode
+    pub fn stdio() -> Self {
+        Self::new(std::io::stdin().lock(), std::io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            breakpoints: Vec::new(),
+            mode: RunMode::Step(1),
+            history: VecDeque::new(),
+            cursor: None,
+            steps: 0,
+            stack: Vec::new(),
+            memory: Vec::new(),
+        }
+    }
+
+    /// Records the state of the VM before executing its next opcode
+    fn record(&mut self, vm: &VM<'_>) -> Snapshot {
+        self.steps += 1;
+        let frame = &vm.current_call_frame;
+        let stack: Vec<U256> = frame.stack.values[frame.stack.offset..]
+            .iter()
+            .rev()
+            .copied()
+            .collect();
+        let memory = frame.memory.to_vec();
+        let (stack_record, memory_record) =
+            if self.history.is_empty() || self.steps % CHECKPOINT_INTERVAL == 0 {
+                (
+                    Recorded::Full(stack.clone()),
+                    Recorded::Full(memory.clone()),
+                )
+            } else {
+                (
+                    Recorded::delta(&self.stack, &stack),
+                    Recorded::delta(&self.memory, &memory),
+                )
+            };
+        self.stack = stack;
+        self.memory = memory;
+        Snapshot::new(self.steps, vm, stack_record, memory_record)
+    }
+
+    fn push_history(&mut self, snapshot: Snapshot) {
+        if self.history.len() == MAX_HISTORY
+            && let Some(oldest) = self.history.pop_front()
+            && let Some(next) = self.history.front_mut()
+        {
+            // The oldest snapshot is always a full one
+            next.stack.materialize(oldest.stack);
+            next.memory.materialize(oldest.memory);
+        }
+        self.history.push_back(snapshot);
+    }
+
+    fn should_stop(&mut self, snapshot: &Snapshot) -> bool {
+        let previous = self.history.back();
+        let at_breakpoint = || {
+            self.breakpoints
+                .iter()
+                .any(|breakpoint| breakpoint.is_hit(snapshot, previous))
+        };
+        match &mut self.mode {
+            RunMode::Step(remaining) => {
+                *remaining = remaining.saturating_sub(1);
+                *remaining == 0 || at_breakpoint()
+            }
+            RunMode::StepOver(depth) => snapshot.depth <= *depth || at_breakpoint(),
+            RunMode::Finish(depth) => snapshot.depth < *depth || at_breakpoint(),
+            RunMode::Continue => at_breakpoint(),
+        }
+    }
+
+    /// Snapshot being inspected, either a rewound one or the live one
+    fn current(&self) -> Option<&Snapshot> {
+        match self.cursor {
+            Some(index) => self.history.get(index),
+            None => self.history.back(),
+        }
+    }
+
+    /// Stack, top first, and memory of the snapshot being inspected
+    fn current_state(&self) -> (Vec<U256>, Vec<u8>) {
+        let (mut stack, memory) = match self.cursor {
+            None => (self.stack.clone(), self.memory.clone()),
+            Some(index) => {
+                // Replays the changes from the last full snapshot
+                let checkpoint = (0..=index)
+                    .rev()
+                    .find(|index| self.history[*index].is_checkpoint())
+                    .unwrap_or_default();
+                let mut stack = Vec::new();
+                let mut memory = Vec::new();
+                for snapshot in self.history.range(checkpoint..=index) {
+                    snapshot.stack.apply(&mut stack);
+                    snapshot.memory.apply(&mut memory);
+                }
+                (stack, memory)
+            }
+        };
+        stack.reverse();
+        (stack, memory)
+    }
+
+    fn print(&mut self, text: impl AsRef<str>) {
+        // Failing to write to the terminal isn't worth aborting the execution
+        let _ = writeln!(self.output, "{}", text.as_ref());
+    }
+
+    fn print_location(&mut self) {
+        let rewound = self.cursor.is_some();
+        if let Some(location) = self.current().map(Snapshot::location) {
+            let suffix = if rewound { " (rewound)" } else { "" };
+            self.print(format!("{location}{suffix}"));
+        }
+    }
+
+    /// Moves the cursor through the history, stopping at the live step
+    fn move_cursor(&mut self, forward: bool, count: usize) {
+        let live = self.history.len().saturating_sub(1);
+        let position = self.cursor.unwrap_or(live);
+        let position = if forward {
+            position.saturating_add(count)
+        } else {
+            position.saturating_sub(count)
+        };
+        self.cursor = (position < live).then_some(position);
+    }
+
+    /// Reads and runs commands until one resumes or aborts the execution
+    fn prompt(&mut self, vm: &mut VM<'_>) -> StepAction {
+        self.print_location();
+        loop {
+            let _ = write!(self.output, "(levm) ");
+            let _ = self.output.flush();
+            let mut line = String::new();
+            // Running to completion when there's no input left
+            if self.input.read_line(&mut line).unwrap_or(0) == 0 {
+                self.mode = RunMode::Continue;
+                self.breakpoints.clear();
+                return StepAction::Continue;
+            }
+            let command = match line.trim().parse::<Command>() {
+                Ok(command) => command,
+                Err(error) => {
+                    self.print(error);
+                    continue;
+                }
+            };
+            if let Some(action) = self.run_command(command, vm) {
+                return action;
+            }
+        }
+    }
+
+    /// Runs a command, returns what to do with the execution if it shouldn't keep prompting
+    fn run_command(&mut self, command: Command, vm: &mut VM<'_>) -> Option<StepAction> {
+        let depth = vm.current_call_frame.depth;
+        let address = vm.current_call_frame.to;
+        match command {
+            Command::Step(count) if self.cursor.is_some() => {
+                self.move_cursor(true, usize::try_from(count).unwrap_or(usize::MAX));
+                self.print_location();
+            }
+            Command::Step(count) => {
+                self.mode = RunMode::Step(count);
+                return Some(StepAction::Continue);
+            }
+            Command::Next | Command::Finish | Command::Continue if self.cursor.is_some() => {
+                self.print("Rewound, move forward to the live step to resume the execution");
+            }
+            Command::Storage(_) | Command::TransientStorage(_) | Command::Frames
+                if self.cursor.is_some() =>
+            {
+                self.print("Rewound, the state is only shown at the live step");
+            }
+            Command::Next => {
+                self.mode = RunMode::StepOver(depth);
+                return Some(StepAction::Continue);
+            }
+            Command::Finish => {
+                self.mode = RunMode::Finish(depth);
+                return Some(StepAction::Continue);
+            }
+            Command::Continue => {
+                self.mode = RunMode::Continue;
+                return Some(StepAction::Continue);
+            }
+            Command::Break(breakpoint) => {
+                self.breakpoints.push(breakpoint);
+                self.print(format!(
+                    "Breakpoint {}: {breakpoint:?}",
+                    self.breakpoints.len() - 1
+                ));
+            }
+            Command::Breakpoints => {
+                let list: Vec<String> = self
+                    .breakpoints
+                    .iter()
+                    .enumerate()
+                    .map(|(index, breakpoint)| format!("{index}: {breakpoint:?}"))
+                    .collect();
+                self.print(list.join("\n"));
+            }
+            Command::Delete(index) => {
+                if index < self.breakpoints.len() {
+                    self.breakpoints.remove(index);
+                } else {
+                    self.print(format!("No breakpoint {index}"));
+                }
+            }
+            Command::Where => self.print_location(),
+            Command::Stack => {
+                let (stack, _) = self.current_state();
+                let stack: Vec<String> = stack
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| format!("{index:>4}: {value:#x}"))
+                    .collect();
+                self.print(stack.join("\n"));
+            }
+            Command::Memory { offset, len } => {
+                let (_, memory) = self.current_state();
+                let end = len
+                    .map_or(memory.len(), |len| offset.saturating_add(len))
+                    .min(memory.len());
+                let memory: Vec<String> = memory
+                    .get(offset..end)
+                    .unwrap_or_default()
+                    .chunks(32)
+                    .enumerate()
+                    .map(|(index, word)| {
+                        let word_offset = offset.saturating_add(index * 32);
+                        format!("{word_offset:#06x}: {}", hex::encode(word))
+                    })
+                    .collect();
+                self.print(memory.join("\n"));
+            }
+            Command::Storage(slot) => {
+                let key = H256::from(slot.to_big_endian());
+                match vm.db.get_storage_value(address, key) {
+                    Ok(value) => self.print(format!("{value:#x}")),
+                    Err(error) => self.print(format!("Failed to read storage: {error}")),
+                }
+            }
+            Command::TransientStorage(slot) => {
+                let value = vm.substate.get_transient(&address, &slot);
+                self.print(format!("{value:#x}"));
+            }
+            Command::Frames => {
+                let frames: Vec<String> = vm
+                    .call_frames
+                    .iter()
+                    .chain(std::iter::once(&vm.current_call_frame))
+                    .map(|frame| {
+                        format!(
+                            "depth {} {:#x} (code {:#x}, caller {:#x}) pc {:#x} gas {}{}{}",
+                            frame.depth,
+                            frame.to,
+                            frame.code_address,
+                            frame.msg_sender,
+                            frame.pc,
+                            frame.gas_remaining,
+                            if frame.is_create { " create" } else { "" },
+                            if frame.is_static { " static" } else { "" },
+                        )
+                    })
+                    .collect();
+                self.print(frames.join("\n"));
+            }
+            Command::Back(count) => {
+                self.move_cursor(false, count);
+                self.print_location();
+            }
+            Command::Forward(count) => {
+                self.move_cursor(true, count);
+                self.print_location();
+            }
+            Command::Help => self.print(HELP),
+            Command::Quit => {
+                self.print("Execution aborted");
+                return Some(StepAction::Abort);
+            }
+        }
+        None
+    }
+}
+
+impl<R: BufRead, W: Write> StepHook for Debugger<R, W> {
+    fn on_step(&mut self, vm: &mut VM<'_>) -> StepAction {
+        let snapshot = self.record(vm);
+        let stop = self.should_stop(&snapshot);
+        self.push_history(snapshot);
+        if stop {
+            self.prompt(vm)
+        } else {
+            StepAction::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!("".parse(), Ok(Command::Step(1)));
+        assert_eq!("s 0x10".parse(), Ok(Command::Step(16)));
+        assert_eq!(
+            "b op sstore".parse(),
+            Ok(Command::Break(Breakpoint::Opcode(0x55)))
+        );
+        assert_eq!("b pc 26".parse(), Ok(Command::Break(Breakpoint::Pc(26))));
+        assert_eq!(
+            "m 0x20".parse(),
+            Ok(Command::Memory {
+                offset: 32,
+                len: None
+            })
+        );
+        assert_eq!("sl 0x01".parse(), Ok(Command::Storage(U256::one())));
+        assert!("b op NOTANOPCODE".parse::<Command>().is_err());
+        assert!("b".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn replay_recorded_changes() {
+        let values: [&[u8]; 6] = [
+            &[1, 2, 3],
+            &[1, 5, 3],
+            &[1, 5, 3],
+            &[1, 5, 3, 0, 0],
+            &[1],
+            &[7, 5],
+        ];
+        let mut previous: &[u8] = &[];
+        let records: Vec<_> = values
+            .iter()
+            .map(|value| {
+                let record = Recorded::delta(previous, value);
+                previous = value;
+                record
+            })
+            .collect();
+
+        let mut replayed = Vec::new();
+        for (record, value) in records.iter().zip(values) {
+            record.apply(&mut replayed);
+            assert_eq!(replayed, value);
+        }
+
+        // Once the previous snapshot is dropped, a delta holds the full vector
+        let mut second = records[1].clone();
+        second.materialize(Recorded::Full(values[0].to_vec()));
+        assert!(matches!(second, Recorded::Full(value) if value == values[1]));
+    }
+
+    #[cfg(feature = "debugger")]
+    mod sessions {
+        use super::*;
+        use crate::t8n::{chain_config_for_fork, database::T8nDatabase};
+        use bytes::Bytes;
+        use ethrex_common::types::{
+            BlockHeader, GenesisAccount, LegacyTransaction, Transaction, TxKind,
+        };
+        use ethrex_levm::{
+            errors::{ExecutionReport, InternalError, VMError},
+            tracing::LevmCallTracer,
+            vm::VMType,
+        };
+        use ethrex_vm::{Evm, backends::levm::LEVM};
+        use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+        const SENDER: Address = Address::repeat_byte(0x01);
+        const CONTRACT: Address = Address::repeat_byte(0x10);
+        const CALLEE: Address = Address::repeat_byte(0x20);
+
+        /// Output of the debugger, still readable once it's moved into the VM
+        #[derive(Clone, Default)]
+        struct Output(Rc<RefCell<Vec<u8>>>);
+
+        impl Write for Output {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        fn account(code: &[u8]) -> GenesisAccount {
+            GenesisAccount {
+                code: Bytes::copy_from_slice(code),
+                storage: Default::default(),
+                balance: U256::from(10).pow(U256::from(18)),
+                nonce: 0,
+            }
+        }
+
+        /// Calls a contract that writes 0x2a to its memory and calls another one writing 0x07,
+        /// with the debugger reading `commands`. Returns the result and what each command printed.
+        fn debug(commands: &[&str]) -> (Result<ExecutionReport, VMError>, Vec<String>) {
+            // MSTORE(0, 0x2a), CALL(GAS, CALLEE, 0, 0, 0, 0, 0), POP, STOP
+            let mut code = vec![0x60, 0x2a, 0x5f, 0x52, 0x5f, 0x5f, 0x5f, 0x5f, 0x5f, 0x73];
+            code.extend_from_slice(CALLEE.as_bytes());
+            code.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x00]);
+            // MSTORE(0, 0x07), STOP
+            let callee = [0x60, 0x07, 0x5f, 0x52, 0x00];
+            let alloc = BTreeMap::from([
+                (SENDER, account(&[])),
+                (CONTRACT, account(&code)),
+                (CALLEE, account(&callee)),
+            ]);
+            let chain_config = chain_config_for_fork("Cancun", 1).unwrap();
+            let mut evm = Evm::new_for_l1(T8nDatabase::new(alloc, BTreeMap::new(), chain_config));
+            let tx = Transaction::LegacyTransaction(LegacyTransaction {
+                gas: 100_000,
+                to: TxKind::Call(CONTRACT),
+                ..Default::default()
+            });
+            let header = BlockHeader {
+                gas_limit: 30_000_000,
+                ..Default::default()
+            };
+            let env = LEVM::setup_env(&tx, SENDER, &header, &evm.db, VMType::L1).unwrap();
+            let mut vm = VM::new(
+                env,
+                &mut evm.db,
+                &tx,
+                LevmCallTracer::disabled(),
+                VMType::L1,
+            )
+            .unwrap();
+
+            let input = commands.join("\n").into_bytes();
+            let output = Output::default();
+            let debugger = Debugger::new(std::io::Cursor::new(input), output.clone());
+            vm.debug_mode.step_hook = Some(Box::new(debugger));
+            let result = vm.execute();
+
+            let output = String::from_utf8(output.0.take()).unwrap();
+            // The first entry is the location of the first opcode, printed before any command
+            let printed = output
+                .split("(levm) ")
+                .skip(1)
+                .map(|printed| printed.trim_end().to_string())
+                .collect();
+            (result, printed)
+        }
+
+        #[test]
+        fn stop_at_breakpoints() {
+            let (result, printed) =
+                debug(&["b pc 0x1f", "c", "b depth 1", "c", "b op mstore", "c", "c"]);
+            assert!(result.unwrap().is_success());
+            assert!(printed[1].contains(&format!("depth 0 {CONTRACT:#x} pc 0x1f: CALL")));
+            assert!(printed[3].contains(&format!("depth 1 {CALLEE:#x} pc 0x0: PUSH1 0x07")));
+            assert!(printed[5].contains(&format!("depth 1 {CALLEE:#x} pc 0x3: MSTORE")));
+            // The last `continue` runs to completion, the opcode breakpoint isn't hit again
+            assert_eq!(printed.len(), 7);
+        }
+
+        #[test]
+        fn step_and_rewind() {
+            let (result, printed) =
+                debug(&["s 4", "back 3", "st", "m", "sl 0", "fw 3", "st", "m", "q"]);
+            assert_eq!(
+                result.unwrap_err(),
+                VMError::Internal(InternalError::ExecutionAborted)
+            );
+
+            assert!(printed[0].contains(&format!("[step 5] depth 0 {CONTRACT:#x} pc 0x5: PUSH0")));
+            assert!(printed[1].contains("[step 2]") && printed[1].ends_with("(rewound)"));
+            // Before MSTORE(0, 0x2a)
+            assert_eq!(printed[2], "   0: 0x2a");
+            assert_eq!(printed[3], "");
+            assert_eq!(
+                printed[4],
+                "Rewound, the state is only shown at the live step"
+            );
+            assert!(printed[5].contains("[step 5]") && !printed[5].ends_with("(rewound)"));
+            assert_eq!(printed[6], "   0: 0x0");
+            assert_eq!(printed[7], format!("0x0000: {}2a", "00".repeat(31)));
+            assert_eq!(printed[8], "Execution aborted");
+        }
+    }
+}
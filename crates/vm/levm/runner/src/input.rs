@@ -4,7 +4,7 @@ use ethrex_common::serde_utils::bytes::deserialize;
 use ethrex_common::serde_utils::u64;
 use ethrex_common::serde_utils::u256;
 use ethrex_common::types::Code;
-use ethrex_common::types::{Account, AccountInfo};
+use ethrex_common::types::{Account, AccountInfo, GenesisAccount};
 use ethrex_common::{Address, U256, types::Fork};
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -52,6 +52,39 @@ impl From<InputAccount> for Account {
     }
 }
 
+/// Account as dumped by the prestate tracer of `debug_traceTransaction`
+#[derive(Deserialize, Debug, Clone)]
+pub struct PrestateAccount {
+    #[serde(default, deserialize_with = "u256::deser_hex_or_dec_str")]
+    pub balance: U256,
+    #[serde(default)]
+    pub nonce: u64,
+    #[serde(default, deserialize_with = "deserialize")]
+    pub code: Bytes,
+    #[serde(default)]
+    pub storage: HashMap<H256, H256>,
+}
+
+impl From<PrestateAccount> for GenesisAccount {
+    fn from(account: PrestateAccount) -> Self {
+        GenesisAccount {
+            code: account.code,
+            storage: account
+                .storage
+                .into_iter()
+                .map(|(k, v)| {
+                    (
+                        U256::from_big_endian(k.as_bytes()),
+                        U256::from_big_endian(v.as_bytes()),
+                    )
+                })
+                .collect(),
+            balance: account.balance,
+            nonce: account.nonce,
+        }
+    }
+}
+
 impl Default for InputAccount {
     fn default() -> Self {
         InputAccount {
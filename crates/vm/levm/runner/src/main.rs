@@ -1,11 +1,11 @@
 use bytes::Bytes;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use env_logger::Env;
 use ethrex_blockchain::vm::StoreVmDatabase;
 use ethrex_common::{
     Address, H160, U256,
     constants::EMPTY_TRIE_HASH,
-    types::{Account, BlockHeader, Code, LegacyTransaction, Transaction},
+    types::{Account, BlockHeader, Code, LegacyTransaction, Transaction, TxKind},
 };
 use ethrex_levm::{
     EVMConfig, Environment,
@@ -16,11 +16,18 @@ use ethrex_levm::{
     vm::{VM, VMType},
 };
 use ethrex_storage::Store;
-use ethrex_vm::DynVmDatabase;
+use ethrex_vm::{DynVmDatabase, Evm, backends::levm::LEVM};
 use log::{debug, error, info};
 use num_bigint::BigUint;
 use num_traits::Num;
-use runner::input::{InputAccount, InputTransaction, RunnerInput};
+use runner::{
+    debugger::Debugger,
+    input::{InputAccount, PrestateAccount, RunnerInput},
+    t8n::{
+        self, BlockBuilderArgs, TransactionArgs, TransitionArgs, chain_config_for_fork,
+        database::T8nDatabase,
+    },
+};
 use rustc_hash::FxHashMap;
 use std::{collections::BTreeMap, io::Write};
 use std::{
@@ -33,6 +40,9 @@ const COINBASE: H160 = H160([0x77; 20]);
 
 #[derive(Parser)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(long, short, help = "Path to the input JSON file")]
     input: Option<String>,
 
@@ -48,6 +58,52 @@ struct Cli {
         help = "Converts mnemonics file into a bytecode file"
     )]
     emit_bytes: Option<String>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        help = "Step through the execution with an interactive debugger (requires the `debugger` feature)"
+    )]
+    debug: bool,
+
+    #[arg(
+        long,
+        requires = "prestate",
+        conflicts_with_all = ["input", "code"],
+        help = "Path to a signed transaction JSON, as returned by eth_getTransactionByHash, to execute instead of the input file"
+    )]
+    tx: Option<String>,
+
+    #[arg(
+        long,
+        help = "Path to the pre-state of the transaction, as dumped by the prestateTracer"
+    )]
+    prestate: Option<String>,
+
+    #[arg(
+        long,
+        help = "Path to the JSON header of the block the transaction is executed in"
+    )]
+    block: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "Prague",
+        help = "Fork the transaction given with --tx is executed in"
+    )]
+    fork: String,
+}
+
+/// Tools compatible with geth's `evm t8n`, `evm t9n` and `evm b11r`, used by
+/// execution-spec-tests to fill fixtures
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Applies transactions on top of a pre-state")]
+    T8n(TransitionArgs),
+    #[command(alias = "t9n", about = "Checks the validity of transactions")]
+    T11n(TransactionArgs),
+    #[command(about = "Assembles a block from its header and body")]
+    B11r(BlockBuilderArgs),
 }
 
 fn main() {
@@ -58,6 +114,24 @@ fn main() {
         .format(|buf, record| writeln!(buf, "{}", record.args()))
         .init();
 
+    if cli.debug && !cfg!(feature = "debugger") {
+        error!("The debugger requires building the runner with the `debugger` feature");
+        std::process::exit(1);
+    }
+
+    if let Some(command) = cli.command {
+        let result = match command {
+            Command::T8n(args) => t8n::run_transition(args),
+            Command::T11n(args) => t8n::run_transaction(args),
+            Command::B11r(args) => t8n::run_block_builder(args),
+        };
+        if let Err(err) = result {
+            error!("{err}");
+            std::process::exit(err.exit_code());
+        }
+        return;
+    }
+
     // Subcommand for just converting mnemonics to bytecode without executing
     if let Some(mnemonics_path) = cli.emit_bytes {
         let file_content =
@@ -82,6 +156,18 @@ fn main() {
         return;
     }
 
+    if let Some(tx_path) = cli.tx {
+        let prestate_path = cli.prestate.expect("--prestate is required by --tx");
+        run_with_prestate(
+            &tx_path,
+            &prestate_path,
+            cli.block.as_deref(),
+            &cli.fork,
+            cli.debug,
+        );
+        return;
+    }
+
     // Parse input
     // Input is mutable just to assign bytecode to the transaction recipient if provided
     let mut runner_input: RunnerInput = if let Some(input_file_path) = cli.input {
@@ -175,6 +261,86 @@ fn main() {
         .memory
         .store_data(0, &runner_input.initial_memory);
 
+    execute(vm, cli.debug);
+
+    // Print Accounts diff
+    compare_initial_and_current_accounts(
+        db.initial_accounts_state,
+        db.current_accounts_state,
+        runner_input.transaction.sender,
+        runner_input.transaction.to,
+    );
+}
+
+/// Executes a transaction on top of the pre-state dumped by a prestate tracer
+fn run_with_prestate(
+    tx_path: &str,
+    prestate_path: &str,
+    block_path: Option<&str>,
+    fork: &str,
+    debug: bool,
+) {
+    let tx_json: serde_json::Value = read_json(tx_path);
+    let tx: Transaction =
+        serde_json::from_value(tx_json.clone()).expect("Failed to parse transaction");
+    let sender = match tx_json.get("from") {
+        Some(from) => serde_json::from_value(from.clone()).expect("Invalid sender"),
+        None => tx.sender().expect("Failed to recover the sender"),
+    };
+    let prestate: BTreeMap<Address, PrestateAccount> = read_json(prestate_path);
+    let header = match block_path {
+        Some(path) => read_json(path),
+        None => BlockHeader {
+            gas_limit: tx.gas_limit(),
+            ..Default::default()
+        },
+    };
+
+    let chain_config = chain_config_for_fork(fork, tx.chain_id().unwrap_or(1))
+        .unwrap_or_else(|err| panic!("{err}"));
+    let alloc = prestate
+        .into_iter()
+        .map(|(address, account)| (address, account.into()))
+        .collect();
+    let mut evm = Evm::new_for_l1(T8nDatabase::new(alloc, BTreeMap::new(), chain_config));
+    let env = LEVM::setup_env(&tx, sender, &header, &evm.db, VMType::L1)
+        .expect("Failed to set up the environment");
+    let vm = VM::new(
+        env,
+        &mut evm.db,
+        &tx,
+        LevmCallTracer::disabled(),
+        VMType::L1,
+    )
+    .expect("Failed to initialize VM");
+
+    execute(vm, debug);
+
+    let recipient = match tx.to() {
+        TxKind::Call(to) => Some(to),
+        TxKind::Create => None,
+    };
+    compare_initial_and_current_accounts(
+        evm.db.initial_accounts_state,
+        evm.db.current_accounts_state,
+        sender,
+        recipient,
+    );
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> T {
+    let file = File::open(path).unwrap_or_else(|_| panic!("File '{}' not found", path));
+    serde_json::from_reader(BufReader::new(file))
+        .unwrap_or_else(|err| panic!("Failed to parse '{}': {}", path, err))
+}
+
+/// Executes the transaction, optionally stepping through it with the debugger, and prints the
+/// result and the final stack and memory
+fn execute(mut vm: VM<'_>, debug: bool) {
+    if debug {
+        vm.debug_mode.step_hook = Some(Box::new(Debugger::stdio()));
+    }
+
     // Execute Transaction
     let result = vm.execute();
 
@@ -195,29 +361,22 @@ fn main() {
             .map(|value| format!("0x{:x}", value))
             .collect::<Vec<_>>()
     );
-    let final_memory: Vec<u8> = callframe.memory.buffer.borrow()[0..callframe.memory.len].to_vec();
-    info!("Final Memory: 0x{}", hex::encode(final_memory));
-
-    // Print Accounts diff
-    compare_initial_and_current_accounts(
-        db.initial_accounts_state,
-        db.current_accounts_state,
-        &runner_input.transaction,
-    );
+    info!("Final Memory: 0x{}", hex::encode(callframe.memory.to_vec()));
 }
 
 /// Prints on screen difference between initial state and current one.
 fn compare_initial_and_current_accounts(
     initial_accounts: FxHashMap<Address, LevmAccount>,
     current_accounts: FxHashMap<Address, LevmAccount>,
-    transaction: &InputTransaction,
+    sender: Address,
+    recipient: Option<Address>,
 ) {
     info!("\nState Diff:");
     for (addr, acc) in current_accounts {
         // Instead of the if-else chain
         let acc_type = match &addr {
-            a if *a == transaction.sender => "Sender ",
-            a if Some(*a) == transaction.to => "Recipient ",
+            a if *a == sender => "Sender ",
+            a if Some(*a) == recipient => "Recipient ",
             a if *a == COINBASE => "Coinbase ",
             _ => "",
         };
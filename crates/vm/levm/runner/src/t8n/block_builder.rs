@@ -0,0 +1,56 @@
+use ethrex_common::{
+    types::{
+        Block, BlockBody, BlockHeader, Transaction, Withdrawal, compute_transactions_root,
+        compute_withdrawals_root,
+    },
+    utils::keccak,
+};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+use serde_json::{Value, json};
+
+use super::{T8nError, decode_hex};
+
+/// Assembles a block from its header and body, filling in the header fields derived from the
+/// body. Returns the RLP encoding of the block and its hash.
+pub fn build(
+    header: BlockHeader,
+    ommers: Vec<BlockHeader>,
+    transactions: Vec<Transaction>,
+    withdrawals: Option<Vec<Withdrawal>>,
+) -> Value {
+    let header = BlockHeader {
+        ommers_hash: keccak(ommers.encode_to_vec()),
+        transactions_root: compute_transactions_root(&transactions),
+        withdrawals_root: withdrawals
+            .as_deref()
+            .map(compute_withdrawals_root)
+            .or(header.withdrawals_root),
+        hash: Default::default(),
+        ..header
+    };
+    let block = Block::new(
+        header,
+        BlockBody {
+            transactions,
+            ommers,
+            withdrawals,
+        },
+    );
+    json!({
+        "rlp": format!("0x{}", hex::encode(block.encode_to_vec())),
+        "hash": block.hash(),
+    })
+}
+
+/// Reads the ommers, given as a list of RLP encoded headers
+pub fn parse_ommers(value: Value) -> Result<Vec<BlockHeader>, T8nError> {
+    let ommers: Option<Vec<String>> = serde_json::from_value(value)?;
+    ommers
+        .unwrap_or_default()
+        .iter()
+        .map(|ommer| {
+            BlockHeader::decode(&decode_hex(ommer)?)
+                .map_err(|error| T8nError::Rlp(error.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use ethrex_common::{
+    Address, H256, U256,
+    constants::EMPTY_KECCACK_HASH,
+    types::{AccountState, AccountUpdate, ChainConfig, Code, GenesisAccount},
+};
+use ethrex_vm::{EvmError, VmDatabase};
+use rustc_hash::FxHashMap;
+
+/// State of the accounts as given by and returned to the tests, keyed by address
+pub type Alloc = BTreeMap<Address, GenesisAccount>;
+
+/// Pre-state of a transition, served to LEVM as if it were the state of the parent block
+#[derive(Clone)]
+pub struct T8nDatabase {
+    alloc: Arc<Alloc>,
+    codes: Arc<FxHashMap<H256, Code>>,
+    block_hashes: Arc<BTreeMap<u64, H256>>,
+    chain_config: ChainConfig,
+    /// Number of the first block whose hash was requested but not given
+    missing_block_hash: Arc<Mutex<Option<u64>>>,
+}
+
+impl T8nDatabase {
+    pub fn new(alloc: Alloc, block_hashes: BTreeMap<u64, H256>, chain_config: ChainConfig) -> Self {
+        let codes = alloc
+            .values()
+            .map(|account| {
+                let code = Code::from_bytecode(account.code.clone());
+                (code.hash, code)
+            })
+            .collect();
+        Self {
+            alloc: Arc::new(alloc),
+            codes: Arc::new(codes),
+            block_hashes: Arc::new(block_hashes),
+            chain_config,
+            missing_block_hash: Default::default(),
+        }
+    }
+
+    pub fn missing_block_hash(&self) -> Option<u64> {
+        self.missing_block_hash
+            .lock()
+            .ok()
+            .and_then(|number| *number)
+    }
+
+    /// Returns the post-state resulting from applying the updates to the pre-state
+    pub fn apply_updates(&self, updates: Vec<AccountUpdate>) -> Alloc {
+        let mut alloc = (*self.alloc).clone();
+        for update in updates {
+            if update.removed {
+                alloc.remove(&update.address);
+                continue;
+            }
+            let account = alloc
+                .entry(update.address)
+                .or_insert_with(|| GenesisAccount {
+                    code: Bytes::new(),
+                    storage: Default::default(),
+                    balance: U256::zero(),
+                    nonce: 0,
+                });
+            if update.removed_storage {
+                account.storage.clear();
+            }
+            if let Some(info) = update.info {
+                account.balance = info.balance;
+                account.nonce = info.nonce;
+            }
+            if let Some(code) = update.code {
+                account.code = code.bytecode;
+            }
+            for (key, value) in update.added_storage {
+                let key = U256::from_big_endian(key.as_bytes());
+                if value.is_zero() {
+                    account.storage.remove(&key);
+                } else {
+                    account.storage.insert(key, value);
+                }
+            }
+        }
+        alloc
+    }
+}
+
+impl VmDatabase for T8nDatabase {
+    fn get_account_state(&self, address: Address) -> Result<Option<AccountState>, EvmError> {
+        Ok(self.alloc.get(&address).map(AccountState::from))
+    }
+
+    fn get_storage_slot(&self, address: Address, key: H256) -> Result<Option<U256>, EvmError> {
+        let key = U256::from_big_endian(key.as_bytes());
+        Ok(self
+            .alloc
+            .get(&address)
+            .and_then(|account| account.storage.get(&key))
+            .copied())
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<H256, EvmError> {
+        match self.block_hashes.get(&block_number) {
+            Some(hash) => Ok(*hash),
+            None => {
+                if let Ok(mut missing) = self.missing_block_hash.lock() {
+                    missing.get_or_insert(block_number);
+                }
+                Err(EvmError::DB(format!(
+                    "Missing hash of block {block_number}"
+                )))
+            }
+        }
+    }
+
+    fn get_chain_config(&self) -> Result<ChainConfig, EvmError> {
+        Ok(self.chain_config.clone())
+    }
+
+    fn get_account_code(&self, code_hash: H256) -> Result<Code, EvmError> {
+        if code_hash == *EMPTY_KECCACK_HASH {
+            return Ok(Code::default());
+        }
+        self.codes
+            .get(&code_hash)
+            .cloned()
+            .ok_or_else(|| EvmError::DB(format!("Code not found for hash: {code_hash:?}")))
+    }
+}
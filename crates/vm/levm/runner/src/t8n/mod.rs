@@ -0,0 +1,613 @@
+//! State transition (`t8n`), transaction (`t11n`) and block builder (`b11r`) tools backed by
+//! LEVM, compatible with the ones of geth's `evm` binary so they can be used by
+//! execution-spec-tests to fill fixtures.
+//!
+//! Inputs can be read from files or, when the path is `stdin`, from a single JSON object read from
+//! the standard input. Likewise, outputs whose path is `stdout` are gathered in a single JSON
+//! object written to the standard output.
+
+pub mod block_builder;
+pub mod database;
+pub mod transaction;
+pub mod transition;
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use clap::Args;
+use ethrex_common::{
+    H160, U256,
+    types::{BlobSchedule, BlockHeader, ChainConfig, Fork, Transaction, Withdrawal},
+    utils::keccak,
+};
+use ethrex_rlp::{decode::RLPDecode, encode::PayloadRLPEncode, structs::Encoder};
+use secp256k1::{Message, SECP256K1, SecretKey};
+use serde_json::{Map, Value, json};
+
+/// Timestamp at which the second fork of a transition fork (e.g. `ShanghaiToCancunAtTime15k`)
+/// is activated
+const TRANSITION_TIMESTAMP: u64 = 15_000;
+
+/// Mainnet deposit contract, used by the tests of EIP-6110
+const DEPOSIT_CONTRACT_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x21, 0x9a, 0xb5, 0x40, 0x35, 0x6c, 0xbb, 0x83, 0x9c, 0xbe, 0x05, 0x30,
+    0x3d, 0x77, 0x05, 0xfa,
+]);
+
+/// Errors of the tools, each one maps to the exit code geth uses for it
+#[derive(Debug, thiserror::Error)]
+pub enum T8nError {
+    #[error("EVM error: {0}")]
+    Evm(String),
+    #[error("Config error: {0}")]
+    Config(String),
+    #[error("Missing block hash: {0}")]
+    MissingBlockHash(u64),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("RLP error: {0}")]
+    Rlp(String),
+}
+
+impl T8nError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            T8nError::Evm(_) => 2,
+            T8nError::Config(_) => 3,
+            T8nError::MissingBlockHash(_) => 4,
+            T8nError::Json(_) => 10,
+            T8nError::Io(_) => 11,
+            T8nError::Rlp(_) => 12,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct TransitionArgs {
+    #[arg(long = "input.alloc", default_value = "alloc.json")]
+    pub input_alloc: String,
+    #[arg(long = "input.env", default_value = "env.json")]
+    pub input_env: String,
+    #[arg(long = "input.txs", default_value = "txs.json")]
+    pub input_txs: String,
+    #[arg(long = "output.basedir", default_value = "")]
+    pub output_basedir: String,
+    #[arg(long = "output.alloc", default_value = "alloc.json")]
+    pub output_alloc: String,
+    #[arg(long = "output.result", default_value = "result.json")]
+    pub output_result: String,
+    #[arg(long = "output.body", default_value = "")]
+    pub output_body: String,
+    #[arg(long = "state.fork", default_value = "Prague")]
+    pub fork: String,
+    #[arg(long = "state.chainid", default_value_t = 1)]
+    pub chain_id: u64,
+    /// Block reward, unused as only post-merge forks are supported
+    #[arg(
+        long = "state.reward",
+        default_value_t = 0,
+        allow_negative_numbers = true
+    )]
+    pub reward: i64,
+}
+
+#[derive(Args)]
+pub struct TransactionArgs {
+    #[arg(long = "input.txs", default_value = "txs.json")]
+    pub input_txs: String,
+    #[arg(long = "state.fork", default_value = "Prague")]
+    pub fork: String,
+    #[arg(long = "state.chainid", default_value_t = 1)]
+    pub chain_id: u64,
+}
+
+#[derive(Args)]
+pub struct BlockBuilderArgs {
+    #[arg(long = "input.header", default_value = "header.json")]
+    pub input_header: String,
+    #[arg(long = "input.ommers", default_value = "")]
+    pub input_ommers: String,
+    #[arg(long = "input.txs", default_value = "txs.rlp")]
+    pub input_txs: String,
+    #[arg(long = "input.withdrawals", default_value = "")]
+    pub input_withdrawals: String,
+    #[arg(long = "output.basedir", default_value = "")]
+    pub output_basedir: String,
+    #[arg(long = "output.block", default_value = "block.json")]
+    pub output_block: String,
+}
+
+/// Applies a list of transactions on top of a pre-state, writing the post-state, the result of
+/// the block and its body
+pub fn run_transition(args: TransitionArgs) -> Result<(), T8nError> {
+    let chain_config = chain_config_for_fork(&args.fork, args.chain_id)?;
+    let mut inputs = Inputs::default();
+    let alloc = serde_json::from_value(inputs.read("alloc", &args.input_alloc)?)?;
+    let env = serde_json::from_value(inputs.read("env", &args.input_env)?)?;
+    let txs = parse_transactions(inputs.read("txs", &args.input_txs)?, Some(args.chain_id))?;
+
+    let transition = transition::apply(alloc, env, txs, chain_config)?;
+
+    let mut outputs = Outputs::new(&args.output_basedir);
+    outputs.write(
+        "alloc",
+        &args.output_alloc,
+        serde_json::to_value(transition.alloc)?,
+    )?;
+    outputs.write("result", &args.output_result, transition.result)?;
+    outputs.write(
+        "body",
+        &args.output_body,
+        json!(format!("0x{}", hex::encode(&transition.body))),
+    )?;
+    outputs.flush()
+}
+
+/// Checks the validity of a list of transactions, printing the result to the standard output
+pub fn run_transaction(args: TransactionArgs) -> Result<(), T8nError> {
+    let chain_config = chain_config_for_fork(&args.fork, args.chain_id)?;
+    let txs = parse_transactions(
+        Inputs::default().read("txs", &args.input_txs)?,
+        Some(args.chain_id),
+    )?;
+    let result = transaction::check(&txs, chain_config)?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
+
+/// Assembles a block from its header and body, writing its RLP encoding and hash
+pub fn run_block_builder(args: BlockBuilderArgs) -> Result<(), T8nError> {
+    let mut inputs = Inputs::default();
+    let header: BlockHeader = serde_json::from_value(inputs.read("header", &args.input_header)?)?;
+    let ommers = match args.input_ommers.as_str() {
+        "" => Vec::new(),
+        path => block_builder::parse_ommers(inputs.read("ommers", path)?)?,
+    };
+    // Blocks are built from signed transactions, there's no chain id to sign them for
+    let txs = parse_transactions(inputs.read("txs", &args.input_txs)?, None)?;
+    let withdrawals: Option<Vec<Withdrawal>> = match args.input_withdrawals.as_str() {
+        "" => None,
+        path => serde_json::from_value(inputs.read("withdrawals", path)?)?,
+    };
+
+    let block = block_builder::build(header, ommers, txs, withdrawals);
+
+    let mut outputs = Outputs::new(&args.output_basedir);
+    outputs.write("block", &args.output_block, block)?;
+    outputs.flush()
+}
+
+/// Inputs given through the standard input, read at most once
+#[derive(Default)]
+pub struct Inputs {
+    stdin: Option<Map<String, Value>>,
+}
+
+impl Inputs {
+    /// Reads the input `name` from `path`, or from the JSON object in the standard input if the
+    /// path is `stdin`
+    pub fn read(&mut self, name: &str, path: &str) -> Result<Value, T8nError> {
+        if path != "stdin" {
+            let content = std::fs::read_to_string(path)?;
+            return Ok(serde_json::from_str(&content)?);
+        }
+        let stdin = match &mut self.stdin {
+            Some(stdin) => stdin,
+            None => {
+                let mut content = String::new();
+                std::io::stdin().read_to_string(&mut content)?;
+                self.stdin.insert(serde_json::from_str(&content)?)
+            }
+        };
+        Ok(stdin.remove(name).unwrap_or(Value::Null))
+    }
+}
+
+/// Outputs of a tool, written once all of them are ready
+pub struct Outputs {
+    basedir: PathBuf,
+    stdout: Map<String, Value>,
+    stderr: Map<String, Value>,
+}
+
+impl Outputs {
+    pub fn new(basedir: &str) -> Self {
+        Self {
+            basedir: PathBuf::from(basedir),
+            stdout: Map::new(),
+            stderr: Map::new(),
+        }
+    }
+
+    /// Writes the output `name` to `path`, relative to the base directory. Outputs with `stdout`
+    /// or `stderr` as path are written at the end by [Outputs::flush], empty paths are skipped.
+    pub fn write(&mut self, name: &str, path: &str, value: Value) -> Result<(), T8nError> {
+        match path {
+            "" => {}
+            "stdout" => {
+                self.stdout.insert(name.to_string(), value);
+            }
+            "stderr" => {
+                self.stderr.insert(name.to_string(), value);
+            }
+            path => {
+                let path = self.basedir.join(Path::new(path));
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, serde_json::to_string_pretty(&value)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flush(self) -> Result<(), T8nError> {
+        if !self.stdout.is_empty() {
+            println!("{}", serde_json::to_string_pretty(&self.stdout)?);
+        }
+        if !self.stderr.is_empty() {
+            eprintln!("{}", serde_json::to_string_pretty(&self.stderr)?);
+        }
+        Ok(())
+    }
+}
+
+/// Builds the chain config for a fork name as used by the tests, either a single fork
+/// (`Cancun`) or a transition between two forks (`CancunToPragueAtTime15k`).
+/// Only forks from Paris onwards are supported: `ChainConfig` can't resolve block based forks
+/// and LEVM doesn't implement their rules (difficulty, block rewards and ommers), so older forks
+/// are rejected instead of being silently executed with post-merge rules.
+pub fn chain_config_for_fork(name: &str, chain_id: u64) -> Result<ChainConfig, T8nError> {
+    let (base, transition) = match name.split_once("To") {
+        Some((from, to)) => {
+            let to = to
+                .strip_suffix("AtTime15k")
+                .ok_or_else(|| T8nError::Config(format!("Unsupported transition fork {name}")))?;
+            (parse_fork(from)?, Some(parse_fork(to)?))
+        }
+        None => (parse_fork(name)?, None),
+    };
+    if base < Fork::Paris {
+        return Err(T8nError::Config(format!(
+            "Unsupported fork {name}, only forks from Paris onwards are supported"
+        )));
+    }
+
+    let activation = |fork: Fork| {
+        if fork <= base {
+            Some(0)
+        } else if transition.is_some_and(|to| fork <= to) {
+            Some(TRANSITION_TIMESTAMP)
+        } else {
+            None
+        }
+    };
+
+    Ok(ChainConfig {
+        chain_id,
+        homestead_block: Some(0),
+        eip150_block: Some(0),
+        eip155_block: Some(0),
+        eip158_block: Some(0),
+        byzantium_block: Some(0),
+        constantinople_block: Some(0),
+        petersburg_block: Some(0),
+        istanbul_block: Some(0),
+        muir_glacier_block: Some(0),
+        berlin_block: Some(0),
+        london_block: Some(0),
+        arrow_glacier_block: Some(0),
+        gray_glacier_block: Some(0),
+        merge_netsplit_block: Some(0),
+        shanghai_time: activation(Fork::Shanghai),
+        cancun_time: activation(Fork::Cancun),
+        prague_time: activation(Fork::Prague),
+        osaka_time: activation(Fork::Osaka),
+        bpo1_time: activation(Fork::BPO1),
+        bpo2_time: activation(Fork::BPO2),
+        bpo3_time: activation(Fork::BPO3),
+        bpo4_time: activation(Fork::BPO4),
+        bpo5_time: activation(Fork::BPO5),
+        amsterdam_time: activation(Fork::Amsterdam),
+        terminal_total_difficulty: Some(0),
+        terminal_total_difficulty_passed: true,
+        blob_schedule: BlobSchedule::default(),
+        deposit_contract_address: DEPOSIT_CONTRACT_ADDRESS,
+        ..Default::default()
+    })
+}
+
+fn parse_fork(name: &str) -> Result<Fork, T8nError> {
+    let name = match name {
+        "Merge" => "Paris",
+        "ConstantinopleFix" => "Petersburg",
+        "EIP150" => "Tangerine",
+        "EIP158" => "SpuriousDragon",
+        name => name,
+    };
+    serde_json::from_value(Value::String(name.to_string()))
+        .map_err(|_| T8nError::Config(format!("Unknown fork {name}")))
+}
+
+/// Reads a list of transactions given either as a JSON list or as the RLP encoding of the list,
+/// as a hex string. Unsigned JSON transactions are signed for `chain_id`, see
+/// [parse_json_transaction].
+pub fn parse_transactions(
+    value: Value,
+    chain_id: Option<u64>,
+) -> Result<Vec<Transaction>, T8nError> {
+    match value {
+        Value::Null => Ok(Vec::new()),
+        Value::String(rlp) => decode_transactions(&decode_hex(&rlp)?),
+        Value::Array(txs) => txs
+            .into_iter()
+            .map(|tx| parse_json_transaction(tx, chain_id))
+            .collect(),
+        _ => Err(T8nError::Config(
+            "Transactions must be a list or an RLP encoded string".to_string(),
+        )),
+    }
+}
+
+pub fn decode_transactions(rlp: &[u8]) -> Result<Vec<Transaction>, T8nError> {
+    Vec::<Transaction>::decode(rlp).map_err(|error| T8nError::Rlp(error.to_string()))
+}
+
+pub fn decode_hex(hex_str: &str) -> Result<Bytes, T8nError> {
+    hex::decode(hex_str.trim().trim_start_matches("0x"))
+        .map(Bytes::from)
+        .map_err(|error| T8nError::Rlp(format!("Invalid hex string: {error}")))
+}
+
+/// Parses a transaction in the JSON format used by the tests, which doesn't always match the RPC
+/// format expected by [Transaction]'s deserializer.
+/// Unsigned transactions given along with their `secretKey` are signed with it, as geth does.
+/// Legacy ones are replay protected (EIP-155) for `chain_id` unless `protected` is false.
+fn parse_json_transaction(tx: Value, chain_id: Option<u64>) -> Result<Transaction, T8nError> {
+    let Value::Object(mut tx) = tx else {
+        return Err(T8nError::Config(
+            "Transaction must be an object".to_string(),
+        ));
+    };
+    let secret_key = match tx.remove("secretKey") {
+        Some(key) if tx.get("r").is_none_or(is_zero) => Some(parse_secret_key(&key)?),
+        _ => None,
+    };
+    let protected = tx
+        .remove("protected")
+        .and_then(|protected| protected.as_bool())
+        .unwrap_or(true);
+    if secret_key.is_some() {
+        // Placeholders for the signature, which is set once the transaction is parsed
+        for field in ["r", "s", "v"] {
+            tx.entry(field)
+                .or_insert_with(|| Value::String("0x0".to_string()));
+        }
+    }
+    // The tests omit the type of legacy transactions, the deserializer defaults to EIP-1559
+    let tx_type = tx
+        .entry("type")
+        .or_insert_with(|| Value::String("0x0".to_string()))
+        .clone();
+    if !tx.contains_key("input")
+        && let Some(data) = tx.remove("data")
+    {
+        tx.insert("input".to_string(), data);
+    }
+    if !tx.contains_key("gas")
+        && let Some(gas) = tx.remove("gasLimit")
+    {
+        tx.insert("gas".to_string(), gas);
+    }
+    if !is_zero(&tx_type)
+        && !tx.contains_key("yParity")
+        && let Some(v) = tx.get("v").cloned()
+    {
+        tx.insert("yParity".to_string(), v);
+    }
+    let tx = serde_json::from_value(Value::Object(tx))?;
+
+    let Some(secret_key) = secret_key else {
+        return Ok(tx);
+    };
+    let legacy_chain_id = match (protected, chain_id) {
+        (false, _) => None,
+        (true, Some(chain_id)) => Some(chain_id),
+        (true, None) => {
+            return Err(T8nError::Config(
+                "Transactions can't be signed without a chain id".to_string(),
+            ));
+        }
+    };
+    sign_transaction(tx, &secret_key, legacy_chain_id)
+}
+
+fn parse_secret_key(key: &Value) -> Result<SecretKey, T8nError> {
+    key.as_str()
+        .and_then(|key| hex::decode(key.trim_start_matches("0x")).ok())
+        .and_then(|key| SecretKey::from_slice(&key).ok())
+        .ok_or_else(|| T8nError::Config("Invalid secretKey".to_string()))
+}
+
+/// Signs the transaction, legacy transactions are replay protected if `legacy_chain_id` is given
+fn sign_transaction(
+    mut tx: Transaction,
+    secret_key: &SecretKey,
+    legacy_chain_id: Option<u64>,
+) -> Result<Transaction, T8nError> {
+    let mut payload = Vec::new();
+    match (&tx, legacy_chain_id) {
+        (Transaction::LegacyTransaction(legacy), Some(chain_id)) => Encoder::new(&mut payload)
+            .encode_field(&legacy.nonce)
+            .encode_field(&legacy.gas_price)
+            .encode_field(&legacy.gas)
+            .encode_field(&legacy.to)
+            .encode_field(&legacy.value)
+            .encode_field(&legacy.data)
+            .encode_field(&chain_id)
+            .encode_field(&0u8)
+            .encode_field(&0u8)
+            .finish(),
+        (Transaction::LegacyTransaction(legacy), None) => legacy.encode_payload(&mut payload),
+        (tx, _) => {
+            payload.push(tx.tx_type() as u8);
+            tx.encode_payload(&mut payload);
+        }
+    }
+    let (recovery_id, signature) = SECP256K1
+        .sign_ecdsa_recoverable(&Message::from_digest(keccak(payload).0), secret_key)
+        .serialize_compact();
+    let r = U256::from_big_endian(&signature[..32]);
+    let s = U256::from_big_endian(&signature[32..]);
+    let y_parity = Into::<i32>::into(recovery_id) != 0;
+
+    let tx_type = tx.tx_type();
+    match &mut tx {
+        Transaction::LegacyTransaction(tx) => {
+            let v = match legacy_chain_id {
+                Some(chain_id) => chain_id
+                    .checked_mul(2)
+                    .and_then(|v| v.checked_add(35 + u64::from(y_parity)))
+                    .ok_or_else(|| T8nError::Config("Chain id is too big".to_string()))?,
+                None => 27 + u64::from(y_parity),
+            };
+            (tx.v, tx.r, tx.s) = (v.into(), r, s);
+        }
+        Transaction::EIP2930Transaction(tx) => {
+            (tx.signature_y_parity, tx.signature_r, tx.signature_s) = (y_parity, r, s);
+        }
+        Transaction::EIP1559Transaction(tx) => {
+            (tx.signature_y_parity, tx.signature_r, tx.signature_s) = (y_parity, r, s);
+        }
+        Transaction::EIP4844Transaction(tx) => {
+            (tx.signature_y_parity, tx.signature_r, tx.signature_s) = (y_parity, r, s);
+        }
+        Transaction::EIP7702Transaction(tx) => {
+            (tx.signature_y_parity, tx.signature_r, tx.signature_s) = (y_parity, r, s);
+        }
+        _ => {
+            return Err(T8nError::Config(format!(
+                "Signing {tx_type:?} transactions is not supported"
+            )));
+        }
+    }
+    Ok(tx)
+}
+
+fn is_zero(value: &Value) -> bool {
+    value
+        .as_str()
+        .is_some_and(|value| value.trim_start_matches("0x").chars().all(|c| c == '0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_config_for_transition_fork() {
+        let config = chain_config_for_fork("CancunToPragueAtTime15k", 1).unwrap();
+        assert_eq!(config.fork(0), Fork::Cancun);
+        assert_eq!(config.fork(TRANSITION_TIMESTAMP), Fork::Prague);
+        assert!(config.osaka_time.is_none());
+
+        let config = chain_config_for_fork("Merge", 1).unwrap();
+        assert_eq!(config.fork(TRANSITION_TIMESTAMP), Fork::Paris);
+
+        assert!(chain_config_for_fork("London", 1).is_err());
+        assert!(chain_config_for_fork("Unknown", 1).is_err());
+    }
+
+    #[test]
+    fn parse_legacy_json_transaction() {
+        let tx = serde_json::json!([{
+            "nonce": "0x0",
+            "gasPrice": "0xa",
+            "gas": "0x5208",
+            "to": "0x000000000000000000000000000000000000beef",
+            "value": "0x1",
+            "data": "0x",
+            "v": "0x1b",
+            "r": "0x1",
+            "s": "0x1",
+        }]);
+        let txs = parse_transactions(tx, Some(1)).unwrap();
+        assert!(matches!(txs[0], Transaction::LegacyTransaction(_)));
+        assert_eq!(txs[0].gas_limit(), 21_000);
+    }
+
+    #[test]
+    fn sign_json_transactions_with_their_secret_key() {
+        let sender: H160 = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b"
+            .parse()
+            .unwrap();
+        let secret_key = "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+        let txs = serde_json::json!([
+            {
+                "nonce": "0x0",
+                "gasPrice": "0xa",
+                "gas": "0x5208",
+                "to": "0x000000000000000000000000000000000000beef",
+                "value": "0x1",
+                "data": "0x",
+                "secretKey": secret_key,
+            },
+            {
+                "nonce": "0x1",
+                "gasPrice": "0xa",
+                "gas": "0x5208",
+                "to": "0x000000000000000000000000000000000000beef",
+                "value": "0x1",
+                "data": "0x",
+                "protected": false,
+                "secretKey": secret_key,
+            },
+            {
+                "type": "0x2",
+                "chainId": "0x1",
+                "nonce": "0x2",
+                "maxFeePerGas": "0xa",
+                "maxPriorityFeePerGas": "0x1",
+                "gas": "0x5208",
+                "to": "0x000000000000000000000000000000000000beef",
+                "value": "0x1",
+                "data": "0x",
+                "accessList": [],
+                "secretKey": secret_key,
+            },
+        ]);
+
+        let txs = parse_transactions(txs, Some(1)).unwrap();
+        for tx in &txs {
+            assert_eq!(tx.sender().unwrap(), sender);
+        }
+        let Transaction::LegacyTransaction(protected) = &txs[0] else {
+            panic!("Expected a legacy transaction");
+        };
+        assert!(protected.v == U256::from(37) || protected.v == U256::from(38));
+        let Transaction::LegacyTransaction(unprotected) = &txs[1] else {
+            panic!("Expected a legacy transaction");
+        };
+        assert!(unprotected.v == U256::from(27) || unprotected.v == U256::from(28));
+        assert!(matches!(txs[2], Transaction::EIP1559Transaction(_)));
+    }
+
+    #[test]
+    fn signing_requires_a_chain_id() {
+        let tx = serde_json::json!([{
+            "nonce": "0x0",
+            "gasPrice": "0xa",
+            "gas": "0x5208",
+            "to": "0x000000000000000000000000000000000000beef",
+            "value": "0x1",
+            "data": "0x",
+            "secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+        }]);
+        assert!(parse_transactions(tx, None).is_err());
+    }
+}
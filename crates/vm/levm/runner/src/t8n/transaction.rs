@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use ethrex_common::types::{BlockHeader, ChainConfig, Fork, Transaction};
+use ethrex_levm::{
+    constants::POST_OSAKA_GAS_LIMIT_CAP,
+    errors::{TxValidationError, VMError},
+    hooks::default_hook::{
+        validate_4844_tx, validate_init_code_size, validate_min_gas_limit, validate_type_4_tx,
+    },
+    tracing::LevmCallTracer,
+    vm::{VM, VMType},
+};
+use ethrex_vm::{Evm, backends::levm::LEVM};
+use serde_json::{Value, json};
+
+use super::{T8nError, database::T8nDatabase};
+
+/// Checks the validity of the transactions that doesn't depend on the state, returning for each
+/// one either its sender, hash and intrinsic gas or the reason it's invalid.
+pub fn check(txs: &[Transaction], chain_config: ChainConfig) -> Result<Value, T8nError> {
+    let fork = chain_config.fork(0);
+    let header = BlockHeader {
+        gas_limit: u64::MAX,
+        base_fee_per_gas: Some(0),
+        excess_blob_gas: (fork >= Fork::Cancun).then_some(0),
+        blob_gas_used: (fork >= Fork::Cancun).then_some(0),
+        ..Default::default()
+    };
+    let db = T8nDatabase::new(Default::default(), BTreeMap::new(), chain_config.clone());
+
+    let results = txs
+        .iter()
+        .map(|tx| {
+            let mut result = json!({"hash": tx.hash()});
+            let sender = match tx.sender() {
+                Ok(sender) => sender,
+                Err(error) => {
+                    result["error"] = json!(format!("invalid signature: {error}"));
+                    return Ok(result);
+                }
+            };
+            result["address"] = json!(sender);
+            if tx
+                .chain_id()
+                .is_some_and(|chain_id| chain_id != chain_config.chain_id)
+            {
+                result["error"] = json!(format!(
+                    "invalid chain id: have {:?}, want {}",
+                    tx.chain_id(),
+                    chain_config.chain_id
+                ));
+                return Ok(result);
+            }
+
+            // Every transaction is checked against an empty state, so nothing leaks between them
+            let mut evm = Evm::new_for_l1(db.clone());
+            let env = LEVM::setup_env(tx, sender, &header, &evm.db, VMType::L1)
+                .map_err(|error| T8nError::Evm(error.to_string()))?;
+            let mut vm = VM::new(env, &mut evm.db, tx, LevmCallTracer::disabled(), VMType::L1)
+                .map_err(|error| T8nError::Evm(error.to_string()))?;
+
+            match vm.get_intrinsic_gas() {
+                Ok(intrinsic_gas) => result["intrinsicGas"] = json!(format!("{intrinsic_gas:#x}")),
+                Err(error) => {
+                    result["error"] = json!(error.to_string());
+                    return Ok(result);
+                }
+            }
+            match validate(&mut vm, tx, fork) {
+                Ok(()) => {}
+                Err(error) if error.should_propagate() => {
+                    return Err(T8nError::Evm(error.to_string()));
+                }
+                Err(error) => result["error"] = json!(error.to_string()),
+            }
+            Ok(result)
+        })
+        .collect::<Result<Vec<_>, T8nError>>()?;
+    Ok(Value::Array(results))
+}
+
+/// Runs the checks LEVM does before executing a transaction that don't read the state
+fn validate(vm: &mut VM<'_>, tx: &Transaction, fork: Fork) -> Result<(), VMError> {
+    if fork >= Fork::Prague {
+        validate_min_gas_limit(vm)?;
+    } else if tx.gas_limit() < vm.get_intrinsic_gas()? {
+        return Err(TxValidationError::IntrinsicGasTooLow.into());
+    }
+    if fork >= Fork::Osaka && tx.gas_limit() > POST_OSAKA_GAS_LIMIT_CAP {
+        return Err(TxValidationError::TxMaxGasLimitExceeded {
+            tx_hash: tx.hash(),
+            tx_gas_limit: tx.gas_limit(),
+        }
+        .into());
+    }
+    if vm.is_create()? {
+        validate_init_code_size(vm)?;
+    }
+    if let (Some(priority_fee), Some(max_fee_per_gas)) =
+        (tx.max_priority_fee(), tx.max_fee_per_gas())
+        && priority_fee > max_fee_per_gas
+    {
+        return Err(TxValidationError::PriorityGreaterThanMaxFeePerGas {
+            priority_fee: priority_fee.into(),
+            max_fee_per_gas: max_fee_per_gas.into(),
+        }
+        .into());
+    }
+    if vm.env.tx_max_fee_per_blob_gas.is_some() {
+        validate_4844_tx(vm)?;
+    }
+    if tx.authorization_list().is_some() {
+        validate_type_4_tx(vm)?;
+    }
+    if tx.nonce() == u64::MAX {
+        return Err(TxValidationError::NonceIsMax.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use ethrex_common::{
+    Address, H256,
+    constants::{DEFAULT_OMMERS_HASH, GAS_PER_BLOB},
+    evm::calculate_create_address,
+    serde_utils,
+    types::{
+        BlockHeader, ChainConfig, ELASTICITY_MULTIPLIER, Fork, Genesis, Log, Receipt, Transaction,
+        TxKind, Withdrawal, bloom_from_logs, calc_excess_blob_gas, calculate_base_fee_per_gas,
+        compute_receipts_root, compute_transactions_root, compute_withdrawals_root,
+        requests::{EncodedRequests, compute_requests_hash},
+    },
+    utils::keccak,
+};
+use ethrex_rlp::encode::RLPEncode;
+use ethrex_vm::{Evm, EvmError};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{
+    T8nError,
+    database::{Alloc, T8nDatabase},
+};
+
+/// Environment of the block the transactions are applied on
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Env {
+    pub current_coinbase: Address,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub current_gas_limit: u64,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub current_number: u64,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub current_timestamp: u64,
+    #[serde(default)]
+    pub current_random: Option<H256>,
+    #[serde(default, with = "serde_utils::u64::hex_str_opt")]
+    pub current_base_fee: Option<u64>,
+    #[serde(default, with = "serde_utils::u64::hex_str_opt")]
+    pub parent_base_fee: Option<u64>,
+    #[serde(default, with = "serde_utils::u64::hex_str_opt")]
+    pub parent_gas_used: Option<u64>,
+    #[serde(default, with = "serde_utils::u64::hex_str_opt")]
+    pub parent_gas_limit: Option<u64>,
+    #[serde(default, with = "serde_utils::u64::hex_str_opt")]
+    pub current_excess_blob_gas: Option<u64>,
+    #[serde(default, with = "serde_utils::u64::hex_str_opt")]
+    pub parent_excess_blob_gas: Option<u64>,
+    #[serde(default, with = "serde_utils::u64::hex_str_opt")]
+    pub parent_blob_gas_used: Option<u64>,
+    #[serde(default)]
+    pub parent_beacon_block_root: Option<H256>,
+    #[serde(default)]
+    pub block_hashes: BTreeMap<String, H256>,
+    #[serde(default)]
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl Env {
+    /// Builds the header of the block being executed, deriving the fields the tests may omit
+    /// from the parent ones
+    fn block_header(&self, chain_config: &ChainConfig) -> Result<BlockHeader, T8nError> {
+        let fork = chain_config.fork(self.current_timestamp);
+        let parent_hash = self
+            .current_number
+            .checked_sub(1)
+            .and_then(|parent| self.block_hashes()?.get(&parent).copied())
+            .unwrap_or_default();
+
+        let base_fee_per_gas = match self.current_base_fee {
+            Some(base_fee) => base_fee,
+            None => {
+                let (Some(parent_base_fee), Some(parent_gas_used), Some(parent_gas_limit)) = (
+                    self.parent_base_fee,
+                    self.parent_gas_used,
+                    self.parent_gas_limit,
+                ) else {
+                    return Err(T8nError::Config(
+                        "currentBaseFee or the parent gas and base fee are required".to_string(),
+                    ));
+                };
+                calculate_base_fee_per_gas(
+                    self.current_gas_limit,
+                    parent_gas_limit,
+                    parent_gas_used,
+                    parent_base_fee,
+                    ELASTICITY_MULTIPLIER,
+                )
+                .ok_or_else(|| {
+                    T8nError::Config("Invalid gas limit in relation to the parent".to_string())
+                })?
+            }
+        };
+
+        let (blob_gas_used, excess_blob_gas, parent_beacon_block_root) = if fork >= Fork::Cancun {
+            let excess_blob_gas = match self.current_excess_blob_gas {
+                Some(excess_blob_gas) => excess_blob_gas,
+                None => {
+                    let schedule = chain_config
+                        .get_fork_blob_schedule(self.current_timestamp)
+                        .ok_or_else(|| T8nError::Config("Missing blob schedule".to_string()))?;
+                    let parent = BlockHeader {
+                        base_fee_per_gas: self.parent_base_fee,
+                        blob_gas_used: Some(self.parent_blob_gas_used.unwrap_or_default()),
+                        excess_blob_gas: Some(self.parent_excess_blob_gas.unwrap_or_default()),
+                        ..Default::default()
+                    };
+                    calc_excess_blob_gas(&parent, schedule, fork)
+                }
+            };
+            (
+                Some(0),
+                Some(excess_blob_gas),
+                Some(self.parent_beacon_block_root.unwrap_or_default()),
+            )
+        } else {
+            (None, None, None)
+        };
+
+        Ok(BlockHeader {
+            parent_hash,
+            ommers_hash: *DEFAULT_OMMERS_HASH,
+            coinbase: self.current_coinbase,
+            number: self.current_number,
+            gas_limit: self.current_gas_limit,
+            timestamp: self.current_timestamp,
+            prev_randao: self.current_random.unwrap_or_default(),
+            base_fee_per_gas: Some(base_fee_per_gas),
+            withdrawals_root: (fork >= Fork::Shanghai)
+                .then(|| compute_withdrawals_root(self.withdrawals.as_deref().unwrap_or_default())),
+            blob_gas_used,
+            excess_blob_gas,
+            parent_beacon_block_root,
+            ..Default::default()
+        })
+    }
+
+    /// The tests give block numbers as decimal or hex strings
+    fn block_hashes(&self) -> Option<BTreeMap<u64, H256>> {
+        self.block_hashes
+            .iter()
+            .map(|(number, hash)| {
+                let number = match number.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                Some((number, *hash))
+            })
+            .collect()
+    }
+}
+
+/// Outcome of applying the transactions of a block on top of a pre-state
+pub struct Transition {
+    pub alloc: Alloc,
+    pub result: Value,
+    pub body: Bytes,
+}
+
+/// Applies the transactions on top of `alloc` in the block described by `env`. Invalid
+/// transactions are skipped and listed as rejected in the result, as geth's t8n does.
+pub fn apply(
+    alloc: Alloc,
+    env: Env,
+    txs: Vec<Transaction>,
+    chain_config: ChainConfig,
+) -> Result<Transition, T8nError> {
+    let header = env.block_header(&chain_config)?;
+    let block_hashes = env
+        .block_hashes()
+        .ok_or_else(|| T8nError::Config("Invalid block number in blockHashes".to_string()))?;
+    let fork = chain_config.fork(header.timestamp);
+    let db = T8nDatabase::new(alloc, block_hashes, chain_config.clone());
+    let evm_error = |error: EvmError| match db.missing_block_hash() {
+        Some(number) => T8nError::MissingBlockHash(number),
+        None => T8nError::Evm(error.to_string()),
+    };
+
+    let mut vm = Evm::new_for_l1(db.clone());
+    if fork >= Fork::Amsterdam {
+        vm.enable_access_recording();
+    }
+    vm.apply_system_calls(&header).map_err(evm_error)?;
+
+    let max_blob_gas = chain_config
+        .get_fork_blob_schedule(header.timestamp)
+        .map(|schedule| u64::from(schedule.max) * u64::from(GAS_PER_BLOB))
+        .unwrap_or_default();
+
+    let mut remaining_gas = header.gas_limit;
+    let mut blob_gas_used = 0;
+    let mut included = Vec::new();
+    let mut receipts = Vec::new();
+    let mut receipts_json = Vec::new();
+    let mut rejected = Vec::new();
+    let mut logs: Vec<Log> = Vec::new();
+
+    for (index, tx) in txs.into_iter().enumerate() {
+        let mut reject = |error: String| rejected.push(json!({"index": index, "error": error}));
+
+        let sender = match tx.sender() {
+            Ok(sender) => sender,
+            Err(error) => {
+                reject(format!("invalid signature: {error}"));
+                continue;
+            }
+        };
+        if tx.gas_limit() > remaining_gas {
+            reject(format!(
+                "gas limit reached: have {remaining_gas}, want {}",
+                tx.gas_limit()
+            ));
+            continue;
+        }
+        let tx_blob_gas = u64::try_from(tx.blob_versioned_hashes().len())
+            .unwrap_or(u64::MAX)
+            .saturating_mul(u64::from(GAS_PER_BLOB));
+        if blob_gas_used.saturating_add(tx_blob_gas) > max_blob_gas {
+            reject(format!(
+                "blob gas limit reached: have {}, want {tx_blob_gas}",
+                max_blob_gas - blob_gas_used
+            ));
+            continue;
+        }
+
+        let (receipt, gas_used) = match vm.execute_tx(&tx, &header, &mut remaining_gas, sender) {
+            Ok(result) => result,
+            Err(EvmError::Transaction(error)) => {
+                reject(error);
+                continue;
+            }
+            Err(error) => return Err(evm_error(error)),
+        };
+        blob_gas_used += tx_blob_gas;
+
+        let contract_address = match tx.to() {
+            TxKind::Create => Some(calculate_create_address(sender, tx.nonce())),
+            TxKind::Call(_) => None,
+        };
+        receipts_json.push(receipt_json(
+            &receipt,
+            &tx,
+            &header,
+            included.len(),
+            gas_used,
+            contract_address,
+        ));
+        logs.extend(receipt.logs.iter().cloned());
+        receipts.push(receipt);
+        included.push(tx);
+    }
+
+    if let Some(withdrawals) = &env.withdrawals
+        && fork >= Fork::Shanghai
+    {
+        vm.process_withdrawals(withdrawals).map_err(evm_error)?;
+    }
+    let requests = if fork >= Fork::Prague {
+        let requests = vm.extract_requests(&receipts, &header).map_err(evm_error)?;
+        Some(
+            requests
+                .iter()
+                .map(|requests| requests.encode())
+                .collect::<Vec<EncodedRequests>>(),
+        )
+    } else {
+        None
+    };
+    let block_access_list = vm.take_block_access_list().map_err(evm_error)?;
+
+    let updates = vm.get_state_transitions().map_err(evm_error)?;
+    let post_alloc = db.apply_updates(updates);
+    let state_root = Genesis {
+        alloc: post_alloc.clone(),
+        ..Default::default()
+    }
+    .compute_state_root();
+
+    let gas_used = header.gas_limit - remaining_gas;
+    let mut result = json!({
+        "stateRoot": state_root,
+        "txRoot": compute_transactions_root(&included),
+        "receiptsRoot": compute_receipts_root(&receipts),
+        "logsHash": keccak(logs.encode_to_vec()),
+        "logsBloom": bloom_from_logs(&logs),
+        "receipts": receipts_json,
+        "rejected": rejected,
+        "currentDifficulty": Value::Null,
+        "gasUsed": hex_u64(gas_used),
+        "currentBaseFee": header.base_fee_per_gas.map(hex_u64),
+    });
+    if let Some(withdrawals_root) = header.withdrawals_root {
+        result["withdrawalsRoot"] = json!(withdrawals_root);
+    }
+    if let Some(excess_blob_gas) = header.excess_blob_gas {
+        result["currentExcessBlobGas"] = json!(hex_u64(excess_blob_gas));
+        result["blobGasUsed"] = json!(hex_u64(blob_gas_used));
+    }
+    if let Some(requests) = requests {
+        result["requestsHash"] = json!(compute_requests_hash(&requests));
+        // Empty requests are not part of the block
+        result["requests"] = requests
+            .iter()
+            .filter(|requests| !requests.is_empty())
+            .map(|requests| json!(format!("0x{}", hex::encode(&requests.0))))
+            .collect();
+    }
+    if let Some(block_access_list) = block_access_list {
+        result["blockAccessListHash"] = json!(block_access_list.hash());
+    }
+
+    Ok(Transition {
+        alloc: post_alloc,
+        result,
+        body: Bytes::from(included.encode_to_vec()),
+    })
+}
+
+fn receipt_json(
+    receipt: &Receipt,
+    tx: &Transaction,
+    header: &BlockHeader,
+    index: usize,
+    gas_used: u64,
+    contract_address: Option<Address>,
+) -> Value {
+    let logs: Vec<Value> = receipt
+        .logs
+        .iter()
+        .map(|log| {
+            json!({
+                "address": log.address,
+                "topics": log.topics,
+                "data": format!("0x{}", hex::encode(&log.data)),
+            })
+        })
+        .collect();
+    json!({
+        "type": format!("{:#x}", tx.tx_type() as u8),
+        "root": "0x",
+        "status": if receipt.succeeded { "0x1" } else { "0x0" },
+        "cumulativeGasUsed": hex_u64(receipt.cumulative_gas_used),
+        "logsBloom": bloom_from_logs(&receipt.logs),
+        "logs": logs,
+        "transactionHash": tx.hash(),
+        "contractAddress": contract_address.unwrap_or_default(),
+        "gasUsed": hex_u64(gas_used),
+        "effectiveGasPrice": tx
+            .effective_gas_price(header.base_fee_per_gas)
+            .map(|price| format!("{price:#x}")),
+        "blockHash": H256::zero(),
+        "transactionIndex": hex_u64(u64::try_from(index).unwrap_or(u64::MAX)),
+    })
+}
+
+fn hex_u64(value: u64) -> String {
+    format!("{value:#x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::t8n::{chain_config_for_fork, decode_transactions, parse_transactions};
+    use ethrex_common::U256;
+
+    const SECRET_KEY: &str = "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+
+    fn address(hex: &str) -> Address {
+        hex.parse().unwrap()
+    }
+
+    fn transfer(nonce: u64) -> Value {
+        json!({
+            "nonce": hex_u64(nonce),
+            "gasPrice": "0xa",
+            "gas": "0x5208",
+            "to": "0x000000000000000000000000000000000000beef",
+            "value": "0x1",
+            "data": "0x",
+            "secretKey": SECRET_KEY,
+        })
+    }
+
+    #[test]
+    fn applies_transfers_and_rejects_invalid_transactions() {
+        let sender = address("0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b");
+        let recipient = address("0x000000000000000000000000000000000000beef");
+        let coinbase = address("0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba");
+        let alloc: Alloc = serde_json::from_value(json!({
+            "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": { "balance": "0x3b9aca00" },
+        }))
+        .unwrap();
+        let env: Env = serde_json::from_value(json!({
+            "currentCoinbase": coinbase,
+            "currentGasLimit": "0x1000000",
+            "currentNumber": "0x1",
+            "currentTimestamp": "0xc",
+            "currentRandom": H256::zero(),
+            "currentBaseFee": "0x7",
+            "currentExcessBlobGas": "0x0",
+            "parentBeaconBlockRoot": H256::zero(),
+            "blockHashes": { "0": H256::repeat_byte(1) },
+            "withdrawals": [],
+        }))
+        .unwrap();
+        // The second transaction skips a nonce
+        let txs = parse_transactions(json!([transfer(0), transfer(2)]), Some(1)).unwrap();
+        let chain_config = chain_config_for_fork("Cancun", 1).unwrap();
+
+        let transition = apply(alloc, env, txs.clone(), chain_config).unwrap();
+
+        let result = &transition.result;
+        assert_eq!(result["gasUsed"], json!("0x5208"));
+        assert_eq!(result["receipts"].as_array().unwrap().len(), 1);
+        assert_eq!(result["receipts"][0]["status"], json!("0x1"));
+        assert_eq!(result["rejected"].as_array().unwrap().len(), 1);
+        assert_eq!(result["rejected"][0]["index"], json!(1));
+        assert_eq!(
+            result["txRoot"],
+            json!(compute_transactions_root(&txs[..1]))
+        );
+        assert_eq!(decode_transactions(&transition.body).unwrap(), txs[..1]);
+
+        let fee = U256::from(21_000 * 10);
+        let alloc = &transition.alloc;
+        assert_eq!(alloc[&recipient].balance, U256::one());
+        assert_eq!(alloc[&sender].nonce, 1);
+        assert_eq!(
+            alloc[&sender].balance,
+            U256::from(1_000_000_000) - fee - U256::one()
+        );
+        // The coinbase gets the priority fee, the gas price minus the base fee
+        assert_eq!(alloc[&coinbase].balance, U256::from(21_000 * 3));
+        assert_eq!(
+            result["stateRoot"],
+            json!(
+                Genesis {
+                    alloc: alloc.clone(),
+                    ..Default::default()
+                }
+                .compute_state_root()
+            )
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! State access events as defined by [EIP-4762](https://eips.ethereum.org/EIPS/eip-4762).
+//!
+//! With stateless gas costs, the cold/warm costs of EIP-2929 are replaced by charges for the
+//! parts of the unified binary tree that a stateless client needs in its witness: the first
+//! access to a stem (branch) and to each of its leaves (chunks) in a transaction, plus the first
+//! write to each of them. The events of every transaction are also gathered per block, to know
+//! which leaves the witness of the block has to prove.
+
+use ethrex_common::{
+    Address, U256,
+    types::ubt_leaf::{UbtLeaf, code_chunks_in_range},
+};
+use rustc_hash::FxHashMap;
+use std::collections::hash_map::Entry;
+
+use crate::{errors::VMError, gas_cost, vm::VM};
+
+/// How a leaf is accessed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessMode {
+    Read,
+    Write,
+    /// Write to a leaf that had no value before
+    Fill,
+}
+
+/// Number of accesses of each kind that are new to the transaction, each one is charged once
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessCharges {
+    pub branch_reads: u64,
+    pub chunk_reads: u64,
+    pub branch_writes: u64,
+    pub chunk_writes: u64,
+    pub chunk_fills: u64,
+}
+
+impl AccessCharges {
+    fn add(&mut self, other: AccessCharges) {
+        self.branch_reads = self.branch_reads.saturating_add(other.branch_reads);
+        self.chunk_reads = self.chunk_reads.saturating_add(other.chunk_reads);
+        self.branch_writes = self.branch_writes.saturating_add(other.branch_writes);
+        self.chunk_writes = self.chunk_writes.saturating_add(other.chunk_writes);
+        self.chunk_fills = self.chunk_fills.saturating_add(other.chunk_fills);
+    }
+}
+
+/// Stems and leaves accessed so far, with whether they were written
+#[derive(Debug, Clone, Default)]
+pub struct AccessEvents {
+    /// Keyed by the owner of the stem and its tree index
+    branches: FxHashMap<(Address, U256), bool>,
+    leaves: FxHashMap<UbtLeaf, bool>,
+}
+
+impl AccessEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an access to `leaf`, returning the charges it adds
+    pub fn touch(&mut self, leaf: UbtLeaf, mode: AccessMode) -> AccessCharges {
+        let mut charges = AccessCharges::default();
+        let write = mode != AccessMode::Read;
+        let (tree_index, _) = leaf.position();
+
+        let branch_written = match self.branches.entry((leaf.address(), tree_index)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                charges.branch_reads = 1;
+                entry.insert(false)
+            }
+        };
+        if write && !*branch_written {
+            *branch_written = true;
+            charges.branch_writes = 1;
+        }
+
+        let leaf_written = match self.leaves.entry(leaf) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                charges.chunk_reads = 1;
+                entry.insert(false)
+            }
+        };
+        if write && !*leaf_written {
+            *leaf_written = true;
+            charges.chunk_writes = 1;
+            if mode == AccessMode::Fill {
+                charges.chunk_fills = 1;
+            }
+        }
+
+        charges
+    }
+
+    /// Records an access to every leaf in `leaves`, returning the charges they add
+    pub fn touch_all(
+        &mut self,
+        leaves: impl IntoIterator<Item = UbtLeaf>,
+        mode: AccessMode,
+    ) -> AccessCharges {
+        let mut charges = AccessCharges::default();
+        for leaf in leaves {
+            charges.add(self.touch(leaf, mode));
+        }
+        charges
+    }
+
+    /// Adds the accesses of `other`, e.g. those of a transaction to the ones of its block
+    pub fn merge(&mut self, other: &AccessEvents) {
+        for (branch, written) in &other.branches {
+            *self.branches.entry(*branch).or_default() |= written;
+        }
+        for (leaf, written) in &other.leaves {
+            *self.leaves.entry(*leaf).or_default() |= written;
+        }
+    }
+
+    /// Returns every leaf accessed, sorted
+    pub fn accessed_leaves(&self) -> Vec<UbtLeaf> {
+        let mut leaves: Vec<UbtLeaf> = self.leaves.keys().copied().collect();
+        leaves.sort_unstable();
+        leaves
+    }
+}
+
+/// Leaves holding the account header: its basic data and code hash
+pub fn account_header(address: Address) -> [UbtLeaf; 2] {
+    [UbtLeaf::BasicData(address), UbtLeaf::CodeHash(address)]
+}
+
+impl<'a> VM<'a> {
+    /// Marks `address` as accessed for EIP-2929, returning whether its cold access cost has to
+    /// be paid. Under EIP-4762 it never does, access events are charged instead.
+    pub(crate) fn access_address(&mut self, address: Address) -> bool {
+        let was_cold = !self.substate.add_accessed_address(address);
+        was_cold && !self.env.config.stateless_gas
+    }
+
+    /// Under EIP-4762, records the accesses to `leaves` and charges the current call frame
+    /// for the ones that are new to the transaction. Does nothing otherwise.
+    pub(crate) fn charge_access_events(
+        &mut self,
+        leaves: impl IntoIterator<Item = UbtLeaf>,
+        mode: AccessMode,
+    ) -> Result<(), VMError> {
+        if !self.env.config.stateless_gas {
+            return Ok(());
+        }
+        let charges = self.access_events.touch_all(leaves, mode);
+        self.current_call_frame
+            .increase_consumed_gas(gas_cost::witness_access(&charges)?)
+    }
+
+    /// Charges for the code chunks of `address` holding the bytes in `offset..offset + size`,
+    /// ignoring the ones past the end of its code
+    pub(crate) fn charge_code_access(
+        &mut self,
+        address: Address,
+        code_len: usize,
+        offset: usize,
+        size: usize,
+    ) -> Result<(), VMError> {
+        if !self.env.config.stateless_gas || offset >= code_len {
+            return Ok(());
+        }
+        let size = size.min(code_len.saturating_sub(offset));
+        let chunks = code_chunks_in_range(
+            u64::try_from(offset).unwrap_or(u64::MAX),
+            u64::try_from(size).unwrap_or(u64::MAX),
+        );
+        self.charge_access_events(
+            chunks.map(|chunk| UbtLeaf::CodeChunk(address, chunk)),
+            AccessMode::Read,
+        )
+    }
+
+    /// Charges for the code chunks holding the opcode just fetched and its immediate data.
+    /// Initcode isn't part of the state, so it's free to read.
+    pub(crate) fn charge_code_execution(&mut self, opcode: u8) -> Result<(), VMError> {
+        let call_frame = &self.current_call_frame;
+        if call_frame.is_create {
+            return Ok(());
+        }
+        // PUSH1..PUSH32 carry 1 to 32 bytes of immediate data
+        let immediate = match opcode {
+            0x60..=0x7f => usize::from(opcode.wrapping_sub(0x5f)),
+            _ => 0,
+        };
+        let (address, code_len, pc) = (
+            call_frame.code_address,
+            call_frame.bytecode.bytecode.len(),
+            call_frame.pc.saturating_sub(1),
+        );
+        self.charge_code_access(address, code_len, pc, immediate.saturating_add(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_common::H256;
+
+    #[test]
+    fn charges_first_accesses_only() {
+        let mut events = AccessEvents::new();
+        let address = Address::repeat_byte(1);
+
+        let charges = events.touch(UbtLeaf::BasicData(address), AccessMode::Read);
+        assert_eq!(
+            charges,
+            AccessCharges {
+                branch_reads: 1,
+                chunk_reads: 1,
+                ..Default::default()
+            }
+        );
+
+        // Same stem, another leaf
+        let charges = events.touch(UbtLeaf::CodeHash(address), AccessMode::Write);
+        assert_eq!(
+            charges,
+            AccessCharges {
+                chunk_reads: 1,
+                branch_writes: 1,
+                chunk_writes: 1,
+                ..Default::default()
+            }
+        );
+
+        let charges = events.touch(UbtLeaf::CodeHash(address), AccessMode::Fill);
+        assert_eq!(charges, AccessCharges::default());
+
+        // Main storage lives in another stem
+        let slot = H256::from_low_u64_be(1000);
+        let charges = events.touch(UbtLeaf::Storage(address, slot), AccessMode::Fill);
+        assert_eq!(
+            charges,
+            AccessCharges {
+                branch_reads: 1,
+                chunk_reads: 1,
+                branch_writes: 1,
+                chunk_writes: 1,
+                chunk_fills: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn merge_keeps_every_leaf() {
+        let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let mut block = AccessEvents::new();
+        block.touch(UbtLeaf::BasicData(a), AccessMode::Read);
+        let mut tx = AccessEvents::new();
+        tx.touch(UbtLeaf::BasicData(a), AccessMode::Write);
+        tx.touch(UbtLeaf::CodeChunk(b, 0), AccessMode::Read);
+
+        block.merge(&tx);
+        assert_eq!(
+            block.accessed_leaves(),
+            vec![UbtLeaf::BasicData(a), UbtLeaf::CodeChunk(b, 0)]
+        );
+        // The write was merged too, so it isn't charged again
+        assert_eq!(
+            block.touch(UbtLeaf::BasicData(a), AccessMode::Write),
+            AccessCharges::default()
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     account::LevmAccount,
+    code_analysis::CodeAnalysis,
     constants::STACK_LIMIT,
     errors::{ExceptionalHalt, InternalError, VMError},
     memory::Memory,
@@ -9,7 +10,7 @@ use crate::{
 use bytes::Bytes;
 use ethrex_common::{Address, U256};
 use ethrex_common::{H256, types::Code};
-use std::{collections::HashMap, fmt, hint::assert_unchecked};
+use std::{collections::HashMap, fmt, hint::assert_unchecked, sync::Arc};
 
 /// [`u64`]s that make up a [`U256`]
 const U64_PER_U256: usize = U256::MAX.0.len();
@@ -190,6 +191,28 @@ impl Stack {
         }
         Ok(())
     }
+
+    /// Same as [`dup`](Self::dup), for a depth only known at runtime
+    pub fn dup_at(&mut self, depth: usize) -> Result<(), ExceptionalHalt> {
+        let value = self
+            .offset
+            .checked_add(depth)
+            .and_then(|index| self.values.get(index))
+            .copied()
+            .ok_or(ExceptionalHalt::StackUnderflow)?;
+        self.push(value)
+    }
+
+    /// Same as [`swap`](Self::swap), for a depth only known at runtime
+    pub fn swap_at(&mut self, depth: usize) -> Result<(), ExceptionalHalt> {
+        let index = self
+            .offset
+            .checked_add(depth)
+            .filter(|index| *index < self.values.len())
+            .ok_or(ExceptionalHalt::StackUnderflow)?;
+        self.values.swap(self.offset, index);
+        Ok(())
+    }
 }
 
 impl Default for Stack {
@@ -244,6 +267,8 @@ pub struct CallFrame {
     /// Its hash field will be bogus for initcodes, as it is inaccessible to the VM
     /// unless associated to an account, which doesn't happen for its initcode.
     pub bytecode: Code,
+    /// Basic blocks of `bytecode`, set when the first one is entered
+    pub code_analysis: Option<Arc<CodeAnalysis>>,
     /// Value sent along the transaction
     pub msg_value: U256,
     pub stack: Stack,
@@ -339,6 +364,7 @@ impl CallFrame {
             to,
             code_address,
             bytecode,
+            code_analysis: None,
             msg_value,
             calldata,
             is_static,
@@ -388,6 +414,7 @@ impl CallFrame {
 
     pub fn set_code(&mut self, code: Code) -> Result<(), VMError> {
         self.bytecode = code;
+        self.code_analysis = None;
         Ok(())
     }
 }
@@ -0,0 +1,774 @@
+//! Basic block analysis of the bytecode run by the interpreter, cached by code hash.
+//!
+//! The code is split into basic blocks, runs of instructions that always execute one after the
+//! other. A block ends after a jump, an instruction that halts or switches call frame, a
+//! `JUMPDEST`, or an instruction whose behavior depends on the gas left (`GAS` and `SSTORE`).
+//! Since jumps charge the `JUMPDEST` they land on and continue right after it, they always land at
+//! the start of a block.
+//!
+//! When a block is entered with enough gas for the static gas of all its instructions and a stack
+//! height that can't underflow nor overflow within it, that gas is charged at once and some common
+//! pairs of instructions are run as a single superinstruction. Otherwise each instruction charges
+//! its own static gas. Charging ahead can only make a dynamic cost of the block run out of gas at
+//! an earlier instruction, and running out of gas consumes all the gas of the call frame anyway.
+
+use std::ops::Range;
+
+use ethrex_common::U256;
+
+use crate::{
+    constants::STACK_LIMIT,
+    errors::{OpcodeResult, VMError},
+    gas_cost::STATIC_GAS,
+    opcodes::Opcode,
+    vm::VM,
+};
+
+/// Pair of instructions run as a single one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Superinstruction {
+    /// `PUSHn target` followed by `JUMP`
+    PushJump { target: U256 },
+    /// `PUSHn target` followed by `JUMPI`
+    PushJumpi { target: U256 },
+    /// `PUSHn value` followed by `ADD`
+    PushAdd { value: U256 },
+    /// `DUPn` followed by `SWAPm`, as the depths taken by `Stack::dup_at` and `Stack::swap_at`
+    DupSwap { dup: usize, swap: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FusedInstruction {
+    /// Position of the first instruction of the pair
+    pub pc: usize,
+    /// Position of the instruction following the pair
+    pub next_pc: usize,
+    pub superinstruction: Superinstruction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub instructions: usize,
+    /// Sum of the [`STATIC_GAS`] of its instructions
+    pub static_gas: u64,
+    /// Stack height needed for none of its instructions to underflow
+    pub min_stack_height: usize,
+    /// How much the stack height grows, at most, over the one it was entered with
+    pub max_stack_growth: usize,
+    /// Its superinstructions, as a range of [`CodeAnalysis::superinstructions`]
+    pub superinstructions: Range<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeAnalysis {
+    /// Sorted by their start
+    pub blocks: Vec<BasicBlock>,
+    /// Sorted by their pc
+    pub superinstructions: Vec<FusedInstruction>,
+}
+
+impl CodeAnalysis {
+    pub fn new(bytecode: &[u8]) -> Self {
+        let mut analysis = Self::default();
+        let mut block = BlockBuilder::new(0, 0);
+        let mut pc = 0;
+
+        while let Some(&byte) = bytecode.get(pc) {
+            let opcode = Opcode::from(byte);
+            let next_pc = pc.saturating_add(instruction_len(byte));
+
+            block.add_instruction(byte);
+            if let Some(superinstruction) = fuse(bytecode, byte, pc, next_pc) {
+                analysis.superinstructions.push(superinstruction);
+            }
+
+            pc = next_pc;
+            if ends_block(opcode) {
+                let next_block = BlockBuilder::new(pc, analysis.superinstructions.len());
+                let finished = std::mem::replace(&mut block, next_block);
+                analysis
+                    .blocks
+                    .push(finished.finish(analysis.superinstructions.len()));
+            }
+        }
+
+        if block.block.instructions > 0 {
+            analysis
+                .blocks
+                .push(block.finish(analysis.superinstructions.len()));
+        }
+
+        analysis
+    }
+
+    /// Returns the block starting at `pc`, if any
+    pub fn block_at(&self, pc: usize) -> Option<&BasicBlock> {
+        let index = self
+            .blocks
+            .binary_search_by_key(&pc, |block| block.start)
+            .ok()?;
+        self.blocks.get(index)
+    }
+}
+
+struct BlockBuilder {
+    block: BasicBlock,
+    /// Stack height relative to the one the block was entered with
+    stack_height: isize,
+    min_stack_height: isize,
+    max_stack_growth: isize,
+}
+
+impl BlockBuilder {
+    fn new(start: usize, first_superinstruction: usize) -> Self {
+        Self {
+            block: BasicBlock {
+                start,
+                instructions: 0,
+                static_gas: 0,
+                min_stack_height: 0,
+                max_stack_growth: 0,
+                superinstructions: first_superinstruction..first_superinstruction,
+            },
+            stack_height: 0,
+            min_stack_height: 0,
+            max_stack_growth: 0,
+        }
+    }
+
+    #[allow(clippy::indexing_slicing, clippy::as_conversions)]
+    fn add_instruction(&mut self, byte: u8) {
+        let (inputs, outputs) = stack_io(byte);
+        self.min_stack_height = self
+            .min_stack_height
+            .max(inputs.saturating_sub(self.stack_height));
+        self.stack_height = self
+            .stack_height
+            .saturating_sub(inputs)
+            .saturating_add(outputs);
+        self.max_stack_growth = self.max_stack_growth.max(self.stack_height);
+
+        self.block.instructions = self.block.instructions.saturating_add(1);
+        self.block.static_gas = self
+            .block
+            .static_gas
+            .saturating_add(STATIC_GAS[byte as usize]);
+    }
+
+    fn finish(mut self, superinstructions_end: usize) -> BasicBlock {
+        self.block.min_stack_height = usize::try_from(self.min_stack_height).unwrap_or_default();
+        self.block.max_stack_growth = usize::try_from(self.max_stack_growth).unwrap_or_default();
+        self.block.superinstructions.end = superinstructions_end;
+        self.block
+    }
+}
+
+/// Length of the instruction, including the immediate value of a `PUSHn`
+fn instruction_len(byte: u8) -> usize {
+    if (u8::from(Opcode::PUSH1)..=u8::from(Opcode::PUSH32)).contains(&byte) {
+        usize::from(byte.wrapping_sub(u8::from(Opcode::PUSH0))).saturating_add(1)
+    } else {
+        1
+    }
+}
+
+/// Returns whether no instruction of the block can run after this one
+fn ends_block(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::STOP
+            | Opcode::JUMP
+            | Opcode::JUMPI
+            | Opcode::JUMPDEST
+            | Opcode::GAS
+            | Opcode::SSTORE
+            | Opcode::CREATE
+            | Opcode::CALL
+            | Opcode::CALLCODE
+            | Opcode::RETURN
+            | Opcode::DELEGATECALL
+            | Opcode::CREATE2
+            | Opcode::STATICCALL
+            | Opcode::REVERT
+            | Opcode::INVALID
+            | Opcode::SELFDESTRUCT
+    )
+}
+
+/// Number of stack items taken and pushed by an instruction
+fn stack_io(byte: u8) -> (isize, isize) {
+    match byte {
+        // PUSH0 ... PUSH32
+        0x5F..=0x7F => return (0, 1),
+        // DUP1 ... DUP16
+        0x80..=0x8F => {
+            let depth = isize::from(byte.wrapping_sub(0x7F));
+            return (depth, depth.saturating_add(1));
+        }
+        // SWAP1 ... SWAP16
+        0x90..=0x9F => {
+            let depth = isize::from(byte.wrapping_sub(0x8E));
+            return (depth, depth);
+        }
+        // LOG0 ... LOG4
+        0xA0..=0xA4 => return (isize::from(byte.wrapping_sub(0x9E)), 0),
+        _ => {}
+    }
+
+    match Opcode::from(byte) {
+        Opcode::STOP | Opcode::JUMPDEST | Opcode::INVALID => (0, 0),
+        Opcode::ADDRESS
+        | Opcode::ORIGIN
+        | Opcode::CALLER
+        | Opcode::CALLVALUE
+        | Opcode::CALLDATASIZE
+        | Opcode::CODESIZE
+        | Opcode::GASPRICE
+        | Opcode::RETURNDATASIZE
+        | Opcode::COINBASE
+        | Opcode::TIMESTAMP
+        | Opcode::NUMBER
+        | Opcode::PREVRANDAO
+        | Opcode::GASLIMIT
+        | Opcode::CHAINID
+        | Opcode::SELFBALANCE
+        | Opcode::BASEFEE
+        | Opcode::BLOBBASEFEE
+        | Opcode::PC
+        | Opcode::MSIZE
+        | Opcode::GAS => (0, 1),
+        Opcode::POP | Opcode::JUMP | Opcode::SELFDESTRUCT => (1, 0),
+        Opcode::ISZERO
+        | Opcode::NOT
+        | Opcode::CLZ
+        | Opcode::BALANCE
+        | Opcode::CALLDATALOAD
+        | Opcode::EXTCODESIZE
+        | Opcode::EXTCODEHASH
+        | Opcode::BLOCKHASH
+        | Opcode::BLOBHASH
+        | Opcode::MLOAD
+        | Opcode::SLOAD
+        | Opcode::TLOAD => (1, 1),
+        Opcode::MSTORE
+        | Opcode::MSTORE8
+        | Opcode::SSTORE
+        | Opcode::JUMPI
+        | Opcode::TSTORE
+        | Opcode::RETURN
+        | Opcode::REVERT => (2, 0),
+        Opcode::ADD
+        | Opcode::MUL
+        | Opcode::SUB
+        | Opcode::DIV
+        | Opcode::SDIV
+        | Opcode::MOD
+        | Opcode::SMOD
+        | Opcode::EXP
+        | Opcode::SIGNEXTEND
+        | Opcode::LT
+        | Opcode::GT
+        | Opcode::SLT
+        | Opcode::SGT
+        | Opcode::EQ
+        | Opcode::AND
+        | Opcode::OR
+        | Opcode::XOR
+        | Opcode::BYTE
+        | Opcode::SHL
+        | Opcode::SHR
+        | Opcode::SAR
+        | Opcode::KECCAK256 => (2, 1),
+        Opcode::CALLDATACOPY | Opcode::CODECOPY | Opcode::RETURNDATACOPY | Opcode::MCOPY => (3, 0),
+        Opcode::ADDMOD | Opcode::MULMOD | Opcode::CREATE => (3, 1),
+        Opcode::EXTCODECOPY => (4, 0),
+        Opcode::CREATE2 => (4, 1),
+        Opcode::DELEGATECALL | Opcode::STATICCALL => (6, 1),
+        Opcode::CALL | Opcode::CALLCODE => (7, 1),
+        // PUSH, DUP, SWAP and LOG were handled above
+        _ => (0, 0),
+    }
+}
+
+/// Returns the superinstruction starting at `pc`, if it's followed by one it can be fused with
+fn fuse(bytecode: &[u8], byte: u8, pc: usize, next_pc: usize) -> Option<FusedInstruction> {
+    let next_byte = *bytecode.get(next_pc)?;
+    let opcode = Opcode::from(byte);
+    let next_opcode = Opcode::from(next_byte);
+
+    let superinstruction = if (Opcode::PUSH1..=Opcode::PUSH32).contains(&opcode) {
+        // The immediate value is complete, since another instruction follows it
+        let value = U256::from_big_endian(bytecode.get(pc.checked_add(1)?..next_pc)?);
+        match next_opcode {
+            Opcode::JUMP => Superinstruction::PushJump { target: value },
+            Opcode::JUMPI => Superinstruction::PushJumpi { target: value },
+            Opcode::ADD => Superinstruction::PushAdd { value },
+            _ => return None,
+        }
+    } else if (Opcode::DUP1..=Opcode::DUP16).contains(&opcode)
+        && (Opcode::SWAP1..=Opcode::SWAP16).contains(&next_opcode)
+    {
+        Superinstruction::DupSwap {
+            dup: usize::from(byte.wrapping_sub(u8::from(Opcode::DUP1))),
+            swap: usize::from(next_byte.wrapping_sub(u8::from(Opcode::DUP16))),
+        }
+    } else {
+        return None;
+    };
+
+    Some(FusedInstruction {
+        pc,
+        next_pc: next_pc.checked_add(1)?,
+        superinstruction,
+    })
+}
+
+/// Where the interpreter loop is within the basic blocks of the current call frame
+#[derive(Debug, Default)]
+pub(crate) struct BlockCursor {
+    /// Instructions of the current block left to run
+    remaining: usize,
+    /// Whether the static gas of the current block was charged when entering it
+    prepaid: bool,
+    /// Next superinstruction of the current block, only set if it was prepaid
+    next_fused: Option<FusedInstruction>,
+    /// Index of the superinstruction following `next_fused`, and end of the block's ones
+    fused_index: usize,
+    fused_end: usize,
+}
+
+impl BlockCursor {
+    pub(crate) fn at_block_boundary(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl<'a> VM<'a> {
+    /// Enters the basic block starting at the current pc, charging its static gas if it can't run
+    /// out of gas nor stack. Nothing is entered if no block starts there, e.g. past the code end.
+    pub(crate) fn enter_block(&mut self, cursor: &mut BlockCursor) {
+        *cursor = BlockCursor::default();
+
+        let call_frame = &mut self.current_call_frame;
+        if call_frame.code_analysis.is_none() {
+            call_frame.code_analysis = Some(self.db.get_code_analysis(&call_frame.bytecode));
+        }
+        let Some(analysis) = &call_frame.code_analysis else {
+            return;
+        };
+        let Some(block) = analysis.block_at(call_frame.pc) else {
+            return;
+        };
+
+        cursor.remaining = block.instructions;
+        let static_gas = i64::try_from(block.static_gas).unwrap_or(i64::MAX);
+        let stack_height = call_frame.stack.len();
+        if call_frame.gas_remaining < static_gas
+            || stack_height < block.min_stack_height
+            || stack_height.saturating_add(block.max_stack_growth) > STACK_LIMIT
+        {
+            return;
+        }
+
+        let fused = block.superinstructions.clone();
+        cursor.prepaid = true;
+        cursor.next_fused = analysis
+            .superinstructions
+            .get(fused.clone())
+            .and_then(<[_]>::first)
+            .copied();
+        cursor.fused_index = fused.start.saturating_add(1);
+        cursor.fused_end = fused.end;
+
+        #[expect(clippy::arithmetic_side_effects, reason = "checked above")]
+        {
+            call_frame.gas_remaining -= static_gas;
+        }
+    }
+
+    /// Runs the instruction whose opcode was just read, and the one following it if they form a
+    /// superinstruction. Its static gas is charged here unless its block was prepaid.
+    #[inline(always)]
+    pub(crate) fn execute_instruction(
+        &mut self,
+        opcode: u8,
+        cursor: &mut BlockCursor,
+    ) -> Result<OpcodeResult, VMError> {
+        cursor.remaining = cursor.remaining.saturating_sub(1);
+
+        if cursor.prepaid {
+            if let Some(fused) = cursor.next_fused {
+                // The pc was already advanced past the opcode
+                if fused.pc.wrapping_add(1) == self.current_call_frame.pc {
+                    cursor.remaining = cursor.remaining.saturating_sub(1);
+                    cursor.next_fused = self
+                        .current_call_frame
+                        .code_analysis
+                        .as_ref()
+                        .and_then(|analysis| {
+                            analysis
+                                .superinstructions
+                                .get(cursor.fused_index..cursor.fused_end)
+                        })
+                        .and_then(<[_]>::first)
+                        .copied();
+                    cursor.fused_index = cursor.fused_index.saturating_add(1);
+                    return self.execute_superinstruction(fused);
+                }
+            }
+        } else {
+            #[allow(clippy::indexing_slicing, clippy::as_conversions)]
+            self.current_call_frame
+                .increase_consumed_gas(STATIC_GAS[opcode as usize])?;
+        }
+
+        // Call the opcode, using the opcode function lookup table.
+        // Indexing will not panic as all the opcode values fit within the table.
+        #[allow(clippy::indexing_slicing, clippy::as_conversions)]
+        let handler = self.opcode_table[opcode as usize];
+        handler.call(self)
+    }
+
+    fn execute_superinstruction(
+        &mut self,
+        fused: FusedInstruction,
+    ) -> Result<OpcodeResult, VMError> {
+        let call_frame = &mut self.current_call_frame;
+        match fused.superinstruction {
+            Superinstruction::PushJump { target } => {
+                Self::jump(call_frame, target)?;
+            }
+            Superinstruction::PushJumpi { target } => {
+                if call_frame.stack.pop1()?.is_zero() {
+                    call_frame.pc = fused.next_pc;
+                } else {
+                    Self::jump(call_frame, target)?;
+                }
+            }
+            Superinstruction::PushAdd { value } => {
+                let addend = call_frame.stack.pop1()?;
+                call_frame.stack.push(value.overflowing_add(addend).0)?;
+                call_frame.pc = fused.next_pc;
+            }
+            Superinstruction::DupSwap { dup, swap } => {
+                call_frame.stack.dup_at(dup)?;
+                call_frame.stack.swap_at(swap)?;
+                call_frame.pc = fused.next_pc;
+            }
+        }
+
+        Ok(OpcodeResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{Database, gen_db::GeneralizedDatabase},
+        environment::{EVMConfig, Environment},
+        errors::{DatabaseError, ExceptionalHalt, ExecutionReport, TxResult},
+        tracing::LevmCallTracer,
+        vm::VMType,
+    };
+    use bytes::Bytes;
+    use ethrex_common::{
+        Address, H256,
+        constants::EMPTY_KECCACK_HASH,
+        types::{AccountState, ChainConfig, Code, EIP1559Transaction, Fork, Transaction, TxKind},
+    };
+    use rustc_hash::FxHashMap;
+    use std::sync::Arc;
+
+    const SENDER: Address = Address::repeat_byte(0x01);
+    const CONTRACT: Address = Address::repeat_byte(0x10);
+    const CHILD: Address = Address::repeat_byte(0x20);
+
+    /// Funded accounts with empty storage, some of them holding code
+    struct Accounts(FxHashMap<Address, Code>);
+
+    impl Database for Accounts {
+        fn get_account_state(&self, address: Address) -> Result<AccountState, DatabaseError> {
+            Ok(AccountState {
+                balance: U256::from(10).pow(U256::from(18)),
+                code_hash: self
+                    .0
+                    .get(&address)
+                    .map_or(*EMPTY_KECCACK_HASH, |code| code.hash),
+                ..Default::default()
+            })
+        }
+
+        fn get_storage_value(&self, _: Address, _: H256) -> Result<U256, DatabaseError> {
+            Ok(U256::zero())
+        }
+
+        fn get_block_hash(&self, _: u64) -> Result<H256, DatabaseError> {
+            Ok(H256::zero())
+        }
+
+        fn get_chain_config(&self) -> Result<ChainConfig, DatabaseError> {
+            Ok(ChainConfig::default())
+        }
+
+        fn get_account_code(&self, code_hash: H256) -> Result<Code, DatabaseError> {
+            Ok(self
+                .0
+                .values()
+                .find(|code| code.hash == code_hash)
+                .cloned()
+                .unwrap_or_default())
+        }
+    }
+
+    /// Calls CONTRACT running `code`, with `gas` on top of the intrinsic gas. CHILD runs `child`.
+    fn run(
+        code: &[u8],
+        child: &[u8],
+        gas: u64,
+        prepay_basic_blocks: bool,
+    ) -> (ExecutionReport, GeneralizedDatabase) {
+        let accounts = Accounts(FxHashMap::from_iter([
+            (CONTRACT, Code::from_bytecode(Bytes::copy_from_slice(code))),
+            (CHILD, Code::from_bytecode(Bytes::copy_from_slice(child))),
+        ]));
+        let mut db = GeneralizedDatabase::new(Arc::new(accounts));
+        let gas_limit = gas.saturating_add(crate::constants::TX_BASE_COST);
+        let tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+            chain_id: 1,
+            gas_limit,
+            to: TxKind::Call(CONTRACT),
+            ..Default::default()
+        });
+        let env = Environment {
+            origin: SENDER,
+            gas_limit,
+            config: EVMConfig::new(Fork::Cancun, EVMConfig::canonical_values(Fork::Cancun)),
+            chain_id: U256::one(),
+            block_gas_limit: gas_limit,
+            tx_max_fee_per_gas: Some(U256::zero()),
+            tx_max_priority_fee_per_gas: Some(U256::zero()),
+            ..Default::default()
+        };
+
+        let mut vm = VM::new(env, &mut db, &tx, LevmCallTracer::disabled(), VMType::L1).unwrap();
+        vm.prepay_basic_blocks = prepay_basic_blocks;
+        let report = vm.execute().unwrap();
+        drop(vm);
+        (report, db)
+    }
+
+    /// Runs the code with each amount of gas, checking that prepaying basic blocks ends with the
+    /// same result, gas used, output and state as charging instruction by instruction
+    fn assert_matches_per_instruction(
+        code: &[u8],
+        child: &[u8],
+        gas: impl IntoIterator<Item = u64>,
+    ) -> Vec<(ExecutionReport, GeneralizedDatabase)> {
+        gas.into_iter()
+            .map(|gas| {
+                let (report, db) = run(code, child, gas, true);
+                let (expected, expected_db) = run(code, child, gas, false);
+                assert_eq!(report, expected, "with {gas} gas");
+                assert_eq!(
+                    db.current_accounts_state, expected_db.current_accounts_state,
+                    "with {gas} gas"
+                );
+                (report, db)
+            })
+            .collect()
+    }
+
+    fn storage(db: &GeneralizedDatabase, address: Address, slot: u64) -> U256 {
+        db.current_accounts_state
+            .get(&address)
+            .and_then(|account| account.storage.get(&H256::from_low_u64_be(slot)))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn halted_with(report: &ExecutionReport, halt: ExceptionalHalt) -> bool {
+        report.result == TxResult::Revert(VMError::ExceptionalHalt(halt))
+    }
+
+    #[test]
+    fn splits_blocks_and_fuses_instructions() {
+        let bytecode = [
+            0x60, 0x04, // PUSH1 4
+            0x56, // JUMP
+            0xFE, // INVALID
+            0x5B, // JUMPDEST
+            0x60, 0x01, // PUSH1 1
+            0x60, 0x02, // PUSH1 2
+            0x01, // ADD
+            0x80, // DUP1
+            0x91, // SWAP2
+            0x00, // STOP
+        ];
+        let analysis = CodeAnalysis::new(&bytecode);
+
+        let starts: Vec<usize> = analysis.blocks.iter().map(|block| block.start).collect();
+        assert_eq!(starts, vec![0, 3, 4, 5]);
+        assert_eq!(analysis.block_at(0).unwrap().static_gas, 11);
+        assert_eq!(
+            analysis.block_at(5),
+            Some(&BasicBlock {
+                start: 5,
+                instructions: 6,
+                static_gas: 15,
+                min_stack_height: 1,
+                max_stack_growth: 2,
+                superinstructions: 1..3,
+            })
+        );
+        assert!(analysis.block_at(6).is_none());
+
+        assert_eq!(
+            analysis.superinstructions,
+            vec![
+                FusedInstruction {
+                    pc: 0,
+                    next_pc: 3,
+                    superinstruction: Superinstruction::PushJump {
+                        target: U256::from(4)
+                    },
+                },
+                FusedInstruction {
+                    pc: 7,
+                    next_pc: 10,
+                    superinstruction: Superinstruction::PushAdd {
+                        value: U256::from(2)
+                    },
+                },
+                FusedInstruction {
+                    pc: 10,
+                    next_pc: 12,
+                    superinstruction: Superinstruction::DupSwap { dup: 0, swap: 2 },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn runs_out_of_gas_within_a_prepaid_block() {
+        // SLOAD(0), SLOAD(1): a single block of 5 static gas with two cold reads of 2100
+        let code = [0x5f, 0x54, 0x60, 0x01, 0x54, 0x00];
+        let runs = assert_matches_per_instruction(&code, &[], 4100..=4300);
+        // Enough from 4205 gas on
+        let first_success = runs.iter().position(|(report, _)| report.is_success());
+        assert_eq!(first_success, Some(105));
+        assert!(halted_with(
+            &runs.first().unwrap().0,
+            ExceptionalHalt::OutOfGas
+        ));
+    }
+
+    #[test]
+    fn gas_and_sstore_end_their_blocks() {
+        let code = [
+            0x60, 0x01, 0x5f, 0x55, // SSTORE(0, 1)
+            0x60, 0x02, 0x5f, 0x55, // SSTORE(0, 2), only with more than 2300 gas left
+            0x5a, 0x60, 0x01, 0x55, // SSTORE(1, GAS)
+            0x00,
+        ];
+        let runs =
+            assert_matches_per_instruction(&code, &[], (24_360..=24_460).chain(44_265..=44_365));
+        let (report, db) = runs.last().unwrap();
+        assert!(report.is_success());
+        assert_eq!(storage(db, CONTRACT, 0), U256::from(2));
+        // GAS was charged before reading the gas left, nothing after it was
+        assert_eq!(storage(db, CONTRACT, 1), U256::from(22_153));
+    }
+
+    #[test]
+    fn falls_back_to_per_instruction_charging_on_stack_errors() {
+        // ADD with an empty stack
+        let runs = assert_matches_per_instruction(&[0x01, 0x00], &[], [10]);
+        assert!(halted_with(
+            &runs.first().unwrap().0,
+            ExceptionalHalt::StackUnderflow
+        ));
+
+        // PUSH0 one more time than the stack fits
+        let mut code = vec![0x5f; STACK_LIMIT.saturating_add(1)];
+        code.push(0x00);
+        let runs = assert_matches_per_instruction(&code, &[], [2100, 3000]);
+        for (report, _) in runs {
+            assert!(halted_with(&report, ExceptionalHalt::StackOverflow));
+        }
+    }
+
+    #[test]
+    fn runs_every_superinstruction() {
+        let code = [
+            0x60, 0x01, 0x60, 0x08, 0x57, // JUMPI(8, 1), taken
+            0xFE, 0xFE, 0xFE, // INVALID
+            0x5B, // JUMPDEST
+            0x5F, 0x60, 0x00, 0x57, // JUMPI(0, 0), not taken
+            0x60, 0x11, 0x56, // JUMP(17)
+            0xFE, // INVALID
+            0x5B, // JUMPDEST
+            0x5F, 0x60, 0x05, 0x60, 0x07, 0x01, // 0, 5 + 7
+            0x80, 0x91, // DUP1, SWAP2
+            0x55, // SSTORE(0, 12)
+            0x60, 0x01, 0x55, // SSTORE(1, 12)
+            0x00,
+        ];
+        let analysis = CodeAnalysis::new(&code);
+        let fused: Vec<usize> = analysis
+            .superinstructions
+            .iter()
+            .map(|fused| fused.pc)
+            .collect();
+        assert_eq!(fused, vec![2, 10, 13, 21, 24]);
+
+        let runs = assert_matches_per_instruction(&code, &[], (0..=60).chain([100_000]));
+        let (report, db) = runs.last().unwrap();
+        assert!(report.is_success());
+        assert_eq!(storage(db, CONTRACT, 0), U256::from(12));
+        assert_eq!(storage(db, CONTRACT, 1), U256::from(12));
+    }
+
+    #[test]
+    fn jumps_to_invalid_destinations_halt() {
+        // JUMP(3) and JUMPI(6, 1) onto a STOP
+        for code in [
+            &[0x60, 0x03, 0x56, 0x00][..],
+            &[0x60, 0x01, 0x60, 0x06, 0x57, 0x00, 0x00][..],
+        ] {
+            let runs = assert_matches_per_instruction(code, &[], [100]);
+            assert!(halted_with(
+                &runs.first().unwrap().0,
+                ExceptionalHalt::InvalidJump
+            ));
+        }
+    }
+
+    #[test]
+    fn returns_into_the_parent_block_after_call_and_create() {
+        let mut code = vec![0x5F; 5];
+        code.push(0x73);
+        code.extend_from_slice(CHILD.as_bytes());
+        code.extend_from_slice(&[
+            0x5A, 0xF1, // CALL(GAS, CHILD, 0, 0, 0, 0, 0)
+            0x60, 0x01, 0x01, 0x5F, 0x55, // SSTORE(0, success + 1)
+            0x5F, 0x5F, 0x5F, 0xF0, // CREATE(0, 0, 0)
+            0x60, 0x01, 0x55, // SSTORE(1, address)
+            0x00,
+        ]);
+        // SSTORE(0, 42)
+        let child = [0x60, 0x2A, 0x5F, 0x55, 0x00];
+
+        let runs = assert_matches_per_instruction(
+            &code,
+            &child,
+            (0..=40).chain(2600..=2700).chain([200_000]),
+        );
+        let (report, db) = runs.last().unwrap();
+        assert!(report.is_success());
+        assert_eq!(storage(db, CHILD, 0), U256::from(42));
+        assert_eq!(storage(db, CONTRACT, 0), U256::from(2));
+        assert!(!storage(db, CONTRACT, 1).is_zero());
+    }
+}
@@ -0,0 +1,355 @@
+//! Attribution of gas and execution time to the contracts and functions being executed.
+//!
+//! Every call frame is identified by the address of the code it runs and its entry point (the
+//! 4-byte selector of its calldata, the fallback or contract creation). The gas and wall time of
+//! each frame are split between the frame itself and its subcalls, so that self costs can be
+//! aggregated both per contract and per call stack. The latter is exported as folded stacks,
+//! the input format of flamegraph tools.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use ethrex_common::Address;
+use serde::Serialize;
+
+use crate::{call_frame::CallFrame, errors::ContextResult, opcodes::Opcode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EntryPoint {
+    Create,
+    /// Called with less than 4 bytes of calldata
+    Fallback,
+    Selector([u8; 4]),
+}
+
+/// Code being executed by a call frame and how it was entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContractKey {
+    pub address: Address,
+    pub entry_point: EntryPoint,
+}
+
+impl ContractKey {
+    fn from_call_frame(call_frame: &CallFrame) -> Self {
+        let entry_point = if call_frame.is_create {
+            EntryPoint::Create
+        } else {
+            match call_frame.calldata.first_chunk::<4>() {
+                Some(selector) => EntryPoint::Selector(*selector),
+                None => EntryPoint::Fallback,
+            }
+        };
+        Self {
+            address: call_frame.code_address,
+            entry_point,
+        }
+    }
+}
+
+impl fmt::Display for ContractKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.entry_point {
+            EntryPoint::Create => write!(f, "{:#x}::create", self.address),
+            EntryPoint::Fallback => write!(f, "{:#x}::fallback", self.address),
+            EntryPoint::Selector(selector) => {
+                write!(f, "{:#x}::0x{}", self.address, hex::encode(selector))
+            }
+        }
+    }
+}
+
+/// Costs of the frames that ran a contract entry point, excluding their subcalls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContractStats {
+    pub calls: u64,
+    pub gas_used: u64,
+    pub time: Duration,
+    pub sloads: u64,
+    pub sstores: u64,
+    pub precompile_time: Duration,
+}
+
+impl ContractStats {
+    fn add(&mut self, other: &ContractStats) {
+        self.calls = self.calls.saturating_add(other.calls);
+        self.gas_used = self.gas_used.saturating_add(other.gas_used);
+        self.time = self.time.saturating_add(other.time);
+        self.sloads = self.sloads.saturating_add(other.sloads);
+        self.sstores = self.sstores.saturating_add(other.sstores);
+        self.precompile_time = self.precompile_time.saturating_add(other.precompile_time);
+    }
+}
+
+/// What the folded stacks are weighted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileWeight {
+    Gas,
+    /// Wall time, in nanoseconds
+    Time,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContractReportEntry {
+    pub contract: String,
+    pub calls: u64,
+    pub gas_used: u64,
+    pub time_ns: u128,
+    pub sloads: u64,
+    pub sstores: u64,
+    pub precompile_time_ns: u128,
+}
+
+/// Per contract profile, sorted by gas used in descending order
+#[derive(Debug, Clone, Serialize)]
+pub struct ContractProfileReport {
+    pub blocks: usize,
+    pub txs: usize,
+    pub contracts: Vec<ContractReportEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct ContractProfile {
+    contracts: HashMap<ContractKey, ContractStats>,
+    /// Self gas and time of every call stack, outermost frame first
+    stacks: HashMap<Vec<ContractKey>, (u64, Duration)>,
+    blocks: usize,
+    txs: usize,
+}
+
+impl ContractProfile {
+    fn record(&mut self, stack: Vec<ContractKey>, stats: ContractStats) {
+        let Some(key) = stack.last() else {
+            return;
+        };
+        self.contracts.entry(*key).or_default().add(&stats);
+        let (gas, time) = self.stacks.entry(stack).or_default();
+        *gas = gas.saturating_add(stats.gas_used);
+        *time = time.saturating_add(stats.time);
+    }
+
+    fn merge(&mut self, other: ContractProfile) {
+        for (key, stats) in other.contracts {
+            self.contracts.entry(key).or_default().add(&stats);
+        }
+        for (stack, (gas, time)) in other.stacks {
+            let entry = self.stacks.entry(stack).or_default();
+            entry.0 = entry.0.saturating_add(gas);
+            entry.1 = entry.1.saturating_add(time);
+        }
+        self.blocks = self.blocks.saturating_add(other.blocks);
+        self.txs = self.txs.saturating_add(other.txs);
+    }
+
+    pub fn inc_block_count(&mut self) {
+        self.blocks = self.blocks.saturating_add(1);
+    }
+
+    pub fn report(&self) -> ContractProfileReport {
+        let mut contracts: Vec<(&ContractKey, &ContractStats)> = self.contracts.iter().collect();
+        contracts.sort_by(|a, b| b.1.gas_used.cmp(&a.1.gas_used).then(a.0.cmp(b.0)));
+        ContractProfileReport {
+            blocks: self.blocks,
+            txs: self.txs,
+            contracts: contracts
+                .into_iter()
+                .map(|(key, stats)| ContractReportEntry {
+                    contract: key.to_string(),
+                    calls: stats.calls,
+                    gas_used: stats.gas_used,
+                    time_ns: stats.time.as_nanos(),
+                    sloads: stats.sloads,
+                    sstores: stats.sstores,
+                    precompile_time_ns: stats.precompile_time.as_nanos(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns one `frame;frame;frame weight` line per call stack, sorted by stack
+    pub fn folded_stacks(&self, weight: ProfileWeight) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, (gas, time))| {
+                let frames: Vec<String> = stack.iter().map(ToString::to_string).collect();
+                let weight = match weight {
+                    ProfileWeight::Gas => u128::from(*gas),
+                    ProfileWeight::Time => time.as_nanos(),
+                };
+                format!("{} {weight}", frames.join(";"))
+            })
+            .collect();
+        lines.sort();
+        lines.join("\n")
+    }
+
+    pub fn info_pretty(&self, top: usize) -> String {
+        let report = self.report();
+        let mut out = format!(
+            "[PERF] contract profile (blocks={}, txs={}, top {top} by gas used):\n",
+            report.blocks, report.txs
+        );
+        for entry in report.contracts.iter().take(top) {
+            out.push_str(&format!(
+                "{:<64} {:>14} gas {:>18?} ({:>8} calls, {:>8} sloads, {:>8} sstores, {:?} in precompiles)\n",
+                entry.contract,
+                entry.gas_used,
+                Duration::from_nanos(u64::try_from(entry.time_ns).unwrap_or(u64::MAX)),
+                entry.calls,
+                entry.sloads,
+                entry.sstores,
+                Duration::from_nanos(u64::try_from(entry.precompile_time_ns).unwrap_or(u64::MAX)),
+            ));
+        }
+        out
+    }
+}
+
+pub static CONTRACT_PROFILE: LazyLock<Mutex<ContractProfile>> =
+    LazyLock::new(|| Mutex::new(ContractProfile::default()));
+
+/// Adds the profile of a transaction executed as part of a block to [`CONTRACT_PROFILE`]
+pub fn record_transaction(profile: ContractProfile) {
+    if let Ok(mut global) = CONTRACT_PROFILE.lock() {
+        global.merge(profile);
+    }
+}
+
+thread_local! {
+    /// Time spent in precompiles since it was last taken by the frame profiler
+    static PRECOMPILE_TIME: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+pub(crate) fn record_precompile_time(time: Duration) {
+    PRECOMPILE_TIME.with(|total| total.set(total.get().saturating_add(time)));
+}
+
+fn take_precompile_time() -> Duration {
+    PRECOMPILE_TIME.with(|total| total.replace(Duration::ZERO))
+}
+
+struct ProfiledFrame {
+    key: ContractKey,
+    started: Instant,
+    stats: ContractStats,
+    /// Gas and time of the subcalls, which are part of what the frame reports
+    children_gas: u64,
+    children_time: Duration,
+}
+
+/// Follows the call frames of a transaction, recording the costs of each one when it returns.
+/// The costs of the transaction are added to the profile of its VM once its initial frame returns.
+#[derive(Default)]
+pub(crate) struct FrameProfiler {
+    frames: Vec<ProfiledFrame>,
+    profile: ContractProfile,
+}
+
+impl FrameProfiler {
+    /// Called before executing each opcode of the call frame at `depth` (0 for the initial frame)
+    pub(crate) fn on_step(&mut self, call_frame: &CallFrame, depth: usize, opcode: u8) {
+        let precompile_time = take_precompile_time();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.stats.precompile_time =
+                frame.stats.precompile_time.saturating_add(precompile_time);
+        }
+
+        // A call frame always executes at least one opcode, so this is where frames are entered
+        if self.frames.len() <= depth {
+            self.frames.push(ProfiledFrame {
+                key: ContractKey::from_call_frame(call_frame),
+                started: Instant::now(),
+                stats: ContractStats {
+                    calls: 1,
+                    ..Default::default()
+                },
+                children_gas: 0,
+                children_time: Duration::ZERO,
+            });
+        }
+
+        if let Some(frame) = self.frames.last_mut() {
+            match Opcode::from(opcode) {
+                Opcode::SLOAD => frame.stats.sloads = frame.stats.sloads.saturating_add(1),
+                Opcode::SSTORE => frame.stats.sstores = frame.stats.sstores.saturating_add(1),
+                _ => {}
+            }
+        }
+    }
+
+    /// Called once the current call frame returns with `result`
+    pub(crate) fn on_frame_exit(
+        &mut self,
+        result: &ContextResult,
+        tx_profile: &mut ContractProfile,
+    ) {
+        let stack: Vec<ContractKey> = self.frames.iter().map(|frame| frame.key).collect();
+        let Some(mut frame) = self.frames.pop() else {
+            return;
+        };
+        let elapsed = frame.started.elapsed();
+        frame.stats.precompile_time = frame
+            .stats
+            .precompile_time
+            .saturating_add(take_precompile_time());
+        frame.stats.gas_used = result.gas_used.saturating_sub(frame.children_gas);
+        frame.stats.time = elapsed.saturating_sub(frame.children_time);
+        self.profile.record(stack, frame.stats);
+
+        match self.frames.last_mut() {
+            Some(parent) => {
+                parent.children_gas = parent.children_gas.saturating_add(result.gas_used);
+                parent.children_time = parent.children_time.saturating_add(elapsed);
+            }
+            None => {
+                let mut profile = std::mem::take(&mut self.profile);
+                profile.txs = 1;
+                tx_profile.merge(profile);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_stacks_with_self_costs() {
+        let outer = ContractKey {
+            address: Address::repeat_byte(1),
+            entry_point: EntryPoint::Selector([0xa9, 0x05, 0x9c, 0xbb]),
+        };
+        let inner = ContractKey {
+            address: Address::repeat_byte(2),
+            entry_point: EntryPoint::Fallback,
+        };
+        let stats = |gas_used| ContractStats {
+            calls: 1,
+            gas_used,
+            ..Default::default()
+        };
+
+        let mut profile = ContractProfile::default();
+        profile.record(vec![outer], stats(100));
+        profile.record(vec![outer, inner], stats(30));
+        profile.record(vec![outer, inner], stats(20));
+
+        let report = profile.report();
+        assert_eq!(report.contracts.len(), 2);
+        assert_eq!(report.contracts.first().unwrap().gas_used, 100);
+        assert_eq!(report.contracts.get(1).unwrap().calls, 2);
+        assert_eq!(
+            profile.folded_stacks(ProfileWeight::Gas),
+            format!(
+                "{:#x}::0xa9059cbb 100\n{:#x}::0xa9059cbb;{:#x}::fallback 50",
+                outer.address, outer.address, inner.address
+            )
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use bytes::Bytes;
+use ethrex_common::{
+    Address, H256,
+    types::custom_precompiles::{CustomPrecompileConfig, CustomPrecompileKind},
+};
+use lambdaworks_crypto::hash::poseidon::{Poseidon, starknet::PoseidonCairoStark252};
+use lambdaworks_math::{
+    field::{
+        element::FieldElement, fields::fft_friendly::stark_252_prime_field::Stark252PrimeField,
+    },
+    traits::ByteConversion,
+};
+
+use crate::{
+    db::gen_db::GeneralizedDatabase,
+    errors::{PrecompileError, VMError},
+    gas_cost,
+    precompiles::increase_precompile_consumed_gas,
+    vm::StorageAccesses,
+};
+
+type StarkFelt = FieldElement<Stark252PrimeField>;
+
+const WORD_SIZE: usize = 32;
+
+/// Executes one of the custom precompiles declared in the chain config of an L2
+pub fn execute_custom_precompile(
+    precompile: &CustomPrecompileConfig,
+    calldata: &Bytes,
+    gas_remaining: &mut u64,
+    db: &mut GeneralizedDatabase,
+    storage_accesses: &mut Option<StorageAccesses>,
+) -> Result<Bytes, VMError> {
+    let gas_cost =
+        gas_cost::custom_precompile(calldata.len(), precompile.base_gas, precompile.word_gas)?;
+    increase_precompile_consumed_gas(gas_cost, gas_remaining)?;
+
+    match precompile.kind {
+        CustomPrecompileKind::Poseidon => poseidon(calldata),
+        CustomPrecompileKind::StorageOracle { contract } => {
+            storage_oracle(contract, calldata, db, storage_accesses)
+        }
+    }
+}
+
+/// Hashes the calldata, which must be a list of 32-byte big-endian Stark field elements in
+/// canonical form, returning the hash as a 32-byte big-endian word
+fn poseidon(calldata: &Bytes) -> Result<Bytes, VMError> {
+    if calldata.is_empty() || calldata.len() % WORD_SIZE != 0 {
+        return Err(PrecompileError::ParsingInputError.into());
+    }
+
+    let inputs = calldata
+        .chunks_exact(WORD_SIZE)
+        .map(|word| {
+            let element =
+                StarkFelt::from_bytes_be(word).map_err(|_| PrecompileError::ParsingInputError)?;
+            // Values not below the modulus would be silently reduced
+            if element.to_bytes_be().as_slice() != word {
+                return Err(PrecompileError::ParsingInputError);
+            }
+            Ok(element)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let hash = PoseidonCairoStark252::hash_many(&inputs);
+    Ok(Bytes::from(hash.to_bytes_be()))
+}
+
+/// Returns the value of the storage slot of `contract` given as calldata
+fn storage_oracle(
+    contract: Address,
+    calldata: &Bytes,
+    db: &mut GeneralizedDatabase,
+    storage_accesses: &mut Option<StorageAccesses>,
+) -> Result<Bytes, VMError> {
+    if calldata.len() != WORD_SIZE {
+        return Err(PrecompileError::ParsingInputError.into());
+    }
+    let key = H256::from_slice(calldata);
+    let value = db.get_storage_value(contract, key)?;
+    if let Some(accesses) = storage_accesses {
+        accesses.slots.insert((contract, key));
+    }
+    Ok(Bytes::copy_from_slice(&value.to_big_endian()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poseidon_rejects_malformed_input() {
+        // Not a multiple of the word size
+        assert!(poseidon(&Bytes::from(vec![1u8; 33])).is_err());
+        // Not a canonical field element
+        assert!(poseidon(&Bytes::from(vec![0xffu8; 32])).is_err());
+    }
+
+    #[test]
+    fn poseidon_hashes_words() {
+        let calldata = [[0u8; 31].as_slice(), &[1], &[0u8; 31], &[2]].concat();
+        let output = poseidon(&Bytes::from(calldata)).unwrap();
+
+        let expected =
+            PoseidonCairoStark252::hash_many(&[StarkFelt::from(1u64), StarkFelt::from(2u64)]);
+        assert_eq!(output.as_ref(), expected.to_bytes_be().as_slice());
+        assert_eq!(output.len(), WORD_SIZE);
+    }
+}
@@ -0,0 +1,278 @@
+use ethrex_common::types::block_access_list::{
+    BlockAccessIndex, BlockAccessList, BlockAccessListBuilder,
+};
+use ethrex_common::types::{AccountInfo, Code};
+use ethrex_common::{Address, H256, U256};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::gen_db::CacheDB;
+use crate::errors::InternalError;
+
+/// Records the accesses made through a `GeneralizedDatabase` to build the block access list
+/// (EIP-7928) of the block being executed.
+///
+/// Accesses are kept pending until the current block access index is committed, so the ones
+/// made by a transaction that ends up not being included can be discarded. Post-values are
+/// obtained at commit time by comparing the accounts mutably accessed since the last commit
+/// with the values they had then.
+#[derive(Debug, Clone, Default)]
+pub struct AccessRecorder {
+    index: BlockAccessIndex,
+    builder: BlockAccessListBuilder,
+    pending_accounts: FxHashSet<Address>,
+    pending_reads: FxHashSet<(Address, H256)>,
+    /// Accounts mutably accessed since the last commit
+    dirty: FxHashSet<Address>,
+    /// Account values as of the last commit, or as loaded from the database
+    committed_info: FxHashMap<Address, AccountInfo>,
+    /// Storage values as of the last commit, or as loaded from the database
+    committed_storage: FxHashMap<(Address, H256), U256>,
+}
+
+impl AccessRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn index(&self) -> BlockAccessIndex {
+        self.index
+    }
+
+    /// Records an access to the account. Every mutation is preceded by an access, so the first
+    /// one seen in the block gives the value the account had before it.
+    pub fn account_accessed(&mut self, address: Address, info: &AccountInfo) {
+        self.committed_info
+            .entry(address)
+            .or_insert_with(|| info.clone());
+        self.pending_accounts.insert(address);
+    }
+
+    pub fn account_mutated(&mut self, address: Address) {
+        self.dirty.insert(address);
+    }
+
+    /// Records a read of the storage slot, as with accounts writes are always preceded by a read
+    pub fn storage_read(&mut self, address: Address, key: H256, value: U256) {
+        self.committed_storage
+            .entry((address, key))
+            .or_insert(value);
+        self.pending_reads.insert((address, key));
+    }
+
+    /// Forgets the accesses made since the last commit, e.g. those of a transaction that was
+    /// undone. Changes don't need to be discarded, undone values match the committed ones.
+    pub fn discard_pending(&mut self) {
+        self.pending_accounts.clear();
+        self.pending_reads.clear();
+    }
+
+    /// Takes the accounts and storage slots accessed since the last commit, so they can be
+    /// recorded by another recorder, e.g. the one of the block for a transaction that was
+    /// executed over a separate database
+    pub fn take_pending(&mut self) -> (FxHashSet<Address>, FxHashSet<(Address, H256)>) {
+        (
+            std::mem::take(&mut self.pending_accounts),
+            std::mem::take(&mut self.pending_reads),
+        )
+    }
+
+    /// Forgets an access made since the last commit, used for accounts that are accessed by the
+    /// client itself rather than by the block, like the system address
+    pub fn discard_account(&mut self, address: Address) {
+        self.pending_accounts.remove(&address);
+    }
+
+    /// Records the accesses and changes made under the current block access index and moves on
+    /// to the next one
+    pub fn commit(
+        &mut self,
+        accounts: &CacheDB,
+        codes: &FxHashMap<H256, Code>,
+    ) -> Result<(), InternalError> {
+        for address in self.pending_accounts.drain() {
+            self.builder.touch_account(address);
+        }
+        for (address, key) in self.pending_reads.drain() {
+            self.builder.read_storage(address, key);
+        }
+        for address in self.dirty.drain() {
+            // Accounts dropped from the cache had their changes dropped too
+            let Some(account) = accounts.get(&address) else {
+                continue;
+            };
+            let committed = self
+                .committed_info
+                .get_mut(&address)
+                .ok_or(InternalError::AccountNotFound)?;
+            if account.info.balance != committed.balance {
+                self.builder
+                    .set_balance(address, self.index, account.info.balance);
+            }
+            if account.info.nonce != committed.nonce {
+                self.builder
+                    .set_nonce(address, self.index, account.info.nonce);
+            }
+            if account.info.code_hash != committed.code_hash {
+                let code = codes
+                    .get(&account.info.code_hash)
+                    .ok_or_else(|| InternalError::msg("Code of modified account not cached"))?;
+                self.builder
+                    .set_code(address, self.index, code.bytecode.clone());
+            }
+            *committed = account.info.clone();
+
+            for (key, value) in &account.storage {
+                let committed = self.committed_storage.entry((address, *key)).or_default();
+                if value != committed {
+                    self.builder
+                        .write_storage(address, *key, self.index, *value);
+                    *committed = *value;
+                }
+            }
+        }
+        self.index = self.index.checked_add(1).ok_or(InternalError::Overflow)?;
+        Ok(())
+    }
+
+    /// Commits the current block access index and builds the list
+    pub fn finish(
+        mut self,
+        accounts: &CacheDB,
+        codes: &FxHashMap<H256, Code>,
+    ) -> Result<BlockAccessList, InternalError> {
+        self.commit(accounts, codes)?;
+        Ok(self.builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::LevmAccount;
+    use ethrex_common::types::AccountState;
+    use ethrex_common::types::block_access_list::{AccountChanges, BalanceChange, StorageChange};
+
+    const SENDER: Address = Address::repeat_byte(1);
+    const CONTRACT: Address = Address::repeat_byte(2);
+
+    fn account(balance: u64) -> LevmAccount {
+        LevmAccount::from(AccountState {
+            balance: balance.into(),
+            ..Default::default()
+        })
+    }
+
+    fn changes_of(list: &BlockAccessList, address: Address) -> &AccountChanges {
+        list.0
+            .iter()
+            .find(|account| account.address == address)
+            .unwrap()
+    }
+
+    #[test]
+    fn records_changes_under_the_index_they_happen_at() {
+        let mut recorder = AccessRecorder::new();
+        let mut accounts = CacheDB::default();
+        let codes = FxHashMap::default();
+        accounts.insert(SENDER, account(100));
+        accounts.insert(CONTRACT, account(0));
+
+        // Index 0 only reads
+        recorder.account_accessed(SENDER, &accounts.get(&SENDER).unwrap().info);
+        recorder.commit(&accounts, &codes).unwrap();
+
+        // Index 1 moves balance and writes a slot
+        recorder.account_accessed(SENDER, &accounts.get(&SENDER).unwrap().info);
+        recorder.account_accessed(CONTRACT, &accounts.get(&CONTRACT).unwrap().info);
+        recorder.storage_read(CONTRACT, H256::zero(), U256::zero());
+        recorder.account_mutated(SENDER);
+        recorder.account_mutated(CONTRACT);
+        accounts.get_mut(&SENDER).unwrap().info.balance = 60.into();
+        let contract = accounts.get_mut(&CONTRACT).unwrap();
+        contract.info.balance = 40.into();
+        contract.storage.insert(H256::zero(), 7.into());
+        recorder.commit(&accounts, &codes).unwrap();
+        assert_eq!(recorder.index(), 2);
+
+        let list = recorder.finish(&accounts, &codes).unwrap();
+        assert_eq!(
+            changes_of(&list, SENDER).balance_changes,
+            vec![BalanceChange {
+                block_access_index: 1,
+                post_balance: 60.into(),
+            }]
+        );
+        let contract = changes_of(&list, CONTRACT);
+        assert_eq!(
+            contract.balance_changes,
+            vec![BalanceChange {
+                block_access_index: 1,
+                post_balance: 40.into(),
+            }]
+        );
+        assert_eq!(contract.storage_changes.len(), 1);
+        assert_eq!(
+            contract.storage_changes.first().unwrap().changes,
+            vec![StorageChange {
+                block_access_index: 1,
+                post_value: 7.into(),
+            }]
+        );
+        assert!(contract.storage_reads.is_empty());
+    }
+
+    #[test]
+    fn values_restored_within_an_index_are_not_changes() {
+        let mut recorder = AccessRecorder::new();
+        let mut accounts = CacheDB::default();
+        let codes = FxHashMap::default();
+        let mut contract = account(10);
+        contract.storage.insert(H256::zero(), 5.into());
+        accounts.insert(CONTRACT, contract);
+
+        recorder.account_accessed(CONTRACT, &accounts.get(&CONTRACT).unwrap().info);
+        recorder.storage_read(CONTRACT, H256::zero(), 5.into());
+        // Written and then restored by the same transaction
+        recorder.account_mutated(CONTRACT);
+
+        let list = recorder.finish(&accounts, &codes).unwrap();
+        let contract = changes_of(&list, CONTRACT);
+        assert!(contract.balance_changes.is_empty());
+        assert!(contract.storage_changes.is_empty());
+        assert_eq!(contract.storage_reads, vec![U256::zero()]);
+    }
+
+    #[test]
+    fn discarded_accesses_are_not_recorded() {
+        let mut recorder = AccessRecorder::new();
+        let accounts = CacheDB::default();
+        let codes = FxHashMap::default();
+
+        recorder.account_accessed(SENDER, &AccountInfo::default());
+        recorder.commit(&accounts, &codes).unwrap();
+        // E.g. a transaction that turned out to be invalid
+        recorder.account_accessed(CONTRACT, &AccountInfo::default());
+        recorder.storage_read(CONTRACT, H256::zero(), U256::zero());
+        recorder.discard_pending();
+
+        let list = recorder.finish(&accounts, &codes).unwrap();
+        assert_eq!(list.accessed_accounts().collect::<Vec<_>>(), vec![SENDER]);
+        assert_eq!(list.accessed_slots().count(), 0);
+    }
+
+    #[test]
+    fn pending_accesses_can_be_taken() {
+        let mut recorder = AccessRecorder::new();
+        recorder.account_accessed(CONTRACT, &AccountInfo::default());
+        recorder.storage_read(CONTRACT, H256::zero(), U256::zero());
+
+        let (accounts, slots) = recorder.take_pending();
+        assert!(accounts.contains(&CONTRACT));
+        assert!(slots.contains(&(CONTRACT, H256::zero())));
+
+        let list = recorder
+            .finish(&CacheDB::default(), &FxHashMap::default())
+            .unwrap();
+        assert_eq!(list.accessed_accounts().count(), 0);
+    }
+}
@@ -5,12 +5,17 @@ use ethrex_common::H256;
 use ethrex_common::U256;
 use ethrex_common::types::Account;
 use ethrex_common::types::Code;
+use ethrex_common::types::block_access_list::BlockAccessList;
+use ethrex_common::types::ubt_leaf::UbtLeaf;
 use ethrex_common::utils::ZERO_U256;
 
 use super::Database;
+use super::access_recorder::AccessRecorder;
+use crate::access_events::AccessEvents;
 use crate::account::AccountStatus;
 use crate::account::LevmAccount;
 use crate::call_frame::CallFrameBackup;
+use crate::code_analysis::CodeAnalysis;
 use crate::errors::InternalError;
 use crate::errors::VMError;
 use crate::utils::account_to_levm_account;
@@ -28,7 +33,13 @@ pub struct GeneralizedDatabase {
     pub current_accounts_state: CacheDB,
     pub initial_accounts_state: CacheDB,
     pub codes: FxHashMap<H256, Code>,
+    /// Basic blocks of the codes executed, by code hash
+    pub code_analyses: FxHashMap<H256, Arc<CodeAnalysis>>,
     pub tx_backup: Option<CallFrameBackup>,
+    /// Set while building the block access list of the block being executed
+    pub access_recorder: Option<AccessRecorder>,
+    /// Set while gathering the EIP-4762 access events of the block being executed
+    pub access_events: Option<AccessEvents>,
 }
 
 impl GeneralizedDatabase {
@@ -39,6 +50,9 @@ impl GeneralizedDatabase {
             initial_accounts_state: Default::default(),
             tx_backup: None,
             codes: Default::default(),
+            code_analyses: Default::default(),
+            access_recorder: None,
+            access_events: None,
         }
     }
 
@@ -62,6 +76,9 @@ impl GeneralizedDatabase {
             initial_accounts_state: levm_accounts,
             tx_backup: None,
             codes,
+            code_analyses: Default::default(),
+            access_recorder: None,
+            access_events: None,
         }
     }
 
@@ -70,10 +87,19 @@ impl GeneralizedDatabase {
     /// If it's the first time it's loaded store it in `initial_accounts_state` and also cache it in `current_accounts_state` for making changes to it
     fn load_account(&mut self, address: Address) -> Result<&mut LevmAccount, InternalError> {
         match self.current_accounts_state.entry(address) {
-            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Occupied(entry) => {
+                let account = entry.into_mut();
+                if let Some(recorder) = &mut self.access_recorder {
+                    recorder.account_accessed(address, &account.info);
+                }
+                Ok(account)
+            }
             Entry::Vacant(entry) => {
                 let state = self.store.get_account_state(address)?;
                 let account = LevmAccount::from(state);
+                if let Some(recorder) = &mut self.access_recorder {
+                    recorder.account_accessed(address, &account.info);
+                }
                 self.initial_accounts_state.insert(address, account.clone());
                 Ok(entry.insert(account))
             }
@@ -88,6 +114,9 @@ impl GeneralizedDatabase {
     /// Gets mutable reference of an account
     /// Warning: Use directly only if outside of the EVM, otherwise use `vm.get_account_mut` because it contemplates call frame backups.
     pub fn get_account_mut(&mut self, address: Address) -> Result<&mut LevmAccount, InternalError> {
+        if let Some(recorder) = &mut self.access_recorder {
+            recorder.account_mutated(address);
+        }
         let acc = self.load_account(address)?;
         acc.mark_modified();
         Ok(acc)
@@ -106,6 +135,18 @@ impl GeneralizedDatabase {
         }
     }
 
+    /// Gets the basic blocks of a code, analyzing it the first time its hash is seen.
+    /// Initcodes don't have a hash, so they are analyzed every time they run.
+    pub fn get_code_analysis(&mut self, code: &Code) -> Arc<CodeAnalysis> {
+        if code.hash.is_zero() {
+            return Arc::new(CodeAnalysis::new(&code.bytecode));
+        }
+        self.code_analyses
+            .entry(code.hash)
+            .or_insert_with(|| Arc::new(CodeAnalysis::new(&code.bytecode)))
+            .clone()
+    }
+
     /// Shortcut for getting the code when we only have the address of an account and we don't need anything else.
     pub fn get_account_code(&mut self, address: Address) -> Result<&Code, InternalError> {
         let code_hash = self.get_account(address)?.info.code_hash;
@@ -134,6 +175,40 @@ impl GeneralizedDatabase {
         Ok(value)
     }
 
+    /// Gets the current value of a storage slot from outside of the VM, caching it if not already cached.
+    /// Same semantics as `VM::get_storage_value` but without recording call frame backups.
+    pub fn get_storage_value(
+        &mut self,
+        address: Address,
+        key: H256,
+    ) -> Result<U256, InternalError> {
+        let account = self.load_account(address)?;
+        if let Some(value) = account.storage.get(&key).copied() {
+            self.record_storage_read(address, key, value);
+            return Ok(value);
+        }
+        // If the account was destroyed and then created then we cannot rely on the DB to obtain storage values
+        if account.status == AccountStatus::DestroyedModified {
+            self.record_storage_read(address, key, U256::zero());
+            return Ok(U256::zero());
+        }
+
+        let value = self.get_value_from_database(address, key)?;
+        self.record_storage_read(address, key, value);
+        self.current_accounts_state
+            .get_mut(&address)
+            .ok_or(InternalError::AccountNotFound)?
+            .storage
+            .insert(key, value);
+        Ok(value)
+    }
+
+    fn record_storage_read(&mut self, address: Address, key: H256, value: U256) {
+        if let Some(recorder) = &mut self.access_recorder {
+            recorder.storage_read(address, key, value);
+        }
+    }
+
     /// Gets the transaction backup, if it exists.
     /// It only works if the `BackupHook` was enabled during the transaction execution.
     pub fn get_tx_backup(&self) -> Result<CallFrameBackup, InternalError> {
@@ -148,9 +223,66 @@ impl GeneralizedDatabase {
     pub fn undo_last_transaction(&mut self) -> Result<(), VMError> {
         let tx_backup = self.get_tx_backup()?;
         restore_cache_state(self, tx_backup)?;
+        if let Some(recorder) = &mut self.access_recorder {
+            recorder.discard_pending();
+        }
         Ok(())
     }
 
+    // ================== Block access list functions =====================
+
+    /// Starts recording the block access list (EIP-7928) of the block about to be executed
+    pub fn enable_access_recording(&mut self) {
+        self.access_recorder = Some(AccessRecorder::new());
+    }
+
+    /// Records the accesses made under the current block access index and moves on to the next
+    /// one, i.e. the next transaction or the post-execution operations. Does nothing if
+    /// recording is disabled.
+    pub fn next_block_access_index(&mut self) -> Result<(), InternalError> {
+        if let Some(recorder) = &mut self.access_recorder {
+            recorder.commit(&self.current_accounts_state, &self.codes)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets the accesses made since the last block access index, e.g. by a transaction that
+    /// failed to execute and won't be included in the block
+    pub fn discard_block_access_index(&mut self) {
+        if let Some(recorder) = &mut self.access_recorder {
+            recorder.discard_pending();
+        }
+    }
+
+    /// Stops recording and returns the block access list, if it was being recorded
+    pub fn take_block_access_list(&mut self) -> Result<Option<BlockAccessList>, InternalError> {
+        self.access_recorder
+            .take()
+            .map(|recorder| recorder.finish(&self.current_accounts_state, &self.codes))
+            .transpose()
+    }
+
+    // ================== Stateless access events functions =====================
+
+    /// Starts gathering the EIP-4762 access events of the transactions about to be executed
+    pub fn enable_access_events_recording(&mut self) {
+        self.access_events = Some(AccessEvents::new());
+    }
+
+    /// Adds the access events of a finished transaction. Does nothing if recording is disabled.
+    pub fn record_access_events(&mut self, events: &AccessEvents) {
+        if let Some(block_events) = &mut self.access_events {
+            block_events.merge(events);
+        }
+    }
+
+    /// Stops recording and returns the tree leaves accessed, if they were being recorded
+    pub fn take_accessed_leaves(&mut self) -> Option<Vec<UbtLeaf>> {
+        self.access_events
+            .take()
+            .map(|events| events.accessed_leaves())
+    }
+
     pub fn get_state_transitions(&mut self) -> Result<Vec<AccountUpdate>, VMError> {
         let mut account_updates: Vec<AccountUpdate> = vec![];
         for (address, new_state_account) in self.current_accounts_state.iter() {
@@ -247,6 +379,7 @@ impl GeneralizedDatabase {
         self.initial_accounts_state.clear();
         self.current_accounts_state.clear();
         self.codes.clear();
+        self.code_analyses.clear();
         Ok(account_updates)
     }
 
@@ -467,13 +600,18 @@ impl<'a> VM<'a> {
     ///
     /// Accessed storage slots are stored in the `accessed_storage_slots` set.
     /// Accessed storage slots take place in some gas cost computation.
+    /// Under EIP-4762 slots are never reported as cold, access events are charged instead.
     pub fn access_storage_slot(
         &mut self,
         address: Address,
         key: H256,
     ) -> Result<(U256, bool), InternalError> {
         // [EIP-2929] - Introduced conditional tracking of accessed storage slots for Berlin and later specs.
-        let storage_slot_was_cold = !self.substate.add_accessed_slot(address, key);
+        let storage_slot_was_cold =
+            !self.substate.add_accessed_slot(address, key) && !self.env.config.stateless_gas;
+        if let Some(accesses) = &mut self.storage_accesses {
+            accesses.slots.insert((address, key));
+        }
 
         let storage_slot = self.get_storage_value(address, key)?;
 
@@ -488,11 +626,13 @@ impl<'a> VM<'a> {
         key: H256,
     ) -> Result<U256, InternalError> {
         if let Some(account) = self.db.current_accounts_state.get(&address) {
-            if let Some(value) = account.storage.get(&key) {
-                return Ok(*value);
+            if let Some(value) = account.storage.get(&key).copied() {
+                self.db.record_storage_read(address, key, value);
+                return Ok(value);
             }
             // If the account was destroyed and then created then we cannot rely on the DB to obtain storage values
             if account.status == AccountStatus::DestroyedModified {
+                self.db.record_storage_read(address, key, U256::zero());
                 return Ok(U256::zero());
             }
         } else {
@@ -501,6 +641,7 @@ impl<'a> VM<'a> {
         }
 
         let value = self.db.get_value_from_database(address, key)?;
+        self.db.record_storage_read(address, key, value);
 
         // Update the account with the fetched value
         let account = self.get_account_mut(address)?;
@@ -4,6 +4,7 @@ use ethrex_common::{
     types::{AccountState, ChainConfig, Code},
 };
 
+pub mod access_recorder;
 pub mod gen_db;
 
 pub trait Database: Send + Sync {
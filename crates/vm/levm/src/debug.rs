@@ -1,10 +1,26 @@
-use crate::errors::InternalError;
+use crate::{errors::InternalError, vm::VM};
 use ethrex_common::U256;
 
 /// Special constant for debugging. 0xFEDEBEBECAFEDECEBADA
 /// It has to match with the constant set in the Solidity contract for this purpose.
 pub const MAGIC_PRINT_OFFSET: U256 = U256([0xBEBECAFEDECEBADA, 0xFEDE, 0, 0]);
 
+/// Hook called before executing each opcode, used by step debuggers.
+///
+/// It's only called when LEVM is built with the `debug` feature.
+pub trait StepHook {
+    /// Called with the VM about to execute the opcode at the pc of its current call frame.
+    fn on_step(&mut self, vm: &mut VM<'_>) -> StepAction;
+}
+
+/// What the VM does after calling a [StepHook]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepAction {
+    Continue,
+    /// Stops the execution, which fails with [InternalError::ExecutionAborted]
+    Abort,
+}
+
 #[derive(Default)]
 pub struct DebugMode {
     pub enabled: bool,
@@ -12,6 +28,8 @@ pub struct DebugMode {
     pub print_buffer: Vec<u8>,
     /// When enabled, store what's read into the buffer. Print the whole buffer when disabling this.
     pub print_mode: bool,
+    /// Hook called before executing each opcode.
+    pub step_hook: Option<Box<dyn StepHook>>,
 }
 
 impl DebugMode {
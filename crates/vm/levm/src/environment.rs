@@ -1,6 +1,8 @@
 use ethrex_common::{
     Address, H256, U256,
-    types::{BlockHeader, ChainConfig, Fork, ForkBlobSchedule},
+    types::{
+        BlockHeader, ChainConfig, Fork, ForkBlobSchedule, custom_precompiles::CustomPrecompiles,
+    },
 };
 
 use crate::constants::{
@@ -50,10 +52,15 @@ pub struct Environment {
 /// However, that function should NOT be used IF you want to use a
 /// custom `ForkBlobSchedule`, like it's described in [EIP-7840](https://eips.ethereum.org/EIPS/eip-7840)
 /// Values are determined by [EIP-7691](https://eips.ethereum.org/EIPS/eip-7691#specification)
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct EVMConfig {
     pub fork: Fork,
     pub blob_schedule: ForkBlobSchedule,
+    /// Custom precompiles of the chain that are active in the block, only used by L2s
+    pub custom_precompiles: CustomPrecompiles,
+    /// Whether state accesses are charged as defined by EIP-4762 instead of EIP-2929, only used
+    /// for experimenting with stateless execution over the unified binary tree
+    pub stateless_gas: bool,
 }
 
 impl EVMConfig {
@@ -61,6 +68,8 @@ impl EVMConfig {
         EVMConfig {
             fork,
             blob_schedule,
+            custom_precompiles: CustomPrecompiles::default(),
+            stateless_gas: false,
         }
     }
 
@@ -71,7 +80,13 @@ impl EVMConfig {
             .get_fork_blob_schedule(block_header.timestamp)
             .unwrap_or_else(|| EVMConfig::canonical_values(fork));
 
-        EVMConfig::new(fork, blob_schedule)
+        EVMConfig {
+            custom_precompiles: chain_config
+                .custom_precompiles
+                .active_at(block_header.timestamp),
+            stateless_gas: chain_config.is_eip4762_activated(block_header.timestamp),
+            ..EVMConfig::new(fork, blob_schedule)
+        }
     }
 
     /// This function is used for running the EF tests. If you don't
@@ -121,9 +136,6 @@ impl Default for EVMConfig {
     /// The default EVMConfig depends on the default Fork.
     fn default() -> Self {
         let fork = core::default::Default::default();
-        EVMConfig {
-            fork,
-            blob_schedule: Self::canonical_values(fork),
-        }
+        EVMConfig::new(fork, Self::canonical_values(fork))
     }
 }
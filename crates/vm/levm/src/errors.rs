@@ -146,6 +146,16 @@ pub enum TxValidationError {
         "Transaction gas limit exceeds maximum. Transaction hash: {tx_hash}, transaction gas limit: {tx_gas_limit}"
     )]
     TxMaxGasLimitExceeded { tx_hash: H256, tx_gas_limit: u64 },
+    #[error("RIP-7560 transactions are only supported on L2")]
+    Rip7560NotSupported,
+    #[error("RIP-7560 validation failed: {0}")]
+    Rip7560ValidationFailed(String),
+    #[error("RIP-7560 validation used restricted opcode 0x{0:02x}")]
+    Rip7560RestrictedOpcode(u8),
+    #[error(
+        "RIP-7560 validation accessed slot {slot:#x} of {address:#x}, not associated with the sender"
+    )]
+    Rip7560UnassociatedStorage { address: Address, slot: H256 },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
@@ -174,6 +184,8 @@ pub enum InternalError {
     RecipientNotFoundForPrivilegedTransaction,
     #[error("Memory Size Sverflow")]
     MemorySizeOverflow,
+    #[error("Execution aborted by the step hook")]
+    ExecutionAborted,
     #[error("Custom error: {0}")]
     Custom(String),
     /// Unexpected error when accessing the database, used in trait `Database`.
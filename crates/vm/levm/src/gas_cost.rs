@@ -1,8 +1,10 @@
 use crate::{
+    access_events::AccessCharges,
     call_frame::CallFrame,
     constants::{WORD_SIZE, WORD_SIZE_IN_BYTES_U64},
     errors::{ExceptionalHalt, InternalError, PrecompileError, VMError},
     memory,
+    opcodes::Opcode,
 };
 use ExceptionalHalt::OutOfGas;
 use bytes::Bytes;
@@ -90,6 +92,82 @@ pub const CODECOPY_DYNAMIC_BASE: u64 = 3;
 pub const GASPRICE: u64 = 2;
 pub const CLZ: u64 = 5;
 
+/// Gas charged by the interpreter before running each opcode, for the opcodes whose whole cost is
+/// known from the opcode alone. The rest charge their gas themselves, so their entry is zero.
+#[allow(
+    clippy::as_conversions,
+    clippy::indexing_slicing,
+    clippy::arithmetic_side_effects
+)]
+pub const STATIC_GAS: [u64; 256] = {
+    let mut table = [0; 256];
+    table[Opcode::ADD as usize] = ADD;
+    table[Opcode::MUL as usize] = MUL;
+    table[Opcode::SUB as usize] = SUB;
+    table[Opcode::DIV as usize] = DIV;
+    table[Opcode::SDIV as usize] = SDIV;
+    table[Opcode::MOD as usize] = MOD;
+    table[Opcode::SMOD as usize] = SMOD;
+    table[Opcode::ADDMOD as usize] = ADDMOD;
+    table[Opcode::MULMOD as usize] = MULMOD;
+    table[Opcode::SIGNEXTEND as usize] = SIGNEXTEND;
+    table[Opcode::LT as usize] = LT;
+    table[Opcode::GT as usize] = GT;
+    table[Opcode::SLT as usize] = SLT;
+    table[Opcode::SGT as usize] = SGT;
+    table[Opcode::EQ as usize] = EQ;
+    table[Opcode::ISZERO as usize] = ISZERO;
+    table[Opcode::AND as usize] = AND;
+    table[Opcode::OR as usize] = OR;
+    table[Opcode::XOR as usize] = XOR;
+    table[Opcode::NOT as usize] = NOT;
+    table[Opcode::BYTE as usize] = BYTE;
+    table[Opcode::SHL as usize] = SHL;
+    table[Opcode::SHR as usize] = SHR;
+    table[Opcode::SAR as usize] = SAR;
+    table[Opcode::CLZ as usize] = CLZ;
+    table[Opcode::ADDRESS as usize] = ADDRESS;
+    table[Opcode::ORIGIN as usize] = ORIGIN;
+    table[Opcode::CALLER as usize] = CALLER;
+    table[Opcode::CALLVALUE as usize] = CALLVALUE;
+    table[Opcode::CALLDATALOAD as usize] = CALLDATALOAD;
+    table[Opcode::CALLDATASIZE as usize] = CALLDATASIZE;
+    table[Opcode::CODESIZE as usize] = CODESIZE;
+    table[Opcode::GASPRICE as usize] = GASPRICE;
+    table[Opcode::RETURNDATASIZE as usize] = RETURNDATASIZE;
+    table[Opcode::BLOCKHASH as usize] = BLOCKHASH;
+    table[Opcode::COINBASE as usize] = COINBASE;
+    table[Opcode::TIMESTAMP as usize] = TIMESTAMP;
+    table[Opcode::NUMBER as usize] = NUMBER;
+    table[Opcode::PREVRANDAO as usize] = PREVRANDAO;
+    table[Opcode::GASLIMIT as usize] = GASLIMIT;
+    table[Opcode::CHAINID as usize] = CHAINID;
+    table[Opcode::SELFBALANCE as usize] = SELFBALANCE;
+    table[Opcode::BASEFEE as usize] = BASEFEE;
+    table[Opcode::BLOBHASH as usize] = BLOBHASH;
+    table[Opcode::BLOBBASEFEE as usize] = BLOBBASEFEE;
+    table[Opcode::POP as usize] = POP;
+    table[Opcode::JUMP as usize] = JUMP;
+    table[Opcode::JUMPI as usize] = JUMPI;
+    table[Opcode::PC as usize] = PC;
+    table[Opcode::MSIZE as usize] = MSIZE;
+    table[Opcode::GAS as usize] = GAS;
+    table[Opcode::JUMPDEST as usize] = JUMPDEST;
+    table[Opcode::PUSH0 as usize] = PUSH0;
+    let mut opcode = Opcode::PUSH1 as usize;
+    while opcode <= Opcode::SWAP16 as usize {
+        table[opcode] = if opcode <= Opcode::PUSH32 as usize {
+            PUSHN
+        } else if opcode <= Opcode::DUP16 as usize {
+            DUPN
+        } else {
+            SWAPN
+        };
+        opcode += 1;
+    }
+    table
+};
+
 pub const SELFDESTRUCT_STATIC: u64 = 5000;
 pub const SELFDESTRUCT_DYNAMIC: u64 = 25000;
 pub const SELFDESTRUCT_REFUND: u64 = 24000;
@@ -171,6 +249,13 @@ pub const BLOB_GAS_PER_BLOB: u64 = 131072;
 pub const ACCESS_LIST_STORAGE_KEY_COST: u64 = 1900;
 pub const ACCESS_LIST_ADDRESS_COST: u64 = 2400;
 
+// [EIP-4762] - Stateless access events costs
+pub const WITNESS_BRANCH_COST: u64 = 1900;
+pub const WITNESS_CHUNK_COST: u64 = 200;
+pub const SUBTREE_EDIT_COST: u64 = 3000;
+pub const CHUNK_EDIT_COST: u64 = 500;
+pub const CHUNK_FILL_COST: u64 = 6200;
+
 // Precompile costs
 pub const ECRECOVER_COST: u64 = 3000;
 pub const BLS12_381_G1ADD_COST: u64 = 375;
@@ -556,6 +641,24 @@ pub fn selfdestruct(
         .ok_or(OutOfGas.into())
 }
 
+/// Gas charged under EIP-4762 for the access events that are new to the transaction
+pub fn witness_access(charges: &AccessCharges) -> Result<u64, VMError> {
+    [
+        (charges.branch_reads, WITNESS_BRANCH_COST),
+        (charges.chunk_reads, WITNESS_CHUNK_COST),
+        (charges.branch_writes, SUBTREE_EDIT_COST),
+        (charges.chunk_writes, CHUNK_EDIT_COST),
+        (charges.chunk_fills, CHUNK_FILL_COST),
+    ]
+    .into_iter()
+    .try_fold(0u64, |total, (count, cost)| {
+        count
+            .checked_mul(cost)
+            .and_then(|cost| total.checked_add(cost))
+    })
+    .ok_or(OutOfGas.into())
+}
+
 pub fn tx_calldata(calldata: &Bytes) -> Result<u64, VMError> {
     // This cost applies both for call and create
     // 4 gas for each zero byte in the transaction data 16 gas for each non-zero byte in the transaction.
@@ -782,6 +885,10 @@ pub fn identity(data_size: usize) -> Result<u64, VMError> {
     precompile(data_size, IDENTITY_STATIC_COST, IDENTITY_DYNAMIC_BASE)
 }
 
+pub fn custom_precompile(data_size: usize, base_gas: u64, word_gas: u64) -> Result<u64, VMError> {
+    precompile(data_size, base_gas, word_gas)
+}
+
 pub fn modexp(
     exponent_first_32_bytes: &Natural,
     base_size: usize,
@@ -11,7 +11,7 @@ use crate::{
 use bytes::Bytes;
 use ethrex_common::{
     Address, H256, U256,
-    types::{Code, Fork},
+    types::{Code, Fork, Transaction},
 };
 
 pub const MAX_REFUND_QUOTIENT: u64 = 5;
@@ -28,6 +28,11 @@ impl Hook for DefaultHook {
     /// - It calculates and adds intrinsic gas to the 'gas used' of callframe and environment.
     ///   See 'docs' for more information about validations.
     fn prepare_execution(&mut self, vm: &mut VM<'_>) -> Result<(), VMError> {
+        // RIP-7560 transactions are only executed by the L2 hooks
+        if matches!(vm.tx, Transaction::RIP7560Transaction(_)) {
+            return Err(TxValidationError::Rip7560NotSupported.into());
+        }
+
         let sender_address = vm.env.origin;
         let sender_info = vm.db.get_account(sender_address)?.info.clone();
 
@@ -1,9 +1,9 @@
 use crate::{
     errors::{ContextResult, VMError},
-    hooks::{L2Hook, backup_hook::BackupHook, default_hook::DefaultHook},
+    hooks::{L2Hook, Rip7560Hook, backup_hook::BackupHook, default_hook::DefaultHook},
     vm::{VM, VMType},
 };
-use ethrex_common::types::fee_config::FeeConfig;
+use ethrex_common::types::{Transaction, fee_config::FeeConfig};
 use std::{cell::RefCell, rc::Rc};
 
 pub trait Hook {
@@ -16,9 +16,12 @@ pub trait Hook {
     ) -> Result<(), VMError>;
 }
 
-pub fn get_hooks(vm_type: &VMType) -> Vec<Rc<RefCell<dyn Hook + 'static>>> {
+pub fn get_hooks(vm_type: &VMType, tx: &Transaction) -> Vec<Rc<RefCell<dyn Hook + 'static>>> {
     match vm_type {
         VMType::L1 => l1_hooks(),
+        VMType::L2(fee_config) if matches!(tx, Transaction::RIP7560Transaction(_)) => {
+            rip7560_hooks(*fee_config)
+        }
         VMType::L2(fee_config) => l2_hooks(*fee_config),
     }
 }
@@ -33,3 +36,10 @@ pub fn l2_hooks(fee_config: FeeConfig) -> Vec<Rc<RefCell<dyn Hook + 'static>>> {
         Rc::new(RefCell::new(BackupHook::default())),
     ]
 }
+
+pub fn rip7560_hooks(fee_config: FeeConfig) -> Vec<Rc<RefCell<dyn Hook + 'static>>> {
+    vec![
+        Rc::new(RefCell::new(Rip7560Hook::new(fee_config))),
+        Rc::new(RefCell::new(BackupHook::default())),
+    ]
+}
@@ -201,7 +201,7 @@ fn finalize_non_privileged_execution(
     Ok(())
 }
 
-fn validate_sufficient_max_fee_per_gas_l2(
+pub(crate) fn validate_sufficient_max_fee_per_gas_l2(
     vm: &VM<'_>,
     operator_fee_config: &Option<OperatorFeeConfig>,
 ) -> Result<(), TxValidationError> {
@@ -225,7 +225,7 @@ fn validate_sufficient_max_fee_per_gas_l2(
 /// Pays the coinbase the priority fee per gas for the gas used.
 /// If an operator fee config is provided, the priority fee is reduced by the operator fee per gas.
 /// If use_fee_token is true, the fee is paid using the fee token contract.
-fn pay_coinbase_l2(
+pub(crate) fn pay_coinbase_l2(
     vm: &mut VM<'_>,
     gas_to_pay: u64,
     operator_fee_config: &Option<OperatorFeeConfig>,
@@ -275,7 +275,7 @@ fn compute_priority_fee_per_gas(
 /// Pays the base fee to the base fee vault for the gas used.
 /// This is calculated as gas_used * base_fee_per_gas.
 /// If use_fee_token is true, the fee is paid using the fee token contract.
-fn pay_base_fee_vault(
+pub(crate) fn pay_base_fee_vault(
     vm: &mut VM<'_>,
     gas_to_pay: u64,
     base_fee_vault: Address,
@@ -296,7 +296,7 @@ fn pay_base_fee_vault(
 /// Pays the operator fee to the operator fee vault for the gas used.
 /// This is calculated as gas_used * operator_fee_per_gas.
 /// If use_fee_token is true, the fee is paid using the fee token contract.
-fn pay_operator_fee(
+pub(crate) fn pay_operator_fee(
     vm: &mut VM<'_>,
     gas_to_pay: u64,
     operator_fee_config: OperatorFeeConfig,
@@ -735,7 +735,7 @@ fn calculate_l1_fee(
 
 /// Calculates the L1 fee gas based on the account diffs size and the L1 fee config.
 /// Returns 0 if no L1 fee config is provided.
-fn calculate_l1_fee_gas(
+pub(crate) fn calculate_l1_fee_gas(
     vm: &VM<'_>,
     l1_fee_config: &Option<L1FeeConfig>,
 ) -> Result<u64, crate::errors::VMError> {
@@ -761,7 +761,7 @@ fn calculate_l1_fee_gas(
 
 /// Pays the L1 fee to the L1 fee vault for the gas used.
 /// This is calculated as gas_to_pay * gas_price.
-fn pay_to_l1_fee_vault(
+pub(crate) fn pay_to_l1_fee_vault(
     vm: &mut VM<'_>,
     gas_to_pay: u64,
     l1_fee_config: L1FeeConfig,
@@ -2,6 +2,8 @@ pub mod backup_hook;
 pub mod default_hook;
 pub mod hook;
 pub mod l2_hook;
+pub mod rip7560_hook;
 
 pub use default_hook::DefaultHook;
 pub use l2_hook::L2Hook;
+pub use rip7560_hook::Rip7560Hook;
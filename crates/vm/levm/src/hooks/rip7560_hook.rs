@@ -0,0 +1,560 @@
+use crate::{
+    errors::{ContextResult, ExecutionReport, InternalError, TxResult, TxValidationError, VMError},
+    gas_cost::{self, ACCESS_LIST_ADDRESS_COST, ACCESS_LIST_STORAGE_KEY_COST},
+    hooks::{default_hook, hook::Hook, l2_hook},
+    opcodes::Opcode,
+    tracing::LevmCallTracer,
+    utils::{address_to_word, u256_to_usize},
+    vm::{StorageAccesses, VM},
+};
+
+use bytes::Bytes;
+use ethrex_common::{
+    Address, H160, H256, U256,
+    types::{EIP1559Transaction, RIP7560Transaction, Transaction, TxKind, fee_config::FeeConfig},
+    utils::u256_from_big_endian,
+};
+use ethrex_rlp::encode::RLPEncode;
+
+/// Caller of the validation, execution and post transaction frames
+pub const AA_ENTRY_POINT: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x75, 0x60,
+]);
+/// Caller of the deployer frame
+pub const AA_SENDER_CREATOR: Address = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0x75, 0x60,
+]);
+/// Base intrinsic gas of a RIP-7560 transaction, in place of the 21000 of other transactions
+pub const AA_BASE_GAS: u64 = 15000;
+/// Version passed to the validation functions
+pub const RIP7560_VERSION: u64 = 0;
+/// How far after the hash of a value keyed by the sender its associated slots go, in ERC-7562
+const MAX_ASSOCIATED_SLOT_OFFSET: u64 = 128;
+
+// validateTransaction(uint256 version, bytes32 txHash, bytes transaction)
+const VALIDATE_TRANSACTION_SELECTOR: [u8; 4] = [0xbf, 0x45, 0xc1, 0x66];
+// validatePaymasterTransaction(uint256 version, bytes32 txHash, bytes transaction)
+const VALIDATE_PAYMASTER_TRANSACTION_SELECTOR: [u8; 4] = [0xe0, 0xe6, 0x18, 0x3a];
+// postPaymasterTransaction(bool success, uint256 actualGasCost, bytes context)
+const POST_PAYMASTER_TRANSACTION_SELECTOR: [u8; 4] = [0x34, 0xa4, 0xa7, 0x7c];
+
+/// Opcodes depending on the block being built, which would let a transaction validate in the
+/// mempool and fail once included. They are forbidden in the validation frames when simulating
+/// them for the mempool, following ERC-7562.
+const RESTRICTED_VALIDATION_OPCODES: [Opcode; 15] = [
+    Opcode::ORIGIN,
+    Opcode::GASPRICE,
+    Opcode::BLOCKHASH,
+    Opcode::COINBASE,
+    Opcode::TIMESTAMP,
+    Opcode::NUMBER,
+    Opcode::PREVRANDAO,
+    Opcode::GASLIMIT,
+    Opcode::BALANCE,
+    Opcode::SELFBALANCE,
+    Opcode::BASEFEE,
+    Opcode::BLOBHASH,
+    Opcode::BLOBBASEFEE,
+    Opcode::CREATE,
+    Opcode::SELFDESTRUCT,
+];
+
+/// Executes [RIP-7560](https://github.com/ethereum/RIPs/blob/master/RIPS/rip-7560.md) native
+/// account abstraction transactions on L2.
+///
+/// Before the execution frame, the sender is deployed if a deployer is set, and then asked to
+/// validate the transaction, followed by the paymaster if there is one. These frames run as
+/// separate executions whose changes persist even if the execution frame reverts, and the
+/// transaction is invalid if any of them fails. After the execution the paymaster is told its
+/// outcome and cost. Fees are paid by the paymaster, or the sender if there is none, and
+/// distributed as for the rest of L2 transactions.
+pub struct Rip7560Hook {
+    pub fee_config: FeeConfig,
+    /// Holds the validation frames to the ERC-7562 rules, see [`simulate_rip7560_validation`]
+    pub enforce_validation_rules: bool,
+    /// Intrinsic gas plus the gas used by the validation frames
+    validation_gas_used: u64,
+    /// Returned by the paymaster validation, passed back to it after the execution
+    paymaster_context: Option<Bytes>,
+}
+
+impl Rip7560Hook {
+    pub fn new(fee_config: FeeConfig) -> Self {
+        Self {
+            fee_config,
+            enforce_validation_rules: false,
+            validation_gas_used: 0,
+            paymaster_context: None,
+        }
+    }
+
+    /// Runs a validation frame of the transaction of `sender`, charging its gas to the
+    /// validation gas
+    fn run_validation_frame(
+        &mut self,
+        vm: &mut VM<'_>,
+        sender: Address,
+        caller: Address,
+        to: Address,
+        data: Bytes,
+        gas_limit: u64,
+    ) -> Result<ExecutionReport, VMError> {
+        let validated_sender = self.enforce_validation_rules.then_some(sender);
+        let report = run_frame(vm, caller, to, data, gas_limit, validated_sender)?;
+        self.validation_gas_used = self
+            .validation_gas_used
+            .checked_add(report.gas_used)
+            .ok_or(InternalError::Overflow)?;
+        Ok(report)
+    }
+}
+
+impl Hook for Rip7560Hook {
+    fn prepare_execution(&mut self, vm: &mut VM<'_>) -> Result<(), VMError> {
+        let tx = rip7560_tx(vm)?;
+        let sender = tx.sender;
+
+        default_hook::validate_gas_allowance(vm)?;
+
+        if tx.max_priority_fee_per_gas > tx.max_fee_per_gas {
+            return Err(TxValidationError::PriorityGreaterThanMaxFeePerGas {
+                priority_fee: tx.max_priority_fee_per_gas.into(),
+                max_fee_per_gas: tx.max_fee_per_gas.into(),
+            }
+            .into());
+        }
+        default_hook::validate_sufficient_max_fee_per_gas(vm)?;
+        l2_hook::validate_sufficient_max_fee_per_gas_l2(vm, &self.fee_config.operator_fee_config)?;
+
+        let sender_info = vm.db.get_account(sender)?.info.clone();
+        if sender_info.nonce != tx.nonce {
+            return Err(TxValidationError::NonceMismatch {
+                expected: sender_info.nonce,
+                actual: tx.nonce,
+            }
+            .into());
+        }
+
+        // The payer is charged the whole gas limit up front
+        let up_front_cost = vm
+            .env
+            .gas_price
+            .checked_mul(vm.env.gas_limit.into())
+            .ok_or(TxValidationError::GasLimitPriceProductOverflow)?;
+        vm.decrease_account_balance(tx.payer(), up_front_cost)
+            .map_err(|_| TxValidationError::InsufficientAccountFunds)?;
+
+        // The intrinsic gas is taken from the validation gas limit
+        let intrinsic_gas = intrinsic_gas(&tx)?;
+        if intrinsic_gas > tx.validation_gas_limit {
+            return Err(TxValidationError::IntrinsicGasTooLow.into());
+        }
+        self.validation_gas_used = intrinsic_gas;
+
+        let sender_has_code = vm.db.get_account(sender)?.has_code();
+        if let Some(deployer) = tx.deployer {
+            if sender_has_code {
+                return Err(validation_failed("sender is already deployed"));
+            }
+            let gas_limit = tx
+                .validation_gas_limit
+                .saturating_sub(self.validation_gas_used);
+            let report = self.run_validation_frame(
+                vm,
+                sender,
+                AA_SENDER_CREATOR,
+                deployer,
+                tx.deployer_data.clone(),
+                gas_limit,
+            )?;
+            if !report.is_success() || !vm.db.get_account(sender)?.has_code() {
+                return Err(validation_failed("deployer did not deploy the sender"));
+            }
+        } else if !sender_has_code {
+            return Err(validation_failed("sender is not deployed"));
+        }
+
+        // Done after the deployment, since a sender with a nonce can't be created
+        vm.increment_account_nonce(sender)
+            .map_err(|_| TxValidationError::NonceIsMax)?;
+
+        let gas_limit = tx
+            .validation_gas_limit
+            .saturating_sub(self.validation_gas_used);
+        let report = self.run_validation_frame(
+            vm,
+            sender,
+            AA_ENTRY_POINT,
+            sender,
+            encode_validation_call(VALIDATE_TRANSACTION_SELECTOR, &tx),
+            gas_limit,
+        )?;
+        if !report.is_success() {
+            return Err(validation_failed("sender validation reverted"));
+        }
+        check_validation_data(vm, &report.output, VALIDATE_TRANSACTION_SELECTOR, "sender")?;
+
+        if let Some(paymaster) = tx.paymaster {
+            let report = self.run_validation_frame(
+                vm,
+                sender,
+                AA_ENTRY_POINT,
+                paymaster,
+                encode_validation_call(VALIDATE_PAYMASTER_TRANSACTION_SELECTOR, &tx),
+                tx.paymaster_validation_gas_limit,
+            )?;
+            if !report.is_success() {
+                return Err(validation_failed("paymaster validation reverted"));
+            }
+            check_validation_data(
+                vm,
+                &report.output,
+                VALIDATE_PAYMASTER_TRANSACTION_SELECTOR,
+                "paymaster",
+            )?;
+            self.paymaster_context = Some(
+                decode_paymaster_context(&report.output)
+                    .ok_or_else(|| validation_failed("invalid paymaster context"))?,
+            );
+        }
+
+        // The execution frame is a call of the entry point to the sender, limited by its own gas
+        vm.current_call_frame.msg_sender = AA_ENTRY_POINT;
+        vm.current_call_frame.gas_limit = tx.call_gas_limit;
+        vm.current_call_frame.gas_remaining =
+            i64::try_from(tx.call_gas_limit).map_err(|_| InternalError::TypeConversion)?;
+
+        default_hook::set_bytecode_and_code_address(vm)
+    }
+
+    fn finalize_execution(
+        &mut self,
+        vm: &mut VM<'_>,
+        ctx_result: &mut ContextResult,
+    ) -> Result<(), VMError> {
+        let tx = rip7560_tx(vm)?;
+
+        let gas_refunded = default_hook::compute_gas_refunded(vm, ctx_result)?;
+        let mut gas_used = self
+            .validation_gas_used
+            .checked_add(ctx_result.gas_used)
+            .ok_or(InternalError::Overflow)?
+            .checked_sub(gas_refunded)
+            .ok_or(InternalError::Underflow)?;
+
+        if let (Some(paymaster), Some(context)) = (tx.paymaster, self.paymaster_context.take()) {
+            let actual_gas_cost = vm
+                .env
+                .gas_price
+                .checked_mul(gas_used.into())
+                .ok_or(InternalError::Overflow)?;
+            let report = run_frame(
+                vm,
+                AA_ENTRY_POINT,
+                paymaster,
+                encode_post_op_call(ctx_result.is_success(), actual_gas_cost, &context),
+                tx.post_op_gas_limit,
+                None,
+            )?;
+            gas_used = gas_used
+                .checked_add(report.gas_used)
+                .ok_or(InternalError::Overflow)?;
+
+            // A failed post transaction call reverts the execution, the transaction stays valid
+            if !report.is_success() && ctx_result.is_success() {
+                vm.substate.revert_backup();
+                vm.restore_cache_state()?;
+
+                ctx_result.result = TxResult::Revert(
+                    TxValidationError::Rip7560ValidationFailed(
+                        "paymaster post transaction reverted".to_owned(),
+                    )
+                    .into(),
+                );
+                ctx_result.output = Bytes::new();
+            }
+        }
+
+        let l1_gas = l2_hook::calculate_l1_fee_gas(vm, &self.fee_config.l1_fee_config)?;
+        let total_gas = gas_used.saturating_add(l1_gas).min(vm.env.gas_limit);
+        let l1_gas = total_gas.saturating_sub(gas_used);
+
+        default_hook::delete_self_destruct_accounts(vm)?;
+
+        // Return the unused gas to whoever paid for it
+        let gas_to_return = vm
+            .env
+            .gas_limit
+            .checked_sub(total_gas)
+            .ok_or(InternalError::Underflow)?;
+        let return_amount = vm
+            .env
+            .gas_price
+            .checked_mul(U256::from(gas_to_return))
+            .ok_or(InternalError::Overflow)?;
+        vm.increase_account_balance(tx.payer(), return_amount)?;
+
+        if let Some(l1_fee_config) = self.fee_config.l1_fee_config {
+            l2_hook::pay_to_l1_fee_vault(vm, l1_gas, l1_fee_config, false)?;
+        }
+
+        let gas_to_pay = total_gas.saturating_sub(l1_gas);
+        l2_hook::pay_coinbase_l2(vm, gas_to_pay, &self.fee_config.operator_fee_config, false)?;
+        if let Some(base_fee_vault) = self.fee_config.base_fee_vault {
+            l2_hook::pay_base_fee_vault(vm, gas_to_pay, base_fee_vault, false)?;
+        }
+        if let Some(operator_fee_config) = self.fee_config.operator_fee_config {
+            l2_hook::pay_operator_fee(vm, gas_to_pay, operator_fee_config, false)?;
+        }
+
+        vm.substate.refunded_gas = gas_refunded;
+        ctx_result.gas_used = total_gas;
+
+        Ok(())
+    }
+}
+
+/// Runs the validation frames of a RIP-7560 transaction as done before accepting it into the
+/// mempool, following the ERC-7562 rules. State changes are undone.
+///
+/// The frames can't use [`RESTRICTED_VALIDATION_OPCODES`], and can only access the storage
+/// associated with the sender, see [`is_associated_slot`]. Otherwise a single write to shared
+/// storage could invalidate many pending transactions once included, after their validation
+/// was run for free. ERC-7562 also lets staked deployers and paymasters access their own
+/// storage, but without a reputation system to throttle them every entity is held to the
+/// unstaked rules.
+pub fn simulate_rip7560_validation(vm: &mut VM<'_>, fee_config: FeeConfig) -> Result<(), VMError> {
+    let mut hook = Rip7560Hook {
+        enforce_validation_rules: true,
+        ..Rip7560Hook::new(fee_config)
+    };
+    let result = hook.prepare_execution(vm);
+    vm.restore_cache_state()?;
+    result
+}
+
+fn rip7560_tx(vm: &VM<'_>) -> Result<RIP7560Transaction, InternalError> {
+    match &vm.tx {
+        Transaction::RIP7560Transaction(tx) => Ok(tx.clone()),
+        _ => Err(InternalError::msg("Expected a RIP-7560 transaction")),
+    }
+}
+
+fn validation_failed(reason: &str) -> VMError {
+    TxValidationError::Rip7560ValidationFailed(reason.to_owned()).into()
+}
+
+/// Base cost, plus the calldata cost of every data field and the access list cost
+fn intrinsic_gas(tx: &RIP7560Transaction) -> Result<u64, VMError> {
+    let mut intrinsic_gas = AA_BASE_GAS;
+    for data in [
+        &tx.sender_validation_data,
+        &tx.deployer_data,
+        &tx.paymaster_data,
+        &tx.execution_data,
+    ] {
+        intrinsic_gas = intrinsic_gas
+            .checked_add(gas_cost::tx_calldata(data)?)
+            .ok_or(TxValidationError::IntrinsicGasTooLow)?;
+    }
+    for (_, keys) in &tx.access_list {
+        let keys: u64 = keys
+            .len()
+            .try_into()
+            .map_err(|_| InternalError::TypeConversion)?;
+        intrinsic_gas = ACCESS_LIST_STORAGE_KEY_COST
+            .checked_mul(keys)
+            .and_then(|keys_cost| keys_cost.checked_add(ACCESS_LIST_ADDRESS_COST))
+            .and_then(|cost| intrinsic_gas.checked_add(cost))
+            .ok_or(TxValidationError::IntrinsicGasTooLow)?;
+    }
+    Ok(intrinsic_gas)
+}
+
+/// Runs a call of `caller` to `to` as a separate execution on the same database.
+/// Its changes are added to the backup of the transaction, so they are undone along with it.
+/// With a `validated_sender`, the call is held to the ERC-7562 rules for validating it.
+fn run_frame(
+    vm: &mut VM<'_>,
+    caller: Address,
+    to: Address,
+    data: Bytes,
+    gas_limit: u64,
+    validated_sender: Option<Address>,
+) -> Result<ExecutionReport, VMError> {
+    let frame_tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+        chain_id: vm.env.chain_id.as_u64(),
+        nonce: vm.env.tx_nonce,
+        gas_limit,
+        to: TxKind::Call(to),
+        data,
+        ..Default::default()
+    });
+    let mut env = vm.env.clone();
+    env.gas_limit = gas_limit;
+    let vm_type = vm.vm_type;
+
+    let mut frame_vm = VM::new(
+        env,
+        &mut *vm.db,
+        &frame_tx,
+        LevmCallTracer::disabled(),
+        vm_type,
+    )?;
+    frame_vm.hooks = vec![];
+    frame_vm.current_call_frame.msg_sender = caller;
+    if validated_sender.is_some() {
+        frame_vm.restrict_opcodes(&RESTRICTED_VALIDATION_OPCODES);
+        frame_vm.record_storage_accesses();
+    }
+    default_hook::set_bytecode_and_code_address(&mut frame_vm)?;
+    let report = frame_vm.execute()?;
+
+    let restricted_opcode = frame_vm.restricted_opcode;
+    let storage_accesses = frame_vm.storage_accesses.take();
+    let frame_backup = std::mem::take(&mut frame_vm.current_call_frame.call_frame_backup);
+    drop(frame_vm);
+    vm.merge_call_frame_backup_with_parent(&frame_backup)?;
+
+    if let Some(opcode) = restricted_opcode {
+        return Err(TxValidationError::Rip7560RestrictedOpcode(opcode).into());
+    }
+    if let (Some(sender), Some(accesses)) = (validated_sender, storage_accesses) {
+        for &(address, slot) in &accesses.slots {
+            if !is_associated_slot(&accesses, sender, address, slot) {
+                return Err(TxValidationError::Rip7560UnassociatedStorage { address, slot }.into());
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Whether the `slot` of `address` is associated with `sender` as defined by ERC-7562: it's in
+/// the storage of the sender, it's the sender address, or it's up to 128 slots after the hash
+/// of a value starting with the sender address, as the values of the mappings keyed by it.
+fn is_associated_slot(
+    accesses: &StorageAccesses,
+    sender: Address,
+    address: Address,
+    slot: H256,
+) -> bool {
+    if address == sender {
+        return true;
+    }
+    let sender_word = address_to_word(sender);
+    let slot = u256_from_big_endian(slot.as_bytes());
+    slot == sender_word
+        || accesses.hashed_words.iter().any(|&(first_word, hash)| {
+            first_word == sender_word
+                && slot
+                    .checked_sub(hash)
+                    .is_some_and(|offset| offset <= U256::from(MAX_ASSOCIATED_SLOT_OFFSET))
+        })
+}
+
+/// Encodes `selector(uint256 version, bytes32 txHash, bytes transaction)`.
+/// The transaction is passed RLP encoded, and its hash is the signing hash.
+fn encode_validation_call(selector: [u8; 4], tx: &RIP7560Transaction) -> Bytes {
+    let encoded_tx = tx.encode_to_vec();
+    let mut data = Vec::new();
+    data.extend_from_slice(&selector);
+    data.extend_from_slice(&U256::from(RIP7560_VERSION).to_big_endian());
+    data.extend_from_slice(tx.signing_hash().as_bytes());
+    data.extend_from_slice(&U256::from(32 * 3).to_big_endian());
+    encode_dynamic_bytes(&mut data, &encoded_tx);
+    data.into()
+}
+
+/// Encodes `postPaymasterTransaction(bool success, uint256 actualGasCost, bytes context)`
+fn encode_post_op_call(success: bool, actual_gas_cost: U256, context: &Bytes) -> Bytes {
+    let mut data = Vec::new();
+    data.extend_from_slice(&POST_PAYMASTER_TRANSACTION_SELECTOR);
+    data.extend_from_slice(&U256::from(u8::from(success)).to_big_endian());
+    data.extend_from_slice(&actual_gas_cost.to_big_endian());
+    data.extend_from_slice(&U256::from(32 * 3).to_big_endian());
+    encode_dynamic_bytes(&mut data, context);
+    data.into()
+}
+
+/// Appends the length and the zero padded contents of an ABI encoded `bytes` value
+fn encode_dynamic_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    data.extend_from_slice(&U256::from(bytes.len()).to_big_endian());
+    data.extend_from_slice(bytes);
+    let padded_len = data.len().next_multiple_of(32);
+    data.resize(padded_len, 0);
+}
+
+/// Checks the `bytes32 validationData` returned by a validation frame. It holds the magic
+/// value of the frame (its selector) in the first 4 bytes, and the `validUntil` and `validAfter`
+/// timestamps in the last 12, where a zero `validUntil` means no expiration.
+fn check_validation_data(
+    vm: &VM<'_>,
+    output: &[u8],
+    magic: [u8; 4],
+    frame: &str,
+) -> Result<(), VMError> {
+    let (Some(prefix), Some(valid_until), Some(valid_after)) =
+        (output.get(0..4), output.get(20..26), output.get(26..32))
+    else {
+        return Err(validation_failed(&format!(
+            "{frame} returned no validation data"
+        )));
+    };
+    if prefix != magic {
+        return Err(validation_failed(&format!(
+            "{frame} rejected the transaction"
+        )));
+    }
+
+    let valid_until = U256::from_big_endian(valid_until);
+    let valid_after = U256::from_big_endian(valid_after);
+    if (!valid_until.is_zero() && vm.env.timestamp > valid_until) || vm.env.timestamp < valid_after
+    {
+        return Err(validation_failed(&format!(
+            "{frame} validation is expired or not yet valid"
+        )));
+    }
+    Ok(())
+}
+
+/// Decodes the `bytes context` returned after the validation data by the paymaster
+fn decode_paymaster_context(output: &[u8]) -> Option<Bytes> {
+    let read_word = |at: usize| {
+        let word = output.get(at..at.checked_add(32)?)?;
+        u256_to_usize(U256::from_big_endian(word)).ok()
+    };
+    let offset = read_word(32)?;
+    let len = read_word(offset)?;
+    let start = offset.checked_add(32)?;
+    output
+        .get(start..start.checked_add(len)?)
+        .map(Bytes::copy_from_slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_decodes_paymaster_context() {
+        let context = Bytes::from_static(&[0xab; 40]);
+        let mut output = vec![0; 32];
+        output.extend_from_slice(&U256::from(64).to_big_endian());
+        encode_dynamic_bytes(&mut output, &context);
+        assert_eq!(output.len(), 32 * 5);
+        assert_eq!(decode_paymaster_context(&output), Some(context.clone()));
+
+        // Truncated context
+        output.truncate(32 * 3 + 10);
+        assert_eq!(decode_paymaster_context(&output), None);
+
+        let call = encode_post_op_call(true, U256::from(7), &context);
+        assert_eq!(call.len(), 4 + 32 * 6);
+        assert_eq!(
+            call.get(0..4),
+            Some(&POST_PAYMASTER_TRANSACTION_SELECTOR[..])
+        );
+    }
+}
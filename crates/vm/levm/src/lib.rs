@@ -1,5 +1,8 @@
+pub mod access_events;
 pub mod call_frame;
+pub mod code_analysis;
 pub mod constants;
+pub mod custom_precompiles;
 pub mod db;
 pub mod debug;
 pub mod environment;
@@ -16,5 +19,7 @@ pub mod utils;
 pub mod vm;
 pub use environment::*;
 pub mod account;
+#[cfg(feature = "perf_contract_profiling")]
+pub mod contract_profiling;
 #[cfg(feature = "perf_opcode_timings")]
 pub mod timings;
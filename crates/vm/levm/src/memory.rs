@@ -65,6 +65,15 @@ impl Memory {
         self.len() == 0
     }
 
+    /// Returns a copy of the current memory, from the current base.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.buffer
+            .borrow()
+            .get(self.current_base..self.current_base.saturating_add(self.len))
+            .map(<[u8]>::to_vec)
+            .unwrap_or_default()
+    }
+
     /// Resizes the from the current base to fit the memory specified at new_memory_size.
     ///
     /// Note: new_memory_size is increased to the next 32 byte multiple.
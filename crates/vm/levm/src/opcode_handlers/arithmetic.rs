@@ -12,8 +12,6 @@ impl<'a> VM<'a> {
     // ADD operation
     pub fn op_add(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::ADD)?;
-
         let [augend, addend] = *current_call_frame.stack.pop()?;
         let sum = augend.overflowing_add(addend).0;
         current_call_frame.stack.push(sum)?;
@@ -24,8 +22,6 @@ impl<'a> VM<'a> {
     // SUB operation
     pub fn op_sub(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SUB)?;
-
         let [minuend, subtrahend] = *current_call_frame.stack.pop()?;
         let difference = minuend.overflowing_sub(subtrahend).0;
         current_call_frame.stack.push(difference)?;
@@ -36,8 +32,6 @@ impl<'a> VM<'a> {
     // MUL operation
     pub fn op_mul(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::MUL)?;
-
         let [multiplicand, multiplier] = *current_call_frame.stack.pop()?;
         let product = multiplicand.overflowing_mul(multiplier).0;
         current_call_frame.stack.push(product)?;
@@ -48,8 +42,6 @@ impl<'a> VM<'a> {
     // DIV operation
     pub fn op_div(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::DIV)?;
-
         let [dividend, divisor] = *current_call_frame.stack.pop()?;
         let Some(quotient) = dividend.checked_div(divisor) else {
             current_call_frame.stack.push_zero()?;
@@ -63,8 +55,6 @@ impl<'a> VM<'a> {
     // SDIV operation
     pub fn op_sdiv(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SDIV)?;
-
         let [dividend, divisor] = *current_call_frame.stack.pop()?;
         if divisor.is_zero() || dividend.is_zero() {
             current_call_frame.stack.push_zero()?;
@@ -94,8 +84,6 @@ impl<'a> VM<'a> {
     // MOD operation
     pub fn op_mod(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::MOD)?;
-
         let [dividend, divisor] = *current_call_frame.stack.pop()?;
 
         let remainder = dividend.checked_rem(divisor).unwrap_or_default();
@@ -108,8 +96,6 @@ impl<'a> VM<'a> {
     // SMOD operation
     pub fn op_smod(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SMOD)?;
-
         let [unchecked_dividend, unchecked_divisor] = *current_call_frame.stack.pop()?;
 
         if unchecked_divisor.is_zero() || unchecked_dividend.is_zero() {
@@ -142,8 +128,6 @@ impl<'a> VM<'a> {
     // ADDMOD operation
     pub fn op_addmod(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::ADDMOD)?;
-
         let [augend, addend, modulus] = *current_call_frame.stack.pop()?;
 
         if modulus.is_zero() {
@@ -178,8 +162,6 @@ impl<'a> VM<'a> {
     // MULMOD operation
     pub fn op_mulmod(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::MULMOD)?;
-
         let [multiplicand, multiplier, modulus] = *current_call_frame.stack.pop()?;
 
         if modulus.is_zero() || multiplicand.is_zero() || multiplier.is_zero() {
@@ -239,8 +221,6 @@ impl<'a> VM<'a> {
     // SIGNEXTEND operation
     pub fn op_signextend(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SIGNEXTEND)?;
-
         let [byte_size_minus_one, value_to_extend] = *current_call_frame.stack.pop()?;
 
         if byte_size_minus_one > U256::from(31) {
@@ -275,9 +255,6 @@ impl<'a> VM<'a> {
     }
 
     pub fn op_clz(&mut self) -> Result<OpcodeResult, VMError> {
-        self.current_call_frame
-            .increase_consumed_gas(gas_cost::CLZ)?;
-
         let value = self.current_call_frame.stack.pop1()?;
 
         self.current_call_frame
@@ -1,7 +1,6 @@
 use crate::{
     constants::WORD_SIZE,
     errors::{InternalError, OpcodeResult, VMError},
-    gas_cost,
     vm::VM,
 };
 use ethrex_common::U256;
@@ -13,7 +12,6 @@ impl<'a> VM<'a> {
     // LT operation
     pub fn op_lt(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::LT)?;
         let [lho, rho] = *current_call_frame.stack.pop()?;
         let result = u256_from_bool(lho < rho);
         current_call_frame.stack.push(result)?;
@@ -24,7 +22,6 @@ impl<'a> VM<'a> {
     // GT operation
     pub fn op_gt(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::GT)?;
         let [lho, rho] = *current_call_frame.stack.pop()?;
         let result = u256_from_bool(lho > rho);
         current_call_frame.stack.push(result)?;
@@ -35,7 +32,6 @@ impl<'a> VM<'a> {
     // SLT operation (signed less than)
     pub fn op_slt(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SLT)?;
         let [lho, rho] = *current_call_frame.stack.pop()?;
         let lho_is_negative = lho.bit(255);
         let rho_is_negative = rho.bit(255);
@@ -54,7 +50,6 @@ impl<'a> VM<'a> {
     // SGT operation (signed greater than)
     pub fn op_sgt(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SGT)?;
         let [lho, rho] = *current_call_frame.stack.pop()?;
         let lho_is_negative = lho.bit(255);
         let rho_is_negative = rho.bit(255);
@@ -73,7 +68,6 @@ impl<'a> VM<'a> {
     // EQ operation (equality check)
     pub fn op_eq(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::EQ)?;
         let [lho, rho] = *current_call_frame.stack.pop()?;
         let result = u256_from_bool(lho == rho);
 
@@ -85,8 +79,6 @@ impl<'a> VM<'a> {
     // ISZERO operation (check if zero)
     pub fn op_iszero(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::ISZERO)?;
-
         let [operand] = current_call_frame.stack.pop()?;
         let result = u256_from_bool(operand.is_zero());
 
@@ -98,7 +90,6 @@ impl<'a> VM<'a> {
     // AND operation
     pub fn op_and(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::AND)?;
         let [a, b] = *current_call_frame.stack.pop()?;
         current_call_frame.stack.push(a & b)?;
 
@@ -108,7 +99,6 @@ impl<'a> VM<'a> {
     // OR operation
     pub fn op_or(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::OR)?;
         let [a, b] = *current_call_frame.stack.pop()?;
         current_call_frame.stack.push(a | b)?;
 
@@ -118,7 +108,6 @@ impl<'a> VM<'a> {
     // XOR operation
     pub fn op_xor(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::XOR)?;
         let [a, b] = *current_call_frame.stack.pop()?;
         current_call_frame.stack.push(a ^ b)?;
 
@@ -128,7 +117,6 @@ impl<'a> VM<'a> {
     // NOT operation
     pub fn op_not(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::NOT)?;
         let a = current_call_frame.stack.pop1()?;
         current_call_frame.stack.push(!a)?;
 
@@ -138,7 +126,6 @@ impl<'a> VM<'a> {
     // BYTE operation
     pub fn op_byte(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::BYTE)?;
         let [op1, op2] = *current_call_frame.stack.pop()?;
         let byte_index = match op1.try_into() {
             Ok(byte_index) => byte_index,
@@ -169,7 +156,6 @@ impl<'a> VM<'a> {
     // SHL operation (shift left)
     pub fn op_shl(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SHL)?;
         let [shift, value] = *current_call_frame.stack.pop()?;
 
         if shift < U256::from(256) {
@@ -185,7 +171,6 @@ impl<'a> VM<'a> {
     // SHR operation (shift right)
     pub fn op_shr(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SHR)?;
         let [shift, value] = *current_call_frame.stack.pop()?;
 
         if shift < U256::from(256) {
@@ -201,7 +186,6 @@ impl<'a> VM<'a> {
     // SAR operation (arithmetic shift right)
     pub fn op_sar(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SAR)?;
         let [shift, value] = *current_call_frame.stack.pop()?;
 
         // In 2's complement arithmetic, the most significant bit being one means the number is negative
@@ -1,11 +1,12 @@
 use crate::{
+    access_events::AccessMode,
     errors::{ExceptionalHalt, InternalError, OpcodeResult, VMError},
     gas_cost::{self},
     memory::calculate_memory_size,
     utils::{size_offset_to_usize, u256_to_usize, word_to_address},
     vm::VM,
 };
-use ethrex_common::{U256, utils::u256_from_big_endian_const};
+use ethrex_common::{U256, types::ubt_leaf::UbtLeaf, utils::u256_from_big_endian_const};
 
 // Environmental Information (16)
 // Opcodes: ADDRESS, BALANCE, ORIGIN, CALLER, CALLVALUE, CALLDATALOAD, CALLDATASIZE, CALLDATACOPY, CODESIZE, CODECOPY, GASPRICE, EXTCODESIZE, EXTCODECOPY, RETURNDATASIZE, RETURNDATACOPY, EXTCODEHASH
@@ -14,8 +15,6 @@ impl<'a> VM<'a> {
     // ADDRESS operation
     pub fn op_address(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::ADDRESS)?;
-
         let addr = current_call_frame.to; // The recipient of the current call.
 
         current_call_frame
@@ -29,9 +28,10 @@ impl<'a> VM<'a> {
     pub fn op_balance(&mut self) -> Result<OpcodeResult, VMError> {
         let address = word_to_address(self.current_call_frame.stack.pop1()?);
 
-        let address_was_cold = !self.substate.add_accessed_address(address);
+        let address_was_cold = self.access_address(address);
         let account_balance = self.db.get_account(address)?.info.balance;
 
+        self.charge_access_events([UbtLeaf::BasicData(address)], AccessMode::Read)?;
         let current_call_frame = &mut self.current_call_frame;
 
         current_call_frame.increase_consumed_gas(gas_cost::balance(address_was_cold)?)?;
@@ -45,8 +45,6 @@ impl<'a> VM<'a> {
     pub fn op_origin(&mut self) -> Result<OpcodeResult, VMError> {
         let origin = self.env.origin;
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::ORIGIN)?;
-
         current_call_frame
             .stack
             .push(u256_from_big_endian_const(origin.to_fixed_bytes()))?;
@@ -57,8 +55,6 @@ impl<'a> VM<'a> {
     // CALLER operation
     pub fn op_caller(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::CALLER)?;
-
         let caller = u256_from_big_endian_const(current_call_frame.msg_sender.to_fixed_bytes());
         current_call_frame.stack.push(caller)?;
 
@@ -68,8 +64,6 @@ impl<'a> VM<'a> {
     // CALLVALUE operation
     pub fn op_callvalue(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::CALLVALUE)?;
-
         let callvalue = current_call_frame.msg_value;
 
         current_call_frame.stack.push(callvalue)?;
@@ -80,8 +74,6 @@ impl<'a> VM<'a> {
     // CALLDATALOAD operation
     pub fn op_calldataload(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::CALLDATALOAD)?;
-
         let calldata_size: U256 = current_call_frame.calldata.len().into();
 
         let offset = current_call_frame.stack.pop1()?;
@@ -122,8 +114,6 @@ impl<'a> VM<'a> {
     // CALLDATASIZE operation
     pub fn op_calldatasize(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::CALLDATASIZE)?;
-
         current_call_frame
             .stack
             .push(U256::from(current_call_frame.calldata.len()))?;
@@ -200,8 +190,6 @@ impl<'a> VM<'a> {
     // CODESIZE operation
     pub fn op_codesize(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::CODESIZE)?;
-
         current_call_frame
             .stack
             .push(U256::from(current_call_frame.bytecode.bytecode.len()))?;
@@ -229,6 +217,15 @@ impl<'a> VM<'a> {
             return Ok(OpcodeResult::Continue);
         }
 
+        if !current_call_frame.is_create {
+            let (address, code_len) = (
+                current_call_frame.code_address,
+                current_call_frame.bytecode.bytecode.len(),
+            );
+            self.charge_code_access(address, code_len, code_offset, size)?;
+        }
+        let current_call_frame = &mut self.current_call_frame;
+
         // Happiest fast path, copy without an intermediate buffer because there is no need to pad 0s and also size doesn't overflow.
         if let Some(code_offset_end) = code_offset.checked_add(size)
             && code_offset_end <= current_call_frame.bytecode.bytecode.len()
@@ -275,8 +272,6 @@ impl<'a> VM<'a> {
     pub fn op_gasprice(&mut self) -> Result<OpcodeResult, VMError> {
         let gas_price = self.env.gas_price;
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::GASPRICE)?;
-
         current_call_frame.stack.push(gas_price)?;
 
         Ok(OpcodeResult::Continue)
@@ -285,10 +280,12 @@ impl<'a> VM<'a> {
     // EXTCODESIZE operation
     pub fn op_extcodesize(&mut self) -> Result<OpcodeResult, VMError> {
         let address = word_to_address(self.current_call_frame.stack.pop1()?);
-        let address_was_cold = !self.substate.add_accessed_address(address);
+        let address_was_cold = self.access_address(address);
         // FIXME: a bit wasteful to fetch the whole code just to get the length.
         let account_code_length = self.db.get_account_code(address)?.bytecode.len().into();
 
+        // The code size is part of the basic data of the account
+        self.charge_access_events([UbtLeaf::BasicData(address)], AccessMode::Read)?;
         let current_call_frame = &mut self.current_call_frame;
 
         current_call_frame.increase_consumed_gas(gas_cost::extcodesize(address_was_cold)?)?;
@@ -308,7 +305,7 @@ impl<'a> VM<'a> {
         let offset = u256_to_usize(offset).unwrap_or(usize::MAX);
 
         let current_memory_size = call_frame.memory.len();
-        let address_was_cold = !self.substate.add_accessed_address(address);
+        let address_was_cold = self.access_address(address);
         let new_memory_size = calculate_memory_size(dest_offset, size)?;
 
         self.current_call_frame
@@ -319,6 +316,12 @@ impl<'a> VM<'a> {
                 address_was_cold,
             )?)?;
 
+        if self.env.config.stateless_gas {
+            self.charge_access_events([UbtLeaf::BasicData(address)], AccessMode::Read)?;
+            let code_len = self.db.get_account_code(address)?.bytecode.len();
+            self.charge_code_access(address, code_len, offset, size)?;
+        }
+
         if size == 0 {
             return Ok(OpcodeResult::Continue);
         }
@@ -363,8 +366,6 @@ impl<'a> VM<'a> {
     // RETURNDATASIZE operation
     pub fn op_returndatasize(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::RETURNDATASIZE)?;
-
         current_call_frame
             .stack
             .push(U256::from(current_call_frame.sub_return_data.len()))?;
@@ -417,10 +418,12 @@ impl<'a> VM<'a> {
     // EXTCODEHASH operation
     pub fn op_extcodehash(&mut self) -> Result<OpcodeResult, VMError> {
         let address = word_to_address(self.current_call_frame.stack.pop1()?);
-        let address_was_cold = !self.substate.add_accessed_address(address);
+        let address_was_cold = self.access_address(address);
         let account = self.db.get_account(address)?;
         let account_is_empty = account.is_empty();
         let account_code_hash = account.info.code_hash.0;
+
+        self.charge_access_events([UbtLeaf::CodeHash(address)], AccessMode::Read)?;
         let current_call_frame = &mut self.current_call_frame;
 
         current_call_frame.increase_consumed_gas(gas_cost::extcodehash(address_was_cold)?)?;
@@ -1,6 +1,5 @@
 use crate::{
     errors::{OpcodeResult, VMError},
-    gas_cost,
     vm::VM,
 };
 
@@ -11,8 +10,6 @@ impl<'a> VM<'a> {
     // SWAP operation
     pub fn op_swap<const N: usize>(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::SWAPN)?;
-
         current_call_frame.stack.swap::<N>()?;
 
         Ok(OpcodeResult::Continue)
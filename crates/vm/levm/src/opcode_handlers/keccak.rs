@@ -25,8 +25,17 @@ impl<'a> VM<'a> {
             size,
         )?)?;
 
-        let hash = keccak_hash(current_call_frame.memory.load_range(offset, size)?);
-        current_call_frame.stack.push(u256_from_big_endian(&hash))?;
+        let data = current_call_frame.memory.load_range(offset, size)?;
+        let hash = u256_from_big_endian(&keccak_hash(&data));
+        current_call_frame.stack.push(hash)?;
+
+        if let Some(accesses) = &mut self.storage_accesses
+            && let Some(first_word) = data.get(..32)
+        {
+            accesses
+                .hashed_words
+                .push((u256_from_big_endian(first_word), hash));
+        }
 
         Ok(OpcodeResult::Continue)
     }
@@ -1,6 +1,5 @@
 use crate::{
     errors::{InternalError, OpcodeResult, VMError},
-    gas_cost,
     vm::VM,
 };
 use ethrex_common::{U256, utils::u256_from_big_endian_const};
@@ -12,8 +11,6 @@ impl<'a> VM<'a> {
     // Generic PUSH operation, optimized at compile time for the given N.
     pub fn op_push<const N: usize>(&mut self) -> Result<OpcodeResult, VMError> {
         let call_frame = &mut self.current_call_frame;
-        call_frame.increase_consumed_gas(gas_cost::PUSHN)?;
-
         // Check to avoid multiple checks.
         let Some(new_pc) = call_frame.pc.checked_add(N) else {
             return Err(InternalError::Overflow.into());
@@ -43,8 +40,6 @@ impl<'a> VM<'a> {
 
     // PUSH0
     pub fn op_push0(&mut self) -> Result<OpcodeResult, VMError> {
-        self.current_call_frame
-            .increase_consumed_gas(gas_cost::PUSH0)?;
         self.current_call_frame.stack.push_zero()?;
         Ok(OpcodeResult::Continue)
     }
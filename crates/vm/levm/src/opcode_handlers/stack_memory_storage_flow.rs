@@ -1,4 +1,5 @@
 use crate::{
+    access_events::AccessMode,
     call_frame::CallFrame,
     constants::{WORD_SIZE, WORD_SIZE_IN_BYTES_USIZE},
     errors::{ExceptionalHalt, InternalError, OpcodeResult, VMError},
@@ -9,6 +10,7 @@ use crate::{
 };
 use ethrex_common::{
     U256,
+    types::ubt_leaf::UbtLeaf,
     utils::{u256_to_big_endian, u256_to_h256},
 };
 
@@ -21,7 +23,6 @@ impl<'a> VM<'a> {
     // POP operation
     pub fn op_pop(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::POP)?;
         current_call_frame.stack.pop1()?;
         Ok(OpcodeResult::Continue)
     }
@@ -138,6 +139,10 @@ impl<'a> VM<'a> {
 
         let (value, storage_slot_was_cold) = self.access_storage_slot(address, storage_slot_key)?;
 
+        self.charge_access_events(
+            [UbtLeaf::Storage(address, storage_slot_key)],
+            AccessMode::Read,
+        )?;
         let current_call_frame = &mut self.current_call_frame;
 
         current_call_frame.increase_consumed_gas(gas_cost::sload(storage_slot_was_cold)?)?;
@@ -220,6 +225,13 @@ impl<'a> VM<'a> {
                 storage_slot_was_cold,
             )?)?;
 
+        let access_mode = if original_value.is_zero() && !new_storage_slot_value.is_zero() {
+            AccessMode::Fill
+        } else {
+            AccessMode::Write
+        };
+        self.charge_access_events([UbtLeaf::Storage(to, key)], access_mode)?;
+
         if new_storage_slot_value != current_value {
             self.update_account_storage(to, key, new_storage_slot_value, current_value)?;
         }
@@ -230,7 +242,6 @@ impl<'a> VM<'a> {
     // MSIZE operation
     pub fn op_msize(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::MSIZE)?;
         current_call_frame
             .stack
             .push(current_call_frame.memory.len().into())?;
@@ -240,8 +251,6 @@ impl<'a> VM<'a> {
     // GAS operation
     pub fn op_gas(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::GAS)?;
-
         let remaining_gas = current_call_frame.gas_remaining;
         // Note: These are not consumed gas calculations, but are related, so I used this wrapping here
         current_call_frame.stack.push(remaining_gas.into())?;
@@ -279,8 +288,6 @@ impl<'a> VM<'a> {
     // JUMP operation
     pub fn op_jump(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::JUMP)?;
-
         let jump_address = current_call_frame.stack.pop1()?;
         Self::jump(current_call_frame, jump_address)?;
 
@@ -325,9 +332,6 @@ impl<'a> VM<'a> {
     pub fn op_jumpi(&mut self) -> Result<OpcodeResult, VMError> {
         let [jump_address, condition] = *self.current_call_frame.stack.pop()?;
 
-        self.current_call_frame
-            .increase_consumed_gas(gas_cost::JUMPI)?;
-
         if !condition.is_zero() {
             // Move the PC but don't increment it afterwards
             Self::jump(&mut self.current_call_frame, jump_address)?;
@@ -338,17 +342,13 @@ impl<'a> VM<'a> {
 
     // JUMPDEST operation
     pub fn op_jumpdest(&mut self) -> Result<OpcodeResult, VMError> {
-        self.current_call_frame
-            .increase_consumed_gas(gas_cost::JUMPDEST)?;
-
+        // Its only effect is its static gas, which is charged before running it
         Ok(OpcodeResult::Continue)
     }
 
     // PC operation
     pub fn op_pc(&mut self) -> Result<OpcodeResult, VMError> {
         let current_call_frame = &mut self.current_call_frame;
-        current_call_frame.increase_consumed_gas(gas_cost::PC)?;
-
         current_call_frame
             .stack
             .push(U256::from(current_call_frame.pc.wrapping_sub(1)))?;
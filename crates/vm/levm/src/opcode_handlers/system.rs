@@ -1,4 +1,5 @@
 use crate::{
+    access_events::{AccessMode, account_header},
     call_frame::CallFrame,
     constants::{FAIL, INIT_CODE_MAX_SIZE, SUCCESS},
     errors::{ContextResult, ExceptionalHalt, InternalError, OpcodeResult, TxResult, VMError},
@@ -12,7 +13,7 @@ use bytes::Bytes;
 use ethrex_common::{Address, H256, U256, evm::calculate_create_address, types::Fork};
 use ethrex_common::{
     tracing::CallType::{self, CALL, CALLCODE, DELEGATECALL, SELFDESTRUCT, STATICCALL},
-    types::Code,
+    types::{Code, ubt_leaf::UbtLeaf},
 };
 
 // System Operations (10)
@@ -78,6 +79,14 @@ impl<'a> VM<'a> {
                 callee,
             )?;
 
+        if !value.is_zero() {
+            let caller = self.current_call_frame.to;
+            self.charge_access_events(
+                [UbtLeaf::BasicData(caller), UbtLeaf::BasicData(callee)],
+                AccessMode::Write,
+            )?;
+        }
+
         let (cost, gas_limit) = gas_cost::call(
             new_memory_size,
             current_memory_size,
@@ -543,12 +552,20 @@ impl<'a> VM<'a> {
             (target_address, to)
         };
 
-        let target_account_is_cold = !self.substate.add_accessed_address(beneficiary);
+        let target_account_is_cold = self.access_address(beneficiary);
         let target_account_is_empty = self.db.get_account(beneficiary)?.is_empty();
 
         let current_account = self.db.get_account(to)?;
         let balance = current_account.info.balance;
 
+        self.charge_access_events([UbtLeaf::BasicData(beneficiary)], AccessMode::Read)?;
+        if !balance.is_zero() {
+            self.charge_access_events(
+                [UbtLeaf::BasicData(to), UbtLeaf::BasicData(beneficiary)],
+                AccessMode::Write,
+            )?;
+        }
+
         self.current_call_frame
             .increase_consumed_gas(gas_cost::selfdestruct(
                 target_account_is_cold,
@@ -605,10 +622,6 @@ impl<'a> VM<'a> {
         // Clear callframe subreturn data
         current_call_frame.sub_return_data = Bytes::new();
 
-        // Reserve gas for subcall
-        let gas_limit = max_message_call_gas(current_call_frame)?;
-        current_call_frame.increase_consumed_gas(gas_limit)?;
-
         // Load code from memory
         let code = self
             .current_call_frame
@@ -630,6 +643,13 @@ impl<'a> VM<'a> {
 
         // Add new contract to accessed addresses
         self.substate.add_accessed_address(new_address);
+        // [EIP-4762] - The header of the new account is written
+        self.charge_access_events(account_header(new_address), AccessMode::Fill)?;
+
+        // Reserve gas for subcall
+        let current_call_frame = &mut self.current_call_frame;
+        let gas_limit = max_message_call_gas(current_call_frame)?;
+        current_call_frame.increase_consumed_gas(gas_limit)?;
 
         // Log CREATE in tracer
         let call_type = match salt {
@@ -754,7 +774,7 @@ impl<'a> VM<'a> {
             return Ok(OpcodeResult::Continue);
         }
 
-        if precompiles::is_precompile(&code_address, self.env.config.fork, self.vm_type)
+        if precompiles::is_precompile(&code_address, &self.env.config, self.vm_type)
             && !is_delegation_7702
         {
             let mut gas_remaining = gas_limit;
@@ -763,7 +783,9 @@ impl<'a> VM<'a> {
                 &calldata,
                 gas_limit,
                 &mut gas_remaining,
-                self.env.config.fork,
+                &self.env.config,
+                self.db,
+                &mut self.storage_accesses,
             )?;
 
             let call_frame = &mut self.current_call_frame;
@@ -998,9 +1020,14 @@ impl<'a> VM<'a> {
         address: Address,
     ) -> Result<(usize, u64, bool, bool), VMError> {
         // Creation of previously empty accounts and cold addresses have higher gas cost
-        let address_was_cold = !self.substate.add_accessed_address(address);
+        let address_was_cold = self.access_address(address);
         let account_is_empty = self.db.get_account(address)?.is_empty();
 
+        // [EIP-4762] - Precompiles aren't part of the witness
+        if !precompiles::is_precompile(&address, &self.env.config, self.vm_type) {
+            self.charge_access_events([UbtLeaf::BasicData(address)], AccessMode::Read)?;
+        }
+
         // Calculated here for memory expansion gas cost
         let new_memory_size_for_args = calculate_memory_size(args_offset, args_size)?;
         let new_memory_size_for_return_data =
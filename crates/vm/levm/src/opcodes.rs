@@ -582,6 +582,32 @@ impl<'a> VM<'a> {
         Err(ExceptionalHalt::InvalidOpcode.into())
     }
 
+    /// Makes the given opcodes halt execution as invalid, recording the first one reached.
+    /// Used to forbid environment dependent opcodes when simulating transaction validation.
+    /// The opcodes fused into superinstructions (`PUSHn`, `DUPn`, `SWAPn`, `JUMP`, `JUMPI` and
+    /// `ADD`) can't be restricted, as superinstructions don't go through the opcode table.
+    pub fn restrict_opcodes(&mut self, opcodes: &[Opcode]) {
+        for opcode in opcodes {
+            if let Some(handler) = self.opcode_table.get_mut(usize::from(u8::from(*opcode))) {
+                *handler = OpCodeFn(VM::on_restricted_opcode);
+            }
+        }
+    }
+
+    /// Used within the opcode table for opcodes disabled by [`VM::restrict_opcodes`].
+    fn on_restricted_opcode(&mut self) -> Result<OpcodeResult, VMError> {
+        // The pc was already advanced past the opcode
+        let opcode = self
+            .current_call_frame
+            .pc
+            .checked_sub(1)
+            .and_then(|pc| self.current_call_frame.bytecode.bytecode.get(pc).copied());
+        if self.restricted_opcode.is_none() {
+            self.restricted_opcode = opcode;
+        }
+        Err(ExceptionalHalt::InvalidOpcode.into())
+    }
+
     pub fn op_stop(&mut self) -> Result<OpcodeResult, VMError> {
         Ok(OpcodeResult::Halt)
     }
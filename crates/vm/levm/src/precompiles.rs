@@ -43,6 +43,7 @@ use sha2::Digest;
 use std::borrow::Cow;
 use std::ops::Mul;
 
+use crate::EVMConfig;
 use crate::constants::{P256_A, P256_B, P256_N};
 use crate::gas_cost::{MODEXP_STATIC_COST, P256_VERIFY_COST};
 use crate::vm::VMType;
@@ -292,9 +293,10 @@ pub fn precompiles_for_fork(fork: Fork) -> impl Iterator<Item = Precompile> {
         .filter(move |precompile| precompile.active_since_fork <= fork)
 }
 
-pub fn is_precompile(address: &Address, fork: Fork, vm_type: VMType) -> bool {
-    (matches!(vm_type, VMType::L2(_)) && *address == P256VERIFY.address)
-        || precompiles_for_fork(fork).any(|precompile| precompile.address == *address)
+pub fn is_precompile(address: &Address, config: &EVMConfig, vm_type: VMType) -> bool {
+    (matches!(vm_type, VMType::L2(_))
+        && (*address == P256VERIFY.address || config.custom_precompiles.get(address).is_some()))
+        || precompiles_for_fork(config.fork).any(|precompile| precompile.address == *address)
 }
 
 #[expect(clippy::as_conversions, clippy::indexing_slicing)]
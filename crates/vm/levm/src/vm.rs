@@ -1,9 +1,12 @@
 use crate::{
     TransientStorage,
+    access_events::{AccessEvents, AccessMode, account_header},
     call_frame::{CallFrame, Stack},
+    code_analysis::BlockCursor,
+    custom_precompiles,
     db::gen_db::GeneralizedDatabase,
     debug::DebugMode,
-    environment::Environment,
+    environment::{EVMConfig, Environment},
     errors::{ContextResult, ExecutionReport, InternalError, OpcodeResult, VMError},
     hooks::{
         backup_hook::BackupHook,
@@ -38,6 +41,16 @@ pub enum VMType {
     L2(FeeConfig),
 }
 
+/// Storage accessed by a transaction, along with the words it hashed, which locate the slots
+/// of the mappings it accessed. Recorded once enabled with [`VM::record_storage_accesses`].
+#[derive(Debug, Default)]
+pub struct StorageAccesses {
+    /// Slots read or written by `SLOAD` and `SSTORE`
+    pub slots: BTreeSet<(Address, H256)>,
+    /// First word and hash of every `KECCAK256` input of at least a word
+    pub hashed_words: Vec<(U256, U256)>,
+}
+
 /// Information that changes during transaction execution.
 // Most fields are private by design. The backup mechanism (`parent` field) will only work properly
 // if data is append-only.
@@ -323,6 +336,19 @@ pub struct VM<'a> {
     /// A pool of stacks to avoid reallocating too much when creating new call frames.
     pub stack_pool: Vec<Stack>,
     pub vm_type: VMType,
+    /// State accessed by the transaction, only tracked under EIP-4762 stateless gas costs
+    pub access_events: AccessEvents,
+    /// First restricted opcode reached, see [`VM::restrict_opcodes`]
+    pub restricted_opcode: Option<u8>,
+    /// Storage accessed by the transaction, see [`VM::record_storage_accesses`]
+    pub storage_accesses: Option<StorageAccesses>,
+    /// Whether the static gas of basic blocks is charged when entering them, unset to charge it
+    /// instruction by instruction
+    pub(crate) prepay_basic_blocks: bool,
+    /// Costs of the contracts run by the transaction, only recorded once enabled with
+    /// [`VM::enable_contract_profiling`]
+    #[cfg(feature = "perf_contract_profiling")]
+    pub contract_profile: Option<crate::contract_profiling::ContractProfile>,
 
     /// The opcode table mapping opcodes to opcode handlers for fast lookup.
     /// Build dynamically according to the given fork config.
@@ -339,7 +365,7 @@ impl<'a> VM<'a> {
     ) -> Result<Self, VMError> {
         db.tx_backup = None; // If BackupHook is enabled, it will contain backup at the end of tx execution.
 
-        let mut substate = Substate::initialize(&env, tx)?;
+        let mut substate = Substate::initialize(&env, tx, vm_type)?;
 
         let (callee, is_create) = Self::get_tx_callee(tx, db, &env, &mut substate)?;
 
@@ -350,13 +376,19 @@ impl<'a> VM<'a> {
             substate,
             db,
             tx: tx.clone(),
-            hooks: get_hooks(&vm_type),
+            hooks: get_hooks(&vm_type, tx),
             substate_backups: Vec::new(),
             storage_original_values: BTreeMap::new(),
             tracer,
             debug_mode: DebugMode::disabled(),
             stack_pool: Vec::new(),
             vm_type,
+            access_events: AccessEvents::new(),
+            restricted_opcode: None,
+            storage_accesses: None,
+            prepay_basic_blocks: true,
+            #[cfg(feature = "perf_contract_profiling")]
+            contract_profile: None,
             current_call_frame: CallFrame::new(
                 env.origin,
                 callee,
@@ -392,6 +424,21 @@ impl<'a> VM<'a> {
             vm.tx.data(),
         );
 
+        if vm.env.config.stateless_gas {
+            // [EIP-4762] - The sender and recipient are part of the witness without charge
+            let origin = vm.env.origin;
+            vm.access_events
+                .touch_all(account_header(origin), AccessMode::Write);
+            vm.access_events.touch_all(
+                account_header(callee),
+                if vm.tx.value().is_zero() {
+                    AccessMode::Read
+                } else {
+                    AccessMode::Write
+                },
+            );
+        }
+
         #[cfg(feature = "debug")]
         {
             // Enable debug mode for printing in Solidity contracts.
@@ -436,11 +483,7 @@ impl<'a> VM<'a> {
     /// Main execution loop.
     pub fn run_execution(&mut self) -> Result<ContextResult, VMError> {
         #[expect(clippy::as_conversions, reason = "remaining gas conversion")]
-        if precompiles::is_precompile(
-            &self.current_call_frame.to,
-            self.env.config.fork,
-            self.vm_type,
-        ) {
+        if precompiles::is_precompile(&self.current_call_frame.to, &self.env.config, self.vm_type) {
             let call_frame = &mut self.current_call_frame;
 
             let mut gas_remaining = call_frame.gas_remaining as u64;
@@ -449,7 +492,9 @@ impl<'a> VM<'a> {
                 &call_frame.calldata,
                 call_frame.gas_limit,
                 &mut gas_remaining,
-                self.env.config.fork,
+                &self.env.config,
+                self.db,
+                &mut self.storage_accesses,
             );
 
             call_frame.gas_remaining = gas_remaining as i64;
@@ -459,18 +504,58 @@ impl<'a> VM<'a> {
 
         #[cfg(feature = "perf_opcode_timings")]
         let mut timings = crate::timings::OPCODE_TIMINGS.lock().expect("poison");
+        #[cfg(feature = "perf_contract_profiling")]
+        let mut profiler = self
+            .contract_profile
+            .is_some()
+            .then(crate::contract_profiling::FrameProfiler::default);
+
+        // Stepping through the code and EIP-4762 code chunk charging need the gas to be charged
+        // instruction by instruction, so basic blocks aren't prepaid with them.
+        #[cfg(feature = "debug")]
+        let stepping = self.debug_mode.step_hook.is_some();
+        #[cfg(not(feature = "debug"))]
+        let stepping = false;
+        let use_basic_blocks =
+            self.prepay_basic_blocks && !stepping && !self.env.config.stateless_gas;
+        let mut block_cursor = BlockCursor::default();
 
         loop {
+            #[cfg(feature = "debug")]
+            if let Some(mut step_hook) = self.debug_mode.step_hook.take() {
+                let action = step_hook.on_step(self);
+                self.debug_mode.step_hook = Some(step_hook);
+                if action == crate::debug::StepAction::Abort {
+                    return Err(InternalError::ExecutionAborted.into());
+                }
+            }
+
+            if use_basic_blocks && block_cursor.at_block_boundary() {
+                self.enter_block(&mut block_cursor);
+            }
+
             let opcode = self.current_call_frame.next_opcode();
             self.advance_pc(1)?;
 
+            #[cfg(feature = "perf_contract_profiling")]
+            if let Some(profiler) = profiler.as_mut() {
+                profiler.on_step(&self.current_call_frame, self.call_frames.len(), opcode);
+            }
+
             #[cfg(feature = "perf_opcode_timings")]
             let opcode_time_start = std::time::Instant::now();
 
-            // Call the opcode, using the opcode function lookup table.
-            // Indexing will not panic as all the opcode values fit within the table.
-            #[allow(clippy::indexing_slicing, clippy::as_conversions)]
-            let op_result = self.opcode_table[opcode as usize].call(self);
+            // [EIP-4762] - The code being executed is charged chunk by chunk
+            let code_access = if self.env.config.stateless_gas {
+                self.charge_code_execution(opcode)
+            } else {
+                Ok(())
+            };
+
+            let op_result = match code_access {
+                Ok(()) => self.execute_instruction(opcode, &mut block_cursor),
+                Err(error) => Err(error),
+            };
 
             #[cfg(feature = "perf_opcode_timings")]
             {
@@ -483,6 +568,15 @@ impl<'a> VM<'a> {
                 Ok(OpcodeResult::Halt) => self.handle_opcode_result()?,
                 Err(error) => self.handle_opcode_error(error)?,
             };
+            // The call frame exited, so its block was left
+            block_cursor = BlockCursor::default();
+
+            #[cfg(feature = "perf_contract_profiling")]
+            if let (Some(profiler), Some(profile)) =
+                (profiler.as_mut(), self.contract_profile.as_mut())
+            {
+                profiler.on_frame_exit(&result, profile);
+            }
 
             // Return the ExecutionReport if the executed callframe was the first one.
             if self.is_initial_call_frame() {
@@ -496,20 +590,51 @@ impl<'a> VM<'a> {
     }
 
     /// Executes precompile and handles the output that it returns, generating a report.
+    /// Custom precompiles are only reachable on L2s, `is_precompile` must be checked first.
+    /// The storage they read is added to `storage_accesses` when recording.
     pub fn execute_precompile(
         code_address: H160,
         calldata: &Bytes,
         gas_limit: u64,
         gas_remaining: &mut u64,
-        fork: Fork,
+        config: &EVMConfig,
+        db: &mut GeneralizedDatabase,
+        storage_accesses: &mut Option<StorageAccesses>,
     ) -> Result<ContextResult, VMError> {
-        let execute_precompile = precompiles::execute_precompile;
+        #[cfg(feature = "perf_contract_profiling")]
+        let precompile_time_start = std::time::Instant::now();
+
+        let result = match config.custom_precompiles.get(&code_address) {
+            Some(precompile) => custom_precompiles::execute_custom_precompile(
+                precompile,
+                calldata,
+                gas_remaining,
+                db,
+                storage_accesses,
+            ),
+            None => {
+                precompiles::execute_precompile(code_address, calldata, gas_remaining, config.fork)
+            }
+        };
+
+        #[cfg(feature = "perf_contract_profiling")]
+        crate::contract_profiling::record_precompile_time(precompile_time_start.elapsed());
+
+        Self::handle_precompile_result(result, gas_limit, *gas_remaining)
+    }
+
+    /// Records the costs of the contracts run by the transaction into [`VM::contract_profile`].
+    /// Only meant for executions whose results are kept, so that the profile of a block isn't
+    /// skewed by speculative or simulated ones.
+    #[cfg(feature = "perf_contract_profiling")]
+    pub fn enable_contract_profiling(&mut self) {
+        self.contract_profile = Some(Default::default());
+    }
 
-        Self::handle_precompile_result(
-            execute_precompile(code_address, calldata, gas_remaining, fork),
-            gas_limit,
-            *gas_remaining,
-        )
+    /// Records the storage accessed by the transaction into [`VM::storage_accesses`], used to
+    /// check which storage a validation depends on.
+    pub fn record_storage_accesses(&mut self) {
+        self.storage_accesses = Some(StorageAccesses::default());
     }
 
     /// True if external transaction is a contract creation
@@ -545,6 +670,7 @@ impl<'a> VM<'a> {
         }
 
         self.tracer.exit_context(&ctx_result, true)?;
+        self.db.record_access_events(&self.access_events);
 
         let report = ExecutionReport {
             result: ctx_result.result.clone(),
@@ -560,7 +686,11 @@ impl<'a> VM<'a> {
 
 impl Substate {
     /// Initializes the VM substate, mainly adding addresses to the "accessed_addresses" field and the same with storage slots
-    pub fn initialize(env: &Environment, tx: &Transaction) -> Result<Substate, VMError> {
+    pub fn initialize(
+        env: &Environment,
+        tx: &Transaction,
+        vm_type: VMType,
+    ) -> Result<Substate, VMError> {
         // Add sender and recipient to accessed accounts [https://www.evm.codes/about#access_list]
         let mut initial_accessed_addresses = HashSet::new();
         let mut initial_accessed_storage_slots: BTreeMap<Address, BTreeSet<H256>> = BTreeMap::new();
@@ -590,6 +720,13 @@ impl Substate {
             initial_accessed_addresses.insert(Address::from_low_u64_be(0x100));
         }
 
+        // Add the custom precompiles declared by the L2
+        if let VMType::L2(_) = vm_type {
+            for precompile in env.config.custom_precompiles.iter() {
+                initial_accessed_addresses.insert(precompile.address);
+            }
+        }
+
         // Add access lists contents to accessed accounts and accessed storage slots.
         for (address, keys) in tx.access_list().clone() {
             initial_accessed_addresses.insert(address);
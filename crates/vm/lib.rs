@@ -1,14 +1,20 @@
 mod db;
+#[cfg(feature = "revm_differential")]
+pub mod differential;
 mod errors;
 mod execution_result;
+#[cfg(test)]
+mod test_utils;
 pub mod tracing;
 mod witness_db;
 
 pub mod backends;
 
-pub use backends::{BlockExecutionResult, Evm};
+pub use backends::{BlockExecutionResult, Evm, PrefetchMode, PrefetchStats};
 pub use db::{DynVmDatabase, VmDatabase};
 pub use errors::EvmError;
+#[cfg(feature = "perf_contract_profiling")]
+pub use ethrex_levm::contract_profiling;
 pub use ethrex_levm::precompiles::precompiles_for_fork;
 pub use execution_result::ExecutionResult;
 pub use witness_db::GuestProgramStateWrapper;
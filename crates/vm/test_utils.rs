@@ -0,0 +1,168 @@
+//! In-memory state and signed transactions shared by the unit tests of the crate.
+#![allow(clippy::unwrap_used)]
+
+use crate::{DynVmDatabase, EvmError, VmDatabase};
+use bytes::Bytes;
+use ethrex_common::{
+    Address, H256, U256,
+    types::{
+        AccountState, Block, BlockBody, BlockHeader, ChainConfig, Code, EIP1559Transaction,
+        Transaction, TxKind, TxType,
+    },
+    utils::keccak,
+};
+use ethrex_levm::db::gen_db::GeneralizedDatabase;
+use ethrex_rlp::encode::PayloadRLPEncode;
+use secp256k1::{Message, PublicKey, SECP256K1, SecretKey};
+use std::{collections::HashMap, sync::Arc};
+
+pub const BASE_FEE: u64 = 1_000_000_000;
+pub const COINBASE: Address = Address::repeat_byte(0xc0);
+
+/// Initial balance of every account created with [`TestDatabase::with_funded`]
+pub fn initial_balance() -> U256 {
+    U256::from(10).pow(U256::from(18))
+}
+
+/// Chain with every fork up to Cancun active from genesis
+pub fn cancun_config() -> ChainConfig {
+    ChainConfig {
+        chain_id: 1,
+        homestead_block: Some(0),
+        eip150_block: Some(0),
+        eip155_block: Some(0),
+        eip158_block: Some(0),
+        byzantium_block: Some(0),
+        constantinople_block: Some(0),
+        petersburg_block: Some(0),
+        istanbul_block: Some(0),
+        berlin_block: Some(0),
+        london_block: Some(0),
+        merge_netsplit_block: Some(0),
+        shanghai_time: Some(0),
+        cancun_time: Some(0),
+        terminal_total_difficulty: Some(0),
+        terminal_total_difficulty_passed: true,
+        ..Default::default()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TestDatabase {
+    pub chain_config: ChainConfig,
+    pub accounts: HashMap<Address, AccountState>,
+    pub storage: HashMap<(Address, H256), U256>,
+    pub codes: HashMap<H256, Code>,
+}
+
+impl TestDatabase {
+    pub fn new(chain_config: ChainConfig) -> Self {
+        Self {
+            chain_config,
+            ..Default::default()
+        }
+    }
+
+    /// Adds an account holding [`initial_balance`] and the given code
+    pub fn with_funded(mut self, address: Address, code: Bytes) -> Self {
+        let code = Code::from_bytecode(code);
+        self.accounts.insert(
+            address,
+            AccountState {
+                balance: initial_balance(),
+                code_hash: code.hash,
+                ..Default::default()
+            },
+        );
+        self.codes.insert(code.hash, code);
+        self
+    }
+
+    pub fn with_storage(mut self, address: Address, key: H256, value: U256) -> Self {
+        // LEVM only checks whether the storage root is empty
+        self.accounts.entry(address).or_default().storage_root = H256::repeat_byte(0x01);
+        self.storage.insert((address, key), value);
+        self
+    }
+
+    pub fn into_generalized(self) -> GeneralizedDatabase {
+        let db: DynVmDatabase = Box::new(self);
+        GeneralizedDatabase::new(Arc::new(db))
+    }
+}
+
+impl VmDatabase for TestDatabase {
+    fn get_account_state(&self, address: Address) -> Result<Option<AccountState>, EvmError> {
+        Ok(self.accounts.get(&address).cloned())
+    }
+
+    fn get_storage_slot(&self, address: Address, key: H256) -> Result<Option<U256>, EvmError> {
+        Ok(self.storage.get(&(address, key)).copied())
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> Result<H256, EvmError> {
+        Ok(keccak(block_number.to_be_bytes()))
+    }
+
+    fn get_chain_config(&self) -> Result<ChainConfig, EvmError> {
+        Ok(self.chain_config.clone())
+    }
+
+    fn get_account_code(&self, code_hash: H256) -> Result<Code, EvmError> {
+        Ok(self.codes.get(&code_hash).cloned().unwrap_or_default())
+    }
+}
+
+pub fn secret_key(seed: u8) -> SecretKey {
+    SecretKey::from_slice(&[seed; 32]).unwrap()
+}
+
+pub fn address_of(key: &SecretKey) -> Address {
+    let public_key = PublicKey::from_secret_key(SECP256K1, key).serialize_uncompressed();
+    Address::from_slice(&keccak(&public_key[1..]).0[12..])
+}
+
+/// Signed EIP-1559 call paying twice [`BASE_FEE`]
+pub fn call_tx(key: &SecretKey, nonce: u64, to: Address, value: U256, data: Bytes) -> Transaction {
+    let mut tx = EIP1559Transaction {
+        chain_id: 1,
+        nonce,
+        max_priority_fee_per_gas: BASE_FEE,
+        max_fee_per_gas: 2 * BASE_FEE,
+        gas_limit: 100_000,
+        to: TxKind::Call(to),
+        value,
+        data,
+        ..Default::default()
+    };
+    let mut payload = vec![TxType::EIP1559 as u8];
+    payload.append(&mut tx.encode_payload_to_vec());
+    let (recovery_id, signature) = SECP256K1
+        .sign_ecdsa_recoverable(&Message::from_digest(keccak(payload).0), key)
+        .serialize_compact();
+    tx.signature_r = U256::from_big_endian(&signature[..32]);
+    tx.signature_s = U256::from_big_endian(&signature[32..]);
+    tx.signature_y_parity = Into::<i32>::into(recovery_id) != 0;
+    Transaction::EIP1559Transaction(tx)
+}
+
+/// Block number 1 containing `transactions`, on top of a chain configured with [`cancun_config`]
+pub fn test_block(transactions: Vec<Transaction>) -> Block {
+    Block::new(
+        BlockHeader {
+            number: 1,
+            timestamp: 12,
+            coinbase: COINBASE,
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(BASE_FEE),
+            blob_gas_used: Some(0),
+            excess_blob_gas: Some(0),
+            ..Default::default()
+        },
+        BlockBody {
+            transactions,
+            ommers: Vec::new(),
+            withdrawals: Some(Vec::new()),
+        },
+    )
+}
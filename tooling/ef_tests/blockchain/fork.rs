@@ -28,68 +28,78 @@ lazy_static! {
     };
     pub static ref MERGE_TO_SHANGHAI_AT_15K_CONFIG: ChainConfig = ChainConfig {
         shanghai_time: Some(0x3a98),
-        ..*MERGE_CONFIG
+        ..MERGE_CONFIG.clone()
     };
     pub static ref SHANGHAI_CONFIG: ChainConfig = ChainConfig {
         shanghai_time: Some(0),
-        ..*MERGE_CONFIG
+        ..MERGE_CONFIG.clone()
     };
     pub static ref SHANGHAI_TO_CANCUN_AT_15K_CONFIG: ChainConfig = ChainConfig {
         cancun_time: Some(0x3a98),
-        ..*SHANGHAI_CONFIG
+        ..SHANGHAI_CONFIG.clone()
     };
     pub static ref CANCUN_CONFIG: ChainConfig = ChainConfig {
         cancun_time: Some(0),
-        ..*SHANGHAI_CONFIG
+        ..SHANGHAI_CONFIG.clone()
     };
     pub static ref CANCUN_TO_PRAGUE_AT_15K_CONFIG: ChainConfig = ChainConfig {
         prague_time: Some(0x3a98),
         // Mainnet address
         deposit_contract_address: H160::from_str("0x00000000219ab540356cbb839cbe05303d7705fa")
             .unwrap(),
-        ..*CANCUN_CONFIG
+        ..CANCUN_CONFIG.clone()
     };
     pub static ref PRAGUE_CONFIG: ChainConfig = ChainConfig {
         prague_time: Some(0),
-        ..*CANCUN_TO_PRAGUE_AT_15K_CONFIG
+        ..CANCUN_TO_PRAGUE_AT_15K_CONFIG.clone()
     };
 
     pub static ref PRAGUE_TO_OSAKA_AT_15K_CONFIG: ChainConfig = ChainConfig {
         osaka_time: Some(0x3a98),
-        ..*PRAGUE_CONFIG
+        ..PRAGUE_CONFIG.clone()
 
     };
 
     pub static ref OSAKA_CONFIG: ChainConfig = ChainConfig {
         osaka_time: Some(0),
-        ..*PRAGUE_CONFIG
+        ..PRAGUE_CONFIG.clone()
     };
 
     pub static ref OSAKA_TO_BPO1_AT_15K_CONFIG: ChainConfig = ChainConfig {
         bpo1_time: Some(0x3a98),
-        ..*OSAKA_CONFIG
+        ..OSAKA_CONFIG.clone()
     };
 
     pub static ref BPO1_TO_BPO2_AT_15K_CONFIG: ChainConfig = ChainConfig {
         bpo1_time: Some(0),
         bpo2_time: Some(0x3a98),
-        ..*OSAKA_CONFIG
+        ..OSAKA_CONFIG.clone()
     };
 
     pub static ref BPO2_TO_BPO3_AT_15K_CONFIG: ChainConfig = ChainConfig {
         bpo2_time: Some(0),
         bpo3_time: Some(0x3a98),
-        ..*OSAKA_CONFIG
+        ..OSAKA_CONFIG.clone()
     };
     pub static ref BPO3_TO_BPO4_AT_15K_CONFIG: ChainConfig = ChainConfig {
         bpo3_time: Some(0),
         bpo4_time: Some(0x3a98),
-        ..*OSAKA_CONFIG
+        ..OSAKA_CONFIG.clone()
     };
     pub static ref BPO4_TO_BPO5_AT_15K_CONFIG: ChainConfig = ChainConfig {
         bpo4_time: Some(0),
         bpo5_time: Some(0x3a98),
-        ..*OSAKA_CONFIG
+        ..OSAKA_CONFIG.clone()
+    };
+
+    pub static ref OSAKA_TO_AMSTERDAM_AT_15K_CONFIG: ChainConfig = ChainConfig {
+        amsterdam_time: Some(0x3a98),
+        ..OSAKA_CONFIG.clone()
+    };
+
+    pub static ref AMSTERDAM_CONFIG: ChainConfig = ChainConfig {
+        amsterdam_time: Some(0),
+        ..OSAKA_CONFIG.clone()
     };
 
 }
@@ -131,6 +141,8 @@ pub enum Fork {
     BPO2ToBPO3AtTime15k,
     BPO3ToBPO4AtTime15k,
     BPO4ToBPO5AtTime15k,
+    OsakaToAmsterdamAtTime15k,
+    Amsterdam,
 }
 
 impl Fork {
@@ -150,6 +162,8 @@ impl Fork {
             Fork::BPO2ToBPO3AtTime15k => &BPO2_TO_BPO3_AT_15K_CONFIG,
             Fork::BPO3ToBPO4AtTime15k => &BPO3_TO_BPO4_AT_15K_CONFIG,
             Fork::BPO4ToBPO5AtTime15k => &BPO4_TO_BPO5_AT_15K_CONFIG,
+            Fork::OsakaToAmsterdamAtTime15k => &OSAKA_TO_AMSTERDAM_AT_15K_CONFIG,
+            Fork::Amsterdam => &AMSTERDAM_CONFIG,
             _ => {
                 panic!("Ethrex doesn't support pre-Merge forks: {self:?}")
             }
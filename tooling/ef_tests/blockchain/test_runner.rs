@@ -43,11 +43,21 @@ pub fn parse_and_execute(
             continue;
         }
 
-        let result = rt.block_on(run_ef_test(&test_key, &test, stateless_backend));
-
-        if let Err(e) = result {
-            eprintln!("Test {test_key} failed: {e:?}");
-            failures.push(format!("{test_key}: {e:?}"));
+        // Every test is run both with sequential and with parallel transaction execution,
+        // the stateless re-execution only needs to happen once.
+        for parallel_execution in [false, true] {
+            let backend = stateless_backend.filter(|_| !parallel_execution);
+            let result = rt.block_on(run_ef_test(&test_key, &test, backend, parallel_execution));
+
+            if let Err(e) = result {
+                let mode = if parallel_execution {
+                    "parallel"
+                } else {
+                    "sequential"
+                };
+                eprintln!("Test {test_key} failed ({mode} execution): {e:?}");
+                failures.push(format!("{test_key} ({mode} execution): {e:?}"));
+            }
         }
     }
 
@@ -63,6 +73,7 @@ pub async fn run_ef_test(
     test_key: &str,
     test: &TestUnit,
     stateless_backend: Option<Backend>,
+    parallel_execution: bool,
 ) -> Result<(), String> {
     // check that the decoded genesis block header matches the deserialized one
     let genesis_rlp = test.genesis_rlp.clone();
@@ -76,7 +87,13 @@ pub async fn run_ef_test(
     check_prestate_against_db(test_key, test, &store);
 
     // Blockchain EF tests are meant for L1.
-    let blockchain = Blockchain::new(store.clone(), BlockchainOptions::default());
+    let blockchain = Blockchain::new(
+        store.clone(),
+        BlockchainOptions {
+            parallel_execution,
+            ..Default::default()
+        },
+    );
 
     // Early return if the exception is in the rlp decoding of the block
     for bf in &test.blocks {
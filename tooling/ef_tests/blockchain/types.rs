@@ -156,7 +156,7 @@ impl TestUnit {
     }
 
     pub fn get_genesis(&self) -> Genesis {
-        let mut config = *self.network.chain_config();
+        let mut config = self.network.chain_config().clone();
         // Overwrite default blob schedule with test's blob schedule
         if let Some(test_config) = &self.config
             && let Some(ref schedule) = test_config.blob_schedule
@@ -188,6 +188,7 @@ impl TestUnit {
                 .excess_blob_gas
                 .map(|v| v.as_u64()),
             requests_hash: self.genesis_block_header.requests_hash,
+            block_access_list_hash: self.genesis_block_header.block_access_list_hash,
         }
     }
 }
@@ -266,6 +267,7 @@ pub struct Header {
     pub excess_blob_gas: Option<U256>,
     pub parent_beacon_block_root: Option<H256>,
     pub requests_hash: Option<H256>,
+    pub block_access_list_hash: Option<H256>,
 }
 
 #[derive(Debug, PartialEq, Eq, Deserialize, Clone)]
@@ -379,6 +381,7 @@ impl From<Header> for BlockHeader {
             excess_blob_gas: val.excess_blob_gas.map(|x| x.as_u64()),
             parent_beacon_block_root: val.parent_beacon_block_root,
             requests_hash: val.requests_hash,
+            block_access_list_hash: val.block_access_list_hash,
             ..Default::default()
         }
     }
@@ -700,6 +700,7 @@ pub fn fork_to_spec_id(fork: Fork) -> SpecId {
         Fork::BPO3 => SpecId::OSAKA,
         Fork::BPO4 => SpecId::OSAKA,
         Fork::BPO5 => SpecId::OSAKA,
+        Fork::Amsterdam => SpecId::OSAKA,
     }
 }
 